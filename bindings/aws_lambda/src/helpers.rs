@@ -22,7 +22,8 @@ pub fn format_csml_client(query_params: &serde_json::Value) -> Result<Client, se
     Some(serde_json::Value::String(channel_id))) => Ok(Client {
         user_id: user_id.to_owned(),
         bot_id: bot_id.to_owned(),
-        channel_id: channel_id.to_owned()
+        channel_id: channel_id.to_owned(),
+        tenant_id: None,
     }),
     _ => return Err(format_response(400, serde_json::json!("Missing query params client info (user_id, bot_id, channel_id)")))
   }
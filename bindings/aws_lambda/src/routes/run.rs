@@ -1,5 +1,5 @@
 use crate::format_response;
-use csml_engine::start_conversation;
+use csml_engine::{data::EngineError, start_conversation};
 use serde_json::{json, Value};
 
 use crate::{routes::RunRequest, Error};
@@ -32,6 +32,13 @@ pub fn handler(body: RunRequest) -> Result<serde_json::Value, Error> {
                 "body": serde_json::json!(data).to_string()
             }
         )),
+        Err(EngineError::Metadata(errors)) => {
+            Ok(format_response(400, serde_json::json!({ "errors": errors })))
+        }
+        Err(EngineError::QuotaExceeded(quota)) => Ok(format_response(
+            429,
+            serde_json::json!({ "error": "quota_exceeded", "quota": quota }),
+        )),
         Err(err) => {
             let error = format!("EngineError: {:?}", err);
             return Ok(format_response(400, serde_json::json!(error)));
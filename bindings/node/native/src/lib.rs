@@ -19,6 +19,7 @@ fn get_client<'a>(cx: &'a mut FunctionContext, object: Handle<JsObject>) -> Neon
         bot_id: bot_id.value(),
         channel_id: channel_id.value(),
         user_id: user_id.value(),
+        tenant_id: None,
     })
 }
 
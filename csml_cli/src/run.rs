@@ -18,6 +18,7 @@ pub fn init_request(string: &str, metadata: Option<serde_json::Value>) -> CsmlRe
             user_id: "user".to_owned(),
             bot_id: "botid".to_owned(),
             channel_id: "CLI".to_owned(),
+            tenant_id: None,
         },
         callback_url: None,
         payload: json!({
@@ -41,6 +42,7 @@ pub fn init_request_flow_trigger(flow_id: &str, step_id: Option<&str>) -> CsmlRe
             user_id: "user".to_owned(),
             bot_id: "botid".to_owned(),
             channel_id: "CLI".to_owned(),
+            tenant_id: None,
         },
         callback_url: None,
         payload: json!({
@@ -103,6 +105,12 @@ pub fn load_info(directory_name: &str) -> Result<CsmlBot, Box<dyn Error>> {
         env: None,
         modules: None,
         multibot: None,
+        apps: None,
+        apps_endpoint_options: None,
+        no_data_retention: None,
+        conversation_expiration: None,
+        metadata_schema: None,
+        locales: None,
     })
 }
 
@@ -27,6 +27,7 @@ fn init_request(string: &str) -> CsmlRequest {
             user_id: "alexis".to_owned(),
             bot_id: "botid".to_owned(),
             channel_id: "some-channel-id".to_owned(),
+            tenant_id: None,
         },
         callback_url: Some("http://httpbin.org/post".to_owned()),
         payload: json!({
@@ -67,6 +68,12 @@ fn init_bot() -> CsmlBot {
         env: None,
         modules: None,
         multibot: None,
+        apps: None,
+        apps_endpoint_options: None,
+        no_data_retention: None,
+        conversation_expiration: None,
+        metadata_schema: None,
+        locales: None,
     }
 }
 
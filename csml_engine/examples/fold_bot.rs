@@ -42,6 +42,12 @@ fn init_bot() -> CsmlBot {
         env: None,
         modules: None,
         multibot: None,
+        apps: None,
+        apps_endpoint_options: None,
+        no_data_retention: None,
+        conversation_expiration: None,
+        metadata_schema: None,
+        locales: None,
     }
 }
 
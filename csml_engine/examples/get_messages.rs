@@ -8,6 +8,7 @@ fn main() {
         user_id: "alexis".to_owned(),
         bot_id: "botid".to_owned(),
         channel_id: "some-channel-id".to_owned(),
+        tenant_id: None,
     };
 
     let messages = get_client_messages(&client, None, None, None, None).unwrap();
@@ -0,0 +1,2 @@
+-- labels of the conversation, as a JSON array of strings
+ALTER TABLE csml_conversations ADD COLUMN tags VARCHAR DEFAULT NULL;
@@ -0,0 +1 @@
+ALTER TABLE csml_conversations DROP COLUMN summary;
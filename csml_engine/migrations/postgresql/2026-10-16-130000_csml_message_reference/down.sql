@@ -0,0 +1 @@
+ALTER TABLE csml_messages DROP COLUMN reference_id;
@@ -0,0 +1,2 @@
+-- id of the previous message referenced by an edited message or a reaction
+ALTER TABLE csml_messages ADD COLUMN reference_id TEXT DEFAULT NULL;
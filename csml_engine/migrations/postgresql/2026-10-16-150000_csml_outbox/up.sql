@@ -0,0 +1,15 @@
+CREATE TABLE csml_outbox (
+  id uuid PRIMARY KEY,
+  bot_id VARCHAR NOT NULL,
+  channel_id VARCHAR NOT NULL,
+  user_id VARCHAR NOT NULL,
+
+  callback_url VARCHAR NOT NULL,
+  payload VARCHAR NOT NULL,
+  -- PENDING until the callback_url accepted the messages, then SENT
+  status VARCHAR NOT NULL,
+
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX outbox_status_created_at ON csml_outbox (status, created_at);
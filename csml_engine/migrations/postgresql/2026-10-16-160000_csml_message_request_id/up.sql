@@ -0,0 +1,2 @@
+-- id of the request that saved the message (X-Request-Id of the server)
+ALTER TABLE csml_messages ADD COLUMN request_id TEXT DEFAULT NULL;
@@ -0,0 +1 @@
+ALTER TABLE csml_messages DROP COLUMN bot_version_id;
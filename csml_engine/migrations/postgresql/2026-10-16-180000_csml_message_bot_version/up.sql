@@ -0,0 +1,2 @@
+-- version of the bot that handled the message, the one picked by a rollout
+ALTER TABLE csml_messages ADD COLUMN bot_version_id TEXT DEFAULT NULL;
@@ -0,0 +1 @@
+DROP INDEX states_client_type_key;
@@ -0,0 +1,8 @@
+-- a state item is saved once per client, type and key: the previous values are replaced.
+-- only the last saved value of the items saved several times is kept
+DELETE FROM csml_states a USING csml_states b
+  WHERE a.bot_id = b.bot_id AND a.channel_id = b.channel_id AND a.user_id = b.user_id
+    AND a.type = b.type AND a.key = b.key
+    AND (a.created_at, a.id) < (b.created_at, b.id);
+
+CREATE UNIQUE INDEX states_client_type_key ON csml_states (bot_id, channel_id, user_id, type, key);
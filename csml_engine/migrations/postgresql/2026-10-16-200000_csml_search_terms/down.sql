@@ -0,0 +1,2 @@
+ALTER TABLE csml_messages DROP COLUMN search_terms;
+ALTER TABLE csml_memories DROP COLUMN search_terms;
@@ -0,0 +1,3 @@
+-- blind index of the words of the encrypted payloads and values, see encrypt.rs
+ALTER TABLE csml_messages ADD COLUMN search_terms TEXT DEFAULT NULL;
+ALTER TABLE csml_memories ADD COLUMN search_terms TEXT DEFAULT NULL;
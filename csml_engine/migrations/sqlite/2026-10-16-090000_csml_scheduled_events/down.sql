@@ -0,0 +1,3 @@
+DROP INDEX scheduled_event_run_at;
+
+DROP TABLE csml_scheduled_events;
@@ -0,0 +1,13 @@
+CREATE TABLE csml_scheduled_events (
+  id BINARY(128) PRIMARY KEY NOT NULL,
+  bot_id VARCHAR NOT NULL,
+  channel_id VARCHAR NOT NULL,
+  user_id VARCHAR NOT NULL,
+
+  event VARCHAR NOT NULL,
+  run_at TIMESTAMP NOT NULL,
+
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX scheduled_event_run_at ON csml_scheduled_events (run_at);
@@ -0,0 +1 @@
+DROP TABLE csml_jobs;
@@ -0,0 +1,11 @@
+CREATE TABLE csml_jobs (
+  name VARCHAR PRIMARY KEY NOT NULL,
+
+  owner VARCHAR NOT NULL,
+  locked_until TIMESTAMP NOT NULL,
+  last_run_at TIMESTAMP,
+  last_error VARCHAR,
+
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
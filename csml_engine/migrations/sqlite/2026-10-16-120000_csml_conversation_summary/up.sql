@@ -0,0 +1,2 @@
+-- summary generated when the conversation is closed
+ALTER TABLE csml_conversations ADD COLUMN summary TEXT DEFAULT NULL;
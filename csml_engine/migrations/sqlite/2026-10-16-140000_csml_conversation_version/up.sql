@@ -0,0 +1,3 @@
+-- incremented by each update of the conversation, the updates of a run only apply to the
+-- version of the conversation it read
+ALTER TABLE csml_conversations ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+DROP INDEX outbox_status_created_at;
+
+DROP TABLE csml_outbox;
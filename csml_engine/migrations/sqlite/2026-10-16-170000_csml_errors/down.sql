@@ -0,0 +1,3 @@
+DROP INDEX errors_bot_id_created_at;
+
+DROP TABLE csml_errors;
@@ -0,0 +1,17 @@
+CREATE TABLE csml_errors (
+  id BINARY(128) PRIMARY KEY NOT NULL,
+  bot_id VARCHAR NOT NULL,
+  channel_id VARCHAR NOT NULL,
+  user_id VARCHAR NOT NULL,
+
+  conversation_id VARCHAR NOT NULL,
+  flow_id VARCHAR NOT NULL,
+  step_id VARCHAR NOT NULL,
+  -- message, interval, snippet, stack and last event of the error, encrypted
+  payload VARCHAR NOT NULL,
+
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP DEFAULT NULL
+);
+
+CREATE INDEX errors_bot_id_created_at ON csml_errors (bot_id, created_at);
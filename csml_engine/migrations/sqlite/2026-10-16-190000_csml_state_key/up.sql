@@ -0,0 +1,7 @@
+-- a state item is saved once per client, type and key: the previous values are replaced.
+-- only the last saved value of the items saved several times is kept
+DELETE FROM csml_states WHERE rowid NOT IN (
+  SELECT MAX(rowid) FROM csml_states GROUP BY bot_id, channel_id, user_id, type, key
+);
+
+CREATE UNIQUE INDEX states_client_type_key ON csml_states (bot_id, channel_id, user_id, type, key);
@@ -0,0 +1,211 @@
+/**
+ * Archival of the closed conversations: the conversations closed for more than
+ * ARCHIVE_AFTER_DAYS days (default 30) are exported with their messages to an S3 compatible
+ * object storage (AWS S3, GCS interoperability endpoint, MinIO...) then deleted from the database.
+ *
+ * Each conversation is saved as a gzip compressed JSON lines object
+ * {ARCHIVE_PREFIX}/{bot_id}/{channel_id}/{user_id}/{conversation_id}.jsonl.gz,
+ * with the conversation on the first line and its messages in chronological order on the next ones.
+ *
+ * The archival is enabled when ARCHIVE_S3_BUCKET is set (requires the object_storage feature):
+ * - ARCHIVE_S3_REGION (default us-east-1)
+ * - ARCHIVE_S3_ENDPOINT (default https://s3.{region}.amazonaws.com), the bucket is in the path
+ * - ARCHIVE_S3_ACCESS_KEY_ID / ARCHIVE_S3_SECRET_ACCESS_KEY,
+ *   or AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY
+ * - ARCHIVE_PREFIX (default csml-archive)
+ */
+use crate::{
+    config::get_config,
+    data::{Database, EngineError},
+    db_connectors::{conversations, messages, utils::get_storage_client, DbConversation},
+    object_storage::Bucket,
+    Client,
+};
+
+use chrono::{prelude::Utc, Duration};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::io::{BufRead, BufReader, Write};
+
+const DEFAULT_AFTER_DAYS: i64 = 30;
+const DEFAULT_PREFIX: &str = "csml-archive";
+// conversations archived between two reads of the database
+const BATCH_SIZE: i64 = 100;
+
+struct ArchiveConfig {
+    bucket: Bucket,
+    prefix: String,
+}
+
+fn get_archive_config() -> Result<Option<ArchiveConfig>, EngineError> {
+    match Bucket::from_config("archive")? {
+        Some(bucket) => Ok(Some(ArchiveConfig {
+            bucket,
+            prefix: get_config()
+                .text("archive.prefix")
+                .unwrap_or(DEFAULT_PREFIX.to_owned()),
+        })),
+        None => Ok(None),
+    }
+}
+
+fn get_after_days() -> i64 {
+    match get_config().integer("archive.after_days") {
+        Some(days) => days as i64,
+        None => DEFAULT_AFTER_DAYS,
+    }
+}
+
+fn get_object_key(prefix: &str, client: &Client, conversation_id: &str) -> String {
+    format!(
+        "{}/{}/{}/{}/{}.jsonl.gz",
+        prefix, client.bot_id, client.channel_id, client.user_id, conversation_id
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// ARCHIVE FORMAT
+////////////////////////////////////////////////////////////////////////////////
+
+fn encode_archive(
+    conversation: &DbConversation,
+    messages: &[serde_json::Value],
+) -> Result<Vec<u8>, EngineError> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+
+    serde_json::to_writer(&mut encoder, conversation)?;
+    encoder.write_all(b"\n")?;
+    for message in messages {
+        serde_json::to_writer(&mut encoder, message)?;
+        encoder.write_all(b"\n")?;
+    }
+
+    Ok(encoder.finish()?)
+}
+
+fn decode_archive(data: &[u8]) -> Result<serde_json::Value, EngineError> {
+    let mut lines = BufReader::new(GzDecoder::new(data)).lines();
+
+    let conversation: serde_json::Value = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => return Err(EngineError::Format("empty conversation archive".to_owned())),
+    };
+
+    let mut messages = vec![];
+    for line in lines {
+        let line = line?;
+        if !line.is_empty() {
+            messages.push(serde_json::from_str::<serde_json::Value>(&line)?);
+        }
+    }
+
+    Ok(serde_json::json!({
+        "conversation": conversation,
+        "messages": messages,
+    }))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Export the conversations closed for more than ARCHIVE_AFTER_DAYS days to the object storage
+ * and delete them from the database. A conversation is only deleted once its archive is saved.
+ * Return the number of archived conversations, 0 when the archival is not configured.
+ */
+pub fn archive_conversations(db: &mut Database) -> Result<usize, EngineError> {
+    let config = match get_archive_config()? {
+        Some(config) => config,
+        None => return Ok(0),
+    };
+
+    let before = Utc::now() - Duration::days(get_after_days());
+    let mut archived = 0;
+
+    loop {
+        let closed = conversations::get_closed_conversations(before, BATCH_SIZE, db)?;
+        if closed.is_empty() {
+            return Ok(archived);
+        }
+
+        for conversation in closed {
+            let messages =
+                messages::get_conversation_messages(&conversation.client, &conversation.id, db)?;
+            let data = encode_archive(&conversation, &messages)?;
+
+            let key = get_object_key(&config.prefix, &conversation.client, &conversation.id);
+            config
+                .bucket
+                .put_object(&key, &data, "application/gzip")?;
+
+            conversations::delete_conversation(&conversation.id, &conversation.client, db)?;
+            archived += 1;
+        }
+    }
+}
+
+/**
+ * The conversations are archived when ARCHIVE_S3_BUCKET is set
+ */
+pub fn is_enabled() -> bool {
+    matches!(get_archive_config(), Ok(Some(_)))
+}
+
+/**
+ * Get an archived conversation of a client with its messages,
+ * {"conversation": {...}, "messages": [...]}, or None if it was not archived
+ */
+pub fn get_archived_conversation(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let config = match get_archive_config()? {
+        Some(config) => config,
+        None => {
+            return Err(EngineError::Manager(
+                "conversation archival is not configured: set ARCHIVE_S3_BUCKET".to_owned(),
+            ))
+        }
+    };
+
+    let key = get_object_key(&config.prefix, &get_storage_client(client), conversation_id);
+    match config.bucket.get_object(&key)? {
+        Some(data) => Ok(Some(decode_archive(&data)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_archive_format() {
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        let conversation = DbConversation {
+            id: "conversation_id".to_owned(),
+            client,
+            flow_id: "Default".to_owned(),
+            step_id: "end".to_owned(),
+            status: "CLOSED".to_owned(),
+            last_interaction_at: "2022-01-01T00:00:00.000Z".to_owned(),
+            updated_at: "2022-01-01T00:00:00.000Z".to_owned(),
+            created_at: "2022-01-01T00:00:00.000Z".to_owned(),
+            version: 0,
+        };
+        let messages = vec![
+            serde_json::json!({"direction": "SEND", "payload": {"content": {"text": "hi"}}}),
+            serde_json::json!({"direction": "RECEIVE", "payload": {"content": {"text": "hello"}}}),
+        ];
+
+        let archive = decode_archive(&encode_archive(&conversation, &messages).unwrap()).unwrap();
+
+        assert_eq!(archive["conversation"]["id"], "conversation_id");
+        assert_eq!(archive["conversation"]["client"]["user_id"], "user_id");
+        assert_eq!(archive["messages"].as_array().unwrap(), &messages);
+    }
+}
@@ -0,0 +1,279 @@
+/**
+ * Batch runs: independent events interpreted in one call, to backfill the events of many
+ * clients or to load test a bot.
+ *
+ * The runs are interpreted by at most `concurrency` threads (default RUN_BATCH_CONCURRENCY
+ * env var, or 4). The events of the same client are interpreted by the same thread in their
+ * order in the batch: they never wait for each other in the client locks.
+ * The result of each run is returned at the index of the run in the batch.
+ */
+use crate::{
+    config::{get_config, with_config},
+    data::{BatchRunRequest, EngineError, RunContext, RunRequest},
+    start_conversation_with_context,
+};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+const MAX_BATCH_SIZE: usize = 100;
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 32;
+
+pub type RunResult = Result<serde_json::Map<String, serde_json::Value>, EngineError>;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_concurrency(request: &BatchRunRequest) -> usize {
+    let concurrency = match request.concurrency {
+        Some(concurrency) => concurrency,
+        None => get_config()
+            .integer("conversations.batch_concurrency")
+            .map(|concurrency| concurrency as usize)
+            .unwrap_or(DEFAULT_CONCURRENCY),
+    };
+
+    concurrency.clamp(1, MAX_CONCURRENCY)
+}
+
+/**
+ * Indexes of the runs of each client, in their order in the batch
+ */
+fn group_by_client(runs: &[RunRequest]) -> VecDeque<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut clients: HashMap<_, usize> = HashMap::new();
+
+    for (index, run) in runs.iter().enumerate() {
+        let client = &run.event.client;
+        let key = (
+            client.tenant_id.to_owned(),
+            client.bot_id.to_owned(),
+            client.channel_id.to_owned(),
+            client.user_id.to_owned(),
+        );
+
+        match clients.get(&key) {
+            Some(group) => groups[*group].push(index),
+            None => {
+                clients.insert(key, groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    groups.into_iter().collect()
+}
+
+fn run(run: &RunRequest, run_context: &RunContext) -> RunResult {
+    let bot_opt = run.get_bot_opt()?;
+
+    let mut request = run.event.to_owned();
+    // request metadata should be an empty object by default
+    if request.metadata.is_null() {
+        request.metadata = serde_json::json!({});
+    }
+
+    start_conversation_with_context(request, bot_opt, run_context.to_owned())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn run_batch(request: BatchRunRequest) -> Result<Vec<RunResult>, EngineError> {
+    if request.runs.len() > MAX_BATCH_SIZE {
+        return Err(EngineError::Format(format!(
+            "a batch can not have more than {} runs",
+            MAX_BATCH_SIZE
+        )));
+    }
+
+    let concurrency = get_concurrency(&request);
+    let groups = group_by_client(&request.runs);
+    let workers = std::cmp::min(concurrency, groups.len());
+
+    let runs = Arc::new(request.runs);
+    let run_context = Arc::new(request.run_context);
+    let groups = Arc::new(Mutex::new(groups));
+    let (sender, receiver) = mpsc::channel();
+
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let runs = runs.clone();
+            let run_context = run_context.clone();
+            let groups = groups.clone();
+            let sender = sender.clone();
+            let config = get_config();
+
+            thread::spawn(move || {
+                with_config(&config, || loop {
+                    let group = match groups.lock() {
+                        Ok(mut groups) => groups.pop_front(),
+                        Err(_) => None,
+                    };
+                    let indexes = match group {
+                        Some(indexes) => indexes,
+                        None => break,
+                    };
+
+                    for index in indexes {
+                        // the receiver waits for all the runs, it is never dropped before the end
+                        sender.send((index, run(&runs[index], &run_context))).ok();
+                    }
+                })
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut results: Vec<Option<RunResult>> = (0..runs.len()).map(|_| None).collect();
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+    for handle in handles {
+        handle.join().ok();
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|result| {
+            result
+                .unwrap_or_else(|| Err(EngineError::Manager("the run was interrupted".to_owned())))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::CsmlRequest;
+    use crate::db_connectors::{conversations, init_db, state};
+    use csml_interpreter::data::{Client, CsmlBot, CsmlFlow};
+
+    fn get_run(user_id: &str, text: &str, bot: Option<CsmlBot>) -> RunRequest {
+        RunRequest {
+            bot,
+            bot_id: None,
+            version_id: None,
+            apps_endpoint: None,
+            multibot: None,
+            environment: None,
+            event: CsmlRequest {
+                request_id: format!("{}_{}", user_id, text),
+                client: Client::new(
+                    "bot_batch".to_owned(),
+                    "channel_id".to_owned(),
+                    user_id.to_owned(),
+                ),
+                callback_url: None,
+                payload: serde_json::json!({"content_type": "text", "content": {"text": text}}),
+                metadata: serde_json::Value::Null,
+                step_limit: None,
+                ttl_duration: None,
+                low_data_mode: None,
+            },
+        }
+    }
+
+    fn get_bot() -> CsmlBot {
+        let content =
+            "start:\n    say \"Hello\"\n    hold\n    say \"You said {{event}}\"\n    goto end";
+
+        CsmlBot::new(
+            "bot_batch",
+            "bot",
+            None,
+            vec![CsmlFlow::new("Default", "Default", content, vec![])],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn get_texts(result: &RunResult) -> Vec<String> {
+        let messages = result.as_ref().unwrap()["messages"].as_array().unwrap();
+
+        messages
+            .iter()
+            .map(|message| {
+                message["payload"]["content"]["text"]
+                    .as_str()
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ok_group_by_client() {
+        let runs = vec![
+            get_run("a", "1", None),
+            get_run("b", "1", None),
+            get_run("a", "2", None),
+        ];
+
+        assert_eq!(group_by_client(&runs), vec![vec![0, 2], vec![1]]);
+    }
+
+    #[test]
+    fn ok_run_batch() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        for user_id in ["batch_a", "batch_b"] {
+            let client = get_run(user_id, "", None).event.client;
+            conversations::close_all_conversations(&client, &mut db).unwrap();
+            state::delete_state_key(&client, "hold", "position", &mut db).unwrap();
+        }
+
+        let bot = Some(get_bot());
+        let results = run_batch(BatchRunRequest {
+            runs: vec![
+                get_run("batch_a", "hi", bot.clone()),
+                get_run("batch_b", "hi", bot.clone()),
+                get_run("batch_a", "fine", bot.clone()),
+                // a run without bot fails alone
+                get_run("batch_b", "fine", None),
+            ],
+            // the sqlite database of the tests does not accept concurrent writes
+            concurrency: Some(1),
+            run_context: RunContext::default(),
+        })
+        .unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(get_texts(&results[0]), vec!["Hello"]);
+        assert_eq!(get_texts(&results[1]), vec!["Hello"]);
+        assert_eq!(get_texts(&results[2]), vec!["You said fine"]);
+        assert!(matches!(results[3], Err(EngineError::Format(_))));
+    }
+
+    #[test]
+    fn ko_run_batch_too_large() {
+        let runs = (0..MAX_BATCH_SIZE + 1)
+            .map(|index| get_run("batch", &index.to_string(), None))
+            .collect();
+
+        let result = run_batch(BatchRunRequest {
+            runs,
+            concurrency: None,
+            run_context: RunContext::default(),
+        });
+        assert!(matches!(result, Err(EngineError::Format(_))));
+    }
+}
@@ -0,0 +1,324 @@
+/**
+ * In-process cache of the initialized bots (native components, modules and validated AST),
+ * so that the requests made to the same bot version skip the parsing of its flows.
+ *
+ * Bot versions are immutable: the cache is keyed by bot_id and version_id and the least recently
+ * used bots are evicted once BOT_CACHE_SIZE (default 100, 0 disables the cache) is reached.
+ * BOT_CACHE_TTL (in seconds) optionally expires the cached bots.
+ */
+use crate::{
+    config::get_config,
+    data::{BotOpt, Database, EngineError},
+    init::{init_bot, init_saved_bot},
+};
+use csml_interpreter::data::{csml_bot::CsmlBot, csml_logs::*};
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct CachedBot {
+    bot: CsmlBot,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct BotCache {
+    // (bot_id, version_id): a version is only found with the bot it belongs to
+    bots: HashMap<(String, String), CachedBot>,
+    // incremented on each access, used to find the least recently used bot
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+static BOT_CACHE: Lazy<Mutex<BotCache>> = Lazy::new(|| Mutex::new(BotCache::default()));
+
+fn get_cache_size() -> usize {
+    get_config()
+        .integer("bots.cache_size")
+        .map(|size| size as usize)
+        .unwrap_or(100)
+}
+
+fn get_cache_ttl() -> Option<Duration> {
+    get_config()
+        .integer("bots.cache_ttl")
+        .map(Duration::from_secs)
+}
+
+impl BotCache {
+    fn get(&mut self, bot_id: &str, version_id: &str) -> Option<CsmlBot> {
+        self.clock += 1;
+        let key = (bot_id.to_owned(), version_id.to_owned());
+
+        let expired = match (self.bots.get(&key), get_cache_ttl()) {
+            (Some(cached), Some(ttl)) => cached.inserted_at.elapsed() >= ttl,
+            _ => false,
+        };
+        if expired {
+            self.bots.remove(&key);
+        }
+
+        match self.bots.get_mut(&key) {
+            Some(cached) => {
+                cached.last_used = self.clock;
+                self.hits += 1;
+                Some(cached.bot.to_owned())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, bot_id: String, version_id: String, bot: CsmlBot, size: usize) {
+        while self.bots.len() >= size {
+            let lru = self
+                .bots
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.to_owned());
+
+            match lru {
+                Some(lru) => self.bots.remove(&lru),
+                None => break,
+            };
+        }
+
+        self.clock += 1;
+        self.bots.insert(
+            (bot_id, version_id),
+            CachedBot {
+                bot,
+                inserted_at: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+}
+
+/**
+ * The apps endpoint and multibot are given by each request
+ */
+fn set_request_options(bot: &mut CsmlBot, bot_opt: &BotOpt) {
+    match bot_opt {
+        BotOpt::Id {
+            apps_endpoint,
+            multibot,
+            ..
+        }
+        | BotOpt::BotId {
+            apps_endpoint,
+            multibot,
+            ..
+        } => {
+            bot.apps_endpoint = apps_endpoint.to_owned();
+            bot.multibot = multibot.to_owned();
+        }
+        BotOpt::CsmlBot(_) => {}
+    }
+}
+
+fn get_cached_bot(bot_id: &str, version_id: &str, bot_opt: &BotOpt) -> Option<CsmlBot> {
+    let mut bot = BOT_CACHE.lock().unwrap().get(bot_id, version_id)?;
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("bot version {} found in cache", version_id),
+        ),
+        LogLvl::Debug,
+    );
+
+    set_request_options(&mut bot, bot_opt);
+    Some(bot)
+}
+
+/**
+ * Search and initialize the bot, from the cache if this version was already initialized
+ */
+pub fn get_initialized_bot(bot_opt: &BotOpt, db: &mut Database) -> Result<CsmlBot, EngineError> {
+    let (bot, _version_id) = get_initialized_bot_version(bot_opt, db)?;
+
+    Ok(bot)
+}
+
+/**
+ * Search and initialize the bot with its version_id, None if the bot is given in the request
+ */
+pub fn get_initialized_bot_version(
+    bot_opt: &BotOpt,
+    db: &mut Database,
+) -> Result<(CsmlBot, Option<String>), EngineError> {
+    let size = get_cache_size();
+
+    // the version is known before searching the bot: the database is not reached on cache hit
+    if let BotOpt::Id {
+        version_id, bot_id, ..
+    } = bot_opt
+    {
+        if size > 0 {
+            if let Some(bot) = get_cached_bot(bot_id, version_id, bot_opt) {
+                return Ok((bot, Some(version_id.to_owned())));
+            }
+        }
+    }
+
+    let (mut bot, version_id) = bot_opt.search_bot_version(db)?;
+
+    let version_id = match version_id {
+        Some(version_id) if size > 0 => version_id,
+        // bots given in the request have no version to cache them
+        None => {
+            init_bot(&mut bot)?;
+            return Ok((bot, None));
+        }
+        Some(version_id) => {
+            init_saved_bot(&mut bot)?;
+            return Ok((bot, Some(version_id)));
+        }
+    };
+
+    let bot_id = match bot_opt {
+        BotOpt::Id { bot_id, .. } => bot_id,
+        BotOpt::BotId { bot_id, .. } => {
+            if let Some(bot) = get_cached_bot(bot_id, &version_id, bot_opt) {
+                return Ok((bot, Some(version_id)));
+            }
+            bot_id
+        }
+        BotOpt::CsmlBot(csml_bot) => &csml_bot.id,
+    };
+
+    init_saved_bot(&mut bot)?;
+    BOT_CACHE.lock().unwrap().insert(
+        bot_id.to_owned(),
+        version_id.to_owned(),
+        bot.to_owned(),
+        size,
+    );
+
+    Ok((bot, Some(version_id)))
+}
+
+/**
+ * Number of cached bots, hits and misses of the cache since the engine started
+ */
+pub fn get_cache_stats() -> serde_json::Value {
+    let cache = BOT_CACHE.lock().unwrap();
+    let requests = cache.hits + cache.misses;
+
+    let hit_rate = match requests {
+        0 => 0.0,
+        _ => cache.hits as f64 / requests as f64,
+    };
+
+    serde_json::json!({
+        "size": cache.bots.len(),
+        "capacity": get_cache_size(),
+        "hits": cache.hits,
+        "misses": cache.misses,
+        "hit_rate": hit_rate,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{bot, init_db};
+    use csml_interpreter::data::csml_flow::CsmlFlow;
+
+    fn get_bot() -> CsmlBot {
+        CsmlBot::new(
+            "bot_cache",
+            "bot_cache",
+            None,
+            vec![CsmlFlow::new(
+                "flow_cache",
+                "Default",
+                "start: say \"hello\" goto end",
+                vec![],
+            )],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn ok_bot_cache_hit() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let version_id =
+            bot::create_bot_version("bot_cache".to_owned(), get_bot(), &mut db).unwrap();
+        let bot_opt = BotOpt::Id {
+            version_id,
+            bot_id: "bot_cache".to_owned(),
+            apps_endpoint: Some("http://localhost".to_owned()),
+            multibot: None,
+        };
+
+        let hits = get_cache_stats()["hits"].as_u64().unwrap();
+        let bot = get_initialized_bot(&bot_opt, &mut db).unwrap();
+        assert!(bot.bot_ast.is_some());
+
+        let cached_bot = get_initialized_bot(&bot_opt, &mut db).unwrap();
+        assert_eq!(cached_bot.bot_ast, bot.bot_ast);
+        assert_eq!(
+            cached_bot.apps_endpoint,
+            Some("http://localhost".to_owned())
+        );
+        assert_eq!(get_cache_stats()["hits"].as_u64().unwrap(), hits + 1);
+
+        bot::delete_bot_versions("bot_cache", &mut db).unwrap();
+    }
+
+    #[test]
+    fn ok_bot_cache_other_bot() {
+        let mut cache = BotCache::default();
+
+        cache.insert("bot_owner".to_owned(), "v1".to_owned(), get_bot(), 2);
+
+        // the version is cached, but it does not belong to this bot
+        assert!(cache.get("bot_other", "v1").is_none());
+        assert!(cache.get("bot_owner", "v1").is_some());
+    }
+
+    #[test]
+    fn ok_bot_cache_eviction() {
+        let mut cache = BotCache::default();
+
+        cache.insert("bot".to_owned(), "v1".to_owned(), get_bot(), 2);
+        cache.insert("bot".to_owned(), "v2".to_owned(), get_bot(), 2);
+        cache.get("bot", "v1");
+        cache.insert("bot".to_owned(), "v3".to_owned(), get_bot(), 2);
+
+        assert!(cache
+            .bots
+            .contains_key(&("bot".to_owned(), "v1".to_owned())));
+        assert!(!cache
+            .bots
+            .contains_key(&("bot".to_owned(), "v2".to_owned())));
+        assert!(cache
+            .bots
+            .contains_key(&("bot".to_owned(), "v3".to_owned())));
+    }
+}
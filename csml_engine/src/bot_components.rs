@@ -0,0 +1,210 @@
+/**
+ * Custom components registered on a bot, without publishing a new version of the bot:
+ * they are merged with the custom components of the bot version when it is loaded for a run.
+ *
+ * A registered component takes precedence over the component of the version with the same
+ * name, these conflicts are logged on each run and returned when the components are
+ * registered. The names of the native components (Button, Card...) can't be registered.
+ *
+ * The registered components of a bot are saved together in the state of the bot client.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::{bot, state},
+    utils::{get_bot_client, validate_memory_key_format},
+    CsmlBot,
+};
+use csml_interpreter::data::csml_logs::*;
+use csml_interpreter::load_components;
+
+use serde_json::{Map, Value};
+
+/**
+ * Components registered on the bot, by name
+ */
+pub fn get_components(bot_id: &str, db: &mut Database) -> Result<Map<String, Value>, EngineError> {
+    match state::get_state_key(&get_bot_client(bot_id), "bot", "custom_components", db)? {
+        Some(Value::Object(components)) => Ok(components),
+        _ => Ok(Map::new()),
+    }
+}
+
+fn save_components(
+    bot_id: &str,
+    components: &Map<String, Value>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+    let value = Value::Object(components.to_owned());
+
+    if components.is_empty() {
+        return state::delete_state_key(&client, "bot", "custom_components", db);
+    }
+    state::replace_state_item(&client, "bot", "custom_components", &value, None, db)
+}
+
+fn validate_components(components: &Map<String, Value>) -> Result<(), EngineError> {
+    let native_components = match load_components() {
+        Ok(native_components) => native_components,
+        Err(err) => return Err(EngineError::Interpreter(err.format_error())),
+    };
+
+    for (name, component) in components.iter() {
+        validate_memory_key_format(name)?;
+
+        if native_components.contains_key(name) {
+            return Err(EngineError::Format(format!(
+                "{} is a native component and can't be registered",
+                name
+            )));
+        }
+        if !component.is_object() {
+            return Err(EngineError::Format(format!(
+                "the component {} must be an object",
+                name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Names of the components of the bot version that are replaced by registered components
+ */
+fn get_conflicts(bot: &CsmlBot, components: &Map<String, Value>) -> Vec<String> {
+    let mut conflicts: Vec<String> = match &bot.custom_components {
+        Some(Value::Object(version_components)) => components
+            .keys()
+            .filter(|name| version_components.contains_key(name.as_str()))
+            .cloned()
+            .collect(),
+        _ => vec![],
+    };
+    conflicts.sort();
+
+    conflicts
+}
+
+/**
+ * Register the components on the bot, replacing the registered components with the same name.
+ * Returns the names of the components of the last version of the bot they replace.
+ */
+pub fn set_components(
+    bot_id: &str,
+    components: &Value,
+    db: &mut Database,
+) -> Result<Vec<String>, EngineError> {
+    let components = match components {
+        Value::Object(components) => components,
+        _ => {
+            return Err(EngineError::Format(
+                "the components must be an object".to_owned(),
+            ))
+        }
+    };
+    validate_components(components)?;
+
+    let mut registered = get_components(bot_id, db)?;
+    registered.extend(components.to_owned());
+    save_components(bot_id, &registered, db)?;
+
+    match bot::get_last_bot_version(bot_id, None, db)? {
+        Some(bot_version) => Ok(get_conflicts(&bot_version.bot, components)),
+        None => Ok(vec![]),
+    }
+}
+
+/**
+ * Deregister a component of the bot, returns false if it is not registered
+ */
+pub fn delete_component(bot_id: &str, name: &str, db: &mut Database) -> Result<bool, EngineError> {
+    let mut components = get_components(bot_id, db)?;
+    if components.remove(name).is_none() {
+        return Ok(false);
+    }
+
+    save_components(bot_id, &components, db)?;
+
+    Ok(true)
+}
+
+/**
+ * Merge the components registered on the bot over the custom components of its version
+ */
+pub fn apply_bot_components(bot: &mut CsmlBot, db: &mut Database) -> Result<(), EngineError> {
+    let components = get_components(&bot.id, db)?;
+    if components.is_empty() {
+        return Ok(());
+    }
+
+    let conflicts = get_conflicts(bot, &components);
+    if !conflicts.is_empty() {
+        csml_logger(
+            CsmlLog::new(
+                None,
+                None,
+                None,
+                format!(
+                    "registered components of bot {} replace the components of its version: {}",
+                    bot.id,
+                    conflicts.join(", ")
+                ),
+            ),
+            LogLvl::Warn,
+        );
+    }
+
+    match &mut bot.custom_components {
+        Some(Value::Object(version_components)) => version_components.extend(components),
+        version_components => *version_components = Some(Value::Object(components)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_bot_components() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let button = serde_json::json!({"params": [{"title": {"type": "String"}}]});
+
+        for name in ["MyButton", "MyCard"].iter() {
+            delete_component("bot_components", name, &mut db).unwrap();
+        }
+        let components = serde_json::json!({"MyButton": button, "MyCard": button});
+        set_components("bot_components", &components, &mut db).unwrap();
+
+        let mut bot = CsmlBot::new(
+            "bot_components", "bot", None, vec![], None, None, "Default", None, None, None, None,
+            None, None, None, None, None, None, None,
+        );
+        bot.custom_components = Some(serde_json::json!({"MyButton": {}, "Other": {}}));
+        assert_eq!(
+            get_conflicts(&bot, &get_components("bot_components", &mut db).unwrap()),
+            vec!["MyButton".to_owned()]
+        );
+
+        assert!(delete_component("bot_components", "MyCard", &mut db).unwrap());
+        assert!(!delete_component("bot_components", "MyCard", &mut db).unwrap());
+
+        // the registered components take precedence
+        apply_bot_components(&mut bot, &mut db).unwrap();
+        assert_eq!(
+            bot.custom_components,
+            Some(serde_json::json!({"MyButton": button, "Other": {}}))
+        );
+
+        let native = serde_json::json!({"Button": button});
+        assert!(set_components("bot_components", &native, &mut db).is_err());
+        let invalid = serde_json::json!({"MyButton": "button"});
+        assert!(set_components("bot_components", &invalid, &mut db).is_err());
+
+        delete_component("bot_components", "MyButton", &mut db).unwrap();
+    }
+}
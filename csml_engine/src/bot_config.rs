@@ -0,0 +1,129 @@
+/**
+ * The configuration of a bot is a set of key/values set per deployment (api urls, feature
+ * switches...), so that the same flows behave differently without editing their sources.
+ *
+ * At run time, the configuration is merged over the env of the bot version, read in the flows
+ * with _env.<key>, and completes the metadata of the events: the metadata of the conversation
+ * and of the event take precedence over these defaults.
+ *
+ * It is saved in the state of the bot client (type "bot_config"), and replaced as a whole.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::state,
+    utils::{get_bot_client, validate_memory_key_format},
+    CsmlBot,
+};
+
+use serde_json::{Map, Value};
+
+/**
+ * Get the configuration of the bot, an empty object if there is none
+ */
+pub fn get_config(bot_id: &str, db: &mut Database) -> Result<Map<String, Value>, EngineError> {
+    match state::get_state_key(&get_bot_client(bot_id), "bot_config", "config", db)? {
+        Some(Value::Object(config)) => Ok(config),
+        _ => Ok(Map::new()),
+    }
+}
+
+/**
+ * Replace the configuration of the bot, an empty object removes it
+ */
+pub fn set_config(
+    bot_id: &str,
+    config: &Value,
+    db: &mut Database,
+) -> Result<Map<String, Value>, EngineError> {
+    let config = match config {
+        Value::Object(config) => config,
+        _ => {
+            return Err(EngineError::Format(
+                "the bot configuration must be an object".to_owned(),
+            ))
+        }
+    };
+    for key in config.keys() {
+        validate_memory_key_format(key)?;
+    }
+
+    let client = get_bot_client(bot_id);
+    match config.is_empty() {
+        true => state::delete_state_key(&client, "bot_config", "config", db)?,
+        false => {
+            let saved = Value::Object(config.to_owned());
+            state::replace_state_item(&client, "bot_config", "config", &saved, None, db)?
+        }
+    }
+
+    Ok(config.to_owned())
+}
+
+/**
+ * Merge the configuration of the bot over the env of its sources
+ */
+pub fn apply_bot_env(bot: &mut CsmlBot, db: &mut Database) -> Result<(), EngineError> {
+    let config = get_config(&bot.id, db)?;
+    if config.is_empty() {
+        return Ok(());
+    }
+
+    match &mut bot.env {
+        Some(Value::Object(env)) => env.extend(config),
+        env => *env = Some(Value::Object(config)),
+    }
+
+    Ok(())
+}
+
+/**
+ * Complete the metadata of the event with the configuration of the bot
+ */
+pub fn add_metadata_defaults(
+    bot_id: &str,
+    metadata: Value,
+    db: &mut Database,
+) -> Result<Value, EngineError> {
+    let mut defaults = get_config(bot_id, db)?;
+
+    match metadata {
+        Value::Object(metadata) => {
+            defaults.extend(metadata);
+            Ok(Value::Object(defaults))
+        }
+        Value::Null if !defaults.is_empty() => Ok(Value::Object(defaults)),
+        // the metadata of the event are kept as they are when they are not an object
+        metadata => Ok(metadata),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_bot_config() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let config = serde_json::json!({"api_url": "https://staging.example.com", "beta": true});
+        set_config("bot_config", &config, &mut db).unwrap();
+        let config = serde_json::json!({"api_url": "https://example.com"});
+        set_config("bot_config", &config, &mut db).unwrap();
+        assert_eq!(Value::Object(get_config("bot_config", &mut db).unwrap()), config);
+
+        // the metadata of the event take precedence
+        let metadata = serde_json::json!({"api_url": "https://test.example.com", "locale": "fr"});
+        let merged = add_metadata_defaults("bot_config", metadata.clone(), &mut db).unwrap();
+        assert_eq!(merged, metadata);
+        let merged = add_metadata_defaults("bot_config", Value::Null, &mut db).unwrap();
+        assert_eq!(merged, config);
+
+        assert!(set_config("bot_config", &serde_json::json!({"api-url": 1}), &mut db).is_err());
+        assert!(set_config("bot_config", &serde_json::json!(["api_url"]), &mut db).is_err());
+
+        set_config("bot_config", &serde_json::json!({}), &mut db).unwrap();
+        assert!(get_config("bot_config", &mut db).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+/**
+ * Globals are a key/value store shared by all the users of a bot (announcements,
+ * feature flags...). They are read only in the flows with _global.<key> and written
+ * through the engine API.
+ *
+ * They are saved as the memories of the bot client, and cached in memory for
+ * GLOBALS_CACHE_TTL seconds (default 60). Updating a global invalidates the cache
+ * of this engine instance: other instances see the new value once their cache expires.
+ */
+use crate::{
+    config::get_config,
+    data::{Database, EngineError},
+    db_connectors::memories,
+    utils::{get_bot_client, validate_memory_key_format},
+};
+use csml_interpreter::data::{context::get_hashmap_from_mem, csml_logs::*, Literal};
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+fn get_cache_ttl() -> Duration {
+    let ttl = get_config().integer("bots.globals_cache_ttl").unwrap_or(60);
+
+    Duration::from_secs(ttl)
+}
+
+// bot_id => (fetched at, globals)
+static GLOBALS_CACHE: Lazy<Mutex<HashMap<String, (Instant, serde_json::Value)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn invalidate_cache(bot_id: &str) {
+    GLOBALS_CACHE.lock().unwrap().remove(bot_id);
+}
+
+fn fetch_globals(bot_id: &str, db: &mut Database) -> Result<serde_json::Value, EngineError> {
+    if let Some((fetched_at, globals)) = GLOBALS_CACHE.lock().unwrap().get(bot_id) {
+        if fetched_at.elapsed() < get_cache_ttl() {
+            return Ok(globals.to_owned());
+        }
+    }
+
+    let globals = get_globals(bot_id, db)?;
+
+    GLOBALS_CACHE
+        .lock()
+        .unwrap()
+        .insert(bot_id.to_owned(), (Instant::now(), globals.to_owned()));
+
+    Ok(globals)
+}
+
+/**
+ * Get the globals of the bot for the interpreter context.
+ * Like secrets, a failing store must not break the conversation: the error is logged.
+ */
+pub fn get_bot_globals(bot_id: &str, flow: &str, db: &mut Database) -> HashMap<String, Literal> {
+    match fetch_globals(bot_id, db) {
+        Ok(globals) => get_hashmap_from_mem(&globals, flow),
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(
+                    None,
+                    Some(flow.to_owned()),
+                    None,
+                    format!("failed to get globals of bot {}: {:?}", bot_id, err),
+                ),
+                LogLvl::Error,
+            );
+
+            HashMap::new()
+        }
+    }
+}
+
+pub fn get_globals(bot_id: &str, db: &mut Database) -> Result<serde_json::Value, EngineError> {
+    memories::internal_use_get_memories(&get_bot_client(bot_id), db)
+}
+
+pub fn set_global(
+    bot_id: &str,
+    key: String,
+    value: serde_json::Value,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    validate_memory_key_format(&key)?;
+
+    memories::create_client_memory(&get_bot_client(bot_id), key, value, None, db)?;
+    invalidate_cache(bot_id);
+
+    Ok(())
+}
+
+pub fn delete_global(bot_id: &str, key: &str, db: &mut Database) -> Result<(), EngineError> {
+    memories::delete_client_memory(&get_bot_client(bot_id), key, db)?;
+    invalidate_cache(bot_id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_globals_cache_invalidation() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        set_global(
+            "bot_globals",
+            "announcement".to_owned(),
+            serde_json::json!("hello"),
+            &mut db,
+        )
+        .unwrap();
+        let globals = get_bot_globals("bot_globals", "Default", &mut db);
+        assert_eq!(globals["announcement"].primitive.to_string(), "hello");
+
+        delete_global("bot_globals", "announcement", &mut db).unwrap();
+        let globals = get_bot_globals("bot_globals", "Default", &mut db);
+        assert!(globals.get("announcement").is_none());
+    }
+}
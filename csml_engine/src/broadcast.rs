@@ -0,0 +1,311 @@
+/**
+ * Broadcasts trigger a flow for many clients of a bot (campaigns, notifications...).
+ *
+ * The conversations are started in a background thread, at most `rate_limit` per second
+ * (default BROADCAST_RATE_LIMIT env var, or 10). The progress is saved in the bot state
+ * so that the broadcast can be polled with its broadcast_id.
+ */
+use crate::{
+    config::get_config,
+    data::{BotOpt, Broadcast, BroadcastRequest, CsmlRequest, Database, EngineError},
+    db_connectors::{conversations, init_db, state, utils::get_tenant_bot_id},
+    shutdown::is_shutting_down,
+    start_system_conversation,
+    utils::get_bot_client,
+    Client,
+};
+use csml_interpreter::data::csml_logs::*;
+
+use chrono::{prelude::Utc, SecondsFormat};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const BROADCAST_RUNNING: &str = "running";
+pub const BROADCAST_DONE: &str = "done";
+// the server running the broadcast was shut down before the end
+pub const BROADCAST_INTERRUPTED: &str = "interrupted";
+
+// the progress of the broadcast is saved every SAVE_INTERVAL conversations
+const SAVE_INTERVAL: usize = 10;
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn get_rate_limit(request: &BroadcastRequest) -> u64 {
+    let rate_limit = match request.rate_limit {
+        Some(rate_limit) => rate_limit,
+        None => get_config()
+            .integer("broadcasts.rate_limit")
+            .unwrap_or(10),
+    };
+
+    std::cmp::max(rate_limit, 1)
+}
+
+pub fn get_broadcast_clients(
+    request: &BroadcastRequest,
+    db: &mut Database,
+) -> Result<Vec<Client>, EngineError> {
+    if request.flow_id.is_empty() {
+        return Err(EngineError::Format(
+            "broadcast flow_id must not be empty".to_owned(),
+        ));
+    }
+
+    match (&request.clients, &request.filter) {
+        (Some(clients), _) => {
+            if clients.iter().any(|client| {
+                get_tenant_bot_id(client.tenant_id.as_deref(), &client.bot_id) != request.bot_id
+            }) {
+                return Err(EngineError::Format(format!(
+                    "all the clients of the broadcast must belong to bot {}",
+                    request.bot_id
+                )));
+            }
+
+            Ok(clients.to_owned())
+        }
+        (None, Some(filter)) => {
+            conversations::get_bot_clients(&request.bot_id, filter.channel_id.as_deref(), db)
+        }
+        (None, None) => Err(EngineError::Format(
+            "broadcast must have a list of clients or a filter".to_owned(),
+        )),
+    }
+}
+
+pub fn get_broadcast(
+    bot_id: &str,
+    broadcast_id: &str,
+    db: &mut Database,
+) -> Result<Option<Broadcast>, EngineError> {
+    let client = get_bot_client(bot_id);
+
+    match state::get_state_key(&client, "broadcast", broadcast_id, db)? {
+        Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn save_broadcast(broadcast: &Broadcast, db: &mut Database) -> Result<(), EngineError> {
+    let client = get_bot_client(&broadcast.bot_id);
+    let value = serde_json::json!(broadcast);
+
+    state::replace_state_item(&client, "broadcast", &broadcast.broadcast_id, &value, None, db)
+}
+
+pub fn new_broadcast(bot_id: &str, total: usize) -> Broadcast {
+    let now = now();
+
+    Broadcast {
+        broadcast_id: uuid::Uuid::new_v4().to_string(),
+        bot_id: bot_id.to_owned(),
+        status: BROADCAST_RUNNING.to_owned(),
+        total,
+        sent: 0,
+        failed: 0,
+        created_at: now.clone(),
+        updated_at: now,
+    }
+}
+
+fn get_request(request: &BroadcastRequest, client: Client) -> CsmlRequest {
+    CsmlRequest {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        client,
+        callback_url: request.callback_url.to_owned(),
+        payload: serde_json::json!({
+            "content_type": "flow_trigger",
+            "content": {
+                "flow_id": request.flow_id,
+                "step_id": request.step_id,
+            }
+        }),
+        metadata: match &request.metadata {
+            serde_json::Value::Null => serde_json::json!({}),
+            metadata => metadata.to_owned(),
+        },
+        step_limit: None,
+        ttl_duration: None,
+        low_data_mode: None,
+    }
+}
+
+/**
+ * Start a conversation for each client of the broadcast, then mark it as done
+ */
+pub fn run_broadcast(request: BroadcastRequest, clients: Vec<Client>, mut broadcast: Broadcast) {
+    let interval = Duration::from_millis(1000 / get_rate_limit(&request));
+
+    let mut db = match init_db() {
+        Ok(db) => db,
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(
+                    None,
+                    None,
+                    None,
+                    format!("broadcast {}: {:?}", broadcast.broadcast_id, err),
+                ),
+                LogLvl::Error,
+            );
+            return;
+        }
+    };
+
+    let mut status = BROADCAST_DONE;
+
+    for (index, client) in clients.into_iter().enumerate() {
+        if is_shutting_down() {
+            status = BROADCAST_INTERRUPTED;
+            break;
+        }
+
+        let started_at = Instant::now();
+
+        let bot_opt = BotOpt::BotId {
+            bot_id: request.bot_id.to_owned(),
+            apps_endpoint: request.apps_endpoint.to_owned(),
+            multibot: request.multibot.to_owned(),
+            environment: request.environment.to_owned(),
+        };
+
+        match start_system_conversation(get_request(&request, client.clone()), bot_opt) {
+            Ok(_) => broadcast.sent += 1,
+            Err(err) => {
+                broadcast.failed += 1;
+
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&client),
+                        Some(request.flow_id.to_owned()),
+                        None,
+                        format!("broadcast {}: {:?}", broadcast.broadcast_id, err),
+                    ),
+                    LogLvl::Error,
+                );
+            }
+        }
+
+        if (index + 1) % SAVE_INTERVAL == 0 {
+            broadcast.updated_at = now();
+            save_broadcast(&broadcast, &mut db).ok();
+        }
+
+        if let Some(remaining) = interval.checked_sub(started_at.elapsed()) {
+            thread::sleep(remaining);
+        }
+    }
+
+    broadcast.status = status.to_owned();
+    broadcast.updated_at = now();
+
+    if let Err(err) = save_broadcast(&broadcast, &mut db) {
+        csml_logger(
+            CsmlLog::new(
+                None,
+                None,
+                None,
+                format!("broadcast {}: {:?}", broadcast.broadcast_id, err),
+            ),
+            LogLvl::Error,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_broadcast_request() -> BroadcastRequest {
+        BroadcastRequest {
+            bot_id: "bot_id".to_owned(),
+            apps_endpoint: None,
+            multibot: None,
+            environment: None,
+            clients: Some(vec![Client::new(
+                "bot_id".to_owned(),
+                "channel_id".to_owned(),
+                "user_id".to_owned(),
+            )]),
+            filter: None,
+            flow_id: "Default".to_owned(),
+            step_id: None,
+            metadata: serde_json::Value::Null,
+            callback_url: None,
+            rate_limit: Some(5),
+        }
+    }
+
+    #[test]
+    fn ok_broadcast_request() {
+        let request = get_broadcast_request();
+        let csml_request = get_request(&request, request.clients.clone().unwrap()[0].clone());
+
+        assert_eq!(csml_request.payload["content_type"], "flow_trigger");
+        assert_eq!(csml_request.payload["content"]["flow_id"], "Default");
+        assert_eq!(csml_request.metadata, serde_json::json!({}));
+        assert_eq!(get_rate_limit(&request), 5);
+    }
+
+    #[test]
+    fn ko_broadcast_other_bot_client() {
+        let mut db = init_db().unwrap();
+        let mut request = get_broadcast_request();
+        request.bot_id = "other_bot_id".to_owned();
+
+        assert!(get_broadcast_clients(&request, &mut db).is_err());
+    }
+
+    #[test]
+    fn ok_broadcast_filter() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "broadcast_bot_id".to_owned(),
+            "broadcast_channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut request = get_broadcast_request();
+        request.bot_id = client.bot_id.to_owned();
+        request.clients = None;
+        request.filter = Some(crate::data::BroadcastFilter {
+            channel_id: Some(client.channel_id.to_owned()),
+        });
+
+        let clients = get_broadcast_clients(&request, &mut db).unwrap();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].user_id, client.user_id);
+
+        crate::db_connectors::user::delete_client(&client, &mut db).unwrap();
+    }
+
+    #[test]
+    fn ok_broadcast_status() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let mut broadcast = new_broadcast("broadcast_status_bot_id", 3);
+        save_broadcast(&broadcast, &mut db).unwrap();
+
+        broadcast.sent = 2;
+        broadcast.failed = 1;
+        broadcast.status = BROADCAST_DONE.to_owned();
+        save_broadcast(&broadcast, &mut db).unwrap();
+
+        let saved = get_broadcast(&broadcast.bot_id, &broadcast.broadcast_id, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.status, BROADCAST_DONE);
+        assert_eq!(saved.sent, 2);
+        assert_eq!(saved.failed, 1);
+
+        assert!(get_broadcast(&broadcast.bot_id, "unknown", &mut db)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,358 @@
+/**
+ * Embedding API: Rust applications configure the engine in their own binaries with an
+ * EngineBuilder instead of the env vars, and interpret the events with the Engine it builds.
+ *
+ * ```ignore
+ * let engine = EngineBuilder::new()
+ *     .sqlite("csml.db")
+ *     .encryption_secret("some-secret-string")
+ *     .limit("step", 50)
+ *     .setting("conversations.ttl_duration", 30)
+ *     .bot(BotOpt::CsmlBot(bot))
+ *     .migrations(true)
+ *     .build()?;
+ *
+ * let response = engine.run(request)?;
+ * ```
+ *
+ * The settings of the builder override the env vars and the configuration file, build()
+ * validates them like the server does at startup. Each Engine keeps its own settings and step
+ * hooks, and runs the events with them: the engines of a process can use different databases
+ * or limits, and the env vars of the process are never changed.
+ */
+use crate::{
+    config::{self, Config},
+    data::{BotOpt, CsmlRequest, EngineError},
+    db_connectors,
+    step_hooks::{with_embedded_hooks, StepHooksFactory},
+    Client,
+};
+use csml_interpreter::data::StepHooks;
+
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+#[derive(Default)]
+pub struct EngineBuilder {
+    config: Config,
+    errors: Vec<String>,
+    step_hooks: Option<Arc<StepHooksFactory>>,
+    bot: Option<BotOpt>,
+    migrations: bool,
+}
+
+impl EngineBuilder {
+    /**
+     * Builder on top of the settings loaded from the configuration file, if any
+     */
+    pub fn new() -> Self {
+        Self {
+            config: config::get_config().as_ref().clone(),
+            ..Self::default()
+        }
+    }
+
+    /**
+     * Any setting of the configuration file, by its key: ("conversations.ttl_duration", 30).
+     * The keys are listed in config.rs.
+     */
+    pub fn setting<V: Into<Value>>(mut self, key: &str, value: V) -> Self {
+        if let Err(err) = self.config.set(key, &value.into()) {
+            self.errors.push(err);
+        }
+
+        self
+    }
+
+    /**
+     * Any other env var of the engine, like the settings of a single bot
+     * (RUN_SIGNATURE_SECRET_<BOT_ID>...)
+     */
+    pub fn env(mut self, name: &str, value: &str) -> Self {
+        self.config.set_var(name, value);
+        self
+    }
+
+    pub fn sqlite(self, url: &str) -> Self {
+        self.setting("database.type", "sqlite")
+            .setting("database.sqlite.url", url)
+    }
+
+    pub fn postgresql(self, url: &str) -> Self {
+        self.setting("database.type", "postgresql")
+            .setting("database.postgresql.url", url)
+    }
+
+    pub fn mongodb(self, uri: &str, database: &str) -> Self {
+        self.setting("database.type", "mongodb")
+            .setting("database.mongodb.uri", uri)
+            .setting("database.mongodb.database", database)
+    }
+
+    pub fn dynamodb(self, table: &str, s3_bucket: &str) -> Self {
+        self.setting("database.type", "dynamodb")
+            .setting("database.dynamodb.table", table)
+            .setting("database.dynamodb.s3_bucket", s3_bucket)
+    }
+
+    /**
+     * Secret of the encryption of the data saved in the database
+     */
+    pub fn encryption_secret(self, secret: &str) -> Self {
+        self.setting("encryption.secret", secret)
+    }
+
+    /**
+     * A limit of the interpreter: step, loop, goto_flow, execution_time (in milliseconds),
+     * string_size, array_size or object_size
+     */
+    pub fn limit(self, name: &str, value: u64) -> Self {
+        self.setting(&format!("limits.{}", name), value)
+    }
+
+    /**
+     * Step hooks of the conversations, in Rust: the factory returns the hooks of the
+     * conversation of a client (with its id), None to keep the hooks of STEP_HOOKS
+     */
+    pub fn step_hooks<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&Client, &str) -> Option<Arc<dyn StepHooks>> + Send + Sync + 'static,
+    {
+        self.step_hooks = Some(Arc::new(factory));
+        self
+    }
+
+    /**
+     * Bot interpreting the events of Engine::run
+     */
+    pub fn bot(mut self, bot: BotOpt) -> Self {
+        self.bot = Some(bot);
+        self
+    }
+
+    /**
+     * Apply the migrations of the database when the engine is built
+     */
+    pub fn migrations(mut self, migrations: bool) -> Self {
+        self.migrations = migrations;
+        self
+    }
+
+    /**
+     * Check the settings and make them the settings of the engine, every invalid setting
+     * is reported
+     */
+    pub fn build(self) -> Result<Engine, EngineError> {
+        if !self.errors.is_empty() {
+            return Err(EngineError::Format(self.errors.join("\n")));
+        }
+
+        let errors = self.config.validate();
+        if !errors.is_empty() {
+            return Err(EngineError::Format(errors.join("\n")));
+        }
+
+        let engine = Engine {
+            config: Arc::new(self.config),
+            step_hooks: self.step_hooks,
+            bot: self.bot,
+        };
+        if self.migrations {
+            engine.with_settings(|| db_connectors::make_migrations_with(&engine.config))?;
+        }
+
+        Ok(engine)
+    }
+}
+
+/**
+ * Handle of an engine configured by an EngineBuilder
+ */
+#[derive(Clone)]
+pub struct Engine {
+    config: Arc<Config>,
+    step_hooks: Option<Arc<StepHooksFactory>>,
+    bot: Option<BotOpt>,
+}
+
+impl std::fmt::Debug for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the settings are not printed, they contain the secrets of the engine
+        f.debug_struct("Engine")
+            .field("step_hooks", &self.step_hooks.is_some())
+            .field("bot", &self.bot)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Engine {
+    /**
+     * Run f with the settings and the step hooks of the engine: the functions of the crate
+     * called by f (get_conversation, delete_client...) use the database of the engine
+     */
+    pub fn with_settings<T>(&self, f: impl FnOnce() -> T) -> T {
+        config::with_config(&self.config, || {
+            with_embedded_hooks(self.step_hooks.clone(), f)
+        })
+    }
+
+    /**
+     * Interpret an event with the bot of the engine
+     */
+    pub fn run(&self, event: CsmlRequest) -> Result<Map<String, Value>, EngineError> {
+        match &self.bot {
+            Some(bot) => self.run_bot(event, bot.to_owned()),
+            None => Err(EngineError::Manager(
+                "the engine has no bot, set it with EngineBuilder::bot or use run_bot".to_owned(),
+            )),
+        }
+    }
+
+    /**
+     * Engine running the bots without database, see stateless.rs
+     */
+    #[cfg(feature = "stateless")]
+    pub fn stateless() -> crate::StatelessEngine {
+        crate::StatelessEngine::new()
+    }
+
+    /**
+     * Interpret an event with another bot than the bot of the engine
+     */
+    pub fn run_bot(
+        &self,
+        mut event: CsmlRequest,
+        bot: BotOpt,
+    ) -> Result<Map<String, Value>, EngineError> {
+        // request metadata should be an empty object by default
+        if event.metadata.is_null() {
+            event.metadata = serde_json::json!({});
+        }
+
+        self.with_settings(|| crate::start_conversation(event, bot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::data::{CsmlBot, CsmlFlow};
+    use std::{sync::Mutex, time::Duration};
+
+    fn get_request(text: &str) -> CsmlRequest {
+        serde_json::from_value(serde_json::json!({
+            "request_id": "request_id",
+            "client": {"bot_id": "builder_bot", "channel_id": "channel_id", "user_id": "user_id"},
+            "payload": {"content_type": "text", "content": {"text": text}},
+            "metadata": null,
+        }))
+        .unwrap()
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordedSteps {
+        steps: Mutex<Vec<String>>,
+    }
+
+    impl StepHooks for RecordedSteps {
+        fn before_step(&self, _flow: &str, step: &str, _variables: &Value) -> Result<(), String> {
+            self.steps.lock().unwrap().push(step.to_owned());
+            Ok(())
+        }
+
+        fn after_step(&self, _flow: &str, _step: &str, _duration: Duration, _variables: &Value) {}
+    }
+
+    #[test]
+    fn ok_engine_step_hooks() {
+        crate::make_migrations().unwrap_or({});
+        let content =
+            "start:\n    say \"Hello\"\n    goto next\n\nnext:\n    say \"Bye\"\n    goto end";
+        let bot = CsmlBot::new(
+            "builder_bot",
+            "bot",
+            None,
+            vec![CsmlFlow::new("Default", "Default", content, vec![])],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let recorded = Arc::new(RecordedSteps::default());
+        let hooks = recorded.clone();
+        let factory = move |client: &Client, _conversation_id: &str| match client.bot_id.as_str() {
+            "builder_bot" => Some(hooks.clone() as Arc<dyn StepHooks>),
+            _ => None,
+        };
+        let engine = EngineBuilder::new()
+            .step_hooks(factory)
+            .bot(BotOpt::CsmlBot(bot))
+            .build()
+            .unwrap();
+
+        let response = engine.run(get_request("hi")).unwrap();
+
+        assert_eq!(response["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(*recorded.steps.lock().unwrap(), vec!["start", "next"]);
+    }
+
+    #[test]
+    fn ko_builder_invalid_settings() {
+        let result = EngineBuilder::new()
+            .setting("database.type", "cassandra")
+            .setting("conversations.unknown", 1)
+            .limit("step", 50)
+            .build();
+
+        let errors = match result {
+            Err(EngineError::Format(errors)) => errors,
+            result => panic!("invalid settings were accepted: {:?}", result.is_ok()),
+        };
+        assert!(errors.contains("database.type: expected one of mongodb"));
+        assert!(errors.contains("conversations.unknown: unknown setting"));
+        assert!(!errors.contains("limits.step"));
+    }
+
+    #[test]
+    fn ko_engine_without_bot() {
+        let engine = EngineBuilder::new().build().unwrap();
+
+        assert!(matches!(
+            engine.run(get_request("hi")),
+            Err(EngineError::Manager(_))
+        ));
+    }
+
+    #[test]
+    fn ok_engines_own_settings() {
+        let step_limit = std::env::var("STEP_LIMIT").ok();
+        let first = EngineBuilder::new().limit("step", 10).build().unwrap();
+        let second = EngineBuilder::new().limit("step", 20).build().unwrap();
+
+        let get_limit =
+            |engine: &Engine| engine.with_settings(|| config::get_config().integer("limits.step"));
+        assert_eq!(get_limit(&first), Some(10));
+        assert_eq!(get_limit(&second), Some(20));
+        assert_eq!(
+            first.with_settings(
+                || second.with_settings(|| config::get_config().integer("limits.step"))
+            ),
+            Some(20)
+        );
+        assert_eq!(get_limit(&first), Some(10));
+
+        assert_eq!(std::env::var("STEP_LIMIT").ok(), step_limit);
+        assert_ne!(config::get_config().integer("limits.step"), Some(10));
+    }
+}
@@ -0,0 +1,139 @@
+/**
+ * Bot bundles are used to promote a bot version from one engine instance to another.
+ *
+ * A bundle contains the bot (flows, modules, custom components, env...) and the metadata
+ * of the exported version. It is signed with HMAC-SHA256: both engine instances must share
+ * the same BOT_BUNDLE_SECRET environment variable.
+ */
+use crate::config::get_config;
+use crate::data::{BotBundle, EngineError};
+use crate::db_connectors::BotVersion;
+
+use chrono::{prelude::Utc, SecondsFormat};
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
+
+fn get_bundle_secret() -> Result<String, EngineError> {
+    match get_config().text("bots.bundle_secret") {
+        Some(secret) => Ok(secret),
+        _ => Err(EngineError::Manager(
+            "No BOT_BUNDLE_SECRET value in env".to_owned(),
+        )),
+    }
+}
+
+fn get_bundle_content(
+    version_id: &str,
+    engine_version: &str,
+    exported_at: &str,
+    bot: &serde_json::Value,
+) -> String {
+    serde_json::json!({
+        "version_id": version_id,
+        "engine_version": engine_version,
+        "exported_at": exported_at,
+        "bot": bot,
+    })
+    .to_string()
+}
+
+fn sign(content: &str) -> Result<Vec<u8>, EngineError> {
+    let secret = get_bundle_secret()?;
+    let key = PKey::hmac(secret.as_bytes())?;
+
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(content.as_bytes())?;
+
+    Ok(signer.sign_to_vec()?)
+}
+
+pub fn export_bundle(bot_version: BotVersion) -> Result<BotBundle, EngineError> {
+    let mut bot = bot_version.bot;
+
+    // those values are set by the engine when the bot is loaded
+    bot.apps_endpoint = None;
+    bot.multibot = None;
+    bot.native_components = None;
+    bot.bot_ast = None;
+
+    let bot = serde_json::to_value(bot)?;
+    let exported_at = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let content = get_bundle_content(
+        &bot_version.version_id,
+        &bot_version.engine_version,
+        &exported_at,
+        &bot,
+    );
+    let signature = base64::encode(sign(&content)?);
+
+    Ok(BotBundle {
+        version_id: bot_version.version_id,
+        engine_version: bot_version.engine_version,
+        exported_at,
+        bot,
+        signature,
+    })
+}
+
+pub fn verify_bundle(bundle: &BotBundle) -> Result<(), EngineError> {
+    let content = get_bundle_content(
+        &bundle.version_id,
+        &bundle.engine_version,
+        &bundle.exported_at,
+        &bundle.bot,
+    );
+
+    let expected = sign(&content)?;
+    let signature = base64::decode(&bundle.signature)?;
+
+    if signature.len() != expected.len() || !memcmp::eq(&signature, &expected) {
+        return Err(EngineError::Format("Invalid bot bundle signature".to_owned()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use csml_interpreter::data::{CsmlBot, CsmlFlow};
+
+    fn get_bot_version() -> BotVersion {
+        BotVersion {
+            bot: CsmlBot::new(
+                "bot_id",
+                "bot",
+                None,
+                vec![CsmlFlow::new("Default", "Default", "start: say \"hello\"", vec![])],
+                None,
+                None,
+                "Default",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+            version_id: "version_id".to_owned(),
+            engine_version: "1.0.0".to_owned(),
+        }
+    }
+
+    #[test]
+    fn ok_bundle_signature() {
+        env::set_var("BOT_BUNDLE_SECRET", "secret");
+
+        let mut bundle = export_bundle(get_bot_version()).unwrap();
+        assert!(verify_bundle(&bundle).is_ok());
+
+        bundle.bot["name"] = serde_json::json!("other");
+        assert!(verify_bundle(&bundle).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+/**
+ * Per client lock of the runs: the events of a client are interpreted one at a time by the
+ * engine process, by priority then in their order of arrival.
+ *
+ * Ordering guarantees, for the events of the same client:
+ * - a run in progress is never interrupted, the events received meanwhile wait for its end
+ * - when a run ends, the waiting system events (handover resume, scheduled events,
+ *   broadcasts) are interpreted before the waiting user messages
+ * - the events of the same priority are interpreted in their order of arrival
+ *
+ * The locks are held by each engine process: the guarantees only hold across several servers
+ * when the events of a client are always sent to the same server. Otherwise the versions of
+ * the conversations keep the servers from interleaving their updates, see update_conversation.
+ * An event waiting for more than CLIENT_LOCK_TIMEOUT milliseconds (default 30000) is refused
+ * with ClientBusy.
+ */
+use crate::{
+    config::get_config,
+    data::{EngineError, EventPriority},
+    db_connectors::utils::get_storage_client,
+    Client,
+};
+
+use once_cell::sync::Lazy;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+const DEFAULT_TIMEOUT: u64 = 30_000;
+
+#[derive(Debug, Default)]
+struct ClientQueue {
+    running: bool,
+    // the first ticket is the next event of the client
+    waiting: BTreeSet<(EventPriority, u64)>,
+}
+
+static QUEUES: Lazy<Mutex<HashMap<String, ClientQueue>>> = Lazy::new(Default::default);
+static RELEASED: Lazy<Condvar> = Lazy::new(Condvar::new);
+static NEXT_TICKET: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * Lock of the run of a client, released when it is dropped
+ */
+#[derive(Debug)]
+pub struct ClientLock {
+    key: String,
+}
+
+fn lock_queues() -> MutexGuard<'static, HashMap<String, ClientQueue>> {
+    QUEUES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/**
+ * The bot_id of the tenant of the client: the clients of two tenants never share a key
+ */
+fn get_key(client: &Client) -> String {
+    let client = get_storage_client(client);
+
+    format!("{}:{}:{}", client.bot_id, client.channel_id, client.user_id)
+}
+
+fn get_timeout() -> Duration {
+    let timeout = get_config()
+        .integer("conversations.client_lock_timeout")
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    Duration::from_millis(timeout)
+}
+
+fn remove_idle_queue(queues: &mut HashMap<String, ClientQueue>, key: &str) {
+    if let Some(queue) = queues.get(key) {
+        if !queue.running && queue.waiting.is_empty() {
+            queues.remove(key);
+        }
+    }
+}
+
+impl Drop for ClientLock {
+    fn drop(&mut self) {
+        let mut queues = lock_queues();
+        if let Some(queue) = queues.get_mut(&self.key) {
+            queue.running = false;
+        }
+        remove_idle_queue(&mut queues, &self.key);
+
+        RELEASED.notify_all();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Wait for the turn of an event of the client, and lock its run
+ */
+pub fn lock_client(client: &Client, priority: EventPriority) -> Result<ClientLock, EngineError> {
+    let key = get_key(client);
+    let ticket = (priority, NEXT_TICKET.fetch_add(1, Ordering::SeqCst));
+    let deadline = Instant::now() + get_timeout();
+
+    let mut queues = lock_queues();
+    queues
+        .entry(key.clone())
+        .or_default()
+        .waiting
+        .insert(ticket);
+
+    loop {
+        let queue = queues.entry(key.clone()).or_default();
+        if !queue.running && queue.waiting.iter().next() == Some(&ticket) {
+            queue.waiting.remove(&ticket);
+            queue.running = true;
+
+            return Ok(ClientLock { key });
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            queue.waiting.remove(&ticket);
+            remove_idle_queue(&mut queues, &key);
+            // the next event may have been waiting behind this one
+            RELEASED.notify_all();
+
+            return Err(EngineError::ClientBusy);
+        }
+
+        queues = RELEASED
+            .wait_timeout(queues, deadline - now)
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    fn get_client(user_id: &str) -> Client {
+        Client::new(
+            "locks_bot".to_owned(),
+            "channel".to_owned(),
+            user_id.to_owned(),
+        )
+    }
+
+    fn waiting_events(client: &Client) -> usize {
+        lock_queues()
+            .get(&get_key(client))
+            .map(|queue| queue.waiting.len())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn ok_tenant_keys() {
+        let mut client = get_client("tenant_user");
+        client.tenant_id = Some("tenant_a".to_owned());
+        let mut other_client = get_client("tenant_user");
+        other_client.tenant_id = Some("tenant_b".to_owned());
+
+        // the same client in two tenants is not locked by the runs of the other tenant
+        assert_ne!(get_key(&client), get_key(&other_client));
+    }
+
+    #[test]
+    fn ok_system_events_first() {
+        let client = get_client("priority_user");
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let lock = lock_client(&client, EventPriority::User).unwrap();
+
+        let mut handles = vec![];
+        for (name, priority) in vec![
+            ("user_1", EventPriority::User),
+            ("system_1", EventPriority::System),
+            ("user_2", EventPriority::User),
+            ("system_2", EventPriority::System),
+        ] {
+            let (event_client, order) = (client.clone(), order.clone());
+            handles.push(thread::spawn(move || {
+                let _lock = lock_client(&event_client, priority).unwrap();
+                order.lock().unwrap().push(name);
+            }));
+
+            // the events are received in this order
+            while waiting_events(&client) < handles.len() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        drop(lock);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["system_1", "system_2", "user_1", "user_2"]
+        );
+        assert!(lock_queues().get(&get_key(&client)).is_none());
+    }
+
+    #[test]
+    fn ok_clients_not_locked_by_others() {
+        let _lock = lock_client(&get_client("first_user"), EventPriority::User).unwrap();
+
+        assert!(lock_client(&get_client("second_user"), EventPriority::User).is_ok());
+    }
+}
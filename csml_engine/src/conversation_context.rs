@@ -0,0 +1,118 @@
+/**
+ * Context variables are written with `remember_temp` and only live for the duration of
+ * the current conversation, unlike memories which are kept until they are forgotten.
+ *
+ * They are saved in the client state (type "context") with the id of their conversation:
+ * once this conversation is closed or expires, they are discarded.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::state,
+    Client,
+};
+use csml_interpreter::data::Memory;
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/**
+ * Get the context variables of the conversation
+ */
+pub fn get_context_variables(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<HashMap<String, Memory>, EngineError> {
+    let context = match state::get_state_key(client, "context", "variables", db)? {
+        Some(context) => context,
+        None => return Ok(HashMap::new()),
+    };
+
+    if context["conversation_id"] != conversation_id {
+        // the variables belong to a conversation that is not open anymore
+        delete_context_variables(client, db)?;
+        return Ok(HashMap::new());
+    }
+
+    let variables = match context["variables"].as_object() {
+        Some(variables) => variables
+            .iter()
+            .map(|(key, value)| {
+                let memory = Memory {
+                    key: key.to_owned(),
+                    value: value.to_owned(),
+                };
+                (key.to_owned(), memory)
+            })
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    Ok(variables)
+}
+
+/**
+ * Replace the context variables of the conversation
+ */
+pub fn save_context_variables(
+    client: &Client,
+    conversation_id: &str,
+    variables: &HashMap<String, Memory>,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    if variables.is_empty() {
+        return delete_context_variables(client, db);
+    }
+
+    let variables: Map<String, Value> = variables
+        .iter()
+        .map(|(key, memory)| (key.to_owned(), memory.value.to_owned()))
+        .collect();
+    let context = serde_json::json!({
+        "conversation_id": conversation_id,
+        "variables": variables,
+    });
+
+    state::replace_state_item(client, "context", "variables", &context, ttl, db)
+}
+
+pub fn delete_context_variables(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    state::delete_state_key(client, "context", "variables", db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_context_variables_of_conversation() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_context".to_owned(),
+            "channel_context".to_owned(),
+            "user_context".to_owned(),
+        );
+
+        let mut variables = HashMap::new();
+        variables.insert(
+            "step".to_owned(),
+            Memory {
+                key: "step".to_owned(),
+                value: serde_json::json!(2),
+            },
+        );
+        save_context_variables(&client, "conversation_1", &variables, None, &mut db).unwrap();
+
+        let saved = get_context_variables(&client, "conversation_1", &mut db).unwrap();
+        assert_eq!(saved["step"].value, serde_json::json!(2));
+
+        // another conversation does not see them, and they are discarded
+        let saved = get_context_variables(&client, "conversation_2", &mut db).unwrap();
+        assert!(saved.is_empty());
+        let saved = get_context_variables(&client, "conversation_1", &mut db).unwrap();
+        assert!(saved.is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+/**
+ * Errors that ended a conversation, saved with the flow, step, source line
+ * and the steps that led to the error so that they can be inspected later.
+ *
+ * The error is saved in the client state (type "error") with the id of the conversation:
+ * an error always closes its conversation, so there is at most one error per conversation.
+ *
+ * The errors are also saved with the errors of their bot for ERRORS_RETENTION seconds
+ * (default 30 days), listed by GET /errors, and published as conversation_error events, which
+ * are sent to the alerting webhook ERRORS_WEBHOOK_URL (see webhooks.rs).
+ */
+use crate::{
+    config::get_config,
+    data::{ConversationInfo, Database, EngineError},
+    db_connectors::{errors, state},
+    events::{publish_run_event, CONVERSATION_ERROR},
+    Client,
+};
+use csml_interpreter::data::{error_info::ErrorInfo, Event};
+
+use chrono::{prelude::Utc, DateTime, SecondsFormat};
+
+const DEFAULT_RETENTION: i64 = 2_592_000;
+const MAX_ERRORS_PER_PAGE: i64 = 25;
+
+pub fn save_conversation_error(
+    client: &Client,
+    conversation_id: &str,
+    error: &ErrorInfo,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let error = serde_json::json!({
+        "conversation_id": conversation_id,
+        "message": error.message,
+        "flow": error.position.flow,
+        "step": error.step,
+        "line": error.position.interval.start_line,
+        "column": error.position.interval.start_column,
+        "snippet": error.snippet,
+        "stack": error.stack,
+        "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    });
+
+    state::replace_state_item(client, "error", conversation_id, &error, ttl, db)
+}
+
+pub fn get_conversation_error(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    state::get_state_key(client, "error", conversation_id, db)
+}
+
+/**
+ * Last event received before the error. Its content is left out for the secure events
+ * and the bots with no data retention.
+ */
+pub fn format_last_event(event: &Event, no_data_retention: bool) -> serde_json::Value {
+    let mut last_event = serde_json::json!({ "content_type": event.content_type });
+    if !event.secure && !no_data_retention {
+        last_event["content"] = event.content.clone();
+    }
+
+    last_event
+}
+
+fn get_retention() -> chrono::Duration {
+    let retention = match get_config().integer("errors.retention") {
+        Some(value) => value as i64,
+        None => DEFAULT_RETENTION,
+    };
+
+    chrono::Duration::seconds(retention)
+}
+
+/**
+ * Save the error with the errors of the bot and publish it as a conversation_error event
+ */
+pub fn report_conversation_error(
+    data: &mut ConversationInfo,
+    error: &ErrorInfo,
+    last_event: &serde_json::Value,
+) -> Result<(), EngineError> {
+    let interval = &error.position.interval;
+    let step_id = match &error.step {
+        Some(step) => step.to_owned(),
+        None => data.context.step.get_step(),
+    };
+    let payload = serde_json::json!({
+        "message": error.message,
+        "interval": {
+            "start_line": interval.start_line,
+            "start_column": interval.start_column,
+            "end_line": interval.end_line,
+            "end_column": interval.end_column,
+        },
+        "snippet": error.snippet,
+        "stack": error.stack,
+        "last_event": last_event,
+        "request_id": data.run_context.request_id,
+    });
+
+    let saved = errors::create_conversation_error(
+        &data.client,
+        &data.conversation_id,
+        &error.position.flow,
+        &step_id,
+        &payload,
+        Some(get_retention()),
+        &mut data.db,
+    )?;
+
+    publish_run_event(
+        CONVERSATION_ERROR,
+        data,
+        serde_json::json!({
+            "error_id": saved.id,
+            "flow_id": saved.flow_id,
+            "step_id": saved.step_id,
+            "error": payload,
+        }),
+    );
+
+    Ok(())
+}
+
+/**
+ * Errors of a bot, newest first, at most 25 per page: {"errors": [...], "pagination_key": "..."}.
+ * The pagination_key, returned when more errors may follow, is the date of the last error.
+ */
+pub fn get_bot_errors(
+    bot_id: &str,
+    limit: Option<i64>,
+    pagination_key: Option<String>,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let limit = match limit {
+        Some(limit) => limit.clamp(1, MAX_ERRORS_PER_PAGE),
+        None => MAX_ERRORS_PER_PAGE,
+    };
+    let before = match pagination_key {
+        Some(key) => match DateTime::parse_from_rfc3339(&key) {
+            Ok(date) => Some(date.with_timezone(&Utc)),
+            Err(_) => {
+                return Err(EngineError::Format(format!(
+                    "invalid pagination_key [{}], expected a date",
+                    key
+                )))
+            }
+        },
+        None => None,
+    };
+
+    let errors = errors::get_bot_errors(bot_id, limit, before, db)?;
+
+    match errors.last() {
+        Some(last) if errors.len() as i64 == limit => {
+            let pagination_key = last.created_at.to_owned();
+            Ok(serde_json::json!({"errors": errors, "pagination_key": pagination_key}))
+        }
+        _ => Ok(serde_json::json!({ "errors": errors })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{init_db, user};
+    use csml_interpreter::data::{error_info::StackFrame, Interval, Position};
+
+    #[test]
+    fn ok_conversation_error() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let client = Client {
+            bot_id: "bot_conversation_error".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "user_id".to_owned(),
+            tenant_id: None,
+        };
+        user::delete_client(&client, &mut db).unwrap();
+
+        let stack = vec![StackFrame {
+            flow: "Default".to_owned(),
+            step: "start".to_owned(),
+        }];
+        let error = ErrorInfo::new(
+            Position::new(Interval::new_as_u32(2, 5, 0, None, None), "Default"),
+            "error".to_owned(),
+        )
+        .with_trace("start", Some("start:\n    say error"), &stack);
+
+        save_conversation_error(&client, "conversation_id", &error, None, &mut db).unwrap();
+
+        let saved = get_conversation_error(&client, "conversation_id", &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved["step"], "start");
+        assert_eq!(saved["line"], 2);
+        assert_eq!(saved["snippet"], "2 |     say error\n  |     ^");
+        assert_eq!(saved["stack"][0]["flow"], "Default");
+
+        assert!(get_conversation_error(&client, "other_id", &mut db)
+            .unwrap()
+            .is_none());
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+/**
+ * Metadata of a conversation, patched during its lifetime (tags, agent id, priority...) and
+ * readable from the flows as `_metadata` on the next events of the conversation.
+ *
+ * The patches are JSON merge patches (RFC 7396): the keys set to null are removed. The metadata
+ * of an event take precedence over the metadata of its conversation.
+ *
+ * Like the context variables, they are saved in the client state (type "conversation") with
+ * the id of their conversation, and discarded once this conversation is closed.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::state,
+    Client,
+};
+
+use serde_json::{Map, Value};
+
+/**
+ * Get the metadata saved for the conversation, an empty object if there is none
+ */
+pub fn get_conversation_metadata(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Map<String, Value>, EngineError> {
+    let saved = match state::get_state_key(client, "conversation", "metadata", db)? {
+        Some(saved) => saved,
+        None => return Ok(Map::new()),
+    };
+
+    if saved["conversation_id"] != conversation_id {
+        // the metadata belong to a conversation that is not open anymore
+        delete_conversation_metadata(client, db)?;
+        return Ok(Map::new());
+    }
+
+    match saved.get("metadata") {
+        Some(Value::Object(metadata)) => Ok(metadata.to_owned()),
+        _ => Ok(Map::new()),
+    }
+}
+
+fn merge_patch(target: &mut Map<String, Value>, patch: &Map<String, Value>) {
+    for (key, value) in patch.iter() {
+        match (target.get_mut(key), value) {
+            (_, Value::Null) => {
+                target.remove(key);
+            }
+            (Some(Value::Object(target)), Value::Object(patch)) => merge_patch(target, patch),
+            (_, Value::Object(patch)) => {
+                let mut object = Map::new();
+                merge_patch(&mut object, patch);
+                target.insert(key.to_owned(), Value::Object(object));
+            }
+            (_, value) => {
+                target.insert(key.to_owned(), value.to_owned());
+            }
+        }
+    }
+}
+
+/**
+ * Apply a JSON merge patch to the metadata of the conversation and return them
+ */
+pub fn update_conversation_metadata(
+    client: &Client,
+    conversation_id: &str,
+    patch: &Value,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<Map<String, Value>, EngineError> {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        _ => {
+            return Err(EngineError::Format(
+                "the metadata patch must be an object".to_owned(),
+            ))
+        }
+    };
+
+    let mut metadata = get_conversation_metadata(client, conversation_id, db)?;
+    merge_patch(&mut metadata, patch);
+
+    let saved = serde_json::json!({
+        "conversation_id": conversation_id,
+        "metadata": metadata,
+    });
+    state::replace_state_item(client, "conversation", "metadata", &saved, ttl, db)?;
+
+    Ok(metadata)
+}
+
+/**
+ * The metadata of the event over the metadata of its conversation
+ */
+pub fn merge_event_metadata(
+    client: &Client,
+    conversation_id: &str,
+    event_metadata: &Value,
+    db: &mut Database,
+) -> Result<Value, EngineError> {
+    let mut metadata = get_conversation_metadata(client, conversation_id, db)?;
+
+    match event_metadata {
+        Value::Object(event_metadata) => metadata.extend(event_metadata.to_owned()),
+        // the metadata of the event are kept as they are when the conversation has none
+        _ if metadata.is_empty() => return Ok(event_metadata.to_owned()),
+        _ => (),
+    }
+
+    Ok(Value::Object(metadata))
+}
+
+pub fn delete_conversation_metadata(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    state::delete_state_key(client, "conversation", "metadata", db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_merge_patch() {
+        let mut metadata = serde_json::json!({"tags": ["vip"], "agent": {"id": 1, "name": "Ann"}})
+            .as_object()
+            .unwrap()
+            .to_owned();
+        let patch =
+            serde_json::json!({"tags": ["vip", "billing"], "agent": {"name": null}, "priority": 2});
+
+        merge_patch(&mut metadata, patch.as_object().unwrap());
+
+        assert_eq!(
+            Value::Object(metadata),
+            serde_json::json!({"tags": ["vip", "billing"], "agent": {"id": 1}, "priority": 2})
+        );
+    }
+
+    #[test]
+    fn ok_conversation_metadata() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_metadata".to_owned(),
+            "channel_metadata".to_owned(),
+            "user_metadata".to_owned(),
+        );
+        delete_conversation_metadata(&client, &mut db).unwrap();
+
+        let patch = serde_json::json!({"priority": 1, "agent_id": "agent"});
+        update_conversation_metadata(&client, "conversation_1", &patch, None, &mut db).unwrap();
+        let patch = serde_json::json!({"priority": null});
+        let metadata =
+            update_conversation_metadata(&client, "conversation_1", &patch, None, &mut db).unwrap();
+        assert_eq!(
+            Value::Object(metadata),
+            serde_json::json!({"agent_id": "agent"})
+        );
+
+        // the metadata of the event take precedence
+        let event_metadata = serde_json::json!({"agent_id": "other", "locale": "fr"});
+        let merged =
+            merge_event_metadata(&client, "conversation_1", &event_metadata, &mut db).unwrap();
+        assert_eq!(
+            merged,
+            serde_json::json!({"agent_id": "other", "locale": "fr"})
+        );
+
+        let invalid = serde_json::json!(["priority"]);
+        assert!(
+            update_conversation_metadata(&client, "conversation_1", &invalid, None, &mut db)
+                .is_err()
+        );
+
+        // another conversation does not see them, and they are discarded
+        let metadata = get_conversation_metadata(&client, "conversation_2", &mut db).unwrap();
+        assert!(metadata.is_empty());
+        let metadata = get_conversation_metadata(&client, "conversation_1", &mut db).unwrap();
+        assert!(metadata.is_empty());
+    }
+}
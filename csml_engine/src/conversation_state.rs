@@ -0,0 +1,177 @@
+/**
+ * State of a conversation for the support tools: where the user is in the bot (flow, step
+ * and the position held by the interpreter while it waits for an input), the local variables
+ * of the held step and the memories saved last, in one document.
+ *
+ * Only the open conversation of the client has a state: the hold and the local variables
+ * are deleted when a conversation is closed.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::{conversations, memories, state},
+    handover::PAUSED_FOR_AGENT,
+    step_latency::get_step_timings,
+    Client,
+};
+
+use serde_json::Value;
+
+const MAX_MEMORY_UPDATES: usize = 10;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * The memories saved last, newest first: [{"key", "value", "interaction_id", "created_at"}].
+ * The history of a memory gives the interaction that saved it.
+ */
+fn get_memory_updates(client: &Client, db: &mut Database) -> Result<Vec<Value>, EngineError> {
+    let saved = match memories::get_memories(client, db)? {
+        Value::Array(saved) => saved,
+        _ => vec![],
+    };
+
+    let mut updates = vec![];
+    for memory in saved {
+        let key = match memory["key"].as_str() {
+            Some(key) => key.to_owned(),
+            None => continue,
+        };
+        let history = memories::get_memory_history(client, &key, db)?;
+
+        let (interaction_id, created_at) = match history.get(0) {
+            Some(last) => (last["interaction_id"].clone(), last["created_at"].clone()),
+            None => (Value::Null, memory["created_at"].clone()),
+        };
+        updates.push(serde_json::json!({
+            "key": key,
+            "value": memory["value"],
+            "interaction_id": interaction_id,
+            "created_at": created_at,
+        }));
+    }
+
+    updates.sort_by(|a, b| {
+        let a = a["created_at"].as_str().unwrap_or_default();
+        let b = b["created_at"].as_str().unwrap_or_default();
+        b.cmp(a)
+    });
+    updates.truncate(MAX_MEMORY_UPDATES);
+
+    Ok(updates)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * {"conversation_id", "status", "flow", "step", "last_interaction_at", "hold", "step_vars",
+ * "memories", "steps"}, None if the conversation is not the open conversation of the client.
+ * "hold" is null when the interpreter does not wait for an input, "steps" are the steps
+ * executed during the last interaction with their start and end times.
+ */
+pub fn get_conversation_state(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Option<Value>, EngineError> {
+    let conversation = match conversations::get_latest_open(client, db)? {
+        Some(conversation) if conversation.id == conversation_id => conversation,
+        _ => return Ok(None),
+    };
+
+    let status = match state::get_state_key(client, "handover", "status", db)? {
+        Some(handover) if handover["conversation_id"] == conversation_id => PAUSED_FOR_AGENT,
+        _ => &conversation.status,
+    };
+
+    let (hold, step_vars) = match state::get_state_key(client, "hold", "position", db)? {
+        Some(hold) => (
+            serde_json::json!({
+                "index": hold["index"],
+                "previous": hold["previous"],
+                "secure": hold["secure"],
+                "breakpoint": hold["breakpoint"],
+            }),
+            hold["step_vars"].clone(),
+        ),
+        None => (Value::Null, serde_json::json!({})),
+    };
+
+    Ok(Some(serde_json::json!({
+        "conversation_id": conversation.id,
+        "status": status,
+        "flow": conversation.flow_id,
+        "step": conversation.step_id,
+        "last_interaction_at": conversation.last_interaction_at,
+        "hold": hold,
+        "step_vars": step_vars,
+        "memories": get_memory_updates(client, db)?,
+        "steps": get_step_timings(client, conversation_id, db)?,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{init_db, user};
+
+    #[test]
+    fn ok_conversation_state() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let client = Client {
+            bot_id: "bot_conversation_state".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "user_id".to_owned(),
+            tenant_id: None,
+        };
+        user::delete_client(&client, &mut db).unwrap();
+
+        let conversation_id =
+            conversations::create_conversation("Default", "ask_name", &client, None, &mut db)
+                .unwrap();
+
+        let state = get_conversation_state(&client, &conversation_id, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(state["status"], "OPEN");
+        assert_eq!(state["step"], "ask_name");
+        assert_eq!(state["hold"], Value::Null);
+
+        let hold = serde_json::json!({
+            "index": {"command_index": 2, "loop_index": []},
+            "step_vars": {"retries": 1},
+            "hash": "hash",
+            "previous": null,
+            "secure": false,
+            "breakpoint": null,
+        });
+        state::set_state_items(&client, "hold", vec![("position", &hold)], None, &mut db).unwrap();
+        for (key, value) in [("name", "Jane"), ("city", "Paris")] {
+            memories::create_client_memory(&client, key.to_owned(), value.into(), None, &mut db)
+                .unwrap();
+            // the updates are ordered by the date of the last value
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let state = get_conversation_state(&client, &conversation_id, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(state["hold"]["index"]["command_index"], 2);
+        assert_eq!(state["step_vars"]["retries"], 1);
+        let updates = state["memories"].as_array().unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0]["key"], "city");
+        assert_eq!(updates[0]["value"], "Paris");
+
+        assert!(get_conversation_state(&client, "other_id", &mut db)
+            .unwrap()
+            .is_none());
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+}
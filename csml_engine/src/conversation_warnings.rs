@@ -0,0 +1,125 @@
+/**
+ * Warnings of the interpreter: the values silently resolved at runtime (a missing value
+ * accessed in a condition is Null, a string holding a number is coerced in an operation).
+ * They do not stop the conversation, so they are only kept for debugging.
+ *
+ * The warnings are saved in the client state (type "warnings") with the id of the conversation,
+ * grouped by interaction: only the last MAX_INTERACTIONS interactions with warnings are kept.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::state,
+    Client,
+};
+use csml_interpreter::data::warnings::Warnings;
+
+use chrono::{prelude::Utc, SecondsFormat};
+use serde_json::Value;
+
+const MAX_INTERACTIONS: usize = 20;
+
+/**
+ * A warning of the interaction, with the step where it happened
+ */
+pub fn format_warning(step: &str, warning: &Warnings) -> Value {
+    serde_json::json!({
+        "message": warning.message,
+        "flow": warning.position.flow,
+        "step": step,
+        "line": warning.position.interval.start_line,
+        "column": warning.position.interval.start_column,
+    })
+}
+
+pub fn save_conversation_warnings(
+    client: &Client,
+    conversation_id: &str,
+    request_id: &str,
+    warnings: Vec<Value>,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    if warnings.is_empty() {
+        return Ok(());
+    }
+
+    let mut interactions = match get_conversation_warnings(client, conversation_id, db)? {
+        Some(Value::Array(interactions)) => interactions,
+        _ => vec![],
+    };
+    interactions.push(serde_json::json!({
+        "request_id": request_id,
+        "warnings": warnings,
+        "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    }));
+    if interactions.len() > MAX_INTERACTIONS {
+        interactions.drain(..interactions.len() - MAX_INTERACTIONS);
+    }
+
+    let interactions = Value::Array(interactions);
+    state::replace_state_item(client, "warnings", conversation_id, &interactions, ttl, db)
+}
+
+pub fn get_conversation_warnings(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Option<Value>, EngineError> {
+    state::get_state_key(client, "warnings", conversation_id, db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{init_db, user};
+    use csml_interpreter::data::{Interval, Position};
+
+    #[test]
+    fn ok_conversation_warnings() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let client = Client {
+            bot_id: "bot_conversation_warnings".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "user_id".to_owned(),
+            tenant_id: None,
+        };
+        user::delete_client(&client, &mut db).unwrap();
+
+        let warning = Warnings {
+            message: "resolved to Null: [name] does not exist".to_owned(),
+            position: Position::new(Interval::new_as_u32(3, 8, 0, None, None), "Default"),
+        };
+        for request_id in 0..MAX_INTERACTIONS + 2 {
+            let warnings = vec![format_warning("start", &warning)];
+            save_conversation_warnings(
+                &client,
+                "conversation_id",
+                &request_id.to_string(),
+                warnings,
+                None,
+                &mut db,
+            )
+            .unwrap();
+        }
+        // the interactions without warnings are not saved
+        save_conversation_warnings(&client, "conversation_id", "last", vec![], None, &mut db)
+            .unwrap();
+
+        let saved = get_conversation_warnings(&client, "conversation_id", &mut db)
+            .unwrap()
+            .unwrap();
+        let interactions = saved.as_array().unwrap();
+        assert_eq!(interactions.len(), MAX_INTERACTIONS);
+        assert_eq!(interactions[0]["request_id"], "2");
+        assert_eq!(interactions[0]["warnings"][0]["step"], "start");
+        assert_eq!(interactions[0]["warnings"][0]["line"], 3);
+
+        assert!(get_conversation_warnings(&client, "other_id", &mut db)
+            .unwrap()
+            .is_none());
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+}
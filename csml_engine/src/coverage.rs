@@ -0,0 +1,167 @@
+/**
+ * Coverage of a bot by a replay: the steps executed and the branches of their if statements
+ * taken (each if, else if and else), per flow and per step, out of all the steps of the bot.
+ * The steps of the modules and the branches of the functions are not counted.
+ */
+use csml_interpreter::{
+    coverage::get_bot_branches,
+    data::{csml_bot::CsmlBot, MSG},
+};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/**
+ * Steps and branches exercised by the interpreted events
+ */
+#[derive(Debug, Default)]
+pub(crate) struct ExercisedPaths {
+    // (flow, step)
+    steps: HashSet<(String, String)>,
+    // (flow, line, column)
+    branches: HashSet<(String, u32, u32)>,
+}
+
+impl ExercisedPaths {
+    pub(crate) fn record(&mut self, msg: &MSG) {
+        match msg {
+            MSG::StepTiming { flow, step, .. } => {
+                self.steps.insert((flow.to_owned(), step.to_owned()));
+            }
+            MSG::Branch { flow, line, column } => {
+                self.branches.insert((flow.to_owned(), *line, *column));
+            }
+            _ => {}
+        }
+    }
+}
+
+/**
+ * Percentage with two decimals, 100 when there is nothing to cover
+ */
+fn get_percentage(covered: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+
+    (covered as f64 * 10000.0 / total as f64).round() / 100.0
+}
+
+pub(crate) fn get_coverage_report(bot: &CsmlBot, exercised: &ExercisedPaths) -> Value {
+    let (mut bot_steps, mut bot_steps_covered) = (0, 0);
+    let (mut bot_branches, mut bot_branches_covered) = (0, 0);
+    let mut flows = vec![];
+
+    for (flow, steps) in get_bot_branches(bot) {
+        let mut steps_covered = 0;
+        let (mut flow_branches, mut flow_branches_covered) = (0, 0);
+        let mut steps_report = vec![];
+
+        for (step, branches) in steps.iter() {
+            let step_covered = exercised
+                .steps
+                .contains(&(flow.to_owned(), step.to_owned()));
+            let branches_covered = branches
+                .iter()
+                .filter(|(line, column)| {
+                    exercised
+                        .branches
+                        .contains(&(flow.to_owned(), *line, *column))
+                })
+                .count();
+
+            steps_covered += step_covered as usize;
+            flow_branches += branches.len();
+            flow_branches_covered += branches_covered;
+
+            steps_report.push(serde_json::json!({
+                "step": step,
+                "exercised": step_covered,
+                "branches": branches.len(),
+                "exercised_branches": branches_covered,
+            }));
+        }
+
+        bot_steps += steps.len();
+        bot_steps_covered += steps_covered;
+        bot_branches += flow_branches;
+        bot_branches_covered += flow_branches_covered;
+
+        flows.push(serde_json::json!({
+            "flow": flow,
+            "percentage": get_percentage(steps_covered, steps.len()),
+            "branches_percentage": get_percentage(flow_branches_covered, flow_branches),
+            "steps": steps_report,
+        }));
+    }
+
+    serde_json::json!({
+        "percentage": get_percentage(bot_steps_covered, bot_steps),
+        "branches_percentage": get_percentage(bot_branches_covered, bot_branches),
+        "flows": flows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::init_bot;
+    use csml_interpreter::data::{CsmlFlow, Context, Event};
+    use csml_interpreter::interpret;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+
+    #[test]
+    fn ok_coverage_report() {
+        let content = [
+            "start:",
+            "    if (event == \"a\") {",
+            "        say \"a\"",
+            "    } else if (event == \"b\") {",
+            "        say \"b\"",
+            "    } else {",
+            "        say \"c\"",
+            "    }",
+            "    goto end",
+            "",
+            "other:",
+            "    goto end",
+        ]
+        .join("\n");
+        let mut bot = CsmlBot::new(
+            "bot_id", "bot", None, vec![], None, None, "Default", None, None, None, None, None,
+            None, None, None, None, None, None,
+        );
+        bot.flows = vec![CsmlFlow::new("Default", "Default", &content, vec![])];
+        init_bot(&mut bot).unwrap();
+
+        let context = Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "Default",
+            None,
+        );
+        let event = Event::new("text", "b", serde_json::json!({"text": "b"}));
+        let (sender, receiver) = mpsc::channel::<MSG>();
+        interpret(bot.clone(), context, event, Some(sender));
+
+        let mut exercised = ExercisedPaths::default();
+        for msg in receiver.try_iter() {
+            exercised.record(&msg);
+        }
+
+        // only the else if branch is taken
+        let report = get_coverage_report(&bot, &exercised);
+        assert_eq!(report["percentage"], 50.0);
+        assert_eq!(report["branches_percentage"], 33.33);
+        assert_eq!(
+            report["flows"][0]["steps"],
+            serde_json::json!([
+                {"step": "other", "exercised": false, "branches": 0, "exercised_branches": 0},
+                {"step": "start", "exercised": true, "branches": 3, "exercised_branches": 1},
+            ])
+        );
+    }
+}
@@ -1,11 +1,15 @@
 use crate::{
-    db_connectors,
+    db_connectors::{self, utils::get_tenant_bot_id},
     encrypt::{decrypt_data, encrypt_data},
     Client, Context,
 };
-use csml_interpreter::data::{CsmlBot, CsmlFlow, Message, Module, MultiBot};
+use csml_interpreter::data::{
+    error_info::ErrorInfo, AppOptions, BotApp, BotLocales, ConversationExpiration, CsmlBot,
+    CsmlFlow, Message, Module, MultiBot,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::fmt;
 
 pub const DEBUG: &str = "DEBUG";
 pub const DISABLE_SSL_VERIFY: &str = "DISABLE_SSL_VERIFY";
@@ -16,6 +20,229 @@ pub struct FlowTrigger {
     pub step_id: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RolloutVersion {
+    pub version_id: String,
+    pub percentage: u8,
+}
+
+/**
+ * Split the traffic of a bot between several of its versions.
+ * Each client is always routed to the same version (sticky rollout).
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BotRollout {
+    pub versions: Vec<RolloutVersion>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagOperator {
+    Eq,
+    Neq,
+    In,
+    NotIn,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlagCondition {
+    // path of the attribute in the client or the metadata of the event, ex: metadata.plan
+    pub attribute: String,
+    pub operator: FlagOperator,
+    pub value: serde_json::Value,
+}
+
+/**
+ * Feature flag of a bot, read in the flows with _flags.<key>.
+ * An enabled flag is on for the clients matching all its conditions, and among them for
+ * `percentage` of the clients (100 by default). Like the rollouts, each client always gets
+ * the same value of a given flag.
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeatureFlag {
+    pub enabled: bool,
+    #[serde(default)]
+    pub percentage: Option<u8>,
+    #[serde(default)]
+    pub conditions: Vec<FlagCondition>,
+}
+
+/**
+ * Portable copy of a bot version, used to move a bot between engine instances.
+ * The bundle is signed, not encrypted: the bot env is readable by anyone having the bundle.
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BotBundle {
+    pub version_id: String,
+    pub engine_version: String,
+    pub exported_at: String,
+    pub bot: serde_json::Value,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BotBundleImported {
+    pub version_id: String,
+    pub engine_version: String,
+    pub source_version_id: String,
+    pub source_engine_version: String,
+    pub exported_at: String,
+}
+
+/**
+ * Output format of the graph of the steps of a bot:
+ * DOT (graphviz) or Mermaid (rendered by most documentation tools)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BotGraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BroadcastFilter {
+    pub channel_id: Option<String>,
+}
+
+/**
+ * Trigger a flow for many clients of a bot at once.
+ * The clients are either listed, or all the clients having a conversation with the bot
+ * that match the filter.
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BroadcastRequest {
+    pub bot_id: String,
+    #[serde(alias = "fn_endpoint")]
+    pub apps_endpoint: Option<String>,
+    pub multibot: Option<Vec<MultiBot>>,
+    pub environment: Option<String>,
+    pub clients: Option<Vec<Client>>,
+    pub filter: Option<BroadcastFilter>,
+    pub flow_id: String,
+    pub step_id: Option<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    pub callback_url: Option<String>,
+    // maximum number of conversations started per second
+    pub rate_limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Broadcast {
+    pub broadcast_id: String,
+    pub bot_id: String,
+    pub status: String,
+    pub total: usize,
+    pub sent: usize,
+    pub failed: usize,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+pub const CHECK_OK: &str = "ok";
+pub const CHECK_WARNING: &str = "warning";
+pub const CHECK_ERROR: &str = "error";
+
+/**
+ * A check of validate_environment, its status is ok, warning or error
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvironmentCheck {
+    pub name: String,
+    pub status: String,
+    pub message: String,
+}
+
+impl EnvironmentCheck {
+    pub fn new(name: &str, status: &str, message: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            status: status.to_owned(),
+            message: message.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvironmentReport {
+    pub checks: Vec<EnvironmentCheck>,
+}
+
+impl EnvironmentReport {
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CHECK_ERROR)
+    }
+}
+
+/**
+ * Document of the knowledge base of a bot, replacing the document with the same id.
+ * The metadata is returned with each of its passages by Search_KB.
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KbDocument {
+    pub id: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/**
+ * Subscribe an url to the events of the conversations of a bot, all of them if events is empty
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookRequest {
+    pub bot_id: String,
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+    // the secret signing the payloads, generated if not set
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    pub webhook_id: String,
+    pub bot_id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    pub delivery_id: String,
+    pub event_id: String,
+    pub event: String,
+    // pending, delivered or failed
+    pub status: String,
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/**
+ * Resume a conversation paused for a human agent at the given step
+ * (of the flow where the conversation was paused, unless flow_id is set)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResumeRequest {
+    pub client: Client,
+    pub flow_id: Option<String>,
+    pub step_id: String,
+    #[serde(alias = "fn_endpoint")]
+    pub apps_endpoint: Option<String>,
+    pub multibot: Option<Vec<MultiBot>>,
+    pub environment: Option<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    pub callback_url: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RunRequest {
     pub bot: Option<CsmlBot>,
@@ -24,6 +251,7 @@ pub struct RunRequest {
     #[serde(alias = "fn_endpoint")]
     pub apps_endpoint: Option<String>,
     pub multibot: Option<Vec<MultiBot>>,
+    pub environment: Option<String>,
     pub event: CsmlRequest,
 }
 
@@ -60,11 +288,13 @@ impl RunRequest {
                 bot_id: Some(bot_id),
                 apps_endpoint,
                 multibot,
+                environment,
                 ..
             } => Ok(BotOpt::BotId {
                 bot_id,
                 apps_endpoint,
                 multibot,
+                environment,
             }),
 
             _ => Err(EngineError::Format("Invalid bot_opt format".to_owned())),
@@ -72,6 +302,31 @@ impl RunRequest {
     }
 }
 
+/**
+ * Independent runs interpreted in one call, at most `concurrency` at the same time
+ * (default RUN_BATCH_CONCURRENCY env var, or 4)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchRunRequest {
+    pub runs: Vec<RunRequest>,
+    pub concurrency: Option<usize>,
+    // context of the request running the batch, shared by its runs
+    #[serde(skip)]
+    pub run_context: RunContext,
+}
+
+/**
+ * A memory saved or forgotten during an interaction that is not saved by the engine
+ * (stateless engine)
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MemoryMutation {
+    Remember { key: String, value: serde_json::Value },
+    Forget { key: String },
+    ForgetAll,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum BotOpt {
     #[serde(rename = "bot")]
@@ -90,27 +345,67 @@ pub enum BotOpt {
         #[serde(alias = "fn_endpoint")]
         apps_endpoint: Option<String>,
         multibot: Option<Vec<MultiBot>>,
+        environment: Option<String>,
     },
 }
 
 impl BotOpt {
+    /**
+     * Id of the bot that runs
+     */
+    pub fn get_bot_id(&self) -> &str {
+        match self {
+            BotOpt::CsmlBot(csml_bot) => &csml_bot.id,
+            BotOpt::Id { bot_id, .. } | BotOpt::BotId { bot_id, .. } => bot_id,
+        }
+    }
+
+    /**
+     * The bots of a tenant are stored with the bot_id of the tenant
+     */
+    pub fn set_tenant(&mut self, tenant_id: Option<&str>) {
+        match self {
+            BotOpt::CsmlBot(csml_bot) => csml_bot.id = get_tenant_bot_id(tenant_id, &csml_bot.id),
+            BotOpt::Id { bot_id, .. } | BotOpt::BotId { bot_id, .. } => {
+                *bot_id = get_tenant_bot_id(tenant_id, bot_id)
+            }
+        }
+    }
+
     pub fn search_bot(&self, db: &mut Database) -> Result<CsmlBot, EngineError> {
+        let (bot, _version_id) = self.search_bot_version(db)?;
+
+        Ok(bot)
+    }
+
+    /**
+     * Search the bot and its version_id, None if the bot is given in the request
+     */
+    pub fn search_bot_version(
+        &self,
+        db: &mut Database,
+    ) -> Result<(CsmlBot, Option<String>), EngineError> {
         match self {
-            BotOpt::CsmlBot(csml_bot) => Ok(csml_bot.to_owned()),
+            BotOpt::CsmlBot(csml_bot) => Ok((csml_bot.to_owned(), None)),
             BotOpt::BotId {
                 bot_id,
                 apps_endpoint,
                 multibot,
+                environment,
             } => {
-                let bot_version = db_connectors::bot::get_last_bot_version(&bot_id, db)?;
+                let bot_version = db_connectors::bot::get_last_bot_version(
+                    &bot_id,
+                    environment.as_deref(),
+                    db,
+                )?;
 
                 match bot_version {
                     Some(mut bot_version) => {
                         bot_version.bot.apps_endpoint = apps_endpoint.to_owned();
                         bot_version.bot.multibot = multibot.to_owned();
-                        Ok(bot_version.bot)
+                        Ok((bot_version.bot, Some(bot_version.version_id)))
                     }
-                    None => Err(EngineError::Manager(format!(
+                    None => Err(EngineError::BotNotFound(format!(
                         "bot ({}) not found in db",
                         bot_id
                     ))),
@@ -128,9 +423,9 @@ impl BotOpt {
                     Some(mut bot_version) => {
                         bot_version.bot.apps_endpoint = apps_endpoint.to_owned();
                         bot_version.bot.multibot = multibot.to_owned();
-                        Ok(bot_version.bot)
+                        Ok((bot_version.bot, Some(bot_version.version_id)))
                     }
-                    None => Err(EngineError::Manager(format!(
+                    None => Err(EngineError::BotNotFound(format!(
                         "bot version ({}) not found in db",
                         version_id
                     ))),
@@ -151,6 +446,15 @@ pub struct SerializeCsmlBot {
     pub no_interruption_delay: Option<i32>,
     pub env: Option<String>,
     pub modules: Option<Vec<Module>>,
+    pub apps: Option<Vec<BotApp>>,
+    pub apps_endpoint_options: Option<AppOptions>,
+    pub no_data_retention: Option<bool>,
+    pub conversation_expiration: Option<ConversationExpiration>,
+    pub metadata_schema: Option<serde_json::Value>,
+    pub locales: Option<BotLocales>,
+    // AST of the flows, only used by the engine version that parsed it
+    pub bot_ast: Option<String>,
+    pub bot_ast_engine_version: Option<String>,
 }
 
 /**
@@ -182,7 +486,31 @@ impl CsmlBotBincode {
             no_interruption_delay: None,
             env: None,
             modules: None,
+            apps: None,
+            apps_endpoint_options: None,
+            no_data_retention: None,
+            conversation_expiration: None,
+            metadata_schema: None,
+            locales: None,
+            bot_ast: None,
+            bot_ast_engine_version: None,
+        }
+    }
+}
+
+/**
+ * A saved AST can only be interpreted by the engine version that parsed it,
+ * other versions parse the flows again
+ */
+pub fn get_compatible_bot_ast(
+    bot_ast: &Option<String>,
+    engine_version: &Option<String>,
+) -> Option<String> {
+    match (bot_ast, engine_version) {
+        (Some(bot_ast), Some(engine_version)) if engine_version == env!("CARGO_PKG_VERSION") => {
+            Some(bot_ast.to_owned())
         }
+        _ => None,
     }
 }
 
@@ -210,6 +538,17 @@ pub fn to_serializable_bot(bot: &CsmlBot) -> SerializeCsmlBot {
             None => None,
         },
         modules: bot.modules.to_owned(),
+        apps: bot.apps.to_owned(),
+        apps_endpoint_options: bot.apps_endpoint_options.to_owned(),
+        no_data_retention: bot.no_data_retention,
+        conversation_expiration: bot.conversation_expiration.to_owned(),
+        metadata_schema: bot.metadata_schema.to_owned(),
+        locales: bot.locales.to_owned(),
+        bot_ast: bot.bot_ast.to_owned(),
+        bot_ast_engine_version: bot
+            .bot_ast
+            .as_ref()
+            .map(|_| env!("CARGO_PKG_VERSION").to_owned()),
     }
 }
 
@@ -239,7 +578,7 @@ impl SerializeCsmlBot {
                 }
             },
             default_flow: self.default_flow.to_owned(),
-            bot_ast: None,
+            bot_ast: get_compatible_bot_ast(&self.bot_ast, &self.bot_ast_engine_version),
             no_interruption_delay: self.no_interruption_delay,
             env: match self.env.to_owned() {
                 Some(value) => decrypt_data(value).ok(),
@@ -247,6 +586,12 @@ impl SerializeCsmlBot {
             },
             modules: self.modules.to_owned(),
             multibot: None,
+            apps: self.apps.to_owned(),
+            apps_endpoint_options: self.apps_endpoint_options.to_owned(),
+            no_data_retention: self.no_data_retention,
+            conversation_expiration: self.conversation_expiration.to_owned(),
+            metadata_schema: self.metadata_schema.to_owned(),
+            locales: self.locales.to_owned(),
         }
     }
 }
@@ -259,6 +604,12 @@ pub struct DynamoBot {
     pub default_flow: String,
     pub no_interruption_delay: Option<i32>,
     pub env: Option<String>,
+    pub apps: Option<Vec<BotApp>>,
+    pub apps_endpoint_options: Option<AppOptions>,
+    pub no_data_retention: Option<bool>,
+    pub conversation_expiration: Option<ConversationExpiration>,
+    pub metadata_schema: Option<serde_json::Value>,
+    pub locales: Option<BotLocales>,
 }
 
 /**
@@ -285,6 +636,12 @@ impl DynamoBotBincode {
             default_flow: self.default_flow,
             no_interruption_delay: None,
             env: None,
+            apps: None,
+            apps_endpoint_options: None,
+            no_data_retention: None,
+            conversation_expiration: None,
+            metadata_schema: None,
+            locales: None,
         }
     }
 }
@@ -303,6 +660,12 @@ pub fn to_dynamo_bot(csml_bot: &CsmlBot) -> DynamoBot {
             Some(value) => encrypt_data(value).ok(),
             None => None,
         },
+        apps: csml_bot.apps.to_owned(),
+        apps_endpoint_options: csml_bot.apps_endpoint_options.to_owned(),
+        no_data_retention: csml_bot.no_data_retention,
+        conversation_expiration: csml_bot.conversation_expiration.to_owned(),
+        metadata_schema: csml_bot.metadata_schema.to_owned(),
+        locales: csml_bot.locales.to_owned(),
     }
 }
 
@@ -332,10 +695,36 @@ impl DynamoBot {
             },
             modules: Some(modules),
             multibot: None,
+            apps: self.apps.to_owned(),
+            apps_endpoint_options: self.apps_endpoint_options.to_owned(),
+            no_data_retention: self.no_data_retention,
+            conversation_expiration: self.conversation_expiration.to_owned(),
+            metadata_schema: self.metadata_schema.to_owned(),
+            locales: self.locales.to_owned(),
         }
     }
 }
 
+/**
+ * Precedence of the events of a client waiting for its run in progress to end:
+ * the system events (handover resume, scheduled events, broadcasts) before the user messages
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventPriority {
+    System,
+    User,
+}
+
+/**
+ * Context of the request of the caller running an event (an http request of the server).
+ * Its request_id is saved with the messages of the run and added to its logs, so that a
+ * conversation turn can be traced across systems.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunContext {
+    pub request_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CsmlRequest {
     pub request_id: String,
@@ -387,12 +776,30 @@ impl PostgresqlClient {
 #[cfg(feature = "mongo")]
 pub struct MongoDbClient {
     pub client: mongodb::sync::Database,
+    // optional read only connection (ex: secondaries) for the read heavy operations
+    pub read_client: Option<mongodb::sync::Database>,
 }
 
 #[cfg(feature = "mongo")]
 impl MongoDbClient {
     pub fn new(client: mongodb::sync::Database) -> Self {
-        Self { client }
+        Self {
+            client,
+            read_client: None,
+        }
+    }
+
+    pub fn with_read_client(mut self, read_client: mongodb::sync::Database) -> Self {
+        self.read_client = Some(read_client);
+        self
+    }
+
+    /**
+     * Connection used by the read heavy operations (bot versions, messages history),
+     * the primary connection if no read only connection is set
+     */
+    pub fn reader(&self) -> &mongodb::sync::Database {
+        self.read_client.as_ref().unwrap_or(&self.client)
     }
 }
 /**
@@ -403,6 +810,8 @@ impl MongoDbClient {
 #[cfg(feature = "dynamo")]
 pub struct DynamoDbClient {
     pub client: rusoto_dynamodb::DynamoDbClient,
+    // optional read only endpoint (ex: a DAX compatible cache) for the read heavy operations
+    pub read_client: Option<rusoto_dynamodb::DynamoDbClient>,
     pub s3_client: rusoto_s3::S3Client,
     pub runtime: tokio::runtime::Runtime,
 }
@@ -412,15 +821,31 @@ impl DynamoDbClient {
     pub fn new(dynamo_region: rusoto_core::Region, s3_region: rusoto_core::Region) -> Self {
         Self {
             client: rusoto_dynamodb::DynamoDbClient::new(dynamo_region),
+            read_client: None,
             s3_client: rusoto_s3::S3Client::new(s3_region),
             runtime: tokio::runtime::Runtime::new().unwrap(),
         }
     }
+
+    pub fn with_read_region(mut self, read_region: rusoto_core::Region) -> Self {
+        self.read_client = Some(rusoto_dynamodb::DynamoDbClient::new(read_region));
+        self
+    }
+
+    /**
+     * Client used by the read heavy operations (bot versions, messages history),
+     * the primary client if no read only endpoint is set
+     */
+    pub fn reader(&self) -> &rusoto_dynamodb::DynamoDbClient {
+        self.read_client.as_ref().unwrap_or(&self.client)
+    }
 }
 
 pub struct ConversationInfo {
     pub request_id: String,
     pub conversation_id: String,
+    // version of the conversation after the last update of the run
+    pub conversation_version: i32,
     pub callback_url: Option<String>,
     pub client: Client,
     pub context: Context,
@@ -428,6 +853,10 @@ pub struct ConversationInfo {
     pub messages: Vec<Message>,
     pub ttl: Option<chrono::Duration>,
     pub low_data: bool,
+    pub no_data_retention: bool,
+    // version of the bot interpreting the event, None for the bots given in the request
+    pub bot_version_id: Option<String>,
+    pub run_context: RunContext,
     pub db: Database,
 }
 
@@ -440,6 +869,35 @@ pub enum Next {
     Error,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/**
+ * A usage quota of the bot or of its tenant is reached for the current month
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaExceeded {
+    // "bot" or "tenant"
+    pub scope: String,
+    // "conversations" or "messages"
+    pub quota: String,
+    pub limit: u64,
+    pub usage: u64,
+    pub period: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DbErrorKind {
+    // ENGINE_DB_TYPE is not set, or its connector is not compiled in the engine
+    Setup,
+    // the database failed or rejected a query
+    Query,
+}
+
 #[derive(Debug)]
 pub enum EngineError {
     Serde(serde_json::Error),
@@ -449,6 +907,20 @@ pub enum EngineError {
     Format(String),
     Interpreter(String),
     Parring(String),
+    Db(DbErrorKind, String),
+    // the bot or the bot version is not saved in the database
+    BotNotFound(String),
+    // the flows of the bot have syntax errors
+    InvalidBot(Vec<ErrorInfo>),
+    // the request metadata does not match the bot's metadata_schema
+    Metadata(Vec<FieldError>),
+    QuotaExceeded(QuotaExceeded),
+    // the engine does not accept new runs during a graceful shutdown
+    ShuttingDown,
+    // the event waited too long for the end of the runs of its client
+    ClientBusy,
+    // another run (on another server) updated the conversation during the run
+    Conflict,
     Time(std::time::SystemTimeError),
     Openssl(openssl::error::ErrorStack),
     Base64(base64::DecodeError),
@@ -473,6 +945,108 @@ pub enum EngineError {
     SqlMigrationsError(String),
 }
 
+impl EngineError {
+    /**
+     * Stable code of the error, returned by the engine APIs with the error
+     */
+    pub fn code(&self) -> &'static str {
+        match self {
+            EngineError::Format(_) | EngineError::Serde(_) => "invalid_request",
+            EngineError::Metadata(_) => "invalid_metadata",
+            EngineError::Parring(_) => "parsing_error",
+            EngineError::InvalidBot(_) => "invalid_bot",
+            EngineError::BotNotFound(_) => "bot_not_found",
+            EngineError::QuotaExceeded(_) => "quota_exceeded",
+            EngineError::ShuttingDown => "shutting_down",
+            EngineError::ClientBusy => "client_busy",
+            EngineError::Conflict => "conversation_conflict",
+            EngineError::Interpreter(_) => "interpreter_error",
+            EngineError::Db(..) => "db_error",
+            #[cfg(any(feature = "mongo"))]
+            EngineError::BsonDecoder(_) | EngineError::BsonEncoder(_) | EngineError::MongoDB(_) => {
+                "db_error"
+            }
+            #[cfg(any(feature = "dynamo"))]
+            EngineError::Rusoto(_)
+            | EngineError::SerdeDynamodb(_)
+            | EngineError::S3ErrorCode(_) => "db_error",
+            #[cfg(any(feature = "postgresql", feature = "sqlite"))]
+            EngineError::SqlErrorCode(_) | EngineError::SqlMigrationsError(_) => "db_error",
+            _ => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Serde(err) => write!(f, "serde error: {}", err),
+            EngineError::Io(err) => write!(f, "io error: {}", err),
+            EngineError::Utf8(err) => write!(f, "utf8 error: {}", err),
+            EngineError::Manager(message)
+            | EngineError::Format(message)
+            | EngineError::Interpreter(message)
+            | EngineError::Parring(message)
+            | EngineError::BotNotFound(message) => write!(f, "{}", message),
+            EngineError::Db(kind, message) => write!(f, "database error ({:?}): {}", kind, message),
+            EngineError::InvalidBot(errors) => {
+                let errors: Vec<String> = errors.iter().map(ErrorInfo::format_error).collect();
+                write!(f, "invalid bot: {}", errors.join(", "))
+            }
+            EngineError::Metadata(errors) => {
+                let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+                write!(f, "invalid metadata: {}", fields.join(", "))
+            }
+            EngineError::QuotaExceeded(quota) => write!(
+                f,
+                "{} quota of {} reached: {}/{} for {}",
+                quota.quota, quota.scope, quota.usage, quota.limit, quota.period
+            ),
+            EngineError::ShuttingDown => write!(f, "the engine is shutting down"),
+            EngineError::ClientBusy => write!(f, "the previous events of the client are running"),
+            EngineError::Conflict => write!(f, "the conversation was updated by another run"),
+            EngineError::Time(err) => write!(f, "time error: {}", err),
+            EngineError::Openssl(err) => write!(f, "openssl error: {}", err),
+            EngineError::Base64(err) => write!(f, "base64 error: {}", err),
+
+            #[cfg(any(feature = "mongo"))]
+            EngineError::BsonDecoder(err) => write!(f, "bson error: {}", err),
+            #[cfg(any(feature = "mongo"))]
+            EngineError::BsonEncoder(err) => write!(f, "bson error: {}", err),
+            #[cfg(any(feature = "mongo"))]
+            EngineError::MongoDB(err) => write!(f, "mongodb error: {}", err),
+
+            #[cfg(any(feature = "dynamo"))]
+            EngineError::Rusoto(message) => write!(f, "dynamodb error: {}", message),
+            #[cfg(any(feature = "dynamo"))]
+            EngineError::SerdeDynamodb(err) => write!(f, "dynamodb error: {}", err),
+            #[cfg(any(feature = "dynamo"))]
+            EngineError::S3ErrorCode(code) => write!(f, "s3 error: status {}", code),
+
+            #[cfg(any(feature = "postgresql", feature = "sqlite"))]
+            EngineError::SqlErrorCode(message) => write!(f, "sql error: {}", message),
+            #[cfg(any(feature = "postgresql", feature = "sqlite"))]
+            EngineError::SqlMigrationsError(message) => write!(f, "migration error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::Serde(err) => Some(err),
+            EngineError::Io(err) => Some(err),
+            EngineError::Utf8(err) => Some(err),
+            EngineError::Time(err) => Some(err),
+            EngineError::Openssl(err) => Some(err),
+            EngineError::Base64(err) => Some(err),
+            #[cfg(any(feature = "mongo"))]
+            EngineError::MongoDB(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl From<serde_json::Error> for EngineError {
     fn from(e: serde_json::Error) -> Self {
         EngineError::Serde(e)
@@ -523,6 +1097,13 @@ impl From<bson::ser::Error> for EngineError {
     }
 }
 
+#[cfg(any(feature = "mongo"))]
+impl From<bson::document::ValueAccessError> for EngineError {
+    fn from(e: bson::document::ValueAccessError) -> Self {
+        EngineError::Manager(format!("invalid document: {}", e))
+    }
+}
+
 #[cfg(any(feature = "mongo"))]
 impl From<mongodb::error::Error> for EngineError {
     fn from(e: mongodb::error::Error) -> Self {
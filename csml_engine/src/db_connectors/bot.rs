@@ -7,7 +7,10 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 #[cfg(feature = "sqlite")]
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
+use crate::db_connectors::state;
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
+use crate::utils::get_bot_client;
 use crate::{BotVersion, CsmlBot, Database, EngineError};
 use csml_interpreter::data::csml_logs::*;
 
@@ -69,6 +72,7 @@ pub fn create_bot_version(
             bot,
             flows.to_string(),
             flow_modules.to_string(),
+            csml_bot.bot_ast.to_owned(),
             db,
         )?;
 
@@ -99,11 +103,12 @@ pub fn create_bot_version(
         return Ok(version_id);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_last_bot_version(
     bot_id: &str,
+    environment: Option<&str>,
     db: &mut Database,
 ) -> Result<Option<BotVersion>, EngineError> {
     csml_logger(
@@ -111,11 +116,22 @@ pub fn get_last_bot_version(
             None,
             None,
             None,
-            format!("db call get last bot version, bot_id: {:?}", bot_id),
+            format!(
+                "db call get last bot version, bot_id: {:?}, environment: {:?}",
+                bot_id, environment
+            ),
         ),
         LogLvl::Info,
     );
 
+    // in a given environment, the last version is the one that was set as active
+    if let Some(environment) = environment {
+        return match get_environment_version_id(bot_id, environment, db)? {
+            Some(version_id) => get_by_version_id(&version_id, bot_id, db),
+            None => Ok(None),
+        };
+    }
+
     #[cfg(feature = "mongo")]
     if is_mongodb() {
         let db = mongodb_connector::get_db(db)?;
@@ -140,7 +156,7 @@ pub fn get_last_bot_version(
         return sqlite_connector::bot::get_last_bot_version(&bot_id, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_by_version_id(
@@ -194,7 +210,7 @@ pub fn get_by_version_id(
         return sqlite_connector::bot::get_bot_by_version_id(&version_id, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_bot_versions(
@@ -253,7 +269,7 @@ pub fn get_bot_versions(
         return sqlite_connector::bot::get_bot_versions(&bot_id, limit, pagination_key, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn delete_bot_version(
@@ -304,7 +320,7 @@ pub fn delete_bot_version(
         return sqlite_connector::bot::delete_bot_version(version_id, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn delete_bot_versions(bot_id: &str, db: &mut Database) -> Result<(), EngineError> {
@@ -346,7 +362,7 @@ pub fn delete_bot_versions(bot_id: &str, db: &mut Database) -> Result<(), Engine
         return sqlite_connector::bot::delete_bot_versions(bot_id, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), EngineError> {
@@ -376,6 +392,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         mongodb_connector::bot::delete_all_bot_data(bot_id, "conversation", db)?;
         mongodb_connector::bot::delete_all_bot_data(bot_id, "state", db)?;
         mongodb_connector::bot::delete_all_bot_data(bot_id, "path", db)?;
+        mongodb_connector::bot::delete_all_bot_data(bot_id, "scheduled_event", db)?;
+        mongodb_connector::bot::delete_all_bot_data(bot_id, "outbox", db)?;
+        mongodb_connector::bot::delete_all_bot_data(bot_id, "error", db)?;
 
         return Ok(());
     }
@@ -391,6 +410,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         // dynamodb_connector::bot::delete_all_bot_data(bot_id, "interaction", db)?;
         dynamodb_connector::bot::delete_all_bot_data(bot_id, "conversation", db)?;
         dynamodb_connector::bot::delete_all_bot_data(bot_id, "state", db)?;
+        dynamodb_connector::scheduled_events::delete_all_bot_data(bot_id, db)?;
+        dynamodb_connector::outbox::delete_all_bot_data(bot_id, db)?;
+        dynamodb_connector::errors::delete_all_bot_data(bot_id, db)?;
         return Ok(());
     }
 
@@ -403,6 +425,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         postgresql_connector::conversations::delete_all_bot_data(bot_id, db)?;
         postgresql_connector::memories::delete_all_bot_data(bot_id, db)?;
         postgresql_connector::state::delete_all_bot_data(bot_id, db)?;
+        postgresql_connector::scheduled_events::delete_all_bot_data(bot_id, db)?;
+        postgresql_connector::outbox::delete_all_bot_data(bot_id, db)?;
+        postgresql_connector::errors::delete_all_bot_data(bot_id, db)?;
         return Ok(());
     }
 
@@ -415,8 +440,46 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         sqlite_connector::conversations::delete_all_bot_data(bot_id, db)?;
         sqlite_connector::memories::delete_all_bot_data(bot_id, db)?;
         sqlite_connector::state::delete_all_bot_data(bot_id, db)?;
+        sqlite_connector::scheduled_events::delete_all_bot_data(bot_id, db)?;
+        sqlite_connector::outbox::delete_all_bot_data(bot_id, db)?;
+        sqlite_connector::errors::delete_all_bot_data(bot_id, db)?;
         return Ok(());
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn get_environment_version_id(
+    bot_id: &str,
+    environment: &str,
+    db: &mut Database,
+) -> Result<Option<String>, EngineError> {
+    let client = get_bot_client(bot_id);
+
+    match state::get_state_key(&client, "environment", environment, db)? {
+        Some(value) => Ok(value["version_id"].as_str().map(|id| id.to_owned())),
+        None => Ok(None),
+    }
+}
+
+pub fn set_environment_version_id(
+    bot_id: &str,
+    environment: &str,
+    version_id: &str,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+    let value = serde_json::json!({ "version_id": version_id });
+
+    state::replace_state_item(&client, "environment", environment, &value, None, db)
+}
+
+pub fn delete_environment_version_id(
+    bot_id: &str,
+    environment: &str,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+
+    state::delete_state_key(&client, "environment", environment, db)
 }
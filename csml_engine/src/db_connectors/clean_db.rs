@@ -8,6 +8,7 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Database, EngineError};
 
@@ -44,5 +45,5 @@ pub fn delete_expired_data(_db: &mut Database) -> Result<(), EngineError> {
     }
 
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
@@ -10,7 +10,9 @@ use crate::db_connectors::{is_sqlite, sqlite_connector};
 use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
 
 use crate::db_connectors::{state, utils::*};
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
+use crate::telemetry;
 use crate::{Client, ConversationInfo, Database, DbConversation, EngineError};
 
 pub fn create_conversation(
@@ -20,6 +22,10 @@ pub fn create_conversation(
     ttl: Option<chrono::Duration>,
     db: &mut Database,
 ) -> Result<String, EngineError> {
+    let _span = telemetry::db_span("create_conversation");
+
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -82,10 +88,14 @@ pub fn create_conversation(
         );
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn close_conversation(id: &str, client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("close_conversation");
+
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -132,10 +142,12 @@ pub fn close_conversation(id: &str, client: &Client, db: &mut Database) -> Resul
         return sqlite_connector::conversations::close_conversation(id, client, "CLOSED", db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn close_all_conversations(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call close all conversations")),
         LogLvl::Info,
@@ -174,13 +186,17 @@ pub fn close_all_conversations(client: &Client, db: &mut Database) -> Result<(),
         return sqlite_connector::conversations::close_all_conversations(client, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_latest_open(
     client: &Client,
     db: &mut Database,
 ) -> Result<Option<DbConversation>, EngineError> {
+    let _span = telemetry::db_span("get_latest_open");
+
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -224,14 +240,41 @@ pub fn get_latest_open(
         return sqlite_connector::conversations::get_latest_open(client, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
+/**
+ * The run has updated the conversation to its next version, or another run updated it first
+ */
+#[cfg(any(
+    feature = "mongo",
+    feature = "dynamo",
+    feature = "postgresql",
+    feature = "sqlite"
+))]
+fn check_conversation_version(
+    data: &mut ConversationInfo,
+    updated: bool,
+) -> Result<(), EngineError> {
+    if !updated {
+        return Err(EngineError::Conflict);
+    }
+
+    data.conversation_version += 1;
+    Ok(())
+}
+
+/**
+ * Update the conversation of the run, only if no other run (on another server) updated it
+ * since it was read: each update increments its version. EngineError::Conflict otherwise.
+ */
 pub fn update_conversation(
     data: &mut ConversationInfo,
     flow_id: Option<String>,
     step_id: Option<String>,
 ) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("update_conversation");
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -260,50 +303,58 @@ pub fn update_conversation(
     #[cfg(feature = "mongo")]
     if is_mongodb() {
         let db = mongodb_connector::get_db(&data.db)?;
-        return mongodb_connector::conversations::update_conversation(
+        let updated = mongodb_connector::conversations::update_conversation(
             &data.conversation_id,
-            &data.client,
+            &get_storage_client(&data.client),
             flow_id,
             step_id,
+            data.conversation_version,
             db,
-        );
+        )?;
+        return check_conversation_version(data, updated);
     }
 
     #[cfg(feature = "dynamo")]
     if is_dynamodb() {
         let db = dynamodb_connector::get_db(&mut data.db)?;
-        return dynamodb_connector::conversations::update_conversation(
+        let updated = dynamodb_connector::conversations::update_conversation(
             &data.conversation_id,
-            &data.client,
+            &get_storage_client(&data.client),
             flow_id,
             step_id,
+            data.conversation_version,
             db,
-        );
+        )?;
+        return check_conversation_version(data, updated);
     }
 
     #[cfg(feature = "postgresql")]
     if is_postgresql() {
         let db = postgresql_connector::get_db(&mut data.db)?;
-        return postgresql_connector::conversations::update_conversation(
+        let updated = postgresql_connector::conversations::update_conversation(
             &data.conversation_id,
             flow_id,
             step_id,
+            data.conversation_version,
             db,
-        );
+        )?;
+        return check_conversation_version(data, updated);
     }
 
     #[cfg(feature = "sqlite")]
     if is_sqlite() {
         let db = sqlite_connector::get_db(&mut data.db)?;
-        return sqlite_connector::conversations::update_conversation(
+        let updated = sqlite_connector::conversations::update_conversation(
             &data.conversation_id,
             flow_id,
             step_id,
+            data.conversation_version,
             db,
-        );
+        )?;
+        return check_conversation_version(data, updated);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_client_conversations(
@@ -311,7 +362,12 @@ pub fn get_client_conversations(
     db: &mut Database,
     limit: Option<i64>,
     pagination_key: Option<String>,
+    tag: Option<&str>,
 ) -> Result<serde_json::Value, EngineError> {
+    let client = &get_storage_client(client);
+    let tag = tag.map(format_tag);
+    let tag = tag.as_deref();
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -344,6 +400,7 @@ pub fn get_client_conversations(
             db,
             limit,
             pagination_key,
+            tag,
         );
     }
 
@@ -357,6 +414,7 @@ pub fn get_client_conversations(
             db,
             limit,
             pagination_key,
+            tag,
         );
     }
 
@@ -368,6 +426,7 @@ pub fn get_client_conversations(
             db,
             limit,
             pagination_key,
+            tag,
         );
     }
 
@@ -379,8 +438,284 @@ pub fn get_client_conversations(
             db,
             limit,
             pagination_key,
+            tag,
+        );
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Tags are case insensitive
+ */
+fn format_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/**
+ * Attach tags to a conversation, the tags it already has are kept
+ */
+pub fn add_conversation_tags(
+    id: &str,
+    client: &Client,
+    tags: &[String],
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+    let tags: Vec<String> = tags.iter().map(|tag| format_tag(tag)).collect();
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call add conversation tags conversation_id: {}", id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(&client),
+            None,
+            None,
+            format!("db call add conversation tags {:?} conversation_id: {}", tags, id),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::conversations::add_conversation_tags(id, &tags, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::conversations::add_conversation_tags(id, client, &tags, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::add_conversation_tags(id, &tags, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::add_conversation_tags(id, &tags, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Save the summary of a closed conversation, replacing the previous one
+ */
+pub fn set_conversation_summary(
+    id: &str,
+    client: &Client,
+    summary: &str,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call set conversation summary conversation_id: {}", id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(&client),
+            None,
+            None,
+            format!("db call set conversation summary {:?} conversation_id: {}", summary, id),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::conversations::set_conversation_summary(id, summary, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::conversations::set_conversation_summary(
+            id, client, summary, db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::set_conversation_summary(id, summary, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::set_conversation_summary(id, summary, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * List the clients that have at least one conversation with the bot,
+ * optionally only on a given channel
+ */
+pub fn get_bot_clients(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    db: &mut Database,
+) -> Result<Vec<Client>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call get bot clients, bot_id: {:?}, channel_id: {:?}",
+                bot_id, channel_id
+            ),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::conversations::get_bot_clients(bot_id, channel_id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::conversations::get_bot_clients(bot_id, channel_id, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::get_bot_clients(bot_id, channel_id, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::get_bot_clients(bot_id, channel_id, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Closed conversations last updated before a date, oldest first
+ */
+pub fn get_closed_conversations(
+    before: chrono::DateTime<chrono::Utc>,
+    limit: i64,
+    db: &mut Database,
+) -> Result<Vec<DbConversation>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get closed conversations before: {}", before),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        let before = bson::DateTime::from_chrono(before);
+        return mongodb_connector::conversations::get_closed_conversations(before, limit, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        let before = before.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+        return dynamodb_connector::conversations::get_closed_conversations(&before, limit, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::get_closed_conversations(
+            before.naive_utc(),
+            limit,
+            db,
         );
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::get_closed_conversations(
+            before.naive_utc(),
+            limit,
+            db,
+        );
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Delete a conversation of a client with all its messages
+ */
+pub fn delete_conversation(
+    id: &str,
+    client: &Client,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call delete conversation id: {}", id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::conversations::delete_conversation(id, client, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::conversations::delete_conversation(id, client, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::delete_conversation(id, client, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::delete_conversation(id, client, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
@@ -1,15 +1,19 @@
 #[cfg(test)]
 mod tests {
-    use csml_interpreter::data::{context::ContextStepInfo, CsmlFlow, Message};
+    use csml_interpreter::data::{context::ContextStepInfo, CsmlFlow, Memory, Message};
     use std::collections::HashMap;
 
-    use crate::{db_connectors::*, init_db, make_migrations, Client, Context, ConversationInfo};
+    use crate::{
+        db_connectors::*, init_db, make_migrations, Client, Context, ConversationInfo, EngineError,
+        RunContext,
+    };
 
     fn get_client() -> Client {
         Client {
             user_id: "alexis".to_owned(),
             bot_id: "botid".to_owned(),
             channel_id: "some-channel-id".to_owned(),
+            tenant_id: None,
         }
     }
 
@@ -22,6 +26,14 @@ mod tests {
             step: ContextStepInfo::Normal("start".to_owned()),
             flow: "Default".to_owned(),
             previous_bot: None,
+            secrets: HashMap::new(),
+            globals: HashMap::new(),
+            flags: HashMap::new(),
+            debugger: None,
+            client: None,
+            knowledge_base: None,
+            sandbox: None,
+            step_hooks: None,
         }
     }
 
@@ -44,6 +56,12 @@ mod tests {
             env: None,
             modules: None,
             multibot: None,
+            apps: None,
+            apps_endpoint_options: None,
+            no_data_retention: None,
+            conversation_expiration: None,
+            metadata_schema: None,
+            locales: None,
         }
     }
 
@@ -55,6 +73,7 @@ mod tests {
         ConversationInfo {
             request_id: "1234".to_owned(),
             conversation_id,
+            conversation_version: 0,
             callback_url: None,
             client: get_client(),
             context: get_context(),
@@ -62,6 +81,9 @@ mod tests {
             messages,
             ttl: None,
             low_data: false,
+            no_data_retention: false,
+            bot_version_id: None,
+            run_context: RunContext::default(),
             db,
         }
     }
@@ -83,7 +105,7 @@ mod tests {
 
         let bot_version = bot::create_bot_version(bot_id.clone(), bot, &mut db).unwrap();
 
-        let last_bot_version = bot::get_last_bot_version(&bot_id, &mut db)
+        let last_bot_version = bot::get_last_bot_version(&bot_id, None, &mut db)
             .unwrap()
             .unwrap();
 
@@ -100,6 +122,30 @@ mod tests {
         assert_eq!(0, versions["bots"].as_array().unwrap().len());
     }
 
+    #[test]
+    fn ok_bot_version_ast() {
+        make_migrations().unwrap_or({});
+
+        let mut bot = init_bot();
+        bot.id = "bot_version_ast".to_owned();
+        bot.bot_ast = Some("ast".to_owned());
+        let mut db = init_db().unwrap();
+
+        bot::create_bot_version(bot.id.clone(), bot, &mut db).unwrap();
+
+        let last_bot_version = bot::get_last_bot_version("bot_version_ast", None, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(last_bot_version.bot.bot_ast, Some("ast".to_owned()));
+
+        // an AST parsed by another engine version is not used
+        let ast =
+            crate::data::get_compatible_bot_ast(&Some("ast".to_owned()), &Some("0.0.0".to_owned()));
+        assert_eq!(ast, None);
+
+        bot::delete_bot_versions("bot_version_ast", &mut db).unwrap();
+    }
+
     #[test]
     fn ok_messages() {
         make_migrations().unwrap_or({});
@@ -149,6 +195,165 @@ mod tests {
         assert_eq!(0, received_msgs.len());
     }
 
+    #[test]
+    fn ok_conversation_messages() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+        let other_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id.clone(), db);
+        messages::add_messages_bulk(&mut data, vec![gen_message("1")], 0, "RECEIVE").unwrap();
+        messages::add_messages_bulk(
+            &mut data,
+            vec![gen_message("2"), gen_message("3")],
+            1,
+            "SEND",
+        )
+        .unwrap();
+
+        data.conversation_id = other_id;
+        messages::add_messages_bulk(&mut data, vec![gen_message("other")], 0, "SEND").unwrap();
+
+        let msgs = messages::get_conversation_messages(&client, &c_id, &mut data.db).unwrap();
+        let texts: Vec<&str> = msgs
+            .iter()
+            .map(|msg| msg["payload"]["content"]["text"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(texts, vec!["1", "2", "3"]);
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
+    #[test]
+    fn ok_message_references() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id.clone(), db);
+        let reaction = serde_json::json!({
+            "content_type": "reaction",
+            "content": {"reference_id": "channel-message-1", "reaction": "+1"},
+        });
+        messages::add_messages_bulk(&mut data, vec![gen_message("1"), reaction], 0, "RECEIVE")
+            .unwrap();
+
+        let msgs = messages::get_conversation_messages(&client, &c_id, &mut data.db).unwrap();
+        let references: Vec<&serde_json::Value> =
+            msgs.iter().map(|msg| &msg["reference_id"]).collect();
+
+        assert_eq!(
+            references,
+            vec![&serde_json::Value::Null, &serde_json::json!("channel-message-1")]
+        );
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
+    #[test]
+    fn ok_message_request_id() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id.clone(), db);
+        data.run_context.request_id = Some("request_id".to_owned());
+        messages::add_messages_bulk(&mut data, vec![gen_message("1")], 0, "SEND").unwrap();
+
+        let msgs = messages::get_conversation_messages(&client, &c_id, &mut data.db).unwrap();
+        assert_eq!(msgs[0]["request_id"], "request_id");
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
+    #[test]
+    fn ok_message_bot_version_id() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id.clone(), db);
+        data.bot_version_id = Some("version_id".to_owned());
+        messages::add_messages_bulk(&mut data, vec![gen_message("1")], 0, "SEND").unwrap();
+
+        let msgs = messages::get_conversation_messages(&client, &c_id, &mut data.db).unwrap();
+        assert_eq!(msgs[0]["bot_version_id"], "version_id");
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
+    #[test]
+    fn ok_messages_no_data_retention() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id, db);
+        data.no_data_retention = true;
+
+        messages::add_messages_bulk(&mut data, vec![gen_message("1")], 0, "SEND").unwrap();
+
+        let response =
+            messages::get_client_messages(&client, &mut data.db, None, None, None, None).unwrap();
+
+        let received_msgs: Vec<serde_json::Value> =
+            serde_json::from_value(response["messages"].clone()).unwrap();
+        assert_eq!(0, received_msgs.len());
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
+    #[test]
+    fn ko_messages_payload_size() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id, db);
+        let msgs = vec![gen_message("1"), gen_message(&"a".repeat(400_000))];
+
+        assert!(messages::add_messages_bulk(&mut data, msgs, 0, "SEND").is_err());
+
+        let response =
+            messages::get_client_messages(&client, &mut data.db, None, None, None, None).unwrap();
+        assert_eq!(response["messages"].as_array().unwrap().len(), 0);
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
     #[test]
     fn ok_conversation() {
         make_migrations().unwrap_or({});
@@ -163,7 +368,7 @@ mod tests {
         conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
 
         let response =
-            conversations::get_client_conversations(&client, &mut db, Some(6), None).unwrap();
+            conversations::get_client_conversations(&client, &mut db, Some(6), None, None).unwrap();
 
         let conversations: Vec<serde_json::Value> =
             serde_json::from_value(response["conversations"].clone()).unwrap();
@@ -173,13 +378,152 @@ mod tests {
         user::delete_client(&client, &mut db).unwrap();
 
         let response =
-            conversations::get_client_conversations(&client, &mut db, Some(6), None).unwrap();
+            conversations::get_client_conversations(&client, &mut db, Some(6), None, None).unwrap();
 
         let conversations: Vec<serde_json::Value> =
             serde_json::from_value(response["conversations"].clone()).unwrap();
         assert_eq!(conversations.len(), 0);
     }
 
+    #[test]
+    fn ok_conversation_tags() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+        conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let tags = vec!["VIP".to_owned(), "billing_%".to_owned()];
+        conversations::add_conversation_tags(&c_id, &client, &tags, &mut db).unwrap();
+        let tags = vec!["vip".to_owned()];
+        conversations::add_conversation_tags(&c_id, &client, &tags, &mut db).unwrap();
+
+        let response =
+            conversations::get_client_conversations(&client, &mut db, Some(6), None, Some("Vip"))
+                .unwrap();
+        let conversations: Vec<serde_json::Value> =
+            serde_json::from_value(response["conversations"].clone()).unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0]["tags"], serde_json::json!(["vip", "billing_%"]));
+
+        // the wildcards of a tag are matched literally
+        let response =
+            conversations::get_client_conversations(&client, &mut db, Some(6), None, Some("billing"))
+                .unwrap();
+        assert_eq!(response["conversations"].as_array().unwrap().len(), 0);
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+
+    #[test]
+    fn ko_conversation_version_conflict() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        // two servers read the conversation at its version 0
+        let mut data = get_conversation_info(vec![], c_id.clone(), init_db().unwrap());
+        let mut other = get_conversation_info(vec![], c_id.clone(), init_db().unwrap());
+
+        conversations::update_conversation(&mut data, None, Some("next".to_owned())).unwrap();
+        assert_eq!(data.conversation_version, 1);
+        assert!(matches!(
+            conversations::update_conversation(&mut other, None, Some("other".to_owned())),
+            Err(EngineError::Conflict)
+        ));
+
+        // the run updating the conversation keeps its next versions
+        conversations::update_conversation(&mut data, None, None).unwrap();
+        let conversation = conversations::get_latest_open(&client, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(conversation.step_id, "next");
+        assert_eq!(conversation.version, 2);
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+
+    #[test]
+    fn ok_conversation_summary() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+        conversations::close_conversation(&c_id, &client, &mut db).unwrap();
+        conversations::set_conversation_summary(&c_id, &client, "The user lost a card", &mut db)
+            .unwrap();
+
+        let response =
+            conversations::get_client_conversations(&client, &mut db, Some(6), None, None).unwrap();
+        let conversations: Vec<serde_json::Value> =
+            serde_json::from_value(response["conversations"].clone()).unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0]["status"], "CLOSED");
+        assert_eq!(conversations[0]["summary"], "The user lost a card");
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+
+    #[test]
+    fn ok_tenants() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let client = get_client();
+        let mut tenant_client = get_client();
+        tenant_client.tenant_id = Some("tenant".to_owned());
+        let mut other_tenant_client = get_client();
+        other_tenant_client.tenant_id = Some("other_tenant".to_owned());
+
+        for client in [&client, &tenant_client, &other_tenant_client].iter() {
+            user::delete_client(client, &mut db).unwrap();
+        }
+
+        conversations::create_conversation("Default", "start", &tenant_client, None, &mut db)
+            .unwrap();
+        memories::create_client_memory(
+            &tenant_client,
+            "key".to_owned(),
+            serde_json::json!("value"),
+            None,
+            &mut db,
+        )
+        .unwrap();
+
+        assert!(conversations::get_latest_open(&tenant_client, &mut db)
+            .unwrap()
+            .is_some());
+        let response = memories::internal_use_get_memories(&tenant_client, &mut db).unwrap();
+        assert_eq!(response["key"], "value");
+
+        // the same client in another tenant, or without tenant, has no access to the data
+        for client in [&client, &other_tenant_client].iter() {
+            assert!(conversations::get_latest_open(client, &mut db)
+                .unwrap()
+                .is_none());
+            let response = memories::internal_use_get_memories(client, &mut db).unwrap();
+            assert_eq!(response.as_object().unwrap().len(), 0);
+        }
+
+        user::delete_client(&tenant_client, &mut db).unwrap();
+    }
+
     #[test]
     fn ok_memories() {
         make_migrations().unwrap_or({});
@@ -276,6 +620,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ok_memory_history() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let db = init_db().unwrap();
+        let mut data = get_conversation_info(vec![], "conversation_id".to_owned(), db);
+
+        user::delete_client(&client, &mut data.db).unwrap();
+
+        let mut mems = HashMap::new();
+        mems.insert(
+            "name".to_owned(),
+            Memory {
+                key: "name".to_owned(),
+                value: serde_json::json!("Jane"),
+            },
+        );
+        memories::add_memories(&mut data, &mems).unwrap();
+        memories::create_client_memory(
+            &client,
+            "name".to_owned(),
+            serde_json::json!("John"),
+            None,
+            &mut data.db,
+        )
+        .unwrap();
+
+        let history = memories::get_memory_history(&client, "name", &mut data.db).unwrap();
+        let history = history.as_array().unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["value"], serde_json::json!("John"));
+        assert_eq!(history[0]["interaction_id"], serde_json::Value::Null);
+        assert_eq!(history[1]["value"], serde_json::json!("Jane"));
+        assert_eq!(history[1]["interaction_id"], serde_json::json!("1234"));
+    }
+
     #[test]
     fn ok_get_memory() {
         make_migrations().unwrap_or({});
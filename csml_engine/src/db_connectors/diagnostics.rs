@@ -0,0 +1,47 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use crate::data::{EnvironmentCheck, CHECK_ERROR};
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::Database;
+
+/**
+ * Check that the tables (or collections) of the engine exist and can be read
+ */
+pub fn check_storage(db: &mut Database) -> Vec<EnvironmentCheck> {
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        if let Ok(db) = mongodb_connector::get_db(db) {
+            return mongodb_connector::diagnostics::check_storage(db);
+        }
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        if let Ok(db) = dynamodb_connector::get_db(db) {
+            return dynamodb_connector::diagnostics::check_storage(db);
+        }
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        if let Ok(db) = postgresql_connector::get_db(db) {
+            return postgresql_connector::diagnostics::check_storage(db);
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        if let Ok(db) = sqlite_connector::get_db(db) {
+            return sqlite_connector::diagnostics::check_storage(db);
+        }
+    }
+
+    vec![EnvironmentCheck::new("tables", CHECK_ERROR, ERROR_DB_SETUP)]
+}
@@ -1,12 +1,13 @@
+use crate::config::get_config;
 use crate::data::DynamoDbClient;
 use crate::EngineError;
 use rusoto_s3::{DeleteObjectRequest, GetObjectRequest, PutObjectRequest, S3};
 use std::io::Read;
 
 pub fn put_object(db: &mut DynamoDbClient, key: &str, content: String) -> Result<(), EngineError> {
-    let bucket = match std::env::var("AWS_S3_BUCKET") {
-        Ok(bucket) => bucket,
-        Err(_) => {
+    let bucket = match get_config().text("database.dynamodb.s3_bucket") {
+        Some(bucket) => bucket,
+        None => {
             return Err(EngineError::Manager(
                 "Missing AWS_S3_BUCKET env var".to_owned(),
             ))
@@ -29,9 +30,9 @@ pub fn put_object(db: &mut DynamoDbClient, key: &str, content: String) -> Result
 }
 
 pub fn get_object(db: &mut DynamoDbClient, key: &str) -> Result<String, EngineError> {
-    let bucket = match std::env::var("AWS_S3_BUCKET") {
-        Ok(bucket) => bucket,
-        Err(_) => {
+    let bucket = match get_config().text("database.dynamodb.s3_bucket") {
+        Some(bucket) => bucket,
+        None => {
             return Err(EngineError::Manager(
                 "Missing AWS_S3_BUCKET env var".to_owned(),
             ))
@@ -62,9 +63,9 @@ pub fn get_object(db: &mut DynamoDbClient, key: &str) -> Result<String, EngineEr
 }
 
 pub fn delete_object(db: &mut DynamoDbClient, key: &str) -> Result<(), EngineError> {
-    let bucket = match std::env::var("AWS_S3_BUCKET") {
-        Ok(bucket) => bucket,
-        Err(_) => {
+    let bucket = match get_config().text("database.dynamodb.s3_bucket") {
+        Some(bucket) => bucket,
+        None => {
             return Err(EngineError::Manager(
                 "Missing AWS_S3_BUCKET env var".to_owned(),
             ))
@@ -1,4 +1,6 @@
-use crate::data::{DynamoBot, DynamoBotBincode, DynamoDbClient};
+use crate::data::{
+    get_compatible_bot_ast, DbErrorKind, DynamoBot, DynamoBotBincode, DynamoDbClient,
+};
 use crate::db_connectors::dynamodb::utils::*;
 use crate::db_connectors::{
     dynamodb::{aws_s3, Bot, BotKeys, Class, DynamoDbKey},
@@ -14,6 +16,7 @@ pub fn create_bot_version(
     bot: String,
     flows: String,
     flow_modules: String,
+    bot_ast: Option<String>,
     db: &mut DynamoDbClient,
 ) -> Result<String, EngineError> {
     let data: Bot = Bot::new(bot_id, bot);
@@ -37,9 +40,34 @@ pub fn create_bot_version(
     );
     aws_s3::put_object(db, &key, flow_modules)?;
 
+    // the AST can be too large for a dynamodb item
+    if let Some(bot_ast) = bot_ast {
+        let key = format!("bots/{}/versions/{}/ast.json", &data.id, &data.version_id);
+        let ast = serde_json::json!({
+            "engine_version": env!("CARGO_PKG_VERSION"),
+            "bot_ast": bot_ast,
+        });
+        aws_s3::put_object(db, &key, ast.to_string())?;
+    }
+
     Ok(data.version_id.to_owned())
 }
 
+/**
+ * Versions saved before the AST was stored, or by another engine version, have no usable AST
+ */
+pub fn get_bot_ast(key: &str, db: &mut DynamoDbClient) -> Option<String> {
+    let object = aws_s3::get_object(db, key).ok()?;
+    let ast: serde_json::Value = serde_json::from_str(&object).ok()?;
+
+    get_compatible_bot_ast(
+        &ast["bot_ast"].as_str().map(|ast| ast.to_owned()),
+        &ast["engine_version"]
+            .as_str()
+            .map(|version| version.to_owned()),
+    )
+}
+
 pub fn get_flows(key: &str, db: &mut DynamoDbClient) -> Result<Vec<CsmlFlow>, EngineError> {
     let object = aws_s3::get_object(db, key)?;
     let flows: Vec<CsmlFlow> = match serde_json::from_str(&object) {
@@ -68,6 +96,7 @@ fn query_bot_version(
     bot_id: &str,
     limit: i64,
     pagination_key: Option<HashMap<String, AttributeValue>>,
+    read_only: bool,
     db: &mut DynamoDbClient,
 ) -> Result<QueryOutput, EngineError> {
     let key_cond_expr =
@@ -115,10 +144,17 @@ fn query_bot_version(
         ..Default::default()
     };
 
-    let query = db.client.query(input);
+    // the deletions always query the primary, the replica may not have all the items yet
+    let client = if read_only { db.reader() } else { &db.client };
+    let query = client.query(input);
     let data = match db.runtime.block_on(query) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("query_bot_version {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_bot_version {:?}", e),
+            ))
+        }
     };
 
     Ok(data)
@@ -136,7 +172,7 @@ pub fn get_bot_versions(
         None => 20,
     };
 
-    let data = query_bot_version(bot_id, limit, pagination_key, db)?;
+    let data = query_bot_version(bot_id, limit, pagination_key, true, db)?;
     /////////
     // The query returns an array of items (max 10, based on the limit param above).
     // If 0 item is returned it means that there is no open conversation, so simply return None
@@ -228,8 +264,12 @@ pub fn get_bot_by_version_id(
             let key = format!("bots/{}/versions/{}/modules.json", bot_id, version_id);
             let modules = get_modules(&key, db)?;
 
+            let mut csml_bot = csml_bot.to_bot(flows, modules);
+            let key = format!("bots/{}/versions/{}/ast.json", bot_id, version_id);
+            csml_bot.bot_ast = get_bot_ast(&key, db);
+
             Ok(Some(BotVersion {
-                bot: csml_bot.to_bot(flows, modules),
+                bot: csml_bot,
                 version_id: bot.version_id,
                 engine_version: env!("CARGO_PKG_VERSION").to_owned(),
             }))
@@ -292,10 +332,10 @@ pub fn get_last_bot_version(
     let data = match db.runtime.block_on(query) {
         Ok(data) => data,
         Err(e) => {
-            return Err(EngineError::Manager(format!(
-                "Fail to find last bot version in db: {:?}",
-                e
-            )))
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("Fail to find last bot version in db: {:?}", e),
+            ))
         }
     };
 
@@ -342,8 +382,12 @@ pub fn get_last_bot_version(
             let key = format!("bots/{}/versions/{}/modules.json", bot_id, bot.version_id);
             let modules = get_modules(&key, db)?;
 
+            let mut csml_bot = csml_bot.to_bot(flows, modules);
+            let key = format!("bots/{}/versions/{}/ast.json", bot_id, bot.version_id);
+            csml_bot.bot_ast = get_bot_ast(&key, db);
+
             Ok(Some(BotVersion {
-                bot: csml_bot.to_bot(flows, modules),
+                bot: csml_bot,
                 version_id: bot.version_id,
                 engine_version: env!("CARGO_PKG_VERSION").to_owned(),
             }))
@@ -363,6 +407,9 @@ pub fn delete_bot_version(
     let key = format!("bots/{}/versions/{}/modules.json", bot_id, version_id);
     aws_s3::delete_object(db, &key)?;
 
+    let key = format!("bots/{}/versions/{}/ast.json", bot_id, version_id);
+    aws_s3::delete_object(db, &key)?;
+
     let item_key = DynamoDbKey {
         hash: Bot::get_hash(bot_id),
         range: Bot::get_range(version_id),
@@ -385,7 +432,7 @@ pub fn delete_bot_versions(bot_id: &str, db: &mut DynamoDbClient) -> Result<(),
 
     loop {
         // 25 is the Maximum operations in a single request for BatchWriteItemInput
-        let data = query_bot_version(bot_id, 25, pagination_key, db)?;
+        let data = query_bot_version(bot_id, 25, pagination_key, false, db)?;
 
         // The query returns an array of items (max 10, based on the limit param above).
         // If 0 item is returned it means that there is no open conversation, so simply return None
@@ -408,6 +455,9 @@ pub fn delete_bot_versions(bot_id: &str, db: &mut DynamoDbClient) -> Result<(),
             let key = format!("bots/{}/versions/{}/modules.json", bot_id, version_id);
             aws_s3::delete_object(db, &key)?;
 
+            let key = format!("bots/{}/versions/{}/ast.json", bot_id, version_id);
+            aws_s3::delete_object(db, &key)?;
+
             let key = serde_dynamodb::to_hashmap(&DynamoDbKey {
                 hash: data.hash,
                 range: data.range,
@@ -493,7 +543,12 @@ fn query_bot_info(
     let future = db.client.query(input);
     let data = match db.runtime.block_on(future) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("query_bot_info {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_bot_info {:?}", e),
+            ))
+        }
     };
 
     Ok(data)
@@ -1,7 +1,8 @@
-use crate::data::DynamoDbClient;
-use crate::db_connectors::dynamodb::{Conversation, ConversationKeys, DynamoDbKey};
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::db_connectors::dynamodb::{Conversation, ConversationKeys, DynamoDbKey, Message};
 use crate::db_connectors::DbConversation;
 use crate::{Client, EngineError};
+use rusoto_core::RusotoError;
 use rusoto_dynamodb::*;
 use std::collections::HashMap;
 
@@ -181,10 +182,10 @@ fn get_all_open_conversations(
     let data = match db.runtime.block_on(query) {
         Ok(data) => data,
         Err(e) => {
-            return Err(EngineError::Manager(format!(
-                "get_all_open_conversations {:?}",
-                e
-            )))
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("get_all_open_conversations {:?}", e),
+            ))
         }
     };
 
@@ -302,7 +303,12 @@ pub fn get_latest_open(
     let query = db.client.query(input);
     let data = match db.runtime.block_on(query) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("get_latest_open {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("get_latest_open {:?}", e),
+            ))
+        }
     };
 
     // The query returns an array of items (max 1, based on the limit param above).
@@ -337,24 +343,39 @@ pub fn get_latest_open(
         last_interaction_at: conv.last_interaction_at.to_string(),
         updated_at: conv.updated_at.to_string(),
         created_at: conv.created_at.to_string(),
+        version: conv.version,
     }))
 }
 
+/**
+ * Update the conversation if it is still at `version`, returns false if another run
+ * updated it since
+ */
 pub fn update_conversation(
     conversation_id: &str,
     client: &Client,
     flow_id: Option<String>,
     step_id: Option<String>,
+    version: i32,
     db: &mut DynamoDbClient,
-) -> Result<(), EngineError> {
+) -> Result<bool, EngineError> {
     let hash = Conversation::get_hash(client);
     let range = Conversation::get_range("OPEN", conversation_id);
 
     // make sure that if the item does not already exist, it is NOT created automatically
-    let condition_expr = "#hashKey = :hashVal AND #rangeKey = :rangeVal".to_string();
+    let condition_expr = match version {
+        // the conversations saved before the versions have no version
+        0 => {
+            "#hashKey = :hashVal AND #rangeKey = :rangeVal \
+            AND (attribute_not_exists(#version) OR #version = :versionVal)"
+        }
+        _ => "#hashKey = :hashVal AND #rangeKey = :rangeVal AND #version = :versionVal",
+    }
+    .to_string();
     let expr_attr_names: HashMap<String, String> = [
         ("#hashKey".to_string(), "hash".to_string()),
         ("#rangeKey".to_string(), "range".to_string()),
+        ("#version".to_string(), "version".to_string()),
     ]
     .iter()
     .cloned()
@@ -379,7 +400,23 @@ pub fn update_conversation(
     .cloned()
     .collect();
 
-    let mut update_expr = "SET last_interaction_at = :lastInteractionAtVal".to_string();
+    expr_attr_values.insert(
+        ":versionVal".to_string(),
+        AttributeValue {
+            n: Some(version.to_string()),
+            ..Default::default()
+        },
+    );
+    expr_attr_values.insert(
+        ":nextVersionVal".to_string(),
+        AttributeValue {
+            n: Some((version + 1).to_string()),
+            ..Default::default()
+        },
+    );
+
+    let mut update_expr =
+        "SET last_interaction_at = :lastInteractionAtVal, #version = :nextVersionVal".to_string();
 
     // all items get the last_interaction_at date updated
     let now = get_date_time();
@@ -425,10 +462,141 @@ pub fn update_conversation(
         ..Default::default()
     };
 
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(false),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("update_conversation {:?}", e),
+            ))
+        }
+    }
+}
+
+/**
+ * Tags are only added to the open conversations
+ */
+pub fn add_conversation_tags(
+    id: &str,
+    client: &Client,
+    tags: &[String],
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let key = Conversation::get_key(client, "OPEN", id);
+
+    let input = GetItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&key)?,
+        ..Default::default()
+    };
+
+    let future = db.client.get_item(input);
+    let conversation: Conversation = match db.runtime.block_on(future)?.item {
+        Some(item) => serde_dynamodb::from_hashmap(item)?,
+        None => return Ok(()),
+    };
+
+    let mut conversation_tags = conversation.tags;
+    for tag in tags {
+        if !conversation_tags.contains(tag) {
+            conversation_tags.push(tag.to_owned());
+        }
+    }
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":tagsVal"),
+        AttributeValue {
+            l: Some(
+                conversation_tags
+                    .into_iter()
+                    .map(|tag| AttributeValue {
+                        s: Some(tag),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        },
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&key)?,
+        // the conversation may have been closed in the meantime
+        condition_expression: Some("attribute_exists(#rangeKey)".to_owned()),
+        update_expression: Some("SET tags = :tagsVal".to_owned()),
+        expression_attribute_names: Some(
+            [("#rangeKey".to_string(), "range".to_string())]
+                .iter()
+                .cloned()
+                .collect(),
+        ),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
     let future = db.client.update_item(input);
     match db.runtime.block_on(future) {
         Ok(_) => Ok(()),
-        Err(e) => return Err(EngineError::Manager(format!("update_conversation {:?}", e))),
+        Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(()),
+        Err(e) => Err(EngineError::Db(
+            DbErrorKind::Query,
+            format!("add_conversation_tags {:?}", e),
+        )),
+    }
+}
+
+/**
+ * The summary is generated once the conversation is closed
+ */
+pub fn set_conversation_summary(
+    id: &str,
+    client: &Client,
+    summary: &str,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let key = Conversation::get_key(client, "CLOSED", id);
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":summaryVal"),
+        AttributeValue {
+            s: Some(summary.to_owned()),
+            ..Default::default()
+        },
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&key)?,
+        // an update must not create a partial conversation
+        condition_expression: Some("attribute_exists(#rangeKey)".to_owned()),
+        update_expression: Some("SET summary = :summaryVal".to_owned()),
+        expression_attribute_names: Some(
+            [("#rangeKey".to_string(), "range".to_string())]
+                .iter()
+                .cloned()
+                .collect(),
+        ),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(()),
+        Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(()),
+        Err(e) => Err(EngineError::Db(
+            DbErrorKind::Query,
+            format!("set_conversation_summary {:?}", e),
+        )),
     }
 }
 
@@ -480,7 +648,12 @@ fn query_conversation(
     let future = db.client.query(input);
     let data = match db.runtime.block_on(future) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("query_conversation {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_conversation {:?}", e),
+            ))
+        }
     };
 
     Ok(data)
@@ -565,6 +738,7 @@ pub fn get_client_conversations(
     db: &mut DynamoDbClient,
     limit: Option<i64>,
     pagination_key: Option<HashMap<String, AttributeValue>>,
+    tag: Option<&str>,
 ) -> Result<serde_json::Value, EngineError> {
     let mut conversations = vec![];
     let limit = match limit {
@@ -636,8 +810,14 @@ pub fn get_client_conversations(
 
     let get_conversations = execute_conversations_batch_get_query(db, input)?;
 
+    // like a filter expression, the tag is filtered after the limit is applied to the page
+    let get_conversations = get_conversations.into_iter().filter(|conversation| match tag {
+        Some(tag) => conversation.tags.iter().any(|conversation_tag| conversation_tag == tag),
+        None => true,
+    });
+
     for conversation in get_conversations {
-        conversations.push(DbConversation {
+        let mut json = serde_json::json!(DbConversation {
             id: conversation.id.to_string(),
             client: client.to_owned(),
             flow_id: conversation.flow_id.to_string(),
@@ -646,7 +826,12 @@ pub fn get_client_conversations(
             last_interaction_at: conversation.last_interaction_at.to_string(),
             updated_at: conversation.updated_at.to_string(),
             created_at: conversation.created_at.to_string(),
-        })
+            version: conversation.version,
+        });
+        json["tags"] = serde_json::json!(conversation.tags);
+        json["summary"] = serde_json::json!(conversation.summary);
+
+        conversations.push(json)
     }
 
     match data.last_evaluated_key {
@@ -660,3 +845,273 @@ pub fn get_client_conversations(
         None => Ok(serde_json::json!({ "conversations": conversations })),
     }
 }
+
+#[derive(serde::Deserialize)]
+struct ConversationClient {
+    client: Option<Client>,
+}
+
+pub fn get_bot_clients(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<Client>, EngineError> {
+    let hash_prefix = match channel_id {
+        Some(channel_id) => format!("bot_id:{}#channel_id:{}#", bot_id, channel_id),
+        None => format!("bot_id:{}#", bot_id),
+    };
+
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#classKey"), String::from("class")),
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#client"), String::from("client")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":classVal"),
+            AttributeValue {
+                s: Some(String::from("conversation")),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":hashPrefix"),
+            AttributeValue {
+                s: Some(hash_prefix),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut clients: Vec<Client> = vec![];
+    // a client can have several conversations
+    let mut known_clients = std::collections::HashSet::new();
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            index_name: Some("ClassByClientIndex".to_owned()),
+            key_condition_expression: Some(
+                "#classKey = :classVal AND begins_with(#hashKey, :hashPrefix)".to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            projection_expression: Some("#client".to_owned()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let data = db.runtime.block_on(db.client.query(input))?;
+
+        for item in data.items.unwrap_or_default() {
+            let conversation: ConversationClient = serde_dynamodb::from_hashmap(item)?;
+
+            if let Some(client) = conversation.client {
+                if known_clients.insert((client.channel_id.clone(), client.user_id.clone())) {
+                    clients.push(client);
+                }
+            }
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(clients);
+        }
+    }
+}
+
+/**
+ * The closed conversations are not indexed by date: the table is scanned
+ * until enough conversations closed before the date are found.
+ */
+pub fn get_closed_conversations(
+    before: &str,
+    limit: i64,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbConversation>, EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#classKey"), String::from("class")),
+        (String::from("#statusKey"), String::from("status")),
+        (String::from("#updatedAtKey"), String::from("updated_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":classVal"),
+            AttributeValue {
+                s: Some(String::from("conversation")),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":statusVal"),
+            AttributeValue {
+                s: Some(String::from("CLOSED")),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":beforeVal"),
+            AttributeValue {
+                s: Some(before.to_owned()),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut conversations = vec![];
+    let mut pagination_key = None;
+
+    loop {
+        let input = ScanInput {
+            table_name: get_table_name()?,
+            filter_expression: Some(
+                "#classKey = :classVal AND #statusKey = :statusVal AND #updatedAtKey < :beforeVal"
+                    .to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let data = db.runtime.block_on(db.client.scan(input))?;
+
+        for item in data.items.unwrap_or_default() {
+            let conv: Conversation = serde_dynamodb::from_hashmap(item)?;
+
+            // the conversations saved without their client can not be archived
+            if let Some(client) = conv.client {
+                conversations.push(DbConversation {
+                    id: conv.id,
+                    client,
+                    flow_id: conv.flow_id,
+                    step_id: conv.step_id,
+                    status: conv.status,
+                    last_interaction_at: conv.last_interaction_at,
+                    updated_at: conv.updated_at,
+                    created_at: conv.created_at,
+                    version: conv.version,
+                });
+            }
+
+            if conversations.len() as i64 >= limit {
+                return Ok(conversations);
+            }
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(conversations);
+        }
+    }
+}
+
+pub fn delete_conversation(
+    id: &str,
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":hashVal"),
+            AttributeValue {
+                s: Some(Message::get_hash(client)),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":rangePrefix"),
+            AttributeValue {
+                s: Some(format!("{}#", Message::get_conversation_range_prefix(id))),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut pagination_key = None;
+
+    // batch writes are limited to 25 items
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some(
+                "#hashKey = :hashVal and begins_with(#rangeKey, :rangePrefix)".to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            projection_expression: Some("#hashKey, #rangeKey".to_owned()),
+            exclusive_start_key: pagination_key,
+            limit: Some(25),
+            ..Default::default()
+        };
+
+        let data = db.runtime.block_on(db.client.query(input))?;
+
+        let mut write_requests = vec![];
+        for item in data.items.unwrap_or_default() {
+            let message: DynamoDbKey = serde_dynamodb::from_hashmap(item)?;
+
+            write_requests.push(WriteRequest {
+                delete_request: Some(DeleteRequest {
+                    key: serde_dynamodb::to_hashmap(&message)?,
+                }),
+                put_request: None,
+            });
+        }
+
+        if !write_requests.is_empty() {
+            let request_items = [(get_table_name()?, write_requests)]
+                .iter()
+                .cloned()
+                .collect();
+
+            let input = BatchWriteItemInput {
+                request_items,
+                ..Default::default()
+            };
+
+            execute_batch_write_query(db, input)?;
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            break;
+        }
+    }
+
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&Conversation::get_key(client, "CLOSED", id))?,
+        ..Default::default()
+    };
+
+    db.runtime.block_on(db.client.delete_item(input))?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::data::*;
+use crate::db_connectors::dynamodb::utils::get_table_name;
+use rusoto_dynamodb::*;
+
+pub fn check_storage(db: &mut DynamoDbClient) -> Vec<EnvironmentCheck> {
+    let table_name = match get_table_name() {
+        Ok(table_name) => table_name,
+        Err(err) => {
+            return vec![EnvironmentCheck::new(
+                "table",
+                CHECK_ERROR,
+                &format!("{:?}", err),
+            )]
+        }
+    };
+    let name = format!("table {}", table_name);
+
+    let input = DescribeTableInput { table_name };
+    let future = db.client.describe_table(input);
+    let table = match db.runtime.block_on(future) {
+        Ok(output) => output.table.unwrap_or_default(),
+        Err(err) => {
+            return vec![EnvironmentCheck::new(
+                &name,
+                CHECK_ERROR,
+                &format!("{} (created by csml_server --create-dynamodb-table)", err),
+            )]
+        }
+    };
+
+    // the items are keyed by a "hash" partition key and a "range" sort key
+    let keys: Vec<String> = table
+        .key_schema
+        .unwrap_or_default()
+        .into_iter()
+        .map(|key| key.attribute_name)
+        .collect();
+    if keys != ["hash", "range"] {
+        return vec![EnvironmentCheck::new(
+            &name,
+            CHECK_ERROR,
+            &format!("expected the keys hash and range, got {:?}", keys),
+        )];
+    }
+
+    match table.table_status.as_deref() {
+        Some("ACTIVE") => vec![EnvironmentCheck::new(&name, CHECK_OK, "active")],
+        status => vec![EnvironmentCheck::new(
+            &name,
+            CHECK_WARNING,
+            &format!("status {}", status.unwrap_or("unknown")),
+        )],
+    }
+}
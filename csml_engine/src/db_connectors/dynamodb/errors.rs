@@ -0,0 +1,236 @@
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::db_connectors::{
+    dynamodb::{ConversationError, DynamoDbKey},
+    DbConversationError,
+};
+use crate::{
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError,
+};
+use chrono::{DateTime, Utc};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+fn format_created_at(created_at: DateTime<Utc>) -> String {
+    created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+fn get_attribute_value(value: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(value.to_owned()),
+        ..Default::default()
+    }
+}
+
+pub fn create_conversation_error(
+    error: &DbConversationError,
+    created_at: DateTime<Utc>,
+    expires_at: Option<i64>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let error = ConversationError::new(
+        error,
+        &encrypt_data(&error.error)?,
+        &format_created_at(created_at),
+        expires_at,
+    );
+
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&error)?,
+        table_name: get_table_name()?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+fn query_errors(
+    bot_id: &str,
+    key_condition_expression: &str,
+    filter_expression: Option<String>,
+    mut expr_attr_values: HashMap<String, AttributeValue>,
+    limit: i64,
+    pagination_key: Option<HashMap<String, AttributeValue>>,
+    db: &mut DynamoDbClient,
+) -> Result<QueryOutput, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    if matches!(&filter_expression, Some(filter) if filter.contains("#client")) {
+        expr_attr_names.insert(String::from("#client"), String::from("client"));
+    }
+
+    expr_attr_values.insert(
+        String::from(":hashVal"),
+        get_attribute_value(&ConversationError::get_hash(bot_id)),
+    );
+
+    let input = QueryInput {
+        table_name: get_table_name()?,
+        key_condition_expression: Some(key_condition_expression.to_owned()),
+        filter_expression,
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        limit: Some(limit),
+        exclusive_start_key: pagination_key,
+        // newest first
+        scan_index_forward: Some(false),
+        ..Default::default()
+    };
+
+    let future = db.client.query(input);
+    let data = match db.runtime.block_on(future) {
+        Ok(data) => data,
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_errors {:?}", e),
+            ))
+        }
+    };
+
+    Ok(data)
+}
+
+pub fn get_bot_errors(
+    bot_id: &str,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbConversationError>, EngineError> {
+    let (key_condition_expression, expr_attr_values) = match before {
+        Some(before) => {
+            let range_max = make_range(&["created_at", &format_created_at(before)]);
+
+            (
+                "#hashKey = :hashVal AND #rangeKey < :rangeMax",
+                [(String::from(":rangeMax"), get_attribute_value(&range_max))]
+                    .iter()
+                    .cloned()
+                    .collect(),
+            )
+        }
+        None => ("#hashKey = :hashVal", HashMap::new()),
+    };
+
+    let data = query_errors(
+        bot_id,
+        key_condition_expression,
+        None,
+        expr_attr_values,
+        limit,
+        None,
+        db,
+    )?;
+
+    let mut errors = vec![];
+    for item in data.items.unwrap_or_default() {
+        let error: ConversationError = serde_dynamodb::from_hashmap(item)?;
+
+        errors.push(DbConversationError {
+            id: error.id,
+            client: error.client,
+            conversation_id: error.conversation_id,
+            flow_id: error.flow_id,
+            step_id: error.step_id,
+            error: decrypt_data(error.payload)?,
+            created_at: error.created_at,
+        });
+    }
+
+    Ok(errors)
+}
+
+fn delete_error_items(
+    bot_id: &str,
+    filter_expression: Option<&str>,
+    expr_attr_values: HashMap<String, AttributeValue>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let mut pagination_key = None;
+
+    loop {
+        // 25 is the Maximum operations in a single request for BatchWriteItemInput
+        let data = query_errors(
+            bot_id,
+            "#hashKey = :hashVal",
+            filter_expression.map(|filter| filter.to_owned()),
+            expr_attr_values.clone(),
+            25,
+            pagination_key,
+            db,
+        )?;
+
+        let items = data.items.unwrap_or_default();
+
+        if !items.is_empty() {
+            let mut write_requests = vec![];
+            for item in items {
+                let error: ConversationError = serde_dynamodb::from_hashmap(item)?;
+
+                let key = serde_dynamodb::to_hashmap(&DynamoDbKey {
+                    hash: error.hash,
+                    range: error.range,
+                })?;
+
+                write_requests.push(WriteRequest {
+                    delete_request: Some(DeleteRequest { key }),
+                    put_request: None,
+                });
+            }
+
+            let request_items = [(get_table_name()?, write_requests)]
+                .iter()
+                .cloned()
+                .collect();
+
+            let input = BatchWriteItemInput {
+                request_items,
+                ..Default::default()
+            };
+
+            execute_batch_write_query(db, input)?;
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(());
+        }
+    }
+}
+
+pub fn delete_client_errors(client: &Client, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    let expr_attr_values = [
+        (
+            String::from(":channelId"),
+            get_attribute_value(&client.channel_id),
+        ),
+        (
+            String::from(":userId"),
+            get_attribute_value(&client.user_id),
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    delete_error_items(
+        &client.bot_id,
+        Some("#client.channel_id = :channelId AND #client.user_id = :userId"),
+        expr_attr_values,
+        db,
+    )
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    delete_error_items(bot_id, None, HashMap::new(), db)
+}
@@ -0,0 +1,140 @@
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::db_connectors::dynamodb::DynamoDbKey;
+use crate::EngineError;
+use chrono::{DateTime, Utc};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string()
+}
+
+fn get_attribute_value(value: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(value.to_owned()),
+        ..Default::default()
+    }
+}
+
+/**
+ * All the jobs are stored in the same partition.
+ * hash = job
+ * range = name#xxxx
+ */
+fn get_job_key(name: &str) -> Result<HashMap<String, AttributeValue>, EngineError> {
+    let key = DynamoDbKey::new("job", &make_range(&["name", name]));
+
+    Ok(serde_dynamodb::to_hashmap(&key)?)
+}
+
+pub fn claim_job(
+    name: &str,
+    owner: &str,
+    now: DateTime<Utc>,
+    locked_until: DateTime<Utc>,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let now = format_date(now);
+
+    let expr_attr_names = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#class"), String::from("class")),
+        (String::from("#name"), String::from("name")),
+        (String::from("#owner"), String::from("owner")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values = [
+        (String::from(":classVal"), get_attribute_value("job")),
+        (String::from(":nameVal"), get_attribute_value(name)),
+        (String::from(":ownerVal"), get_attribute_value(owner)),
+        (
+            String::from(":lockedUntilVal"),
+            get_attribute_value(&format_date(locked_until)),
+        ),
+        (String::from(":nowVal"), get_attribute_value(&now)),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    // the item is created on the first run of the job (dates are compared as strings)
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: get_job_key(name)?,
+        condition_expression: Some(
+            "attribute_not_exists(#hashKey) OR locked_until <= :nowVal".to_owned(),
+        ),
+        update_expression: Some(
+            "SET #class = :classVal, #name = :nameVal, #owner = :ownerVal, \
+            locked_until = :lockedUntilVal, last_run_at = :nowVal, updated_at = :nowVal, \
+            created_at = if_not_exists(created_at, :nowVal)"
+                .to_owned(),
+        ),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(false),
+        Err(e) => Err(EngineError::Db(DbErrorKind::Query, format!("claim_job {:?}", e))),
+    }
+}
+
+pub fn complete_job(
+    name: &str,
+    owner: &str,
+    next_run_at: DateTime<Utc>,
+    error: Option<&str>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_names = [(String::from("#owner"), String::from("owner"))]
+        .iter()
+        .cloned()
+        .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [
+        (String::from(":ownerVal"), get_attribute_value(owner)),
+        (
+            String::from(":lockedUntilVal"),
+            get_attribute_value(&format_date(next_run_at)),
+        ),
+        (String::from(":nowVal"), get_attribute_value(&get_date_time())),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let update_expr = match error {
+        Some(error) => {
+            expr_attr_values.insert(String::from(":errorVal"), get_attribute_value(error));
+            "SET locked_until = :lockedUntilVal, last_error = :errorVal, updated_at = :nowVal"
+        }
+        None => "SET locked_until = :lockedUntilVal, updated_at = :nowVal REMOVE last_error",
+    };
+
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: get_job_key(name)?,
+        condition_expression: Some("#owner = :ownerVal".to_owned()),
+        update_expression: Some(update_expr.to_owned()),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        // the lock was taken by another server after it expired
+        Ok(_) | Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(()),
+        Err(e) => Err(EngineError::Db(DbErrorKind::Query, format!("complete_job {:?}", e))),
+    }
+}
@@ -1,6 +1,11 @@
-use crate::data::DynamoDbClient;
+use crate::db_connectors::utils::get_storage_client;
+use crate::data::{DbErrorKind, DynamoDbClient};
 use crate::db_connectors::dynamodb::{get_db, DynamoDbKey, Memory, MemoryDeleteInfo, MemoryKeys};
-use crate::{encrypt::encrypt_data, Client, ConversationInfo, EngineError};
+use crate::db_connectors::search::format_search_terms;
+use crate::{
+    encrypt::{encrypt_data, get_search_terms},
+    Client, ConversationInfo, EngineError,
+};
 use csml_interpreter::data::Memory as InterpreterMemory;
 use rusoto_dynamodb::*;
 use std::collections::HashMap;
@@ -15,12 +20,15 @@ fn format_memories(
     let mut res = vec![];
 
     for (_, mem) in memories.iter() {
-        res.push(Memory::new(
-            &data.client,
+        let mut memory = Memory::new(
+            &get_storage_client(&data.client),
             &mem.key,
             Some(encrypt_data(&mem.value)?),
             expires_at,
-        ));
+        );
+        memory.search_terms = format_search_terms(get_search_terms(&mem.value)?);
+
+        res.push(memory);
     }
 
     Ok(res)
@@ -77,7 +85,8 @@ pub fn create_client_memory(
     expires_at: Option<i64>,
     db: &mut DynamoDbClient,
 ) -> Result<(), EngineError> {
-    let memories = Memory::new(client, &key, Some(encrypt_data(&value)?), expires_at);
+    let mut memories = Memory::new(client, &key, Some(encrypt_data(&value)?), expires_at);
+    memories.search_terms = format_search_terms(get_search_terms(&value)?);
 
     let input = PutItemInput {
         item: serde_dynamodb::to_hashmap(&memories)?,
@@ -122,7 +131,12 @@ fn query_memories(
     let future = db.client.query(input);
     let data = match db.runtime.block_on(future) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("query_memories {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_memories {:?}", e),
+            ))
+        }
     };
 
     Ok(data)
@@ -1,7 +1,13 @@
+use crate::db_connectors::utils::get_storage_client;
 use crate::db_connectors::dynamodb::{
     get_db, DynamoDbClient, DynamoDbKey, Message, MessageFromDateInfo, MessageKeys,
 };
-use crate::{data::EngineError, encrypt::encrypt_data, Client, ConversationInfo};
+use crate::{
+    data::{DbErrorKind, EngineError},
+    db_connectors::search::format_search_terms,
+    encrypt::{decrypt_data, encrypt_data, get_search_terms},
+    Client, ConversationInfo,
+};
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
 use rusoto_dynamodb::*;
 use std::collections::HashMap;
@@ -18,8 +24,8 @@ fn format_messages(
     let mut res = vec![];
 
     for (i, message) in messages.iter().enumerate() {
-        res.push(Message::new(
-            &data.client,
+        let mut item = Message::new(
+            &get_storage_client(&data.client),
             &data.conversation_id,
             &data.context.flow,
             &data.context.step.get_step(),
@@ -29,7 +35,15 @@ fn format_messages(
             &encrypt_data(&message)?,
             &message["content_type"].to_string(),
             expires_at,
-        ));
+        );
+        item.reference_id = message["content"]["reference_id"]
+            .as_str()
+            .map(|reference_id| reference_id.to_owned());
+        item.request_id = data.run_context.request_id.to_owned();
+        item.bot_version_id = data.bot_version_id.to_owned();
+        item.search_terms = format_search_terms(get_search_terms(message)?);
+
+        res.push(item);
     }
 
     Ok(res)
@@ -62,9 +76,7 @@ pub fn write_messages_batch(
             ..Default::default()
         };
 
-        let future = db.client.batch_write_item(input);
-
-        db.runtime.block_on(future)?;
+        execute_batch_write_query(db, input)?;
     }
 
     Ok(())
@@ -97,6 +109,7 @@ fn query_messages(
     expression_attribute_names: Option<HashMap<String, String>>,
     key_condition_expression: Option<String>,
     projection_expression: Option<String>,
+    read_only: bool,
 ) -> Result<QueryOutput, EngineError> {
     let hash = Message::get_hash(client);
 
@@ -134,10 +147,17 @@ fn query_messages(
         ..Default::default()
     };
 
-    let future = db.client.query(input);
+    // the deletions always query the primary, the replica may not have all the items yet
+    let reader = if read_only { db.reader() } else { &db.client };
+    let future = reader.query(input);
     let data = match db.runtime.block_on(future) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("query_messages {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_messages {:?}", e),
+            ))
+        }
     };
 
     Ok(data)
@@ -197,10 +217,15 @@ fn query_messages_from_date(
         ..Default::default()
     };
 
-    let future = db.client.query(input);
+    let future = db.reader().query(input);
     let data = match db.runtime.block_on(future) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("query_messages {:?}", e))),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_messages {:?}", e),
+            ))
+        }
     };
 
     Ok(data)
@@ -240,6 +265,7 @@ pub fn get_client_messages(
         Some(expr_attr_names),
         Some(key_condition_expression),
         Some(String::from("#rangeKey, #hashKey")),
+        true,
     )?;
 
     // The query returns an array of items (max 10, based on the limit param above).
@@ -379,6 +405,99 @@ pub fn get_client_messages_from_date(
     }
 }
 
+/**
+ * The range key of a message starts with its conversation id, so all the messages
+ * of a conversation are fetched in chronological order with a single paginated query
+ */
+pub fn get_conversation_messages(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut messages = vec![];
+    let mut pagination_key = None;
+
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":hashVal"),
+            AttributeValue {
+                s: Some(Message::get_hash(client)),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":rangePrefix"),
+            AttributeValue {
+                s: Some(format!(
+                    "{}#",
+                    Message::get_conversation_range_prefix(conversation_id)
+                )),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some(
+                "#hashKey = :hashVal and begins_with(#rangeKey, :rangePrefix)".to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            scan_index_forward: Some(true),
+            ..Default::default()
+        };
+
+        let future = db.reader().query(input);
+        let data = match db.runtime.block_on(future) {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(EngineError::Db(
+                    DbErrorKind::Query,
+                    format!("get_conversation_messages {:?}", e),
+                ))
+            }
+        };
+
+        for item in data.items.unwrap_or_default() {
+            let message: Message = serde_dynamodb::from_hashmap(item)?;
+
+            messages.push(serde_json::json!({
+                "client": message.client,
+                "conversation_id": message.conversation_id,
+                "flow_id": message.flow_id,
+                "step_id": message.step_id,
+                "message_order": message.message_order,
+                "interaction_order": message.interaction_order,
+                "direction": message.direction,
+                "payload": decrypt_data(message.payload)?,
+                "reference_id": message.reference_id,
+                "request_id": message.request_id,
+                "bot_version_id": message.bot_version_id,
+                "created_at": message.created_at
+            }));
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if pagination_key.is_none() {
+            return Ok(messages);
+        }
+    }
+}
+
 pub fn delete_user_messages(client: &Client, db: &mut DynamoDbClient) -> Result<(), EngineError> {
     let mut pagination_key = None;
 
@@ -405,6 +524,7 @@ pub fn delete_user_messages(client: &Client, db: &mut DynamoDbClient) -> Result<
             Some(expr_attr_names.clone()),
             Some(key_condition_expression.clone()),
             Some("#hashKey, #rangeKey".to_owned()),
+            false,
         )?;
 
         // The query returns an array of items (max 10, based on the limit param above).
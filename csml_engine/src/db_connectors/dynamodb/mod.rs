@@ -1,4 +1,6 @@
-use crate::data::DynamoDbClient;
+use crate::config::Config;
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::db_connectors::DbConversationError;
 use crate::{Client, Database, EngineError};
 use rusoto_dynamodb::AttributeValue;
 use serde::{Deserialize, Serialize};
@@ -8,19 +10,27 @@ use uuid::Uuid;
 pub mod aws_s3;
 pub mod bot;
 pub mod conversations;
+pub mod diagnostics;
+pub mod errors;
+pub mod jobs;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod scheduled_events;
+pub mod search;
 pub mod state;
+pub mod table;
 pub mod utils;
 
 use crate::db_connectors::dynamodb::utils::*;
 
 use rusoto_core::Region;
 
-pub fn init() -> Result<Database, EngineError> {
-    let region_name = std::env::var("AWS_REGION").ok();
-    let dynamodb_endpoint = std::env::var("AWS_DYNAMODB_ENDPOINT").ok();
-    let s3_endpoint = std::env::var("AWS_S3_ENDPOINT").ok();
+pub fn init(config: &Config) -> Result<Database, EngineError> {
+    let region_name = config.text("aws.region");
+    let dynamodb_endpoint = config.text("database.dynamodb.endpoint");
+    let dynamodb_read_endpoint = config.text("database.dynamodb.read_endpoint");
+    let s3_endpoint = config.text("database.dynamodb.s3_endpoint");
 
     let mut dynamodb_region = Region::default();
     if let (Some(region_name), Some(dynamodb_endpoint)) = (region_name.clone(), dynamodb_endpoint) {
@@ -30,6 +40,11 @@ pub fn init() -> Result<Database, EngineError> {
         };
     }
 
+    let dynamodb_read_region = dynamodb_read_endpoint.map(|endpoint| Region::Custom {
+        name: region_name.clone().unwrap_or_else(|| Region::default().name().to_owned()),
+        endpoint,
+    });
+
     let mut s3_region = Region::default();
     if let (Some(region_name), Some(s3_endpoint)) = (region_name, s3_endpoint) {
         s3_region = Region::Custom {
@@ -41,7 +56,10 @@ pub fn init() -> Result<Database, EngineError> {
     // check that the table name is set in env
     get_table_name()?;
 
-    let client = DynamoDbClient::new(dynamodb_region, s3_region);
+    let mut client = DynamoDbClient::new(dynamodb_region, s3_region);
+    if let Some(read_region) = dynamodb_read_region {
+        client = client.with_read_region(read_region);
+    }
 
     Ok(Database::Dynamodb(client))
 }
@@ -49,7 +67,8 @@ pub fn init() -> Result<Database, EngineError> {
 pub fn get_db<'a>(db: &'a mut Database) -> Result<&'a mut DynamoDbClient, EngineError> {
     match db {
         Database::Dynamodb(val) => Ok(val),
-        _ => Err(EngineError::Manager(
+        _ => Err(EngineError::Db(
+            DbErrorKind::Setup,
             "DynamoDB connector is not setup correctly".to_owned(),
         )),
     }
@@ -157,6 +176,13 @@ pub struct Conversation {
     pub flow_id: String,
     pub step_id: String,
     pub status: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    // the conversations saved before the versions have the version 0
+    #[serde(default)]
+    pub version: i32,
     pub last_interaction_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<i64>,
@@ -202,6 +228,9 @@ impl Conversation {
             flow_id: flow_id.to_owned(),
             step_id: step_id.to_owned(),
             status: status.to_owned(),
+            tags: vec![],
+            summary: None,
+            version: 0,
             last_interaction_at: now.to_owned(),
             expires_at,
             updated_at: now.to_owned(),
@@ -233,6 +262,9 @@ pub struct Memory {
     pub user_id: Option<String>,
     pub key: String,
     pub value: Option<String>,
+    // blind index of the words of the encrypted value, see encrypt.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_terms: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<i64>,
     pub created_at: String,
@@ -274,6 +306,7 @@ impl Memory {
             user_id: Some(client.user_id.to_owned()),
             key: key.to_owned(),
             value: encrypted_value.clone(),
+            search_terms: None,
             expires_at,
             created_at: now.to_owned(),
         }
@@ -313,6 +346,18 @@ pub struct Message {
     pub direction: String,
     pub payload: String,
     pub content_type: String,
+    // id of the previous message referenced by an edited message or a reaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_id: Option<String>,
+    // id of the request that saved the message, see RunContext
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    // version of the bot that handled the message, the one picked by a rollout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot_version_id: Option<String>,
+    // blind index of the words of the encrypted payload, see encrypt.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_terms: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<i64>,
     pub created_at: String,
@@ -370,13 +415,33 @@ impl Message {
         make_hash(client)
     }
 
-    pub fn get_range(conversation_id: &str, id: &str) -> String {
-        make_range(&["message", conversation_id, id])
+    /**
+     * The orders are zero padded so that the messages of a conversation
+     * are sorted chronologically by their range key
+     */
+    pub fn get_range(
+        conversation_id: &str,
+        created_at: &str,
+        interaction_order: i32,
+        message_order: i32,
+        id: &str,
+    ) -> String {
+        make_range(&[
+            &Self::get_conversation_range_prefix(conversation_id),
+            created_at,
+            &format!("{:06}", interaction_order),
+            &format!("{:06}", message_order),
+            id,
+        ])
+    }
+
+    pub fn get_conversation_range_prefix(conversation_id: &str) -> String {
+        make_range(&["message", conversation_id])
     }
 
     /**
      * hash = bot_id:xxxx#channel_id:xxxx#user_id:xxxx
-     * range = message#conversation_id#id
+     * range = message#conversation_id#timestamp#interaction_order#message_order#id
      * range_time = message#timestamp#interaction_order#message_order#id
      */
     pub fn new(
@@ -396,7 +461,13 @@ impl Message {
         let now = get_date_time();
         Self {
             hash: Self::get_hash(&client),
-            range: Self::get_range(&conversation_id, &id),
+            range: Self::get_range(
+                &conversation_id,
+                &now,
+                interaction_order,
+                message_order,
+                &id,
+            ),
             range_time: make_range(&[
                 class_name,
                 &now,
@@ -418,6 +489,10 @@ impl Message {
             direction: direction.to_owned(),
             payload: payload.to_owned(),
             content_type: content_type.to_owned(),
+            reference_id: None,
+            request_id: None,
+            bot_version_id: None,
+            search_terms: None,
             expires_at,
             created_at: now.to_owned(),
         }
@@ -491,6 +566,159 @@ impl State {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScheduledEvent {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub id: String,
+    pub client: Client,
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+    pub event: String,
+    pub run_at: String,
+    pub created_at: String,
+}
+
+impl ScheduledEvent {
+    pub fn get_hash() -> String {
+        "scheduled_event".to_owned()
+    }
+
+    pub fn get_range(run_at: &str, id: &str) -> String {
+        make_range(&["run_at", run_at, "id", id])
+    }
+
+    /**
+     * All the scheduled events are stored in the same partition to be able to query the due ones.
+     * hash = scheduled_event
+     * range = run_at#xxxx#id#xxxx
+     */
+    pub fn new(client: &Client, id: &str, encrypted_event: &str, run_at: &str) -> Self {
+        let class_name = "scheduled_event";
+        let now = get_date_time();
+        Self {
+            hash: Self::get_hash(),
+            range: Self::get_range(run_at, id),
+            class: class_name.to_string(),
+            id: id.to_owned(),
+            client: client.to_owned(),
+            bot_id: client.bot_id.to_owned(),
+            channel_id: client.channel_id.to_owned(),
+            user_id: client.user_id.to_owned(),
+            event: encrypted_event.to_owned(),
+            run_at: run_at.to_owned(),
+            created_at: now,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OutboxMessage {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub id: String,
+    pub client: Client,
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+    pub callback_url: String,
+    pub payload: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+impl OutboxMessage {
+    pub fn get_hash() -> String {
+        "outbox".to_owned()
+    }
+
+    pub fn get_range(created_at: &str, id: &str) -> String {
+        make_range(&["created_at", created_at, "id", id])
+    }
+
+    /**
+     * All the outbox messages are stored in the same partition to query the pending ones.
+     * hash = outbox
+     * range = created_at#xxxx#id#xxxx
+     */
+    pub fn new(
+        client: &Client,
+        id: &str,
+        callback_url: &str,
+        encrypted_payload: &str,
+        created_at: &str,
+    ) -> Self {
+        Self {
+            hash: Self::get_hash(),
+            range: Self::get_range(created_at, id),
+            class: "outbox".to_owned(),
+            id: id.to_owned(),
+            client: client.to_owned(),
+            bot_id: client.bot_id.to_owned(),
+            channel_id: client.channel_id.to_owned(),
+            user_id: client.user_id.to_owned(),
+            callback_url: callback_url.to_owned(),
+            payload: encrypted_payload.to_owned(),
+            status: "PENDING".to_owned(),
+            created_at: created_at.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConversationError {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub id: String,
+    pub client: Client,
+    pub conversation_id: String,
+    pub flow_id: String,
+    pub step_id: String,
+    pub payload: String,
+    pub created_at: String,
+    pub expires_at: Option<i64>,
+}
+
+impl ConversationError {
+    pub fn get_hash(bot_id: &str) -> String {
+        format!("error#{}", bot_id)
+    }
+
+    pub fn get_range(created_at: &str, id: &str) -> String {
+        make_range(&["created_at", created_at, "id", id])
+    }
+
+    /**
+     * The errors of a bot are stored in the same partition to query them by date.
+     * hash = error#bot_id
+     * range = created_at#xxxx#id#xxxx
+     */
+    pub fn new(
+        error: &DbConversationError,
+        encrypted_payload: &str,
+        created_at: &str,
+        expires_at: Option<i64>,
+    ) -> Self {
+        Self {
+            hash: Self::get_hash(&error.client.bot_id),
+            range: Self::get_range(created_at, &error.id),
+            class: "error".to_owned(),
+            id: error.id.to_owned(),
+            client: error.client.to_owned(),
+            conversation_id: error.conversation_id.to_owned(),
+            flow_id: error.flow_id.to_owned(),
+            step_id: error.step_id.to_owned(),
+            payload: encrypted_payload.to_owned(),
+            created_at: created_at.to_owned(),
+            expires_at,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Class {
     pub class: String,
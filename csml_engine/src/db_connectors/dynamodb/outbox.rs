@@ -0,0 +1,309 @@
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::db_connectors::{
+    dynamodb::{DynamoDbKey, OutboxMessage},
+    DbOutboxMessage,
+};
+use crate::{
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError,
+};
+use chrono::{DateTime, Utc};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+fn format_created_at(created_at: DateTime<Utc>) -> String {
+    created_at.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string()
+}
+
+fn get_attribute_value(value: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(value.to_owned()),
+        ..Default::default()
+    }
+}
+
+pub fn create_outbox_message(
+    outbox_message: &DbOutboxMessage,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let outbox_message = OutboxMessage::new(
+        &outbox_message.client,
+        &outbox_message.id,
+        &outbox_message.callback_url,
+        &encrypt_data(&outbox_message.payload)?,
+        &outbox_message.created_at,
+    );
+
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&outbox_message)?,
+        table_name: get_table_name()?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+fn query_outbox_messages(
+    key_condition_expression: &str,
+    filter_expression: Option<String>,
+    mut expr_attr_values: HashMap<String, AttributeValue>,
+    limit: i64,
+    pagination_key: Option<HashMap<String, AttributeValue>>,
+    db: &mut DynamoDbClient,
+) -> Result<QueryOutput, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    // status is a reserved word of dynamodb
+    if matches!(&filter_expression, Some(filter) if filter.contains("#status")) {
+        expr_attr_names.insert(String::from("#status"), String::from("status"));
+    }
+
+    expr_attr_values.insert(
+        String::from(":hashVal"),
+        get_attribute_value(&OutboxMessage::get_hash()),
+    );
+
+    let input = QueryInput {
+        table_name: get_table_name()?,
+        key_condition_expression: Some(key_condition_expression.to_owned()),
+        filter_expression,
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        limit: Some(limit),
+        exclusive_start_key: pagination_key,
+        scan_index_forward: Some(true),
+        ..Default::default()
+    };
+
+    let future = db.client.query(input);
+    let data = match db.runtime.block_on(future) {
+        Ok(data) => data,
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_outbox_messages {:?}", e),
+            ))
+        }
+    };
+
+    Ok(data)
+}
+
+pub fn get_pending_outbox_messages(
+    before: DateTime<Utc>,
+    limit: i64,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let range_max = make_range(&["created_at", &format_created_at(before)]);
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (String::from(":rangeMax"), get_attribute_value(&range_max)),
+        (String::from(":pendingVal"), get_attribute_value("PENDING")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut outbox_messages = vec![];
+    let mut pagination_key = None;
+    loop {
+        let data = query_outbox_messages(
+            "#hashKey = :hashVal AND #rangeKey < :rangeMax",
+            Some("#status = :pendingVal".to_owned()),
+            expr_attr_values.clone(),
+            limit,
+            pagination_key,
+            db,
+        )?;
+
+        for item in data.items.unwrap_or_default() {
+            let outbox_message: OutboxMessage = serde_dynamodb::from_hashmap(item)?;
+
+            outbox_messages.push(DbOutboxMessage {
+                id: outbox_message.id,
+                client: outbox_message.client,
+                callback_url: outbox_message.callback_url,
+                payload: decrypt_data(outbox_message.payload)?,
+                created_at: outbox_message.created_at,
+            });
+        }
+
+        // the filter is applied after the limit: read the next page until enough messages are found
+        pagination_key = data.last_evaluated_key;
+        if outbox_messages.len() as i64 >= limit || pagination_key.is_none() {
+            outbox_messages.truncate(limit as usize);
+            return Ok(outbox_messages);
+        }
+    }
+}
+
+pub fn set_outbox_message_sent(
+    outbox_message: &DbOutboxMessage,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let item_key = DynamoDbKey {
+        hash: OutboxMessage::get_hash(),
+        range: OutboxMessage::get_range(&outbox_message.created_at, &outbox_message.id),
+    };
+
+    let expr_attr_values = [
+        (String::from(":pendingVal"), get_attribute_value("PENDING")),
+        (String::from(":sentVal"), get_attribute_value("SENT")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&item_key)?,
+        condition_expression: Some("#status = :pendingVal".to_owned()),
+        update_expression: Some("SET #status = :sentVal".to_owned()),
+        expression_attribute_names: Some(
+            [("#status".to_string(), "status".to_string())]
+                .iter()
+                .cloned()
+                .collect(),
+        ),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(false),
+        Err(e) => Err(EngineError::Db(
+            DbErrorKind::Query,
+            format!("set_outbox_message_sent {:?}", e),
+        )),
+    }
+}
+
+fn delete_outbox_items(
+    key_condition_expression: &str,
+    filter_expression: Option<&str>,
+    expr_attr_values: HashMap<String, AttributeValue>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let mut pagination_key = None;
+
+    loop {
+        // 25 is the Maximum operations in a single request for BatchWriteItemInput
+        let data = query_outbox_messages(
+            key_condition_expression,
+            filter_expression.map(|filter| filter.to_owned()),
+            expr_attr_values.clone(),
+            25,
+            pagination_key,
+            db,
+        )?;
+
+        let items = data.items.unwrap_or_default();
+
+        if !items.is_empty() {
+            let mut write_requests = vec![];
+            for item in items {
+                let outbox_message: OutboxMessage = serde_dynamodb::from_hashmap(item)?;
+
+                let key = serde_dynamodb::to_hashmap(&DynamoDbKey {
+                    hash: outbox_message.hash,
+                    range: outbox_message.range,
+                })?;
+
+                write_requests.push(WriteRequest {
+                    delete_request: Some(DeleteRequest { key }),
+                    put_request: None,
+                });
+            }
+
+            let request_items = [(get_table_name()?, write_requests)]
+                .iter()
+                .cloned()
+                .collect();
+
+            let input = BatchWriteItemInput {
+                request_items,
+                ..Default::default()
+            };
+
+            execute_batch_write_query(db, input)?;
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(());
+        }
+    }
+}
+
+pub fn delete_outbox_messages(
+    before: DateTime<Utc>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let range_max = make_range(&["created_at", &format_created_at(before)]);
+
+    let expr_attr_values = [(String::from(":rangeMax"), get_attribute_value(&range_max))]
+        .iter()
+        .cloned()
+        .collect();
+
+    delete_outbox_items(
+        "#hashKey = :hashVal AND #rangeKey < :rangeMax",
+        None,
+        expr_attr_values,
+        db,
+    )
+}
+
+pub fn delete_client_outbox_messages(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_values = [
+        (String::from(":botId"), get_attribute_value(&client.bot_id)),
+        (
+            String::from(":channelId"),
+            get_attribute_value(&client.channel_id),
+        ),
+        (
+            String::from(":userId"),
+            get_attribute_value(&client.user_id),
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    delete_outbox_items(
+        "#hashKey = :hashVal",
+        Some("bot_id = :botId AND channel_id = :channelId AND user_id = :userId"),
+        expr_attr_values,
+        db,
+    )
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    let expr_attr_values = [(String::from(":botId"), get_attribute_value(bot_id))]
+        .iter()
+        .cloned()
+        .collect();
+
+    delete_outbox_items(
+        "#hashKey = :hashVal",
+        Some("bot_id = :botId"),
+        expr_attr_values,
+        db,
+    )
+}
@@ -0,0 +1,243 @@
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::db_connectors::{
+    dynamodb::{DynamoDbKey, ScheduledEvent},
+    DbScheduledEvent,
+};
+use crate::{
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError,
+};
+use chrono::{DateTime, Utc};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+fn format_run_at(run_at: DateTime<Utc>) -> String {
+    run_at.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string()
+}
+
+fn get_attribute_value(value: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(value.to_owned()),
+        ..Default::default()
+    }
+}
+
+pub fn create_scheduled_event(
+    client: &Client,
+    id: &str,
+    event: &serde_json::Value,
+    run_at: DateTime<Utc>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let scheduled_event =
+        ScheduledEvent::new(client, id, &encrypt_data(event)?, &format_run_at(run_at));
+
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&scheduled_event)?,
+        table_name: get_table_name()?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+fn query_scheduled_events(
+    key_condition_expression: &str,
+    filter_expression: Option<String>,
+    mut expr_attr_values: HashMap<String, AttributeValue>,
+    limit: i64,
+    pagination_key: Option<HashMap<String, AttributeValue>>,
+    db: &mut DynamoDbClient,
+) -> Result<QueryOutput, EngineError> {
+    let expr_attr_names = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    expr_attr_values.insert(
+        String::from(":hashVal"),
+        get_attribute_value(&ScheduledEvent::get_hash()),
+    );
+
+    let input = QueryInput {
+        table_name: get_table_name()?,
+        key_condition_expression: Some(key_condition_expression.to_owned()),
+        filter_expression,
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        limit: Some(limit),
+        exclusive_start_key: pagination_key,
+        scan_index_forward: Some(true),
+        ..Default::default()
+    };
+
+    let future = db.client.query(input);
+    let data = match db.runtime.block_on(future) {
+        Ok(data) => data,
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("query_scheduled_events {:?}", e),
+            ))
+        }
+    };
+
+    Ok(data)
+}
+
+pub fn get_due_scheduled_events(
+    now: DateTime<Utc>,
+    limit: i64,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbScheduledEvent>, EngineError> {
+    // every event scheduled at `now` is before this range ('~' is sorted after the "id" key)
+    let range_max = make_range(&["run_at", &format_run_at(now), "~"]);
+
+    let expr_attr_values = [(String::from(":rangeMax"), get_attribute_value(&range_max))]
+        .iter()
+        .cloned()
+        .collect();
+
+    let data = query_scheduled_events(
+        "#hashKey = :hashVal AND #rangeKey < :rangeMax",
+        None,
+        expr_attr_values,
+        limit,
+        None,
+        db,
+    )?;
+
+    let mut scheduled_events = vec![];
+    for item in data.items.unwrap_or_default() {
+        let scheduled_event: ScheduledEvent = serde_dynamodb::from_hashmap(item)?;
+
+        scheduled_events.push(DbScheduledEvent {
+            id: scheduled_event.id,
+            client: scheduled_event.client,
+            event: decrypt_data(scheduled_event.event)?,
+            run_at: scheduled_event.run_at,
+            created_at: scheduled_event.created_at,
+        });
+    }
+
+    Ok(scheduled_events)
+}
+
+pub fn delete_scheduled_event(
+    scheduled_event: &DbScheduledEvent,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let item_key = DynamoDbKey {
+        hash: ScheduledEvent::get_hash(),
+        range: ScheduledEvent::get_range(&scheduled_event.run_at, &scheduled_event.id),
+    };
+
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&item_key)?,
+        return_values: Some("ALL_OLD".to_owned()),
+        ..Default::default()
+    };
+
+    let future = db.client.delete_item(input);
+    let output = db.runtime.block_on(future)?;
+
+    Ok(output.attributes.is_some())
+}
+
+fn delete_scheduled_events(
+    filter_expression: &str,
+    expr_attr_values: HashMap<String, AttributeValue>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let mut pagination_key = None;
+
+    loop {
+        // 25 is the Maximum operations in a single request for BatchWriteItemInput
+        let data = query_scheduled_events(
+            "#hashKey = :hashVal",
+            Some(filter_expression.to_owned()),
+            expr_attr_values.clone(),
+            25,
+            pagination_key,
+            db,
+        )?;
+
+        let items = data.items.unwrap_or_default();
+
+        if !items.is_empty() {
+            let mut write_requests = vec![];
+            for item in items {
+                let scheduled_event: ScheduledEvent = serde_dynamodb::from_hashmap(item)?;
+
+                let key = serde_dynamodb::to_hashmap(&DynamoDbKey {
+                    hash: scheduled_event.hash,
+                    range: scheduled_event.range,
+                })?;
+
+                write_requests.push(WriteRequest {
+                    delete_request: Some(DeleteRequest { key }),
+                    put_request: None,
+                });
+            }
+
+            let request_items = [(get_table_name()?, write_requests)]
+                .iter()
+                .cloned()
+                .collect();
+
+            let input = BatchWriteItemInput {
+                request_items,
+                ..Default::default()
+            };
+
+            execute_batch_write_query(db, input)?;
+        }
+
+        // the filter is applied after the limit: keep going until the partition is fully read
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(());
+        }
+    }
+}
+
+pub fn delete_client_scheduled_events(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_values = [
+        (String::from(":botId"), get_attribute_value(&client.bot_id)),
+        (
+            String::from(":channelId"),
+            get_attribute_value(&client.channel_id),
+        ),
+        (String::from(":userId"), get_attribute_value(&client.user_id)),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    delete_scheduled_events(
+        "bot_id = :botId AND channel_id = :channelId AND user_id = :userId",
+        expr_attr_values,
+        db,
+    )
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    let expr_attr_values = [(String::from(":botId"), get_attribute_value(bot_id))]
+        .iter()
+        .cloned()
+        .collect();
+
+    delete_scheduled_events("bot_id = :botId", expr_attr_values, db)
+}
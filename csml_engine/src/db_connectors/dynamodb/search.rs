@@ -0,0 +1,140 @@
+/**
+ * DynamoDB has no text search: the items of the table are searched in an OpenSearch index
+ * fed with the items of the table (by a DynamoDB zero-ETL integration or a DynamoDB Streams
+ * pipeline), with the default dynamic mapping of their attributes.
+ *
+ * - SEARCH_OPENSEARCH_URL: url of the OpenSearch (or Elasticsearch) cluster
+ * - SEARCH_OPENSEARCH_INDEX: name of the index, defaults to the name of the table
+ * - SEARCH_OPENSEARCH_AUTH: optional Authorization header of the requests
+ *
+ * The encrypted items are searched with the terms of their blind index (search_terms).
+ */
+use crate::db_connectors::{dynamodb::utils::get_table_name, search::SearchQuery};
+use crate::{config::get_config, encrypt::decrypt_data, EngineError};
+
+use chrono::{NaiveDateTime, SecondsFormat};
+use std::time::Duration;
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::<chrono::Utc>::from_utc(
+        NaiveDateTime::from_timestamp(timestamp, 0),
+        chrono::Utc,
+    )
+    .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn get_search_body(query: &SearchQuery, class: &str, field: &str) -> serde_json::Value {
+    let text_match = match &query.terms {
+        Some(terms) => serde_json::json!({
+            "search_terms": { "query": terms.join(" "), "operator": "and" }
+        }),
+        None => serde_json::json!({ field: { "query": query.text, "operator": "and" } }),
+    };
+    let mut created_at = serde_json::json!({ "gte": format_date(query.from.unwrap_or(0)) });
+    if let Some(to) = query.to {
+        created_at["lte"] = serde_json::json!(format_date(to));
+    }
+
+    serde_json::json!({
+        "size": query.limit,
+        "sort": [{ "created_at": { "order": "desc" } }],
+        "query": {
+            "bool": {
+                "must": [{ "match": text_match }],
+                "filter": [
+                    { "term": { "class.keyword": class } },
+                    { "term": { "bot_id.keyword": query.bot_id } },
+                    { "range": { "created_at": created_at } },
+                ],
+            }
+        }
+    })
+}
+
+/**
+ * Items of the class matching the query, in the _source of the hits
+ */
+fn search_items(
+    query: &SearchQuery,
+    class: &str,
+    field: &str,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let config = get_config();
+    let url = match config.text("search.opensearch_url") {
+        Some(url) => url,
+        _ => {
+            return Err(EngineError::Manager(
+                "the search of a DynamoDB table requires an OpenSearch index of its items, \
+                 set SEARCH_OPENSEARCH_URL"
+                    .to_owned(),
+            ))
+        }
+    };
+    let index = match config.text("search.opensearch_index") {
+        Some(index) => index,
+        None => get_table_name()?,
+    };
+
+    let mut request = ureq::post(&format!("{}/{}/_search", url.trim_end_matches('/'), index))
+        .timeout(Duration::from_secs(30));
+    if let Some(auth) = config.text("search.opensearch_auth") {
+        request = request.set("Authorization", &auth);
+    }
+
+    let response: serde_json::Value = request
+        .send_json(get_search_body(query, class, field))
+        .map_err(|err| EngineError::Manager(format!("OpenSearch search failed: {}", err)))?
+        .into_json()
+        .map_err(|err| EngineError::Manager(format!("OpenSearch search failed: {}", err)))?;
+
+    Ok(response["hits"]["hits"]
+        .as_array()
+        .map(|hits| hits.iter().map(|hit| hit["_source"].to_owned()).collect())
+        .unwrap_or_default())
+}
+
+fn get_client(item: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "bot_id": item["bot_id"],
+        "channel_id": item["channel_id"],
+        "user_id": item["user_id"],
+    })
+}
+
+pub fn search_messages(query: &SearchQuery) -> Result<Vec<serde_json::Value>, EngineError> {
+    search_items(query, "message", "payload")?
+        .into_iter()
+        .filter(|item| item["payload"].is_string())
+        .map(|item| {
+            let payload = item["payload"].as_str().unwrap_or_default().to_owned();
+
+            Ok(serde_json::json!({
+                "client": get_client(&item),
+                "conversation_id": item["conversation_id"],
+                "flow_id": item["flow_id"],
+                "step_id": item["step_id"],
+                "direction": item["direction"],
+                "payload": decrypt_data(payload)?,
+                "created_at": item["created_at"],
+            }))
+        })
+        .collect()
+}
+
+pub fn search_memories(query: &SearchQuery) -> Result<Vec<serde_json::Value>, EngineError> {
+    search_items(query, "memory", "value")?
+        .into_iter()
+        // the memories without a value are deleted
+        .filter(|item| item["value"].is_string())
+        .map(|item| {
+            let value = item["value"].as_str().unwrap_or_default().to_owned();
+
+            Ok(serde_json::json!({
+                "client": get_client(&item),
+                "key": item["key"],
+                "value": decrypt_data(value)?,
+                "created_at": item["created_at"],
+            }))
+        })
+        .collect()
+}
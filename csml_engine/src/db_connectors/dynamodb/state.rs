@@ -1,4 +1,4 @@
-use crate::data::DynamoDbClient;
+use crate::data::{DbErrorKind, DynamoDbClient};
 use crate::db_connectors::dynamodb::{DynamoDbKey, State, StatDeleteInfo};
 use crate::{
     encrypt::{decrypt_data, encrypt_data},
@@ -156,6 +156,120 @@ pub fn set_state_items(
     Ok(())
 }
 
+pub fn replace_state_item(
+    client: &Client,
+    _type: &str,
+    key: &str,
+    value: &serde_json::Value,
+    expires_at: Option<i64>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let mut states = format_state_data(&client, _type, vec![(key, value)], expires_at)?;
+
+    // the item of the same hash and range is replaced
+    let input = PutItemInput {
+        table_name: get_table_name()?,
+        item: serde_dynamodb::to_hashmap(&states.remove(0))?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+pub fn increment_state_counter(
+    client: &Client,
+    _type: &str,
+    key: &str,
+    n: i64,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let item_key = DynamoDbKey {
+        hash: State::get_hash(client),
+        range: State::get_range(_type, key),
+    };
+
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#class"), String::from("class")),
+        (String::from("#type"), String::from("type")),
+        (String::from("#key"), String::from("key")),
+        (String::from("#value"), String::from("value")),
+        (String::from("#createdAt"), String::from("created_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let string_value = |value: &str| AttributeValue {
+        s: Some(value.to_owned()),
+        ..Default::default()
+    };
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (String::from(":classVal"), string_value("state")),
+        (String::from(":typeVal"), string_value(_type)),
+        (String::from(":keyVal"), string_value(key)),
+        (String::from(":createdAtVal"), string_value(&get_date_time())),
+        (
+            String::from(":n"),
+            AttributeValue {
+                n: Some(n.to_string()),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    // the type and key are set for delete_user_state, the counter starts at 0
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&item_key)?,
+        update_expression: Some(
+            "SET #class = :classVal, #type = :typeVal, #key = :keyVal, \
+            #createdAt = if_not_exists(#createdAt, :createdAtVal) ADD #value :n"
+                .to_owned(),
+        ),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+pub fn get_state_counter(
+    client: &Client,
+    _type: &str,
+    key: &str,
+    db: &mut DynamoDbClient,
+) -> Result<i64, EngineError> {
+    let item_key = DynamoDbKey {
+        hash: State::get_hash(client),
+        range: State::get_range(_type, key),
+    };
+
+    let input = GetItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&item_key)?,
+        ..Default::default()
+    };
+
+    let future = db.client.get_item(input);
+    let res = db.runtime.block_on(future)?;
+
+    let value = res
+        .item
+        .and_then(|item| item.get("value").and_then(|value| value.n.to_owned()));
+
+    Ok(value.and_then(|value| value.parse().ok()).unwrap_or(0))
+}
+
 fn query_states(
     client: &Client,
     db: &mut DynamoDbClient,
@@ -204,7 +318,7 @@ fn query_states(
     let data = match db.runtime.block_on(future) {
         Ok(data) => data,
         Err(e) => {
-            return Err(EngineError::Manager(format!("query_states {:?}", e)))
+            return Err(EngineError::Db(DbErrorKind::Query, format!("query_states {:?}", e)))
         }
     };
 
@@ -0,0 +1,170 @@
+use crate::data::{DbErrorKind, DynamoDbClient};
+use crate::EngineError;
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::*;
+use std::{thread, time::Duration};
+
+use crate::db_connectors::dynamodb::utils::get_table_name;
+
+// the table is created on demand: no capacity to provision
+const BILLING_MODE: &str = "PAY_PER_REQUEST";
+// the items with an expires_at timestamp (see get_expires_at_for_dynamodb) are removed by the TTL
+const TTL_ATTRIBUTE: &str = "expires_at";
+const ACTIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn key(attribute_name: &str, key_type: &str) -> KeySchemaElement {
+    KeySchemaElement {
+        attribute_name: attribute_name.to_owned(),
+        key_type: key_type.to_owned(),
+    }
+}
+
+/**
+ * Global secondary indexes queried by the connector:
+ * - TimeIndex: the items of a client sorted by date (bots, conversations, messages)
+ * - ClassByClientIndex: the items of a class (bot, conversation...) by client
+ * - CreatedIndex: the items of a class by creation date (messages)
+ */
+fn index(index_name: &str, hash: &str, range: &str) -> GlobalSecondaryIndex {
+    GlobalSecondaryIndex {
+        index_name: index_name.to_owned(),
+        key_schema: vec![key(hash, "HASH"), key(range, "RANGE")],
+        projection: Projection {
+            projection_type: Some("ALL".to_owned()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn get_table_definition(table_name: &str) -> CreateTableInput {
+    let attributes = ["hash", "range", "range_time", "class", "created_at"];
+
+    CreateTableInput {
+        table_name: table_name.to_owned(),
+        billing_mode: Some(BILLING_MODE.to_owned()),
+        attribute_definitions: attributes
+            .iter()
+            .map(|attribute_name| AttributeDefinition {
+                attribute_name: attribute_name.to_string(),
+                attribute_type: "S".to_owned(),
+            })
+            .collect(),
+        key_schema: vec![key("hash", "HASH"), key("range", "RANGE")],
+        global_secondary_indexes: Some(vec![
+            index("TimeIndex", "hash", "range_time"),
+            index("ClassByClientIndex", "class", "hash"),
+            index("CreatedIndex", "class", "created_at"),
+        ]),
+        ..Default::default()
+    }
+}
+
+/**
+ * Return the status of the table, or None if it does not exist
+ */
+fn get_table_status(
+    db: &mut DynamoDbClient,
+    table_name: &str,
+) -> Result<Option<String>, EngineError> {
+    let input = DescribeTableInput {
+        table_name: table_name.to_owned(),
+    };
+
+    let future = db.client.describe_table(input);
+    match db.runtime.block_on(future) {
+        Ok(output) => Ok(Some(
+            output
+                .table
+                .and_then(|table| table.table_status)
+                .unwrap_or_default(),
+        )),
+        Err(RusotoError::Service(DescribeTableError::ResourceNotFound(_))) => Ok(None),
+        Err(e) => Err(EngineError::Db(DbErrorKind::Query, format!("describe_table {:?}", e))),
+    }
+}
+
+fn wait_until_active(db: &mut DynamoDbClient, table_name: &str) -> Result<(), EngineError> {
+    let start = std::time::Instant::now();
+
+    while start.elapsed() < ACTIVE_TIMEOUT {
+        if let Some("ACTIVE") = get_table_status(db, table_name)?.as_deref() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    Err(EngineError::Manager(format!(
+        "table {} is not active after {}s",
+        table_name,
+        ACTIVE_TIMEOUT.as_secs()
+    )))
+}
+
+fn enable_ttl(db: &mut DynamoDbClient, table_name: &str) -> Result<(), EngineError> {
+    let input = DescribeTimeToLiveInput {
+        table_name: table_name.to_owned(),
+    };
+
+    let future = db.client.describe_time_to_live(input);
+    let description = match db.runtime.block_on(future) {
+        Ok(output) => output.time_to_live_description.unwrap_or_default(),
+        Err(e) => {
+            return Err(EngineError::Db(
+                DbErrorKind::Query,
+                format!("describe_time_to_live {:?}", e),
+            ))
+        }
+    };
+
+    match description.time_to_live_status.as_deref() {
+        Some("ENABLED") | Some("ENABLING") => return Ok(()),
+        _ => (),
+    };
+
+    let input = UpdateTimeToLiveInput {
+        table_name: table_name.to_owned(),
+        time_to_live_specification: TimeToLiveSpecification {
+            attribute_name: TTL_ATTRIBUTE.to_owned(),
+            enabled: true,
+        },
+    };
+
+    let future = db.client.update_time_to_live(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(EngineError::Db(DbErrorKind::Query, format!("update_time_to_live {:?}", e))),
+    }
+}
+
+/**
+ * Create the table of the engine (AWS_DYNAMODB_TABLE) with its key schema and indexes
+ * if it does not exist, then enable the TTL on the expires_at attribute.
+ * Return false if the table already existed.
+ */
+pub fn create_table(db: &mut DynamoDbClient) -> Result<bool, EngineError> {
+    let table_name = get_table_name()?;
+
+    let created = match get_table_status(db, &table_name)? {
+        Some(_) => false,
+        None => {
+            let future = db.client.create_table(get_table_definition(&table_name));
+            match db.runtime.block_on(future) {
+                Ok(_) => true,
+                // created by another server in the meantime
+                Err(RusotoError::Service(CreateTableError::ResourceInUse(_))) => false,
+                Err(e) => {
+                    return Err(EngineError::Db(
+                        DbErrorKind::Query,
+                        format!("create_table {:?}", e),
+                    ))
+                }
+            }
+        }
+    };
+
+    wait_until_active(db, &table_name)?;
+    enable_ttl(db, &table_name)?;
+
+    Ok(created)
+}
@@ -1,5 +1,6 @@
 use crate::db_connectors::dynamodb::{Bot, Conversation, Memory, Message};
 use crate::{
+    config::get_config,
     data::{DynamoBot, DynamoBotBincode, DynamoDbClient},
     encrypt::decrypt_data,
     Client, EngineError,
@@ -35,8 +36,8 @@ pub fn get_date_time() -> String {
  * Return the table's name
  */
 pub fn get_table_name() -> Result<String, EngineError> {
-    match std::env::var("AWS_DYNAMODB_TABLE") {
-        Ok(val) => return Ok(val),
+    match get_config().text("database.dynamodb.table") {
+        Some(val) => return Ok(val),
         _ => {
             return Err(EngineError::Manager(
                 "Missing AWS_DYNAMODB_TABLE env var".to_owned(),
@@ -70,11 +71,13 @@ pub fn make_range(args: &[&str]) -> String {
 }
 
 /**
- * Batch write query wrapper with exponential backoff in case of exceeded throughput
+ * Batch write query wrapper with exponential backoff in case of exceeded throughput.
+ * DynamoDB can accept a batch partially: the unprocessed items are sent again
+ * with the same backoff until they are all written.
  */
 pub fn execute_batch_write_query(
     db: &mut DynamoDbClient,
-    input: BatchWriteItemInput,
+    mut input: BatchWriteItemInput,
 ) -> Result<(), RusotoError<BatchWriteItemError>> {
     let mut retry_times = 1;
 
@@ -85,7 +88,25 @@ pub fn execute_batch_write_query(
             .runtime
             .block_on(db.client.batch_write_item(input.clone()))
         {
-            Ok(_) => return Ok(()),
+            Ok(output) => match output.unprocessed_items {
+                Some(unprocessed_items) if !unprocessed_items.is_empty() => {
+                    input.request_items = unprocessed_items;
+
+                    let interval = std::cmp::min(MAX_INTERVAL_LIMIT, RETRY_BASE * 2 * retry_times);
+                    let interval_jitter = rng.gen_range(0..interval);
+                    thread::sleep(time::Duration::from_millis(interval_jitter));
+
+                    if now.elapsed() >= time::Duration::from_millis(MAX_ELAPSED_TIME_MILLIS) {
+                        // the items could not be written before MAX_ELAPSED_TIME_MILLIS
+                        return Err(RusotoError::Service(
+                            BatchWriteItemError::ProvisionedThroughputExceeded(
+                                "unprocessed items remaining after retries".to_owned(),
+                            ),
+                        ));
+                    }
+                }
+                _ => return Ok(()),
+            },
             // request rate is too high, reduce the frequency of requests and use exponential backoff. "https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Programming.Errors.html#Programming.Errors.RetryAndBackoff"
             Err(RusotoError::Service(BatchWriteItemError::ProvisionedThroughputExceeded(err))) => {
                 let interval = std::cmp::min(MAX_INTERVAL_LIMIT, RETRY_BASE * 2 * retry_times);
@@ -119,7 +140,10 @@ pub fn execute_bot_version_batch_get_query(
     let mut rng = rand::thread_rng();
     let now = time::Instant::now();
     loop {
-        match db.runtime.block_on(db.client.batch_get_item(input.clone())) {
+        match db
+            .runtime
+            .block_on(db.reader().batch_get_item(input.clone()))
+        {
             Ok(output) => {
                 let items = match output.responses {
                     None => return Ok(vec![]),
@@ -195,7 +219,10 @@ pub fn execute_messages_batch_get_query(
     let mut rng = rand::thread_rng();
     let now = time::Instant::now();
     loop {
-        match db.runtime.block_on(db.client.batch_get_item(input.clone())) {
+        match db
+            .runtime
+            .block_on(db.reader().batch_get_item(input.clone()))
+        {
             Ok(output) => {
                 let items = match output.responses {
                     None => return Ok(vec![]),
@@ -217,6 +244,9 @@ pub fn execute_messages_batch_get_query(
                             "interaction_order": message.interaction_order,
                             "direction": message.direction,
                             "payload": decrypt_data(message.payload)?,
+                            "reference_id": message.reference_id,
+                            "request_id": message.request_id,
+                            "bot_version_id": message.bot_version_id,
                             "created_at": message.created_at
                         });
 
@@ -0,0 +1,220 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use crate::db_connectors::{utils::*, DbConversationError};
+use crate::data::DbErrorKind;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Database, EngineError};
+use chrono::{DateTime, Utc};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+/**
+ * Save the error that ended a conversation, it is removed after `ttl`
+ */
+pub fn create_conversation_error(
+    client: &Client,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
+    error: &serde_json::Value,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<DbConversationError, EngineError> {
+    let client = &get_storage_client(client);
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            Some(flow_id.to_owned()),
+            None,
+            format!("db call create conversation error, step_id: {:?}", step_id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            Some(flow_id.to_owned()),
+            None,
+            format!(
+                "db call create conversation error, step_id: {:?}, error: {:?}",
+                step_id, error
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    let created_at = Utc::now();
+    let conversation_error = DbConversationError {
+        id: uuid::Uuid::new_v4().to_string(),
+        client: client.to_owned(),
+        conversation_id: conversation_id.to_owned(),
+        flow_id: flow_id.to_owned(),
+        step_id: step_id.to_owned(),
+        error: error.to_owned(),
+        created_at: created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+    };
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        let expires_at = get_expires_at_for_mongodb(ttl);
+
+        mongodb_connector::errors::create_conversation_error(
+            &conversation_error,
+            created_at,
+            expires_at,
+            db,
+        )?;
+        return Ok(conversation_error);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        let expires_at = get_expires_at_for_dynamodb(ttl);
+
+        dynamodb_connector::errors::create_conversation_error(
+            &conversation_error,
+            created_at,
+            expires_at,
+            db,
+        )?;
+        return Ok(conversation_error);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        let expires_at = get_expires_at_for_postgresql(ttl);
+
+        postgresql_connector::errors::create_conversation_error(
+            &conversation_error,
+            created_at,
+            expires_at,
+            db,
+        )?;
+        return Ok(conversation_error);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        let expires_at = get_expires_at_for_sqlite(ttl);
+
+        sqlite_connector::errors::create_conversation_error(
+            &conversation_error,
+            created_at,
+            expires_at,
+            db,
+        )?;
+        return Ok(conversation_error);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Return at most `limit` errors of a bot saved before `before`, newest first
+ */
+pub fn get_bot_errors(
+    bot_id: &str,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+    db: &mut Database,
+) -> Result<Vec<DbConversationError>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call get bot errors, bot_id: {:?}, before: {:?}",
+                bot_id, before
+            ),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::errors::get_bot_errors(bot_id, limit, before, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::errors::get_bot_errors(bot_id, limit, before, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::errors::get_bot_errors(bot_id, limit, before, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::errors::get_bot_errors(bot_id, limit, before, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{init_db, user};
+
+    #[test]
+    fn ok_conversation_errors() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let client = Client {
+            bot_id: "bot_errors".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "user_id".to_owned(),
+            tenant_id: None,
+        };
+        user::delete_client(&client, &mut db).unwrap();
+
+        let first = serde_json::json!({"message": "first error"});
+        let second = serde_json::json!({"message": "second error"});
+        let mut saved = vec![];
+        for (step_id, error) in [("start", &first), ("end", &second)].iter() {
+            saved.push(
+                create_conversation_error(&client, "id", "Default", step_id, error, None, &mut db)
+                    .unwrap(),
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let errors = get_bot_errors(&client.bot_id, 10, None, &mut db).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].id, saved[1].id);
+        assert_eq!(errors[0].step_id, "end");
+        assert_eq!(errors[0].error, second);
+        assert_eq!(errors[1].error, first);
+
+        let before = DateTime::parse_from_rfc3339(&errors[0].created_at)
+            .unwrap()
+            .with_timezone(&Utc);
+        let older = get_bot_errors(&client.bot_id, 10, Some(before), &mut db).unwrap();
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].step_id, "start");
+
+        user::delete_client(&client, &mut db).unwrap();
+        assert!(get_bot_errors(&client.bot_id, 10, None, &mut db)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use crate::data::DbErrorKind;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Database, EngineError};
+use chrono::{DateTime, Utc};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+/**
+ * Lock the job `name` for `owner` until `locked_until` if it is not locked (or if its lock
+ * expired before `now`). Only one of the servers sharing the database gets the lock.
+ */
+pub fn claim_job(
+    name: &str,
+    owner: &str,
+    now: DateTime<Utc>,
+    locked_until: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call claim job: {:?}, owner: {:?}", name, owner),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::jobs::claim_job(name, owner, now, locked_until, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::jobs::claim_job(name, owner, now, locked_until, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::jobs::claim_job(name, owner, now, locked_until, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::jobs::claim_job(name, owner, now, locked_until, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Save the result of a run of the job `name` and keep it locked until its next run.
+ * Nothing is updated if the lock is now owned by another server.
+ */
+pub fn complete_job(
+    name: &str,
+    owner: &str,
+    next_run_at: DateTime<Utc>,
+    error: Option<&str>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call complete job: {:?}, owner: {:?}", name, owner),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::jobs::complete_job(name, owner, next_run_at, error, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::jobs::complete_job(name, owner, next_run_at, error, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::jobs::complete_job(name, owner, next_run_at, error, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::jobs::complete_job(name, owner, next_run_at, error, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_jobs() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let name = format!("test_job_{}", uuid::Uuid::new_v4());
+
+        let now = Utc::now();
+        let lease = now + chrono::Duration::minutes(10);
+
+        // only one server gets the lock
+        assert!(claim_job(&name, "server_a", now, lease, &mut db).unwrap());
+        assert!(!claim_job(&name, "server_b", now, lease, &mut db).unwrap());
+
+        // the job stays locked until its next run
+        complete_job(&name, "server_a", now, Some("error"), &mut db).unwrap();
+        let later = now + chrono::Duration::seconds(1);
+        assert!(claim_job(&name, "server_b", later, lease, &mut db).unwrap());
+
+        // the previous owner can not release the lock of the new one
+        complete_job(&name, "server_a", now, None, &mut db).unwrap();
+        assert!(!claim_job(&name, "server_a", later, lease, &mut db).unwrap());
+
+        // an expired lock can be taken over
+        let expired = lease + chrono::Duration::seconds(1);
+        assert!(claim_job(&name, "server_a", expired, expired, &mut db).unwrap());
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+
+use crate::{Database, EngineError};
+use csml_interpreter::data::{
+    csml_logs::{csml_logger, CsmlLog, LogLvl},
+    Passage,
+};
+
+/**
+ * Passage of a document with the embedding of its text
+ */
+#[derive(Debug, Clone)]
+pub struct KbPassage {
+    pub id: String,
+    pub text: String,
+    pub metadata: serde_json::Value,
+    pub embedding: Vec<f64>,
+}
+
+const ERROR_PGVECTOR: &str = "the pgvector knowledge base requires the postgresql database";
+
+pub fn upsert_passages(
+    _bot_id: &str,
+    document_id: &str,
+    _passages: &[KbPassage],
+    _db: &mut Database,
+) -> Result<(), EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call upsert knowledge base document: {:?}", document_id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(_db)?;
+        return postgresql_connector::knowledge_base::upsert_passages(
+            _bot_id,
+            document_id,
+            _passages,
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_PGVECTOR.to_owned()))
+}
+
+pub fn delete_document(
+    _bot_id: &str,
+    document_id: &str,
+    _db: &mut Database,
+) -> Result<bool, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call delete knowledge base document: {:?}", document_id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(_db)?;
+        return postgresql_connector::knowledge_base::delete_document(_bot_id, document_id, db);
+    }
+
+    Err(EngineError::Manager(ERROR_PGVECTOR.to_owned()))
+}
+
+pub fn search_passages(
+    bot_id: &str,
+    _embedding: &[f64],
+    _top_k: usize,
+    _db: &mut Database,
+) -> Result<Vec<Passage>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call search knowledge base of bot: {:?}", bot_id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(_db)?;
+        return postgresql_connector::knowledge_base::search_passages(bot_id, _embedding, _top_k, db);
+    }
+
+    Err(EngineError::Manager(ERROR_PGVECTOR.to_owned()))
+}
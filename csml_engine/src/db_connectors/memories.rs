@@ -10,15 +10,87 @@ use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 use csml_interpreter::data::csml_logs::{LogLvl, CsmlLog, csml_logger};
 
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
+use crate::telemetry;
+use crate::config::get_config;
 use crate::{Client, ConversationInfo, Database, EngineError, Memory};
-use crate::db_connectors::utils::*;
+use crate::db_connectors::{state, utils::*};
+use chrono::{prelude::Utc, SecondsFormat};
 use std::collections::HashMap;
 
+/**
+ * Number of values kept in the history of each memory: MEMORY_HISTORY_LIMIT (default 10).
+ * The history is disabled with 0.
+ */
+fn get_history_limit() -> usize {
+    get_config()
+        .integer("conversations.memory_history_limit")
+        .map(|limit| limit as usize)
+        .unwrap_or(10)
+}
+
+/**
+ * Every value saved in a memory is kept in the client state (type "memory_history")
+ * with the date and the id of the interaction that saved it, newest first.
+ */
+fn add_memory_history(
+    client: &Client,
+    key: &str,
+    value: &serde_json::Value,
+    interaction_id: Option<&str>,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let limit = get_history_limit();
+    if limit == 0 {
+        return Ok(());
+    }
+
+    let mut history = match get_memory_history(client, key, db)? {
+        serde_json::Value::Array(history) => history,
+        _ => vec![],
+    };
+
+    history.insert(0, serde_json::json!({
+        "value": value,
+        "interaction_id": interaction_id,
+        "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    }));
+    history.truncate(limit);
+
+    state::replace_state_item(
+        client,
+        "memory_history",
+        key,
+        &serde_json::Value::Array(history),
+        ttl,
+        db,
+    )
+}
+
+/**
+ * Get the previous values of a client memory, newest first
+ */
+pub fn get_memory_history(
+    client: &Client,
+    key: &str,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let client = &get_storage_client(client);
+
+    match state::get_state_key(client, "memory_history", key, db)? {
+        Some(history) => Ok(history),
+        None => Ok(serde_json::json!([])),
+    }
+}
+
 pub fn add_memories(
     data: &mut ConversationInfo,
     memories: &HashMap<String, Memory>,
 ) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("add_memories");
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -38,6 +110,21 @@ pub fn add_memories(
         LogLvl::Debug
     );
 
+    for (key, memory) in memories.iter() {
+        check_payload_size(&memory.value, &format!("memory [{}]", key))?;
+    }
+
+    for (key, memory) in memories.iter() {
+        add_memory_history(
+            &data.client,
+            key,
+            &memory.value,
+            Some(&data.request_id),
+            data.ttl,
+            &mut data.db,
+        )?;
+    }
+
     #[cfg(feature = "mongo")]
     if is_mongodb() {
         let expires_at = get_expires_at_for_mongodb(data.ttl);
@@ -62,7 +149,7 @@ pub fn add_memories(
         return sqlite_connector::memories::add_memories(data, &memories, expires_at);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn create_client_memory(
@@ -72,6 +159,7 @@ pub fn create_client_memory(
     ttl: Option<chrono::Duration>,
     db: &mut Database
 ) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
 
     csml_logger(
         CsmlLog::new(
@@ -92,6 +180,8 @@ pub fn create_client_memory(
         LogLvl::Debug
     );
 
+    add_memory_history(client, &key, &value, None, ttl, db)?;
+
     #[cfg(feature = "mongo")]
     if is_mongodb() {
         let db = mongodb_connector::get_db(db)?;
@@ -121,10 +211,14 @@ pub fn create_client_memory(
         return sqlite_connector::memories::create_client_memory(client, &key, &value, expires_at,db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn internal_use_get_memories(client: &Client, db: &mut Database) -> Result<serde_json::Value, EngineError> {
+    let _span = telemetry::db_span("internal_use_get_memories");
+
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -168,13 +262,15 @@ pub fn internal_use_get_memories(client: &Client, db: &mut Database) -> Result<s
         return sqlite_connector::memories::internal_use_get_memories(client, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 /**
  * Get client Memories
  */
  pub fn get_memories(client: &Client, db: &mut Database) -> Result<serde_json::Value, EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -218,13 +314,15 @@ pub fn internal_use_get_memories(client: &Client, db: &mut Database) -> Result<s
         return sqlite_connector::memories::get_memories(client, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 /**
  * Get client Memory
  */
  pub fn get_memory(client: &Client, key: &str, db: &mut Database) -> Result<serde_json::Value, EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -268,11 +366,13 @@ pub fn internal_use_get_memories(client: &Client, db: &mut Database) -> Result<s
         return sqlite_connector::memories::get_memory(client, key, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 
 pub fn delete_client_memory(client: &Client, key: &str, db: &mut Database) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -316,10 +416,12 @@ pub fn delete_client_memory(client: &Client, key: &str, db: &mut Database) -> Re
         return sqlite_connector::memories::delete_client_memory(client, key, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn delete_client_memories(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -363,5 +465,5 @@ pub fn delete_client_memories(client: &Client, db: &mut Database) -> Result<(),
         return sqlite_connector::memories::delete_client_memories(client, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
@@ -8,7 +8,9 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 use crate::db_connectors::utils::*;
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
+use crate::telemetry;
 use crate::{Client, ConversationInfo, Database, EngineError};
 use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
 
@@ -18,13 +20,32 @@ pub fn add_messages_bulk(
     interaction_order: i32,
     direction: &str,
 ) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("add_messages_bulk");
+
+    // bots with no data retention never store the content of their conversations
+    if data.no_data_retention {
+        csml_logger(
+            CsmlLog::new(
+                Some(&data.client),
+                None,
+                None,
+                "no data retention: messages are not saved".to_owned(),
+            )
+            .with_request_id(data.run_context.request_id.as_deref()),
+            LogLvl::Debug,
+        );
+
+        return Ok(());
+    }
+
     csml_logger(
         CsmlLog::new(
             None,
             None,
             None,
             format!("db call save messages {:?}", msgs),
-        ),
+        )
+        .with_request_id(data.run_context.request_id.as_deref()),
         LogLvl::Info,
     );
     csml_logger(
@@ -33,10 +54,21 @@ pub fn add_messages_bulk(
             None,
             None,
             format!("db call save messages {:?}", msgs),
-        ),
+        )
+        .with_request_id(data.run_context.request_id.as_deref()),
         LogLvl::Debug,
     );
 
+    for (message_order, msg) in msgs.iter().enumerate() {
+        check_payload_size(
+            msg,
+            &format!(
+                "message {} of interaction {}",
+                message_order, interaction_order
+            ),
+        )?;
+    }
+
     #[cfg(feature = "mongo")]
     if is_mongodb() {
         let expires_at = get_expires_at_for_mongodb(data.ttl);
@@ -89,7 +121,7 @@ pub fn add_messages_bulk(
         );
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_client_messages(
@@ -100,6 +132,8 @@ pub fn get_client_messages(
     from_date: Option<i64>,
     to_date: Option<i64>,
 ) -> Result<serde_json::Value, EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call get messages")),
         LogLvl::Info,
@@ -178,5 +212,64 @@ pub fn get_client_messages(
         );
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Get all the messages of a conversation in chronological order
+ */
+pub fn get_conversation_messages(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let client = &get_storage_client(client);
+
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call get conversation messages"),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+
+        return mongodb_connector::messages::get_conversation_messages(client, conversation_id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+
+        return dynamodb_connector::messages::get_conversation_messages(
+            client,
+            conversation_id,
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+
+        return postgresql_connector::messages::get_conversation_messages(
+            client,
+            conversation_id,
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+
+        return sqlite_connector::messages::get_conversation_messages(client, conversation_id, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
@@ -31,7 +31,8 @@
  * Each method of each module must be fully reimplemented in order to extend the "generic"
  * implementation at the root of db_connectors directory.
  */
-use crate::data::{Database, EngineError};
+use crate::config::{get_config, Config};
+use crate::data::{Database, DbErrorKind, EngineError};
 use crate::error_messages::ERROR_DB_SETUP;
 use csml_interpreter::data::csml_bot::CsmlBot;
 use serde::{Deserialize, Serialize};
@@ -47,8 +48,15 @@ use self::sqlite as sqlite_connector;
 
 pub mod bot;
 pub mod conversations;
+pub mod diagnostics;
+pub mod errors;
+pub mod jobs;
+pub mod knowledge_base;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod scheduled_events;
+pub mod search;
 pub mod state;
 
 pub mod user;
@@ -81,6 +89,9 @@ pub struct DbConversation {
     pub last_interaction_at: String,
     pub updated_at: String,
     pub created_at: String,
+    // incremented by each update of the conversation, see update_conversation
+    #[serde(default, skip_serializing)]
+    pub version: i32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -110,6 +121,12 @@ pub struct DbMessage {
     pub interaction_order: i32,
     pub direction: String,
     pub payload: serde_json::Value,
+    // id of the previous message referenced by an edited message or a reaction
+    pub reference_id: Option<String>,
+    // id of the request that saved the message, see RunContext
+    pub request_id: Option<String>,
+    // version of the bot that handled the message, the one picked by a rollout
+    pub bot_version_id: Option<String>,
     pub created_at: String,
 }
 
@@ -124,6 +141,36 @@ pub struct DbState {
     pub created_at: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DbScheduledEvent {
+    pub id: String,
+    pub client: Client,
+    pub event: serde_json::Value,
+    pub run_at: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DbOutboxMessage {
+    pub id: String,
+    pub client: Client,
+    pub callback_url: String,
+    pub payload: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DbConversationError {
+    pub id: String,
+    pub client: Client,
+    pub conversation_id: String,
+    pub flow_id: String,
+    pub step_id: String,
+    // message, interval, snippet, stack and last event of the error
+    pub error: serde_json::Value,
+    pub created_at: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DbBot {
     pub id: String,
@@ -157,74 +204,112 @@ impl BotVersion {
     }
 }
 
+// If the setting is not set at all, use mongodb by default
+#[cfg_attr(not(feature = "mongo"), allow(dead_code))]
+fn uses_mongodb(config: &Config) -> bool {
+    match config.text("database.type") {
+        Some(val) => val == "mongodb",
+        None => true,
+    }
+}
+
+#[cfg_attr(
+    not(any(feature = "dynamo", feature = "postgresql", feature = "sqlite")),
+    allow(dead_code)
+)]
+fn uses_db(config: &Config, db_type: &str) -> bool {
+    config.text("database.type").as_deref() == Some(db_type)
+}
+
 #[cfg(feature = "mongo")]
 pub fn is_mongodb() -> bool {
-    // If the env var is not set at all, use mongodb by default
-    match std::env::var("ENGINE_DB_TYPE") {
-        Ok(val) => val == "mongodb".to_owned(),
-        Err(_) => true,
-    }
+    uses_mongodb(&get_config())
 }
 
 #[cfg(feature = "dynamo")]
 pub fn is_dynamodb() -> bool {
-    match std::env::var("ENGINE_DB_TYPE") {
-        Ok(val) => val == "dynamodb".to_owned(),
-        Err(_) => false,
-    }
+    uses_db(&get_config(), "dynamodb")
 }
 
 #[cfg(feature = "postgresql")]
 pub fn is_postgresql() -> bool {
-    match std::env::var("ENGINE_DB_TYPE") {
-        Ok(val) => val == "postgresql".to_owned(),
-        Err(_) => false,
-    }
+    uses_db(&get_config(), "postgresql")
 }
 
 #[cfg(feature = "sqlite")]
 pub fn is_sqlite() -> bool {
-    match std::env::var("ENGINE_DB_TYPE") {
-        Ok(val) => val == "sqlite".to_owned(),
-        Err(_) => false,
-    }
+    uses_db(&get_config(), "sqlite")
 }
 
 pub fn init_db() -> Result<Database, EngineError> {
+    init_db_with(&get_config())
+}
+
+/**
+ * Connection to the database of the settings of an engine
+ */
+pub fn init_db_with(config: &Config) -> Result<Database, EngineError> {
     #[cfg(feature = "mongo")]
-    if is_mongodb() {
-        return mongodb_connector::init();
+    if uses_mongodb(config) {
+        return mongodb_connector::init(config);
     }
 
     #[cfg(feature = "dynamo")]
-    if is_dynamodb() {
-        return dynamodb_connector::init();
+    if uses_db(config, "dynamodb") {
+        return dynamodb_connector::init(config);
     }
 
     #[cfg(feature = "postgresql")]
-    if is_postgresql() {
-        return postgresql_connector::init();
+    if uses_db(config, "postgresql") {
+        return postgresql_connector::init(config);
     }
 
     #[cfg(feature = "sqlite")]
-    if is_sqlite() {
-        return sqlite_connector::init();
+    if uses_db(config, "sqlite") {
+        return sqlite_connector::init(config);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn make_migrations() -> Result<(), EngineError> {
+    make_migrations_with(&get_config())
+}
+
+pub fn make_migrations_with(config: &Config) -> Result<(), EngineError> {
+    #[cfg(feature = "mongo")]
+    if uses_mongodb(config) {
+        return self::mongodb::make_migrations(config);
+    }
 
     #[cfg(feature = "postgresql")]
-    if is_postgresql() {
-        return self::postgresql::make_migrations();
+    if uses_db(config, "postgresql") {
+        return self::postgresql::make_migrations(config);
     }
 
     #[cfg(feature = "sqlite")]
-    if is_sqlite() {
-        return self::sqlite::make_migrations();
+    if uses_db(config, "sqlite") {
+        return self::sqlite::make_migrations(config);
     }
 
+    let _ = config;
     Ok(())
 }
+
+/**
+ * Create the DynamoDB table of the engine if it does not exist (the other databases are
+ * set up by make_migrations). Return false if the table already existed.
+ */
+pub fn create_dynamodb_table() -> Result<bool, EngineError> {
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let mut db = init_db()?;
+        let db = dynamodb_connector::get_db(&mut db)?;
+
+        return dynamodb_connector::table::create_table(db);
+    }
+
+    Err(EngineError::Manager(
+        "the table can only be created for ENGINE_DB_TYPE=dynamodb".to_owned(),
+    ))
+}
@@ -48,7 +48,7 @@ pub fn get_bot_versions(
     pagination_key: Option<String>,
     db: &MongoDbClient,
 ) -> Result<serde_json::Value, EngineError> {
-    let collection = db.client.collection::<Document>("bot");
+    let collection = db.reader().collection::<Document>("bot");
 
     let limit = match limit {
         Some(limit) => std::cmp::min(limit + 1 , 26),
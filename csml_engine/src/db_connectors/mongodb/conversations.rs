@@ -26,6 +26,8 @@ fn format_conversation_struct(
             .unwrap()
             .to_chrono()
             .to_rfc3339_opts(SecondsFormat::Millis, true),
+        // the conversations saved before the versions have the version 0
+        version: conversation.get_i32("version").unwrap_or(0),
     })
 }
 
@@ -44,6 +46,7 @@ pub fn create_conversation(
         "flow_id": flow_id,
         "step_id": step_id,
         "status": "OPEN",
+        "version": 0,
         "last_interaction_at": &time,
         "expires_at": expires_at,
         "updated_at": &time,
@@ -135,15 +138,22 @@ pub fn update_conversation(
     client: &Client,
     flow_id: Option<String>,
     step_id: Option<String>,
+    version: i32,
     db: &MongoDbClient,
-) -> Result<(), EngineError> {
+) -> Result<bool, EngineError> {
     let collection = db.client.collection::<Document>("conversation");
 
+    // the conversations saved before the versions have no version field
+    let version_filter = match version {
+        0 => doc! { "$in": [0, bson::Bson::Null] },
+        version => doc! { "$eq": version },
+    };
     let filter = doc! {
         "_id": bson::oid::ObjectId::parse_str(conversation_id).unwrap(),
         "client.bot_id": client.bot_id.to_owned(),
         "client.user_id": client.user_id.to_owned(),
         "client.channel_id": client.channel_id.to_owned(),
+        "version": version_filter,
     };
 
     let doc = match (flow_id, step_id) {
@@ -160,13 +170,17 @@ pub fn update_conversation(
         (None, None) => doc! {},
     };
 
-    let update = doc! {
-        "$set": doc,
+    let mut update = doc! {
+        "$inc": { "version": 1 },
         "$currentDate": { "last_interaction_at": true }
     };
+    // an empty $set is refused by the older servers
+    if !doc.is_empty() {
+        update.insert("$set", doc);
+    }
 
-    collection.update_one(filter, update, None)?;
-    Ok(())
+    let result = collection.update_one(filter, update, None)?;
+    Ok(result.matched_count == 1)
 }
 
 pub fn delete_user_conversations(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
@@ -188,6 +202,7 @@ pub fn get_client_conversations(
     db: &MongoDbClient,
     limit: Option<i64>,
     pagination_key: Option<String>,
+    tag: Option<&str>,
 ) -> Result<serde_json::Value, EngineError> {
     let collection = db.client.collection::<Document>("conversation");
 
@@ -196,7 +211,7 @@ pub fn get_client_conversations(
         None => 26,
     };
 
-    let filter = match pagination_key {
+    let mut filter = match pagination_key {
         Some(key) => {
             doc! {
                 "client.bot_id": client.bot_id.to_owned(),
@@ -212,6 +227,10 @@ pub fn get_client_conversations(
         },
     };
 
+    if let Some(tag) = tag {
+        filter.insert("tags", tag);
+    }
+
     let find_options = mongodb::options::FindOptions::builder()
         .sort(doc! { "$natural": -1 })
         .batch_size(30)
@@ -223,6 +242,11 @@ pub fn get_client_conversations(
     for doc in cursor {
         match doc {
             Ok(conv) => {
+                let tags: Vec<String> = match conv.get("tags") {
+                    Some(tags) => bson::from_bson(tags.to_owned())?,
+                    None => vec![],
+                };
+                let summary = conv.get_str("summary").ok().map(|summary| summary.to_owned());
                 let conversation = format_conversation_struct(conv)?;
 
                 let json = serde_json::json!({
@@ -230,6 +254,8 @@ pub fn get_client_conversations(
                     "flow_id": conversation.flow_id,
                     "step_id": conversation.step_id,
                     "status": conversation.status,
+                    "tags": tags,
+                    "summary": summary,
                     "last_interaction_at": conversation.last_interaction_at,
                     "updated_at": conversation.updated_at,
                     "created_at": conversation.created_at
@@ -258,3 +284,121 @@ pub fn get_client_conversations(
         false => Ok(serde_json::json!({ "conversations": conversations })),
     }
 }
+
+pub fn add_conversation_tags(
+    id: &str,
+    tags: &[String],
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("conversation");
+
+    let id = match bson::oid::ObjectId::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    let update = doc! {
+        "$addToSet": { "tags": { "$each": tags } }
+    };
+
+    collection.update_one(doc! { "_id": id }, update, None)?;
+    Ok(())
+}
+
+pub fn set_conversation_summary(
+    id: &str,
+    summary: &str,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("conversation");
+
+    let id = match bson::oid::ObjectId::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    let update = doc! {
+        "$set": { "summary": summary }
+    };
+
+    collection.update_one(doc! { "_id": id }, update, None)?;
+    Ok(())
+}
+
+pub fn get_bot_clients(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    db: &MongoDbClient,
+) -> Result<Vec<Client>, EngineError> {
+    let collection = db.client.collection::<Document>("conversation");
+
+    let mut filter = doc! {
+        "client.bot_id": bot_id,
+    };
+    if let Some(channel_id) = channel_id {
+        filter.insert("client.channel_id", channel_id);
+    }
+
+    let clients = collection.distinct("client", filter, None)?;
+
+    clients
+        .into_iter()
+        .map(|client| Ok(bson::from_bson(client)?))
+        .collect()
+}
+
+pub fn get_closed_conversations(
+    before: bson::DateTime,
+    limit: i64,
+    db: &MongoDbClient,
+) -> Result<Vec<DbConversation>, EngineError> {
+    let collection = db.client.collection::<Document>("conversation");
+
+    let filter = doc! {
+        "status": "CLOSED",
+        "updated_at": { "$lt": before },
+    };
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "updated_at": 1 })
+        .limit(limit)
+        .build();
+    let cursor = collection.find(filter, find_options)?;
+
+    let mut conversations = vec![];
+    for doc in cursor {
+        conversations.push(format_conversation_struct(doc?)?);
+    }
+
+    Ok(conversations)
+}
+
+pub fn delete_conversation(id: &str, client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
+    let object_id = match bson::oid::ObjectId::parse_str(id) {
+        Ok(object_id) => object_id,
+        Err(_) => return Ok(()),
+    };
+
+    let messages = db.client.collection::<Document>("message");
+    messages.delete_many(
+        doc! {
+            "client.bot_id": client.bot_id.to_owned(),
+            "client.user_id": client.user_id.to_owned(),
+            "client.channel_id": client.channel_id.to_owned(),
+            "conversation_id": id,
+        },
+        None,
+    )?;
+
+    let conversations = db.client.collection::<Document>("conversation");
+    conversations.delete_one(
+        doc! {
+            "_id": object_id,
+            "client.bot_id": client.bot_id.to_owned(),
+            "client.user_id": client.user_id.to_owned(),
+            "client.channel_id": client.channel_id.to_owned(),
+        },
+        None,
+    )?;
+
+    Ok(())
+}
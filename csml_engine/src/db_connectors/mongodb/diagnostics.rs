@@ -0,0 +1,49 @@
+use crate::{data::*, MongoDbClient};
+use bson::{doc, Document};
+
+const COLLECTIONS: [&str; 7] = [
+    "bot",
+    "conversation",
+    "memory",
+    "message",
+    "state",
+    "scheduled_event",
+    "job",
+];
+
+pub fn check_storage(db: &MongoDbClient) -> Vec<EnvironmentCheck> {
+    let existing = match db.client.list_collection_names(None) {
+        Ok(existing) => existing,
+        Err(err) => {
+            return vec![EnvironmentCheck::new(
+                "collections",
+                CHECK_ERROR,
+                &format!("can not list the collections: {}", err),
+            )]
+        }
+    };
+
+    COLLECTIONS
+        .iter()
+        .map(|collection| {
+            let name = format!("collection {}", collection);
+
+            if !existing.iter().any(|existing| existing == collection) {
+                return EnvironmentCheck::new(
+                    &name,
+                    CHECK_WARNING,
+                    "missing, it is created on the first write",
+                );
+            }
+
+            match db
+                .client
+                .collection::<Document>(collection)
+                .find_one(doc! {}, None)
+            {
+                Ok(_) => EnvironmentCheck::new(&name, CHECK_OK, "readable"),
+                Err(err) => EnvironmentCheck::new(&name, CHECK_ERROR, &err.to_string()),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,89 @@
+use crate::{
+    db_connectors::DbConversationError,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, MongoDbClient,
+};
+use bson::{doc, Document};
+use chrono::{DateTime, SecondsFormat, Utc};
+
+fn format_error_struct(error: Document) -> Result<DbConversationError, EngineError> {
+    Ok(DbConversationError {
+        id: error.get_str("id").unwrap().to_owned(),
+        client: bson::from_bson(error.get("client").unwrap().to_owned())?,
+        conversation_id: error.get_str("conversation_id").unwrap().to_owned(),
+        flow_id: error.get_str("flow_id").unwrap().to_owned(),
+        step_id: error.get_str("step_id").unwrap().to_owned(),
+        error: decrypt_data(error.get_str("payload").unwrap().to_owned())?,
+        created_at: error
+            .get_datetime("created_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+pub fn create_conversation_error(
+    error: &DbConversationError,
+    created_at: DateTime<Utc>,
+    expires_at: Option<bson::DateTime>,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("error");
+
+    let error = doc! {
+        "id": &error.id,
+        "client": bson::to_bson(&error.client)?,
+        "conversation_id": &error.conversation_id,
+        "flow_id": &error.flow_id,
+        "step_id": &error.step_id,
+        "payload": encrypt_data(&error.error)?,
+        "expires_at": expires_at,
+        "created_at": bson::DateTime::from_chrono(created_at),
+    };
+
+    collection.insert_one(error, None)?;
+
+    Ok(())
+}
+
+pub fn get_bot_errors(
+    bot_id: &str,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+    db: &MongoDbClient,
+) -> Result<Vec<DbConversationError>, EngineError> {
+    let collection = db.client.collection::<Document>("error");
+
+    let mut filter = doc! {
+        "client.bot_id": bot_id,
+    };
+    if let Some(before) = before {
+        filter.insert(
+            "created_at",
+            doc! { "$lt": bson::DateTime::from_chrono(before) },
+        );
+    }
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": -1 })
+        .limit(limit)
+        .build();
+    let cursor = collection.find(filter, find_options)?;
+
+    let mut errors = vec![];
+    for doc in cursor {
+        errors.push(format_error_struct(doc?)?);
+    }
+
+    Ok(errors)
+}
+
+pub fn delete_client_errors(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("error");
+
+    let filter = doc! {
+        "client": bson::to_bson(client)?,
+    };
+    collection.delete_many(filter, None)?;
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use crate::{EngineError, MongoDbClient};
+use bson::{doc, Document};
+use chrono::{DateTime, Utc};
+use mongodb::{
+    error::{ErrorKind, WriteFailure},
+    options::UpdateOptions,
+};
+
+const DUPLICATE_KEY: i32 = 11000;
+
+pub fn claim_job(
+    name: &str,
+    owner: &str,
+    now: DateTime<Utc>,
+    locked_until: DateTime<Utc>,
+    db: &MongoDbClient,
+) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("job");
+
+    let filter = doc! {
+        "name": name,
+        "locked_until": { "$lte": bson::DateTime::from_chrono(now) },
+    };
+    let update = doc! {
+        "$set": {
+            "owner": owner,
+            "locked_until": bson::DateTime::from_chrono(locked_until),
+            "last_run_at": bson::DateTime::from_chrono(now),
+            "updated_at": bson::DateTime::from_chrono(now),
+        },
+        "$setOnInsert": {
+            "created_at": bson::DateTime::from_chrono(now),
+        }
+    };
+    // the job is inserted on its first run, the unique index on "name" makes the
+    // upsert fail when the job exists and is locked by another server
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    match collection.update_one(filter, update, options) {
+        Ok(_) => Ok(true),
+        Err(err) => match *err.kind {
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == DUPLICATE_KEY =>
+            {
+                Ok(false)
+            }
+            _ => Err(EngineError::MongoDB(err)),
+        },
+    }
+}
+
+pub fn complete_job(
+    name: &str,
+    owner: &str,
+    next_run_at: DateTime<Utc>,
+    error: Option<&str>,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("job");
+
+    let filter = doc! {
+        "name": name,
+        "owner": owner,
+    };
+    let update = doc! {
+        "$set": {
+            "locked_until": bson::DateTime::from_chrono(next_run_at),
+            "last_error": error,
+            "updated_at": bson::DateTime::from_chrono(Utc::now()),
+        }
+    };
+
+    collection.update_one(filter, update, None)?;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
+use crate::db_connectors::utils::get_storage_client;
 use crate::{
     db_connectors::mongodb::get_db,
-    encrypt::{decrypt_data, encrypt_data},
+    encrypt::{decrypt_data, encrypt_data, get_search_terms},
     Client, ConversationInfo, EngineError, Memory, MongoDbClient,
 };
 use bson::{doc, Bson, Document};
@@ -11,11 +12,12 @@ fn format_memories(
     memories: &HashMap<String, Memory>,
     expires_at: Option<bson::DateTime>,
 ) -> Result<Vec<bson::Document>, EngineError> {
-    let client = bson::to_bson(&data.client)?;
+    let client = bson::to_bson(&get_storage_client(&data.client))?;
 
     memories.iter().fold(Ok(vec![]), |vec, (_, mem)| {
         let time = bson::DateTime::from_chrono(chrono::Utc::now());
         let value = encrypt_data(&mem.value)?;
+        let search_terms = get_search_terms(&mem.value)?;
 
         let mut vec = vec?;
 
@@ -23,6 +25,7 @@ fn format_memories(
             "client": client.clone(),
             "key": &mem.key,
             "value": value, // encrypted
+            "search_terms": search_terms,
             "expires_at": Bson::Null,
             "expires_at": expires_at,
             "created_at": time.clone(),
@@ -62,6 +65,7 @@ pub fn create_client_memory(
         "client": bson::to_bson(&client)?,
         "key": key,
         "value": encrypt_data(&value)?, // encrypted
+        "search_terms": get_search_terms(&value)?,
         "expires_at": expires_at,
         "created_at": &time,
         "updated_at": time
@@ -1,6 +1,7 @@
+use crate::db_connectors::utils::get_storage_client;
 use crate::{
     db_connectors::{mongodb::get_db, DbMessage},
-    encrypt::{decrypt_data, encrypt_data},
+    encrypt::{decrypt_data, encrypt_data, get_search_terms},
     Client, ConversationInfo, EngineError, MongoDbClient,
 };
 use bson::{doc, Document};
@@ -39,7 +40,7 @@ fn format_message(
 ) -> Result<Document, EngineError> {
     let time = bson::DateTime::from_chrono(chrono::Utc::now());
     let doc = doc! {
-        "client": bson::to_bson(&data.client)?,
+        "client": bson::to_bson(&get_storage_client(&data.client))?,
         "conversation_id": &data.conversation_id,
         "flow_id": &data.context.flow,
         "step_id": &data.context.step.get_step(),
@@ -47,6 +48,10 @@ fn format_message(
         "interaction_order": interaction_order,
         "direction": direction,
         "payload": encrypt_data(&message)?, // encrypted
+        "search_terms": get_search_terms(&message)?,
+        "reference_id": message["content"]["reference_id"].as_str(),
+        "request_id": data.run_context.request_id.as_deref(),
+        "bot_version_id": data.bot_version_id.as_deref(),
         "expires_at": expires_at,
         "created_at": time
     };
@@ -68,6 +73,18 @@ fn format_message_struct(message: bson::document::Document) -> Result<DbMessage,
         interaction_order: message.get_i32("interaction_order").unwrap(),
         direction: message.get_str("direction").unwrap().to_owned(),
         payload,
+        reference_id: message
+            .get_str("reference_id")
+            .ok()
+            .map(|reference_id| reference_id.to_owned()),
+        request_id: message
+            .get_str("request_id")
+            .ok()
+            .map(|request_id| request_id.to_owned()),
+        bot_version_id: message
+            .get_str("bot_version_id")
+            .ok()
+            .map(|bot_version_id| bot_version_id.to_owned()),
         created_at: message
             .get_datetime("created_at")
             .unwrap()
@@ -118,7 +135,7 @@ pub fn get_client_messages(
     from_date: Option<i64>,
     to_date: Option<i64>,
 ) -> Result<serde_json::Value, EngineError> {
-    let collection = db.client.collection::<Document>("message");
+    let collection = db.reader().collection::<Document>("message");
 
     let limit = match limit {
         Some(limit) => std::cmp::min(limit + 1, 26),
@@ -191,6 +208,9 @@ pub fn get_client_messages(
                     "step_id": message.step_id,
                     "direction": message.direction,
                     "payload": message.payload,
+                    "reference_id": message.reference_id,
+                    "request_id": message.request_id,
+                    "bot_version_id": message.bot_version_id,
                     "created_at": message.created_at,
                 });
 
@@ -215,3 +235,46 @@ pub fn get_client_messages(
         false => Ok(serde_json::json!({ "messages": messages })),
     }
 }
+
+pub fn get_conversation_messages(
+    client: &Client,
+    conversation_id: &str,
+    db: &MongoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let collection = db.reader().collection::<Document>("message");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "conversation_id": conversation_id,
+    };
+
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": 1, "interaction_order": 1, "message_order": 1 })
+        .build();
+
+    let cursor = collection.find(filter, find_options)?;
+
+    let mut messages = vec![];
+    for doc in cursor {
+        let message = format_message_struct(doc?)?;
+
+        messages.push(serde_json::json!({
+            "client": message.client,
+            "conversation_id": message.conversation_id,
+            "flow_id": message.flow_id,
+            "step_id": message.step_id,
+            "message_order": message.message_order,
+            "interaction_order": message.interaction_order,
+            "direction": message.direction,
+            "payload": message.payload,
+            "reference_id": message.reference_id,
+            "request_id": message.request_id,
+            "bot_version_id": message.bot_version_id,
+            "created_at": message.created_at,
+        }));
+    }
+
+    Ok(messages)
+}
@@ -0,0 +1,286 @@
+/**
+ * Versioned migrations of the MongoDB collections.
+ *
+ * Each migration is applied once: the applied versions are saved in the "migration"
+ * collection. New migrations must be appended to MIGRATIONS with the next version,
+ * applied migrations must never be modified.
+ */
+use crate::{EngineError, MongoDbClient};
+use bson::{doc, Document};
+use core::time::Duration as CoreDuration;
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+use mongodb::{options::IndexOptions, IndexModel};
+use std::sync::Once;
+
+const MIGRATION_COLLECTION: &str = "migration";
+
+struct Migration {
+    version: i32,
+    name: &'static str,
+    run: fn(&MongoDbClient) -> Result<(), EngineError>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_ttl_and_client_indexes",
+        run: create_ttl_and_client_indexes,
+    },
+    Migration {
+        version: 2,
+        name: "create_query_indexes",
+        run: create_query_indexes,
+    },
+    Migration {
+        version: 3,
+        name: "create_job_index",
+        run: create_job_index,
+    },
+    Migration {
+        version: 4,
+        name: "create_search_indexes",
+        run: create_search_indexes,
+    },
+    Migration {
+        version: 5,
+        name: "create_error_indexes",
+        run: create_error_indexes,
+    },
+    Migration {
+        version: 6,
+        name: "create_state_unique_index",
+        run: create_state_unique_index,
+    },
+    Migration {
+        version: 7,
+        name: "create_search_term_indexes",
+        run: create_search_term_indexes,
+    },
+];
+
+static INIT_MIGRATIONS: Once = Once::new();
+
+fn create_index(
+    db: &MongoDbClient,
+    collection: &str,
+    keys: Document,
+    options: Option<IndexOptions>,
+) -> Result<(), EngineError> {
+    let index = IndexModel::builder().keys(keys).options(options).build();
+
+    db.client
+        .collection::<Document>(collection)
+        .create_index(index, None)?;
+
+    Ok(())
+}
+
+fn client_keys() -> Document {
+    doc! {
+        "client.bot_id": 1,
+        "client.channel_id": 1,
+        "client.user_id": 1
+    }
+}
+
+fn client_keys_with(extra: Document) -> Document {
+    let mut keys = client_keys();
+    keys.extend(extra);
+    keys
+}
+
+fn create_ttl_and_client_indexes(db: &MongoDbClient) -> Result<(), EngineError> {
+    // documents are removed by mongodb once their expires_at date is reached
+    for collection in ["conversation", "memory", "message", "state"].iter() {
+        let options = IndexOptions::builder()
+            .expire_after(CoreDuration::new(0, 0))
+            .build();
+
+        create_index(db, collection, doc! { "expires_at": 1 }, Some(options))?;
+        create_index(db, collection, client_keys(), None)?;
+    }
+
+    // used to poll the due events
+    create_index(db, "scheduled_event", doc! { "run_at": 1 }, None)?;
+    // used to poll the pending messages
+    create_index(db, "outbox", doc! { "status": 1, "created_at": 1 }, None)
+}
+
+fn create_query_indexes(db: &MongoDbClient) -> Result<(), EngineError> {
+    // open conversation of a client
+    create_index(
+        db,
+        "conversation",
+        client_keys_with(doc! { "status": 1 }),
+        None,
+    )?;
+
+    // messages of a client and of a conversation, sorted by creation date
+    create_index(
+        db,
+        "message",
+        client_keys_with(doc! { "created_at": -1 }),
+        None,
+    )?;
+    create_index(
+        db,
+        "message",
+        doc! { "conversation_id": 1, "created_at": 1 },
+        None,
+    )?;
+
+    // memory and state lookups by key
+    create_index(db, "memory", client_keys_with(doc! { "key": 1 }), None)?;
+    create_index(
+        db,
+        "state",
+        client_keys_with(doc! { "type": 1, "key": 1 }),
+        None,
+    )
+}
+
+fn create_job_index(db: &MongoDbClient) -> Result<(), EngineError> {
+    // a job can only be locked by one server at a time
+    let options = IndexOptions::builder().unique(true).build();
+
+    create_index(db, "job", doc! { "name": 1 }, Some(options))
+}
+
+fn create_search_indexes(db: &MongoDbClient) -> Result<(), EngineError> {
+    // text search of the messages and memories of a bot (see search.rs),
+    // the encrypted payloads and values are searched with their blind index
+    create_index(db, "message", doc! { "payload": "text" }, None)?;
+    create_index(db, "memory", doc! { "value": "text" }, None)
+}
+
+fn create_error_indexes(db: &MongoDbClient) -> Result<(), EngineError> {
+    let options = IndexOptions::builder()
+        .expire_after(CoreDuration::new(0, 0))
+        .build();
+
+    create_index(db, "error", doc! { "expires_at": 1 }, Some(options))?;
+    create_index(db, "error", client_keys(), None)?;
+    // errors of a bot, newest first (see errors.rs)
+    create_index(
+        db,
+        "error",
+        doc! { "client.bot_id": 1, "created_at": -1 },
+        None,
+    )
+}
+
+fn create_state_unique_index(db: &MongoDbClient) -> Result<(), EngineError> {
+    let state = db.client.collection::<Document>("state");
+
+    // only the last saved value of the items saved several times is kept
+    let pipeline = vec![
+        doc! { "$sort": { "created_at": -1 } },
+        doc! { "$group": {
+            "_id": {
+                "bot_id": "$client.bot_id",
+                "channel_id": "$client.channel_id",
+                "user_id": "$client.user_id",
+                "type": "$type",
+                "key": "$key",
+            },
+            "ids": { "$push": "$_id" },
+        }},
+        doc! { "$match": { "ids.1": { "$exists": true } } },
+    ];
+    for duplicates in state.aggregate(pipeline, None)? {
+        if let Ok(ids) = duplicates?.get_array("ids") {
+            state.delete_many(doc! { "_id": { "$in": ids[1..].to_vec() } }, None)?;
+        }
+    }
+
+    // a state item is saved once per client, type and key,
+    // the index replaces the lookup index of create_query_indexes
+    state.drop_index(
+        "client.bot_id_1_client.channel_id_1_client.user_id_1_type_1_key_1",
+        None,
+    )?;
+    let options = IndexOptions::builder().unique(true).build();
+
+    create_index(
+        db,
+        "state",
+        client_keys_with(doc! { "type": 1, "key": 1 }),
+        Some(options),
+    )
+}
+
+fn create_search_term_indexes(db: &MongoDbClient) -> Result<(), EngineError> {
+    // blind index of the encrypted payloads and values (see encrypt.rs)
+    let keys = doc! { "client.bot_id": 1, "search_terms": 1 };
+
+    create_index(db, "message", keys.clone(), None)?;
+    create_index(db, "memory", keys, None)
+}
+
+fn is_applied(db: &MongoDbClient, version: i32) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>(MIGRATION_COLLECTION);
+
+    Ok(collection
+        .find_one(doc! { "version": version }, None)?
+        .is_some())
+}
+
+/**
+ * Apply the migrations that were not applied yet, in order
+ */
+pub fn run_migrations(db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>(MIGRATION_COLLECTION);
+
+    for migration in MIGRATIONS.iter() {
+        if is_applied(db, migration.version)? {
+            continue;
+        }
+
+        (migration.run)(db)?;
+
+        collection.insert_one(
+            doc! {
+                "version": migration.version,
+                "name": migration.name,
+                "applied_at": bson::DateTime::from_chrono(chrono::Utc::now()),
+            },
+            None,
+        )?;
+
+        csml_logger(
+            CsmlLog::new(
+                None,
+                None,
+                None,
+                format!(
+                    "mongodb migration {} {} applied",
+                    migration.version, migration.name
+                ),
+            ),
+            LogLvl::Info,
+        );
+    }
+
+    Ok(())
+}
+
+/**
+ * Apply the pending migrations the first time the engine connects to the database.
+ * A failing migration must not break the requests: the error is logged and
+ * `make_migrations` can be called to get it.
+ */
+pub fn run_init_migrations(db: &MongoDbClient) {
+    INIT_MIGRATIONS.call_once(|| {
+        if let Err(err) = run_migrations(db) {
+            csml_logger(
+                CsmlLog::new(
+                    None,
+                    None,
+                    None,
+                    format!("mongodb migrations failed: {:?}", err),
+                ),
+                LogLvl::Error,
+            );
+        }
+    });
+}
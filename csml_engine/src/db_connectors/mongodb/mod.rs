@@ -1,68 +1,85 @@
 pub mod bot;
 pub mod conversations;
+pub mod diagnostics;
+pub mod errors;
+pub mod jobs;
 pub mod memories;
 pub mod messages;
+pub mod migrations;
+pub mod outbox;
+pub mod scheduled_events;
+pub mod search;
 pub mod state;
 
-use crate::{Database, EngineError, MongoDbClient};
-use bson::{doc, Document};
-use core::time::Duration as CoreDuration;
-use mongodb::{IndexModel, options::IndexOptions};
+use crate::{config::Config, data::DbErrorKind, Database, EngineError, MongoDbClient};
 
-fn create_mongodb_uri() -> Result<String, EngineError> {
+fn create_mongodb_uri(config: &Config) -> Result<String, EngineError> {
     let mut uri = "mongodb://".to_owned();
 
     match (
-        std::env::var("MONGODB_USERNAME"),
-        std::env::var("MONGODB_PASSWORD"),
+        config.text("database.mongodb.username"),
+        config.text("database.mongodb.password"),
     ) {
-        (Ok(username), Ok(password)) if !username.is_empty() && !password.is_empty() => {
+        (Some(username), Some(password)) => {
             uri = format!("{}{}:{}@", uri, username, password)
         }
         _ => {}
     }
 
-    match std::env::var("MONGODB_HOST") {
-        Ok(host) => uri = format!("{}{}", uri, host),
+    match config.text("database.mongodb.host") {
+        Some(host) => uri = format!("{}{}", uri, host),
         _ => return Err(EngineError::Manager(format!("Missing MONGODB_HOST in env"))),
     }
 
-    match std::env::var("MONGODB_PORT") {
-        Ok(var) => match var.parse::<u16>() {
+    match config.integer("database.mongodb.port") {
+        Some(var) => match <u16 as std::convert::TryFrom<u64>>::try_from(var) {
             Ok(port) => uri = format!("{}:{}", uri, port),
             Err(err) => return Err(EngineError::Manager(err.to_string())),
         },
-        _ => {}
+        None => {}
     };
 
     Ok(uri)
 }
 
-pub fn init() -> Result<Database, EngineError> {
-    let dbname = match std::env::var("MONGODB_DATABASE") {
-        Ok(var) => var,
+pub fn init(config: &Config) -> Result<Database, EngineError> {
+    let dbname = match config.text("database.mongodb.database") {
+        Some(var) => var,
         _ => return Err(EngineError::Manager(format!("Missing MONGODB_DATABASE in env"))),
     };
 
-    let uri = match std::env::var("MONGODB_URI") {
-        Ok(var) => var,
-        _ => create_mongodb_uri()?,
+    let uri = match config.text("database.mongodb.uri") {
+        Some(var) => var,
+        _ => create_mongodb_uri(config)?,
     };
 
     let client = mongodb::sync::Client::with_uri_str(&uri)?;
-    let mongodb_client = MongoDbClient::new(client.database(&dbname));
-    create_ttl_indexes(&mongodb_client);
-    create_client_indexes(&mongodb_client);
+    let mut mongodb_client = MongoDbClient::new(client.database(&dbname));
+
+    // the read preference (ex: readPreference=secondaryPreferred) is set in the uri
+    if let Some(read_uri) = config.text("database.mongodb.read_uri") {
+        let read_client = mongodb::sync::Client::with_uri_str(&read_uri)?;
+        mongodb_client = mongodb_client.with_read_client(read_client.database(&dbname));
+    }
+
+    migrations::run_init_migrations(&mongodb_client);
 
     let db = Database::Mongo(mongodb_client);
 
     Ok(db)
 }
 
+pub fn make_migrations(config: &Config) -> Result<(), EngineError> {
+    let db = init(config)?;
+
+    migrations::run_migrations(get_db(&db)?)
+}
+
 pub fn get_db<'a>(db: &'a Database) -> Result<&'a MongoDbClient, EngineError> {
     match db {
         Database::Mongo(db) => Ok(db),
-        _ => Err(EngineError::Manager(
+        _ => Err(EngineError::Db(
+            DbErrorKind::Setup,
             "MongoDB connector is not setup correctly".to_owned(),
         )),
     }
@@ -86,113 +103,3 @@ pub fn get_pagination_key(pagination_key: Option<String>) -> Result<Option<Strin
         None => Ok(None),
     }
 }
-
-fn create_ttl_indexes(
-    db: &MongoDbClient,
-) {
-    // create index expires_at for conversation
-    let conversation = db.client.collection::<Document>("conversation");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "expires_at": 1
-        }
-    )
-    .options(Some(IndexOptions::builder().expire_after(CoreDuration::new(0, 0)).build()))
-    .build();
-    conversation.create_index(index, None).ok();
-
-    // create index expires_at for memory
-    let memory = db.client.collection::<Document>("memory");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "expires_at": 1
-        }
-    )
-    .options(Some(IndexOptions::builder().expire_after(CoreDuration::new(0, 0)).build()))
-    .build();
-
-    memory.create_index(index,None).ok();
-
-
-    // create index expires_at for message
-    let message = db.client.collection::<Document>("message");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "expires_at": 1
-        }
-    )
-    .options(Some(IndexOptions::builder().expire_after(CoreDuration::new(0, 0)).build()))
-    .build();
-    message.create_index(index,None).ok();
-
-    // create index expires_at for state
-    let state = db.client.collection::<Document>("state");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "expires_at": 1
-        }
-    )
-    .options(Some(IndexOptions::builder().expire_after(CoreDuration::new(0, 0)).build()))
-    .build();
-    state.create_index(index,None).ok();
-}
-
-fn create_client_indexes(
-    db: &MongoDbClient,
-) {
-    // create compound client index for conversation
-    let conversation = db.client.collection::<Document>("conversation");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "client.bot_id": 1,
-            "client.channel_id": 1,
-            "client.user_id": 1
-        }
-    )
-    .build();
-    conversation.create_index(index, None).ok();
-
-    // create compound client index for memory
-    let memory = db.client.collection::<Document>("memory");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "client.bot_id": 1,
-            "client.channel_id": 1,
-            "client.user_id": 1
-        }
-    )
-    .build();
-    memory.create_index(index, None).ok();
-
-    // create compound client index for message
-    let message = db.client.collection::<Document>("message");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "client.bot_id": 1,
-            "client.channel_id": 1,
-            "client.user_id": 1
-        }
-    )
-    .build();
-    message.create_index(index,None).ok();
-
-    // create compound client index for state
-    let state = db.client.collection::<Document>("state");
-    let index: IndexModel = IndexModel::builder()
-    .keys(
-        doc! {
-            "client.bot_id": 1,
-            "client.channel_id": 1,
-            "client.user_id": 1
-        }
-    )
-    .build();
-    state.create_index(index,None).ok();
-}
@@ -0,0 +1,106 @@
+use crate::{
+    db_connectors::DbOutboxMessage,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, MongoDbClient,
+};
+use bson::{doc, Document};
+use chrono::{DateTime, SecondsFormat, Utc};
+
+fn format_outbox_message_struct(outbox_message: Document) -> Result<DbOutboxMessage, EngineError> {
+    Ok(DbOutboxMessage {
+        id: outbox_message.get_str("id").unwrap().to_owned(),
+        client: bson::from_bson(outbox_message.get("client").unwrap().to_owned())?,
+        callback_url: outbox_message.get_str("callback_url").unwrap().to_owned(),
+        payload: decrypt_data(outbox_message.get_str("payload").unwrap().to_owned())?,
+        created_at: outbox_message
+            .get_datetime("created_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+pub fn create_outbox_message(
+    outbox_message: &DbOutboxMessage,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let outbox_message = doc! {
+        "id": &outbox_message.id,
+        "client": bson::to_bson(&outbox_message.client)?,
+        "callback_url": &outbox_message.callback_url,
+        "payload": encrypt_data(&outbox_message.payload)?,
+        "status": "PENDING",
+        "created_at": bson::DateTime::from_chrono(Utc::now()),
+    };
+
+    collection.insert_one(outbox_message, None)?;
+
+    Ok(())
+}
+
+pub fn get_pending_outbox_messages(
+    before: DateTime<Utc>,
+    limit: i64,
+    db: &MongoDbClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "status": "PENDING",
+        "created_at": { "$lt": bson::DateTime::from_chrono(before) },
+    };
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": 1 })
+        .limit(limit)
+        .build();
+    let cursor = collection.find(filter, find_options)?;
+
+    let mut outbox_messages = vec![];
+    for doc in cursor {
+        outbox_messages.push(format_outbox_message_struct(doc?)?);
+    }
+
+    Ok(outbox_messages)
+}
+
+pub fn set_outbox_message_sent(id: &str, db: &MongoDbClient) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "id": id,
+        "status": "PENDING",
+    };
+    let result = collection.update_one(filter, doc! { "$set": { "status": "SENT" } }, None)?;
+
+    Ok(result.modified_count > 0)
+}
+
+pub fn delete_outbox_messages(
+    before: DateTime<Utc>,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "created_at": { "$lt": bson::DateTime::from_chrono(before) },
+    };
+    collection.delete_many(filter, None)?;
+
+    Ok(())
+}
+
+pub fn delete_client_outbox_messages(
+    client: &Client,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "client": bson::to_bson(client)?,
+    };
+    collection.delete_many(filter, None)?;
+
+    Ok(())
+}
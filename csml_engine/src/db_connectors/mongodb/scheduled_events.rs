@@ -0,0 +1,95 @@
+use crate::{
+    db_connectors::DbScheduledEvent,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, MongoDbClient,
+};
+use bson::{doc, Document};
+use chrono::{DateTime, SecondsFormat, Utc};
+
+fn format_scheduled_event_struct(
+    scheduled_event: Document,
+) -> Result<DbScheduledEvent, EngineError> {
+    Ok(DbScheduledEvent {
+        id: scheduled_event.get_str("id").unwrap().to_owned(),
+        client: bson::from_bson(scheduled_event.get("client").unwrap().to_owned())?,
+        event: decrypt_data(scheduled_event.get_str("event").unwrap().to_owned())?,
+        run_at: scheduled_event
+            .get_datetime("run_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        created_at: scheduled_event
+            .get_datetime("created_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+pub fn create_scheduled_event(
+    client: &Client,
+    id: &str,
+    event: &serde_json::Value,
+    run_at: DateTime<Utc>,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("scheduled_event");
+
+    let scheduled_event = doc! {
+        "id": id,
+        "client": bson::to_bson(client)?,
+        "event": encrypt_data(event)?,
+        "run_at": bson::DateTime::from_chrono(run_at),
+        "created_at": bson::DateTime::from_chrono(Utc::now()),
+    };
+
+    collection.insert_one(scheduled_event, None)?;
+
+    Ok(())
+}
+
+pub fn get_due_scheduled_events(
+    now: DateTime<Utc>,
+    limit: i64,
+    db: &MongoDbClient,
+) -> Result<Vec<DbScheduledEvent>, EngineError> {
+    let collection = db.client.collection::<Document>("scheduled_event");
+
+    let filter = doc! {
+        "run_at": { "$lte": bson::DateTime::from_chrono(now) },
+    };
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "run_at": 1 })
+        .limit(limit)
+        .build();
+    let cursor = collection.find(filter, find_options)?;
+
+    let mut scheduled_events = vec![];
+    for doc in cursor {
+        scheduled_events.push(format_scheduled_event_struct(doc?)?);
+    }
+
+    Ok(scheduled_events)
+}
+
+pub fn delete_scheduled_event(id: &str, db: &MongoDbClient) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("scheduled_event");
+
+    let result = collection.delete_one(doc! { "id": id }, None)?;
+
+    Ok(result.deleted_count > 0)
+}
+
+pub fn delete_client_scheduled_events(
+    client: &Client,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("scheduled_event");
+
+    let filter = doc! {
+        "client": bson::to_bson(client)?,
+    };
+    collection.delete_many(filter, None)?;
+
+    Ok(())
+}
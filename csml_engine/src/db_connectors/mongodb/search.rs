@@ -0,0 +1,96 @@
+use crate::{
+    db_connectors::search::SearchQuery, encrypt::decrypt_data, EngineError, MongoDbClient,
+};
+use bson::{doc, Document};
+use chrono::SecondsFormat;
+
+/**
+ * The text is searched with the text indexes of the payloads and values (see migrations.rs):
+ * the documents containing any of its words (or their stems) match.
+ * The encrypted documents holding every term of the blind index of the text match.
+ */
+fn get_filter(query: &SearchQuery) -> Document {
+    let from = bson::DateTime::from_millis(query.from.unwrap_or(0) * 1000);
+    let to = match query.to {
+        Some(to) => bson::DateTime::from_millis(to * 1000),
+        None => bson::DateTime::from_chrono(chrono::Utc::now()),
+    };
+
+    let mut filter = doc! {
+        "client.bot_id": &query.bot_id,
+        "created_at": { "$gte": from, "$lte": to },
+    };
+    match &query.terms {
+        Some(terms) => filter.insert("search_terms", doc! { "$all": terms }),
+        None => filter.insert("$text", doc! { "$search": &query.text }),
+    };
+
+    filter
+}
+
+fn find(
+    query: &SearchQuery,
+    collection: &str,
+    db: &MongoDbClient,
+) -> Result<Vec<Document>, EngineError> {
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": -1 })
+        .limit(query.limit)
+        .build();
+
+    db.reader()
+        .collection::<Document>(collection)
+        .find(get_filter(query), find_options)?
+        .map(|doc| Ok(doc?))
+        .collect()
+}
+
+fn format_date(doc: &Document) -> Result<String, EngineError> {
+    Ok(doc
+        .get_datetime("created_at")?
+        .to_chrono()
+        .to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+pub fn search_messages(
+    query: &SearchQuery,
+    db: &MongoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    find(query, "message", db)?
+        .into_iter()
+        .map(|message| {
+            let client: serde_json::Value =
+                bson::from_bson(message.get("client").unwrap().to_owned())?;
+
+            Ok(serde_json::json!({
+                "client": client,
+                "conversation_id": message.get_str("conversation_id")?,
+                "flow_id": message.get_str("flow_id")?,
+                "step_id": message.get_str("step_id")?,
+                "direction": message.get_str("direction")?,
+                "payload": decrypt_data(message.get_str("payload")?.to_owned())?,
+                "created_at": format_date(&message)?,
+            }))
+        })
+        .collect()
+}
+
+pub fn search_memories(
+    query: &SearchQuery,
+    db: &MongoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    find(query, "memory", db)?
+        .into_iter()
+        .map(|memory| {
+            let client: serde_json::Value =
+                bson::from_bson(memory.get("client").unwrap().to_owned())?;
+
+            Ok(serde_json::json!({
+                "client": client,
+                "key": memory.get_str("key")?,
+                "value": decrypt_data(memory.get_str("value")?.to_owned())?,
+                "created_at": format_date(&memory)?,
+            }))
+        })
+        .collect()
+}
@@ -3,6 +3,7 @@ use crate::{
     EngineError, MongoDbClient,
 };
 use bson::{doc, Document};
+use mongodb::options::{ReplaceOptions, UpdateOptions};
 use csml_interpreter::data::Client;
 
 pub fn format_state_data(
@@ -118,13 +119,91 @@ pub fn set_state_items(
         return Ok(());
     }
 
-    let state_data = format_state_data(client, _type, keys_values, expires_at)?;
+    for (key, value) in keys_values.iter() {
+        replace_state_item(client, _type, key, value, expires_at, db)?;
+    }
+
+    Ok(())
+}
+
+pub fn replace_state_item(
+    client: &Client,
+    _type: &str,
+    key: &str,
+    value: &serde_json::Value,
+    expires_at: Option<bson::DateTime>,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let state = db.client.collection::<Document>("state");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "type": _type,
+        "key": key,
+    };
+    let mut state_data = format_state_data(client, _type, vec![(key, value)], expires_at)?;
+    let options = ReplaceOptions::builder().upsert(true).build();
+
+    state.replace_one(filter, state_data.remove(0), options)?;
+
+    Ok(())
+}
+
+pub fn increment_state_counter(
+    client: &Client,
+    _type: &str,
+    key: &str,
+    n: i64,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
     let state = db.client.collection::<Document>("state");
-    state.insert_many(state_data, None)?;
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "type": _type,
+        "key": key,
+    };
+    let update = doc! {
+        "$inc": { "value": n },
+        "$setOnInsert": { "created_at": bson::DateTime::from_chrono(chrono::Utc::now()) },
+    };
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    state.update_one(filter, update, options)?;
 
     Ok(())
 }
 
+pub fn get_state_counter(
+    client: &Client,
+    _type: &str,
+    key: &str,
+    db: &MongoDbClient,
+) -> Result<i64, EngineError> {
+    let state = db.client.collection::<Document>("state");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "type": _type,
+        "key": key,
+    };
+
+    match state.find_one(filter, None)? {
+        Some(doc) => match doc.get("value") {
+            Some(bson::Bson::Int64(value)) => Ok(*value),
+            Some(bson::Bson::Int32(value)) => Ok(*value as i64),
+            _ => Ok(0),
+        },
+        None => Ok(0),
+    }
+}
+
 pub fn delete_user_state(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
     let collection = db.client.collection::<Document>("state");
 
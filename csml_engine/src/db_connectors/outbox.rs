@@ -0,0 +1,271 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use crate::db_connectors::{utils::get_storage_client, DbOutboxMessage};
+use crate::data::DbErrorKind;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Database, EngineError};
+use chrono::{DateTime, Utc};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+/**
+ * Save the messages sent to a callback_url as PENDING until they are marked sent
+ */
+pub fn create_outbox_message(
+    client: &Client,
+    callback_url: &str,
+    payload: &serde_json::Value,
+    db: &mut Database,
+) -> Result<DbOutboxMessage, EngineError> {
+    let client = &get_storage_client(client);
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call create outbox message, callback_url: {:?}",
+                callback_url
+            ),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!(
+                "db call create outbox message, callback_url: {:?}, payload: {:?}",
+                callback_url, payload
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    let outbox_message = DbOutboxMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        client: client.to_owned(),
+        callback_url: callback_url.to_owned(),
+        payload: payload.to_owned(),
+        created_at: Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+    };
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        mongodb_connector::outbox::create_outbox_message(&outbox_message, db)?;
+        return Ok(outbox_message);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        dynamodb_connector::outbox::create_outbox_message(&outbox_message, db)?;
+        return Ok(outbox_message);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        postgresql_connector::outbox::create_outbox_message(&outbox_message, db)?;
+        return Ok(outbox_message);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        sqlite_connector::outbox::create_outbox_message(&outbox_message, db)?;
+        return Ok(outbox_message);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Return at most `limit` PENDING messages saved before `before`, oldest first
+ */
+pub fn get_pending_outbox_messages(
+    before: DateTime<Utc>,
+    limit: i64,
+    db: &mut Database,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get pending outbox messages, before: {:?}", before),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::get_pending_outbox_messages(before, limit, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::get_pending_outbox_messages(before, limit, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::get_pending_outbox_messages(before, limit, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::get_pending_outbox_messages(before, limit, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Mark a PENDING message as SENT. Returns false if the message was already marked sent
+ * or deleted.
+ */
+pub fn set_outbox_message_sent(
+    outbox_message: &DbOutboxMessage,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call set outbox message sent: {:?}", outbox_message.id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(&outbox_message.client),
+            None,
+            None,
+            format!("db call set outbox message sent: {:?}", outbox_message.id),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::set_outbox_message_sent(&outbox_message.id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::set_outbox_message_sent(outbox_message, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::set_outbox_message_sent(&outbox_message.id, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::set_outbox_message_sent(&outbox_message.id, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Delete the messages saved before `before`, sent or not
+ */
+pub fn delete_outbox_messages(before: DateTime<Utc>, db: &mut Database) -> Result<(), EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call delete outbox messages, before: {:?}", before),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::delete_outbox_messages(before, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::delete_outbox_messages(before, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::delete_outbox_messages(before, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::delete_outbox_messages(before, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_outbox_messages() {
+        crate::make_migrations().unwrap_or({});
+        let client = Client {
+            bot_id: "bot_id".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "outbox_test".to_owned(),
+            tenant_id: None,
+        };
+        let mut db = init_db().unwrap();
+
+        let payload = serde_json::json!({"messages": [{"payload": {"content_type": "text"}}]});
+        let outbox_message =
+            create_outbox_message(&client, "http://localhost/callback", &payload, &mut db).unwrap();
+
+        let later = Utc::now() + chrono::Duration::seconds(10);
+        let pending: Vec<DbOutboxMessage> = get_pending_outbox_messages(later, 100, &mut db)
+            .unwrap()
+            .into_iter()
+            .filter(|message| message.client.user_id == client.user_id)
+            .collect();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, outbox_message.id);
+        assert_eq!(pending[0].payload, payload);
+
+        assert!(set_outbox_message_sent(&pending[0], &mut db).unwrap());
+        assert!(!set_outbox_message_sent(&pending[0], &mut db).unwrap());
+        assert!(get_pending_outbox_messages(later, 100, &mut db)
+            .unwrap()
+            .iter()
+            .all(|message| message.id != outbox_message.id));
+
+        delete_outbox_messages(later, &mut db).unwrap();
+    }
+}
@@ -8,7 +8,8 @@ use crate::{
 use super::{
     models,
     schema::cmsl_bot_versions,
-    pagination::*
+    pagination::*,
+    with_retry
 };
 
 use std::env;
@@ -25,9 +26,11 @@ pub fn create_bot_version(
         engine_version: env!("CARGO_PKG_VERSION"),
     };
 
-    let bot: models::Bot = diesel::insert_into(cmsl_bot_versions::table)
-    .values(&newbot)
-    .get_result(&db.client)?;
+    let bot: models::Bot = with_retry(|| {
+        diesel::insert_into(cmsl_bot_versions::table)
+        .values(&newbot)
+        .get_result(&db.client)
+    })?;
 
     Ok(bot.id.to_string())
 }
@@ -1,5 +1,6 @@
-use diesel::{RunQueryDsl, ExpressionMethods, QueryDsl};
+use diesel::{RunQueryDsl, ExpressionMethods, OptionalExtension, QueryDsl, TextExpressionMethods};
 
+use crate::db_connectors::utils::get_tag_like_pattern;
 use crate::{
     EngineError, PostgresqlClient,
     Client, DbConversation
@@ -8,8 +9,9 @@ use chrono::{NaiveDateTime};
 
 use super::{
     models,
-    schema::csml_conversations,
-    pagination::*
+    schema::{csml_conversations, csml_messages},
+    pagination::*,
+    with_retry
 };
 
 pub fn create_conversation(
@@ -30,9 +32,11 @@ pub fn create_conversation(
         expires_at,
     };
 
-    let conversation: models::Conversation = diesel::insert_into(csml_conversations::table)
-    .values(&new_conversation)
-    .get_result(&db.client)?;
+    let conversation: models::Conversation = with_retry(|| {
+        diesel::insert_into(csml_conversations::table)
+        .values(&new_conversation)
+        .get_result(&db.client)
+    })?;
 
     Ok(conversation.id.to_string())
 }
@@ -45,25 +49,29 @@ pub fn close_conversation(
 ) -> Result<(), EngineError> {
     let id: uuid::Uuid = uuid::Uuid::parse_str(id).unwrap();
 
-    diesel::update(
-        csml_conversations::table
-        .filter(csml_conversations::id.eq(id))
-    )
-    .set(csml_conversations::status.eq(status))
-    .execute(&db.client)?;
+    with_retry(|| {
+        diesel::update(
+            csml_conversations::table
+            .filter(csml_conversations::id.eq(id))
+        )
+        .set(csml_conversations::status.eq(status))
+        .execute(&db.client)
+    })?;
 
     Ok(())
 }
 
 pub fn close_all_conversations(client: &Client, db: &PostgresqlClient) -> Result<(), EngineError> {
-    diesel::update(
-        csml_conversations::table
-        .filter(csml_conversations::bot_id.eq(&client.bot_id))
-        .filter(csml_conversations::channel_id.eq(&client.channel_id))
-        .filter(csml_conversations::user_id.eq(&client.user_id))
-    )
-    .set(csml_conversations::status.eq("CLOSED"))
-    .execute(&db.client)?;
+    with_retry(|| {
+        diesel::update(
+            csml_conversations::table
+            .filter(csml_conversations::bot_id.eq(&client.bot_id))
+            .filter(csml_conversations::channel_id.eq(&client.channel_id))
+            .filter(csml_conversations::user_id.eq(&client.user_id))
+        )
+        .set(csml_conversations::status.eq("CLOSED"))
+        .execute(&db.client)
+    })?;
 
     Ok(())
 }
@@ -88,7 +96,8 @@ pub fn get_latest_open(
                 client: Client{ 
                     bot_id: conv.bot_id,
                     channel_id: conv.channel_id,
-                    user_id: conv.user_id
+                    user_id: conv.user_id,
+                    tenant_id: None,
                 },
                 flow_id: conv.flow_id,
                 step_id: conv.step_id,
@@ -96,6 +105,7 @@ pub fn get_latest_open(
                 last_interaction_at: conv.last_interaction_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
                 updated_at: conv.updated_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
                 created_at: conv.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+                version: conv.version,
             };
 
             Ok(Some(conversation))
@@ -105,47 +115,75 @@ pub fn get_latest_open(
     }
 }
 
+/**
+ * Update the conversation if it is still at `version`, returns false if another run
+ * updated it since
+ */
 pub fn update_conversation(
     conversation_id: &str,
     flow_id: Option<String>,
     step_id: Option<String>,
+    version: i32,
     db: &PostgresqlClient,
-) -> Result<(), EngineError> {
+) -> Result<bool, EngineError> {
 
     let id: uuid::Uuid = uuid::Uuid::parse_str(conversation_id).unwrap();
 
-    match (flow_id, step_id) {
+    let now = chrono::Utc::now().naive_utc();
+
+    let conversation = csml_conversations::table
+        .filter(csml_conversations::id.eq(id))
+        .filter(csml_conversations::version.eq(version));
+    let next_version = csml_conversations::version.eq(version + 1);
+
+    let updated = match (flow_id, step_id) {
         (Some(flow_id), Some(step_id)) => {
-           diesel::update(
-                csml_conversations::table
-                .filter(csml_conversations::id.eq(&id))
-            )
-            .set((
-                csml_conversations::flow_id.eq(flow_id.as_str()),
-                csml_conversations::step_id.eq(step_id.as_str())
-            ))
-            .execute(&db.client)?;
+            with_retry(|| {
+                diesel::update(conversation)
+                .set((
+                    csml_conversations::flow_id.eq(flow_id.as_str()),
+                    csml_conversations::step_id.eq(step_id.as_str()),
+                    csml_conversations::last_interaction_at.eq(now),
+                    next_version,
+                ))
+                .execute(&db.client)
+            })?
         }
         (Some(flow_id), _) => {
-            diesel::update(
-                csml_conversations::table
-                .filter(csml_conversations::id.eq(&id))
-            )
-            .set(csml_conversations::flow_id.eq(flow_id.as_str()))
-            .get_result::<models::Conversation>(&db.client)?;
+            with_retry(|| {
+                diesel::update(conversation)
+                .set((
+                    csml_conversations::flow_id.eq(flow_id.as_str()),
+                    csml_conversations::last_interaction_at.eq(now),
+                    next_version,
+                ))
+                .execute(&db.client)
+            })?
         }
         (_, Some(step_id)) => {
-            diesel::update(
-                csml_conversations::table
-                .filter(csml_conversations::id.eq(&id))
-            )
-            .set(csml_conversations::step_id.eq(step_id.as_str()))
-            .get_result::<models::Conversation>(&db.client)?;
+            with_retry(|| {
+                diesel::update(conversation)
+                .set((
+                    csml_conversations::step_id.eq(step_id.as_str()),
+                    csml_conversations::last_interaction_at.eq(now),
+                    next_version,
+                ))
+                .execute(&db.client)
+            })?
+        }
+        _ => {
+            with_retry(|| {
+                diesel::update(conversation)
+                .set((
+                    csml_conversations::last_interaction_at.eq(now),
+                    next_version,
+                ))
+                .execute(&db.client)
+            })?
         }
-        _ => return Ok(())
     };
 
-    Ok(())
+    Ok(updated == 1)
 }
 
 pub fn delete_user_conversations(client: &Client, db: &PostgresqlClient) -> Result<(), EngineError> {
@@ -163,6 +201,7 @@ pub fn get_client_conversations(
     db: &PostgresqlClient,
     limit: Option<i64>,
     pagination_key: Option<String>,
+    tag: Option<&str>,
 ) -> Result<serde_json::Value, EngineError> {
 
     let pagination_key = match pagination_key {
@@ -175,7 +214,13 @@ pub fn get_client_conversations(
         .filter(csml_conversations::bot_id.eq(&client.bot_id))
         .filter(csml_conversations::channel_id.eq(&client.channel_id))
         .filter(csml_conversations::user_id.eq(&client.user_id))
-        .paginate(pagination_key);
+        .into_boxed();
+
+    if let Some(tag) = tag {
+        query = query.filter(csml_conversations::tags.like(get_tag_like_pattern(tag)));
+    }
+
+    let mut query = query.paginate(pagination_key);
 
     let limit_per_page = match limit {
         Some(limit) => std::cmp::min(limit, 25),
@@ -197,6 +242,8 @@ pub fn get_client_conversations(
             "flow_id": conversation.flow_id,
             "step_id": conversation.step_id,
             "status": conversation.status,
+            "tags": parse_tags(conversation.tags),
+            "summary": conversation.summary,
             "last_interaction_at": conversation.last_interaction_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
             "updated_at": conversation.updated_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
             "created_at": conversation.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
@@ -216,6 +263,72 @@ pub fn get_client_conversations(
     }
 }
 
+fn parse_tags(tags: Option<String>) -> Vec<String> {
+    tags.and_then(|tags| serde_json::from_str(&tags).ok())
+        .unwrap_or_default()
+}
+
+pub fn add_conversation_tags(
+    id: &str,
+    tags: &[String],
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    let saved: Option<Option<String>> = csml_conversations::table
+        .filter(csml_conversations::id.eq(&id))
+        .select(csml_conversations::tags)
+        .first(&db.client)
+        .optional()?;
+
+    let mut conversation_tags = match saved {
+        Some(saved) => parse_tags(saved),
+        None => return Ok(()),
+    };
+    for tag in tags {
+        if !conversation_tags.contains(tag) {
+            conversation_tags.push(tag.to_owned());
+        }
+    }
+
+    let tags = serde_json::to_string(&conversation_tags)?;
+    with_retry(|| {
+        diesel::update(
+            csml_conversations::table
+            .filter(csml_conversations::id.eq(&id))
+        )
+        .set(csml_conversations::tags.eq(&tags))
+        .execute(&db.client)
+    })?;
+
+    Ok(())
+}
+
+pub fn set_conversation_summary(
+    id: &str,
+    summary: &str,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    with_retry(|| {
+        diesel::update(
+            csml_conversations::table
+            .filter(csml_conversations::id.eq(&id))
+        )
+        .set(csml_conversations::summary.eq(summary))
+        .execute(&db.client)
+    })?;
+
+    Ok(())
+}
+
 pub fn delete_all_bot_data(
     bot_id: &str,
     db: &PostgresqlClient,
@@ -227,3 +340,94 @@ pub fn delete_all_bot_data(
 
     Ok(())
 }
+
+pub fn get_bot_clients(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    db: &PostgresqlClient,
+) -> Result<Vec<Client>, EngineError> {
+    let mut query = csml_conversations::table
+        .select((
+            csml_conversations::bot_id,
+            csml_conversations::channel_id,
+            csml_conversations::user_id,
+        ))
+        .filter(csml_conversations::bot_id.eq(bot_id))
+        .distinct()
+        .into_boxed();
+
+    if let Some(channel_id) = channel_id {
+        query = query.filter(csml_conversations::channel_id.eq(channel_id));
+    }
+
+    let clients: Vec<(String, String, String)> = query.load(&db.client)?;
+
+    Ok(clients
+        .into_iter()
+        .map(|(bot_id, channel_id, user_id)| Client {
+            bot_id,
+            channel_id,
+            user_id,
+            tenant_id: None,
+        })
+        .collect())
+}
+
+pub fn get_closed_conversations(
+    before: NaiveDateTime,
+    limit: i64,
+    db: &PostgresqlClient,
+) -> Result<Vec<DbConversation>, EngineError> {
+    let conversations: Vec<models::Conversation> = csml_conversations::table
+        .filter(csml_conversations::status.eq("CLOSED"))
+        .filter(csml_conversations::updated_at.lt(before))
+        .order_by(csml_conversations::updated_at.asc())
+        .limit(limit)
+        .load(&db.client)?;
+
+    Ok(conversations
+        .into_iter()
+        .map(|conv| DbConversation {
+            id: conv.id.to_string(),
+            client: Client {
+                bot_id: conv.bot_id,
+                channel_id: conv.channel_id,
+                user_id: conv.user_id,
+                tenant_id: None,
+            },
+            flow_id: conv.flow_id,
+            step_id: conv.step_id,
+            status: conv.status,
+            last_interaction_at: conv.last_interaction_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+            updated_at: conv.updated_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+            created_at: conv.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+            version: conv.version,
+        })
+        .collect())
+}
+
+pub fn delete_conversation(
+    id: &str,
+    client: &Client,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    diesel::delete(
+        csml_messages::table
+        .filter(csml_messages::conversation_id.eq(&id))
+    ).execute(&db.client)?;
+
+    diesel::delete(
+        csml_conversations::table
+        .filter(csml_conversations::id.eq(&id))
+        .filter(csml_conversations::bot_id.eq(&client.bot_id))
+        .filter(csml_conversations::channel_id.eq(&client.channel_id))
+        .filter(csml_conversations::user_id.eq(&client.user_id))
+    ).execute(&db.client)?;
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use diesel::{sql_query, RunQueryDsl};
+
+use crate::{data::*, PostgresqlClient};
+
+const TABLES: [&str; 7] = [
+    "cmsl_bot_versions",
+    "csml_conversations",
+    "csml_memories",
+    "csml_messages",
+    "csml_states",
+    "csml_scheduled_events",
+    "csml_jobs",
+];
+
+pub fn check_storage(db: &PostgresqlClient) -> Vec<EnvironmentCheck> {
+    TABLES
+        .iter()
+        .map(|table| {
+            let name = format!("table {}", table);
+
+            match sql_query(format!("SELECT 1 FROM {} LIMIT 1", table)).execute(&db.client) {
+                Ok(_) => EnvironmentCheck::new(&name, CHECK_OK, "readable"),
+                Err(err) => EnvironmentCheck::new(
+                    &name,
+                    CHECK_ERROR,
+                    &format!("{} (the migrations are applied by the server at startup)", err),
+                ),
+            }
+        })
+        .collect()
+}
@@ -7,7 +7,7 @@ use crate::{
 use super::{
     schema::{
         csml_conversations,
-        csml_memories, csml_states
+        csml_memories, csml_states, csml_errors
     }
 };
 
@@ -31,5 +31,10 @@ pub fn delete_expired_data(
         .filter(csml_states::expires_at.lt(date_now))
     ).execute(&db.client).ok();
 
+    diesel::delete(
+        csml_errors::table
+        .filter(csml_errors::expires_at.lt(date_now))
+    ).execute(&db.client).ok();
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,74 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{EngineError, PostgresqlClient};
+
+use super::{models, schema::csml_jobs, with_retry};
+use chrono::{DateTime, Utc};
+
+pub fn claim_job(
+    name: &str,
+    owner: &str,
+    now: DateTime<Utc>,
+    locked_until: DateTime<Utc>,
+    db: &PostgresqlClient,
+) -> Result<bool, EngineError> {
+    let updated = with_retry(|| {
+        diesel::update(
+            csml_jobs::table
+                .filter(csml_jobs::name.eq(name))
+                .filter(csml_jobs::locked_until.le(now.naive_utc())),
+        )
+        .set((
+            csml_jobs::owner.eq(owner),
+            csml_jobs::locked_until.eq(locked_until.naive_utc()),
+            csml_jobs::last_run_at.eq(Some(now.naive_utc())),
+            csml_jobs::updated_at.eq(now.naive_utc()),
+        ))
+        .execute(&db.client)
+    })?;
+
+    if updated > 0 {
+        return Ok(true);
+    }
+
+    // first run of the job: only one of the concurrent inserts is applied
+    let job = models::NewJob {
+        name,
+        owner,
+        locked_until: locked_until.naive_utc(),
+        last_run_at: Some(now.naive_utc()),
+    };
+
+    let inserted = with_retry(|| {
+        diesel::insert_into(csml_jobs::table)
+            .values(&job)
+            .on_conflict_do_nothing()
+            .execute(&db.client)
+    })?;
+
+    Ok(inserted > 0)
+}
+
+pub fn complete_job(
+    name: &str,
+    owner: &str,
+    next_run_at: DateTime<Utc>,
+    error: Option<&str>,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    with_retry(|| {
+        diesel::update(
+            csml_jobs::table
+                .filter(csml_jobs::name.eq(name))
+                .filter(csml_jobs::owner.eq(owner)),
+        )
+        .set((
+            csml_jobs::locked_until.eq(next_run_at.naive_utc()),
+            csml_jobs::last_error.eq(error),
+            csml_jobs::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(&db.client)
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use diesel::{connection::SimpleConnection, sql_query, sql_types, Connection, RunQueryDsl};
+
+use crate::{db_connectors::knowledge_base::KbPassage, EngineError, PostgresqlClient};
+use csml_interpreter::data::Passage;
+
+#[derive(QueryableByName)]
+struct PassageRow {
+    #[sql_type = "sql_types::Text"]
+    document_id: String,
+    #[sql_type = "sql_types::Text"]
+    text: String,
+    #[sql_type = "sql_types::Text"]
+    metadata: String,
+    #[sql_type = "sql_types::Double"]
+    score: f64,
+}
+
+/**
+ * pgvector literal of an embedding: [0.1,0.2,...]
+ */
+fn to_vector(embedding: &[f64]) -> String {
+    let values: Vec<String> = embedding.iter().map(|value| value.to_string()).collect();
+
+    format!("[{}]", values.join(","))
+}
+
+/**
+ * The table is not created by the migrations: it requires the vector extension,
+ * only installed on the databases used as a knowledge base
+ */
+fn create_table(db: &PostgresqlClient) -> Result<(), EngineError> {
+    db.client.batch_execute(
+        "CREATE EXTENSION IF NOT EXISTS vector;
+        CREATE TABLE IF NOT EXISTS csml_kb_passages (
+            id VARCHAR PRIMARY KEY NOT NULL,
+            bot_id VARCHAR NOT NULL,
+            document_id VARCHAR NOT NULL,
+            text TEXT NOT NULL,
+            metadata JSONB NOT NULL,
+            embedding vector NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX IF NOT EXISTS csml_kb_passages_bot_document
+            ON csml_kb_passages (bot_id, document_id);",
+    )?;
+
+    Ok(())
+}
+
+fn delete_passages(
+    bot_id: &str,
+    document_id: &str,
+    db: &PostgresqlClient,
+) -> Result<usize, EngineError> {
+    let deleted = sql_query("DELETE FROM csml_kb_passages WHERE bot_id = $1 AND document_id = $2")
+        .bind::<sql_types::VarChar, _>(bot_id)
+        .bind::<sql_types::VarChar, _>(document_id)
+        .execute(&db.client)?;
+
+    Ok(deleted)
+}
+
+/**
+ * Replace the passages of a document
+ */
+pub fn upsert_passages(
+    bot_id: &str,
+    document_id: &str,
+    passages: &[KbPassage],
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    create_table(db)?;
+
+    db.client.transaction::<_, EngineError, _>(|| {
+        delete_passages(bot_id, document_id, db)?;
+
+        for passage in passages {
+            sql_query(
+                "INSERT INTO csml_kb_passages (id, bot_id, document_id, text, metadata, embedding)
+                VALUES ($1, $2, $3, $4, $5::jsonb, $6::vector)",
+            )
+            .bind::<sql_types::VarChar, _>(&passage.id)
+            .bind::<sql_types::VarChar, _>(bot_id)
+            .bind::<sql_types::VarChar, _>(document_id)
+            .bind::<sql_types::Text, _>(&passage.text)
+            .bind::<sql_types::Text, _>(passage.metadata.to_string())
+            .bind::<sql_types::Text, _>(to_vector(&passage.embedding))
+            .execute(&db.client)?;
+        }
+
+        Ok(())
+    })
+}
+
+pub fn delete_document(
+    bot_id: &str,
+    document_id: &str,
+    db: &PostgresqlClient,
+) -> Result<bool, EngineError> {
+    create_table(db)?;
+
+    Ok(delete_passages(bot_id, document_id, db)? > 0)
+}
+
+/**
+ * Passages of the bot the closest to the embedding (cosine distance), best first
+ */
+pub fn search_passages(
+    bot_id: &str,
+    embedding: &[f64],
+    top_k: usize,
+    db: &PostgresqlClient,
+) -> Result<Vec<Passage>, EngineError> {
+    create_table(db)?;
+
+    let rows: Vec<PassageRow> = sql_query(
+        "SELECT document_id, text, metadata::text AS metadata,
+            1 - (embedding <=> $2::vector) AS score
+        FROM csml_kb_passages
+        WHERE bot_id = $1
+        ORDER BY embedding <=> $2::vector
+        LIMIT $3",
+    )
+    .bind::<sql_types::VarChar, _>(bot_id)
+    .bind::<sql_types::Text, _>(to_vector(embedding))
+    .bind::<sql_types::BigInt, _>(top_k as i64)
+    .load(&db.client)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Passage {
+            document_id: row.document_id,
+            text: row.text,
+            score: row.score,
+            metadata: serde_json::from_str(&row.metadata).unwrap_or_default(),
+        })
+        .collect())
+}
@@ -1,15 +1,18 @@
 use diesel::{RunQueryDsl, ExpressionMethods, QueryDsl};
 
+use crate::db_connectors::utils::get_storage_client;
 use crate::{
     db_connectors::postgresql::get_db,
-    encrypt::{decrypt_data, encrypt_data},
+    db_connectors::search::format_search_terms,
+    encrypt::{decrypt_data, encrypt_data, get_search_terms},
     EngineError, PostgresqlClient,
     ConversationInfo, Memory, Client
 };
 
 use super::{
     models,
-    schema::csml_memories
+    schema::csml_memories,
+    with_retry
 };
 
 use chrono::{NaiveDateTime};
@@ -27,7 +30,7 @@ pub fn add_memories(
     let db = get_db(&data.db)?;
 
     for (key, mem) in memories.iter() {
-        create_client_memory(&data.client, key, &mem.value, expires_at, db)?;
+        create_client_memory(&get_storage_client(&data.client), key, &mem.value, expires_at, db)?;
     }
 
     Ok(())
@@ -41,6 +44,7 @@ pub fn create_client_memory(
     db: &PostgresqlClient,
 ) -> Result<(), EngineError> {
 
+    let search_terms = format_search_terms(get_search_terms(value)?);
     let value = encrypt_data(&value)?;
 
     let new_memories = models::NewMemory {
@@ -51,14 +55,20 @@ pub fn create_client_memory(
         key,
         value: value.clone(),
         expires_at,
+        search_terms: search_terms.clone(),
     };
 
-    diesel::insert_into(csml_memories::table)
-    .values(&new_memories)
-    .on_conflict((csml_memories::bot_id, csml_memories::channel_id, csml_memories::user_id, csml_memories::key))
-    .do_update()
-    .set(csml_memories::value.eq(value))
-    .execute(&db.client)?;
+    with_retry(|| {
+        diesel::insert_into(csml_memories::table)
+        .values(&new_memories)
+        .on_conflict((csml_memories::bot_id, csml_memories::channel_id, csml_memories::user_id, csml_memories::key))
+        .do_update()
+        .set((
+            csml_memories::value.eq(&value),
+            csml_memories::search_terms.eq(&search_terms),
+        ))
+        .execute(&db.client)
+    })?;
 
     Ok(())
 }
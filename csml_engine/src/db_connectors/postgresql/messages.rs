@@ -2,7 +2,8 @@ use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 
 use crate::{
     db_connectors::postgresql::get_db,
-    encrypt::{decrypt_data, encrypt_data},
+    db_connectors::search::format_search_terms,
+    encrypt::{decrypt_data, encrypt_data, get_search_terms},
     Client, ConversationInfo, EngineError, PostgresqlClient,
 };
 
@@ -10,6 +11,7 @@ use super::{
     models,
     pagination::*,
     schema::{csml_conversations, csml_messages},
+    with_retry,
 };
 use chrono::NaiveDateTime;
 
@@ -43,14 +45,20 @@ pub fn add_messages_bulk(
             message_order: message_order as i32,
             interaction_order,
             expires_at,
+            reference_id: message["content"]["reference_id"].as_str(),
+            request_id: data.run_context.request_id.as_deref(),
+            bot_version_id: data.bot_version_id.as_deref(),
+            search_terms: format_search_terms(get_search_terms(message)?),
         };
 
         new_messages.push(msg);
     }
 
-    diesel::insert_into(csml_messages::table)
-        .values(&new_messages)
-        .get_result::<models::Message>(&db.client)?;
+    with_retry(|| {
+        diesel::insert_into(csml_messages::table)
+            .values(&new_messages)
+            .get_result::<models::Message>(&db.client)
+    })?;
 
     Ok(())
 }
@@ -150,6 +158,9 @@ pub fn get_client_messages(
             "step_id": message.step_id,
             "direction": message.direction,
             "payload": decrypt_data(message.payload)?,
+            "reference_id": message.reference_id,
+            "request_id": message.request_id,
+            "bot_version_id": message.bot_version_id,
 
             "updated_at": message.updated_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
             "created_at": message.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
@@ -166,3 +177,52 @@ pub fn get_client_messages(
         false => Ok(serde_json::json!({ "messages": msgs })),
     }
 }
+
+pub fn get_conversation_messages(
+    client: &Client,
+    conversation_id: &str,
+    db: &PostgresqlClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let conversation_id = match uuid::Uuid::parse_str(conversation_id) {
+        Ok(id) => id,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let messages: Vec<models::Message> = csml_conversations::table
+        .filter(csml_conversations::id.eq(&conversation_id))
+        .filter(csml_conversations::bot_id.eq(&client.bot_id))
+        .filter(csml_conversations::channel_id.eq(&client.channel_id))
+        .filter(csml_conversations::user_id.eq(&client.user_id))
+        .inner_join(csml_messages::table)
+        .select(csml_messages::all_columns)
+        .order_by(csml_messages::created_at.asc())
+        .then_order_by(csml_messages::interaction_order.asc())
+        .then_order_by(csml_messages::message_order.asc())
+        .load(&db.client)?;
+
+    let mut msgs = vec![];
+    for message in messages {
+        msgs.push(serde_json::json!({
+            "client": {
+                "bot_id": &client.bot_id,
+                "channel_id": &client.channel_id,
+                "user_id": &client.user_id
+            },
+            "conversation_id": message.conversation_id,
+            "flow_id": message.flow_id,
+            "step_id": message.step_id,
+            "message_order": message.message_order,
+            "interaction_order": message.interaction_order,
+            "direction": message.direction,
+            "payload": decrypt_data(message.payload)?,
+            "reference_id": message.reference_id,
+            "request_id": message.request_id,
+            "bot_version_id": message.bot_version_id,
+
+            "updated_at": message.updated_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+            "created_at": message.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
+        }));
+    }
+
+    Ok(msgs)
+}
@@ -1,7 +1,14 @@
 pub mod bot;
 pub mod conversations;
+pub mod diagnostics;
+pub mod errors;
+pub mod jobs;
+pub mod knowledge_base;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod scheduled_events;
+pub mod search;
 pub mod state;
 
 pub mod pagination;
@@ -11,19 +18,93 @@ pub mod models;
 
 pub mod expired_data;
 
-use crate::{Database, EngineError, PostgresqlClient};
+use crate::{config::{get_config, Config}, data::DbErrorKind, Database, EngineError, PostgresqlClient};
 
+use diesel::connection::SimpleConnection;
 use diesel::prelude::{Connection, PgConnection};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use std::{thread, time::Duration};
 
 embed_migrations!("migrations/postgresql");
 
-pub fn init() -> Result<Database, EngineError> {
+const MAX_BACKOFF: u64 = 1000;
 
-    let uri = match std::env::var("POSTGRESQL_URL") {
-        Ok(var) => var,
-        _ => "".to_owned(),
+/**
+ * Compatibility mode for the databases using the postgres protocol with serializable
+ * transactions, like CockroachDB or YugabyteDB (POSTGRESQL_COMPATIBILITY=cockroachdb):
+ * - the writes failing with a serialization failure (40001) are retried,
+ *   at most POSTGRESQL_MAX_RETRIES times (default 5)
+ * - the helper functions of the initial diesel migration are not created:
+ *   the engine does not use them and they are written in plpgsql
+ * In every mode the ids are generated by the engine and no advisory lock is taken.
+ */
+pub fn is_compatibility_mode() -> bool {
+    uses_compatibility_mode(&get_config())
+}
+
+fn uses_compatibility_mode(config: &Config) -> bool {
+    match config.text("database.postgresql.compatibility") {
+        Some(mode) => mode != "postgresql",
+        None => false,
+    }
+}
+
+fn get_max_retries() -> u32 {
+    match get_config().integer("database.postgresql.max_retries") {
+        Some(retries) => retries as u32,
+        None => 5,
+    }
+}
+
+/**
+ * Exponential backoff of a retry, in milliseconds: 20ms, 40ms, 80ms... at most MAX_BACKOFF
+ */
+fn get_backoff(retries: u32) -> u64 {
+    1u64.checked_shl(retries)
+        .map(|factor| factor.saturating_mul(10))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF)
+}
+
+/**
+ * Run a write query, retried with an exponential backoff on serialization failures
+ * in compatibility mode
+ */
+pub fn with_retry<T, F>(query: F) -> Result<T, DieselError>
+where
+    F: FnMut() -> Result<T, DieselError>,
+{
+    let max_retries = match is_compatibility_mode() {
+        true => get_max_retries(),
+        false => 0,
     };
 
+    retry_query(max_retries, query)
+}
+
+fn retry_query<T, F>(max_retries: u32, mut query: F) -> Result<T, DieselError>
+where
+    F: FnMut() -> Result<T, DieselError>,
+{
+    let mut retries = 0;
+
+    loop {
+        match query() {
+            Err(DieselError::DatabaseError(DatabaseErrorKind::SerializationFailure, _))
+                if retries < max_retries =>
+            {
+                retries += 1;
+                thread::sleep(Duration::from_millis(get_backoff(retries)));
+            }
+            res => return res,
+        }
+    }
+}
+
+pub fn init(config: &Config) -> Result<Database, EngineError> {
+
+    let uri = config.text("database.postgresql.url").unwrap_or_default();
+
     let pg_connection = PgConnection::establish(&uri)
         .unwrap_or_else(|_| panic!("Error connecting to {}", uri));
 
@@ -33,15 +114,24 @@ pub fn init() -> Result<Database, EngineError> {
     Ok(db)
 }
 
-pub fn make_migrations() -> Result<(), EngineError> {
-    let uri = match std::env::var("POSTGRESQL_URL") {
-        Ok(var) => var,
-        _ => "".to_owned(),
-    };
+pub fn make_migrations(config: &Config) -> Result<(), EngineError> {
+    let uri = config.text("database.postgresql.url").unwrap_or_default();
 
     let pg_connection = PgConnection::establish(&uri)
         .unwrap_or_else(|_| panic!("Error connecting to {}", uri));
 
+    // the initial diesel migration is marked as done before the migrations are run
+    if uses_compatibility_mode(config) {
+        pg_connection.batch_execute(
+            "CREATE TABLE IF NOT EXISTS __diesel_schema_migrations (
+                version VARCHAR(50) PRIMARY KEY NOT NULL,
+                run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO __diesel_schema_migrations (version) VALUES ('00000000000000')
+            ON CONFLICT DO NOTHING;",
+        )?;
+    }
+
     embedded_migrations::run_with_output(&pg_connection, &mut std::io::stdout())?;
 
     Ok(())
@@ -50,8 +140,62 @@ pub fn make_migrations() -> Result<(), EngineError> {
 pub fn get_db<'a>(db: &'a Database) -> Result<&'a PostgresqlClient, EngineError> {
     match db {
         Database::Postgresql(db) => Ok(db),
-        _ => Err(EngineError::Manager(
+        _ => Err(EngineError::Db(
+            DbErrorKind::Setup,
             "Postgresql connector is not setup correctly".to_owned(),
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_retry_serialization_failures() {
+        let serialization_failure = || {
+            DieselError::DatabaseError(
+                DatabaseErrorKind::SerializationFailure,
+                Box::new("restart transaction".to_owned()),
+            )
+        };
+
+        // without compatibility mode the error is returned
+        let mut attempts = 0;
+        let res: Result<(), DieselError> = retry_query(0, || {
+            attempts += 1;
+            Err(serialization_failure())
+        });
+        assert!(res.is_err());
+        assert_eq!(attempts, 1);
+
+        // in compatibility mode the serialization failures are retried
+        let mut attempts = 0;
+        let res = retry_query(5, || {
+            attempts += 1;
+            match attempts {
+                1 | 2 => Err(serialization_failure()),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(res.unwrap(), 3);
+
+        // the other errors are not retried
+        let mut attempts = 0;
+        let res: Result<(), DieselError> = retry_query(5, || {
+            attempts += 1;
+            Err(DieselError::NotFound)
+        });
+        assert!(res.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn ok_backoff_capped() {
+        assert_eq!(get_backoff(1), 20);
+        assert_eq!(get_backoff(3), 80);
+        assert_eq!(get_backoff(60), MAX_BACKOFF);
+        assert_eq!(get_backoff(64), MAX_BACKOFF);
+        assert_eq!(get_backoff(u32::MAX), MAX_BACKOFF);
+    }
+}
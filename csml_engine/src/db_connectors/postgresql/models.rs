@@ -45,6 +45,12 @@ pub struct Conversation {
     pub updated_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
     pub expires_at: Option<NaiveDateTime>,
+    // JSON array of the tags of the conversation
+    pub tags: Option<String>,
+    // generated when the conversation is closed
+    pub summary: Option<String>,
+    // incremented by each update of the conversation
+    pub version: i32,
 }
 
 #[derive(Insertable, Queryable, Associations, PartialEq, Debug)]
@@ -76,6 +82,8 @@ pub struct Memory {
     pub expires_at: Option<NaiveDateTime>,
     pub updated_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
+    // blind index of the words of the encrypted value, see encrypt.rs
+    pub search_terms: Option<String>,
 }
 
 #[derive(Insertable, Queryable, Associations, PartialEq, Debug)]
@@ -90,6 +98,7 @@ pub struct NewMemory<'a> {
     pub value: String, //serde_json::Value,
 
     pub expires_at: Option<NaiveDateTime>,
+    pub search_terms: Option<String>,
 }
 
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
@@ -112,6 +121,11 @@ pub struct Message {
     pub created_at: NaiveDateTime,
 
     pub expires_at: Option<NaiveDateTime>,
+    pub reference_id: Option<String>,
+    pub request_id: Option<String>,
+    pub bot_version_id: Option<String>,
+    // blind index of the words of the encrypted payload, see encrypt.rs
+    pub search_terms: Option<String>,
 }
 
 #[derive(Insertable, Queryable, Associations, PartialEq, Debug)]
@@ -130,6 +144,10 @@ pub struct NewMessages<'a> {
     pub interaction_order: i32,
 
     pub expires_at: Option<NaiveDateTime>,
+    pub reference_id: Option<&'a str>,
+    pub request_id: Option<&'a str>,
+    pub bot_version_id: Option<&'a str>,
+    pub search_terms: Option<String>,
 }
 
 #[derive(Identifiable, Insertable, Queryable, Associations, PartialEq, Debug)]
@@ -165,6 +183,106 @@ pub struct NewState<'a> {
     pub expires_at: Option<NaiveDateTime>,
 }
 
+#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_scheduled_events"]
+pub struct ScheduledEvent {
+    pub id: Uuid,
+
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+
+    pub event: String,
+    pub run_at: NaiveDateTime,
+
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_scheduled_events"]
+pub struct NewScheduledEvent<'a> {
+    pub id: Uuid,
+    pub bot_id: &'a str,
+    pub channel_id: &'a str,
+    pub user_id: &'a str,
+
+    pub event: String,
+    pub run_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_errors"]
+pub struct ConversationError {
+    pub id: Uuid,
+
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+
+    pub conversation_id: String,
+    pub flow_id: String,
+    pub step_id: String,
+    pub payload: String,
+
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_errors"]
+pub struct NewConversationError<'a> {
+    pub id: Uuid,
+    pub bot_id: &'a str,
+    pub channel_id: &'a str,
+    pub user_id: &'a str,
+
+    pub conversation_id: &'a str,
+    pub flow_id: &'a str,
+    pub step_id: &'a str,
+    pub payload: String,
+
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_outbox"]
+pub struct OutboxMessage {
+    pub id: Uuid,
+
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+
+    pub callback_url: String,
+    pub payload: String,
+    pub status: String,
+
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_outbox"]
+pub struct NewOutboxMessage<'a> {
+    pub id: Uuid,
+    pub bot_id: &'a str,
+    pub channel_id: &'a str,
+    pub user_id: &'a str,
+
+    pub callback_url: &'a str,
+    pub payload: String,
+    pub status: &'a str,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_jobs"]
+pub struct NewJob<'a> {
+    pub name: &'a str,
+    pub owner: &'a str,
+    pub locked_until: NaiveDateTime,
+    pub last_run_at: Option<NaiveDateTime>,
+}
+
 
 
 
@@ -0,0 +1,121 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{
+    db_connectors::DbOutboxMessage,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, PostgresqlClient,
+};
+
+use super::{models, schema::csml_outbox, with_retry};
+use chrono::{DateTime, Utc};
+
+pub fn create_outbox_message(
+    outbox_message: &DbOutboxMessage,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let id = uuid::Uuid::parse_str(&outbox_message.id)
+        .map_err(|err| EngineError::Format(format!("invalid outbox message id: {}", err)))?;
+
+    let new_outbox_message = models::NewOutboxMessage {
+        id,
+        bot_id: &outbox_message.client.bot_id,
+        channel_id: &outbox_message.client.channel_id,
+        user_id: &outbox_message.client.user_id,
+        callback_url: &outbox_message.callback_url,
+        payload: encrypt_data(&outbox_message.payload)?,
+        status: "PENDING",
+    };
+
+    with_retry(|| {
+        diesel::insert_into(csml_outbox::table)
+            .values(&new_outbox_message)
+            .execute(&db.client)
+    })?;
+
+    Ok(())
+}
+
+pub fn get_pending_outbox_messages(
+    before: DateTime<Utc>,
+    limit: i64,
+    db: &PostgresqlClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let outbox_messages: Vec<models::OutboxMessage> = csml_outbox::table
+        .filter(csml_outbox::status.eq("PENDING"))
+        .filter(csml_outbox::created_at.lt(before.naive_utc()))
+        .order_by(csml_outbox::created_at.asc())
+        .limit(limit)
+        .load(&db.client)?;
+
+    outbox_messages
+        .into_iter()
+        .map(|outbox_message| {
+            Ok(DbOutboxMessage {
+                id: outbox_message.id.to_string(),
+                client: Client {
+                    bot_id: outbox_message.bot_id,
+                    channel_id: outbox_message.channel_id,
+                    user_id: outbox_message.user_id,
+                    tenant_id: None,
+                },
+                callback_url: outbox_message.callback_url,
+                payload: decrypt_data(outbox_message.payload)?,
+                created_at: outbox_message
+                    .created_at
+                    .format("%Y-%m-%dT%H:%M:%S%.fZ")
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn set_outbox_message_sent(id: &str, db: &PostgresqlClient) -> Result<bool, EngineError> {
+    let id = uuid::Uuid::parse_str(id)
+        .map_err(|err| EngineError::Format(format!("invalid outbox message id: {}", err)))?;
+
+    let updated = with_retry(|| {
+        diesel::update(
+            csml_outbox::table
+                .filter(csml_outbox::id.eq(id))
+                .filter(csml_outbox::status.eq("PENDING")),
+        )
+        .set(csml_outbox::status.eq("SENT"))
+        .execute(&db.client)
+    })?;
+
+    Ok(updated > 0)
+}
+
+pub fn delete_outbox_messages(
+    before: DateTime<Utc>,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    diesel::delete(csml_outbox::table.filter(csml_outbox::created_at.lt(before.naive_utc())))
+        .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_client_outbox_messages(
+    client: &Client,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_outbox::table
+            .filter(csml_outbox::bot_id.eq(&client.bot_id))
+            .filter(csml_outbox::channel_id.eq(&client.channel_id))
+            .filter(csml_outbox::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)
+    .ok();
+
+    Ok(())
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &PostgresqlClient) -> Result<(), EngineError> {
+    diesel::delete(csml_outbox::table.filter(csml_outbox::bot_id.eq(bot_id)))
+        .execute(&db.client)
+        .ok();
+
+    Ok(())
+}
@@ -22,6 +22,9 @@ table! {
         updated_at -> Timestamp,
         created_at -> Timestamp,
         expires_at -> Nullable<Timestamp>,
+        tags -> Nullable<Varchar>,
+        summary -> Nullable<Text>,
+        version -> Integer,
     }
 }
 
@@ -36,6 +39,7 @@ table! {
         expires_at -> Nullable<Timestamp>,
         updated_at -> Timestamp,
         created_at -> Timestamp,
+        search_terms -> Nullable<Text>,
     }
 }
 
@@ -53,6 +57,10 @@ table! {
         updated_at -> Timestamp,
         created_at -> Timestamp,
         expires_at -> Nullable<Timestamp>,
+        reference_id -> Nullable<Text>,
+        request_id -> Nullable<Text>,
+        bot_version_id -> Nullable<Text>,
+        search_terms -> Nullable<Text>,
     }
 }
 
@@ -72,12 +80,68 @@ table! {
     }
 }
 
+table! {
+    csml_scheduled_events (id) {
+        id -> Uuid,
+        bot_id -> Varchar,
+        channel_id -> Varchar,
+        user_id -> Varchar,
+        event -> Varchar,
+        run_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    csml_jobs (name) {
+        name -> Varchar,
+        owner -> Varchar,
+        locked_until -> Timestamp,
+        last_run_at -> Nullable<Timestamp>,
+        last_error -> Nullable<Varchar>,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    csml_errors (id) {
+        id -> Uuid,
+        bot_id -> Varchar,
+        channel_id -> Varchar,
+        user_id -> Varchar,
+        conversation_id -> Varchar,
+        flow_id -> Varchar,
+        step_id -> Varchar,
+        payload -> Varchar,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    csml_outbox (id) {
+        id -> Uuid,
+        bot_id -> Varchar,
+        channel_id -> Varchar,
+        user_id -> Varchar,
+        callback_url -> Varchar,
+        payload -> Varchar,
+        status -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 joinable!(csml_messages -> csml_conversations (conversation_id));
 
 allow_tables_to_appear_in_same_query!(
     cmsl_bot_versions,
     csml_conversations,
+    csml_errors,
+    csml_jobs,
     csml_memories,
     csml_messages,
+    csml_outbox,
+    csml_scheduled_events,
     csml_states,
 );
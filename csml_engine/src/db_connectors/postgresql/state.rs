@@ -1,4 +1,8 @@
 use diesel::{RunQueryDsl, ExpressionMethods, QueryDsl};
+use diesel::dsl::now;
+use diesel::sql_query;
+use diesel::sql_types;
+use diesel::pg::upsert::excluded;
 
 use crate::{
     encrypt::{decrypt_data, encrypt_data},
@@ -8,7 +12,8 @@ use crate::{
 
 use super::{
     models,
-    schema::csml_states
+    schema::csml_states,
+    with_retry
 };
 use chrono::{NaiveDateTime};
 
@@ -18,13 +23,15 @@ pub fn delete_state_key(
     key: &str,
     db: &PostgresqlClient,
 ) -> Result<(), EngineError> {
-    diesel::delete(csml_states::table
-        .filter(csml_states::bot_id.eq(&client.bot_id))
-        .filter(csml_states::channel_id.eq(&client.channel_id))
-        .filter(csml_states::user_id.eq(&client.user_id))
-        .filter(csml_states::type_.eq(type_))
-        .filter(csml_states::key.eq(key))
-    ).execute(&db.client)?;
+    with_retry(|| {
+        diesel::delete(csml_states::table
+            .filter(csml_states::bot_id.eq(&client.bot_id))
+            .filter(csml_states::channel_id.eq(&client.channel_id))
+            .filter(csml_states::user_id.eq(&client.user_id))
+            .filter(csml_states::type_.eq(type_))
+            .filter(csml_states::key.eq(key))
+        ).execute(&db.client)
+    })?;
 
     Ok(())
 }
@@ -116,13 +123,84 @@ pub fn set_state_items(
         new_states.push(mem);
     }
 
-    diesel::insert_into(csml_states::table)
-    .values(&new_states)
-    .execute(&db.client)?;
+    // the items already saved are replaced
+    with_retry(|| {
+        diesel::insert_into(csml_states::table)
+        .values(&new_states)
+        .on_conflict((csml_states::bot_id, csml_states::channel_id, csml_states::user_id, csml_states::type_, csml_states::key))
+        .do_update()
+        .set((
+            csml_states::value.eq(excluded(csml_states::value)),
+            csml_states::expires_at.eq(excluded(csml_states::expires_at)),
+            csml_states::updated_at.eq(now),
+        ))
+        .execute(&db.client)
+    })?;
 
     Ok(())
 }
 
+pub fn replace_state_item(
+    client: &Client,
+    type_: &str,
+    key: &str,
+    value: &serde_json::Value,
+    expires_at: Option<NaiveDateTime>,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    set_state_items(client, type_, vec![(key, value)], expires_at, db)
+}
+
+pub fn increment_state_counter(
+    client: &Client,
+    type_: &str,
+    key: &str,
+    n: i64,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    with_retry(|| {
+        sql_query("
+            INSERT INTO csml_states (id, bot_id, channel_id, user_id, type, key, value)
+                VALUES($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT(bot_id, channel_id, user_id, type, key)
+                DO UPDATE SET value=CAST(CAST(csml_states.value AS BIGINT) + CAST(excluded.value AS BIGINT) AS VARCHAR),
+                    updated_at=CURRENT_TIMESTAMP;
+        ")
+        .bind::<sql_types::Uuid, _>(uuid::Uuid::new_v4())
+        .bind::<sql_types::VarChar, _>(&client.bot_id)
+        .bind::<sql_types::VarChar, _>(&client.channel_id)
+        .bind::<sql_types::VarChar, _>(&client.user_id)
+        .bind::<sql_types::VarChar, _>(type_)
+        .bind::<sql_types::VarChar, _>(key)
+        .bind::<sql_types::VarChar, _>(n.to_string())
+        .execute(&db.client)
+    })?;
+
+    Ok(())
+}
+
+pub fn get_state_counter(
+    client: &Client,
+    type_: &str,
+    key: &str,
+    db: &PostgresqlClient,
+) -> Result<i64, EngineError> {
+    let state: Result<models::State, diesel::result::Error> = csml_states::table
+    .filter(csml_states::bot_id.eq(&client.bot_id))
+    .filter(csml_states::channel_id.eq(&client.channel_id))
+    .filter(csml_states::user_id.eq(&client.user_id))
+
+    .filter(csml_states::type_.eq(type_))
+    .filter(csml_states::key.eq(key))
+
+    .get_result(&db.client);
+
+    match state {
+        Ok(state) => Ok(state.value.parse().unwrap_or(0)),
+        Err(_err) => Ok(0),
+    }
+}
+
 pub fn delete_user_state(
     client: &Client,
     db: &PostgresqlClient
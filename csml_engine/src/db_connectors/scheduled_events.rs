@@ -0,0 +1,230 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use crate::db_connectors::{utils::get_storage_client, DbScheduledEvent};
+use crate::data::DbErrorKind;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Database, EngineError};
+use chrono::{DateTime, Utc};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+pub fn create_scheduled_event(
+    client: &Client,
+    id: &str,
+    event: &serde_json::Value,
+    run_at: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call create scheduled event: {:?}, run at: {:?}", id, run_at),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!(
+                "db call create scheduled event: {:?}, run at: {:?}, event: {:?}",
+                id, run_at, event
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::scheduled_events::create_scheduled_event(
+            client, id, event, run_at, db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::scheduled_events::create_scheduled_event(
+            client, id, event, run_at, db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::scheduled_events::create_scheduled_event(
+            client, id, event, run_at, db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::scheduled_events::create_scheduled_event(
+            client, id, event, run_at, db,
+        );
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Return at most `limit` scheduled events that should have run before `now`, oldest first
+ */
+pub fn get_due_scheduled_events(
+    now: DateTime<Utc>,
+    limit: i64,
+    db: &mut Database,
+) -> Result<Vec<DbScheduledEvent>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get due scheduled events, now: {:?}", now),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::scheduled_events::get_due_scheduled_events(now, limit, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::scheduled_events::get_due_scheduled_events(now, limit, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::scheduled_events::get_due_scheduled_events(now, limit, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::scheduled_events::get_due_scheduled_events(now, limit, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Delete a scheduled event. Returns false if the event was already deleted,
+ * which means that another worker has already claimed it.
+ */
+pub fn delete_scheduled_event(
+    scheduled_event: &DbScheduledEvent,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call delete scheduled event: {:?}", scheduled_event.id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(&scheduled_event.client),
+            None,
+            None,
+            format!("db call delete scheduled event: {:?}", scheduled_event.id),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::scheduled_events::delete_scheduled_event(&scheduled_event.id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::scheduled_events::delete_scheduled_event(scheduled_event, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::scheduled_events::delete_scheduled_event(
+            &scheduled_event.id,
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::scheduled_events::delete_scheduled_event(&scheduled_event.id, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_scheduled_events() {
+        let client = Client {
+            bot_id: "bot_id".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "scheduled_events_test".to_owned(),
+            tenant_id: None,
+        };
+        let mut db = init_db().unwrap();
+
+        let now = Utc::now();
+        let due_id = uuid::Uuid::new_v4().to_string();
+        let later_id = uuid::Uuid::new_v4().to_string();
+        let event = serde_json::json!({
+            "payload": {"content_type": "text", "content": {"text": "reminder"}},
+        });
+
+        create_scheduled_event(&client, &due_id, &event, now - chrono::Duration::seconds(10), &mut db)
+            .unwrap();
+        create_scheduled_event(&client, &later_id, &event, now + chrono::Duration::hours(2), &mut db)
+            .unwrap();
+
+        let due: Vec<DbScheduledEvent> = get_due_scheduled_events(now, 100, &mut db)
+            .unwrap()
+            .into_iter()
+            .filter(|scheduled_event| scheduled_event.client.user_id == client.user_id)
+            .collect();
+
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, due_id);
+        assert_eq!(due[0].event, event);
+
+        assert!(delete_scheduled_event(&due[0], &mut db).unwrap());
+        assert!(!delete_scheduled_event(&due[0], &mut db).unwrap());
+
+        let due = get_due_scheduled_events(now + chrono::Duration::hours(3), 100, &mut db).unwrap();
+        for scheduled_event in due.iter().filter(|event| event.client.user_id == client.user_id) {
+            delete_scheduled_event(scheduled_event, &mut db).unwrap();
+        }
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use crate::data::DbErrorKind;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Database, EngineError};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+/**
+ * Text searched in the messages or memories of a bot, created between the unix timestamps
+ * `from` and `to` (in seconds), most recent first
+ */
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub bot_id: String,
+    pub text: String,
+    // blind index of the words of the text when the values are encrypted (see encrypt.rs)
+    pub terms: Option<Vec<String>>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub limit: i64,
+}
+
+impl SearchQuery {
+    /**
+     * LIKE pattern of the text, with its wildcards escaped by a backslash
+     */
+    pub fn like_pattern(&self) -> String {
+        let text = self
+            .text
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        format!("%{}%", text)
+    }
+
+    /**
+     * LIKE patterns of the terms of the blind index, saved between spaces (see format_search_terms)
+     */
+    pub fn term_patterns(&self) -> Option<Vec<String>> {
+        self.terms.as_ref().map(|terms| {
+            terms
+                .iter()
+                .map(|term| format!("% {} %", term))
+                .collect()
+        })
+    }
+}
+
+/**
+ * Terms of the blind index of a value as saved in a text column: " term term "
+ */
+pub fn format_search_terms(terms: Option<Vec<String>>) -> Option<String> {
+    terms.map(|terms| format!(" {} ", terms.join(" ")))
+}
+
+pub fn search_messages(
+    query: &SearchQuery,
+    db: &mut Database,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call search messages of bot: {:?}", query.bot_id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::search::search_messages(query, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        // the items of the table are searched in their OpenSearch index
+        dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::search::search_messages(query);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::search::search_messages(query, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::search::search_messages(query, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn search_memories(
+    query: &SearchQuery,
+    db: &mut Database,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call search memories of bot: {:?}", query.bot_id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::search::search_memories(query, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        // the items of the table are searched in their OpenSearch index
+        dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::search::search_memories(query);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::search::search_memories(query, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::search::search_memories(query, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
@@ -0,0 +1,102 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{
+    db_connectors::DbConversationError,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, SqliteClient,
+};
+
+use super::{models, schema::csml_errors};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+pub fn create_conversation_error(
+    error: &DbConversationError,
+    created_at: DateTime<Utc>,
+    expires_at: Option<NaiveDateTime>,
+    db: &SqliteClient,
+) -> Result<(), EngineError> {
+    let id = models::UUID::parse_str(&error.id)
+        .map_err(|err| EngineError::Format(format!("invalid error id: {}", err)))?;
+
+    let new_error = models::NewConversationError {
+        id,
+        bot_id: &error.client.bot_id,
+        channel_id: &error.client.channel_id,
+        user_id: &error.client.user_id,
+        conversation_id: &error.conversation_id,
+        flow_id: &error.flow_id,
+        step_id: &error.step_id,
+        payload: encrypt_data(&error.error)?,
+        created_at: created_at.naive_utc(),
+        expires_at,
+    };
+
+    diesel::insert_into(csml_errors::table)
+        .values(&new_error)
+        .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn get_bot_errors(
+    bot_id: &str,
+    limit: i64,
+    before: Option<DateTime<Utc>>,
+    db: &SqliteClient,
+) -> Result<Vec<DbConversationError>, EngineError> {
+    let mut query = csml_errors::table
+        .filter(csml_errors::bot_id.eq(bot_id))
+        .into_boxed();
+    if let Some(before) = before {
+        query = query.filter(csml_errors::created_at.lt(before.naive_utc()));
+    }
+
+    let errors: Vec<models::ConversationError> = query
+        .order_by(csml_errors::created_at.desc())
+        .limit(limit)
+        .load(&db.client)?;
+
+    errors
+        .into_iter()
+        .map(|error| {
+            Ok(DbConversationError {
+                id: error.id.to_string(),
+                client: Client {
+                    bot_id: error.bot_id,
+                    channel_id: error.channel_id,
+                    user_id: error.user_id,
+                    tenant_id: None,
+                },
+                conversation_id: error.conversation_id,
+                flow_id: error.flow_id,
+                step_id: error.step_id,
+                error: decrypt_data(error.payload)?,
+                created_at: error
+                    .created_at
+                    .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn delete_client_errors(client: &Client, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_errors::table
+            .filter(csml_errors::bot_id.eq(&client.bot_id))
+            .filter(csml_errors::channel_id.eq(&client.channel_id))
+            .filter(csml_errors::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)
+    .ok();
+
+    Ok(())
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(csml_errors::table.filter(csml_errors::bot_id.eq(bot_id)))
+        .execute(&db.client)
+        .ok();
+
+    Ok(())
+}
@@ -2,9 +2,11 @@ use diesel::{RunQueryDsl, ExpressionMethods, QueryDsl};
 use diesel::sql_query;
 use diesel::sql_types;
 
+use crate::db_connectors::utils::get_storage_client;
 use crate::{
     db_connectors::sqlite::get_db,
-    encrypt::{decrypt_data, encrypt_data},
+    db_connectors::search::format_search_terms,
+    encrypt::{decrypt_data, encrypt_data, get_search_terms},
     EngineError, SqliteClient,
     ConversationInfo, Memory, Client,
 };
@@ -29,7 +31,7 @@ pub fn add_memories(
     let db = get_db(&data.db)?;
 
     for (key, mem) in memories.iter() {
-        create_client_memory(&data.client, key, &mem.value, expires_at, db)?;
+        create_client_memory(&get_storage_client(&data.client), key, &mem.value, expires_at, db)?;
     }
 
     Ok(())
@@ -43,14 +45,15 @@ pub fn create_client_memory(
     db: &SqliteClient,
 ) -> Result<(), EngineError> {
 
+    let search_terms = format_search_terms(get_search_terms(value)?);
     let value = encrypt_data(&value)?;
 
     if let Some(expires_at) = expires_at {
         sql_query("
-            INSERT INTO csml_memories (id, bot_id, channel_id, user_id, key, value, expires_at)
-                VALUES(?, ?, ?, ?, ?, ?)
+            INSERT INTO csml_memories (id, bot_id, channel_id, user_id, key, value, search_terms, expires_at)
+                VALUES(?, ?, ?, ?, ?, ?, ?, ?)
                 ON CONFLICT(bot_id, channel_id, user_id, key)
-                DO UPDATE SET key=excluded.value;
+                DO UPDATE SET value=excluded.value, search_terms=excluded.search_terms;
         ")
         .bind::<sql_types::Binary, _>(models::UUID::new_v4())
         .bind::<sql_types::VarChar, _>(&client.bot_id)
@@ -58,15 +61,16 @@ pub fn create_client_memory(
         .bind::<sql_types::VarChar, _>(&client.user_id)
         .bind::<sql_types::VarChar, _>(key)
         .bind::<sql_types::VarChar, _>(value.clone())
+        .bind::<sql_types::Nullable<sql_types::VarChar>, _>(search_terms.clone())
         .bind::<sql_types::Timestamp, _>(expires_at)
         .execute(&db.client)?;
     }
     else {
         sql_query("
-            INSERT INTO csml_memories (id, bot_id, channel_id, user_id, key, value)
-                VALUES(?, ?, ?, ?, ?, ?)
+            INSERT INTO csml_memories (id, bot_id, channel_id, user_id, key, value, search_terms)
+                VALUES(?, ?, ?, ?, ?, ?, ?)
                 ON CONFLICT(bot_id, channel_id, user_id, key)
-                DO UPDATE SET value=excluded.value;
+                DO UPDATE SET value=excluded.value, search_terms=excluded.search_terms;
         ")
         .bind::<sql_types::Binary, _>(models::UUID::new_v4())
         .bind::<sql_types::VarChar, _>(&client.bot_id)
@@ -74,6 +78,7 @@ pub fn create_client_memory(
         .bind::<sql_types::VarChar, _>(&client.user_id)
         .bind::<sql_types::VarChar, _>(key)
         .bind::<sql_types::VarChar, _>(value.clone())
+        .bind::<sql_types::Nullable<sql_types::VarChar>, _>(search_terms.clone())
         .execute(&db.client)?;
     }
 
@@ -1,7 +1,13 @@
 pub mod bot;
 pub mod conversations;
+pub mod diagnostics;
+pub mod errors;
+pub mod jobs;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod scheduled_events;
+pub mod search;
 pub mod state;
 
 pub mod pagination;
@@ -12,18 +18,15 @@ pub mod schema;
 
 pub mod expired_data;
 
-use crate::{Database, EngineError, SqliteClient};
+use crate::{config::Config, data::DbErrorKind, Database, EngineError, SqliteClient};
 
 use diesel::prelude::*;
 
 embed_migrations!("migrations/sqlite");
 
-pub fn init() -> Result<Database, EngineError> {
+pub fn init(config: &Config) -> Result<Database, EngineError> {
 
-    let uri = match std::env::var("SQLITE_URL") {
-        Ok(var) => var,
-        _ => "".to_owned(),
-    };
+    let uri = config.text("database.sqlite.url").unwrap_or_default();
 
     let sqlite_connection = SqliteConnection::establish(&uri)
         .unwrap_or_else(|_| panic!("Error connecting to {}", uri));
@@ -34,11 +37,8 @@ pub fn init() -> Result<Database, EngineError> {
     Ok(db)
 }
 
-pub fn make_migrations() -> Result<(), EngineError> {
-    let uri = match std::env::var("SQLITE_URL") {
-        Ok(var) => var,
-        _ => "".to_owned(),
-    };
+pub fn make_migrations(config: &Config) -> Result<(), EngineError> {
+    let uri = config.text("database.sqlite.url").unwrap_or_default();
 
     let sqlite_connection = SqliteConnection::establish(&uri)
         .unwrap_or_else(|_| panic!("Error connecting to {}", uri));
@@ -51,7 +51,8 @@ pub fn make_migrations() -> Result<(), EngineError> {
 pub fn get_db<'a>(db: &'a Database) -> Result<&'a SqliteClient, EngineError> {
     match db {
         Database::SqLite(db) => Ok(db),
-        _ => Err(EngineError::Manager(
+        _ => Err(EngineError::Db(
+            DbErrorKind::Setup,
             "SqLite connector is not setup correctly".to_owned(),
         )),
     }
@@ -0,0 +1,105 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{
+    db_connectors::DbScheduledEvent,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, SqliteClient,
+};
+
+use super::{models, schema::csml_scheduled_events};
+use chrono::{DateTime, Utc};
+
+pub fn create_scheduled_event(
+    client: &Client,
+    id: &str,
+    event: &serde_json::Value,
+    run_at: DateTime<Utc>,
+    db: &SqliteClient,
+) -> Result<(), EngineError> {
+    let id = models::UUID::parse_str(id)
+        .map_err(|err| EngineError::Format(format!("invalid scheduled event id: {}", err)))?;
+
+    let scheduled_event = models::NewScheduledEvent {
+        id,
+        bot_id: &client.bot_id,
+        channel_id: &client.channel_id,
+        user_id: &client.user_id,
+        event: encrypt_data(event)?,
+        run_at: run_at.naive_utc(),
+    };
+
+    diesel::insert_into(csml_scheduled_events::table)
+        .values(&scheduled_event)
+        .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn get_due_scheduled_events(
+    now: DateTime<Utc>,
+    limit: i64,
+    db: &SqliteClient,
+) -> Result<Vec<DbScheduledEvent>, EngineError> {
+    let scheduled_events: Vec<models::ScheduledEvent> = csml_scheduled_events::table
+        .filter(csml_scheduled_events::run_at.le(now.naive_utc()))
+        .order_by(csml_scheduled_events::run_at.asc())
+        .limit(limit)
+        .load(&db.client)?;
+
+    scheduled_events
+        .into_iter()
+        .map(|scheduled_event| {
+            Ok(DbScheduledEvent {
+                id: scheduled_event.id.to_string(),
+                client: Client {
+                    bot_id: scheduled_event.bot_id,
+                    channel_id: scheduled_event.channel_id,
+                    user_id: scheduled_event.user_id,
+                    tenant_id: None,
+                },
+                event: decrypt_data(scheduled_event.event)?,
+                run_at: scheduled_event
+                    .run_at
+                    .format("%Y-%m-%dT%H:%M:%S%.fZ")
+                    .to_string(),
+                created_at: scheduled_event
+                    .created_at
+                    .format("%Y-%m-%dT%H:%M:%S%.fZ")
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn delete_scheduled_event(id: &str, db: &SqliteClient) -> Result<bool, EngineError> {
+    let id = models::UUID::parse_str(id)
+        .map_err(|err| EngineError::Format(format!("invalid scheduled event id: {}", err)))?;
+
+    let deleted = diesel::delete(
+        csml_scheduled_events::table.filter(csml_scheduled_events::id.eq(id)),
+    )
+    .execute(&db.client)?;
+
+    Ok(deleted > 0)
+}
+
+pub fn delete_client_scheduled_events(client: &Client, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_scheduled_events::table
+            .filter(csml_scheduled_events::bot_id.eq(&client.bot_id))
+            .filter(csml_scheduled_events::channel_id.eq(&client.channel_id))
+            .filter(csml_scheduled_events::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)
+    .ok();
+
+    Ok(())
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(csml_scheduled_events::table.filter(csml_scheduled_events::bot_id.eq(bot_id)))
+        .execute(&db.client)
+        .ok();
+
+    Ok(())
+}
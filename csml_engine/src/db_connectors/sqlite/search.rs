@@ -0,0 +1,113 @@
+use diesel::expression_methods::EscapeExpressionMethods;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, TextExpressionMethods};
+
+use crate::{db_connectors::search::SearchQuery, encrypt::decrypt_data, EngineError, SqliteClient};
+
+use super::{
+    models,
+    schema::{csml_conversations, csml_memories, csml_messages},
+};
+use chrono::NaiveDateTime;
+
+fn get_date_range(query: &SearchQuery) -> (NaiveDateTime, NaiveDateTime) {
+    let from = NaiveDateTime::from_timestamp(query.from.unwrap_or(0), 0);
+    let to = match query.to {
+        Some(to) => NaiveDateTime::from_timestamp(to, 0),
+        None => chrono::Utc::now().naive_utc(),
+    };
+
+    (from, to)
+}
+
+pub fn search_messages(
+    query: &SearchQuery,
+    db: &SqliteClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let (from, to) = get_date_range(query);
+
+    let mut messages = csml_conversations::table
+        .filter(csml_conversations::bot_id.eq(&query.bot_id))
+        .inner_join(csml_messages::table)
+        .filter(csml_messages::created_at.ge(from))
+        .filter(csml_messages::created_at.le(to))
+        .select((csml_conversations::all_columns, csml_messages::all_columns))
+        .into_boxed();
+
+    messages = match query.term_patterns() {
+        // the encrypted payloads are searched with their blind index
+        Some(patterns) => patterns.into_iter().fold(messages, |messages, pattern| {
+            messages.filter(csml_messages::search_terms.like(pattern))
+        }),
+        // SQLite compares the ASCII characters case insensitively
+        None => messages.filter(
+            csml_messages::payload
+                .like(query.like_pattern())
+                .escape('\\'),
+        ),
+    };
+
+    let results: Vec<(models::Conversation, models::Message)> = messages
+        .order_by(csml_messages::created_at.desc())
+        .limit(query.limit)
+        .load(&db.client)?;
+
+    results
+        .into_iter()
+        .map(|(conversation, message)| {
+            Ok(serde_json::json!({
+                "client": {
+                    "bot_id": conversation.bot_id,
+                    "channel_id": conversation.channel_id,
+                    "user_id": conversation.user_id
+                },
+                "conversation_id": message.conversation_id.get_uuid(),
+                "flow_id": message.flow_id,
+                "step_id": message.step_id,
+                "direction": message.direction,
+                "payload": decrypt_data(message.payload)?,
+                "created_at": message.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
+            }))
+        })
+        .collect()
+}
+
+pub fn search_memories(
+    query: &SearchQuery,
+    db: &SqliteClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let (from, to) = get_date_range(query);
+
+    let mut memories = csml_memories::table
+        .filter(csml_memories::bot_id.eq(&query.bot_id))
+        .filter(csml_memories::created_at.ge(from))
+        .filter(csml_memories::created_at.le(to))
+        .into_boxed();
+
+    memories = match query.term_patterns() {
+        Some(patterns) => patterns.into_iter().fold(memories, |memories, pattern| {
+            memories.filter(csml_memories::search_terms.like(pattern))
+        }),
+        None => memories.filter(csml_memories::value.like(query.like_pattern()).escape('\\')),
+    };
+
+    let memories: Vec<models::Memory> = memories
+        .order_by(csml_memories::created_at.desc())
+        .limit(query.limit)
+        .load(&db.client)?;
+
+    memories
+        .into_iter()
+        .map(|memory| {
+            Ok(serde_json::json!({
+                "client": {
+                    "bot_id": memory.bot_id,
+                    "channel_id": memory.channel_id,
+                    "user_id": memory.user_id
+                },
+                "key": memory.key,
+                "value": decrypt_data(memory.value)?,
+                "created_at": memory.created_at.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
+            }))
+        })
+        .collect()
+}
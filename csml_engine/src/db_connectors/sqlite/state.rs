@@ -1,4 +1,6 @@
 use diesel::{RunQueryDsl, ExpressionMethods, QueryDsl};
+use diesel::sql_query;
+use diesel::sql_types;
 
 use crate::{
     encrypt::{decrypt_data, encrypt_data},
@@ -85,6 +87,35 @@ pub fn get_current_state(
     Ok(Some(current_state))
 }
 
+pub fn replace_state_item(
+    client: &Client,
+    type_: &str,
+    key: &str,
+    value: &serde_json::Value,
+    expires_at: Option<NaiveDateTime>,
+    db: &SqliteClient,
+) -> Result<(), EngineError> {
+    let value = encrypt_data(value)?;
+
+    sql_query("
+        INSERT INTO csml_states (id, bot_id, channel_id, user_id, type, key, value, expires_at)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(bot_id, channel_id, user_id, type, key)
+            DO UPDATE SET value=excluded.value, expires_at=excluded.expires_at, updated_at=CURRENT_TIMESTAMP;
+    ")
+    .bind::<sql_types::Binary, _>(models::UUID::new_v4())
+    .bind::<sql_types::VarChar, _>(&client.bot_id)
+    .bind::<sql_types::VarChar, _>(&client.channel_id)
+    .bind::<sql_types::VarChar, _>(&client.user_id)
+    .bind::<sql_types::VarChar, _>(type_)
+    .bind::<sql_types::VarChar, _>(key)
+    .bind::<sql_types::VarChar, _>(value)
+    .bind::<sql_types::Nullable<sql_types::Timestamp>, _>(expires_at)
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
 pub fn set_state_items(
     client: &Client,
     type_: &str,
@@ -92,35 +123,59 @@ pub fn set_state_items(
     expires_at: Option<NaiveDateTime>,
     db: &SqliteClient,
 ) -> Result<(), EngineError> {
-    if keys_values.len() == 0 {
-        return Ok(());
+    for (key, value) in keys_values.iter() {
+        replace_state_item(client, type_, key, value, expires_at, db)?;
     }
 
-    let mut new_states = vec!();
-    for (key, value) in keys_values.iter() {
+    Ok(())
+}
 
-        let value = encrypt_data(value)?;
+pub fn increment_state_counter(
+    client: &Client,
+    type_: &str,
+    key: &str,
+    n: i64,
+    db: &SqliteClient,
+) -> Result<(), EngineError> {
+    sql_query("
+        INSERT INTO csml_states (id, bot_id, channel_id, user_id, type, key, value)
+            VALUES(?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(bot_id, channel_id, user_id, type, key)
+            DO UPDATE SET value=CAST(CAST(csml_states.value AS INTEGER) + CAST(excluded.value AS INTEGER) AS TEXT),
+                updated_at=CURRENT_TIMESTAMP;
+    ")
+    .bind::<sql_types::Binary, _>(models::UUID::new_v4())
+    .bind::<sql_types::VarChar, _>(&client.bot_id)
+    .bind::<sql_types::VarChar, _>(&client.channel_id)
+    .bind::<sql_types::VarChar, _>(&client.user_id)
+    .bind::<sql_types::VarChar, _>(type_)
+    .bind::<sql_types::VarChar, _>(key)
+    .bind::<sql_types::VarChar, _>(n.to_string())
+    .execute(&db.client)?;
 
-        let mem = models::NewState {
-            id: models::UUID::new_v4(),
+    Ok(())
+}
 
-            bot_id: &client.bot_id,
-            channel_id: &client.channel_id,
-            user_id: &client.user_id,
-            type_,
-            key,
-            value,
-            expires_at,
-        };
+pub fn get_state_counter(
+    client: &Client,
+    type_: &str,
+    key: &str,
+    db: &SqliteClient,
+) -> Result<i64, EngineError> {
+    let state: Result<models::State, diesel::result::Error> = csml_states::table
+    .filter(csml_states::bot_id.eq(&client.bot_id))
+    .filter(csml_states::channel_id.eq(&client.channel_id))
+    .filter(csml_states::user_id.eq(&client.user_id))
 
-        new_states.push(mem);
-    }
+    .filter(csml_states::type_.eq(type_))
+    .filter(csml_states::key.eq(key))
 
-    diesel::insert_into(csml_states::table)
-    .values(&new_states)
-    .execute(&db.client)?;
+    .get_result(&db.client);
 
-    Ok(())
+    match state {
+        Ok(state) => Ok(state.value.parse().unwrap_or(0)),
+        Err(_err) => Ok(0),
+    }
 }
 
 pub fn delete_user_state(
@@ -9,7 +9,9 @@ use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 
 use csml_interpreter::data::csml_logs::{LogLvl, CsmlLog, csml_logger};
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
+use crate::telemetry;
 use crate::{Database, EngineError};
 use crate::db_connectors::utils::*;
 use csml_interpreter::data::Client;
@@ -20,6 +22,10 @@ pub fn delete_state_key(
     key: &str,
     db: &mut Database,
 ) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("delete_state_key");
+
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -63,7 +69,7 @@ pub fn delete_state_key(
         return sqlite_connector::state::delete_state_key(client, _type, key, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_state_key(
@@ -72,6 +78,10 @@ pub fn get_state_key(
     _key: &str,
     db: &mut Database,
 ) -> Result<Option<serde_json::Value>, EngineError> {
+    let _span = telemetry::db_span("get_state_key");
+
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -115,13 +125,15 @@ pub fn get_state_key(
         return sqlite_connector::state::get_state_key(client, _type, _key, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn get_current_state(
     client: &Client,
     db: &mut Database,
 ) -> Result<Option<serde_json::Value>, EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -165,7 +177,7 @@ pub fn get_current_state(
         return sqlite_connector::state::get_current_state(client, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 pub fn set_state_items(
@@ -175,6 +187,10 @@ pub fn set_state_items(
     ttl: Option<chrono::Duration>,
     _db: &mut Database,
 ) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("set_state_items");
+
+    let _client = &get_storage_client(_client);
+
     csml_logger(
         CsmlLog::new(
             None,
@@ -226,7 +242,178 @@ pub fn set_state_items(
         return sqlite_connector::state::set_state_items(_client, _type, _keys_values, expires_at, db);
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Save the value of a state item, in place of its previous value
+ */
+pub fn replace_state_item(
+    _client: &Client,
+    _type: &str,
+    _key: &str,
+    _value: &serde_json::Value,
+    ttl: Option<chrono::Duration>,
+    _db: &mut Database,
+) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("replace_state_item");
+
+    let _client = &get_storage_client(_client);
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call replace state key: {:?}, type: {:?}", _key, _type)
+        ),
+        LogLvl::Info
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(_client),
+            None,
+            None,
+            format!("db call replace state key: {:?}, type: {:?}, value: {:?}", _key, _type, _value)
+        ),
+        LogLvl::Debug
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(_db)?;
+        let expires_at = get_expires_at_for_mongodb(ttl);
+
+        return mongodb_connector::state::replace_state_item(_client, _type, _key, _value, expires_at, &db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(_db)?;
+        let expires_at = get_expires_at_for_dynamodb(ttl);
+
+        return dynamodb_connector::state::replace_state_item(_client, _type, _key, _value, expires_at, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(_db)?;
+        let expires_at = get_expires_at_for_postgresql(ttl);
+
+        return postgresql_connector::state::replace_state_item(_client, _type, _key, _value, expires_at, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(_db)?;
+        let expires_at = get_expires_at_for_sqlite(ttl);
+
+        return sqlite_connector::state::replace_state_item(_client, _type, _key, _value, expires_at, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Add n to a counter of the client, created at 0. The counters are incremented by the
+ * database, so their values are saved without encryption
+ */
+pub fn increment_state_counter(
+    _client: &Client,
+    _type: &str,
+    _key: &str,
+    _n: i64,
+    _db: &mut Database,
+) -> Result<(), EngineError> {
+    let _span = telemetry::db_span("increment_state_counter");
+
+    let _client = &get_storage_client(_client);
+
+    csml_logger(
+        CsmlLog::new(
+            Some(_client),
+            None,
+            None,
+            format!("db call increment state counter: {:?}, type: {:?}, by {}", _key, _type, _n)
+        ),
+        LogLvl::Debug
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(_db)?;
+        return mongodb_connector::state::increment_state_counter(_client, _type, _key, _n, &db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(_db)?;
+        return dynamodb_connector::state::increment_state_counter(_client, _type, _key, _n, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(_db)?;
+        return postgresql_connector::state::increment_state_counter(_client, _type, _key, _n, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(_db)?;
+        return sqlite_connector::state::increment_state_counter(_client, _type, _key, _n, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Value of a counter of the client, 0 if it was never incremented
+ */
+pub fn get_state_counter(
+    _client: &Client,
+    _type: &str,
+    _key: &str,
+    _db: &mut Database,
+) -> Result<i64, EngineError> {
+    let _span = telemetry::db_span("get_state_counter");
+
+    let _client = &get_storage_client(_client);
+
+    csml_logger(
+        CsmlLog::new(
+            Some(_client),
+            None,
+            None,
+            format!("db call get state counter: {:?}, type: {:?}", _key, _type)
+        ),
+        LogLvl::Debug
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(_db)?;
+        return mongodb_connector::state::get_state_counter(_client, _type, _key, &db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(_db)?;
+        return dynamodb_connector::state::get_state_counter(_client, _type, _key, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(_db)?;
+        return postgresql_connector::state::get_state_counter(_client, _type, _key, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(_db)?;
+        return sqlite_connector::state::get_state_counter(_client, _type, _key, db);
+    }
+
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
 
 #[cfg(test)]
@@ -238,10 +425,12 @@ mod tests {
 
     #[test]
     fn ok_hold() {
+        crate::make_migrations().unwrap_or({});
         let client = Client {
             bot_id: "bot_id".to_owned(),
             channel_id: "channel_id".to_owned(),
             user_id: "test".to_owned(),
+            tenant_id: None,
         };
         let mut db = init_db().unwrap();
 
@@ -255,7 +444,8 @@ mod tests {
             step_name: "step_name".to_owned(),
             flow_name: "flow_name".to_owned(),
             previous: None,
-            secure: false
+            secure: false,
+            breakpoint: None,
         };
 
         let state_hold: serde_json::Value = serde_json::json!({
@@ -281,6 +471,15 @@ mod tests {
             panic!("db get hodl got the wrong value")
         }
 
+        // the saved item is replaced
+        let state_hold = serde_json::json!({"index": {"command_index": 7, "loop_index": []}});
+        replace_state_item(&client, "hold", "position", &state_hold, None, &mut db).unwrap();
+        set_state_items(&client, "hold", vec![("position", &state_hold)], None, &mut db).unwrap();
+        let hold = get_state_key(&client, "hold", "position", &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hold["index"]["command_index"], 7);
+
         delete_state_key(&client, "hold", "position", &mut db).unwrap();
 
         match get_state_key(&client, "hold", "position", &mut db).unwrap() {
@@ -290,4 +489,24 @@ mod tests {
             None => {}
         }
     }
+
+    #[test]
+    fn ok_state_counter() {
+        crate::make_migrations().unwrap_or({});
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "counter".to_owned(),
+        );
+        let mut db = init_db().unwrap();
+
+        delete_state_key(&client, "usage", "counter", &mut db).unwrap();
+        assert_eq!(get_state_counter(&client, "usage", "counter", &mut db).unwrap(), 0);
+
+        increment_state_counter(&client, "usage", "counter", 1, &mut db).unwrap();
+        increment_state_counter(&client, "usage", "counter", 2, &mut db).unwrap();
+        assert_eq!(get_state_counter(&client, "usage", "counter", &mut db).unwrap(), 3);
+
+        delete_state_key(&client, "usage", "counter", &mut db).unwrap();
+    }
 }
@@ -7,11 +7,15 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 #[cfg(feature = "sqlite")]
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
+use crate::db_connectors::utils::get_storage_client;
+use crate::data::DbErrorKind;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Client, Database, EngineError};
 use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
 
 pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let client = &get_storage_client(client);
+
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call delete client")),
         LogLvl::Info,
@@ -29,6 +33,9 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         mongodb_connector::memories::delete_client_memories(client, db)?;
         mongodb_connector::messages::delete_user_messages(client, db)?;
         mongodb_connector::state::delete_user_state(client, db)?;
+        mongodb_connector::scheduled_events::delete_client_scheduled_events(client, db)?;
+        mongodb_connector::outbox::delete_client_outbox_messages(client, db)?;
+        mongodb_connector::errors::delete_client_errors(client, db)?;
 
         return Ok(());
     }
@@ -41,6 +48,9 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         dynamodb_connector::messages::delete_user_messages(client, db)?;
         dynamodb_connector::conversations::delete_user_conversations(client, db)?;
         dynamodb_connector::state::delete_user_state(client, db)?;
+        dynamodb_connector::scheduled_events::delete_client_scheduled_events(client, db)?;
+        dynamodb_connector::outbox::delete_client_outbox_messages(client, db)?;
+        dynamodb_connector::errors::delete_client_errors(client, db)?;
 
         return Ok(());
     }
@@ -53,6 +63,9 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         postgresql_connector::memories::delete_client_memories(client, db)?;
         postgresql_connector::messages::delete_user_messages(client, db)?;
         postgresql_connector::state::delete_user_state(client, db)?;
+        postgresql_connector::scheduled_events::delete_client_scheduled_events(client, db)?;
+        postgresql_connector::outbox::delete_client_outbox_messages(client, db)?;
+        postgresql_connector::errors::delete_client_errors(client, db)?;
 
         return Ok(());
     }
@@ -65,9 +78,12 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         sqlite_connector::memories::delete_client_memories(client, db)?;
         sqlite_connector::messages::delete_user_messages(client, db)?;
         sqlite_connector::state::delete_user_state(client, db)?;
+        sqlite_connector::scheduled_events::delete_client_scheduled_events(client, db)?;
+        sqlite_connector::outbox::delete_client_outbox_messages(client, db)?;
+        sqlite_connector::errors::delete_client_errors(client, db)?;
 
         return Ok(());
     }
 
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+    Err(EngineError::Db(DbErrorKind::Setup, ERROR_DB_SETUP.to_owned()))
 }
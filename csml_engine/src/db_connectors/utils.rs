@@ -1,4 +1,10 @@
 
+use crate::config::get_config;
+use crate::error_messages::ERROR_DB_PAYLOAD_SIZE;
+use crate::{Client, EngineError};
+
+const DB_PAYLOAD_SIZE_LIMIT: usize = 300_000;
+
 #[cfg(feature = "mongo")]
 pub fn get_expires_at_for_mongodb(ttl: Option<chrono::Duration>) -> Option<bson::DateTime> {
 
@@ -46,4 +52,65 @@ pub fn get_expires_at_for_sqlite(ttl: Option<chrono::Duration>) -> Option<chrono
         },
         None => None
     }
-}
\ No newline at end of file
+}
+
+/**
+ * The tags of the SQL conversations are saved as a JSON array: LIKE pattern of a tag in this array,
+ * with its wildcards escaped by a backslash
+ */
+#[cfg(any(feature = "postgresql", feature = "sqlite"))]
+pub fn get_tag_like_pattern(tag: &str) -> String {
+    let tag = serde_json::json!(tag)
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{}%", tag)
+}
+
+/**
+ * Maximum size in bytes of a single message or memory saved in the database (DB_PAYLOAD_SIZE_LIMIT).
+ * The default keeps the encrypted payloads under the 400KB item limit of DynamoDB.
+ */
+pub fn check_payload_size(payload: &serde_json::Value, name: &str) -> Result<(), EngineError> {
+    let limit = match get_config().integer("database.payload_size_limit") {
+        Some(limit) => limit as usize,
+        None => DB_PAYLOAD_SIZE_LIMIT,
+    };
+
+    let size = serde_json::to_vec(payload)?.len();
+    if size > limit {
+        return Err(EngineError::Format(format!(
+            "{}: {} is {} bytes (limit {} bytes)",
+            ERROR_DB_PAYLOAD_SIZE, name, size, limit
+        )));
+    }
+
+    Ok(())
+}
+
+/**
+ * Multi-tenancy: the data of a tenant is stored with the bot_id "{tenant_id}::{bot_id}",
+ * so a bot id used by several tenants never gives access to the data of another tenant.
+ * A bot_id that is already the bot_id of the tenant is kept as is.
+ */
+pub fn get_tenant_bot_id(tenant_id: Option<&str>, bot_id: &str) -> String {
+    match tenant_id {
+        Some(tenant_id) if !bot_id.starts_with(&format!("{}::", tenant_id)) => {
+            format!("{}::{}", tenant_id, bot_id)
+        }
+        _ => bot_id.to_owned(),
+    }
+}
+
+/**
+ * The client as it is stored in the database, with the bot_id of its tenant
+ */
+pub fn get_storage_client(client: &Client) -> Client {
+    Client::new(
+        get_tenant_bot_id(client.tenant_id.as_deref(), &client.bot_id),
+        client.channel_id.to_owned(),
+        client.user_id.to_owned(),
+    )
+}
@@ -0,0 +1,213 @@
+/**
+ * Debounce of the user messages: the text messages a user sends in a burst are coalesced into
+ * a single event, interpreted once instead of once per message.
+ *
+ * The debounce of a bot is enabled with CONVERSATION_DEBOUNCE (CONVERSATION_DEBOUNCE_<BOT_ID>
+ * for a single bot), the window in milliseconds. The first text message of a client waits for
+ * the window, which restarts with each new text message of the client. The messages received
+ * meanwhile are added to the first one and answered with no messages: the messages of the bot
+ * are the answer of the first request (and are sent to its callback_url).
+ *
+ * The coalesced event is a text event {"text": String, "texts": [String]}, its text is the
+ * texts of the messages joined by line breaks. The other events (buttons, files, secure
+ * messages, system events) are never delayed.
+ *
+ * The messages are coalesced by each engine process: like the client locks, the messages of
+ * a client must always be sent to the same server.
+ */
+use crate::{
+    config::get_config, data::CsmlRequest, db_connectors::utils::get_storage_client, Client,
+};
+
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug)]
+struct PendingTexts {
+    texts: Vec<String>,
+    // the window restarts with each message
+    last_received: Instant,
+}
+
+static PENDING: Lazy<Mutex<HashMap<String, PendingTexts>>> = Lazy::new(Default::default);
+
+fn lock_pending() -> MutexGuard<'static, HashMap<String, PendingTexts>> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/**
+ * The bot_id of the tenant of the client: the clients of two tenants never share a key
+ */
+fn get_key(client: &Client) -> String {
+    let client = get_storage_client(client);
+
+    format!("{}:{}:{}", client.bot_id, client.channel_id, client.user_id)
+}
+
+fn get_window(bot_id: &str) -> Option<Duration> {
+    get_config()
+        .text_for("conversations.debounce", bot_id)
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|window| *window > 0)
+        .map(Duration::from_millis)
+}
+
+/**
+ * The text of a debounced event, None for the events interpreted right away
+ */
+fn get_text(request: &CsmlRequest) -> Option<String> {
+    let payload = &request.payload;
+    if payload["content_type"] != "text" || payload["secure"].as_bool().unwrap_or(false) {
+        return None;
+    }
+
+    payload["content"]["text"]
+        .as_str()
+        .map(|text| text.to_owned())
+}
+
+/**
+ * Some(texts) received during the window for the first message of the burst,
+ * None for the messages added to it
+ */
+fn coalesce_text(key: &str, text: String, window: Duration) -> Option<Vec<String>> {
+    {
+        let mut pending = lock_pending();
+        if let Some(burst) = pending.get_mut(key) {
+            burst.texts.push(text);
+            burst.last_received = Instant::now();
+            return None;
+        }
+
+        pending.insert(
+            key.to_owned(),
+            PendingTexts {
+                texts: vec![text],
+                last_received: Instant::now(),
+            },
+        );
+    }
+
+    let mut wait = window;
+    loop {
+        thread::sleep(wait);
+
+        let mut pending = lock_pending();
+        let elapsed = match pending.get(key) {
+            Some(burst) => burst.last_received.elapsed(),
+            None => return Some(vec![]),
+        };
+
+        if elapsed >= window {
+            return pending.remove(key).map(|burst| burst.texts);
+        }
+        wait = window - elapsed;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Wait for the end of the burst of the text message of the request, and replace its event
+ * by the coalesced event. false if the message was added to the burst of another request,
+ * which interprets it.
+ */
+pub fn debounce_event(request: &mut CsmlRequest) -> bool {
+    let window = match get_window(&request.client.bot_id) {
+        Some(window) => window,
+        None => return true,
+    };
+    let text = match get_text(request) {
+        Some(text) => text,
+        None => return true,
+    };
+
+    let texts = match coalesce_text(&get_key(&request.client), text, window) {
+        Some(texts) => texts,
+        None => return false,
+    };
+
+    if texts.len() > 1 {
+        request.payload["content"] = json!({
+            "text": texts.join("\n"),
+            "texts": texts,
+        });
+    } else {
+        request.payload["content"]["texts"] = Value::from(texts);
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_tenant_keys() {
+        let mut client = Client::new(
+            "bot".to_owned(),
+            "channel".to_owned(),
+            "tenant_user".to_owned(),
+        );
+        client.tenant_id = Some("tenant_a".to_owned());
+        let mut other_client = client.clone();
+        other_client.tenant_id = Some("tenant_b".to_owned());
+
+        // the messages of the same user in two tenants are never coalesced
+        assert_ne!(get_key(&client), get_key(&other_client));
+    }
+
+    #[test]
+    fn ok_burst_coalesced() {
+        let window = Duration::from_millis(100);
+
+        let first = thread::spawn(move || {
+            coalesce_text("bot:channel:burst_user", "hello".to_owned(), window)
+        });
+
+        // the first message of the burst is waiting
+        while lock_pending().get("bot:channel:burst_user").is_none() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            coalesce_text("bot:channel:burst_user", "I lost".to_owned(), window),
+            None
+        );
+        assert_eq!(
+            coalesce_text("bot:channel:burst_user", "my card".to_owned(), window),
+            None
+        );
+
+        assert_eq!(
+            first.join().unwrap(),
+            Some(vec![
+                "hello".to_owned(),
+                "I lost".to_owned(),
+                "my card".to_owned()
+            ])
+        );
+        assert!(lock_pending().get("bot:channel:burst_user").is_none());
+    }
+
+    #[test]
+    fn ok_single_message() {
+        let texts = coalesce_text(
+            "bot:channel:single_user",
+            "hello".to_owned(),
+            Duration::from_millis(10),
+        );
+
+        assert_eq!(texts, Some(vec!["hello".to_owned()]));
+    }
+}
@@ -0,0 +1,223 @@
+/**
+ * Debug sessions: while a client is debugged, the interpreter pauses its conversation at the
+ * breakpoints of the session. The pause is saved as a hold (type "hold") with the line of
+ * the paused instruction, and the request that was interpreted is saved in the client state
+ * (type "debug") so the debugger can resume it with the same event.
+ */
+use crate::{
+    data::{BotOpt, ConversationInfo, CsmlRequest, Database, EngineError},
+    db_connectors::{conversations, memories, state},
+    Client,
+};
+use csml_interpreter::data::{Breakpoint, Debugger};
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn get_debugger(client: &Client, db: &mut Database) -> Result<Option<Debugger>, EngineError> {
+    match state::get_state_key(client, "debug", "session", db)? {
+        Some(session) => Ok(serde_json::from_value(session).ok()),
+        None => Ok(None),
+    }
+}
+
+pub fn set_debugger(
+    client: &Client,
+    debugger: &Debugger,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let session = serde_json::json!(debugger);
+
+    state::replace_state_item(client, "debug", "session", &session, None, db)
+}
+
+pub fn start_session(
+    client: &Client,
+    breakpoints: Vec<Breakpoint>,
+    db: &mut Database,
+) -> Result<Debugger, EngineError> {
+    let debugger = Debugger::new(breakpoints);
+    set_debugger(client, &debugger, db)?;
+
+    Ok(debugger)
+}
+
+/**
+ * The conversation is not debugged anymore, a pending pause is resumed by the next event
+ */
+pub fn stop_session(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    state::delete_state_key(client, "debug", "session", db)?;
+    state::delete_state_key(client, "debug", "request", db)
+}
+
+/**
+ * Save the request of the current interaction if the interpreter paused at a breakpoint
+ */
+pub fn save_pause(
+    data: &mut ConversationInfo,
+    request: &CsmlRequest,
+    bot_opt: &BotOpt,
+) -> Result<(), EngineError> {
+    state::delete_state_key(&data.client, "debug", "request", &mut data.db)?;
+
+    match state::get_state_key(&data.client, "hold", "position", &mut data.db)? {
+        Some(hold) if !hold["breakpoint"].is_null() => {
+            let pause = serde_json::json!({
+                "request": request,
+                "bot_opt": bot_opt,
+            });
+
+            state::set_state_items(
+                &data.client,
+                "debug",
+                vec![("request", &pause)],
+                data.ttl,
+                &mut data.db,
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+/**
+ * Get the request and the bot of the paused interaction
+ */
+pub fn get_pause(
+    client: &Client,
+    db: &mut Database,
+) -> Result<Option<(CsmlRequest, BotOpt)>, EngineError> {
+    let pause = match state::get_state_key(client, "debug", "request", db)? {
+        Some(pause) => pause,
+        None => return Ok(None),
+    };
+
+    match (
+        serde_json::from_value(pause["request"].clone()),
+        serde_json::from_value(pause["bot_opt"].clone()),
+    ) {
+        (Ok(request), Ok(bot_opt)) => Ok(Some((request, bot_opt))),
+        _ => Ok(None),
+    }
+}
+
+/**
+ * Inspect a debugged conversation: the breakpoints of the session and, if it is paused,
+ * the position of the pause with the step variables and the memories of the client
+ */
+pub fn inspect(
+    client: &Client,
+    debugger: &Debugger,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let hold = match state::get_state_key(client, "hold", "position", db)? {
+        Some(hold) if !hold["breakpoint"].is_null() => hold,
+        _ => {
+            return Ok(serde_json::json!({
+                "breakpoints": debugger.breakpoints,
+                "step_over": debugger.step_over,
+                "paused": null,
+            }))
+        }
+    };
+
+    let conversation_id = conversations::get_latest_open(client, db)?.map(|conv| conv.id);
+
+    Ok(serde_json::json!({
+        "breakpoints": debugger.breakpoints,
+        "step_over": debugger.step_over,
+        "paused": {
+            "conversation_id": conversation_id,
+            "flow": hold["breakpoint"]["flow"],
+            "step": hold["breakpoint"]["step"],
+            "line": hold["breakpoint"]["line"],
+            "step_vars": hold["step_vars"],
+            "memories": memories::get_memories(client, db)?,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+    use csml_interpreter::data::{csml_bot::CsmlBot, CsmlFlow};
+
+    fn get_bot_opt() -> BotOpt {
+        BotOpt::CsmlBot(CsmlBot::new(
+            "bot_id",
+            "bot",
+            None,
+            vec![CsmlFlow::new(
+                "Default",
+                "Default",
+                "start:\n    do value = 42\n    say \"1\"\n    say \"2\"\n    goto end",
+                vec![],
+            )],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ))
+    }
+
+    fn get_request(client: &Client) -> CsmlRequest {
+        CsmlRequest {
+            request_id: "request_id".to_owned(),
+            client: client.to_owned(),
+            callback_url: None,
+            payload: serde_json::json!({"content_type": "text", "content": {"text": "hello"}}),
+            metadata: serde_json::json!({}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+        }
+    }
+
+    #[test]
+    fn ok_debug_session() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "debugger_test".to_owned(),
+        );
+        conversations::close_all_conversations(&client, &mut db).unwrap();
+        state::delete_state_key(&client, "hold", "position", &mut db).unwrap();
+
+        let breakpoint = Breakpoint::Line {
+            flow: "Default".to_owned(),
+            line: 4,
+        };
+        start_session(&client, vec![breakpoint], &mut db).unwrap();
+
+        let messages = crate::start_conversation(get_request(&client), get_bot_opt()).unwrap();
+        assert_eq!(messages["messages"].as_array().unwrap().len(), 1);
+
+        let session = get_debugger(&client, &mut db).unwrap().unwrap();
+        let inspected = inspect(&client, &session, &mut db).unwrap();
+        assert_eq!(inspected["paused"]["step"], "start");
+        assert_eq!(inspected["paused"]["line"], 4);
+        assert_eq!(inspected["paused"]["step_vars"]["value"], 42);
+
+        // the paused instruction is executed when the conversation is continued
+        let messages = crate::continue_debug_session(&client).unwrap();
+        assert_eq!(messages["messages"].as_array().unwrap().len(), 1);
+        assert!(messages["debug"]["paused"].is_null());
+        assert!(get_pause(&client, &mut db).unwrap().is_none());
+
+        stop_session(&client, &mut db).unwrap();
+        assert!(get_debugger(&client, &mut db).unwrap().is_none());
+    }
+}
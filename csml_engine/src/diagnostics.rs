@@ -0,0 +1,141 @@
+/**
+ * Diagnostics of the environment of the engine, to find a misconfiguration at startup
+ * (csml_server --check) instead of on the first live request:
+ * - the settings (see config.rs)
+ * - the connection to the database and the tables (or collections) of the engine
+ * - the permission to write in the database, with a state item written and deleted
+ * - the encryption of the data when ENCRYPTION_SECRET is set
+ */
+use crate::{
+    config,
+    data::*,
+    db_connectors::{diagnostics::check_storage, init_db, state},
+    encrypt::{decrypt_data, encrypt_data},
+    utils::get_bot_client,
+};
+
+use std::panic::{self, AssertUnwindSafe};
+
+const DIAGNOSTICS_BOT_ID: &str = "csml_diagnostics";
+
+fn check_config() -> Vec<EnvironmentCheck> {
+    let errors = config::validate_config();
+    if errors.is_empty() {
+        return vec![EnvironmentCheck::new("settings", CHECK_OK, "valid")];
+    }
+
+    errors
+        .iter()
+        .map(|err| EnvironmentCheck::new("settings", CHECK_ERROR, err))
+        .collect()
+}
+
+fn connect() -> Result<Database, String> {
+    // some connectors panic when the database can not be reached: the panic is reported
+    // as a failed check, without the message of the default hook (it may print the password)
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(init_db));
+    panic::set_hook(hook);
+
+    match result {
+        Ok(Ok(db)) => Ok(db),
+        Ok(Err(err)) => Err(format!("{:?}", err)),
+        Err(_) => Err("the connection to the database failed".to_owned()),
+    }
+}
+
+fn check_write(db: &mut Database) -> EnvironmentCheck {
+    let client = get_bot_client(DIAGNOSTICS_BOT_ID);
+    let value = serde_json::json!({"checked_at": chrono::Utc::now().to_rfc3339()});
+
+    let result = state::set_state_items(&client, "diagnostics", vec![("write", &value)], None, db)
+        .and_then(|_| state::get_state_key(&client, "diagnostics", "write", db))
+        .and_then(|saved| {
+            state::delete_state_key(&client, "diagnostics", "write", db)?;
+            Ok(saved)
+        });
+
+    match result {
+        Ok(Some(saved)) if saved == value => {
+            EnvironmentCheck::new("database write", CHECK_OK, "written, read and deleted")
+        }
+        Ok(_) => EnvironmentCheck::new(
+            "database write",
+            CHECK_ERROR,
+            "the written state item was not read back",
+        ),
+        Err(err) => EnvironmentCheck::new("database write", CHECK_ERROR, &format!("{:?}", err)),
+    }
+}
+
+fn check_encryption() -> EnvironmentCheck {
+    if !config::get_config().is_set("encryption.secret") {
+        return EnvironmentCheck::new(
+            "encryption",
+            CHECK_WARNING,
+            "ENCRYPTION_SECRET is not set, the data is saved unencrypted",
+        );
+    }
+
+    let value = serde_json::json!({"diagnostics": true});
+    match encrypt_data(&value).and_then(decrypt_data) {
+        Ok(decrypted) if decrypted == value => {
+            EnvironmentCheck::new("encryption", CHECK_OK, "enabled")
+        }
+        Ok(_) => EnvironmentCheck::new("encryption", CHECK_ERROR, "the decrypted data differs"),
+        Err(err) => EnvironmentCheck::new("encryption", CHECK_ERROR, &format!("{:?}", err)),
+    }
+}
+
+pub fn validate_environment() -> EnvironmentReport {
+    let mut checks = check_config();
+    checks.push(check_encryption());
+
+    let db_type = config::get_config()
+        .text("database.type")
+        .unwrap_or_else(|| "mongodb".to_owned());
+    match connect() {
+        Ok(mut db) => {
+            checks.push(EnvironmentCheck::new("database connection", CHECK_OK, &db_type));
+            checks.extend(check_storage(&mut db));
+            checks.push(check_write(&mut db));
+        }
+        Err(err) => checks.push(EnvironmentCheck::new(
+            "database connection",
+            CHECK_ERROR,
+            &format!("{}: {}", db_type, err),
+        )),
+    }
+
+    EnvironmentReport { checks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_validate_environment() {
+        crate::make_migrations().unwrap_or({});
+
+        let report = validate_environment();
+        let connection = report
+            .checks
+            .iter()
+            .find(|check| check.name == "database connection")
+            .unwrap();
+        assert_eq!(connection.status, CHECK_OK);
+
+        for check in report.checks.iter().filter(|check| check.name.starts_with("table")) {
+            assert_eq!(check.status, CHECK_OK, "{:?}", check);
+        }
+
+        let write = report
+            .checks
+            .iter()
+            .find(|check| check.name == "database write")
+            .unwrap();
+        assert_eq!(write.status, CHECK_OK, "{:?}", write);
+    }
+}
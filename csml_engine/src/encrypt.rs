@@ -3,27 +3,37 @@
  * It is not mandatory to setup encryption in CSML engine, however it is greatly recommended
  * if the chatbot is handling sensitive data of any sort.
  *
- * To automatically setup encryption/decryption of data, you must set an ENCRYPTION_SECRET
- * environment variable with a complex enough string.
+ * To automatically setup encryption/decryption of data, you must set the encryption.secret
+ * setting (ENCRYPTION_SECRET environment variable) with a complex enough string.
  *
  * Encrypt: Data is JSON-stringified before encryption, and is returned as an encrypted string.
  * Decrypt: Data is decrypted from an encrypted string and is returned as a JSON Value.
  *
  * The encryption algorithm used is AES-256-GCM.
+ *
+ * Search: the encrypted messages and memories are searched with a blind index of their words,
+ * the HMAC-SHA256 of each word keyed with the ENCRYPTION_SECRET (see get_search_terms).
  */
-use crate::EngineError;
+use crate::{config::get_config, EngineError};
 
 use openssl::{
+    hash::MessageDigest,
     pkcs5::pbkdf2_hmac,
+    pkey::PKey,
     rand::rand_bytes,
+    sign::Signer,
     symm::{decrypt_aead, encrypt_aead, Cipher},
 };
-use std::env;
+use std::collections::BTreeSet;
+
+// the terms of the blind index are never equal to another HMAC of the secret
+const SEARCH_TERM_PREFIX: &[u8] = b"csml_search_term:";
+const SEARCH_TERM_LENGTH: usize = 16;
 
 fn get_key(salt: &[u8], key: &mut [u8]) -> Result<(), EngineError> {
-    let pass = match env::var("ENCRYPTION_SECRET") {
-        Ok(var) => var,
-        _ => panic!("No ENCRYPTION_SECRET value in env"),
+    let pass = match get_config().text("encryption.secret") {
+        Some(var) => var,
+        None => panic!("No ENCRYPTION_SECRET value in env"),
     };
 
     pbkdf2_hmac(
@@ -71,9 +81,9 @@ fn encrypt(text: &[u8]) -> Result<String, EngineError> {
 }
 
 pub fn encrypt_data(value: &serde_json::Value) -> Result<String, EngineError> {
-    match env::var("ENCRYPTION_SECRET") {
-        Ok(..) => encrypt(&value.to_string().as_bytes()),
-        _ => Ok(value.to_string()),
+    match get_config().is_set("encryption.secret") {
+        true => encrypt(&value.to_string().as_bytes()),
+        false => Ok(value.to_string()),
     }
 }
 
@@ -101,14 +111,68 @@ fn decrypt(text: String) -> Result<String, EngineError> {
 }
 
 pub fn decrypt_data(value: String) -> Result<serde_json::Value, EngineError> {
-    match env::var("ENCRYPTION_SECRET") {
-        Ok(..) => {
+    match get_config().is_set("encryption.secret") {
+        true => {
             let value: serde_json::Value = serde_json::from_str(&decrypt(value)?)?;
             Ok(value)
         }
-        _ => {
+        false => {
             let value: serde_json::Value = serde_json::from_str(&value)?;
             Ok(value)
         }
     }
 }
+
+fn split_words(text: &str, words: &mut BTreeSet<String>) {
+    words.extend(
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase),
+    );
+}
+
+/**
+ * Words of the strings, numbers and booleans of a value, the keys of its objects are skipped
+ */
+fn collect_words(value: &serde_json::Value, words: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::String(text) => split_words(text, words),
+        serde_json::Value::Number(number) => split_words(&number.to_string(), words),
+        serde_json::Value::Bool(boolean) => split_words(&boolean.to_string(), words),
+        serde_json::Value::Array(values) => {
+            values.iter().for_each(|value| collect_words(value, words))
+        }
+        serde_json::Value::Object(map) => map.values().for_each(|value| collect_words(value, words)),
+        serde_json::Value::Null => {}
+    }
+}
+
+fn hash_search_term(secret: &str, word: &str) -> Result<String, EngineError> {
+    let key = PKey::hmac(secret.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(SEARCH_TERM_PREFIX)?;
+    signer.update(word.as_bytes())?;
+
+    Ok(hex::encode(&signer.sign_to_vec()?[..SEARCH_TERM_LENGTH]))
+}
+
+/**
+ * Blind index of a value saved encrypted: its lowercased words, each replaced by its keyed hash.
+ * A search matches the values holding every word of the searched text, as whole words.
+ * None without encryption, the values are then searched in clear.
+ */
+pub fn get_search_terms(value: &serde_json::Value) -> Result<Option<Vec<String>>, EngineError> {
+    let secret = match get_config().text("encryption.secret") {
+        Some(var) => var,
+        None => return Ok(None),
+    };
+
+    let mut words = BTreeSet::new();
+    collect_words(value, &mut words);
+
+    words
+        .iter()
+        .map(|word| hash_search_term(&secret, word))
+        .collect::<Result<Vec<String>, EngineError>>()
+        .map(Some)
+}
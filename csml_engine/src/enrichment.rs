@@ -0,0 +1,190 @@
+/**
+ * Enrichment of the events before their interpretation: each enricher is an HTTP service
+ * annotating the event (sentiment, entities, language...), its response is available in the
+ * flows with `event.enrichment.<name>` and saved with the received message for the analytics.
+ *
+ * - EVENT_ENRICHERS=sentiment:https://nlp.example.com/sentiment,entities:https://... lists the
+ *   enrichers by name (EVENT_ENRICHERS_<BOT_ID> for the events of a single bot)
+ * - EVENT_ENRICHERS_TIMEOUT: milliseconds an enricher has to answer (default 2000)
+ * - EVENT_ENRICHERS_AUTH: optional Authorization header of the requests
+ *
+ * The enrichers are called in parallel with
+ * {"client": Client, "conversation_id": String, "event": {"content_type": String, "content": Object},
+ * "metadata": Object} and must answer with a JSON value. An enricher that fails or does not answer
+ * in time is skipped: the event is interpreted without its annotations.
+ *
+ * The secure events are never sent to the enrichers.
+ */
+use crate::{config::get_config, data::ConversationInfo};
+
+use csml_interpreter::data::{csml_logs::*, Event};
+use serde_json::{Map, Value};
+use std::{thread, time::Duration};
+
+const DEFAULT_TIMEOUT: u64 = 2000;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Enricher {
+    name: String,
+    url: String,
+}
+
+/**
+ * name:url,name:url... (the urls contain colons, the name ends at the first one)
+ */
+fn parse_enrichers(value: &str) -> Vec<Enricher> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (name, url) = entry.trim().split_once(':')?;
+            match (name.trim(), url.trim()) {
+                ("", _) | (_, "") => None,
+                (name, url) => Some(Enricher {
+                    name: name.to_owned(),
+                    url: url.to_owned(),
+                }),
+            }
+        })
+        .collect()
+}
+
+fn get_timeout() -> Duration {
+    let timeout = get_config()
+        .integer("enrichment.timeout")
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    Duration::from_millis(timeout)
+}
+
+fn call_enricher(
+    enricher: &Enricher,
+    body: &Value,
+    timeout: Duration,
+    auth: Option<&str>,
+) -> Result<Value, String> {
+    let mut request = ureq::post(&enricher.url).timeout(timeout);
+    if let Some(auth) = auth {
+        request = request.set("Authorization", &auth);
+    }
+
+    match request.send_json(body.to_owned()) {
+        Ok(response) => response.into_json().map_err(|err| err.to_string()),
+        Err(ureq::Error::Status(status, _)) => Err(format!("response status {}", status)),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/**
+ * Annotate the event with the responses of the enrichers of its bot
+ */
+pub fn enrich_event(event: &mut Event, data: &ConversationInfo) {
+    if event.secure {
+        return;
+    }
+
+    let enrichers = match get_config().text_for("enrichment.enrichers", &data.client.bot_id) {
+        Some(value) => parse_enrichers(&value),
+        None => return,
+    };
+
+    let body = serde_json::json!({
+        "client": data.client,
+        "conversation_id": data.conversation_id,
+        "event": {
+            "content_type": event.content_type,
+            "content": event.content,
+        },
+        "metadata": data.metadata,
+    });
+    let timeout = get_timeout();
+    // read once for all the calls
+    let auth = get_config().text("enrichment.auth");
+
+    let calls: Vec<_> = enrichers
+        .into_iter()
+        .map(|enricher| {
+            let body = body.clone();
+            let auth = auth.clone();
+            thread::spawn(move || {
+                let result = call_enricher(&enricher, &body, timeout, auth.as_deref());
+                (enricher, result)
+            })
+        })
+        .collect();
+
+    let mut enrichment = Map::new();
+    for call in calls {
+        match call.join() {
+            Ok((enricher, Ok(value))) => {
+                enrichment.insert(enricher.name, value);
+            }
+            Ok((enricher, Err(err))) => csml_logger(
+                CsmlLog::new(
+                    Some(&data.client),
+                    None,
+                    None,
+                    format!("enricher {} skipped: {}", enricher.name, err),
+                ),
+                LogLvl::Warn,
+            ),
+            Err(_) => {}
+        }
+    }
+
+    event.enrichment = Value::Object(enrichment);
+}
+
+/**
+ * The received message saved for the event, with its annotations
+ */
+pub fn add_enrichment(payload: &Value, event: &Event) -> Value {
+    let mut payload = payload.to_owned();
+
+    match (&event.enrichment, payload.as_object_mut()) {
+        (Value::Object(enrichment), Some(map)) if !enrichment.is_empty() => {
+            map.insert("enrichment".to_owned(), event.enrichment.to_owned());
+        }
+        _ => {}
+    }
+
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_parse_enrichers() {
+        let enrichers =
+            parse_enrichers("sentiment:https://nlp.local:8080/sentiment, entities:http://ner/,bad");
+
+        assert_eq!(
+            enrichers,
+            vec![
+                Enricher {
+                    name: "sentiment".to_owned(),
+                    url: "https://nlp.local:8080/sentiment".to_owned(),
+                },
+                Enricher {
+                    name: "entities".to_owned(),
+                    url: "http://ner/".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ok_add_enrichment() {
+        let payload = serde_json::json!({"content_type": "text", "content": {"text": "hi"}});
+        let mut event = Event::new("text", "hi", serde_json::json!({"text": "hi"}));
+
+        assert_eq!(add_enrichment(&payload, &event), payload);
+
+        event.enrichment = serde_json::json!({"sentiment": {"score": 0.8}});
+        assert_eq!(
+            add_enrichment(&payload, &event)["enrichment"]["sentiment"]["score"],
+            0.8
+        );
+    }
+}
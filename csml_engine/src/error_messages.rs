@@ -1 +1,3 @@
 pub const ERROR_DB_SETUP: &'static str = "Database connector is not setup correctly";
+pub const ERROR_MEMORY_SCOPE: &'static str = "Memory scope must be \"user\" or \"channel\"";
+pub const ERROR_DB_PAYLOAD_SIZE: &'static str = "Payload exceeds the maximum size that can be saved in the database";
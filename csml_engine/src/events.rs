@@ -0,0 +1,606 @@
+/**
+ * Engine events published to an event bus for the analytics and CDP systems:
+ * conversation_started, conversation_closed, step_entered, message_sent, memory_updated,
+ * experiment_assigned and conversation_error (see conversation_errors.rs), and step_started and
+ * step_completed for the bots with step hooks (see step_hooks.rs).
+ * They are also sent to the webhooks subscribed to them (see webhooks.rs), and the closed
+ * conversations are summarized (see summaries.rs).
+ *
+ * The publisher is selected with EVENTS_PUBLISHER:
+ * - nats: published with async-nats on the NATS server EVENTS_NATS_URL
+ *   (default nats://localhost:4222), authenticated with EVENTS_NATS_TOKEN or
+ *   EVENTS_NATS_USER / EVENTS_NATS_PASSWORD. Requires the nats feature.
+ * - kafka: produced with rdkafka on the brokers EVENTS_KAFKA_BROKERS, authenticated with SASL
+ *   over SSL when EVENTS_KAFKA_USERNAME / EVENTS_KAFKA_PASSWORD are set (mechanism
+ *   EVENTS_KAFKA_SASL_MECHANISM, default PLAIN). Requires the kafka feature.
+ *   The records are keyed by client, so the events of a client keep their order.
+ *
+ * Each event is published on the topic (or NATS subject) EVENTS_TOPIC_<EVENT>, EVENTS_TOPIC
+ * for every event, or csml.<event> by default.
+ *
+ * The events are sent by a background thread: a slow or unavailable event bus never delays the
+ * conversations. When more than EVENTS_QUEUE_SIZE events (default 10000) are waiting, the new
+ * events are dropped.
+ */
+use crate::{
+    config::{get_config, Config},
+    data::{ConversationInfo, EngineError},
+    summaries,
+    webhooks, Client,
+};
+use csml_interpreter::data::{csml_logs::*, Memory, Message};
+
+use chrono::{prelude::Utc, SecondsFormat};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+#[cfg(feature = "kafka")]
+use rdkafka::{
+    config::ClientConfig,
+    producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext},
+    util::Timeout,
+    ClientContext, Message as _,
+};
+#[cfg(any(feature = "nats", feature = "kafka"))]
+use std::time::Duration;
+
+pub const CONVERSATION_STARTED: &str = "conversation_started";
+pub const CONVERSATION_CLOSED: &str = "conversation_closed";
+pub const STEP_ENTERED: &str = "step_entered";
+pub const MESSAGE_SENT: &str = "message_sent";
+pub const MEMORY_UPDATED: &str = "memory_updated";
+pub const EXPERIMENT_ASSIGNED: &str = "experiment_assigned";
+pub const STEP_STARTED: &str = "step_started";
+pub const STEP_COMPLETED: &str = "step_completed";
+pub const CONVERSATION_ERROR: &str = "conversation_error";
+
+const DEFAULT_QUEUE_SIZE: usize = 10_000;
+#[cfg(feature = "nats")]
+const DEFAULT_NATS_URL: &str = "nats://localhost:4222";
+// how long the publisher waits for the event bus to acknowledge a batch of events
+#[cfg(any(feature = "nats", feature = "kafka"))]
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// queued events that were not published yet
+static PENDING_EVENTS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg_attr(not(any(feature = "nats", feature = "kafka")), allow(dead_code))]
+struct Event {
+    topic: String,
+    key: String,
+    value: serde_json::Value,
+}
+
+trait EventPublisher {
+    fn publish(&mut self, event: &Event) -> Result<(), EngineError>;
+
+    // called after each batch of queued events
+    fn flush(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NATS
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "nats")]
+struct NatsPublisher {
+    runtime: tokio::runtime::Runtime,
+    client: async_nats::Client,
+}
+
+#[cfg(feature = "nats")]
+fn nats_error(err: impl std::fmt::Display) -> EngineError {
+    EngineError::Manager(format!("NATS error: {}", err))
+}
+
+#[cfg(feature = "nats")]
+impl NatsPublisher {
+    fn new(
+        url: &str,
+        token: Option<String>,
+        user: Option<String>,
+        pass: Option<String>,
+    ) -> Result<Self, EngineError> {
+        // the client keeps the connection alive and reconnects on its own runtime
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+
+        let mut options = async_nats::ConnectOptions::new().name("csml_engine");
+        if let Some(token) = token {
+            options = options.token(token);
+        } else if let (Some(user), Some(pass)) = (user, pass) {
+            options = options.user_and_password(user, pass);
+        }
+
+        let client = runtime
+            .block_on(options.retry_on_initial_connect().connect(url))
+            .map_err(nats_error)?;
+
+        Ok(Self { runtime, client })
+    }
+}
+
+#[cfg(feature = "nats")]
+impl EventPublisher for NatsPublisher {
+    fn publish(&mut self, event: &Event) -> Result<(), EngineError> {
+        let payload = serde_json::to_vec(&event.value)?;
+
+        self.runtime
+            .block_on(self.client.publish(event.topic.clone(), payload.into()))
+            .map_err(nats_error)
+    }
+
+    fn flush(&mut self) -> Result<(), EngineError> {
+        self.runtime.block_on(async {
+            match tokio::time::timeout(FLUSH_TIMEOUT, self.client.flush()).await {
+                Ok(res) => res.map_err(nats_error),
+                Err(_) => Err(nats_error("flush timed out")),
+            }
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// KAFKA
+////////////////////////////////////////////////////////////////////////////////
+
+// logs the records that could not be delivered after the retries of the producer
+#[cfg(feature = "kafka")]
+struct KafkaContext;
+
+#[cfg(feature = "kafka")]
+impl ClientContext for KafkaContext {}
+
+#[cfg(feature = "kafka")]
+impl ProducerContext for KafkaContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        if let Err((err, message)) = result {
+            log_error(format!(
+                "failed to publish event on {}: {}",
+                message.topic(),
+                err
+            ));
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+struct KafkaPublisher {
+    producer: BaseProducer<KafkaContext>,
+}
+
+#[cfg(feature = "kafka")]
+fn kafka_error(err: impl std::fmt::Display) -> EngineError {
+    EngineError::Manager(format!("Kafka error: {}", err))
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaPublisher {
+    fn new(
+        brokers: &str,
+        username: Option<String>,
+        password: Option<String>,
+        mechanism: Option<String>,
+    ) -> Result<Self, EngineError> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", brokers)
+            .set("client.id", "csml_engine");
+
+        if let (Some(username), Some(password)) = (username, password) {
+            let mechanism = mechanism.unwrap_or_else(|| "PLAIN".to_owned());
+
+            config
+                .set("security.protocol", "SASL_SSL")
+                .set("sasl.mechanism", mechanism)
+                .set("sasl.username", username)
+                .set("sasl.password", password);
+        }
+
+        let producer = config
+            .create_with_context(KafkaContext)
+            .map_err(kafka_error)?;
+
+        Ok(Self { producer })
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl EventPublisher for KafkaPublisher {
+    fn publish(&mut self, event: &Event) -> Result<(), EngineError> {
+        let payload = serde_json::to_vec(&event.value)?;
+
+        self.producer
+            .send(
+                BaseRecord::to(&event.topic)
+                    .key(&event.key)
+                    .payload(&payload),
+            )
+            .map_err(|(err, _)| kafka_error(err))?;
+        // serves the delivery callbacks of the previous records
+        self.producer.poll(Duration::ZERO);
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), EngineError> {
+        self.producer
+            .flush(Timeout::After(FLUSH_TIMEOUT))
+            .map_err(kafka_error)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// BACKGROUND PUBLISHER
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "nats")]
+fn get_nats_publisher(config: &Config) -> Result<Box<dyn EventPublisher + Send>, EngineError> {
+    Ok(Box::new(NatsPublisher::new(
+        &config
+            .text("events.nats.url")
+            .unwrap_or_else(|| DEFAULT_NATS_URL.to_owned()),
+        config.text("events.nats.token"),
+        config.text("events.nats.user"),
+        config.text("events.nats.password"),
+    )?))
+}
+
+#[cfg(not(feature = "nats"))]
+fn get_nats_publisher(_config: &Config) -> Result<Box<dyn EventPublisher + Send>, EngineError> {
+    Err(EngineError::Manager(
+        "EVENTS_PUBLISHER=nats requires the nats feature".to_owned(),
+    ))
+}
+
+#[cfg(feature = "kafka")]
+fn get_kafka_publisher(config: &Config) -> Result<Box<dyn EventPublisher + Send>, EngineError> {
+    match config.text("events.kafka.brokers") {
+        Some(brokers) => Ok(Box::new(KafkaPublisher::new(
+            &brokers,
+            config.text("events.kafka.username"),
+            config.text("events.kafka.password"),
+            config.text("events.kafka.sasl_mechanism"),
+        )?)),
+        None => Err(EngineError::Manager(
+            "EVENTS_KAFKA_BROKERS must be set to use the kafka events publisher".to_owned(),
+        )),
+    }
+}
+
+#[cfg(not(feature = "kafka"))]
+fn get_kafka_publisher(_config: &Config) -> Result<Box<dyn EventPublisher + Send>, EngineError> {
+    Err(EngineError::Manager(
+        "EVENTS_PUBLISHER=kafka requires the kafka feature".to_owned(),
+    ))
+}
+
+fn get_publisher(config: &Config) -> Result<Option<Box<dyn EventPublisher + Send>>, EngineError> {
+    let publisher = match config.text("events.publisher") {
+        Some(publisher) => publisher,
+        None => return Ok(None),
+    };
+
+    match publisher.as_str() {
+        "nats" => Ok(Some(get_nats_publisher(config)?)),
+        "kafka" => Ok(Some(get_kafka_publisher(config)?)),
+        publisher => Err(EngineError::Manager(format!(
+            "unknown events publisher: {}",
+            publisher
+        ))),
+    }
+}
+
+fn log_error(message: String) {
+    csml_logger(CsmlLog::new(None, None, None, message), LogLvl::Error);
+}
+
+fn start_publisher() -> Option<Mutex<SyncSender<Event>>> {
+    let config = get_config();
+    let mut publisher = match get_publisher(&config) {
+        Ok(Some(publisher)) => publisher,
+        Ok(None) => return None,
+        Err(err) => {
+            log_error(format!("events publisher disabled: {:?}", err));
+            return None;
+        }
+    };
+
+    let queue_size = config
+        .integer("events.queue_size")
+        .map(|size| size as usize)
+        .unwrap_or(DEFAULT_QUEUE_SIZE);
+    let (sender, receiver) = mpsc::sync_channel::<Event>(queue_size);
+
+    thread::spawn(move || {
+        while let Ok(event) = receiver.recv() {
+            // the events queued meanwhile are flushed together
+            let mut events = vec![event];
+            events.extend(receiver.try_iter());
+
+            for event in events.iter() {
+                if let Err(err) = publisher.publish(event) {
+                    log_error(format!(
+                        "failed to publish event on {}: {:?}",
+                        event.topic, err
+                    ));
+                }
+            }
+            if let Err(err) = publisher.flush() {
+                log_error(format!("failed to flush the events: {:?}", err));
+            }
+
+            PENDING_EVENTS.fetch_sub(events.len(), Ordering::SeqCst);
+        }
+    });
+
+    Some(Mutex::new(sender))
+}
+
+static PUBLISHER: Lazy<Option<Mutex<SyncSender<Event>>>> = Lazy::new(start_publisher);
+
+fn get_topic(event: &str) -> String {
+    match get_config().text_for("events.topic", event) {
+        Some(topic) => topic,
+        None => format!("csml.{}", event),
+    }
+}
+
+fn make_event(
+    event: &str,
+    client: &Client,
+    conversation_id: Option<&str>,
+    bot_version_id: Option<&str>,
+    data: serde_json::Value,
+) -> Event {
+    Event {
+        topic: get_topic(event),
+        key: format!("{}:{}:{}", client.bot_id, client.channel_id, client.user_id),
+        value: serde_json::json!({
+            "id": uuid::Uuid::new_v4().to_string(),
+            "event": event,
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "client": client,
+            "conversation_id": conversation_id,
+            "bot_version_id": bot_version_id,
+            "data": data,
+        }),
+    }
+}
+
+/**
+ * Queue an event of a client for the event bus and the webhooks of its bot
+ */
+pub fn publish_event(
+    event: &str,
+    client: &Client,
+    conversation_id: Option<&str>,
+    data: serde_json::Value,
+) {
+    publish_version_event(event, client, conversation_id, None, data)
+}
+
+/**
+ * Queue an event of a run, tagged with the version of the bot handling the run
+ * (the one picked by a rollout) so that the analytics can compare the versions
+ */
+pub fn publish_version_event(
+    event: &str,
+    client: &Client,
+    conversation_id: Option<&str>,
+    bot_version_id: Option<&str>,
+    data: serde_json::Value,
+) {
+    let event = make_event(event, client, conversation_id, bot_version_id, data);
+    webhooks::queue_event(&event.value);
+    summaries::queue_event(&event.value);
+
+    if let Some(sender) = &*PUBLISHER {
+        PENDING_EVENTS.fetch_add(1, Ordering::SeqCst);
+
+        if let Err(err) = sender.lock().unwrap().try_send(event) {
+            PENDING_EVENTS.fetch_sub(1, Ordering::SeqCst);
+
+            if let TrySendError::Full(event) = err {
+                log_error(format!(
+                    "events queue full: event on {} dropped",
+                    event.topic
+                ));
+            }
+        }
+    }
+}
+
+/**
+ * Wait until the queued events are published or the deadline, return false if some
+ * of them are still waiting
+ */
+pub fn flush(deadline: Instant) -> bool {
+    let flushed = crate::shutdown::wait_until(deadline, || {
+        PENDING_EVENTS.load(Ordering::SeqCst) == 0
+    });
+
+    if !flushed {
+        log_error(format!(
+            "{} events not published before the shutdown",
+            PENDING_EVENTS.load(Ordering::SeqCst)
+        ));
+    }
+
+    flushed
+}
+
+/**
+ * Queue an event of the run of a conversation
+ */
+pub fn publish_run_event(event: &str, data: &ConversationInfo, payload: serde_json::Value) {
+    publish_version_event(
+        event,
+        &data.client,
+        Some(&data.conversation_id),
+        data.bot_version_id.as_deref(),
+        payload,
+    );
+}
+
+pub fn publish_step_entered(data: &ConversationInfo, flow_id: &str) {
+    publish_run_event(
+        STEP_ENTERED,
+        data,
+        serde_json::json!({"flow_id": flow_id, "step_id": data.context.step.get_step()}),
+    );
+}
+
+/**
+ * The content of the messages of the bots with no data retention is not published
+ */
+pub fn publish_message_sent(data: &ConversationInfo, message: &Message) {
+    let payload = match data.no_data_retention {
+        true => serde_json::json!({ "content_type": message.content_type }),
+        false => message.clone().message_to_json(),
+    };
+
+    publish_run_event(
+        MESSAGE_SENT,
+        data,
+        serde_json::json!({
+            "flow_id": data.context.flow,
+            "step_id": data.context.step.get_step(),
+            "payload": payload,
+        }),
+    );
+}
+
+/**
+ * The values of the memories of the bots with no data retention are not published
+ */
+pub fn publish_memories_updated(
+    data: &ConversationInfo,
+    memories: &HashMap<String, Memory>,
+    scope: &str,
+) {
+    for memory in memories.values() {
+        let mut event = serde_json::json!({"key": memory.key, "scope": scope});
+        if !data.no_data_retention {
+            event["value"] = memory.value.clone();
+        }
+
+        publish_run_event(MEMORY_UPDATED, data, event);
+    }
+}
+
+/**
+ * The outcomes of an experiment are measured by joining its assignments with the later
+ * events of the same clients
+ */
+pub fn publish_experiment_assigned(data: &ConversationInfo, experiment: &str, variant: &str) {
+    publish_run_event(
+        EXPERIMENT_ASSIGNED,
+        data,
+        serde_json::json!({
+            "flow_id": data.context.flow,
+            "step_id": data.context.step.get_step(),
+            "experiment": experiment,
+            "variant": variant,
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step_entered_event() -> Event {
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+
+        make_event(
+            STEP_ENTERED,
+            &client,
+            Some("conversation_id"),
+            Some("version_id"),
+            serde_json::json!({"flow_id": "Default", "step_id": "start"}),
+        )
+    }
+
+    #[test]
+    fn ok_make_event() {
+        let event = step_entered_event();
+
+        assert_eq!(event.topic, "csml.step_entered");
+        assert_eq!(event.key, "bot_id:channel_id:user_id");
+        assert_eq!(event.value["event"], STEP_ENTERED);
+        assert_eq!(event.value["client"]["user_id"], "user_id");
+        assert_eq!(event.value["conversation_id"], "conversation_id");
+        assert_eq!(event.value["bot_version_id"], "version_id");
+        assert_eq!(event.value["data"]["step_id"], "start");
+    }
+
+    #[cfg(feature = "nats")]
+    #[test]
+    fn ok_nats_publisher() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // answers the pings of the client until it publishes a message
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"INFO {\"server_id\":\"test\",\"max_payload\":1048576}\r\n")
+                .unwrap();
+
+            let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+            let mut connect = String::new();
+            loop {
+                let line = lines.next().unwrap().unwrap();
+                if line.starts_with("CONNECT") {
+                    connect = line;
+                } else if line == "PING" {
+                    stream.write_all(b"PONG\r\n").unwrap();
+                } else if line.starts_with("PUB") {
+                    let payload = lines.next().unwrap().unwrap();
+                    return (connect, line, payload);
+                }
+            }
+        });
+
+        let mut publisher = NatsPublisher::new(
+            &format!("nats://{}", address),
+            Some("token".to_owned()),
+            None,
+            None,
+        )
+        .unwrap();
+        let event = step_entered_event();
+        publisher.publish(&event).unwrap();
+        publisher.flush().unwrap();
+
+        let (connect, publish, payload) = server.join().unwrap();
+        assert!(connect.contains("\"auth_token\":\"token\""));
+        assert_eq!(publish, format!("PUB csml.step_entered {}", payload.len()));
+
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(payload, event.value);
+    }
+}
@@ -0,0 +1,279 @@
+/**
+ * Feature flags turn the new conversational paths of a bot version on for a part of its
+ * clients, read in the flows with _flags.<key> (see FeatureFlag).
+ *
+ * The conditions of a flag target the attributes of the client and of the metadata of the
+ * event (client.channel_id, metadata.plan...), and its percentage places each client in a
+ * bucket of the flag: a given client keeps the same value while the flag is unchanged.
+ *
+ * The flags of a bot are saved together in the state of the bot client.
+ */
+use crate::{
+    data::{Database, EngineError, FeatureFlag, FlagOperator},
+    db_connectors::state,
+    utils::{get_bot_client, validate_memory_key_format},
+    Client,
+};
+use csml_interpreter::data::{context::get_hashmap_from_mem, csml_logs::*, Literal};
+
+use md5::{Digest, Md5};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+pub fn get_flags(
+    bot_id: &str,
+    db: &mut Database,
+) -> Result<HashMap<String, FeatureFlag>, EngineError> {
+    match state::get_state_key(&get_bot_client(bot_id), "bot", "feature_flags", db)? {
+        Some(value) => Ok(serde_json::from_value(value)?),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn save_flags(
+    bot_id: &str,
+    flags: &HashMap<String, FeatureFlag>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+    let value = serde_json::json!(flags);
+
+    if flags.is_empty() {
+        return state::delete_state_key(&client, "bot", "feature_flags", db);
+    }
+    state::replace_state_item(&client, "bot", "feature_flags", &value, None, db)
+}
+
+pub fn validate_flag(key: &str, flag: &FeatureFlag) -> Result<(), EngineError> {
+    validate_memory_key_format(key)?;
+
+    if matches!(flag.percentage, Some(percentage) if percentage > 100) {
+        return Err(EngineError::Format(
+            "the percentage of a flag must be between 0 and 100".to_owned(),
+        ));
+    }
+
+    for condition in flag.conditions.iter() {
+        match (&condition.operator, &condition.value) {
+            (FlagOperator::In, value) | (FlagOperator::NotIn, value) if !value.is_array() => {
+                return Err(EngineError::Format(format!(
+                    "the value of the condition on {} must be an array",
+                    condition.attribute
+                )))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+pub fn set_flag(
+    bot_id: &str,
+    key: &str,
+    flag: FeatureFlag,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    validate_flag(key, &flag)?;
+
+    let mut flags = get_flags(bot_id, db)?;
+    flags.insert(key.to_owned(), flag);
+
+    save_flags(bot_id, &flags, db)
+}
+
+/**
+ * Delete a flag of the bot, returns false if it does not exist
+ */
+pub fn delete_flag(bot_id: &str, key: &str, db: &mut Database) -> Result<bool, EngineError> {
+    let mut flags = get_flags(bot_id, db)?;
+    if flags.remove(key).is_none() {
+        return Ok(false);
+    }
+
+    save_flags(bot_id, &flags, db)?;
+
+    Ok(true)
+}
+
+/**
+ * Place the client in a bucket of the flag between 0 and 99
+ */
+fn get_flag_bucket(key: &str, client: &Client) -> u64 {
+    let mut hash = Md5::new();
+
+    hash.update(key.as_bytes());
+    hash.update(client.bot_id.as_bytes());
+    hash.update(client.channel_id.as_bytes());
+    hash.update(client.user_id.as_bytes());
+
+    let digest = hash.finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+
+    u64::from_be_bytes(bytes) % 100
+}
+
+fn is_flag_on(key: &str, flag: &FeatureFlag, client: &Client, attributes: &Value) -> bool {
+    if !flag.enabled {
+        return false;
+    }
+
+    let conditions_match = flag.conditions.iter().all(|condition| {
+        let pointer = format!("/{}", condition.attribute.replace('.', "/"));
+        let attribute = attributes.pointer(&pointer).unwrap_or(&Value::Null);
+
+        match (&condition.operator, &condition.value) {
+            (FlagOperator::Eq, value) => attribute == value,
+            (FlagOperator::Neq, value) => attribute != value,
+            (FlagOperator::In, Value::Array(values)) => values.contains(attribute),
+            (FlagOperator::NotIn, Value::Array(values)) => !values.contains(attribute),
+            _ => false,
+        }
+    });
+
+    conditions_match && get_flag_bucket(key, client) < flag.percentage.unwrap_or(100) as u64
+}
+
+/**
+ * Value of each flag of the bot for the client and the metadata of its event
+ */
+pub fn evaluate_flags(
+    flags: &HashMap<String, FeatureFlag>,
+    client: &Client,
+    metadata: &Value,
+) -> Map<String, Value> {
+    let attributes = serde_json::json!({
+        "client": client,
+        "metadata": metadata,
+    });
+
+    flags
+        .iter()
+        .map(|(key, flag)| {
+            let on = is_flag_on(key, flag, client, &attributes);
+            (key.to_owned(), Value::Bool(on))
+        })
+        .collect()
+}
+
+/**
+ * Get the flags of the bot for the interpreter context.
+ * Like the globals, a failing store must not break the conversation: the error is logged.
+ */
+pub fn get_client_flags(
+    client: &Client,
+    metadata: &Value,
+    flow: &str,
+    db: &mut Database,
+) -> HashMap<String, Literal> {
+    match get_flags(&client.bot_id, db) {
+        Ok(flags) => get_hashmap_from_mem(
+            &Value::Object(evaluate_flags(&flags, client, metadata)),
+            flow,
+        ),
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(
+                    Some(client),
+                    Some(flow.to_owned()),
+                    None,
+                    format!("failed to get feature flags of bot {}: {:?}", client.bot_id, err),
+                ),
+                LogLvl::Error,
+            );
+
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::FlagCondition;
+    use crate::db_connectors::init_db;
+
+    fn get_flag(percentage: Option<u8>, conditions: Vec<FlagCondition>) -> FeatureFlag {
+        FeatureFlag {
+            enabled: true,
+            percentage,
+            conditions,
+        }
+    }
+
+    #[test]
+    fn ok_evaluate_flags() {
+        let client = Client::new("bot".to_owned(), "channel".to_owned(), "user".to_owned());
+        let plan = |operator, value| FlagCondition {
+            attribute: "metadata.plan".to_owned(),
+            operator,
+            value,
+        };
+
+        let mut flags = HashMap::new();
+        flags.insert("all".to_owned(), get_flag(None, vec![]));
+        flags.insert("nobody".to_owned(), get_flag(Some(0), vec![]));
+        flags.insert(
+            "premium".to_owned(),
+            get_flag(None, vec![plan(FlagOperator::Eq, serde_json::json!("premium"))]),
+        );
+        flags.insert(
+            "paid".to_owned(),
+            get_flag(
+                None,
+                vec![plan(FlagOperator::In, serde_json::json!(["premium", "pro"]))],
+            ),
+        );
+        let mut disabled = get_flag(None, vec![]);
+        disabled.enabled = false;
+        flags.insert("disabled".to_owned(), disabled);
+
+        let metadata = serde_json::json!({"plan": "pro"});
+        let values = evaluate_flags(&flags, &client, &metadata);
+        assert_eq!(
+            Value::Object(values),
+            serde_json::json!({
+                "all": true, "nobody": false, "premium": false, "paid": true, "disabled": false
+            })
+        );
+    }
+
+    #[test]
+    fn ok_flag_percentage() {
+        let flag = get_flag(Some(30), vec![]);
+        let attributes = serde_json::json!({});
+
+        let on = (0..1000)
+            .filter(|user| {
+                let client = Client::new("bot".to_owned(), "channel".to_owned(), user.to_string());
+                is_flag_on("new_menu", &flag, &client, &attributes)
+            })
+            .count();
+        assert!(on > 200 && on < 400);
+
+        // a given client always gets the same value
+        let client = Client::new("bot".to_owned(), "channel".to_owned(), "user".to_owned());
+        let first = is_flag_on("new_menu", &flag, &client, &attributes);
+        assert!((0..10).all(|_| is_flag_on("new_menu", &flag, &client, &attributes) == first));
+    }
+
+    #[test]
+    fn ok_feature_flags() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        set_flag("bot_flags", "new_menu", get_flag(Some(50), vec![]), &mut db).unwrap();
+        set_flag("bot_flags", "beta", get_flag(None, vec![]), &mut db).unwrap();
+        assert!(set_flag("bot_flags", "beta", get_flag(Some(101), vec![]), &mut db).is_err());
+
+        let flags = get_flags("bot_flags", &mut db).unwrap();
+        assert_eq!(flags.len(), 2);
+        assert_eq!(flags["new_menu"].percentage, Some(50));
+
+        assert!(delete_flag("bot_flags", "new_menu", &mut db).unwrap());
+        assert!(!delete_flag("bot_flags", "new_menu", &mut db).unwrap());
+        assert!(delete_flag("bot_flags", "beta", &mut db).unwrap());
+        assert!(get_flags("bot_flags", &mut db).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+/**
+ * Storage of the files received by the bots: the urls of the file, image, audio and video events
+ * sent by the channels often expire after a few minutes or hours. When FILES_S3_BUCKET is set,
+ * the file is downloaded, optionally scanned, then saved to an object storage (see
+ * object_storage.rs for the FILES_S3_* configuration, requires the object_storage feature) and
+ * the event gets a durable url:
+ *
+ * event.file = {"status": "stored", "url": String, "original_url": String,
+ *               "content_type": String, "size": Number}
+ *
+ * - FILES_PREFIX: prefix of the keys of the files (default csml-files)
+ * - FILES_PUBLIC_URL: base url of the stored files (a CDN or a public bucket), defaults to the
+ *   url of the objects in the bucket
+ * - FILES_MAX_SIZE: maximum size of the files in bytes (default 20 MB)
+ * - FILES_TIMEOUT: milliseconds to download a file (default 10000)
+ * - FILES_SCAN_URL: optional virus scan hook, receives the content of the file and answers
+ *   {"clean": Boolean}, with the optional Authorization header FILES_SCAN_AUTH
+ *
+ * A file rejected by the scan is not stored: event.file.status is "rejected" and event.file has
+ * no url. When the file can not be downloaded, scanned or stored, event.file.status is "failed"
+ * and the flows only have the url of the channel.
+ */
+use crate::{
+    config::get_config,
+    data::EngineError,
+    object_storage::Bucket,
+    Client,
+};
+
+use csml_interpreter::data::{csml_logs::*, Event};
+use serde_json::Value;
+use std::{io::Read, time::Duration};
+
+const FILE_CONTENT_TYPES: [&str; 4] = ["file", "image", "audio", "video"];
+const DEFAULT_PREFIX: &str = "csml-files";
+const DEFAULT_MAX_SIZE: u64 = 20 * 1024 * 1024;
+const DEFAULT_TIMEOUT: u64 = 10_000;
+
+struct DownloadedFile {
+    content_type: String,
+    data: Vec<u8>,
+}
+
+fn get_setting_number(key: &str, default: u64) -> u64 {
+    get_config().integer(key).unwrap_or(default)
+}
+
+/**
+ * Extension of the file in its url, kept in the key of the stored object
+ */
+fn get_extension(url: &str) -> String {
+    let path = url
+        .split(|c| c == '?' || c == '#')
+        .next()
+        .unwrap_or_default();
+    let name = path.rsplit('/').next().unwrap_or_default();
+
+    match name.rsplit_once('.') {
+        Some((_, extension))
+            if !extension.is_empty()
+                && extension.len() <= 8
+                && extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            format!(".{}", extension.to_ascii_lowercase())
+        }
+        _ => String::new(),
+    }
+}
+
+fn get_object_key(client: &Client, url: &str) -> String {
+    format!(
+        "{}/{}/{}/{}/{}{}",
+        get_config().text("files.prefix").unwrap_or(DEFAULT_PREFIX.to_owned()),
+        client.bot_id,
+        client.channel_id,
+        client.user_id,
+        uuid::Uuid::new_v4(),
+        get_extension(url)
+    )
+}
+
+fn get_public_url(bucket: &Bucket, key: &str) -> String {
+    match get_config().text("files.public_url") {
+        Some(base_url) => format!("{}/{}", base_url.trim_end_matches('/'), key),
+        None => bucket.object_url(key),
+    }
+}
+
+fn download_file(url: &str) -> Result<DownloadedFile, EngineError> {
+    let max_size = get_setting_number("files.max_size", DEFAULT_MAX_SIZE);
+    let timeout = Duration::from_millis(get_setting_number("files.timeout", DEFAULT_TIMEOUT));
+
+    let response = ureq::get(url)
+        .timeout(timeout)
+        .call()
+        .map_err(|err| EngineError::Manager(format!("file download error: {}", err)))?;
+
+    let content_type = response
+        .header("Content-Type")
+        .unwrap_or("application/octet-stream")
+        .to_owned();
+
+    let mut data = vec![];
+    response
+        .into_reader()
+        .take(max_size + 1)
+        .read_to_end(&mut data)?;
+
+    if data.len() as u64 > max_size {
+        return Err(EngineError::Manager(format!(
+            "file larger than FILES_MAX_SIZE ({} bytes)",
+            max_size
+        )));
+    }
+
+    Ok(DownloadedFile { content_type, data })
+}
+
+/**
+ * Ask the scan hook if the file is clean, every file is clean without a hook
+ */
+fn scan_file(file: &DownloadedFile) -> Result<bool, EngineError> {
+    let url = match get_config().text("files.scan_url") {
+        Some(url) => url,
+        None => return Ok(true),
+    };
+
+    let mut request = ureq::post(&url)
+        .timeout(Duration::from_millis(get_setting_number(
+            "files.timeout",
+            DEFAULT_TIMEOUT,
+        )))
+        .set("Content-Type", &file.content_type);
+    if let Some(auth) = get_config().text("files.scan_auth") {
+        request = request.set("Authorization", &auth);
+    }
+
+    let response: Value = request
+        .send_bytes(&file.data)
+        .map_err(|err| EngineError::Manager(format!("file scan error: {}", err)))?
+        .into_json()?;
+
+    match response["clean"].as_bool() {
+        Some(clean) => Ok(clean),
+        None => Err(EngineError::Manager(
+            "file scan error: expect {\"clean\": Boolean}".to_owned(),
+        )),
+    }
+}
+
+fn store_file(bucket: &Bucket, client: &Client, url: &str) -> Result<Value, EngineError> {
+    let file = download_file(url)?;
+
+    if !scan_file(&file)? {
+        return Ok(serde_json::json!({"status": "rejected", "original_url": url}));
+    }
+
+    let key = get_object_key(client, url);
+    bucket.put_object(&key, &file.data, &file.content_type)?;
+
+    Ok(serde_json::json!({
+        "status": "stored",
+        "url": get_public_url(bucket, &key),
+        "original_url": url,
+        "content_type": file.content_type,
+        "size": file.data.len(),
+    }))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Store the file of the event and set event.file, return true if the event was changed
+ */
+pub fn store_event_file(event: &mut Event, client: &Client) -> bool {
+    if event.secure || !FILE_CONTENT_TYPES.contains(&event.content_type.as_str()) {
+        return false;
+    }
+
+    let bucket = match Bucket::from_config("files") {
+        Ok(Some(bucket)) => bucket,
+        Ok(None) => return false,
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(Some(client), None, None, format!("{:?}", err)),
+                LogLvl::Error,
+            );
+            return false;
+        }
+    };
+    let url = match event.content["url"].as_str() {
+        Some(url) => url.to_owned(),
+        None => return false,
+    };
+
+    let file = match store_file(&bucket, client, &url) {
+        Ok(file) => file,
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(
+                    Some(client),
+                    None,
+                    None,
+                    format!("file of the event not stored: {:?}", err),
+                ),
+                LogLvl::Warn,
+            );
+            serde_json::json!({"status": "failed", "original_url": url})
+        }
+    };
+
+    event.content["file"] = file;
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_get_extension() {
+        assert_eq!(
+            get_extension("https://cdn.example.com/a/photo.JPG?sig=1"),
+            ".jpg"
+        );
+        assert_eq!(get_extension("https://cdn.example.com/a/file"), "");
+        assert_eq!(
+            get_extension("https://cdn.example.com/a/file.tar.gz#x"),
+            ".gz"
+        );
+        assert_eq!(
+            get_extension("https://cdn.example.com/a/file.php%00.jpg!"),
+            ""
+        );
+    }
+}
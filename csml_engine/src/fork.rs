@@ -0,0 +1,183 @@
+/**
+ * Forks of a conversation, for what-if analysis: the open conversation of a client is cloned
+ * into a sandbox client, so that a support agent or a test harness can send other events
+ * from the exact state of the user without changing the real conversation.
+ *
+ * The sandbox client has the bot and channel of the client and its own user_id
+ * ("{user_id}:fork:{uuid}"). The fork gets the flow and step of the conversation, the position
+ * held by the interpreter with the local variables of the step, the context variables, the
+ * metadata of the conversation and the memories of the client. The messages are not copied.
+ * The data of the sandbox expire after SANDBOX_TTL_HOURS.
+ */
+use crate::{
+    conversation_context, conversation_metadata,
+    data::{Database, EngineError},
+    db_connectors::{conversations, memories, state},
+    Client,
+};
+
+use serde_json::Value;
+
+const SANDBOX_TTL_HOURS: i64 = 24;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_sandbox_client(client: &Client) -> Client {
+    Client {
+        user_id: format!("{}:fork:{}", client.user_id, uuid::Uuid::new_v4()),
+        ..client.to_owned()
+    }
+}
+
+fn copy_memories(
+    client: &Client,
+    sandbox: &Client,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let saved = match memories::get_memories(client, db)? {
+        Value::Array(saved) => saved,
+        _ => vec![],
+    };
+
+    for memory in saved {
+        if let Some(key) = memory["key"].as_str() {
+            memories::create_client_memory(
+                sandbox,
+                key.to_owned(),
+                memory["value"].to_owned(),
+                ttl,
+                db,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * {"client", "conversation_id", "forked_from"}: the sandbox client and its conversation.
+ * None if the conversation is not the open conversation of the client.
+ */
+pub fn fork_conversation(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Option<Value>, EngineError> {
+    let conversation = match conversations::get_latest_open(client, db)? {
+        Some(conversation) if conversation.id == conversation_id => conversation,
+        _ => return Ok(None),
+    };
+
+    let sandbox = get_sandbox_client(client);
+    let ttl = Some(chrono::Duration::hours(SANDBOX_TTL_HOURS));
+
+    let fork_id = conversations::create_conversation(
+        &conversation.flow_id,
+        &conversation.step_id,
+        &sandbox,
+        ttl,
+        db,
+    )?;
+
+    if let Some(hold) = state::get_state_key(client, "hold", "position", db)? {
+        state::set_state_items(&sandbox, "hold", vec![("position", &hold)], ttl, db)?;
+    }
+
+    let variables = conversation_context::get_context_variables(client, conversation_id, db)?;
+    conversation_context::save_context_variables(&sandbox, &fork_id, &variables, ttl, db)?;
+
+    let metadata = conversation_metadata::get_conversation_metadata(client, conversation_id, db)?;
+    if !metadata.is_empty() {
+        conversation_metadata::update_conversation_metadata(
+            &sandbox,
+            &fork_id,
+            &Value::Object(metadata),
+            ttl,
+            db,
+        )?;
+    }
+
+    copy_memories(client, &sandbox, ttl, db)?;
+
+    Ok(Some(serde_json::json!({
+        "client": sandbox,
+        "conversation_id": fork_id,
+        "forked_from": conversation_id,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{init_db, user};
+
+    #[test]
+    fn ok_fork_conversation() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let client = Client {
+            bot_id: "bot_fork".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "user_id".to_owned(),
+            tenant_id: None,
+        };
+        user::delete_client(&client, &mut db).unwrap();
+
+        let conversation_id =
+            conversations::create_conversation("Default", "ask_name", &client, None, &mut db)
+                .unwrap();
+        let hold = serde_json::json!({
+            "index": {"command_index": 2, "loop_index": []},
+            "step_vars": {"retries": 1},
+            "hash": "hash",
+            "previous": null,
+            "secure": false,
+            "breakpoint": null,
+        });
+        state::set_state_items(&client, "hold", vec![("position", &hold)], None, &mut db).unwrap();
+        memories::create_client_memory(&client, "name".to_owned(), "Jane".into(), None, &mut db)
+            .unwrap();
+
+        let fork = fork_conversation(&client, &conversation_id, &mut db)
+            .unwrap()
+            .unwrap();
+        let sandbox: Client = serde_json::from_value(fork["client"].clone()).unwrap();
+        assert_eq!(sandbox.bot_id, client.bot_id);
+        assert_ne!(sandbox.user_id, client.user_id);
+        assert_eq!(fork["forked_from"], conversation_id.as_str());
+
+        let forked = conversations::get_latest_open(&sandbox, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(forked.id, fork["conversation_id"]);
+        assert_eq!(forked.step_id, "ask_name");
+        let forked_hold = state::get_state_key(&sandbox, "hold", "position", &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(forked_hold["step_vars"]["retries"], 1);
+        let forked_memories = memories::get_memories(&sandbox, &mut db).unwrap();
+        assert_eq!(forked_memories[0]["value"], "Jane");
+
+        // the real conversation is not changed by the events of the sandbox
+        conversations::close_all_conversations(&sandbox, &mut db).unwrap();
+        let open = conversations::get_latest_open(&client, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(open.id, conversation_id);
+
+        assert!(fork_conversation(&client, "other_id", &mut db)
+            .unwrap()
+            .is_none());
+
+        user::delete_client(&sandbox, &mut db).unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+    }
+}
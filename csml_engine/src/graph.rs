@@ -0,0 +1,277 @@
+/**
+ * Conversation maps: the steps of every flow of a bot and the goto transitions between them,
+ * rendered in DOT (graphviz) or Mermaid.
+ *
+ * The transitions are read from the AST of the flows, a goto targeting a variable
+ * (goto $step) is only known at runtime and is not part of the graph.
+ */
+use crate::data::{BotGraphFormat, EngineError};
+
+use csml_interpreter::data::csml_bot::CsmlBot;
+use csml_interpreter::parse_flow_to_ast_json;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURES
+////////////////////////////////////////////////////////////////////////////////
+
+struct FlowNodes {
+    name: String,
+    steps: Vec<String>,
+}
+
+#[derive(PartialEq)]
+enum Target {
+    Step { flow: String, step: String },
+    End,
+}
+
+#[derive(PartialEq)]
+struct Transition {
+    flow: String,
+    step: String,
+    target: Target,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_target(flow: &str, goto: &serde_json::Value) -> Option<Target> {
+    // gotos to a variable or to another bot can not be drawn
+    if goto["dynamic"].as_bool() == Some(true) || !goto["bot"].is_null() {
+        return None;
+    }
+
+    let step = goto["step"].as_str();
+    let target_flow = goto["flow"].as_str();
+
+    match (step, target_flow) {
+        (Some("end"), None) => Some(Target::End),
+        (step, target_flow) => Some(Target::Step {
+            flow: target_flow.unwrap_or(flow).to_owned(),
+            step: step.unwrap_or("start").to_owned(),
+        }),
+    }
+}
+
+fn get_nodes(bot: &CsmlBot) -> Result<(Vec<FlowNodes>, Vec<Transition>), EngineError> {
+    let mut flows: Vec<_> = bot.flows.iter().collect();
+    flows.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut nodes = vec![];
+    let mut transitions = vec![];
+
+    for flow in flows {
+        let ast = parse_flow_to_ast_json(&flow.content)
+            .map_err(|err| EngineError::Parring(err.format_error()))?;
+
+        let mut steps = vec![];
+        for step in ast["steps"].as_array().into_iter().flatten() {
+            let name = step["name"].as_str().unwrap_or_default().to_owned();
+
+            for goto in step["gotos"].as_array().into_iter().flatten() {
+                let transition = match get_target(&flow.name, goto) {
+                    Some(target) => Transition {
+                        flow: flow.name.to_owned(),
+                        step: name.to_owned(),
+                        target,
+                    },
+                    None => continue,
+                };
+
+                if !transitions.contains(&transition) {
+                    transitions.push(transition);
+                }
+            }
+
+            steps.push(name);
+        }
+
+        nodes.push(FlowNodes {
+            name: flow.name.to_owned(),
+            steps,
+        });
+    }
+
+    Ok((nodes, transitions))
+}
+
+fn get_mermaid_id(flow: &str, step: &str) -> String {
+    format!("{}__{}", flow, step)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn make_mermaid(nodes: &[FlowNodes], transitions: &[Transition]) -> String {
+    let mut lines = vec!["flowchart LR".to_owned()];
+
+    for flow in nodes.iter() {
+        lines.push(format!(
+            "    subgraph {}[{}]",
+            get_mermaid_id(&flow.name, ""),
+            flow.name
+        ));
+        for step in flow.steps.iter() {
+            lines.push(format!(
+                "        {}[{}]",
+                get_mermaid_id(&flow.name, step),
+                step
+            ));
+        }
+        lines.push("    end".to_owned());
+    }
+
+    // 'end' is a keyword in mermaid
+    if transitions.iter().any(|t| t.target == Target::End) {
+        lines.push("    csml_end((end))".to_owned());
+    }
+
+    for transition in transitions.iter() {
+        let target = match &transition.target {
+            Target::Step { flow, step } => get_mermaid_id(flow, step),
+            Target::End => "csml_end".to_owned(),
+        };
+
+        lines.push(format!(
+            "    {} --> {}",
+            get_mermaid_id(&transition.flow, &transition.step),
+            target
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn make_dot(nodes: &[FlowNodes], transitions: &[Transition]) -> String {
+    let mut lines = vec!["digraph bot {".to_owned()];
+
+    for flow in nodes.iter() {
+        lines.push(format!("    subgraph \"cluster_{}\" {{", flow.name));
+        lines.push(format!("        label=\"{}\";", flow.name));
+        for step in flow.steps.iter() {
+            lines.push(format!(
+                "        \"{}.{}\" [label=\"{}\"];",
+                flow.name, step, step
+            ));
+        }
+        lines.push("    }".to_owned());
+    }
+
+    if transitions.iter().any(|t| t.target == Target::End) {
+        lines.push("    \"end\" [shape=doublecircle];".to_owned());
+    }
+
+    for transition in transitions.iter() {
+        let target = match &transition.target {
+            Target::Step { flow, step } => format!("{}.{}", flow, step),
+            Target::End => "end".to_owned(),
+        };
+
+        lines.push(format!(
+            "    \"{}.{}\" -> \"{}\";",
+            transition.flow, transition.step, target
+        ));
+    }
+
+    lines.push("}".to_owned());
+    lines.join("\n")
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn make_graph(bot: &CsmlBot, format: BotGraphFormat) -> Result<String, EngineError> {
+    let (nodes, transitions) = get_nodes(bot)?;
+
+    match format {
+        BotGraphFormat::Dot => Ok(make_dot(&nodes, &transitions)),
+        BotGraphFormat::Mermaid => Ok(make_mermaid(&nodes, &transitions)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::data::CsmlFlow;
+
+    fn get_bot() -> CsmlBot {
+        CsmlBot::new(
+            "bot_id",
+            "bot",
+            None,
+            vec![
+                CsmlFlow::new(
+                    "Default",
+                    "Default",
+                    "start:\n    goto next\n\nnext:\n    goto flow other\n    goto $var",
+                    vec![],
+                ),
+                CsmlFlow::new(
+                    "other",
+                    "other",
+                    "start:\n    goto last\n\nlast:\n    goto next@Default\n    goto end",
+                    vec![],
+                ),
+            ],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn ok_mermaid_graph() {
+        let graph = make_graph(&get_bot(), BotGraphFormat::Mermaid).unwrap();
+
+        assert_eq!(
+            graph,
+            "flowchart LR
+    subgraph Default__[Default]
+        Default__start[start]
+        Default__next[next]
+    end
+    subgraph other__[other]
+        other__start[start]
+        other__last[last]
+    end
+    csml_end((end))
+    Default__start --> Default__next
+    Default__next --> other__start
+    other__start --> other__last
+    other__last --> Default__next
+    other__last --> csml_end"
+        );
+    }
+
+    #[test]
+    fn ok_dot_graph() {
+        let graph = make_graph(&get_bot(), BotGraphFormat::Dot).unwrap();
+
+        assert!(graph.starts_with("digraph bot {\n    subgraph \"cluster_Default\" {"));
+        assert!(graph.contains("        \"Default.next\" [label=\"next\"];"));
+        assert!(graph.contains("    \"Default.next\" -> \"other.start\";"));
+        assert!(graph.contains("    \"other.last\" -> \"end\";"));
+        assert!(graph.ends_with('}'));
+    }
+
+    #[test]
+    fn ko_graph_parsing_error() {
+        let mut bot = get_bot();
+        bot.flows[0].content = "start:\n    say (".to_owned();
+
+        assert!(make_graph(&bot, BotGraphFormat::Dot).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+/**
+ * Human handover: a conversation can be paused for a human agent, either by the bot
+ * with the Handover() builtin or through the engine API.
+ *
+ * While a conversation is PAUSED_FOR_AGENT, the user's events are saved but not interpreted.
+ * When the agent is done, the conversation is resumed at a chosen step of the bot.
+ *
+ * The handover is saved in the client state (type "handover") with the id of the paused
+ * conversation: if this conversation is closed or expires, the handover is discarded.
+ */
+use crate::{
+    data::{ConversationInfo, CsmlRequest, Database, EngineError, RunContext},
+    db_connectors::{conversations, messages, state, DbConversation},
+    init::init_context,
+    utils::{get_low_data_mode_value, get_ttl_duration_value, messages_formatter},
+    Client,
+};
+use csml_interpreter::data::{context::ContextStepInfo, csml_bot::CsmlBot, Event};
+
+use chrono::{prelude::Utc, SecondsFormat};
+
+pub const PAUSED_FOR_AGENT: &str = "PAUSED_FOR_AGENT";
+
+/**
+ * Pause a conversation until a human agent resumes it
+ */
+pub fn pause_conversation(
+    client: &Client,
+    conversation_id: &str,
+    metadata: &serde_json::Value,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let handover = serde_json::json!({
+        "conversation_id": conversation_id,
+        "status": PAUSED_FOR_AGENT,
+        "metadata": metadata,
+        "paused_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    });
+
+    state::replace_state_item(client, "handover", "status", &handover, ttl, db)
+}
+
+pub fn resume_conversation(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    state::delete_state_key(client, "handover", "status", db)
+}
+
+/**
+ * Return the open conversation of the client if it is paused for an agent
+ */
+pub fn get_paused_conversation(
+    client: &Client,
+    db: &mut Database,
+) -> Result<Option<DbConversation>, EngineError> {
+    let handover = match state::get_state_key(client, "handover", "status", db)? {
+        Some(handover) => handover,
+        None => return Ok(None),
+    };
+
+    match conversations::get_latest_open(client, db)? {
+        Some(mut conversation) if handover["conversation_id"] == conversation.id.as_str() => {
+            conversation.status = PAUSED_FOR_AGENT.to_owned();
+            Ok(Some(conversation))
+        }
+        // the paused conversation is not open anymore
+        _ => {
+            resume_conversation(client, db)?;
+            Ok(None)
+        }
+    }
+}
+
+/**
+ * Save the user's event in the paused conversation without interpreting it
+ */
+pub fn save_paused_event(
+    conversation: DbConversation,
+    request: &CsmlRequest,
+    event: &Event,
+    bot: &CsmlBot,
+    run_context: &RunContext,
+    mut db: Database,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut context = init_context(
+        conversation.flow_id.to_owned(),
+        request.client.clone(),
+        bot,
+        &mut db,
+    );
+    context.step = ContextStepInfo::Normal(conversation.step_id.to_owned());
+
+    let mut data = ConversationInfo {
+        request_id: request.request_id.clone(),
+        conversation_id: conversation.id,
+        conversation_version: conversation.version,
+        callback_url: request.callback_url.clone(),
+        client: request.client.clone(),
+        context,
+        metadata: request.metadata.clone(),
+        messages: vec![],
+        ttl: get_ttl_duration_value(Some(event)),
+        low_data: get_low_data_mode_value(event),
+        no_data_retention: bot.no_data_retention.unwrap_or(false),
+        bot_version_id: None,
+        run_context: run_context.to_owned(),
+        db,
+    };
+
+    match (data.low_data, event.secure) {
+        (false, true) => {
+            let msgs = vec![serde_json::json!({"content_type": "secure"})];
+            messages::add_messages_bulk(&mut data, msgs, 0, "RECEIVE")?;
+        }
+        (false, false) => {
+            let msgs = vec![request.payload.to_owned()];
+            messages::add_messages_bulk(&mut data, msgs, 0, "RECEIVE")?;
+        }
+        (true, _) => {}
+    }
+
+    Ok(messages_formatter(&mut data, vec![], 0, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_handover() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "handover_test".to_owned(),
+        );
+        conversations::close_all_conversations(&client, &mut db).unwrap();
+
+        let conversation_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+        assert!(get_paused_conversation(&client, &mut db).unwrap().is_none());
+
+        let metadata = serde_json::json!({"reason": "billing question"});
+        pause_conversation(&client, &conversation_id, &metadata, None, &mut db).unwrap();
+
+        let paused = get_paused_conversation(&client, &mut db).unwrap().unwrap();
+        assert_eq!(paused.id, conversation_id);
+        assert_eq!(paused.status, PAUSED_FOR_AGENT);
+
+        resume_conversation(&client, &mut db).unwrap();
+        assert!(get_paused_conversation(&client, &mut db).unwrap().is_none());
+
+        // a handover is discarded once its conversation is closed
+        pause_conversation(&client, &conversation_id, &metadata, None, &mut db).unwrap();
+        conversations::close_conversation(&conversation_id, &client, &mut db).unwrap();
+        assert!(get_paused_conversation(&client, &mut db).unwrap().is_none());
+        assert!(state::get_state_key(&client, "handover", "status", &mut db)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,7 +1,18 @@
-use crate::db_connectors::{conversations::*, memories::*, state};
+use crate::bot_config::{add_metadata_defaults, apply_bot_env};
+use crate::bot_globals::get_bot_globals;
+use crate::config::get_config;
+use crate::conversation_context::get_context_variables;
+use crate::conversation_metadata::merge_event_metadata;
+use crate::debugger::get_debugger;
+use crate::events::*;
+use crate::feature_flags::get_client_flags;
+use crate::memory_scopes::get_user_memories;
+use crate::db_connectors::{conversations::*, init_db, memories::*, state, DbConversation};
 use crate::interpreter_actions::SwitchBot;
+use crate::knowledge_base::get_bot_knowledge_base;
+use crate::secrets::get_bot_secrets;
 use crate::{
-    data::{ConversationInfo, CsmlRequest, Database, EngineError},
+    data::{ConversationInfo, CsmlRequest, Database, EngineError, RunContext},
     utils::{
         get_default_flow, get_flow_by_id, get_low_data_mode_value, get_ttl_duration_value,
         search_flow, send_msg_to_callback_url,
@@ -19,7 +30,10 @@ use csml_interpreter::{
     load_components, search_for_modules, validate_bot,
 };
 
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, thread, time::Duration};
+
+const DEFAULT_CONFLICT_RETRIES: u32 = 3;
 
 /**
  * Initialize a new ConversationInfo data, usually upon new chat request.
@@ -37,20 +51,16 @@ use std::collections::HashMap;
  */
 pub fn init_conversation_info<'a>(
     default_flow: String,
-    event: &Event,
+    event: &mut Event,
     request: &'a CsmlRequest,
     bot: &'a CsmlBot,
+    bot_version_id: Option<String>,
     mut db: Database,
 ) -> Result<ConversationInfo, EngineError> {
     // Create a new interaction. An interaction is basically each request,
     // initiated from the bot or the user.
 
-    let mut context = init_context(
-        default_flow,
-        request.client.clone(),
-        &bot.apps_endpoint,
-        &mut db,
-    );
+    let mut context = init_context(default_flow, request.client.clone(), bot, &mut db);
     let ttl = get_ttl_duration_value(Some(event));
     let low_data = get_low_data_mode_value(event);
 
@@ -58,31 +68,62 @@ pub fn init_conversation_info<'a>(
     // or another, this takes precedence over any previously open conversation
     // and a new conversation is created with the new flow as a starting point.
     let flow_found = search_flow(event, &bot, &request.client, &mut db).ok();
-    let conversation_id = get_or_create_conversation(
+    let (conversation_id, conversation_version) = get_or_create_conversation(
         &mut context,
         &bot,
         flow_found,
         &request.client,
+        event,
         ttl,
+        bot_version_id.as_deref(),
         &mut db,
     )?;
 
-    context.metadata = get_hashmap_from_json(&request.metadata, &context.flow);
+    // the metadata patched during the conversation are completed by the metadata of the event
+    let metadata = merge_event_metadata(
+        &request.client,
+        &conversation_id,
+        &request.metadata,
+        &mut db,
+    )?;
+    // the configuration of the bot provides the defaults of the metadata
+    let metadata = add_metadata_defaults(&bot.id, metadata, &mut db)?;
+    context.metadata = get_hashmap_from_json(&metadata, &context.flow);
+    context.flags = get_client_flags(&request.client, &metadata, &context.flow, &mut db);
+    // channel memories take precedence over the memories shared by the channels of the user
     context.current = get_hashmap_from_mem(
-        &internal_use_get_memories(&request.client, &mut db)?,
+        &get_user_memories(&request.client, &mut db)?,
         &context.flow,
     );
+    context.current.extend(get_hashmap_from_mem(
+        &internal_use_get_memories(&request.client, &mut db)?,
+        &context.flow,
+    ));
+    // context variables of the conversation take precedence over the memories
+    let variables = get_context_variables(&request.client, &conversation_id, &mut db)?;
+    let variables: serde_json::Map<String, serde_json::Value> = variables
+        .into_iter()
+        .map(|(key, memory)| (key, memory.value))
+        .collect();
+    context.current.extend(get_hashmap_from_mem(
+        &serde_json::Value::Object(variables),
+        &context.flow,
+    ));
 
     let mut data = ConversationInfo {
         conversation_id,
+        conversation_version,
         context,
-        metadata: request.metadata.clone(), // ??
+        metadata,
         request_id: request.request_id.clone(),
         callback_url: request.callback_url.clone(),
         client: request.client.clone(),
         messages: vec![],
         ttl,
         low_data,
+        no_data_retention: bot.no_data_retention.unwrap_or(false),
+        bot_version_id,
+        run_context: RunContext::default(),
         db,
     };
 
@@ -91,15 +132,89 @@ pub fn init_conversation_info<'a>(
 
     // Now that everything is correctly setup, update the conversation with wherever
     // we are now and continue with the rest of the request!
-    update_conversation(&mut data, Some(flow), Some(step.get_step()))?;
+    update_conversation(&mut data, Some(flow.clone()), Some(step.get_step()))?;
+    publish_run_event(
+        STEP_ENTERED,
+        &data,
+        serde_json::json!({"flow_id": flow, "step_id": step.get_step()}),
+    );
 
     Ok(data)
 }
 
+/**
+ * init_conversation_info, retried with a backoff when another server updated the conversation
+ * between its read and the first update of the run (EngineError::Conflict): the conversation
+ * is read again. Nothing is saved for the event before this update.
+ * The conflicts are retried CONFLICT_RETRIES times (default 3).
+ */
+pub fn claim_conversation_info<'a>(
+    default_flow: String,
+    event: &mut Event,
+    request: &'a CsmlRequest,
+    bot: &'a CsmlBot,
+    bot_version_id: Option<String>,
+    db: Database,
+) -> Result<ConversationInfo, EngineError> {
+    let max_retries = get_config()
+        .integer("conversations.conflict_retries")
+        .map(|value| value as u32)
+        .unwrap_or(DEFAULT_CONFLICT_RETRIES);
+    let mut retries = 0;
+    let mut db = Some(db);
+
+    loop {
+        let db = match db.take() {
+            Some(db) => db,
+            None => init_db()?,
+        };
+        // the event of an expired conversation is changed by its restart
+        let mut claimed_event = event.clone();
+
+        match init_conversation_info(
+            default_flow.to_owned(),
+            &mut claimed_event,
+            request,
+            bot,
+            bot_version_id.to_owned(),
+            db,
+        ) {
+            Ok(data) => {
+                *event = claimed_event;
+                return Ok(data);
+            }
+            Err(EngineError::Conflict) if retries < max_retries => {
+                retries += 1;
+                thread::sleep(Duration::from_millis(10 << retries));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 /**
  * Initialize the bot
  */
 pub fn init_bot(bot: &mut CsmlBot) -> Result<(), EngineError> {
+    load_bot_dependencies(bot)?;
+
+    set_bot_ast(bot)
+}
+
+/**
+ * Initialize a bot version saved by the engine: its flows are only parsed
+ * if no AST compatible with this engine version was saved with it
+ */
+pub fn init_saved_bot(bot: &mut CsmlBot) -> Result<(), EngineError> {
+    load_bot_dependencies(bot)?;
+
+    match bot.bot_ast {
+        Some(_) => Ok(()),
+        None => set_bot_ast(bot),
+    }
+}
+
+fn load_bot_dependencies(bot: &mut CsmlBot) -> Result<(), EngineError> {
     // load native components into the bot
     bot.native_components = match load_components() {
         Ok(components) => Some(components),
@@ -110,7 +225,17 @@ pub fn init_bot(bot: &mut CsmlBot) -> Result<(), EngineError> {
         return Err(EngineError::Interpreter(format!("{:?}", err)));
     }
 
-    set_bot_ast(bot)
+    Ok(())
+}
+
+/**
+ * Serialize the AST of the bot flows, as expected in bot.bot_ast
+ */
+pub fn encode_bot_ast(
+    flows: &HashMap<String, Flow>,
+    extern_flows: &HashMap<String, Flow>,
+) -> String {
+    base64::encode(bincode::serialize(&(flows, extern_flows)).unwrap())
 }
 
 /**
@@ -124,9 +249,7 @@ fn set_bot_ast(bot: &mut CsmlBot) -> Result<(), EngineError> {
             errors: None,
             ..
         } => {
-            bot.bot_ast = Some(base64::encode(
-                bincode::serialize(&(&flows, &extern_flows)).unwrap(),
-            ));
+            bot.bot_ast = Some(encode_bot_ast(&flows, &extern_flows));
         }
         CsmlResult {
             flows: Some(flows),
@@ -136,18 +259,13 @@ fn set_bot_ast(bot: &mut CsmlBot) -> Result<(), EngineError> {
         } => {
             let extern_flows: HashMap<String, Flow> = HashMap::new();
 
-            bot.bot_ast = Some(base64::encode(
-                bincode::serialize(&(&flows, &extern_flows)).unwrap(),
-            ));
+            bot.bot_ast = Some(encode_bot_ast(&flows, &extern_flows));
         }
         CsmlResult {
             errors: Some(errors),
             ..
         } => {
-            return Err(EngineError::Interpreter(format!(
-                "invalid bot {:?}",
-                errors
-            )))
+            return Err(EngineError::InvalidBot(errors))
         }
         _ => return Err(EngineError::Interpreter(format!("empty bot"))),
     }
@@ -158,21 +276,23 @@ fn set_bot_ast(bot: &mut CsmlBot) -> Result<(), EngineError> {
 /**
  * Initialize the context object for incoming requests
  */
-pub fn init_context(
-    flow: String,
-    client: Client,
-    apps_endpoint: &Option<String>,
-    db: &mut Database,
-) -> Context {
-    let previous_bot = get_previous_bot(&client, db);
+pub fn init_context(flow: String, client: Client, bot: &CsmlBot, db: &mut Database) -> Context {
+    let mut context = init_stateless_context(flow, client.clone(), bot);
 
-    let api_info = match apps_endpoint {
-        Some(value) => Some(ApiInfo {
-            client,
-            apps_endpoint: value.to_owned(),
-        }),
-        None => None,
-    };
+    context.previous_bot = get_previous_bot(&client, db);
+    context.debugger = get_debugger(&client, db).unwrap_or(None);
+    context.globals = get_bot_globals(&bot.id, &context.flow, db);
+
+    context
+}
+
+/**
+ * Initialize the context without the data saved in the database
+ * (previous bot, debugger and globals of the bot)
+ */
+pub fn init_stateless_context(flow: String, client: Client, bot: &CsmlBot) -> Context {
+    let api_info = get_api_info(client.clone(), bot);
+    let secrets = get_bot_secrets(&bot.id, &flow);
 
     Context {
         current: HashMap::new(),
@@ -181,7 +301,32 @@ pub fn init_context(
         hold: None,
         step: ContextStepInfo::Normal("start".to_owned()),
         flow,
-        previous_bot,
+        previous_bot: None,
+        secrets,
+        globals: HashMap::new(),
+        flags: HashMap::new(),
+        debugger: None,
+        client: Some(client),
+        knowledge_base: get_bot_knowledge_base(&bot.id),
+        sandbox: None,
+        step_hooks: None,
+    }
+}
+
+/**
+ * Apps can be called if the bot has an apps_endpoint or at least one named app
+ */
+fn get_api_info(client: Client, bot: &CsmlBot) -> Option<ApiInfo> {
+    let apps = bot.apps.to_owned().unwrap_or_default();
+
+    match (&bot.apps_endpoint, apps.is_empty()) {
+        (None, true) => None,
+        (apps_endpoint, _) => Some(ApiInfo {
+            client,
+            apps_endpoint: apps_endpoint.to_owned(),
+            apps_endpoint_options: bot.apps_endpoint_options.to_owned().unwrap_or_default(),
+            apps,
+        }),
     }
 }
 
@@ -192,18 +337,109 @@ fn get_previous_bot(client: &Client, db: &mut Database) -> Option<PreviousBot> {
     }
 }
 
+/**
+ * A conversation is expired when the bot has a conversation_expiration
+ * and there was no interaction during its inactivity_timeout
+ */
+fn is_conversation_expired(conversation: &DbConversation, bot: &CsmlBot) -> bool {
+    let expiration = match &bot.conversation_expiration {
+        Some(expiration) => expiration,
+        None => return false,
+    };
+
+    match DateTime::parse_from_rfc3339(&conversation.last_interaction_at) {
+        Ok(last_interaction_at) => {
+            Utc::now().signed_duration_since(last_interaction_at)
+                > chrono::Duration::seconds(expiration.inactivity_timeout as i64)
+        }
+        Err(_) => false,
+    }
+}
+
+/**
+ * Close the expired conversation and start a new one.
+ * If the bot has an expiration flow, the new conversation starts in this flow and the event
+ * is sent as a conversation_expired event: its value is kept and the original event
+ * is available in its content.
+ */
+fn restart_expired_conversation<'a>(
+    conversation: DbConversation,
+    context: &mut Context,
+    bot: &'a CsmlBot,
+    flow_found: Option<(&'a CsmlFlow, String)>,
+    client: &Client,
+    event: &mut Event,
+    ttl: Option<chrono::Duration>,
+    bot_version_id: Option<&str>,
+    db: &mut Database,
+) -> Result<String, EngineError> {
+    close_conversation(&conversation.id, client, db)?;
+    publish_version_event(
+        CONVERSATION_CLOSED,
+        client,
+        Some(&conversation.id),
+        bot_version_id,
+        serde_json::json!({"reason": "expired"}),
+    );
+    // a hold of the expired conversation must not be resumed
+    state::delete_state_key(client, "hold", "position", db)?;
+
+    let expiration_flow = bot
+        .conversation_expiration
+        .as_ref()
+        .and_then(|expiration| expiration.flow.as_ref())
+        .and_then(|flow| get_flow_by_id(flow, &bot.flows).ok());
+
+    let flow_found = match expiration_flow {
+        Some(flow) => {
+            event.content = serde_json::json!({
+                "expired_conversation_id": conversation.id,
+                "last_interaction_at": conversation.last_interaction_at,
+                "event": {
+                    "content_type": event.content_type,
+                    "content": event.content,
+                },
+            });
+            event.content_type = "conversation_expired".to_owned();
+
+            Some((flow, "start".to_owned()))
+        }
+        None => flow_found,
+    };
+
+    create_new_conversation(context, bot, flow_found, client, ttl, bot_version_id, db)
+}
+
 /**
  * Retrieve the current conversation, or create one if none exists.
+ * Returns the id and the version of the conversation.
  */
 fn get_or_create_conversation<'a>(
     context: &mut Context,
     bot: &'a CsmlBot,
     flow_found: Option<(&'a CsmlFlow, String)>,
     client: &Client,
+    event: &mut Event,
     ttl: Option<chrono::Duration>,
+    bot_version_id: Option<&str>,
     db: &mut Database,
-) -> Result<String, EngineError> {
+) -> Result<(String, i32), EngineError> {
     match get_latest_open(client, db)? {
+        Some(conversation) if is_conversation_expired(&conversation, bot) => {
+            let conversation_id = restart_expired_conversation(
+                conversation,
+                context,
+                bot,
+                flow_found,
+                client,
+                event,
+                ttl,
+                bot_version_id,
+                db,
+            )?;
+
+            Ok((conversation_id, 0))
+        }
         Some(conversation) => {
             match flow_found {
                 Some((flow, step)) => {
@@ -216,10 +452,24 @@ fn get_or_create_conversation<'a>(
                         Err(..) => {
                             // if flow id exist in db but not in bot close conversation
                             close_conversation(&conversation.id, &client, db)?;
-                            // start new conversation at default flow
-                            return create_new_conversation(
-                                context, bot, flow_found, client, ttl, db,
+                            publish_version_event(
+                                CONVERSATION_CLOSED,
+                                client,
+                                Some(&conversation.id),
+                                bot_version_id,
+                                serde_json::json!({"reason": "unknown_flow"}),
                             );
+                            // start new conversation at default flow
+                            let conversation_id = create_new_conversation(
+                                context,
+                                bot,
+                                flow_found,
+                                client,
+                                ttl,
+                                bot_version_id,
+                                db,
+                            )?;
+                            return Ok((conversation_id, 0));
                         }
                     };
 
@@ -228,9 +478,20 @@ fn get_or_create_conversation<'a>(
                 }
             };
 
-            Ok(conversation.id)
+            Ok((conversation.id, conversation.version))
+        }
+        None => {
+            let conversation_id = create_new_conversation(
+                context,
+                bot,
+                flow_found,
+                client,
+                ttl,
+                bot_version_id,
+                db,
+            )?;
+            Ok((conversation_id, 0))
         }
-        None => create_new_conversation(context, bot, flow_found, client, ttl, db),
     }
 }
 
@@ -243,6 +504,7 @@ fn create_new_conversation<'a>(
     flow_found: Option<(&'a CsmlFlow, String)>,
     client: &Client,
     ttl: Option<chrono::Duration>,
+    bot_version_id: Option<&str>,
     db: &mut Database,
 ) -> Result<String, EngineError> {
     let (flow, step) = match flow_found {
@@ -251,6 +513,13 @@ fn create_new_conversation<'a>(
     };
 
     let conversation_id = create_conversation(&flow.id, &step, client, ttl, db)?;
+    publish_version_event(
+        CONVERSATION_STARTED,
+        client,
+        Some(&conversation_id),
+        bot_version_id,
+        serde_json::json!({"flow_id": flow.id, "step_id": step}),
+    );
 
     context.step = ContextStepInfo::UnknownFlow(step);
     context.flow = flow.name.to_owned();
@@ -268,6 +537,14 @@ pub fn switch_bot(
     bot_opt: &mut BotOpt,
     event: &mut Event,
 ) -> Result<(), EngineError> {
+    // the next bot is searched in the same environment as the current one
+    let environment = match bot_opt {
+        BotOpt::BotId { environment, .. } => environment.take(),
+        _ => None,
+    };
+
+    let next_bot_id = next_bot.bot_id.to_owned();
+
     // update data info with new bot |ex| client bot_id, create new conversation
     *bot_opt = match next_bot.version_id {
         Some(version_id) => BotOpt::Id {
@@ -280,12 +557,17 @@ pub fn switch_bot(
             bot_id: next_bot.bot_id,
             apps_endpoint: bot.apps_endpoint.take(),
             multibot: bot.multibot.take(),
+            environment,
         },
     };
+    // a bot can only switch to the bots of its tenant
+    bot_opt.set_tenant(data.client.tenant_id.as_deref());
 
-    let mut new_bot = bot_opt.search_bot(&mut data.db)?;
+    let (mut new_bot, version_id) = bot_opt.search_bot_version(&mut data.db)?;
+    data.bot_version_id = version_id;
     new_bot.custom_components = bot.custom_components.take();
     new_bot.native_components = bot.native_components.take();
+    apply_bot_env(&mut new_bot, &mut data.db)?;
 
     *bot = new_bot;
 
@@ -298,7 +580,12 @@ pub fn switch_bot(
     };
 
     // update client with the new bot id
-    data.client.bot_id = bot.id.to_owned();
+    data.client.bot_id = next_bot_id;
+    data.context.api_info = get_api_info(data.client.clone(), bot);
+    data.context.secrets = get_bot_secrets(&bot.id, &data.context.flow);
+    data.context.knowledge_base = get_bot_knowledge_base(&bot.id);
+    data.context.flags =
+        get_client_flags(&data.client, &data.metadata, &data.context.flow, &mut data.db);
 
     let (flow, step) = match get_flow_by_id(&data.context.flow, &bot.flows) {
         Ok(flow) => (flow, data.context.step.clone()),
@@ -345,6 +632,12 @@ pub fn switch_bot(
         data.ttl.clone(),
         &mut data.db,
     )?;
+    data.conversation_version = 0;
+    publish_run_event(
+        CONVERSATION_STARTED,
+        &data,
+        serde_json::json!({"flow_id": flow.id, "step_id": step.get_step()}),
+    );
 
     // and get memories of the new bot form db,
     // clearing the permanent memories form scope of the previous bot
@@ -355,3 +648,64 @@ pub fn switch_bot(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::data::ConversationExpiration;
+
+    fn get_conversation(last_interaction_at: DateTime<Utc>) -> DbConversation {
+        DbConversation {
+            id: "conversation_id".to_owned(),
+            client: Client::new(
+                "bot_id".to_owned(),
+                "channel_id".to_owned(),
+                "user_id".to_owned(),
+            ),
+            flow_id: "Default".to_owned(),
+            step_id: "start".to_owned(),
+            status: "OPEN".to_owned(),
+            last_interaction_at: last_interaction_at.to_rfc3339(),
+            updated_at: last_interaction_at.to_rfc3339(),
+            created_at: last_interaction_at.to_rfc3339(),
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn ok_conversation_expiration() {
+        let mut bot = CsmlBot::new(
+            "bot_id",
+            "bot",
+            None,
+            vec![CsmlFlow::new("Default", "Default", "start: say \"hello\"", vec![])],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let old_conversation = get_conversation(Utc::now() - chrono::Duration::hours(2));
+        let recent_conversation = get_conversation(Utc::now() - chrono::Duration::minutes(5));
+
+        assert!(!is_conversation_expired(&old_conversation, &bot));
+
+        bot.conversation_expiration = Some(ConversationExpiration {
+            inactivity_timeout: 3600,
+            flow: None,
+        });
+
+        assert!(is_conversation_expired(&old_conversation, &bot));
+        assert!(!is_conversation_expired(&recent_conversation, &bot));
+    }
+}
@@ -1,4 +1,19 @@
-use crate::db_connectors::{conversations::*, memories::*, messages::*, state::*};
+use crate::conversation_context::*;
+use crate::conversation_errors::{
+    format_last_event, report_conversation_error, save_conversation_error,
+};
+use crate::conversation_metadata::delete_conversation_metadata;
+use crate::conversation_warnings::{format_warning, save_conversation_warnings};
+use crate::events::*;
+use crate::db_connectors::{
+    conversations::*, memories::*, messages::*, scheduled_events::create_scheduled_event, state::*,
+};
+use crate::memory_scopes::*;
+use crate::speech;
+use crate::step_latency::{format_step_timing, save_step_timings};
+use crate::config::{get_config, with_config};
+use crate::step_hooks::get_step_hooks;
+use crate::telemetry;
 use crate::utils::*;
 use crate::{data::*, delete_client_memories};
 
@@ -33,6 +48,8 @@ pub struct SwitchBot {
  * This is the CSML Engine action.
  * A request came in and should be handled. Once the ConversationInfo is correctly setup,
  * this step is called in a loop until a `hold` or `goto end` is reached.
+ * The messages and the memories of the run are only saved if no other run updated the
+ * conversation meanwhile (EngineError::Conflict otherwise), see update_conversation.
  */
 pub fn interpret_step(
     data: &mut ConversationInfo,
@@ -43,7 +60,8 @@ pub fn interpret_step(
     let mut interaction_order = 0;
     let mut conversation_end = false;
     let (sender, receiver) = mpsc::channel::<MSG>();
-    let context = data.context.clone();
+    let mut context = data.context.clone();
+    context.step_hooks = get_step_hooks(data);
     let mut switch_bot = None;
 
     csml_logger(
@@ -52,7 +70,8 @@ pub fn interpret_step(
             Some(data.context.flow.to_string()),
             None,
             format!("interpreter: start interpretations of bot {:?}", bot.id),
-        ),
+        )
+        .with_request_id(data.run_context.request_id.as_deref()),
         LogLvl::Info,
     );
     csml_logger(
@@ -64,45 +83,82 @@ pub fn interpret_step(
                 "interpreter: start interpretations of bot {:?}, with ",
                 bot.id
             ),
-        ),
+        )
+        .with_request_id(data.run_context.request_id.as_deref()),
         LogLvl::Debug,
     );
+    let last_event = format_last_event(&event, data.no_data_retention);
     let new_bot = bot.clone();
+    // the hooks of the interpreter thread read the settings of the engine running the event
+    let config = get_config();
     thread::spawn(move || {
-        interpret(new_bot, context, event, Some(sender));
+        with_config(&config, || interpret(new_bot, context, event, Some(sender)));
     });
 
     let mut memories = HashMap::new();
+    let mut context_variables =
+        get_context_variables(&data.client, &data.conversation_id, &mut data.db)?;
+    let mut user_memories = HashMap::new();
+    let mut forget_all = false;
+    let mut forgotten = vec![];
+    let mut warnings = vec![];
+    let mut step_timings = vec![];
+    let user_memory_keys: Vec<String> = match get_user_memories(&data.client, &mut data.db)? {
+        Value::Object(map) => map.keys().cloned().collect(),
+        _ => vec![],
+    };
 
     for received in receiver {
         match received {
+            // updating a context variable keeps it in the context of the conversation
+            MSG::Remember(mem) if context_variables.contains_key(&mem.key) => {
+                context_variables.insert(mem.key.clone(), mem);
+            }
+            // updating a user memory keeps it shared by all the channels of the user
+            MSG::Remember(mem) if user_memory_keys.contains(&mem.key) => {
+                user_memories.insert(mem.key.clone(), mem);
+            }
             MSG::Remember(mem) => {
                 memories.insert(mem.key.clone(), mem);
             }
+            MSG::RememberTemp(mem) => {
+                context_variables.insert(mem.key.clone(), mem);
+            }
+            MSG::RememberUser(mem) => {
+                user_memories.insert(mem.key.clone(), mem);
+            }
             MSG::Forget(mem) => match mem {
                 ForgetMemory::ALL => {
                     memories.clear();
-                    delete_client_memories(&data.client)?;
+                    context_variables.clear();
+                    user_memories.clear();
+                    forget_all = true;
+                    forgotten.clear();
                 }
                 ForgetMemory::SINGLE(memory) => {
                     memories.remove(&memory.ident);
-                    crate::delete_client_memory(&data.client, &memory.ident)?;
+                    context_variables.remove(&memory.ident);
+                    user_memories.remove(&memory.ident);
+                    forgotten.push(memory.ident);
                 }
                 ForgetMemory::LIST(mem_list) => {
-                    for mem in mem_list.iter() {
+                    for mem in mem_list.into_iter() {
                         memories.remove(&mem.ident);
-                        crate::delete_client_memory(&data.client, &mem.ident)?;
+                        context_variables.remove(&mem.ident);
+                        user_memories.remove(&mem.ident);
+                        forgotten.push(mem.ident);
                     }
                 }
             },
-            MSG::Message(msg) => {
+            MSG::Message(mut msg) => {
                 csml_logger(
                     CsmlLog::new(
                         None,
                         Some(data.context.flow.to_string()),
                         None,
                         format!("sending message"),
-                    ),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
                     LogLvl::Info,
                 );
                 csml_logger(
@@ -111,11 +167,14 @@ pub fn interpret_step(
                         Some(data.context.flow.to_string()),
                         None,
                         format!("sending message {:?}", msg),
-                    ),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
                     LogLvl::Debug,
                 );
 
+                speech::synthesize_message(&mut msg, &data.client, &data.metadata);
                 send_msg_to_callback_url(data, vec![msg.clone()], interaction_order, false);
+                publish_message_sent(data, &msg);
                 data.messages.push(msg);
             }
             MSG::Log {
@@ -125,7 +184,8 @@ pub fn interpret_step(
                 log_lvl,
             } => {
                 csml_logger(
-                    CsmlLog::new(Some(&data.client), Some(flow), Some(line), message),
+                    CsmlLog::new(Some(&data.client), Some(flow), Some(line), message)
+                        .with_request_id(data.run_context.request_id.as_deref()),
                     log_lvl,
                 );
             }
@@ -136,14 +196,25 @@ pub fn interpret_step(
                 flow_name,
                 previous,
                 secure,
+                breakpoint,
             }) => {
                 let hash = get_current_step_hash(&data.context, bot)?;
+                // a pause of the debugger is saved as a hold with the paused position
+                let state_breakpoint = match breakpoint {
+                    Some(line) => serde_json::json!({
+                        "flow": flow_name,
+                        "step": step_name,
+                        "line": line,
+                    }),
+                    None => Value::Null,
+                };
                 let state_hold: Value = serde_json::json!({
                     "index": index,
                     "step_vars": step_vars,
                     "hash": hash,
                     "previous": previous,
-                    "secure": secure
+                    "secure": secure,
+                    "breakpoint": state_breakpoint,
                 });
 
                 csml_logger(
@@ -152,7 +223,8 @@ pub fn interpret_step(
                         Some(data.context.flow.to_string()),
                         None,
                         format!("hold bot"),
-                    ),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
                     LogLvl::Info,
                 );
                 csml_logger(
@@ -161,10 +233,13 @@ pub fn interpret_step(
                         Some(data.context.flow.to_string()),
                         None,
                         format!("hold bot, state_hold {:?}", state_hold),
-                    ),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
                     LogLvl::Debug,
                 );
 
+                // the hold is only saved by the run that still owns the conversation
+                update_conversation(data, None, None)?;
                 set_state_items(
                     &data.client,
                     "hold",
@@ -179,6 +254,7 @@ pub fn interpret_step(
                     flow_name,
                     previous,
                     secure,
+                    breakpoint,
                 });
             }
             MSG::Next {
@@ -186,7 +262,7 @@ pub fn interpret_step(
                 step,
                 bot: None,
             } => {
-                if let Ok(InterpreterReturn::End) = manage_internal_goto(
+                match manage_internal_goto(
                     data,
                     &mut conversation_end,
                     &mut interaction_order,
@@ -196,7 +272,10 @@ pub fn interpret_step(
                     flow,
                     step,
                 ) {
-                    break;
+                    Ok(InterpreterReturn::End) => break,
+                    // another run updated the conversation, nothing else is saved by this run
+                    Err(EngineError::Conflict) => return Err(EngineError::Conflict),
+                    _ => {}
                 }
             }
 
@@ -213,7 +292,125 @@ pub fn interpret_step(
                 }
             }
 
-            MSG::Error(err_msg) => {
+            MSG::Schedule { id, event, delay } => {
+                // everything needed to inject the event later through start_conversation
+                let scheduled_event: Value = serde_json::json!({
+                    "payload": event,
+                    "metadata": data.metadata,
+                    "callback_url": data.callback_url,
+                    "low_data_mode": data.low_data,
+                    "apps_endpoint": bot.apps_endpoint,
+                    "multibot": bot.multibot,
+                });
+
+                create_scheduled_event(
+                    &data.client,
+                    &id,
+                    &scheduled_event,
+                    chrono::Utc::now() + chrono::Duration::seconds(delay),
+                    &mut data.db,
+                )?;
+            }
+
+            MSG::Handover(metadata) => {
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&data.client),
+                        Some(data.context.flow.to_string()),
+                        None,
+                        format!("handover to agent, metadata {:?}", metadata),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
+                    LogLvl::Debug,
+                );
+
+                crate::handover::pause_conversation(
+                    &data.client,
+                    &data.conversation_id,
+                    &metadata,
+                    data.ttl,
+                    &mut data.db,
+                )?;
+            }
+
+            MSG::Tag(tags) => {
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&data.client),
+                        Some(data.context.flow.to_string()),
+                        None,
+                        format!("tag conversation {:?}", tags),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
+                    LogLvl::Debug,
+                );
+
+                add_conversation_tags(&data.conversation_id, &data.client, &tags, &mut data.db)?;
+            }
+
+            MSG::Experiment { name, variant } => {
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&data.client),
+                        Some(data.context.flow.to_string()),
+                        None,
+                        format!("experiment {} assigned variant {}", name, variant),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
+                    LogLvl::Debug,
+                );
+
+                publish_experiment_assigned(data, &name, &variant);
+            }
+
+            // the chunks are only delivered to the callback_url, the flow says the whole text
+            MSG::Stream { id, text, done } => {
+                let chunk = Message {
+                    content_type: "stream".to_owned(),
+                    content: serde_json::json!({"id": id, "text": text, "done": done}),
+                };
+
+                send_msg_to_callback_url(data, vec![chunk], interaction_order, false);
+            }
+
+            MSG::Warning { step, warning } => {
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&data.client),
+                        Some(warning.position.flow.to_string()),
+                        Some(warning.position.interval.start_line),
+                        format!("interpreter warning: {}", warning.message),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
+                    LogLvl::Warn,
+                );
+
+                warnings.push(format_warning(&step, &warning));
+            }
+
+            MSG::StepTiming {
+                flow,
+                step,
+                started_at,
+                ended_at,
+            } => {
+                step_timings.push(format_step_timing(&flow, &step, started_at, ended_at));
+            }
+
+            MSG::AppCall {
+                fn_id,
+                flow,
+                started_at,
+                ended_at,
+                error,
+            } => {
+                telemetry::record_app_call(&fn_id, &flow, started_at, ended_at, error.as_deref());
+            }
+
+            // the branches taken are only reported by the replays, see coverage.rs
+            MSG::Branch { .. } => {}
+
+            MSG::Error(err_msg, error) => {
                 conversation_end = true;
                 csml_logger(
                     CsmlLog::new(
@@ -221,18 +418,37 @@ pub fn interpret_step(
                         Some(data.context.flow.to_string()),
                         None,
                         format!("interpreter error: {:?}", err_msg),
-                    ),
+                    )
+                    .with_request_id(data.run_context.request_id.as_deref()),
                     LogLvl::Error,
                 );
 
                 send_msg_to_callback_url(data, vec![err_msg.clone()], interaction_order, true);
+                publish_message_sent(data, &err_msg);
                 data.messages.push(err_msg);
+                // the error is only saved by the run that still owns the conversation
+                update_conversation(data, None, None)?;
+                save_conversation_error(
+                    &data.client,
+                    &data.conversation_id,
+                    &error,
+                    data.ttl,
+                    &mut data.db,
+                )?;
+                report_conversation_error(data, &error, &last_event)?;
                 close_conversation(&data.conversation_id, &data.client, &mut data.db)?;
+                publish_run_event(
+                    CONVERSATION_CLOSED,
+                    data,
+                    serde_json::json!({"reason": "error"}),
+                );
             }
         }
     }
 
-    // save in db
+    // save in db, only if no other run updated the conversation during this run
+    update_conversation(data, None, None)?;
+
     let msgs: Vec<serde_json::Value> = data
         .messages
         .iter()
@@ -243,7 +459,50 @@ pub fn interpret_step(
         add_messages_bulk(data, msgs, interaction_order, "SEND")?;
     }
 
+    // the memories forgotten during the run are deleted before the memories it remembered
+    if forget_all {
+        delete_client_memories(&data.client)?;
+        delete_user_memories(&data.client, &mut data.db)?;
+    }
+    for key in forgotten.iter() {
+        crate::delete_client_memory(&data.client, key)?;
+        delete_user_memory(&data.client, key, &mut data.db)?;
+    }
+
     add_memories(data, &memories)?;
+    save_conversation_warnings(
+        &data.client,
+        &data.conversation_id,
+        &data.request_id,
+        warnings,
+        data.ttl,
+        &mut data.db,
+    )?;
+    save_step_timings(
+        &data.client,
+        &data.conversation_id,
+        data.bot_version_id.as_deref(),
+        step_timings,
+        data.ttl,
+        &mut data.db,
+    )?;
+    add_user_memories(&data.client, &user_memories, data.ttl, &mut data.db)?;
+    publish_memories_updated(data, &memories, "client");
+    publish_memories_updated(data, &user_memories, "user");
+
+    // context variables are purged when their conversation is closed
+    if conversation_end || switch_bot.is_some() {
+        delete_context_variables(&data.client, &mut data.db)?;
+        delete_conversation_metadata(&data.client, &mut data.db)?;
+    } else {
+        save_context_variables(
+            &data.client,
+            &data.conversation_id,
+            &context_variables,
+            data.ttl,
+            &mut data.db,
+        )?;
+    }
 
     Ok((
         messages_formatter(
@@ -304,7 +563,8 @@ fn manage_switch_bot<'a>(
                     Some(data.context.flow.to_string()),
                     None,
                     error_message,
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Error,
             );
             return Ok(InterpreterReturn::End);
@@ -324,7 +584,8 @@ fn manage_switch_bot<'a>(
                         "goto flow: {flow}, step: {step_name} in bot: {target_bot} from: flow: {} step: {} in bot: {}",
                         data.context.flow, data.context.step.get_step(), bot.id
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Info,
             );
 
@@ -340,7 +601,8 @@ fn manage_switch_bot<'a>(
                         "goto flow: {flow}, step: start in bot: {target_bot} from: flow: {} step: {} in bot: {}",
                         data.context.flow, data.context.step.get_step(), bot.id
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Info,
             );
 
@@ -358,7 +620,8 @@ fn manage_switch_bot<'a>(
                         "goto flow: default_flow, step: {step_name} in bot: {target_bot} from: flow: {} step: {} in bot: {}",
                         data.context.flow, data.context.step.get_step(), bot.id
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Info,
             );
 
@@ -374,7 +637,8 @@ fn manage_switch_bot<'a>(
                         "goto flow: default_flow step: start in bot: {target_bot} from: flow: {} step: {} in bot: {}",
                         data.context.flow, data.context.step.get_step(), bot.id
                      ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Info,
             );
 
@@ -394,11 +658,17 @@ fn manage_switch_bot<'a>(
             Some(data.context.flow.to_string()),
             None,
             format!("switch bot"),
-        ),
+        )
+        .with_request_id(data.run_context.request_id.as_deref()),
         LogLvl::Info,
     );
 
     close_conversation(&data.conversation_id, &data.client, &mut data.db)?;
+    publish_run_event(
+        CONVERSATION_CLOSED,
+        data,
+        serde_json::json!({"reason": "switch_bot", "next_bot_id": next_bot.id}),
+    );
 
     let previous_bot: Value = serde_json::json!({
         "bot": data.client.bot_id,
@@ -450,7 +720,8 @@ fn manage_internal_goto<'a>(
                         data.context.flow,
                         data.context.step.get_step()
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Debug,
             );
             update_current_context(data, &memories);
@@ -468,7 +739,8 @@ fn manage_internal_goto<'a>(
                         data.context.flow,
                         data.context.step.get_step()
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Debug,
             );
             update_current_context(data, &memories);
@@ -489,7 +761,8 @@ fn manage_internal_goto<'a>(
                         data.context.flow,
                         data.context.step.get_step()
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Debug,
             );
             if goto_step(data, conversation_end, interaction_order, step)? {
@@ -507,7 +780,8 @@ fn manage_internal_goto<'a>(
                         data.context.flow,
                         data.context.step.get_step()
                     ),
-                ),
+                )
+                .with_request_id(data.run_context.request_id.as_deref()),
                 LogLvl::Debug,
             );
 
@@ -541,6 +815,7 @@ fn goto_flow<'a>(
         Some(current_flow.id.clone()),
         Some(data.context.step.get_step()),
     )?;
+    publish_step_entered(data, &current_flow.id);
 
     *interaction_order += 1;
 
@@ -561,15 +836,115 @@ fn goto_step<'a>(
 
         // send end of conversation
         send_msg_to_callback_url(data, vec![], *interaction_order, *conversation_end);
+        // the conversation is only closed by the run that still owns it
+        update_conversation(data, None, None)?;
         close_conversation(&data.conversation_id, &data.client, &mut data.db)?;
+        publish_run_event(
+            CONVERSATION_CLOSED,
+            data,
+            serde_json::json!({"reason": "end"}),
+        );
 
         // break interpret_step loop
         return Ok(*conversation_end);
     } else {
         data.context.step = nextstep;
         update_conversation(data, None, Some(data.context.step.get_step()))?;
+        let flow_id = data.context.flow.to_owned();
+        publish_step_entered(data, &flow_id);
     }
 
     *interaction_order += 1;
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db_connectors::{init_db, user},
+        init::{init_bot, init_stateless_context},
+        make_migrations, RunContext,
+    };
+    use csml_interpreter::data::CsmlFlow;
+
+    fn get_client() -> Client {
+        Client::new(
+            "conflict_bot".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        )
+    }
+
+    fn get_conversation_info(conversation_id: &str, bot: &CsmlBot) -> ConversationInfo {
+        ConversationInfo {
+            request_id: "request_id".to_owned(),
+            conversation_id: conversation_id.to_owned(),
+            conversation_version: 0,
+            callback_url: None,
+            client: get_client(),
+            context: init_stateless_context("Default".to_owned(), get_client(), bot),
+            metadata: serde_json::json!({}),
+            messages: vec![],
+            ttl: None,
+            low_data: false,
+            no_data_retention: false,
+            bot_version_id: None,
+            run_context: RunContext::default(),
+            db: init_db().unwrap(),
+        }
+    }
+
+    #[test]
+    fn ko_interpret_step_conflict() {
+        make_migrations().unwrap_or({});
+
+        let content = "start:\n    say \"Hello\"\n    remember answer = 42\n    goto end";
+        let mut bot = CsmlBot::new(
+            "conflict_bot",
+            "bot",
+            None,
+            vec![CsmlFlow::new("Default", "Default", content, vec![])],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        init_bot(&mut bot).unwrap();
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id = create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        // another server updates the conversation during the run
+        let mut data = get_conversation_info(&c_id, &bot);
+        let mut other = get_conversation_info(&c_id, &bot);
+        update_conversation(&mut other, None, Some("start".to_owned())).unwrap();
+
+        let event = Event::new("text", "hi", serde_json::json!({"text": "hi"}));
+        assert!(matches!(
+            interpret_step(&mut data, event, &bot),
+            Err(EngineError::Conflict)
+        ));
+
+        // neither the messages nor the memories of the run are saved
+        let messages = get_client_messages(&client, &mut db, None, None, None, None).unwrap();
+        assert_eq!(messages["messages"].as_array().unwrap().len(), 0);
+        let memories = internal_use_get_memories(&client, &mut db).unwrap();
+        assert!(memories.get("answer").is_none());
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+}
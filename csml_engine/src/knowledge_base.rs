@@ -0,0 +1,644 @@
+/**
+ * Knowledge base of the bots: the documents are cut into passages, embedded, then saved to a
+ * vector store. The flows search the passages the most similar to a query with
+ * Search_KB(query, top_k), to answer from the documentation of a product for example.
+ *
+ * The store is selected with KB_VECTOR_STORE:
+ * - pgvector: the csml_kb_passages table of the PostgreSQL database of the engine, created with
+ *   the vector extension on the first use
+ * - qdrant: a Qdrant collection, KB_QDRANT_COLLECTION (default csml_kb) at KB_QDRANT_URL, with the
+ *   optional api key KB_QDRANT_API_KEY. The passages of every bot share the collection.
+ *
+ * The embeddings are computed by an OpenAI compatible API:
+ * - KB_EMBEDDINGS_URL (default https://api.openai.com/v1)
+ * - KB_EMBEDDINGS_API_KEY (defaults to LLM_API_KEY)
+ * - KB_EMBEDDINGS_MODEL (default text-embedding-3-small)
+ *
+ * KB_CHUNK_SIZE is the maximum number of characters of a passage (default 1000): the passages
+ * are made of whole paragraphs, the longer paragraphs are cut between two words.
+ * KB_TIMEOUT is the number of milliseconds a request to the embeddings API or to Qdrant has to
+ * complete (default 10000).
+ */
+use crate::{
+    data::{EngineError, KbDocument},
+    db_connectors::{
+        init_db,
+        knowledge_base::{self as kb_db, KbPassage},
+    },
+    config::get_config,
+};
+
+use csml_interpreter::data::{KnowledgeBase, Passage};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_EMBEDDINGS_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_EMBEDDINGS_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_COLLECTION: &str = "csml_kb";
+const DEFAULT_CHUNK_SIZE: usize = 1000;
+const DEFAULT_TIMEOUT: u64 = 10_000;
+// texts embedded by a single request
+const EMBEDDINGS_BATCH_SIZE: usize = 64;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+struct Embeddings {
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+trait VectorStore {
+    // replace the passages of a document
+    fn upsert(
+        &self,
+        bot_id: &str,
+        document_id: &str,
+        passages: &[KbPassage],
+    ) -> Result<(), EngineError>;
+
+    fn delete(&self, bot_id: &str, document_id: &str) -> Result<bool, EngineError>;
+
+    fn search(
+        &self,
+        bot_id: &str,
+        embedding: &[f64],
+        top_k: usize,
+    ) -> Result<Vec<Passage>, EngineError>;
+}
+
+struct PgvectorStore;
+
+impl VectorStore for PgvectorStore {
+    fn upsert(
+        &self,
+        bot_id: &str,
+        document_id: &str,
+        passages: &[KbPassage],
+    ) -> Result<(), EngineError> {
+        let mut db = init_db()?;
+
+        kb_db::upsert_passages(bot_id, document_id, passages, &mut db)
+    }
+
+    fn delete(&self, bot_id: &str, document_id: &str) -> Result<bool, EngineError> {
+        let mut db = init_db()?;
+
+        kb_db::delete_document(bot_id, document_id, &mut db)
+    }
+
+    fn search(
+        &self,
+        bot_id: &str,
+        embedding: &[f64],
+        top_k: usize,
+    ) -> Result<Vec<Passage>, EngineError> {
+        let mut db = init_db()?;
+
+        kb_db::search_passages(bot_id, embedding, top_k, &mut db)
+    }
+}
+
+struct QdrantStore {
+    url: String,
+    api_key: Option<String>,
+    collection: String,
+}
+
+impl QdrantStore {
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let mut request = ureq::request(
+            method,
+            &format!(
+                "{}/collections/{}{}",
+                self.url.trim_end_matches('/'),
+                self.collection,
+                path
+            ),
+        )
+        .timeout(get_timeout());
+        if let Some(api_key) = &self.api_key {
+            request = request.set("api-key", api_key);
+        }
+
+        request
+    }
+
+    fn call(&self, method: &str, path: &str, body: Value) -> Result<Value, EngineError> {
+        match self.request(method, path).send_json(body) {
+            Ok(response) => Ok(response.into_json()?),
+            Err(err) => Err(EngineError::Manager(format!("Qdrant error: {}", err))),
+        }
+    }
+
+    fn document_filter(bot_id: &str, document_id: &str) -> Value {
+        serde_json::json!({"must": [
+            {"key": "bot_id", "match": {"value": bot_id}},
+            {"key": "document_id", "match": {"value": document_id}},
+        ]})
+    }
+
+    fn create_collection(&self, size: usize) -> Result<(), EngineError> {
+        match self.request("GET", "").call() {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(404, _)) => {
+                let vectors = serde_json::json!({"vectors": {"size": size, "distance": "Cosine"}});
+                self.call("PUT", "", vectors)?;
+
+                Ok(())
+            }
+            Err(err) => Err(EngineError::Manager(format!("Qdrant error: {}", err))),
+        }
+    }
+}
+
+impl VectorStore for QdrantStore {
+    fn upsert(
+        &self,
+        bot_id: &str,
+        document_id: &str,
+        passages: &[KbPassage],
+    ) -> Result<(), EngineError> {
+        let size = match passages.first() {
+            Some(passage) => passage.embedding.len(),
+            None => return self.delete(bot_id, document_id).map(|_| ()),
+        };
+        self.create_collection(size)?;
+        self.delete(bot_id, document_id)?;
+
+        let points: Vec<Value> = passages
+            .iter()
+            .map(|passage| {
+                serde_json::json!({
+                    "id": passage.id,
+                    "vector": passage.embedding,
+                    "payload": {
+                        "bot_id": bot_id,
+                        "document_id": document_id,
+                        "text": passage.text,
+                        "metadata": passage.metadata,
+                    }
+                })
+            })
+            .collect();
+        self.call(
+            "PUT",
+            "/points?wait=true",
+            serde_json::json!({ "points": points }),
+        )?;
+
+        Ok(())
+    }
+
+    fn delete(&self, bot_id: &str, document_id: &str) -> Result<bool, EngineError> {
+        let filter = Self::document_filter(bot_id, document_id);
+
+        let count = match self
+            .request("POST", "/points/count")
+            .send_json(serde_json::json!({"filter": filter, "exact": true}))
+        {
+            Ok(response) => response.into_json::<Value>()?["result"]["count"]
+                .as_u64()
+                .unwrap_or(0),
+            // no document was ever saved
+            Err(ureq::Error::Status(404, _)) => return Ok(false),
+            Err(err) => return Err(EngineError::Manager(format!("Qdrant error: {}", err))),
+        };
+
+        if count > 0 {
+            self.call(
+                "POST",
+                "/points/delete?wait=true",
+                serde_json::json!({ "filter": filter }),
+            )?;
+        }
+
+        Ok(count > 0)
+    }
+
+    fn search(
+        &self,
+        bot_id: &str,
+        embedding: &[f64],
+        top_k: usize,
+    ) -> Result<Vec<Passage>, EngineError> {
+        let body = serde_json::json!({
+            "vector": embedding,
+            "limit": top_k,
+            "with_payload": true,
+            "filter": {"must": [{"key": "bot_id", "match": {"value": bot_id}}]},
+        });
+
+        let response: Value = match self.request("POST", "/points/search").send_json(body) {
+            Ok(response) => response.into_json()?,
+            Err(ureq::Error::Status(404, _)) => return Ok(vec![]),
+            Err(err) => return Err(EngineError::Manager(format!("Qdrant error: {}", err))),
+        };
+
+        let points = response["result"].as_array().cloned().unwrap_or_default();
+
+        Ok(points
+            .iter()
+            .map(|point| Passage {
+                document_id: point["payload"]["document_id"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_owned(),
+                text: point["payload"]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_owned(),
+                score: point["score"].as_f64().unwrap_or_default(),
+                metadata: point["payload"]["metadata"].to_owned(),
+            })
+            .collect())
+    }
+}
+
+/**
+ * Knowledge base of a bot, searched by the interpreter
+ */
+#[derive(Debug)]
+struct BotKnowledgeBase {
+    bot_id: String,
+}
+
+impl KnowledgeBase for BotKnowledgeBase {
+    fn search(&self, query: &str, top_k: usize) -> Result<Vec<Passage>, String> {
+        search_passages(&self.bot_id, query, top_k).map_err(|err| format!("{:?}", err))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_timeout() -> Duration {
+    let timeout = get_config()
+        .integer("knowledge_base.timeout")
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    Duration::from_millis(timeout)
+}
+
+fn get_store() -> Result<Box<dyn VectorStore>, EngineError> {
+    let config = get_config();
+
+    match config.text("knowledge_base.vector_store").as_deref() {
+        Some("pgvector") => Ok(Box::new(PgvectorStore)),
+        Some("qdrant") => match config.text("knowledge_base.qdrant_url") {
+            Some(url) => Ok(Box::new(QdrantStore {
+                url,
+                api_key: config.text("knowledge_base.qdrant_api_key"),
+                collection: config
+                    .text("knowledge_base.qdrant_collection")
+                    .unwrap_or_else(|| DEFAULT_COLLECTION.to_owned()),
+            })),
+            None => Err(EngineError::Manager(
+                "KB_QDRANT_URL must be set to use the qdrant knowledge base".to_owned(),
+            )),
+        },
+        Some(store) => Err(EngineError::Manager(format!(
+            "unknown knowledge base vector store: {}",
+            store
+        ))),
+        None => Err(EngineError::Manager(
+            "no knowledge base: KB_VECTOR_STORE is not set".to_owned(),
+        )),
+    }
+}
+
+fn get_embeddings() -> Embeddings {
+    let config = get_config();
+
+    Embeddings {
+        url: config
+            .text("knowledge_base.embeddings_url")
+            .unwrap_or_else(|| DEFAULT_EMBEDDINGS_URL.to_owned()),
+        api_key: config
+            .text("knowledge_base.embeddings_api_key")
+            .or_else(|| config.text("llm.api_key")),
+        model: config
+            .text("knowledge_base.embeddings_model")
+            .unwrap_or_else(|| DEFAULT_EMBEDDINGS_MODEL.to_owned()),
+    }
+}
+
+impl Embeddings {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f64>>, EngineError> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+
+        for batch in texts.chunks(EMBEDDINGS_BATCH_SIZE) {
+            let mut request = ureq::post(&format!("{}/embeddings", self.url.trim_end_matches('/')))
+                .timeout(get_timeout());
+            if let Some(api_key) = &self.api_key {
+                request = request.set("Authorization", &format!("Bearer {}", api_key));
+            }
+
+            let response: Value = request
+                .send_json(serde_json::json!({"model": self.model, "input": batch}))
+                .map_err(|err| EngineError::Manager(format!("embeddings error: {}", err)))?
+                .into_json()?;
+
+            let mut data = response["data"].as_array().cloned().unwrap_or_default();
+            if data.len() != batch.len() {
+                return Err(EngineError::Manager(format!(
+                    "embeddings error: {} embeddings for {} texts",
+                    data.len(),
+                    batch.len()
+                )));
+            }
+            data.sort_by_key(|item| item["index"].as_u64());
+
+            for item in data {
+                let embedding: Vec<f64> = serde_json::from_value(item["embedding"].to_owned())?;
+                embeddings.push(embedding);
+            }
+        }
+
+        Ok(embeddings)
+    }
+}
+
+/**
+ * Cut a long paragraph between two words
+ */
+fn split_paragraph(paragraph: &str, size: usize) -> Vec<String> {
+    if paragraph.chars().count() <= size {
+        return vec![paragraph.to_owned()];
+    }
+
+    let mut pieces = vec![];
+    let mut piece = String::new();
+
+    for word in paragraph.split_whitespace() {
+        if !piece.is_empty() && piece.chars().count() + word.chars().count() + 1 > size {
+            pieces.push(std::mem::take(&mut piece));
+        }
+        if !piece.is_empty() {
+            piece.push(' ');
+        }
+        piece.push_str(word);
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+
+    pieces
+}
+
+/**
+ * Passages of a text: its paragraphs, gathered up to `size` characters
+ */
+fn split_passages(text: &str, size: usize) -> Vec<String> {
+    let mut passages = vec![];
+    let mut passage = String::new();
+
+    let paragraphs = text
+        .split("\n\n")
+        .map(|paragraph| paragraph.trim())
+        .filter(|paragraph| !paragraph.is_empty());
+
+    for paragraph in paragraphs {
+        for piece in split_paragraph(paragraph, size) {
+            if !passage.is_empty() && passage.chars().count() + piece.chars().count() + 2 > size {
+                passages.push(std::mem::take(&mut passage));
+            }
+            if !passage.is_empty() {
+                passage.push_str("\n\n");
+            }
+            passage.push_str(&piece);
+        }
+    }
+    if !passage.is_empty() {
+        passages.push(passage);
+    }
+
+    passages
+}
+
+fn search_with(
+    store: &dyn VectorStore,
+    embeddings: &Embeddings,
+    bot_id: &str,
+    query: &str,
+    top_k: usize,
+) -> Result<Vec<Passage>, EngineError> {
+    let embedding = match embeddings.embed(&[query.to_owned()])?.pop() {
+        Some(embedding) => embedding,
+        None => return Ok(vec![]),
+    };
+
+    store.search(bot_id, &embedding, top_k)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Knowledge base of the bot in the context of its conversations, when one is configured
+ */
+pub fn get_bot_knowledge_base(bot_id: &str) -> Option<Arc<dyn KnowledgeBase>> {
+    match get_config().text("knowledge_base.vector_store") {
+        Some(_) => Some(Arc::new(BotKnowledgeBase {
+            bot_id: bot_id.to_owned(),
+        })),
+        None => None,
+    }
+}
+
+/**
+ * Save a document to the knowledge base of the bot, replacing the document with the same id
+ */
+pub fn ingest_document(bot_id: &str, document: KbDocument) -> Result<Value, EngineError> {
+    let store = get_store()?;
+
+    let size = get_config()
+        .integer("knowledge_base.chunk_size")
+        .map(|size| size as usize)
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_CHUNK_SIZE);
+    let texts = split_passages(&document.text, size);
+    if texts.is_empty() {
+        return Err(EngineError::Format(
+            "the text of the document must not be empty".to_owned(),
+        ));
+    }
+
+    let document_id = document
+        .id
+        .to_owned()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let embeddings = get_embeddings().embed(&texts)?;
+
+    let passages: Vec<KbPassage> = texts
+        .into_iter()
+        .zip(embeddings)
+        .map(|(text, embedding)| KbPassage {
+            id: uuid::Uuid::new_v4().to_string(),
+            text,
+            metadata: document.metadata.to_owned(),
+            embedding,
+        })
+        .collect();
+
+    store.upsert(bot_id, &document_id, &passages)?;
+
+    Ok(serde_json::json!({
+        "document_id": document_id,
+        "passages": passages.len(),
+    }))
+}
+
+pub fn delete_document(bot_id: &str, document_id: &str) -> Result<bool, EngineError> {
+    get_store()?.delete(bot_id, document_id)
+}
+
+/**
+ * Passages of the knowledge base of the bot the most similar to the query, best first
+ */
+pub fn search_passages(
+    bot_id: &str,
+    query: &str,
+    top_k: usize,
+) -> Result<Vec<Passage>, EngineError> {
+    let store = get_store()?;
+
+    search_with(store.as_ref(), &get_embeddings(), bot_id, query, top_k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /**
+     * Answer each request with the response of its path, return the requests
+     */
+    fn mock_server(
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (String, thread::JoinHandle<Vec<(String, Value)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let mut requests = vec![];
+
+            for (path, response) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                            length = value.parse().unwrap();
+                        }
+                        None if line.trim_end().is_empty() => break,
+                        _ => (),
+                    }
+                }
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let request_path = request_line.split(' ').nth(1).unwrap_or_default();
+                assert_eq!(request_path, path);
+
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            response.len(),
+                            response
+                        )
+                        .as_bytes(),
+                    )
+                    .unwrap();
+
+                requests.push((
+                    request_path.to_owned(),
+                    serde_json::from_slice(&body).unwrap_or(Value::Null),
+                ));
+            }
+
+            requests
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn ok_split_passages() {
+        let text = "First paragraph.\n\nSecond paragraph.\n\n\n\nA third paragraph, longer than the size of a passage.";
+
+        assert_eq!(
+            split_passages(text, 40),
+            vec![
+                "First paragraph.\n\nSecond paragraph.",
+                "A third paragraph, longer than the size",
+                "of a passage.",
+            ]
+        );
+        assert!(split_passages(" \n\n ", 40).is_empty());
+    }
+
+    #[test]
+    fn ok_search_qdrant() {
+        let (url, server) = mock_server(vec![
+            (
+                "/embeddings",
+                r#"{"data": [{"index": 0, "embedding": [0.1, 0.2, 0.3]}]}"#,
+            ),
+            (
+                "/collections/csml_kb/points/search",
+                r#"{"result": [{"id": "1", "score": 0.87, "payload": {"bot_id": "bot_id", "document_id": "faq", "text": "Open the settings.", "metadata": {"lang": "en"}}}]}"#,
+            ),
+        ]);
+
+        let store = QdrantStore {
+            url: url.to_owned(),
+            api_key: None,
+            collection: "csml_kb".to_owned(),
+        };
+        let embeddings = Embeddings {
+            url,
+            api_key: Some("key".to_owned()),
+            model: "test-model".to_owned(),
+        };
+
+        let passages = search_with(&store, &embeddings, "bot_id", "reset password", 3).unwrap();
+
+        assert_eq!(
+            passages,
+            vec![Passage {
+                document_id: "faq".to_owned(),
+                text: "Open the settings.".to_owned(),
+                score: 0.87,
+                metadata: serde_json::json!({"lang": "en"}),
+            }]
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0].1["input"],
+            serde_json::json!(["reset password"])
+        );
+        assert_eq!(requests[1].1["limit"], 3);
+        assert_eq!(
+            requests[1].1["filter"]["must"][0]["match"]["value"],
+            "bot_id"
+        );
+    }
+}
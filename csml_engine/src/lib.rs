@@ -1,12 +1,69 @@
 pub mod data;
 
+mod archive;
+mod batch;
+mod bot_cache;
+mod bot_components;
+mod bot_config;
+mod bot_globals;
+mod broadcast;
+mod builder;
+mod bundle;
+mod client_locks;
+mod config;
+mod conversation_context;
+mod conversation_errors;
+mod conversation_metadata;
+mod conversation_state;
+mod conversation_warnings;
+mod coverage;
 mod db_connectors;
+mod debounce;
+mod debugger;
+mod diagnostics;
 mod encrypt;
+mod enrichment;
+mod events;
+mod feature_flags;
+mod files;
+mod fork;
+mod graph;
 mod error_messages;
+mod handover;
 mod init;
 mod interpreter_actions;
+mod knowledge_base;
+mod memory_scopes;
+mod metadata_schema;
+mod object_storage;
+mod outbox;
+mod quotas;
+mod replay;
+mod rollout;
+mod run_messages;
+mod search;
+mod secrets;
 mod send;
+mod shutdown;
+mod signature;
+mod speech;
+#[cfg(feature = "stateless")]
+mod stateless;
+mod step_hooks;
+mod step_latency;
+mod summaries;
+mod telemetry;
+mod transcripts;
 mod utils;
+mod webhooks;
+
+pub use builder::{Engine, EngineBuilder};
+pub use config::{get_config, Config, SettingValue};
+pub use db_connectors::utils::get_tenant_bot_id;
+pub use signature::TIMESTAMP_HEADER;
+#[cfg(feature = "stateless")]
+pub use stateless::{StatelessEngine, StatelessRun, StatelessState};
+pub use transcripts::TranscriptFormat;
 
 pub use csml_interpreter::{
     data::{
@@ -15,9 +72,10 @@ pub use csml_interpreter::{
         error_info::ErrorInfo,
         position::Position,
         warnings::Warnings,
-        Client, CsmlResult, Event,
+        Breakpoint, Client, CsmlResult, Debugger, Event, StepHooks,
     },
-    load_components, search_for_modules,
+    format_flow, load_components, parse_flow_to_ast_json, parse_flow_to_partial_ast_json,
+    parse_step, round_trip, search_for_modules,
 };
 
 #[cfg(any(feature = "postgresql", feature = "sqlite"))]
@@ -30,7 +88,7 @@ extern crate diesel_migrations;
 
 use data::*;
 use db_connectors::{
-    bot, clean_db, conversations, init_db, memories, messages, state,
+    bot, clean_db, conversations, init_db, jobs, memories, messages, scheduled_events, state,
     state::{delete_state_key, set_state_items},
     user, BotVersion, BotVersionCreated, DbConversation,
 };
@@ -59,23 +117,142 @@ use std::{collections::HashMap, env};
  */
 pub fn start_conversation(
     request: CsmlRequest,
+    bot_opt: BotOpt,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    run_conversation(request, bot_opt, RunContext::default(), EventPriority::User)
+}
+
+/**
+ * Same as start_conversation, with the context of the request of the caller: its request_id
+ * is saved with the messages of the run and added to its logs.
+ */
+pub fn start_conversation_with_context(
+    request: CsmlRequest,
+    bot_opt: BotOpt,
+    run_context: RunContext,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    run_conversation(request, bot_opt, run_context, EventPriority::User)
+}
+
+/**
+ * Interpret independent runs in one call, at most `concurrency` at the same time.
+ * The events of the same client are interpreted in their order in the batch, and the result
+ * of each run is returned at its index: a failed run does not stop the others.
+ */
+pub fn run_batch(request: BatchRunRequest) -> Result<Vec<batch::RunResult>, EngineError> {
+    batch::run_batch(request)
+}
+
+/**
+ * Initiate a CSML chat request for an event sent by the system instead of the user
+ * (handover resume, scheduled events, broadcasts). When the client has a run in progress,
+ * it is interpreted before the user messages waiting for the end of the run.
+ */
+pub fn start_system_conversation(
+    request: CsmlRequest,
+    bot_opt: BotOpt,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    run_conversation(request, bot_opt, RunContext::default(), EventPriority::System)
+}
+
+/**
+ * Traced run, the unexpected errors of the run are reported (see telemetry.rs)
+ */
+fn run_conversation(
+    request: CsmlRequest,
+    bot_opt: BotOpt,
+    run_context: RunContext,
+    priority: EventPriority,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let client = request.client.to_owned();
+    let request_id = run_context.request_id.to_owned();
+    let mut span = telemetry::start_run_span(&client, request_id.as_deref());
+
+    let res = interpret_conversation(request, bot_opt, run_context, priority, &mut span);
+    if let Err(err) = &res {
+        telemetry::capture_error(&mut span, err, &client, request_id.as_deref());
+    }
+
+    res
+}
+
+fn interpret_conversation(
+    mut request: CsmlRequest,
     mut bot_opt: BotOpt,
+    run_context: RunContext,
+    priority: EventPriority,
+    span: &mut telemetry::Span,
 ) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
     init_logger();
 
+    // refused once the server is shutting down, awaited by drain otherwise
+    let _run = shutdown::start_run()?;
+    // the text messages sent in a burst are interpreted once, by the first request of the burst
+    if priority == EventPriority::User && !debounce::debounce_event(&mut request) {
+        return Ok(serde_json::Map::new());
+    }
+    // the events of a client are interpreted one at a time, see client_locks.rs
+    let _client_lock = client_locks::lock_client(&request.client, priority)?;
+
     let mut formatted_event = format_event(&request)?;
     let mut db = init_db()?;
 
-    let mut bot = bot_opt.search_bot(&mut db)?;
-    init_bot(&mut bot)?;
+    // the bots of a tenant are only reachable by its clients
+    bot_opt.set_tenant(request.client.tenant_id.as_deref());
+    rollout::resolve_bot_rollout(&mut bot_opt, &request.client, &mut db)?;
+
+    let (mut bot, bot_version_id) = bot_cache::get_initialized_bot_version(&bot_opt, &mut db)?;
+    // the metrics of the run are compared between the versions of a rollout
+    if let Some(version_id) = &bot_version_id {
+        span.set_attribute("csml.bot_version_id", version_id);
+    }
+    // the configuration of the deployment completes the env of the bot
+    bot_config::apply_bot_env(&mut bot, &mut db)?;
+    // the components registered on the bot complete the components of its version
+    bot_components::apply_bot_components(&mut bot, &mut db)?;
+
+    if let Some(schema) = &bot.metadata_schema {
+        request.metadata = metadata_schema::validate_metadata(schema, &request.metadata)?;
+    }
+
+    // the received files are stored with a durable url, event.file.url in the flows,
+    // then the audio events are transcribed to be interpreted as text
+    let file_stored = files::store_event_file(&mut formatted_event, &request.client);
+    let transcribed =
+        speech::transcribe_event(&mut formatted_event, &request.client, &request.metadata);
+    if file_stored || transcribed {
+        request.payload["content_type"] = serde_json::json!(formatted_event.content_type);
+        request.payload["content"] = formatted_event.content.to_owned();
+    }
+
+    // a human agent is handling the conversation: only save the user's event
+    if let Some(conversation) = handover::get_paused_conversation(&request.client, &mut db)? {
+        return handover::save_paused_event(
+            conversation,
+            &request,
+            &formatted_event,
+            &bot,
+            &run_context,
+            db,
+        );
+    }
+
+    // the quota of conversations only applies to the events starting a new conversation
+    let open_conversation_id =
+        conversations::get_latest_open(&request.client, &mut db)?.map(|conv| conv.id);
+    quotas::check_quotas(&request.client, open_conversation_id.is_none(), &mut db)?;
 
-    let mut data = init_conversation_info(
+    // the servers sharing the database update the conversation of the client in turn,
+    // the runs claiming a conversation updated meanwhile by another server are retried
+    let mut data = claim_conversation_info(
         get_default_flow(&bot)?.name.to_owned(),
-        &formatted_event,
+        &mut formatted_event,
         &request,
         &bot,
+        bot_version_id,
         db,
     )?;
+    data.run_context = run_context;
 
     check_for_hold(&mut data, &bot, &mut formatted_event)?;
 
@@ -105,231 +282,1252 @@ pub fn start_conversation(
     }
     //////////////////////////////////////
 
+    // annotations of the enrichers, available as event.enrichment in the flows
+    enrichment::enrich_event(&mut formatted_event, &data);
+
+    // the event of an interaction paused by the debugger was saved when it was received
+    let debugger_pause = matches!(&data.context.hold, Some(hold) if hold.breakpoint.is_some());
+
     // save event in db as message RECEIVE
     match (data.low_data, formatted_event.secure) {
+        _ if debugger_pause => {}
         (false, true) => {
             let msgs = vec![serde_json::json!({"content_type": "secure"})];
 
-            messages::add_messages_bulk(&mut data, msgs, 0, "RECEIVE")?;
-        }
-        (false, false) => {
-            let msgs = vec![request.payload.to_owned()];
+            messages::add_messages_bulk(&mut data, msgs, 0, "RECEIVE")?;
+        }
+        (false, false) => {
+            let msgs = vec![enrichment::add_enrichment(&request.payload, &formatted_event)];
+
+            messages::add_messages_bulk(&mut data, msgs, 0, "RECEIVE")?;
+        }
+        (true, _) => {}
+    }
+
+    let result = interpret_step(&mut data, formatted_event.to_owned(), &bot);
+
+    let mut messages = check_switch_bot(
+        result,
+        &mut data,
+        &mut bot,
+        &mut bot_opt,
+        &mut formatted_event,
+    )?;
+
+    if data.context.debugger.is_some() {
+        debugger::save_pause(&mut data, &request, &bot_opt)?;
+    }
+
+    // the event and the messages of the bot are counted in the usage of the bot
+    let new_conversation = open_conversation_id.as_ref() != Some(&data.conversation_id);
+    quotas::add_usage(
+        &request.client,
+        new_conversation as u64,
+        1 + data.messages.len() as u64,
+        &mut data.db,
+    )?;
+
+    // the messages over RUN_MESSAGES_LIMIT are read with get_run_messages
+    run_messages::paginate_run_messages(&mut messages, &request.client, &mut data.db)?;
+
+    Ok(messages)
+}
+
+fn check_switch_bot(
+    result: Result<
+        (
+            serde_json::Map<String, serde_json::Value>,
+            Option<SwitchBot>,
+        ),
+        EngineError,
+    >,
+    data: &mut ConversationInfo,
+    bot: &mut CsmlBot,
+    bot_opt: &mut BotOpt,
+    event: &mut Event,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    match result {
+        Ok((mut messages, Some(next_bot))) => {
+            if let Err(err) = switch_bot(data, bot, next_bot, bot_opt, event) {
+                // End no interruption delay
+                if let Some(_) = bot.no_interruption_delay {
+                    delete_state_key(&data.client, "delay", "content", &mut data.db)?;
+                }
+                return Err(err);
+            };
+
+            let result = interpret_step(data, event.clone(), &bot);
+
+            let mut new_messages = check_switch_bot(result, data, bot, bot_opt, event)?;
+
+            messages.append(&mut new_messages);
+
+            Ok(messages)
+        }
+        Ok((messages, None)) => {
+            // End no interruption delay
+            if let Some(_) = bot.no_interruption_delay {
+                delete_state_key(&data.client, "delay", "content", &mut data.db)?;
+            }
+
+            Ok(messages)
+        }
+        Err(err) => {
+            // End no interruption delay
+            if let Some(_) = bot.no_interruption_delay {
+                delete_state_key(&data.client, "delay", "content", &mut data.db)?;
+            }
+
+            Err(err)
+        }
+    }
+}
+
+/**
+ * Send the scheduled events that are due through start_system_conversation, at most `limit`
+ * at once.
+ * Each event is deleted before being sent, so that it is only sent once even when several
+ * workers poll the same database. Returns the number of events sent.
+ */
+pub fn run_scheduled_events(limit: i64) -> Result<usize, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let due_events = scheduled_events::get_due_scheduled_events(Utc::now(), limit, &mut db)?;
+
+    let mut sent = 0;
+    for scheduled_event in due_events {
+        // the events left are sent by the next run, from another server
+        if shutdown::is_shutting_down() {
+            break;
+        }
+
+        if !scheduled_events::delete_scheduled_event(&scheduled_event, &mut db)? {
+            continue;
+        }
+
+        let event = scheduled_event.event;
+        let request = CsmlRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            client: scheduled_event.client.clone(),
+            callback_url: event["callback_url"].as_str().map(|url| url.to_owned()),
+            payload: event["payload"].to_owned(),
+            metadata: event["metadata"].to_owned(),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: Some(event["low_data_mode"].to_owned()),
+        };
+        let bot_opt = BotOpt::BotId {
+            bot_id: scheduled_event.client.bot_id.to_owned(),
+            apps_endpoint: event["apps_endpoint"].as_str().map(|url| url.to_owned()),
+            multibot: serde_json::from_value(event["multibot"].to_owned()).unwrap_or(None),
+            environment: None,
+        };
+
+        match start_system_conversation(request, bot_opt) {
+            Ok(_) => sent += 1,
+            Err(err) => csml_logger(
+                CsmlLog::new(
+                    Some(&scheduled_event.client),
+                    None,
+                    None,
+                    format!(
+                        "failed to send scheduled event {}: {:?}",
+                        scheduled_event.id, err
+                    ),
+                ),
+                LogLvl::Error,
+            ),
+        }
+    }
+
+    Ok(sent)
+}
+
+/**
+ * Send again the messages that their callback_url did not accept, at most `limit` at once.
+ * Returns the number of messages sent, see outbox.rs.
+ */
+pub fn deliver_outbox_messages(limit: i64) -> Result<usize, EngineError> {
+    init_logger();
+
+    outbox::deliver_pending_messages(limit)
+}
+
+/**
+ * Refuse the new runs with EngineError::ShuttingDown, the runs in progress are not interrupted
+ */
+pub fn start_shutdown() {
+    shutdown::start_shutdown()
+}
+
+pub fn is_shutting_down() -> bool {
+    shutdown::is_shutting_down()
+}
+
+/**
+ * Start the shutdown, then wait at most `timeout` for the runs in progress to save their
+ * conversations and for the queued events and webhook deliveries to be sent.
+ * Returns false if some of them were still pending after the timeout.
+ */
+pub fn drain(timeout: std::time::Duration) -> bool {
+    shutdown::drain(std::time::Instant::now() + timeout)
+}
+
+/**
+ * Try to become the server running the background job `name` for at most `lease` seconds.
+ * Only one of the servers sharing the database gets it until the lease expires or the run
+ * is completed with complete_job.
+ */
+pub fn claim_job(name: &str, owner: &str, lease: i64) -> Result<bool, EngineError> {
+    let mut db = init_db()?;
+
+    let now = Utc::now();
+    jobs::claim_job(name, owner, now, now + chrono::Duration::seconds(lease), &mut db)
+}
+
+/**
+ * Save the result of a run of the background job `name`: the job stays locked for
+ * `interval` seconds from the end of the run, then any server can claim its next run.
+ */
+pub fn complete_job(
+    name: &str,
+    owner: &str,
+    interval: i64,
+    error: Option<&str>,
+) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+
+    let next_run_at = Utc::now() + chrono::Duration::seconds(interval);
+    jobs::complete_job(name, owner, next_run_at, error, &mut db)
+}
+
+/**
+ * Return the latest conversation that is still open for a given user
+ * (there should not be more than one), or None if there isn't any.
+ * The status of a conversation handled by a human agent is PAUSED_FOR_AGENT.
+ */
+pub fn get_open_conversation(client: &Client) -> Result<Option<DbConversation>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    match handover::get_paused_conversation(client, &mut db)? {
+        Some(conversation) => Ok(Some(conversation)),
+        None => conversations::get_latest_open(client, &mut db),
+    }
+}
+
+/**
+ * Return the error that ended a conversation, with the flow, step and source line of the error
+ * and the steps that led to it, or None if the conversation did not end with an error.
+ */
+pub fn get_conversation_error(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    conversation_errors::get_conversation_error(client, conversation_id, &mut db)
+}
+
+/**
+ * Errors that ended the conversations of a bot, newest first, with their flow, step, source
+ * interval and last event
+ */
+pub fn get_bot_errors(
+    bot_id: &str,
+    limit: Option<i64>,
+    pagination_key: Option<String>,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    conversation_errors::get_bot_errors(bot_id, limit, pagination_key, &mut db)
+}
+
+/**
+ * Runtime warnings of the interpreter during a conversation, grouped by interaction
+ * (the last ones), or None if the conversation has no warning.
+ */
+pub fn get_conversation_warnings(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    conversation_warnings::get_conversation_warnings(client, conversation_id, &mut db)
+}
+
+/**
+ * Where the user is in the open conversation: its flow and step, the position held by the
+ * interpreter, the local variables of the step and the memories saved last. None if the
+ * conversation is not the open conversation of the client.
+ */
+pub fn get_conversation_state(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    conversation_state::get_conversation_state(client, conversation_id, &mut db)
+}
+
+/**
+ * Clone the open conversation of the client into a sandbox client, to try other events from
+ * the state of the user without changing the real conversation. None if the conversation is not
+ * the open conversation of the client.
+ */
+pub fn fork_conversation(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    fork::fork_conversation(client, conversation_id, &mut db)
+}
+
+/**
+ * Human-readable transcript of a conversation of the client, None if it has no message
+ */
+pub fn get_conversation_transcript(
+    client: &Client,
+    conversation_id: &str,
+    format: TranscriptFormat,
+) -> Result<Option<String>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    transcripts::get_conversation_transcript(client, conversation_id, format, &mut db)
+}
+
+/**
+ * Metadata patched during the conversation (see update_conversation_metadata)
+ */
+pub fn get_conversation_metadata(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let metadata = conversation_metadata::get_conversation_metadata(client, conversation_id, &mut db)?;
+
+    Ok(serde_json::Value::Object(metadata))
+}
+
+/**
+ * Apply a JSON merge patch (the keys set to null are removed) to the metadata of the open
+ * conversation of a client, readable as `_metadata` by its next events.
+ * Returns the updated metadata, or None if the conversation is not open.
+ */
+pub fn update_conversation_metadata(
+    client: &Client,
+    conversation_id: &str,
+    patch: &serde_json::Value,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    match conversations::get_latest_open(client, &mut db)? {
+        Some(conversation) if conversation.id == conversation_id => {}
+        _ => return Ok(None),
+    };
+
+    let metadata = conversation_metadata::update_conversation_metadata(
+        client,
+        conversation_id,
+        patch,
+        get_ttl_duration_value(None),
+        &mut db,
+    )?;
+
+    Ok(Some(serde_json::Value::Object(metadata)))
+}
+
+/**
+ * Hand the open conversation of a client over to a human agent.
+ * Until it is resumed, the user's events are saved but not interpreted.
+ */
+pub fn pause_conversation_for_agent(
+    client: &Client,
+    metadata: serde_json::Value,
+) -> Result<DbConversation, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let mut conversation = match conversations::get_latest_open(client, &mut db)? {
+        Some(conversation) => conversation,
+        None => {
+            return Err(EngineError::Format(
+                "the client has no open conversation".to_owned(),
+            ))
+        }
+    };
+
+    handover::pause_conversation(client, &conversation.id, &metadata, None, &mut db)?;
+    conversation.status = handover::PAUSED_FOR_AGENT.to_owned();
+
+    Ok(conversation)
+}
+
+/**
+ * Give a paused conversation back to the bot when the agent is done,
+ * and start it at the requested step (of the flow where it was paused, unless flow_id is set)
+ */
+pub fn resume_conversation(
+    request: ResumeRequest,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let conversation = match handover::get_paused_conversation(&request.client, &mut db)? {
+        Some(conversation) => conversation,
+        None => {
+            return Err(EngineError::Format(
+                "the client has no conversation paused for an agent".to_owned(),
+            ))
+        }
+    };
+
+    handover::resume_conversation(&request.client, &mut db)?;
+
+    let csml_request = CsmlRequest {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        client: request.client.to_owned(),
+        callback_url: request.callback_url.to_owned(),
+        payload: serde_json::json!({
+            "content_type": "flow_trigger",
+            "content": {
+                "flow_id": request.flow_id.unwrap_or(conversation.flow_id),
+                "step_id": request.step_id,
+            }
+        }),
+        metadata: match request.metadata {
+            serde_json::Value::Null => serde_json::json!({}),
+            metadata => metadata,
+        },
+        step_limit: None,
+        ttl_duration: None,
+        low_data_mode: None,
+    };
+
+    let bot_opt = BotOpt::BotId {
+        bot_id: request.client.bot_id.to_owned(),
+        apps_endpoint: request.apps_endpoint,
+        multibot: request.multibot,
+        environment: request.environment,
+    };
+
+    start_system_conversation(csml_request, bot_opt)
+}
+
+/**
+ * Debug the conversation of a client: until the session is stopped, its interpretation
+ * pauses at the given breakpoints and waits to be continued
+ */
+pub fn start_debug_session(
+    client: &Client,
+    breakpoints: Vec<Breakpoint>,
+) -> Result<Debugger, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    debugger::start_session(client, breakpoints, &mut db)
+}
+
+pub fn stop_debug_session(client: &Client) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    debugger::stop_session(client, &mut db)
+}
+
+/**
+ * Get the breakpoints of the debug session of a client and, if its conversation is paused,
+ * where it is paused with the variables of the step and the memories of the client.
+ * None if the client is not debugged.
+ */
+pub fn inspect_debug_session(client: &Client) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    match debugger::get_debugger(client, &mut db)? {
+        Some(session) => Ok(Some(debugger::inspect(client, &session, &mut db)?)),
+        None => Ok(None),
+    }
+}
+
+/**
+ * Resume a conversation paused by the debugger until the next breakpoint
+ */
+pub fn continue_debug_session(
+    client: &Client,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    resume_debug_session(client, false)
+}
+
+/**
+ * Resume a conversation paused by the debugger and pause it again before the next instruction
+ */
+pub fn step_over_debug_session(
+    client: &Client,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    resume_debug_session(client, true)
+}
+
+fn resume_debug_session(
+    client: &Client,
+    step_over: bool,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let mut session = match debugger::get_debugger(client, &mut db)? {
+        Some(session) => session,
+        None => {
+            return Err(EngineError::Format(
+                "the client has no debug session".to_owned(),
+            ))
+        }
+    };
+
+    let (request, bot_opt) = match debugger::get_pause(client, &mut db)? {
+        Some(pause) => pause,
+        None => {
+            return Err(EngineError::Format(
+                "the conversation of the client is not paused by the debugger".to_owned(),
+            ))
+        }
+    };
+
+    session.step_over = step_over;
+    debugger::set_debugger(client, &session, &mut db)?;
+
+    // the paused interaction is interpreted again with the same event, from the paused instruction
+    let mut messages = start_conversation(request, bot_opt)?;
+    messages.insert(
+        "debug".to_owned(),
+        debugger::inspect(client, &session, &mut db)?,
+    );
+
+    Ok(messages)
+}
+
+pub fn get_client_memories(client: &Client) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    memories::get_memories(client, &mut db)
+}
+
+pub fn get_client_memory(client: &Client, key: &str) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    memories::get_memory(client, key, &mut db)
+}
+
+/**
+ * Get the previous values of a client memory, newest first, with the date and the
+ * id of the interaction that saved them
+ */
+pub fn get_client_memory_history(
+    client: &Client,
+    key: &str,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    memories::get_memory_history(client, key, &mut db)
+}
+
+/**
+ * Get the client whose memories are those of the given scope: "user" memories are shared
+ * by all the channels of the user, "channel" memories (default) are those of the client.
+ * The returned client can be used with all the client memory functions.
+ */
+pub fn get_memory_scope_client(
+    client: &Client,
+    scope: Option<&str>,
+) -> Result<Client, EngineError> {
+    memory_scopes::get_scope_client(client, scope)
+}
+
+pub fn get_client_messages(
+    client: &Client,
+    limit: Option<i64>,
+    pagination_key: Option<String>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    messages::get_client_messages(client, &mut db, limit, pagination_key, from_date, to_date)
+}
+
+/**
+ * Page of the messages of a run over RUN_MESSAGES_LIMIT, from the pagination_key returned by
+ * the run (or by the previous page). None once the messages have expired (RUN_MESSAGES_TTL).
+ */
+pub fn get_run_messages(
+    client: &Client,
+    request_id: &str,
+    limit: Option<usize>,
+    pagination_key: Option<String>,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    run_messages::get_run_messages(client, request_id, limit, pagination_key, &mut db)
+}
+
+/**
+ * Messages and memories of a bot containing the text, created between the unix timestamps
+ * `from` and `to`, most recent first
+ */
+pub fn search(
+    bot_id: &str,
+    text: &str,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    search::search(bot_id, text, from, to, limit, &mut db)
+}
+
+pub fn get_conversation_messages(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    messages::get_conversation_messages(client, conversation_id, &mut db)
+}
+
+/**
+ * Name of the header that must contain the signature of the requests sent for a bot,
+ * None if its requests are not signed
+ */
+pub fn get_request_signature_header(bot_id: &str) -> Option<String> {
+    signature::get_signature_header(bot_id)
+}
+
+/**
+ * Verify the HMAC signature of the body of a request sent for a bot
+ * and that it was sent recently (see signature.rs)
+ */
+pub fn verify_request_signature(
+    bot_id: &str,
+    signature: &str,
+    timestamp: &str,
+    body: &[u8],
+) -> Result<(), EngineError> {
+    signature::verify_signature(bot_id, signature, timestamp, body)
+}
+
+/**
+ * Replay the events of a conversation against a bot version, without saving anything.
+ * Returns the interactions whose messages diverge from the messages of the conversation,
+ * or None if the conversation has no saved event. A deterministic replay runs in a sandbox
+ * (seeded random builtins, time of the events, no network calls).
+ * The report includes the coverage of the steps of the bot and of their branches.
+ */
+pub fn replay_conversation(
+    client: &Client,
+    conversation_id: &str,
+    mut bot_opt: BotOpt,
+    deterministic: bool,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot_opt.set_tenant(client.tenant_id.as_deref());
+    let bot = bot_cache::get_initialized_bot(&bot_opt, &mut db)?;
+
+    replay::replay_conversation(client, conversation_id, &bot, deterministic, &mut db)
+}
+
+/**
+ * Conversations of the client, only the ones with the tag if any
+ */
+pub fn get_client_conversations(
+    client: &Client,
+    limit: Option<i64>,
+    pagination_key: Option<String>,
+    tag: Option<&str>,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    conversations::get_client_conversations(client, &mut db, limit, pagination_key, tag)
+}
+
+/**
+ * Get current State ether Hold or NULL
+ */
+pub fn get_current_state(client: &Client) -> Result<Option<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    state::get_current_state(client, &mut db)
+}
+
+/**
+ * Create memory
+ */
+pub fn create_client_memory(
+    client: &Client,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+    validate_memory_key_format(&key)?;
+
+    let ttl = get_ttl_duration_value(None);
+
+    memories::create_client_memory(client, key.clone(), value.clone(), ttl, &mut db)?;
+    events::publish_event(
+        events::MEMORY_UPDATED,
+        client,
+        None,
+        serde_json::json!({"key": key, "scope": "client", "value": value}),
+    );
+
+    Ok(())
+}
+
+/**
+ * Create bot version
+ */
+pub fn create_bot_version(mut csml_bot: CsmlBot) -> Result<BotVersionCreated, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let bot_id = csml_bot.id.clone();
+
+    // search for modules to download
+    if let Err(err) = search_for_modules(&mut csml_bot) {
+        return Err(EngineError::Interpreter(format!("{:?}", err)));
+    }
+
+    match validate_bot(csml_bot.clone()) {
+        CsmlResult {
+            errors: Some(errors),
+            ..
+        } => Err(EngineError::InvalidBot(errors)),
+        CsmlResult {
+            flows, extern_flows, ..
+        } => {
+            // the AST is saved with the version, so that engines do not parse the flows again
+            csml_bot.bot_ast = flows.map(|flows| {
+                encode_bot_ast(&flows, &extern_flows.unwrap_or_else(HashMap::new))
+            });
+
+            let version_id = bot::create_bot_version(bot_id, csml_bot, &mut db)?;
+            let engine_version = env!("CARGO_PKG_VERSION").to_owned();
+
+            Ok(BotVersionCreated {
+                version_id,
+                engine_version,
+            })
+        }
+    }
+}
+
+/**
+ * Export a bot version as a signed bundle, that can be imported in another engine instance
+ */
+pub fn export_bot_version(bot_id: &str, version_id: &str) -> Result<Option<BotBundle>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    match bot::get_by_version_id(version_id, bot_id, &mut db)? {
+        Some(bot_version) => Ok(Some(bundle::export_bundle(bot_version)?)),
+        None => Ok(None),
+    }
+}
+
+/**
+ * Graph of the steps of every flow of a bot and of the goto transitions between them,
+ * in DOT or Mermaid format
+ */
+pub fn make_bot_graph(bot: &CsmlBot, format: BotGraphFormat) -> Result<String, EngineError> {
+    graph::make_graph(bot, format)
+}
+
+/**
+ * Graph of the steps and goto transitions of a bot version
+ */
+pub fn get_bot_version_graph(
+    bot_id: &str,
+    version_id: &str,
+    format: BotGraphFormat,
+) -> Result<Option<String>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    match bot::get_by_version_id(version_id, bot_id, &mut db)? {
+        Some(bot_version) => Ok(Some(graph::make_graph(&bot_version.bot, format)?)),
+        None => Ok(None),
+    }
+}
+
+/**
+ * Import a signed bot bundle as a new version of the bot.
+ * The metadata of the exported version are returned along with the new version_id.
+ * With a tenant_id, the bot is imported as a bot of this tenant.
+ */
+pub fn import_bot_bundle(
+    bot_bundle: BotBundle,
+    tenant_id: Option<&str>,
+) -> Result<BotBundleImported, EngineError> {
+    init_logger();
+
+    bundle::verify_bundle(&bot_bundle)?;
+
+    let mut csml_bot: CsmlBot = serde_json::from_value(bot_bundle.bot)?;
+    csml_bot.id = get_tenant_bot_id(tenant_id, &csml_bot.id);
+    let BotVersionCreated {
+        version_id,
+        engine_version,
+    } = create_bot_version(csml_bot)?;
+
+    Ok(BotBundleImported {
+        version_id,
+        engine_version,
+        source_version_id: bot_bundle.version_id,
+        source_engine_version: bot_bundle.engine_version,
+        exported_at: bot_bundle.exported_at,
+    })
+}
+
+/**
+ * get by bot_id
+ */
+pub fn get_last_bot_version(bot_id: &str) -> Result<Option<BotVersion>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot::get_last_bot_version(bot_id, None, &mut db)
+}
+
+/**
+ * get the active version of a bot in a given environment (dev, staging, prod...)
+ */
+pub fn get_environment_bot_version(
+    bot_id: &str,
+    environment: &str,
+) -> Result<Option<BotVersion>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot::get_last_bot_version(bot_id, Some(environment), &mut db)
+}
+
+/**
+ * Set the active version of a bot in a given environment.
+ * Requests made with this environment and no version_id will run this version.
+ */
+pub fn set_environment_bot_version(
+    bot_id: &str,
+    environment: &str,
+    version_id: &str,
+) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    if bot::get_by_version_id(version_id, bot_id, &mut db)?.is_none() {
+        return Err(EngineError::BotNotFound(format!(
+            "bot version ({}) not found in db",
+            version_id
+        )));
+    }
+
+    bot::set_environment_version_id(bot_id, environment, version_id, &mut db)
+}
+
+/**
+ * Remove the active version of a bot in a given environment
+ */
+pub fn delete_environment_bot_version(bot_id: &str, environment: &str) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot::delete_environment_version_id(bot_id, environment, &mut db)
+}
+
+/**
+ * get bot by version_id
+ */
+pub fn get_bot_by_version_id(id: &str, bot_id: &str) -> Result<Option<BotVersion>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot::get_by_version_id(id, bot_id, &mut db)
+}
+
+/**
+ * List the last 20 versions of the bot if no limit is set
+ *
+ * BOT = {
+ *  "version_id": String,
+ *  "id": String,
+ *  "name": String,
+ *  "custom_components": Option<String>,
+ *  "default_flow": String
+ *  "engine_version": String
+ *  "created_at": String
+ * }
+ */
+pub fn get_bot_versions(
+    bot_id: &str,
+    limit: Option<i64>,
+    last_key: Option<String>,
+) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot::get_bot_versions(bot_id, limit, last_key, &mut db)
+}
+
+/**
+ * Get the configuration of a given bot, merged over its env (_env.<key>) and used as the
+ * defaults of the metadata of its events (_metadata.<key>)
+ */
+pub fn get_bot_config(
+    bot_id: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot_config::get_config(bot_id, &mut db)
+}
+
+/**
+ * Replace the configuration of a given bot
+ */
+pub fn set_bot_config(
+    bot_id: &str,
+    config: serde_json::Value,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot_config::set_config(bot_id, &config, &mut db)
+}
 
-            messages::add_messages_bulk(&mut data, msgs, 0, "RECEIVE")?;
-        }
-        (true, _) => {}
-    }
+/**
+ * Get the custom components registered on a given bot, merged over the custom components
+ * of its version when it runs
+ */
+pub fn get_bot_components(
+    bot_id: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
 
-    let result = interpret_step(&mut data, formatted_event.to_owned(), &bot);
+    bot_components::get_components(bot_id, &mut db)
+}
 
-    check_switch_bot(
-        result,
-        &mut data,
-        &mut bot,
-        &mut bot_opt,
-        &mut formatted_event,
-    )
+/**
+ * Register custom components on a given bot without publishing a new version.
+ * Returns the names of the components of its last version they replace.
+ */
+pub fn set_bot_components(
+    bot_id: &str,
+    components: serde_json::Value,
+) -> Result<Vec<String>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot_components::set_components(bot_id, &components, &mut db)
 }
 
-fn check_switch_bot(
-    result: Result<
-        (
-            serde_json::Map<String, serde_json::Value>,
-            Option<SwitchBot>,
-        ),
-        EngineError,
-    >,
-    data: &mut ConversationInfo,
-    bot: &mut CsmlBot,
-    bot_opt: &mut BotOpt,
-    event: &mut Event,
-) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
-    match result {
-        Ok((mut messages, Some(next_bot))) => {
-            if let Err(err) = switch_bot(data, bot, next_bot, bot_opt, event) {
-                // End no interruption delay
-                if let Some(_) = bot.no_interruption_delay {
-                    delete_state_key(&data.client, "delay", "content", &mut data.db)?;
-                }
-                return Err(err);
-            };
+/**
+ * Deregister a custom component of a given bot, returns false if it is not registered
+ */
+pub fn delete_bot_component(bot_id: &str, name: &str) -> Result<bool, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
 
-            let result = interpret_step(data, event.clone(), &bot);
+    bot_components::delete_component(bot_id, name, &mut db)
+}
 
-            let mut new_messages = check_switch_bot(result, data, bot, bot_opt, event)?;
+/**
+ * Get the globals of a given bot: the key/value store shared by all its users,
+ * readable in the flows with _global.<key>
+ */
+pub fn get_bot_globals(bot_id: &str) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
 
-            messages.append(&mut new_messages);
+    bot_globals::get_globals(bot_id, &mut db)
+}
 
-            Ok(messages)
-        }
-        Ok((messages, None)) => {
-            // End no interruption delay
-            if let Some(_) = bot.no_interruption_delay {
-                delete_state_key(&data.client, "delay", "content", &mut data.db)?;
-            }
+/**
+ * Create or update a global of a given bot
+ */
+pub fn set_bot_global(
+    bot_id: &str,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
 
-            Ok(messages)
-        }
-        Err(err) => {
-            // End no interruption delay
-            if let Some(_) = bot.no_interruption_delay {
-                delete_state_key(&data.client, "delay", "content", &mut data.db)?;
-            }
+    bot_globals::set_global(bot_id, key, value, &mut db)
+}
 
-            Err(err)
-        }
-    }
+/**
+ * Delete a global of a given bot
+ */
+pub fn delete_bot_global(bot_id: &str, key: &str) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    bot_globals::delete_global(bot_id, key, &mut db)
 }
 
 /**
- * Return the latest conversation that is still open for a given user
- * (there should not be more than one), or None if there isn't any.
+ * Get the feature flags of a given bot, by key
  */
-pub fn get_open_conversation(client: &Client) -> Result<Option<DbConversation>, EngineError> {
+pub fn get_bot_feature_flags(bot_id: &str) -> Result<HashMap<String, FeatureFlag>, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    conversations::get_latest_open(client, &mut db)
+    feature_flags::get_flags(bot_id, &mut db)
 }
 
-pub fn get_client_memories(client: &Client) -> Result<serde_json::Value, EngineError> {
+/**
+ * Create or update a feature flag of a given bot, readable in the flows with _flags.<key>
+ */
+pub fn set_bot_feature_flag(bot_id: &str, key: &str, flag: FeatureFlag) -> Result<(), EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    memories::get_memories(client, &mut db)
+    feature_flags::set_flag(bot_id, key, flag, &mut db)
 }
 
-pub fn get_client_memory(client: &Client, key: &str) -> Result<serde_json::Value, EngineError> {
+/**
+ * Delete a feature flag of a given bot, returns false if it does not exist
+ */
+pub fn delete_bot_feature_flag(bot_id: &str, key: &str) -> Result<bool, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    memories::get_memory(client, key, &mut db)
+    feature_flags::delete_flag(bot_id, key, &mut db)
 }
 
-pub fn get_client_messages(
-    client: &Client,
-    limit: Option<i64>,
-    pagination_key: Option<String>,
-    from_date: Option<i64>,
-    to_date: Option<i64>,
-) -> Result<serde_json::Value, EngineError> {
+/**
+ * Get the rollout of a given bot, if any
+ *
+ * ROLLOUT = {
+ *  "versions": [{"version_id": String, "percentage": Integer}]
+ * }
+ */
+pub fn get_bot_rollout(bot_id: &str) -> Result<Option<BotRollout>, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    messages::get_client_messages(client, &mut db, limit, pagination_key, from_date, to_date)
+    rollout::get_rollout(bot_id, &mut db)
 }
 
-pub fn get_client_conversations(
-    client: &Client,
-    limit: Option<i64>,
-    pagination_key: Option<String>,
-) -> Result<serde_json::Value, EngineError> {
+/**
+ * Split the traffic of a bot between several versions.
+ * Requests made with only the bot_id will be routed to one of the rollout versions,
+ * always the same one for a given client.
+ */
+pub fn set_bot_rollout(bot_id: &str, bot_rollout: BotRollout) -> Result<(), EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    conversations::get_client_conversations(client, &mut db, limit, pagination_key)
+    rollout::validate_rollout(bot_id, &bot_rollout, &mut db)?;
+    rollout::set_rollout(bot_id, &bot_rollout, &mut db)
 }
 
 /**
- * Get current State ether Hold or NULL
+ * Remove the rollout of a given bot: all requests will use the latest version again
  */
-pub fn get_current_state(client: &Client) -> Result<Option<serde_json::Value>, EngineError> {
+pub fn delete_bot_rollout(bot_id: &str) -> Result<(), EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    state::get_current_state(client, &mut db)
+    rollout::delete_rollout(bot_id, &mut db)
 }
 
 /**
- * Create memory
+ * Start a flow for many clients of a bot. The conversations are started in the background
+ * with rate control: the returned broadcast_id can be used to poll the broadcast status.
  */
-pub fn create_client_memory(
-    client: &Client,
-    key: String,
-    value: serde_json::Value,
-) -> Result<(), EngineError> {
+pub fn start_broadcast(request: BroadcastRequest) -> Result<Broadcast, EngineError> {
     let mut db = init_db()?;
     init_logger();
-    validate_memory_key_format(&key)?;
 
-    let ttl = get_ttl_duration_value(None);
+    let clients = broadcast::get_broadcast_clients(&request, &mut db)?;
+    let new_broadcast = broadcast::new_broadcast(&request.bot_id, clients.len());
+    broadcast::save_broadcast(&new_broadcast, &mut db)?;
 
-    memories::create_client_memory(client, key, value, ttl, &mut db)
+    let running_broadcast = new_broadcast.clone();
+    // the broadcast runs with the settings of the engine that started it
+    let config = get_config();
+    std::thread::spawn(move || {
+        config::with_config(&config, || {
+            broadcast::run_broadcast(request, clients, running_broadcast)
+        })
+    });
+
+    Ok(new_broadcast)
 }
 
 /**
- * Create bot version
+ * Get the status of a broadcast: running or done, with the number of sent and failed conversations
  */
-pub fn create_bot_version(mut csml_bot: CsmlBot) -> Result<BotVersionCreated, EngineError> {
+pub fn get_broadcast(bot_id: &str, broadcast_id: &str) -> Result<Option<Broadcast>, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    let bot_id = csml_bot.id.clone();
+    broadcast::get_broadcast(bot_id, broadcast_id, &mut db)
+}
 
-    // search for modules to download
-    if let Err(err) = search_for_modules(&mut csml_bot) {
-        return Err(EngineError::Interpreter(format!("{:?}", err)));
-    }
+/**
+ * Subscribe an url to the events of the conversations of a bot.
+ * The secret signing the payloads is only returned at the creation of the webhook.
+ */
+pub fn create_webhook(request: WebhookRequest) -> Result<Webhook, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
 
-    match validate_bot(csml_bot.clone()) {
-        CsmlResult {
-            errors: Some(errors),
-            ..
-        } => Err(EngineError::Interpreter(format!("{:?}", errors))),
-        CsmlResult { .. } => {
-            let version_id = bot::create_bot_version(bot_id, csml_bot, &mut db)?;
-            let engine_version = env!("CARGO_PKG_VERSION").to_owned();
+    webhooks::create_webhook(request, &mut db)
+}
 
-            Ok(BotVersionCreated {
-                version_id,
-                engine_version,
-            })
-        }
+/**
+ * Get the webhooks of a bot, without their secrets
+ */
+pub fn get_webhooks(bot_id: &str) -> Result<Vec<Webhook>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let mut webhooks = webhooks::get_webhooks(bot_id, &mut db)?;
+    for webhook in webhooks.iter_mut() {
+        webhook.secret = None;
     }
+
+    Ok(webhooks)
 }
 
 /**
- * get by bot_id
+ * Remove a webhook of a bot, return false if the bot has no such webhook
  */
-pub fn get_last_bot_version(bot_id: &str) -> Result<Option<BotVersion>, EngineError> {
+pub fn delete_webhook(bot_id: &str, webhook_id: &str) -> Result<bool, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    bot::get_last_bot_version(bot_id, &mut db)
+    webhooks::delete_webhook(bot_id, webhook_id, &mut db)
 }
 
 /**
- * get bot by version_id
+ * Get the last deliveries of a webhook, most recent first
  */
-pub fn get_bot_by_version_id(id: &str, bot_id: &str) -> Result<Option<BotVersion>, EngineError> {
+pub fn get_webhook_deliveries(
+    bot_id: &str,
+    webhook_id: &str,
+) -> Result<Option<Vec<WebhookDelivery>>, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    bot::get_by_version_id(id, bot_id, &mut db)
+    webhooks::get_webhook_deliveries(bot_id, webhook_id, &mut db)
 }
 
 /**
- * List the last 20 versions of the bot if no limit is set
- *
- * BOT = {
- *  "version_id": String,
- *  "id": String,
- *  "name": String,
- *  "custom_components": Option<String>,
- *  "default_flow": String
- *  "engine_version": String
- *  "created_at": String
- * }
+ * Add a document to the knowledge base of a bot, searched in the flows with Search_KB.
+ * Return its id and its number of passages.
  */
-pub fn get_bot_versions(
+pub fn ingest_kb_document(bot_id: &str, document: KbDocument) -> Result<serde_json::Value, EngineError> {
+    init_logger();
+
+    knowledge_base::ingest_document(bot_id, document)
+}
+
+/**
+ * Remove a document from the knowledge base of a bot, return false if the bot has no such document
+ */
+pub fn delete_kb_document(bot_id: &str, document_id: &str) -> Result<bool, EngineError> {
+    init_logger();
+
+    knowledge_base::delete_document(bot_id, document_id)
+}
+
+/**
+ * Passages of the knowledge base of a bot the most similar to the query, best first
+ */
+pub fn search_kb(
     bot_id: &str,
-    limit: Option<i64>,
-    last_key: Option<String>,
+    query: &str,
+    top_k: usize,
+) -> Result<Vec<csml_interpreter::data::Passage>, EngineError> {
+    init_logger();
+
+    knowledge_base::search_passages(bot_id, query, top_k)
+}
+
+/**
+ * Get the number of conversations and messages of a bot and/or a tenant for a month
+ * (YYYY-MM, the current one by default), with their quotas
+ */
+pub fn get_usage(
+    bot_id: Option<&str>,
+    tenant_id: Option<&str>,
+    period: Option<&str>,
 ) -> Result<serde_json::Value, EngineError> {
     let mut db = init_db()?;
     init_logger();
 
-    bot::get_bot_versions(bot_id, limit, last_key, &mut db)
+    quotas::get_usage(bot_id, tenant_id, period, &mut db)
+}
+
+/**
+ * p50 and p95 latency of the steps executed with a bot version ("draft" for the bots run without
+ * version), slowest first
+ */
+pub fn get_step_latency(bot_id: &str, version_id: &str) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    step_latency::get_step_latency(bot_id, version_id, &mut db)
+}
+
+/**
+ * Export the conversations closed for more than ARCHIVE_AFTER_DAYS days to the archive
+ * object storage and delete them from the database, return the number of archived conversations
+ */
+pub fn archive_conversations() -> Result<usize, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    archive::archive_conversations(&mut db)
+}
+
+/**
+ * Get an archived conversation of a client with its messages,
+ * {"conversation": {...}, "messages": [...]}, or None if it was not archived
+ */
+pub fn get_archived_conversation(
+    client: &Client,
+    conversation_id: &str,
+) -> Result<Option<serde_json::Value>, EngineError> {
+    init_logger();
+
+    archive::get_archived_conversation(client, conversation_id)
 }
 
 /**
@@ -389,6 +1587,7 @@ pub fn delete_client(client: &Client) -> Result<(), EngineError> {
     let mut db = init_db()?;
     init_logger();
 
+    memory_scopes::delete_user_memories(client, &mut db)?;
     user::delete_client(client, &mut db)
 }
 
@@ -457,8 +1656,21 @@ pub fn user_close_all_conversations(client: Client) -> Result<(), EngineError> {
     let mut db = init_db()?;
     init_logger();
 
+    // the open conversation is summarized once closed
+    let conversation = conversations::get_latest_open(&client, &mut db)?;
+
     state::delete_state_key(&client, "hold", "position", &mut db)?;
-    conversations::close_all_conversations(&client, &mut db)
+    conversation_context::delete_context_variables(&client, &mut db)?;
+    conversation_metadata::delete_conversation_metadata(&client, &mut db)?;
+    conversations::close_all_conversations(&client, &mut db)?;
+    events::publish_event(
+        events::CONVERSATION_CLOSED,
+        &client,
+        conversation.as_ref().map(|conversation| conversation.id.as_str()),
+        serde_json::json!({"reason": "closed_by_api"}),
+    );
+
+    Ok(())
 }
 
 /**
@@ -513,6 +1725,7 @@ fn check_for_hold(
                 flow_name: data.context.flow.to_owned(),
                 previous: serde_json::from_value(hold["previous"].clone()).unwrap_or(None),
                 secure: secure_hold,
+                breakpoint: hold["breakpoint"]["line"].as_u64().map(|line| line as u32),
             });
 
             state::delete_state_key(&data.client, "hold", "position", &mut data.db)?;
@@ -530,10 +1743,11 @@ fn check_for_hold(
 pub fn get_status() -> Result<serde_json::Value, EngineError> {
     let mut status = serde_json::Map::new();
 
+    let config = config::get_config();
     let mut ready = true;
 
-    match std::env::var("ENGINE_DB_TYPE") {
-        Ok(db_name) => match init_db() {
+    match config.text("database.type") {
+        Some(db_name) => match init_db() {
             Ok(_) => status.insert("database_type".to_owned(), serde_json::json!(db_name)),
             Err(_) => {
                 ready = false;
@@ -543,7 +1757,7 @@ pub fn get_status() -> Result<serde_json::Value, EngineError> {
                 )
             }
         },
-        Err(_) => {
+        None => {
             ready = false;
             status.insert(
                 "database_type".to_owned(),
@@ -557,32 +1771,37 @@ pub fn get_status() -> Result<serde_json::Value, EngineError> {
         false => status.insert("server_ready".to_owned(), serde_json::json!(false)),
     };
 
-    match std::env::var("ENGINE_SERVER_PORT") {
-        Ok(port) => status.insert("server_port".to_owned(), serde_json::json!(port)),
-        Err(_) => status.insert("server_port".to_owned(), serde_json::json!(5000)), // DEFAULT
+    match config.text("server.port") {
+        Some(port) => status.insert("server_port".to_owned(), serde_json::json!(port)),
+        None => status.insert("server_port".to_owned(), serde_json::json!(5000)), // DEFAULT
     };
 
-    match std::env::var("ENGINE_SERVER_API_KEYS") {
-        Ok(_) => status.insert("server_auth_enabled".to_owned(), serde_json::json!(true)),
-        Err(_) => status.insert("server_auth_enabled".to_owned(), serde_json::json!(false)),
+    match (
+        config.is_set("auth.api_keys"),
+        config.is_set("auth.tenant_api_keys"),
+    ) {
+        (false, false) => {
+            status.insert("server_auth_enabled".to_owned(), serde_json::json!(false))
+        }
+        _ => status.insert("server_auth_enabled".to_owned(), serde_json::json!(true)),
     };
 
-    match std::env::var("ENCRYPTION_SECRET") {
-        Ok(_) => status.insert("encryption_enabled".to_owned(), serde_json::json!(true)),
-        Err(_) => status.insert("encryption_enabled".to_owned(), serde_json::json!(false)),
-    };
+    status.insert(
+        "encryption_enabled".to_owned(),
+        serde_json::json!(config.is_set("encryption.secret")),
+    );
 
-    match std::env::var("DEBUG") {
-        Ok(_) => status.insert("debug_mode_enabled".to_owned(), serde_json::json!(true)),
-        Err(_) => status.insert("debug_mode_enabled".to_owned(), serde_json::json!(false)),
-    };
+    status.insert(
+        "debug_mode_enabled".to_owned(),
+        serde_json::json!(config.is_set("log.debug")),
+    );
 
-    match std::env::var("CSML_LOG_LEVEL") {
-        Ok(val) => status.insert(
+    match config.text("log.level") {
+        Some(val) => status.insert(
             "csml_log_level".to_owned(),
             serde_json::json!(val.to_owned()),
         ),
-        Err(_) => status.insert(
+        None => status.insert(
             "csml_log_level".to_owned(),
             serde_json::json!("info".to_owned()),
         ),
@@ -593,16 +1812,51 @@ pub fn get_status() -> Result<serde_json::Value, EngineError> {
         serde_json::json!(env!("CARGO_PKG_VERSION")),
     );
 
+    status.insert("bot_cache".to_owned(), bot_cache::get_cache_stats());
+
     Ok(serde_json::json!(status))
 }
 
 /**
- * Make migrations for PgSQL and do nothing for MongoDB and DynamoDB
+ * Make migrations for PgSQL, SQLite and MongoDB (indexes), do nothing for DynamoDB
  */
 pub fn make_migrations() -> Result<(), EngineError> {
     db_connectors::make_migrations()
 }
 
+/**
+ * Create the DynamoDB table (AWS_DYNAMODB_TABLE) with its key schema, indexes and TTL
+ * if it does not exist, for example in a dynamodb-local. Return false if it already existed.
+ */
+pub fn create_dynamodb_table() -> Result<bool, EngineError> {
+    db_connectors::create_dynamodb_table()
+}
+
+/**
+ * Load the TOML or YAML configuration file at `path` (or CSML_CONFIG, or ./csml.toml...) into
+ * the settings of the engine, the env vars override its values. Returns the path of the
+ * loaded file, if any.
+ */
+pub fn load_config(path: Option<&str>) -> Result<Option<String>, EngineError> {
+    config::load_config(path)
+}
+
+/**
+ * Return the errors of the settings of the engine (invalid values, missing settings
+ * required by the database...), to be checked at startup
+ */
+pub fn validate_config() -> Vec<String> {
+    config::validate_config()
+}
+
+/**
+ * Check the settings, the database (connection, tables, write permission) and the
+ * encryption of the engine, to find a misconfiguration before the first request
+ */
+pub fn validate_environment() -> EnvironmentReport {
+    diagnostics::validate_environment()
+}
+
 /**
  * delete expired data
  */
@@ -0,0 +1,126 @@
+/**
+ * Memories are saved at channel scope by default: they are only available in the channel
+ * where they were remembered. With `remember key = value scope "user"`, a memory is shared
+ * by all the channels of the user, and `scope "conversation"` is the same as `remember_temp`.
+ *
+ * User memories are saved as the memories of the client whose channel_id is USER_SCOPE_CHANNEL,
+ * so every connector stores them without a dedicated table.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::memories,
+    error_messages::ERROR_MEMORY_SCOPE,
+    Client,
+};
+use csml_interpreter::data::{tokens::*, Memory};
+
+use std::collections::HashMap;
+
+pub const USER_SCOPE_CHANNEL: &str = "__user_scope__";
+
+/**
+ * Get the client owning the memories of the given scope: "user" or "channel" (default)
+ */
+pub fn get_scope_client(client: &Client, scope: Option<&str>) -> Result<Client, EngineError> {
+    match scope {
+        None | Some(CHANNEL_SCOPE) => Ok(client.to_owned()),
+        Some(USER_SCOPE) => Ok(Client {
+            bot_id: client.bot_id.to_owned(),
+            channel_id: USER_SCOPE_CHANNEL.to_owned(),
+            user_id: client.user_id.to_owned(),
+            tenant_id: client.tenant_id.to_owned(),
+        }),
+        Some(_) => Err(EngineError::Format(ERROR_MEMORY_SCOPE.to_owned())),
+    }
+}
+
+pub fn get_user_memories(
+    client: &Client,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let client = get_scope_client(client, Some(USER_SCOPE))?;
+
+    memories::internal_use_get_memories(&client, db)
+}
+
+pub fn add_user_memories(
+    client: &Client,
+    user_memories: &HashMap<String, Memory>,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_scope_client(client, Some(USER_SCOPE))?;
+
+    for (key, memory) in user_memories.iter() {
+        memories::create_client_memory(&client, key.to_owned(), memory.value.to_owned(), ttl, db)?;
+    }
+
+    Ok(())
+}
+
+pub fn delete_user_memory(
+    client: &Client,
+    key: &str,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_scope_client(client, Some(USER_SCOPE))?;
+
+    memories::delete_client_memory(&client, key, db)
+}
+
+pub fn delete_user_memories(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let client = get_scope_client(client, Some(USER_SCOPE))?;
+
+    memories::delete_client_memories(&client, db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_user_memories_shared_by_channels() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let web = Client::new(
+            "bot_scope".to_owned(),
+            "web".to_owned(),
+            "user_scope".to_owned(),
+        );
+        let messenger = Client::new(
+            "bot_scope".to_owned(),
+            "messenger".to_owned(),
+            "user_scope".to_owned(),
+        );
+        delete_user_memories(&web, &mut db).unwrap();
+
+        let mut user_memories = HashMap::new();
+        user_memories.insert(
+            "name".to_owned(),
+            Memory {
+                key: "name".to_owned(),
+                value: serde_json::json!("Jane"),
+            },
+        );
+        add_user_memories(&web, &user_memories, None, &mut db).unwrap();
+
+        let saved = get_user_memories(&messenger, &mut db).unwrap();
+        assert_eq!(saved["name"], serde_json::json!("Jane"));
+
+        // they are not channel memories
+        let saved = memories::internal_use_get_memories(&messenger, &mut db).unwrap();
+        assert!(saved.get("name").is_none());
+
+        delete_user_memory(&messenger, "name", &mut db).unwrap();
+        let saved = get_user_memories(&web, &mut db).unwrap();
+        assert!(saved.get("name").is_none());
+    }
+
+    #[test]
+    fn err_unknown_scope() {
+        let client = Client::new("bot".to_owned(), "web".to_owned(), "user".to_owned());
+
+        assert!(get_scope_client(&client, Some("bot")).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+/**
+ * Validation of the request metadata against the metadata_schema of the bot.
+ *
+ * The schema is a subset of JSON schema: type, properties, required, additionalProperties,
+ * items, enum, minimum, maximum, minLength, maxLength and pattern.
+ * Values sent as strings are converted to the declared number, integer or boolean type,
+ * so that the flows get metadata with the correct primitive types.
+ */
+use crate::data::{EngineError, FieldError};
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+fn get_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_type(value: &Value, schema_type: &str) -> bool {
+    match (get_type(value), schema_type) {
+        (value_type, schema_type) if value_type == schema_type => true,
+        ("integer", "number") => true,
+        _ => false,
+    }
+}
+
+/**
+ * Convert a string to the expected type, e.g. "42" for an integer
+ */
+fn coerce(value: &Value, schema_type: &str) -> Option<Value> {
+    let string = value.as_str()?;
+
+    match schema_type {
+        "integer" => string.parse::<i64>().ok().map(Value::from),
+        "number" => string.parse::<f64>().ok().map(Value::from),
+        "boolean" => string.parse::<bool>().ok().map(Value::from),
+        _ => None,
+    }
+}
+
+fn get_schema_types(schema: &Value) -> Vec<&str> {
+    match &schema["type"] {
+        Value::String(schema_type) => vec![schema_type.as_str()],
+        Value::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+        _ => vec![],
+    }
+}
+
+fn add_error(errors: &mut Vec<FieldError>, field: &str, message: String) {
+    errors.push(FieldError {
+        field: field.to_owned(),
+        message,
+    });
+}
+
+fn validate_value(
+    schema: &Value,
+    value: &Value,
+    field: &str,
+    errors: &mut Vec<FieldError>,
+) -> Value {
+    let types = get_schema_types(schema);
+    let mut value = value.to_owned();
+
+    if !types.is_empty() && !types.iter().any(|schema_type| is_type(&value, schema_type)) {
+        match types
+            .iter()
+            .find_map(|schema_type| coerce(&value, schema_type))
+        {
+            Some(coerced) => value = coerced,
+            None => {
+                add_error(
+                    errors,
+                    field,
+                    format!("expected {}, got {}", types.join(" or "), get_type(&value)),
+                );
+                return value;
+            }
+        }
+    }
+
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(&value) {
+            add_error(
+                errors,
+                field,
+                format!("must be one of {}", Value::Array(values.to_owned())),
+            );
+        }
+    }
+
+    match &mut value {
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+
+            if let Some(minimum) = schema["minimum"].as_f64() {
+                if number < minimum {
+                    add_error(errors, field, format!("must be at least {}", minimum));
+                }
+            }
+            if let Some(maximum) = schema["maximum"].as_f64() {
+                if number > maximum {
+                    add_error(errors, field, format!("must be at most {}", maximum));
+                }
+            }
+        }
+        Value::String(string) => {
+            let length = string.chars().count() as u64;
+
+            if let Some(min_length) = schema["minLength"].as_u64() {
+                if length < min_length {
+                    add_error(
+                        errors,
+                        field,
+                        format!("must have at least {} characters", min_length),
+                    );
+                }
+            }
+            if let Some(max_length) = schema["maxLength"].as_u64() {
+                if length > max_length {
+                    add_error(
+                        errors,
+                        field,
+                        format!("must have at most {} characters", max_length),
+                    );
+                }
+            }
+            if let Some(pattern) = schema["pattern"].as_str() {
+                match Regex::new(pattern) {
+                    Ok(regex) if regex.is_match(string) => {}
+                    Ok(_) => add_error(errors, field, format!("must match {}", pattern)),
+                    Err(_) => add_error(errors, field, format!("invalid pattern {}", pattern)),
+                }
+            }
+        }
+        Value::Array(items) => {
+            if schema["items"].is_object() {
+                for (index, item) in items.iter_mut().enumerate() {
+                    let item_field = format!("{}[{}]", field, index);
+                    *item = validate_value(&schema["items"], item, &item_field, errors);
+                }
+            }
+        }
+        Value::Object(object) => validate_object(schema, object, field, errors),
+        _ => {}
+    }
+
+    value
+}
+
+fn validate_object(
+    schema: &Value,
+    object: &mut Map<String, Value>,
+    field: &str,
+    errors: &mut Vec<FieldError>,
+) {
+    let get_field = |key: &str| match field {
+        "" => key.to_owned(),
+        field => format!("{}.{}", field, key),
+    };
+
+    if let Some(required) = schema["required"].as_array() {
+        for key in required.iter().filter_map(|key| key.as_str()) {
+            if !object.contains_key(key) {
+                add_error(errors, &get_field(key), "is required".to_owned());
+            }
+        }
+    }
+
+    let properties = schema["properties"].as_object();
+
+    for (key, value) in object.iter_mut() {
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(property) => *value = validate_value(property, value, &get_field(key), errors),
+            None if schema["additionalProperties"] == Value::Bool(false) => {
+                add_error(errors, &get_field(key), "is not allowed".to_owned())
+            }
+            None => {}
+        }
+    }
+}
+
+/**
+ * Validate the request metadata and return it with the values converted to the schema types.
+ * All the invalid fields are returned at once in an EngineError::Metadata.
+ */
+pub fn validate_metadata(schema: &Value, metadata: &Value) -> Result<Value, EngineError> {
+    let mut errors = vec![];
+
+    let metadata = match metadata {
+        Value::Null => Value::Object(Map::new()),
+        metadata => metadata.to_owned(),
+    };
+    let metadata = validate_value(schema, &metadata, "", &mut errors);
+
+    match errors.is_empty() {
+        true => Ok(metadata),
+        false => Err(EngineError::Metadata(errors)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_schema() -> Value {
+        serde_json::json!({
+            "type": "object",
+            "required": ["firstname", "age"],
+            "properties": {
+                "firstname": {"type": "string", "minLength": 1},
+                "age": {"type": "integer", "minimum": 0},
+                "premium": {"type": "boolean"},
+                "plan": {"type": "string", "enum": ["free", "pro"]},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        })
+    }
+
+    #[test]
+    fn ok_metadata_coerce() {
+        let metadata = serde_json::json!({
+            "firstname": "Jane",
+            "age": "42",
+            "premium": "true",
+            "tags": ["a", "b"],
+            "other": {"free": "form"}
+        });
+
+        let metadata = validate_metadata(&get_schema(), &metadata).unwrap();
+
+        assert_eq!(metadata["age"], serde_json::json!(42));
+        assert_eq!(metadata["premium"], serde_json::json!(true));
+        assert_eq!(metadata["other"], serde_json::json!({"free": "form"}));
+    }
+
+    #[test]
+    fn ko_metadata_field_errors() {
+        let metadata = serde_json::json!({
+            "firstname": "",
+            "age": "unknown",
+            "plan": "gold",
+            "tags": ["a", 1]
+        });
+
+        let errors = match validate_metadata(&get_schema(), &metadata) {
+            Err(EngineError::Metadata(errors)) => errors,
+            result => panic!("expected metadata errors, got {:?}", result),
+        };
+        let mut fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        fields.sort();
+
+        assert_eq!(fields, vec!["age", "firstname", "plan", "tags[1]"]);
+    }
+
+    #[test]
+    fn ko_metadata_required() {
+        let errors = match validate_metadata(&get_schema(), &Value::Null) {
+            Err(EngineError::Metadata(errors)) => errors,
+            result => panic!("expected metadata errors, got {:?}", result),
+        };
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "is required");
+    }
+}
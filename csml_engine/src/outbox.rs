@@ -0,0 +1,79 @@
+/**
+ * Outbox of the callback_url: the messages of a run are saved as PENDING before being POSTed
+ * to the callback_url of the request, then marked SENT once the callback_url accepted them.
+ *
+ * The messages that were not sent (the callback_url was down, the process stopped during the
+ * run...) are sent again by deliver_pending_messages, called by a background job. Only the
+ * messages saved more than OUTBOX_DELAY seconds ago (default 30) are sent again, so that the
+ * runs in progress deliver their own messages. The messages are delivered at least once: the
+ * callback_url can receive the same messages twice if the process stops right after sending
+ * them. The messages are deleted after OUTBOX_RETENTION seconds (default 86400), sent or not.
+ *
+ * The bots without data retention do not save their messages in the outbox.
+ */
+use crate::{
+    config::get_config,
+    data::EngineError,
+    db_connectors::{init_db, outbox},
+    send::format_and_transfer,
+    shutdown,
+};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use chrono::{Duration, Utc};
+
+const DEFAULT_DELAY: i64 = 30;
+const DEFAULT_RETENTION: i64 = 86_400;
+
+fn get_setting_number(key: &str, default: i64) -> i64 {
+    match get_config().integer(key) {
+        Some(value) => value as i64,
+        None => default,
+    }
+}
+
+/**
+ * Send the PENDING messages to their callback_url, at most `limit` at once.
+ * Returns the number of messages sent.
+ */
+pub fn deliver_pending_messages(limit: i64) -> Result<usize, EngineError> {
+    let mut db = init_db()?;
+
+    let now = Utc::now();
+    let retention = get_setting_number("conversations.outbox_retention", DEFAULT_RETENTION);
+    outbox::delete_outbox_messages(now - Duration::seconds(retention), &mut db)?;
+
+    let delay = get_setting_number("conversations.outbox_delay", DEFAULT_DELAY);
+    let pending =
+        outbox::get_pending_outbox_messages(now - Duration::seconds(delay), limit, &mut db)?;
+
+    let mut sent = 0;
+    for outbox_message in pending {
+        // the messages left are sent by the next run, from another server
+        if shutdown::is_shutting_down() {
+            break;
+        }
+
+        if !format_and_transfer(&outbox_message.callback_url, &outbox_message.payload) {
+            csml_logger(
+                CsmlLog::new(
+                    Some(&outbox_message.client),
+                    None,
+                    None,
+                    format!(
+                        "outbox message {} not sent, retried later",
+                        outbox_message.id
+                    ),
+                ),
+                LogLvl::Error,
+            );
+            continue;
+        }
+
+        if outbox::set_outbox_message_sent(&outbox_message, &mut db)? {
+            sent += 1;
+        }
+    }
+
+    Ok(sent)
+}
@@ -0,0 +1,318 @@
+/**
+ * Usage quotas: the conversations started and the messages exchanged by the clients of a bot
+ * are counted per month, for the bot and for its tenant. When a quota is reached, the events
+ * are refused with an EngineError::QuotaExceeded until the next month.
+ *
+ * The quotas are set with env vars, without limit by default:
+ * - QUOTA_CONVERSATIONS_PER_MONTH_<BOT_ID> or QUOTA_CONVERSATIONS_PER_MONTH for every bot
+ * - QUOTA_MESSAGES_PER_MONTH_<BOT_ID> or QUOTA_MESSAGES_PER_MONTH
+ * - TENANT_QUOTA_CONVERSATIONS_PER_MONTH_<TENANT_ID> or TENANT_QUOTA_CONVERSATIONS_PER_MONTH
+ * - TENANT_QUOTA_MESSAGES_PER_MONTH_<TENANT_ID> or TENANT_QUOTA_MESSAGES_PER_MONTH
+ * with BOT_ID the bot id of the tenant ("{tenant_id}::{bot_id}") for the bots of a tenant.
+ *
+ * Each new conversation counts as one conversation, each event received and each message
+ * sent by the bot count as one message. The open conversations go on when the conversations
+ * quota is reached, as long as the messages quota is not. The usage is only counted for the
+ * bots and tenants with a quota.
+ */
+use crate::{
+    config::get_config,
+    data::{Database, EngineError, QuotaExceeded},
+    db_connectors::{state, utils::get_tenant_bot_id},
+    utils::get_bot_client,
+    Client,
+};
+
+use chrono::{prelude::Utc, NaiveDate};
+
+const CONVERSATIONS: &str = "conversations";
+const MESSAGES: &str = "messages";
+
+const BOT_SCOPE: &str = "bot";
+const TENANT_SCOPE: &str = "tenant";
+
+/**
+ * A bot or a tenant, and the client under which its usage is saved
+ */
+struct UsageScope {
+    scope: &'static str,
+    id: String,
+    client: Client,
+}
+
+fn get_period() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+fn get_bot_scope(bot_id: &str) -> UsageScope {
+    UsageScope {
+        scope: BOT_SCOPE,
+        id: bot_id.to_owned(),
+        client: get_bot_client(bot_id),
+    }
+}
+
+fn get_tenant_scope(tenant_id: &str) -> UsageScope {
+    UsageScope {
+        scope: TENANT_SCOPE,
+        id: tenant_id.to_owned(),
+        // no bot of the tenant can have an empty bot id
+        client: get_bot_client(&get_tenant_bot_id(Some(tenant_id), "")),
+    }
+}
+
+fn get_client_scopes(client: &Client) -> Vec<UsageScope> {
+    let tenant_id = client.tenant_id.as_deref();
+    let mut scopes = vec![get_bot_scope(&get_tenant_bot_id(
+        tenant_id,
+        &client.bot_id,
+    ))];
+
+    if let Some(tenant_id) = tenant_id {
+        scopes.push(get_tenant_scope(tenant_id));
+    }
+
+    scopes
+}
+
+fn get_limit(scope: &UsageScope, quota: &str) -> Option<u64> {
+    let key = match scope.scope {
+        TENANT_SCOPE => format!("quotas.tenant_{}_per_month", quota),
+        _ => format!("quotas.{}_per_month", quota),
+    };
+
+    get_config().text_for(&key, &scope.id)?.parse().ok()
+}
+
+/**
+ * Each quota is counted in its own counter of the month: "{period}:{quota}"
+ */
+fn get_counter_key(period: &str, quota: &str) -> String {
+    format!("{}:{}", period, quota)
+}
+
+fn get_scope_usage(
+    scope: &UsageScope,
+    period: &str,
+    db: &mut Database,
+) -> Result<(u64, u64), EngineError> {
+    let mut usage = vec![];
+    for quota in [CONVERSATIONS, MESSAGES].iter() {
+        let key = get_counter_key(period, quota);
+        usage.push(state::get_state_counter(&scope.client, "usage", &key, db)?.max(0) as u64);
+    }
+
+    Ok((usage[0], usage[1]))
+}
+
+fn format_scope_usage(
+    scope: &UsageScope,
+    period: &str,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let (conversations, messages) = get_scope_usage(scope, period, db)?;
+
+    Ok(serde_json::json!({
+        "id": scope.id,
+        CONVERSATIONS: conversations,
+        MESSAGES: messages,
+        "limits": {
+            CONVERSATIONS: get_limit(scope, CONVERSATIONS),
+            MESSAGES: get_limit(scope, MESSAGES),
+        }
+    }))
+}
+
+/**
+ * Refuse the event of the client if a quota of its bot or of its tenant is reached.
+ * The conversations quota is only checked when the event starts a new conversation.
+ */
+pub fn check_quotas(
+    client: &Client,
+    new_conversation: bool,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let period = get_period();
+
+    for scope in get_client_scopes(client) {
+        let mut limits = vec![];
+        if new_conversation {
+            if let Some(limit) = get_limit(&scope, CONVERSATIONS) {
+                limits.push((CONVERSATIONS, limit));
+            }
+        }
+        if let Some(limit) = get_limit(&scope, MESSAGES) {
+            limits.push((MESSAGES, limit));
+        }
+
+        // the usage is only read for the scopes with quotas
+        if limits.is_empty() {
+            continue;
+        }
+
+        let (conversations, messages) = get_scope_usage(&scope, &period, db)?;
+        for (quota, limit) in limits {
+            let usage = match quota {
+                CONVERSATIONS => conversations,
+                _ => messages,
+            };
+
+            if usage >= limit {
+                return Err(EngineError::QuotaExceeded(QuotaExceeded {
+                    scope: scope.scope.to_owned(),
+                    quota: quota.to_owned(),
+                    limit,
+                    usage,
+                    period,
+                }));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Count the conversations and messages of an interaction in the usage of the current month
+ */
+pub fn add_usage(
+    client: &Client,
+    conversations: u64,
+    messages: u64,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let period = get_period();
+
+    for scope in get_client_scopes(client) {
+        if get_limit(&scope, CONVERSATIONS).is_none() && get_limit(&scope, MESSAGES).is_none() {
+            continue;
+        }
+
+        // the counters are incremented by the database: the interactions of the other
+        // servers are not lost
+        for (quota, n) in [(CONVERSATIONS, conversations), (MESSAGES, messages)].iter() {
+            if *n > 0 {
+                let key = get_counter_key(&period, quota);
+                state::increment_state_counter(&scope.client, "usage", &key, *n as i64, db)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Usage and quotas of a bot and/or of a tenant for a month (YYYY-MM, the current one by default)
+ */
+pub fn get_usage(
+    bot_id: Option<&str>,
+    tenant_id: Option<&str>,
+    period: Option<&str>,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let period = match period {
+        Some(period) => {
+            if NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d").is_err() {
+                return Err(EngineError::Format(format!(
+                    "invalid period [{}], expected YYYY-MM",
+                    period
+                )));
+            }
+
+            period.to_owned()
+        }
+        None => get_period(),
+    };
+
+    let bot = match bot_id {
+        Some(bot_id) => {
+            let scope = get_bot_scope(&get_tenant_bot_id(tenant_id, bot_id));
+            format_scope_usage(&scope, &period, db)?
+        }
+        None => serde_json::Value::Null,
+    };
+    let tenant = match tenant_id {
+        Some(tenant_id) => format_scope_usage(&get_tenant_scope(tenant_id), &period, db)?,
+        None => serde_json::Value::Null,
+    };
+
+    Ok(serde_json::json!({
+        "period": period,
+        BOT_SCOPE: bot,
+        TENANT_SCOPE: tenant,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+    use std::env;
+
+    #[test]
+    fn ok_quotas() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let mut client = Client::new(
+            "quota_bot".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        client.tenant_id = Some("quota_tenant".to_owned());
+        let period = get_period();
+
+        for scope in get_client_scopes(&client) {
+            for quota in [CONVERSATIONS, MESSAGES].iter() {
+                let key = get_counter_key(&period, quota);
+                state::delete_state_key(&scope.client, "usage", &key, &mut db).unwrap();
+            }
+        }
+
+        env::set_var("QUOTA_CONVERSATIONS_PER_MONTH_QUOTA_TENANT__QUOTA_BOT", "1");
+        env::set_var("TENANT_QUOTA_MESSAGES_PER_MONTH_QUOTA_TENANT", "3");
+
+        assert!(check_quotas(&client, true, &mut db).is_ok());
+        add_usage(&client, 1, 2, &mut db).unwrap();
+
+        // the open conversations go on
+        assert!(check_quotas(&client, false, &mut db).is_ok());
+        match check_quotas(&client, true, &mut db) {
+            Err(EngineError::QuotaExceeded(quota)) => {
+                assert_eq!(quota.scope, BOT_SCOPE);
+                assert_eq!(quota.quota, CONVERSATIONS);
+                assert_eq!(quota.usage, 1);
+            }
+            res => panic!("the conversations quota is not reached: {:?}", res),
+        }
+
+        add_usage(&client, 0, 1, &mut db).unwrap();
+        match check_quotas(&client, false, &mut db) {
+            Err(EngineError::QuotaExceeded(quota)) => {
+                assert_eq!(quota.scope, TENANT_SCOPE);
+                assert_eq!(quota.quota, MESSAGES);
+                assert_eq!(quota.limit, 3);
+            }
+            res => panic!("the messages quota is not reached: {:?}", res),
+        }
+
+        let usage = get_usage(Some("quota_bot"), Some("quota_tenant"), None, &mut db).unwrap();
+        assert_eq!(usage["bot"]["conversations"], 1);
+        assert_eq!(usage["bot"]["limits"]["conversations"], 1);
+        assert_eq!(usage["tenant"]["messages"], 3);
+        assert!(usage["bot"]["limits"]["messages"].is_null());
+        assert!(get_usage(Some("quota_bot"), None, Some("2020-13"), &mut db).is_err());
+
+        // the usage of the bots without quotas is not counted
+        let free_client = Client::new(
+            "quota_free_bot".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        add_usage(&free_client, 1, 1, &mut db).unwrap();
+        let usage = get_usage(Some("quota_free_bot"), None, None, &mut db).unwrap();
+        assert_eq!(usage["bot"]["messages"], 0);
+
+        env::remove_var("QUOTA_CONVERSATIONS_PER_MONTH_QUOTA_TENANT__QUOTA_BOT");
+        env::remove_var("TENANT_QUOTA_MESSAGES_PER_MONTH_QUOTA_TENANT");
+    }
+}
@@ -0,0 +1,488 @@
+/**
+ * Conversation replay: the events of a conversation are saved as RECEIVE messages with their
+ * raw payload, followed by the SEND messages of the bot. The events are interpreted again,
+ * in the same order, against a bot version and the messages of the bot are compared with
+ * the saved ones.
+ *
+ * The replay has no side effect: the position, hold and memories of the replayed conversation
+ * are kept in memory and nothing is saved in the database. It starts without memories, and
+ * the secure events (which payload is not saved) can't be replayed.
+ *
+ * A deterministic replay runs the events in a sandbox: the random builtins are seeded with the
+ * index of the interaction, Time returns the time of the event and the network calls fail,
+ * so replaying a conversation twice always gives the same result.
+ */
+use crate::{
+    coverage::{get_coverage_report, ExercisedPaths},
+    data::{CsmlRequest, Database, EngineError, MemoryMutation},
+    db_connectors::messages,
+    init::init_context,
+    utils::{format_event, get_default_flow, match_flow},
+    Client,
+};
+use csml_interpreter::{
+    data::{
+        ast::ForgetMemory, context::get_hashmap_from_mem, context::ContextStepInfo,
+        csml_bot::CsmlBot, Context, Event, Hold, Sandbox, MSG,
+    },
+    interpret,
+};
+use chrono::DateTime;
+use serde_json::{Map, Value};
+use std::sync::{mpsc, Arc};
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * An event of the user and the messages the bot answered
+ */
+struct Interaction {
+    event: Value,
+    // time of the event, in milliseconds
+    created_at: Option<i64>,
+    messages: Vec<Value>,
+}
+
+/**
+ * In-memory state of the replayed conversation, or of a conversation of the stateless engine
+ */
+pub(crate) struct ReplayState {
+    pub(crate) flow: String,
+    pub(crate) step: ContextStepInfo,
+    pub(crate) hold: Option<Hold>,
+    pub(crate) memories: Map<String, Value>,
+    // context variables are purged when the conversation is closed
+    pub(crate) variables: Map<String, Value>,
+    pub(crate) open: bool,
+    pub(crate) handover: bool,
+    pub(crate) exercised: ExercisedPaths,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Group the messages of a conversation by event: each RECEIVE message starts an interaction
+ */
+fn get_interactions(messages: Vec<Value>) -> Vec<Interaction> {
+    let mut interactions: Vec<Interaction> = vec![];
+
+    for message in messages {
+        match message["direction"].as_str() {
+            Some("RECEIVE") => interactions.push(Interaction {
+                event: message["payload"].clone(),
+                created_at: message["created_at"]
+                    .as_str()
+                    .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                    .map(|date| date.timestamp_millis()),
+                messages: vec![],
+            }),
+            Some("SEND") => {
+                if let Some(interaction) = interactions.last_mut() {
+                    interaction.messages.push(message["payload"].clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    interactions
+}
+
+/**
+ * Interpret an event like start_conversation does, and return the messages of the bot
+ */
+fn replay_event(
+    state: &mut ReplayState,
+    event: &Value,
+    sandbox: Option<Sandbox>,
+    client: &Client,
+    bot: &CsmlBot,
+    db: &mut Database,
+) -> Result<Vec<Value>, EngineError> {
+    let request = CsmlRequest {
+        request_id: "replay".to_owned(),
+        client: client.to_owned(),
+        callback_url: None,
+        payload: event.to_owned(),
+        metadata: serde_json::json!({}),
+        step_limit: None,
+        ttl_duration: None,
+        low_data_mode: None,
+    };
+    let mut event = format_event(&request)?;
+    // the annotations of the enrichers were saved with the event
+    if let Some(enrichment) = request.payload.get("enrichment") {
+        event.enrichment = enrichment.to_owned();
+    }
+
+    open_flow(state, &event, bot)?;
+
+    let mut context = init_context(state.flow.to_owned(), client.to_owned(), bot, db);
+    context.previous_bot = None;
+    context.debugger = None;
+    context.sandbox = sandbox.map(Arc::new);
+
+    let (messages, _) = interpret_in_memory(state, context, event, bot);
+
+    Ok(messages)
+}
+
+/**
+ * A flow requested by the user takes precedence over the open conversation,
+ * the default flow is opened if there is no open conversation
+ */
+pub(crate) fn open_flow(
+    state: &mut ReplayState,
+    event: &Event,
+    bot: &CsmlBot,
+) -> Result<(), EngineError> {
+    match match_flow(event, bot) {
+        Ok((flow, step)) => {
+            state.flow = flow.name.to_owned();
+            state.step = ContextStepInfo::UnknownFlow(step);
+            state.hold = None;
+            state.open = true;
+        }
+        Err(_) if !state.open => {
+            state.flow = get_default_flow(bot)?.name.to_owned();
+            state.step = ContextStepInfo::UnknownFlow("start".to_owned());
+            state.hold = None;
+            state.open = true;
+        }
+        Err(_) => {}
+    }
+
+    Ok(())
+}
+
+/**
+ * Interpret an event with the in-memory state of the conversation, and return the messages
+ * of the bot with the memories it saved or forgot
+ */
+pub(crate) fn interpret_in_memory(
+    state: &mut ReplayState,
+    mut context: Context,
+    event: Event,
+    bot: &CsmlBot,
+) -> (Vec<Value>, Vec<MemoryMutation>) {
+    let mut current = state.memories.clone();
+    current.extend(state.variables.clone());
+
+    context.current = get_hashmap_from_mem(&Value::Object(current), &state.flow);
+    context.step = state.step.to_owned();
+    context.hold = state.hold.take();
+
+    let (sender, receiver) = mpsc::channel::<MSG>();
+    interpret(bot.to_owned(), context, event, Some(sender));
+
+    // the steps and branches are recorded before the end of the conversation stops the reading
+    let received: Vec<MSG> = receiver.try_iter().collect();
+    for msg in received.iter() {
+        state.exercised.record(msg);
+    }
+
+    let mut messages = vec![];
+    let mut mutations = vec![];
+    for received in received.into_iter() {
+        match received {
+            MSG::Remember(mem) | MSG::RememberUser(mem) => {
+                mutations.push(MemoryMutation::Remember {
+                    key: mem.key.to_owned(),
+                    value: mem.value.to_owned(),
+                });
+                state.memories.insert(mem.key, mem.value);
+            }
+            MSG::RememberTemp(mem) => {
+                state.variables.insert(mem.key, mem.value);
+            }
+            MSG::Forget(ForgetMemory::ALL) => {
+                mutations.push(MemoryMutation::ForgetAll);
+                state.memories.clear();
+                state.variables.clear();
+            }
+            MSG::Forget(ForgetMemory::SINGLE(memory)) => {
+                mutations.push(MemoryMutation::Forget {
+                    key: memory.ident.to_owned(),
+                });
+                state.memories.remove(&memory.ident);
+                state.variables.remove(&memory.ident);
+            }
+            MSG::Forget(ForgetMemory::LIST(mem_list)) => {
+                for mem in mem_list.iter() {
+                    mutations.push(MemoryMutation::Forget {
+                        key: mem.ident.to_owned(),
+                    });
+                    state.memories.remove(&mem.ident);
+                    state.variables.remove(&mem.ident);
+                }
+            }
+            MSG::Message(mut msg) => messages.push(msg.message_to_json()),
+            MSG::Hold(hold) => state.hold = Some(hold),
+            MSG::Next {
+                flow,
+                step,
+                bot: None,
+            } => match (flow, step) {
+                (Some(flow), step) => {
+                    state.flow = flow;
+                    state.step =
+                        step.unwrap_or_else(|| ContextStepInfo::Normal("start".to_owned()));
+                }
+                (None, Some(step)) if !step.is_step("end") => state.step = step,
+                (None, _) => {
+                    state.open = false;
+                    break;
+                }
+            },
+            // the conversation continues with another bot
+            MSG::Next { bot: Some(_), .. } => {
+                state.open = false;
+                break;
+            }
+            MSG::Handover(_) => state.handover = true,
+            MSG::Error(mut err_msg, _) => {
+                messages.push(err_msg.message_to_json());
+                state.open = false;
+            }
+            MSG::Log { .. }
+            | MSG::Schedule { .. }
+            | MSG::Tag(_)
+            | MSG::Experiment { .. }
+            | MSG::Stream { .. }
+            | MSG::Warning { .. }
+            | MSG::StepTiming { .. }
+            | MSG::AppCall { .. }
+            | MSG::Branch { .. } => {}
+        }
+    }
+
+    if !state.open {
+        state.hold = None;
+        state.variables.clear();
+    }
+
+    (messages, mutations)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Replay the events of a conversation against a bot, returns None if the conversation
+ * has no saved event. Each interaction whose replayed messages are not the messages
+ * of the conversation is returned as a divergence.
+ */
+pub fn replay_conversation(
+    client: &Client,
+    conversation_id: &str,
+    bot: &CsmlBot,
+    deterministic: bool,
+    db: &mut Database,
+) -> Result<Option<Value>, EngineError> {
+    let messages = messages::get_conversation_messages(client, conversation_id, db)?;
+    let interactions = get_interactions(messages);
+
+    if interactions.is_empty() {
+        return Ok(None);
+    }
+
+    let mut state = ReplayState {
+        flow: get_default_flow(bot)?.name.to_owned(),
+        step: ContextStepInfo::Normal("start".to_owned()),
+        hold: None,
+        memories: Map::new(),
+        variables: Map::new(),
+        open: false,
+        handover: false,
+        exercised: ExercisedPaths::default(),
+    };
+    let mut divergences = vec![];
+    let mut skipped = vec![];
+
+    for (index, interaction) in interactions.iter().enumerate() {
+        // the events received while an agent handles the conversation are not interpreted
+        if state.handover {
+            skipped.push(serde_json::json!({
+                "interaction": index,
+                "reason": "the conversation was handed over to an agent",
+            }));
+            continue;
+        }
+
+        if interaction.event["content_type"] == "secure" {
+            skipped.push(serde_json::json!({
+                "interaction": index,
+                "reason": "the content of secure events is not saved",
+            }));
+            continue;
+        }
+
+        let sandbox = match deterministic {
+            true => Some(
+                Sandbox::new(index as u64).with_time(interaction.created_at.unwrap_or_default()),
+            ),
+            false => None,
+        };
+
+        match replay_event(&mut state, &interaction.event, sandbox, client, bot, db) {
+            Ok(messages) if messages == interaction.messages => {}
+            Ok(messages) => divergences.push(serde_json::json!({
+                "interaction": index,
+                "event": interaction.event,
+                "expected": interaction.messages,
+                "replayed": messages,
+            })),
+            Err(err) => skipped.push(serde_json::json!({
+                "interaction": index,
+                "reason": format!("{:?}", err),
+            })),
+        }
+    }
+
+    Ok(Some(serde_json::json!({
+        "conversation_id": conversation_id,
+        "interactions": interactions.len(),
+        "divergences": divergences,
+        "skipped": skipped,
+        "coverage": get_coverage_report(bot, &state.exercised),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{conversations, init_db, state};
+    use crate::init::init_bot;
+    use csml_interpreter::data::CsmlFlow;
+
+    fn get_bot(greeting: &str) -> CsmlBot {
+        let content = format!(
+            "start:\n    say \"{}\"\n    hold\n    say \"You said {{{{event}}}}\"\n    goto end",
+            greeting
+        );
+
+        CsmlBot::new(
+            "bot_id",
+            "bot",
+            None,
+            vec![CsmlFlow::new("Default", "Default", &content, vec![])],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn get_request(client: &Client, text: &str) -> CsmlRequest {
+        CsmlRequest {
+            request_id: "request_id".to_owned(),
+            client: client.to_owned(),
+            callback_url: None,
+            payload: serde_json::json!({"content_type": "text", "content": {"text": text}}),
+            metadata: serde_json::json!({}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+        }
+    }
+
+    #[test]
+    fn ok_replay_conversation() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "replay_test".to_owned(),
+        );
+        conversations::close_all_conversations(&client, &mut db).unwrap();
+        state::delete_state_key(&client, "hold", "position", &mut db).unwrap();
+
+        let bot_opt = crate::BotOpt::CsmlBot(get_bot("Hello"));
+        crate::start_conversation(get_request(&client, "hi"), bot_opt.clone()).unwrap();
+        let conversation_id = conversations::get_latest_open(&client, &mut db)
+            .unwrap()
+            .unwrap()
+            .id;
+        crate::start_conversation(get_request(&client, "ok"), bot_opt).unwrap();
+
+        let mut bot = get_bot("Hello");
+        init_bot(&mut bot).unwrap();
+        let replay = replay_conversation(&client, &conversation_id, &bot, false, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(replay["interactions"], 2);
+        assert_eq!(replay["divergences"], serde_json::json!([]));
+        assert_eq!(replay["coverage"]["percentage"], 100.0);
+
+        // only the first interaction diverges: the hold is resumed by the second event
+        let mut bot = get_bot("Hi");
+        init_bot(&mut bot).unwrap();
+        let replay = replay_conversation(&client, &conversation_id, &bot, false, &mut db)
+            .unwrap()
+            .unwrap();
+        let divergences = replay["divergences"].as_array().unwrap();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0]["interaction"], 0);
+        assert_eq!(divergences[0]["expected"][0]["content"]["text"], "Hello");
+        assert_eq!(divergences[0]["replayed"][0]["content"]["text"], "Hi");
+
+        assert!(replay_conversation(&client, "unknown", &bot, false, &mut db)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn ok_deterministic_replay() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "deterministic_replay_test".to_owned(),
+        );
+        conversations::close_all_conversations(&client, &mut db).unwrap();
+        state::delete_state_key(&client, "hold", "position", &mut db).unwrap();
+
+        let content = "start:\n    say \"{{Random()}} {{UUID()}}\"\n    hold\n    goto end";
+        let mut bot = get_bot("Hello");
+        bot.flows[0].content = content.to_owned();
+
+        let bot_opt = crate::BotOpt::CsmlBot(bot.clone());
+        crate::start_conversation(get_request(&client, "hi"), bot_opt).unwrap();
+        let conversation_id = conversations::get_latest_open(&client, &mut db)
+            .unwrap()
+            .unwrap()
+            .id;
+
+        init_bot(&mut bot).unwrap();
+        let first = replay_conversation(&client, &conversation_id, &bot, true, &mut db)
+            .unwrap()
+            .unwrap();
+        let second = replay_conversation(&client, &conversation_id, &bot, true, &mut db)
+            .unwrap()
+            .unwrap();
+
+        // the random values of the conversation are not the seeded ones of the sandbox
+        assert_eq!(first["divergences"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            first["divergences"][0]["replayed"],
+            second["divergences"][0]["replayed"]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    data::{BotOpt, BotRollout, Database, EngineError},
+    db_connectors::{bot, state},
+    utils::get_bot_client,
+    Client,
+};
+use csml_interpreter::data::csml_logs::*;
+
+use md5::{Digest, Md5};
+
+/**
+ * Check that every version in the rollout exists and that the percentages
+ * cover exactly 100% of the traffic.
+ */
+pub fn validate_rollout(
+    bot_id: &str,
+    rollout: &BotRollout,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    if rollout.versions.is_empty() {
+        return Err(EngineError::Format(
+            "rollout must contain at least one version".to_owned(),
+        ));
+    }
+
+    let total: u32 = rollout
+        .versions
+        .iter()
+        .map(|version| version.percentage as u32)
+        .sum();
+
+    if total != 100 {
+        return Err(EngineError::Format(format!(
+            "rollout percentages must add up to 100, got {}",
+            total
+        )));
+    }
+
+    for version in rollout.versions.iter() {
+        if bot::get_by_version_id(&version.version_id, bot_id, db)?.is_none() {
+            return Err(EngineError::BotNotFound(format!(
+                "bot version ({}) not found in db",
+                version.version_id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn get_rollout(bot_id: &str, db: &mut Database) -> Result<Option<BotRollout>, EngineError> {
+    let client = get_bot_client(bot_id);
+
+    match state::get_state_key(&client, "bot", "rollout", db)? {
+        Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn set_rollout(bot_id: &str, rollout: &BotRollout, db: &mut Database) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+    let value = serde_json::json!(rollout);
+
+    state::replace_state_item(&client, "bot", "rollout", &value, None, db)
+}
+
+pub fn delete_rollout(bot_id: &str, db: &mut Database) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+
+    state::delete_state_key(&client, "bot", "rollout", db)
+}
+
+/**
+ * Place the client in a bucket between 0 and 99.
+ * The bucket only depends on the client, so a given user always gets the same version.
+ */
+fn get_client_bucket(client: &Client) -> u64 {
+    let mut hash = Md5::new();
+
+    hash.update(client.bot_id.as_bytes());
+    hash.update(client.channel_id.as_bytes());
+    hash.update(client.user_id.as_bytes());
+
+    let digest = hash.finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+
+    u64::from_be_bytes(bytes) % 100
+}
+
+pub fn pick_rollout_version<'a>(rollout: &'a BotRollout, client: &Client) -> Option<&'a str> {
+    let bucket = get_client_bucket(client);
+    let mut threshold = 0;
+
+    for version in rollout.versions.iter() {
+        threshold += version.percentage as u64;
+
+        if bucket < threshold {
+            return Some(&version.version_id);
+        }
+    }
+
+    None
+}
+
+/**
+ * When the request targets the latest version of a bot and a rollout is set for this bot,
+ * replace the requested bot by the version assigned to the client.
+ * Requests made on a given environment always use the active version of this environment.
+ */
+pub fn resolve_bot_rollout(
+    bot_opt: &mut BotOpt,
+    client: &Client,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let (bot_id, apps_endpoint, multibot) = match bot_opt {
+        BotOpt::BotId {
+            bot_id,
+            apps_endpoint,
+            multibot,
+            environment: None,
+        } => (bot_id.to_owned(), apps_endpoint.to_owned(), multibot.to_owned()),
+        _ => return Ok(()),
+    };
+
+    let rollout = match get_rollout(&bot_id, db)? {
+        Some(rollout) => rollout,
+        None => return Ok(()),
+    };
+
+    if let Some(version_id) = pick_rollout_version(&rollout, client) {
+        csml_logger(
+            CsmlLog::new(
+                Some(client),
+                None,
+                None,
+                format!("rollout: bot version [{}] selected", version_id),
+            ),
+            LogLvl::Info,
+        );
+
+        *bot_opt = BotOpt::Id {
+            version_id: version_id.to_owned(),
+            bot_id,
+            apps_endpoint,
+            multibot,
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::RolloutVersion;
+
+    fn get_rollout() -> BotRollout {
+        BotRollout {
+            versions: vec![
+                RolloutVersion {
+                    version_id: "A".to_owned(),
+                    percentage: 90,
+                },
+                RolloutVersion {
+                    version_id: "B".to_owned(),
+                    percentage: 10,
+                },
+            ],
+        }
+    }
+
+    fn get_client(user_id: usize) -> Client {
+        Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            format!("user_{}", user_id),
+        )
+    }
+
+    #[test]
+    fn ok_rollout_sticky() {
+        let rollout = get_rollout();
+
+        for user_id in 0..100 {
+            let client = get_client(user_id);
+
+            assert_eq!(
+                pick_rollout_version(&rollout, &client),
+                pick_rollout_version(&rollout, &client)
+            );
+        }
+    }
+
+    #[test]
+    fn ok_rollout_split() {
+        let rollout = get_rollout();
+
+        let version_b = (0..10_000)
+            .filter(|user_id| pick_rollout_version(&rollout, &get_client(*user_id)) == Some("B"))
+            .count();
+
+        assert!(version_b > 700 && version_b < 1300);
+    }
+
+    #[test]
+    fn ok_rollout_update() {
+        let mut db = crate::db_connectors::init_db().unwrap();
+        let bot_id = "rollout_update_bot_id";
+
+        set_rollout(bot_id, &get_rollout(), &mut db).unwrap();
+
+        let mut rollout = get_rollout();
+        rollout.versions[0].percentage = 50;
+        rollout.versions[1].percentage = 50;
+        set_rollout(bot_id, &rollout, &mut db).unwrap();
+
+        let saved = super::get_rollout(bot_id, &mut db).unwrap().unwrap();
+        assert_eq!(saved.versions[0].percentage, 50);
+
+        delete_rollout(bot_id, &mut db).unwrap();
+        assert!(super::get_rollout(bot_id, &mut db).unwrap().is_none());
+    }
+    #[test]
+    fn ko_rollout_unknown_version() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = crate::db_connectors::init_db().unwrap();
+
+        let version_id = uuid::Uuid::new_v4().to_string();
+        let rollout = BotRollout {
+            versions: vec![RolloutVersion {
+                version_id: version_id.clone(),
+                percentage: 100,
+            }],
+        };
+
+        let err = validate_rollout("rollout_unknown_bot_id", &rollout, &mut db).unwrap_err();
+
+        assert!(matches!(err, EngineError::BotNotFound(_)));
+        assert_eq!(err.code(), "bot_not_found");
+        assert_eq!(
+            err.to_string(),
+            format!("bot version ({}) not found in db", version_id)
+        );
+    }
+}
@@ -0,0 +1,191 @@
+/**
+ * Pagination of the messages returned by a run: when a step sends more than RUN_MESSAGES_LIMIT
+ * messages (without limit by default), the run only returns the first ones with a pagination_key,
+ * and the others are saved in the state of the client for RUN_MESSAGES_TTL seconds (default 600).
+ *
+ * The next pages are read with get_run_messages (GET /run/{request_id}/messages), the
+ * pagination_key being the position of the next message in the remaining messages.
+ */
+use crate::{
+    config::get_config,
+    data::{Database, EngineError},
+    db_connectors::state,
+    Client,
+};
+
+use serde_json::{Map, Value};
+
+const RUN_MESSAGES: &str = "run_messages";
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+fn get_setting_number(key: &str) -> Option<usize> {
+    get_config()
+        .integer(key)
+        .map(|value| value as usize)
+        .filter(|value| *value > 0)
+}
+
+fn get_page(messages: &[Value], offset: usize, limit: usize) -> (Vec<Value>, Option<String>) {
+    let end = std::cmp::min(offset + limit, messages.len());
+    let page = messages.get(offset..end).unwrap_or_default().to_vec();
+
+    match end < messages.len() {
+        true => (page, Some(end.to_string())),
+        false => (page, None),
+    }
+}
+
+/**
+ * Keep the first RUN_MESSAGES_LIMIT messages of the result of a run and save the others
+ */
+pub fn paginate_run_messages(
+    result: &mut Map<String, Value>,
+    client: &Client,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    match get_setting_number("conversations.run_messages_limit") {
+        Some(limit) => save_remaining_messages(result, client, limit, db),
+        None => Ok(()),
+    }
+}
+
+fn save_remaining_messages(
+    result: &mut Map<String, Value>,
+    client: &Client,
+    limit: usize,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let request_id = match result.get("request_id").and_then(Value::as_str) {
+        Some(request_id) => request_id.to_owned(),
+        None => return Ok(()),
+    };
+
+    let messages = match result.get_mut("messages").and_then(Value::as_array_mut) {
+        Some(messages) if messages.len() > limit => messages.split_off(limit),
+        _ => return Ok(()),
+    };
+
+    let ttl = get_setting_number("conversations.run_messages_ttl").unwrap_or(600);
+    state::replace_state_item(
+        client,
+        RUN_MESSAGES,
+        &request_id,
+        &Value::Array(messages),
+        Some(chrono::Duration::seconds(ttl as i64)),
+        db,
+    )?;
+
+    result.insert("pagination_key".to_owned(), Value::String("0".to_owned()));
+
+    Ok(())
+}
+
+/**
+ * Page of the remaining messages of a run, or None if the run has no (or no longer any)
+ * remaining messages
+ */
+pub fn get_run_messages(
+    client: &Client,
+    request_id: &str,
+    limit: Option<usize>,
+    pagination_key: Option<String>,
+    db: &mut Database,
+) -> Result<Option<Value>, EngineError> {
+    let messages = match state::get_state_key(client, RUN_MESSAGES, request_id, db)? {
+        Some(Value::Array(messages)) => messages,
+        _ => return Ok(None),
+    };
+
+    let offset = match pagination_key {
+        Some(key) => key
+            .parse::<usize>()
+            .map_err(|_| EngineError::Format("Invalid pagination_key".to_owned()))?,
+        None => 0,
+    };
+    let limit = limit
+        .filter(|limit| *limit > 0)
+        .or_else(|| get_setting_number("conversations.run_messages_limit"))
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let (page, pagination_key) = get_page(&messages, offset, limit);
+
+    Ok(Some(serde_json::json!({
+        "request_id": request_id,
+        "messages": page,
+        "pagination_key": pagination_key,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    #[test]
+    fn ok_get_page() {
+        let messages: Vec<Value> = (0..5).map(|index| serde_json::json!(index)).collect();
+
+        assert_eq!(
+            get_page(&messages, 0, 2),
+            (
+                vec![serde_json::json!(0), serde_json::json!(1)],
+                Some("2".to_owned())
+            )
+        );
+        assert_eq!(
+            get_page(&messages, 4, 2),
+            (vec![serde_json::json!(4)], None)
+        );
+        assert_eq!(get_page(&messages, 10, 2), (vec![], None));
+    }
+
+    #[test]
+    fn ok_save_remaining_messages() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "run_messages_bot".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+
+        let messages: Vec<Value> = (0..5)
+            .map(|index| serde_json::json!({ "index": index }))
+            .collect();
+        let mut result = Map::new();
+        result.insert(
+            "request_id".to_owned(),
+            serde_json::json!("run_messages_request"),
+        );
+        result.insert("messages".to_owned(), Value::Array(messages));
+
+        save_remaining_messages(&mut result, &client, 2, &mut db).unwrap();
+
+        assert_eq!(result["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(result["pagination_key"], "0");
+
+        let page = get_run_messages(&client, "run_messages_request", Some(2), None, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(page["messages"][0]["index"], 2);
+        assert_eq!(page["pagination_key"], "2");
+
+        let page = get_run_messages(
+            &client,
+            "run_messages_request",
+            Some(2),
+            Some("2".to_owned()),
+            &mut db,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(page["messages"].as_array().unwrap().len(), 1);
+        assert!(page["pagination_key"].is_null());
+
+        assert!(
+            get_run_messages(&client, "unknown_request", None, None, &mut db)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
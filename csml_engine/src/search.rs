@@ -0,0 +1,104 @@
+/**
+ * Text search in the stored messages and memories of a bot, for the support teams looking for
+ * the conversations mentioning a keyword.
+ *
+ * The search is made by the database: a text index on MongoDB (the documents containing any of
+ * the words), a LIKE on SQLite and PostgreSQL (the values containing the text, case insensitive),
+ * and an OpenSearch index of the items of the table on DynamoDB (see dynamodb/search.rs).
+ *
+ * When ENCRYPTION_SECRET is set, the messages and memories are searched with the blind index
+ * saved with them (see encrypt.rs): the items holding every word of the text match, as whole
+ * words and case insensitive. The items saved before the encryption was set are not found.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::search::{self, SearchQuery},
+    encrypt::get_search_terms,
+};
+
+const DEFAULT_LIMIT: i64 = 25;
+const MAX_LIMIT: i64 = 100;
+
+pub fn search(
+    bot_id: &str,
+    text: &str,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(EngineError::Format(
+            "the searched text must not be empty".to_owned(),
+        ));
+    }
+    let terms = get_search_terms(&serde_json::json!(text))?;
+    if let Some(terms) = &terms {
+        if terms.is_empty() {
+            return Err(EngineError::Format(
+                "the searched text of encrypted values must contain a word".to_owned(),
+            ));
+        }
+    }
+
+    let query = SearchQuery {
+        bot_id: bot_id.to_owned(),
+        text: text.to_owned(),
+        terms,
+        from,
+        to,
+        limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+    };
+
+    Ok(serde_json::json!({
+        "messages": search::search_messages(&query, db)?,
+        "memories": search::search_memories(&query, db)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{init_db, memories};
+    use crate::Client;
+
+    #[test]
+    fn ok_search_memories() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let client = Client::new(
+            "bot_search".to_owned(),
+            "channel_search".to_owned(),
+            "user_search".to_owned(),
+        );
+        memories::delete_client_memories(&client, &mut db).unwrap();
+
+        assert!(search("bot_search", " ", None, None, None, &mut db).is_err());
+
+        let value = serde_json::json!("my order 50%_off was refunded");
+        memories::create_client_memory(&client, "order".to_owned(), value, None, &mut db).unwrap();
+
+        let result = search("bot_search", "Refunded", None, None, None, &mut db).unwrap();
+        assert_eq!(result["memories"][0]["key"], "order");
+        assert_eq!(result["memories"][0]["client"]["user_id"], "user_search");
+
+        // the encrypted values are searched by whole words
+        if crate::config::get_config().is_set("encryption.secret") {
+            let result = search("bot_search", "ORDER refunded", None, None, None, &mut db).unwrap();
+            assert_eq!(result["memories"].as_array().unwrap().len(), 1);
+            let result = search("bot_search", "refund", None, None, None, &mut db).unwrap();
+            assert!(result["memories"].as_array().unwrap().is_empty());
+            assert!(search("bot_search", "%_", None, None, None, &mut db).is_err());
+        } else {
+            // the LIKE wildcards of the text are matched literally
+            let result = search("bot_search", "0%_o", None, None, None, &mut db).unwrap();
+            assert_eq!(result["memories"].as_array().unwrap().len(), 1);
+            let result = search("bot_search", "5_%", None, None, None, &mut db).unwrap();
+            assert!(result["memories"].as_array().unwrap().is_empty());
+        }
+
+        let result = search("other_bot", "refunded", None, None, None, &mut db).unwrap();
+        assert!(result["memories"].as_array().unwrap().is_empty());
+    }
+}
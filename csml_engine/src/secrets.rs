@@ -0,0 +1,225 @@
+/**
+ * Secrets are exposed to the flows in the _secrets namespace. They are secure variables:
+ * they can be used in HTTP/App calls but never displayed, saved in memories or in messages.
+ *
+ * The provider is selected with SECRETS_PROVIDER:
+ * - env: every CSML_SECRET_<NAME> variable is available as _secrets.<name> (in lowercase)
+ * - vault: KV v2 secret read at VAULT_ADDR/v1/<SECRETS_VAULT_MOUNT>/data/<SECRETS_VAULT_PATH>
+ *   with VAULT_TOKEN. The mount defaults to "secret" and the path to "csml/{bot_id}"
+ * - aws: AWS Secrets Manager secret SECRETS_AWS_SECRET_ID (default "csml/{bot_id}"),
+ *   the secret string must be a JSON object. Requires the aws_secrets feature.
+ *
+ * Secrets are cached in memory for SECRETS_CACHE_TTL seconds (default 300).
+ */
+use crate::config::get_config;
+use crate::data::EngineError;
+use csml_interpreter::data::{context::get_hashmap_from_json, csml_logs::*, Literal};
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Secrets = serde_json::Map<String, serde_json::Value>;
+
+trait SecretsProvider {
+    fn get_secrets(&self, bot_id: &str) -> Result<Secrets, EngineError>;
+}
+
+struct EnvProvider;
+
+impl SecretsProvider for EnvProvider {
+    fn get_secrets(&self, _bot_id: &str) -> Result<Secrets, EngineError> {
+        let secrets = env::vars()
+            .filter_map(|(key, value)| {
+                key.strip_prefix("CSML_SECRET_")
+                    .map(|name| (name.to_lowercase(), serde_json::json!(value)))
+            })
+            .collect();
+
+        Ok(secrets)
+    }
+}
+
+struct VaultProvider {
+    address: String,
+    token: String,
+    mount: String,
+    path: String,
+}
+
+impl SecretsProvider for VaultProvider {
+    fn get_secrets(&self, bot_id: &str) -> Result<Secrets, EngineError> {
+        let url = format!(
+            "{}/v1/{}/data/{}",
+            self.address.trim_end_matches('/'),
+            self.mount,
+            self.path.replace("{bot_id}", bot_id)
+        );
+
+        let response = ureq::get(&url)
+            .set("X-Vault-Token", &self.token)
+            .call()
+            .map_err(|err| EngineError::Manager(format!("Vault error: {}", err)))?;
+        let body: serde_json::Value = response.into_json()?;
+
+        match body["data"]["data"].as_object() {
+            Some(secrets) => Ok(secrets.to_owned()),
+            None => Err(EngineError::Format(format!(
+                "Vault secret {} is not a key/value secret",
+                url
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "aws_secrets")]
+struct AwsSecretsManagerProvider {
+    secret_id: String,
+}
+
+#[cfg(feature = "aws_secrets")]
+impl SecretsProvider for AwsSecretsManagerProvider {
+    fn get_secrets(&self, bot_id: &str) -> Result<Secrets, EngineError> {
+        use rusoto_core::Region;
+        use rusoto_secretsmanager::{GetSecretValueRequest, SecretsManager, SecretsManagerClient};
+
+        let client = SecretsManagerClient::new(Region::default());
+        let request = GetSecretValueRequest {
+            secret_id: self.secret_id.replace("{bot_id}", bot_id),
+            ..Default::default()
+        };
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let response = runtime
+            .block_on(client.get_secret_value(request))
+            .map_err(|err| EngineError::Manager(format!("AWS Secrets Manager error: {}", err)))?;
+
+        match response.secret_string {
+            Some(secret) => match serde_json::from_str::<serde_json::Value>(&secret)? {
+                serde_json::Value::Object(secrets) => Ok(secrets),
+                _ => Err(EngineError::Format(
+                    "AWS secret must be a JSON object".to_owned(),
+                )),
+            },
+            None => Ok(Secrets::new()),
+        }
+    }
+}
+
+fn get_provider() -> Result<Option<Box<dyn SecretsProvider>>, EngineError> {
+    let config = get_config();
+    let provider = match config.text("secrets.provider") {
+        Some(provider) => provider,
+        None => return Ok(None),
+    };
+
+    match provider.as_str() {
+        "env" => Ok(Some(Box::new(EnvProvider))),
+        "vault" => {
+            let (address, token) = match (
+                config.text("secrets.vault_addr"),
+                config.text("secrets.vault_token"),
+            ) {
+                (Some(address), Some(token)) => (address, token),
+                _ => {
+                    return Err(EngineError::Manager(
+                        "VAULT_ADDR and VAULT_TOKEN must be set to use the vault secrets provider"
+                            .to_owned(),
+                    ))
+                }
+            };
+
+            Ok(Some(Box::new(VaultProvider {
+                address,
+                token,
+                mount: config
+                    .text("secrets.vault_mount")
+                    .unwrap_or_else(|| "secret".to_owned()),
+                path: config
+                    .text("secrets.vault_path")
+                    .unwrap_or_else(|| "csml/{bot_id}".to_owned()),
+            })))
+        }
+        #[cfg(feature = "aws_secrets")]
+        "aws" => Ok(Some(Box::new(AwsSecretsManagerProvider {
+            secret_id: config
+                .text("secrets.aws_secret_id")
+                .unwrap_or_else(|| "csml/{bot_id}".to_owned()),
+        }))),
+        provider => Err(EngineError::Manager(format!(
+            "unknown secrets provider: {}",
+            provider
+        ))),
+    }
+}
+
+fn get_cache_ttl() -> Duration {
+    let ttl = get_config().integer("secrets.cache_ttl").unwrap_or(300);
+
+    Duration::from_secs(ttl)
+}
+
+// bot_id => (fetched at, secrets)
+static SECRETS_CACHE: Lazy<Mutex<HashMap<String, (Instant, Secrets)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn fetch_secrets(bot_id: &str) -> Result<Secrets, EngineError> {
+    if let Some((fetched_at, secrets)) = SECRETS_CACHE.lock().unwrap().get(bot_id) {
+        if fetched_at.elapsed() < get_cache_ttl() {
+            return Ok(secrets.to_owned());
+        }
+    }
+
+    let secrets = match get_provider()? {
+        Some(provider) => provider.get_secrets(bot_id)?,
+        None => return Ok(Secrets::new()),
+    };
+
+    SECRETS_CACHE
+        .lock()
+        .unwrap()
+        .insert(bot_id.to_owned(), (Instant::now(), secrets.to_owned()));
+
+    Ok(secrets)
+}
+
+/**
+ * Get the secrets of the bot for the interpreter context.
+ * A failing provider must not break the conversation: the error is logged and no secret is set.
+ */
+pub fn get_bot_secrets(bot_id: &str, flow: &str) -> HashMap<String, Literal> {
+    match fetch_secrets(bot_id) {
+        Ok(secrets) => get_hashmap_from_json(&serde_json::Value::Object(secrets), flow),
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(
+                    None,
+                    Some(flow.to_owned()),
+                    None,
+                    format!("failed to get secrets of bot {}: {:?}", bot_id, err),
+                ),
+                LogLvl::Error,
+            );
+
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_env_secrets() {
+        env::set_var("CSML_SECRET_API_KEY", "secret_key");
+
+        let secrets = EnvProvider.get_secrets("bot_id").unwrap();
+
+        assert_eq!(secrets["api_key"], serde_json::json!("secret_key"));
+    }
+}
@@ -1,27 +1,64 @@
 use crate::data::{ConversationInfo};
+use crate::db_connectors::outbox::{create_outbox_message, set_outbox_message_sent};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
 
-fn format_and_transfer(callback_url: &str, msg: serde_json::Value) {
+/**
+ * POST the messages to the callback_url, returns true if they were accepted
+ */
+pub fn format_and_transfer(callback_url: &str, msg: &serde_json::Value) -> bool {
     let mut request = ureq::post(callback_url);
 
     request = request.set("Accept", "application/json")
                     .set("Content-Type", "application/json");
 
-    let response = request.send_json(msg);
+    let response = request.send_json(msg.to_owned());
 
     if let Err(err) = response{
         eprintln!("callback_url call failed: {:?}", err.to_string());
+        return false;
     }
+
+    true
 }
 
 /**
  * If a callback_url is defined, we must send each message to its endpoint as it comes.
  * Otherwise, just continue!
+ *
+ * The messages are first saved in the outbox: if the callback_url does not accept them or if
+ * the process stops before they are sent, the outbox job sends them again (see outbox.rs).
  */
 pub fn send_to_callback_url(c_info: &mut ConversationInfo, msg: serde_json::Value) {
     let callback_url = match &c_info.callback_url {
-        Some(callback_url) => callback_url,
+        Some(callback_url) => callback_url.to_owned(),
         None => return,
     };
 
-    format_and_transfer(callback_url, msg)
+    // the bots without data retention only send their messages once
+    let outbox_message = match c_info.no_data_retention {
+        true => None,
+        false => match create_outbox_message(&c_info.client, &callback_url, &msg, &mut c_info.db) {
+            Ok(outbox_message) => Some(outbox_message),
+            Err(err) => {
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&c_info.client),
+                        None,
+                        None,
+                        format!("the messages were not saved in the outbox: {:?}", err),
+                    ),
+                    LogLvl::Error,
+                );
+                None
+            }
+        },
+    };
+
+    if !format_and_transfer(&callback_url, &msg) {
+        return;
+    }
+
+    if let Some(outbox_message) = outbox_message {
+        set_outbox_message_sent(&outbox_message, &mut c_info.db).ok();
+    }
 }
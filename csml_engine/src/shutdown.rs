@@ -0,0 +1,102 @@
+/**
+ * Graceful shutdown of the engine, for the servers that are stopped during a deploy.
+ *
+ * Once the shutdown is started, the new runs are refused with EngineError::ShuttingDown
+ * and the broadcasts in progress are interrupted. `drain` then waits for the runs in
+ * progress to save the state of their conversations, for the queued events and webhook
+ * deliveries to be sent, for the closed conversations to be summarized and for the spans of the
+ * runs to be exported, until a deadline.
+ */
+use crate::{data::EngineError, events, summaries, telemetry, webhooks};
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static RUNS_IN_PROGRESS: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * A run in progress, until it is dropped
+ */
+pub struct RunGuard;
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        RUNS_IN_PROGRESS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn start_run() -> Result<RunGuard, EngineError> {
+    RUNS_IN_PROGRESS.fetch_add(1, Ordering::SeqCst);
+    let guard = RunGuard;
+
+    // checked after the increment so that drain can not miss a run that was accepted
+    match is_shutting_down() {
+        true => Err(EngineError::ShuttingDown),
+        false => Ok(guard),
+    }
+}
+
+pub fn runs_in_progress() -> usize {
+    RUNS_IN_PROGRESS.load(Ordering::SeqCst)
+}
+
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+pub fn start_shutdown() {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+}
+
+/**
+ * Wait until `is_done` or the deadline, return whether it is done
+ */
+pub fn wait_until(deadline: Instant, is_done: impl Fn() -> bool) -> bool {
+    loop {
+        if is_done() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/**
+ * Start the shutdown and wait for the runs in progress and the queued deliveries,
+ * return false if some of them were still pending at the deadline
+ */
+pub fn drain(deadline: Instant) -> bool {
+    start_shutdown();
+
+    let runs_done = wait_until(deadline, || runs_in_progress() == 0);
+    // the last runs may have queued events
+    let events_done = events::flush(deadline);
+    let webhooks_done = webhooks::flush(deadline);
+    let summaries_done = summaries::flush(deadline);
+    let telemetry_done = telemetry::flush(deadline);
+
+    runs_done && events_done && webhooks_done && summaries_done && telemetry_done
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_wait_until() {
+        let deadline = Instant::now() + Duration::from_millis(120);
+        assert!(!wait_until(deadline, || false));
+        assert!(Instant::now() >= deadline);
+
+        assert!(wait_until(Instant::now(), || true));
+    }
+}
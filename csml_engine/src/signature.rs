@@ -0,0 +1,132 @@
+/**
+ * Signature of the requests that start conversations: when a secret is configured for a bot,
+ * only the channel gateways sharing this secret can send events to its conversations.
+ *
+ * The gateway sends the unix timestamp of the request in the X-Csml-Timestamp header and the
+ * hex encoded HMAC-SHA256 of "{timestamp}.{body}" in the signature header.
+ * - the secret is RUN_SIGNATURE_SECRET_<BOT_ID> or RUN_SIGNATURE_SECRET for every bot
+ * - the header is RUN_SIGNATURE_HEADER_<BOT_ID> or RUN_SIGNATURE_HEADER (default X-Csml-Signature)
+ * with BOT_ID the bot id in uppercase, other characters than letters and digits replaced by "_".
+ *
+ * Requests older than RUN_SIGNATURE_TOLERANCE seconds (default 300) are rejected,
+ * so a signed request can't be replayed later.
+ */
+use crate::{config::get_config, data::EngineError};
+
+use chrono::prelude::Utc;
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
+
+pub const TIMESTAMP_HEADER: &str = "X-Csml-Timestamp";
+
+const DEFAULT_HEADER: &str = "X-Csml-Signature";
+const DEFAULT_TOLERANCE: i64 = 300;
+
+fn get_tolerance() -> i64 {
+    match get_config().integer("auth.run_signature_tolerance") {
+        Some(tolerance) => tolerance as i64,
+        None => DEFAULT_TOLERANCE,
+    }
+}
+
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> Result<Vec<u8>, EngineError> {
+    let key = PKey::hmac(secret.as_bytes())?;
+
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(timestamp.as_bytes())?;
+    signer.update(b".")?;
+    signer.update(body)?;
+
+    Ok(signer.sign_to_vec()?)
+}
+
+/**
+ * Name of the header of the signature, None if the requests of the bot are not signed
+ */
+pub fn get_signature_header(bot_id: &str) -> Option<String> {
+    let config = get_config();
+    config.text_for("auth.run_signature_secret", bot_id)?;
+
+    match config.text_for("auth.run_signature_header", bot_id) {
+        Some(header) => Some(header),
+        None => Some(DEFAULT_HEADER.to_owned()),
+    }
+}
+
+pub fn verify_signature(
+    bot_id: &str,
+    signature: &str,
+    timestamp: &str,
+    body: &[u8],
+) -> Result<(), EngineError> {
+    let secret = match get_config().text_for("auth.run_signature_secret", bot_id) {
+        Some(secret) => secret,
+        None => return Ok(()),
+    };
+
+    let sent_at = match timestamp.parse::<i64>() {
+        Ok(sent_at) => sent_at,
+        Err(_) => {
+            return Err(EngineError::Format(format!(
+                "Invalid {} value",
+                TIMESTAMP_HEADER
+            )))
+        }
+    };
+
+    if (Utc::now().timestamp() - sent_at).abs() > get_tolerance() {
+        return Err(EngineError::Format("Expired request signature".to_owned()));
+    }
+
+    let expected = sign(&secret, timestamp, body)?;
+    let signature = signature.trim_start_matches("sha256=");
+    let signature = match hex::decode(signature) {
+        Ok(signature) => signature,
+        Err(_) => return Err(EngineError::Format("Invalid request signature".to_owned())),
+    };
+
+    // constant-time comparison, the length of the signature is not secret
+    if signature.len() != expected.len() || !memcmp::eq(&signature, &expected) {
+        return Err(EngineError::Format("Invalid request signature".to_owned()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn ok_request_signature() {
+        let bot_id = "signed-bot";
+        env::set_var("RUN_SIGNATURE_SECRET_SIGNED_BOT", "secret");
+
+        assert_eq!(
+            get_signature_header(bot_id),
+            Some(DEFAULT_HEADER.to_owned())
+        );
+        assert_eq!(get_signature_header("unsigned_bot"), None);
+
+        let body = br#"{"event": {}}"#;
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature = hex::encode(sign("secret", &timestamp, body).unwrap());
+
+        assert!(verify_signature(bot_id, &signature, &timestamp, body).is_ok());
+        assert!(
+            verify_signature(bot_id, &format!("sha256={}", signature), &timestamp, body).is_ok()
+        );
+        // the requests of a bot without secret are not verified
+        assert!(verify_signature("unsigned_bot", "", "", body).is_ok());
+
+        assert!(verify_signature(bot_id, &signature, &timestamp, br#"{"event": null}"#).is_err());
+        assert!(verify_signature(bot_id, "00", &timestamp, body).is_err());
+
+        // a request can't be replayed out of the tolerance window
+        let timestamp = (Utc::now().timestamp() - 3600).to_string();
+        let signature = hex::encode(sign("secret", &timestamp, body).unwrap());
+        assert!(verify_signature(bot_id, &signature, &timestamp, body).is_err());
+
+        env::remove_var("RUN_SIGNATURE_SECRET_SIGNED_BOT");
+    }
+}
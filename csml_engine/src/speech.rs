@@ -0,0 +1,260 @@
+/**
+ * Voice channels: the audio events are transcribed before their interpretation and the text
+ * messages of the bots are synthesized, with HTTP services wrapping the speech providers.
+ *
+ * - STT_URL: speech-to-text service (STT_URL_<BOT_ID> for the events of a single bot), receives
+ *   {"url": String, "locale": String, "client": Client} and answers {"text": String,
+ *   "confidence": Number}. The audio event becomes a text event with the transcript in
+ *   event.text, the url of the audio and event.transcription.confidence.
+ * - TTS_URL: text-to-speech service (TTS_URL_<BOT_ID> for a single bot), receives
+ *   {"text": String, "locale": String, "client": Client} and answers {"url": String},
+ *   added as audio_url to the content of the text messages.
+ * - STT_AUTH / TTS_AUTH: optional Authorization headers of the requests
+ * - SPEECH_TIMEOUT: milliseconds a service has to answer (default 10000)
+ *
+ * The audio events are transcribed after their file is stored (see files.rs), the service
+ * receives the durable url of the file when it is available. When a service fails, the event
+ * is interpreted as an audio event and the messages are sent without audio.
+ */
+use crate::{config::get_config, Client};
+
+use csml_interpreter::data::{csml_logs::*, Event, Message};
+use serde_json::Value;
+use std::time::Duration;
+
+const DEFAULT_TIMEOUT: u64 = 10_000;
+
+fn call_service(url: &str, auth: &str, body: Value) -> Result<Value, String> {
+    let config = get_config();
+    let timeout = config.integer("speech.timeout").unwrap_or(DEFAULT_TIMEOUT);
+
+    let mut request = ureq::post(url).timeout(Duration::from_millis(timeout));
+    if let Some(auth) = config.text(auth) {
+        request = request.set("Authorization", &auth);
+    }
+
+    match request.send_json(body) {
+        Ok(response) => response.into_json().map_err(|err| err.to_string()),
+        Err(ureq::Error::Status(status, _)) => Err(format!("response status {}", status)),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn log_failure(client: &Client, service: &str, err: String) {
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("{} service failed: {}", service, err),
+        ),
+        LogLvl::Warn,
+    );
+}
+
+/**
+ * The locale of the user is metadata.locale, or the locale of the event
+ */
+fn get_locale(metadata: &Value, event_locale: &Option<String>) -> Value {
+    match metadata["locale"].as_str() {
+        Some(locale) => Value::from(locale),
+        None => serde_json::json!(event_locale),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Turn an audio event into a text event with its transcript,
+ * return true if the event was changed
+ */
+pub fn transcribe_event(event: &mut Event, client: &Client, metadata: &Value) -> bool {
+    if event.secure || event.content_type != "audio" {
+        return false;
+    }
+
+    let stt_url = match get_config().text_for("speech.stt_url", &client.bot_id) {
+        Some(url) => url,
+        None => return false,
+    };
+    let audio_url = match (
+        event.content["file"]["url"].as_str(),
+        event.content["url"].as_str(),
+    ) {
+        (Some(url), _) | (None, Some(url)) => url.to_owned(),
+        (None, None) => return false,
+    };
+
+    let body = serde_json::json!({
+        "url": audio_url,
+        "locale": get_locale(metadata, &event.locale),
+        "client": client,
+    });
+
+    let transcription = match call_service(&stt_url, "speech.stt_auth", body) {
+        Ok(transcription) => transcription,
+        Err(err) => {
+            log_failure(client, "speech-to-text", err);
+            return false;
+        }
+    };
+    let text = match transcription["text"].as_str() {
+        Some(text) if !text.trim().is_empty() => text.to_owned(),
+        _ => return false,
+    };
+
+    event.content["text"] = Value::from(text.as_str());
+    event.content["transcription"] = serde_json::json!({"confidence": transcription["confidence"]});
+    event.content_type = "text".to_owned();
+    event.content_value = text;
+
+    true
+}
+
+/**
+ * Add the url of its speech to a text message of the bot
+ */
+pub fn synthesize_message(message: &mut Message, client: &Client, metadata: &Value) {
+    if message.content_type != "text" {
+        return;
+    }
+
+    let tts_url = match get_config().text_for("speech.tts_url", &client.bot_id) {
+        Some(url) => url,
+        None => return,
+    };
+    let text = match message.content["text"].as_str() {
+        Some(text) if !text.trim().is_empty() => text.to_owned(),
+        _ => return,
+    };
+
+    let body = serde_json::json!({
+        "text": text,
+        "locale": get_locale(metadata, &None),
+        "client": client,
+    });
+
+    match call_service(&tts_url, "speech.tts_auth", body) {
+        Ok(speech) => match speech["url"].as_str() {
+            Some(url) => message.content["audio_url"] = Value::from(url),
+            None => log_failure(
+                client,
+                "text-to-speech",
+                "no url in the response".to_owned(),
+            ),
+        },
+        Err(err) => log_failure(client, "text-to-speech", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /**
+     * Answer a single request with a JSON body, return the body of the request
+     */
+    fn mock_service(response: &'static str) -> (String, thread::JoinHandle<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/speech", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        length = value.parse().unwrap();
+                    }
+                    None if line.trim_end().is_empty() => break,
+                    _ => (),
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+
+            serde_json::from_slice(&body).unwrap()
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn ok_transcribe_event() {
+        let client = Client::new(
+            "bot_stt".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        let (url, server) = mock_service(r#"{"text": "I want to cancel", "confidence": 0.92}"#);
+        std::env::set_var("STT_URL_BOT_STT", url);
+
+        let mut event = Event::new(
+            "audio",
+            "https://channel.example.com/voice.ogg",
+            serde_json::json!({"url": "https://channel.example.com/voice.ogg"}),
+        );
+        let metadata = serde_json::json!({"locale": "en-US"});
+
+        assert!(transcribe_event(&mut event, &client, &metadata));
+        assert_eq!(event.content_type, "text");
+        assert_eq!(event.content_value, "I want to cancel");
+        assert_eq!(
+            event.content["url"],
+            "https://channel.example.com/voice.ogg"
+        );
+        assert_eq!(event.content["transcription"]["confidence"], 0.92);
+
+        let request = server.join().unwrap();
+        assert_eq!(request["url"], "https://channel.example.com/voice.ogg");
+        assert_eq!(request["locale"], "en-US");
+
+        // only the audio events are transcribed
+        let mut event = Event::new("text", "hi", serde_json::json!({"text": "hi"}));
+        assert!(!transcribe_event(&mut event, &client, &metadata));
+    }
+
+    #[test]
+    fn ok_synthesize_message() {
+        let client = Client::new(
+            "bot_tts".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        let (url, server) = mock_service(r#"{"url": "https://tts.example.com/1.mp3"}"#);
+        std::env::set_var("TTS_URL_BOT_TTS", url);
+
+        let mut message = Message {
+            content_type: "text".to_owned(),
+            content: serde_json::json!({"text": "Your order is cancelled"}),
+        };
+        synthesize_message(&mut message, &client, &serde_json::json!({}));
+
+        assert_eq!(
+            message.content["audio_url"],
+            "https://tts.example.com/1.mp3"
+        );
+        assert_eq!(server.join().unwrap()["text"], "Your order is cancelled");
+    }
+}
@@ -0,0 +1,283 @@
+/**
+ * Stateless engine (feature "stateless"): the bots are run without database, for the
+ * platforms that already store the conversations. The caller gives the state of the
+ * conversation with each event (position, hold, memories and context variables) and gets
+ * back the messages of the bot, the memories saved or forgotten and the new state to store.
+ *
+ * The events are interpreted like start_conversation does, without the data saved by the
+ * engine: the globals of the bots, the debugger, the messages, the callback_url and the
+ * switches to another bot.
+ */
+use crate::{
+    data::{CsmlRequest, EngineError, MemoryMutation},
+    init::{init_bot, init_stateless_context},
+    replay::{interpret_in_memory, open_flow, ReplayState},
+    utils::{format_event, get_default_flow},
+};
+use csml_interpreter::data::{
+    context::{get_hashmap_from_json, ContextStepInfo},
+    csml_bot::CsmlBot,
+    Hold, IndexInfo,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * State of the conversation of a client, stored by the caller between the events
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatelessState {
+    // flow and step of the open conversation, None if no conversation is open
+    #[serde(default)]
+    pub flow: Option<String>,
+    #[serde(default)]
+    pub step: Option<String>,
+    // position of the interpreter in the step while it waits for an input
+    #[serde(default)]
+    pub hold: Option<Value>,
+    #[serde(default)]
+    pub memories: Map<String, Value>,
+    // context variables of the open conversation
+    #[serde(default)]
+    pub variables: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatelessRun {
+    pub messages: Vec<Value>,
+    pub memories: Vec<MemoryMutation>,
+    pub state: StatelessState,
+    // the conversation was handed over to an agent
+    pub handover: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatelessEngine {
+    bot: Option<CsmlBot>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn hold_to_json(hold: &Hold) -> Value {
+    serde_json::json!({
+        "index": hold.index,
+        "step_vars": hold.step_vars,
+        "step": hold.step_name,
+        "flow": hold.flow_name,
+        "previous": hold.previous,
+        "secure": hold.secure,
+    })
+}
+
+fn hold_from_json(hold: &Value, flow: &str, step: &str) -> Option<Hold> {
+    let index = serde_json::from_value::<IndexInfo>(hold["index"].clone()).ok()?;
+
+    Some(Hold {
+        index,
+        step_vars: hold["step_vars"].clone(),
+        step_name: hold["step"].as_str().unwrap_or(step).to_owned(),
+        flow_name: hold["flow"].as_str().unwrap_or(flow).to_owned(),
+        previous: serde_json::from_value(hold["previous"].clone()).unwrap_or(None),
+        secure: hold["secure"].as_bool().unwrap_or(false),
+        breakpoint: None,
+    })
+}
+
+fn get_replay_state(state: &StatelessState, bot: &CsmlBot) -> Result<ReplayState, EngineError> {
+    let flow = match &state.flow {
+        Some(flow) => flow.to_owned(),
+        None => get_default_flow(bot)?.name.to_owned(),
+    };
+    let step = state.step.to_owned().unwrap_or_else(|| "start".to_owned());
+    let hold = match (&state.flow, &state.hold) {
+        (Some(_), Some(hold)) => hold_from_json(hold, &flow, &step),
+        _ => None,
+    };
+
+    Ok(ReplayState {
+        flow,
+        step: ContextStepInfo::Normal(step),
+        hold,
+        memories: state.memories.to_owned(),
+        variables: state.variables.to_owned(),
+        open: state.flow.is_some(),
+        handover: false,
+        exercised: Default::default(),
+    })
+}
+
+fn get_stateless_state(state: ReplayState) -> StatelessState {
+    match state.open {
+        true => StatelessState {
+            flow: Some(state.flow),
+            step: Some(state.step.get_step()),
+            hold: state.hold.as_ref().map(hold_to_json),
+            memories: state.memories,
+            variables: state.variables,
+        },
+        false => StatelessState {
+            memories: state.memories,
+            ..Default::default()
+        },
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+impl StatelessEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Bot interpreting the events, its flows are parsed once
+     */
+    pub fn bot(mut self, mut bot: CsmlBot) -> Result<Self, EngineError> {
+        init_bot(&mut bot)?;
+        self.bot = Some(bot);
+
+        Ok(self)
+    }
+
+    /**
+     * Interpret an event in the state of the conversation of its client, nothing is saved
+     */
+    pub fn run(
+        &self,
+        state: &StatelessState,
+        request: &CsmlRequest,
+    ) -> Result<StatelessRun, EngineError> {
+        let bot = match &self.bot {
+            Some(bot) => bot,
+            None => {
+                return Err(EngineError::Manager(
+                    "the stateless engine has no bot, set it with StatelessEngine::bot".to_owned(),
+                ))
+            }
+        };
+
+        let mut event = format_event(request)?;
+        let mut replay_state = get_replay_state(state, bot)?;
+        if matches!(&replay_state.hold, Some(hold) if hold.secure) {
+            event.secure = true;
+        }
+        open_flow(&mut replay_state, &event, bot)?;
+
+        let mut context =
+            init_stateless_context(replay_state.flow.to_owned(), request.client.to_owned(), bot);
+        context.metadata = get_hashmap_from_json(&request.metadata, &context.flow);
+
+        let (messages, memories) = interpret_in_memory(&mut replay_state, context, event, bot);
+
+        Ok(StatelessRun {
+            messages,
+            memories,
+            handover: replay_state.handover,
+            state: get_stateless_state(replay_state),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::data::{Client, CsmlFlow};
+
+    fn get_request(text: &str) -> CsmlRequest {
+        CsmlRequest {
+            request_id: "request_id".to_owned(),
+            client: Client::new(
+                "stateless_bot".to_owned(),
+                "channel_id".to_owned(),
+                "user_id".to_owned(),
+            ),
+            callback_url: None,
+            payload: serde_json::json!({"content_type": "text", "content": {"text": text}}),
+            metadata: serde_json::json!({"firstname": "Jane"}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+        }
+    }
+
+    fn get_engine() -> StatelessEngine {
+        let content = "start:\n    say \"Hello {{_metadata.firstname}}\"\n    remember visits = 1\n    hold\n    remember name = event\n    forget visits\n    say \"Bye {{name}}\"\n    goto end";
+        let bot = CsmlBot::new(
+            "stateless_bot",
+            "bot",
+            None,
+            vec![CsmlFlow::new("Default", "Default", content, vec![])],
+            None,
+            None,
+            "Default",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        StatelessEngine::new().bot(bot).unwrap()
+    }
+
+    #[test]
+    fn ok_stateless_run() {
+        let engine = get_engine();
+
+        let first = engine
+            .run(&StatelessState::default(), &get_request("hi"))
+            .unwrap();
+        assert_eq!(first.messages[0]["content"]["text"], "Hello Jane");
+        assert_eq!(
+            first.memories,
+            vec![MemoryMutation::Remember {
+                key: "visits".to_owned(),
+                value: serde_json::json!(1),
+            }]
+        );
+        assert_eq!(first.state.step.as_deref(), Some("start"));
+        assert!(first.state.hold.is_some());
+
+        // the state returned by the first run is stored by the caller between the events
+        let state: StatelessState = serde_json::from_value(serde_json::json!(first.state)).unwrap();
+        let second = engine.run(&state, &get_request("Jane")).unwrap();
+        assert_eq!(second.messages[0]["content"]["text"], "Bye Jane");
+        assert_eq!(
+            second.memories,
+            vec![
+                MemoryMutation::Remember {
+                    key: "name".to_owned(),
+                    value: serde_json::json!("Jane"),
+                },
+                MemoryMutation::Forget {
+                    key: "visits".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(second.state.flow, None);
+        assert_eq!(second.state.hold, None);
+        assert_eq!(second.state.memories["name"], "Jane");
+    }
+
+    #[test]
+    fn ko_stateless_run_without_bot() {
+        let result = StatelessEngine::new().run(&StatelessState::default(), &get_request("hi"));
+
+        assert!(matches!(result, Err(EngineError::Manager(_))));
+    }
+}
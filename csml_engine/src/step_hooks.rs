@@ -0,0 +1,313 @@
+/**
+ * Step hooks: external monitoring or business rule systems observe the steps of the
+ * conversations of a bot, and can veto them.
+ *
+ * The hooks of a bot are enabled with STEP_HOOKS (STEP_HOOKS_<BOT_ID> for a single bot):
+ * - webhook: before and after each step, {"hook": "before_step" | "after_step",
+ *   "client": Client, "conversation_id": String, "flow_id": String, "step_id": String,
+ *   "duration_ms": Number (after_step), "variables": Object} is POSTed to STEP_HOOKS_URL
+ *   (STEP_HOOKS_URL_<BOT_ID>), with the optional Authorization header STEP_HOOKS_AUTH.
+ *   A before_step hook answering {"allow": false, "reason": String} vetoes the step: it is not
+ *   executed and the conversation ends with an error. The hooks are called during the
+ *   conversation, in at most STEP_HOOKS_TIMEOUT milliseconds (default 5000): an unavailable
+ *   hook never vetoes a step.
+ * - events: the step_started and step_completed events are published (see events.rs),
+ *   without blocking the conversation. They can't veto the steps.
+ *
+ * The variables are the memories and the step variables, without the secure variables.
+ * They are not sent for the bots with no data retention.
+ *
+ * An application embedding the engine can also observe the steps in Rust with the hooks
+ * returned for each conversation by EngineBuilder::step_hooks, used instead of STEP_HOOKS
+ * (the conversations without embedded hooks keep the hooks of STEP_HOOKS).
+ */
+use crate::{
+    config::{get_config, with_scoped},
+    data::ConversationInfo,
+    events::{publish_event, STEP_COMPLETED, STEP_STARTED},
+    Client,
+};
+use csml_interpreter::data::{csml_logs::*, StepHooks};
+
+use serde_json::{json, Value};
+use std::{cell::RefCell, sync::Arc, time::Duration};
+
+const DEFAULT_TIMEOUT: u64 = 5_000;
+
+/**
+ * Step hooks of the conversation of a client (with the id of the conversation),
+ * None for the conversations it does not observe
+ */
+pub type StepHooksFactory = dyn Fn(&Client, &str) -> Option<Arc<dyn StepHooks>> + Send + Sync;
+
+thread_local! {
+    // hooks of the Engine running on this thread, see with_embedded_hooks
+    static EMBEDDED_HOOKS: RefCell<Option<Arc<StepHooksFactory>>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug)]
+enum HookTarget {
+    Webhook(String),
+    Events,
+}
+
+#[derive(Debug)]
+struct BotStepHooks {
+    target: HookTarget,
+    client: Client,
+    conversation_id: String,
+    no_data_retention: bool,
+}
+
+fn log_error(client: &Client, message: String) {
+    csml_logger(
+        CsmlLog::new(Some(client), None, None, message),
+        LogLvl::Error,
+    );
+}
+
+/**
+ * Some(reason) if the hook vetoes the step
+ */
+fn post_hook(url: &str, body: Value) -> Result<Option<String>, String> {
+    let config = get_config();
+    let timeout = config
+        .integer("step_hooks.timeout")
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    let mut request = ureq::post(url).timeout(Duration::from_millis(timeout));
+    if let Some(auth) = config.text("step_hooks.auth") {
+        request = request.set("Authorization", &auth);
+    }
+
+    let response: Value = match request.send_json(body) {
+        Ok(response) => response.into_json().unwrap_or(Value::Null),
+        Err(ureq::Error::Status(status, _)) => return Err(format!("response status {}", status)),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    match response["allow"] {
+        Value::Bool(false) => Ok(Some(
+            response["reason"]
+                .as_str()
+                .unwrap_or("no reason given")
+                .to_owned(),
+        )),
+        _ => Ok(None),
+    }
+}
+
+impl BotStepHooks {
+    fn make_body(&self, hook: &str, flow: &str, step: &str, variables: &Value) -> Value {
+        let mut body = json!({
+            "hook": hook,
+            "client": self.client,
+            "conversation_id": self.conversation_id,
+            "flow_id": flow,
+            "step_id": step,
+        });
+        if !self.no_data_retention {
+            body["variables"] = variables.to_owned();
+        }
+
+        body
+    }
+}
+
+impl StepHooks for BotStepHooks {
+    fn before_step(&self, flow: &str, step: &str, variables: &Value) -> Result<(), String> {
+        let body = self.make_body("before_step", flow, step, variables);
+
+        match &self.target {
+            HookTarget::Webhook(url) => match post_hook(url, body) {
+                Ok(Some(reason)) => Err(reason),
+                Ok(None) => Ok(()),
+                Err(err) => {
+                    log_error(&self.client, format!("before_step hook failed: {}", err));
+                    Ok(())
+                }
+            },
+            HookTarget::Events => {
+                publish_event(
+                    STEP_STARTED,
+                    &self.client,
+                    Some(&self.conversation_id),
+                    body,
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn after_step(&self, flow: &str, step: &str, duration: Duration, variables: &Value) {
+        let mut body = self.make_body("after_step", flow, step, variables);
+        body["duration_ms"] = json!(duration.as_millis() as u64);
+
+        match &self.target {
+            HookTarget::Webhook(url) => {
+                if let Err(err) = post_hook(url, body) {
+                    log_error(&self.client, format!("after_step hook failed: {}", err));
+                }
+            }
+            HookTarget::Events => {
+                publish_event(
+                    STEP_COMPLETED,
+                    &self.client,
+                    Some(&self.conversation_id),
+                    body,
+                );
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Run f with the step hooks of the Engine of the application embedding the engine,
+ * for all the bots
+ */
+pub fn with_embedded_hooks<T>(factory: Option<Arc<StepHooksFactory>>, f: impl FnOnce() -> T) -> T {
+    with_scoped(&EMBEDDED_HOOKS, factory, f)
+}
+
+/**
+ * Step hooks of the conversation, when they are enabled for its bot
+ */
+pub fn get_step_hooks(data: &ConversationInfo) -> Option<Arc<dyn StepHooks>> {
+    let embedded = EMBEDDED_HOOKS.with(|hooks| hooks.borrow().clone());
+    if let Some(hooks) = embedded.and_then(|factory| factory(&data.client, &data.conversation_id)) {
+        return Some(hooks);
+    }
+
+    let config = get_config();
+    let target = match config.text_for("step_hooks.type", &data.client.bot_id)?.as_str() {
+        "webhook" => match config.text_for("step_hooks.url", &data.client.bot_id) {
+            Some(url) => HookTarget::Webhook(url),
+            None => {
+                log_error(
+                    &data.client,
+                    "the webhook step hooks require STEP_HOOKS_URL".to_owned(),
+                );
+                return None;
+            }
+        },
+        "events" => HookTarget::Events,
+        target => {
+            log_error(&data.client, format!("unknown step hooks {}", target));
+            return None;
+        }
+    };
+
+    Some(Arc::new(BotStepHooks {
+        target,
+        client: data.client.to_owned(),
+        conversation_id: data.conversation_id.to_owned(),
+        no_data_retention: data.no_data_retention,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn get_hooks(url: String) -> BotStepHooks {
+        BotStepHooks {
+            target: HookTarget::Webhook(url),
+            client: Client::new(
+                "hooks_bot".to_owned(),
+                "channel".to_owned(),
+                "user".to_owned(),
+            ),
+            conversation_id: "conversation".to_owned(),
+            no_data_retention: false,
+        }
+    }
+
+    // answer a single request with the response, return the body of the request
+    fn serve_once(listener: TcpListener, response: &'static str) -> thread::JoinHandle<Value> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        length = value.parse().unwrap();
+                    }
+                    None if line.trim_end().is_empty() => break,
+                    _ => (),
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+
+            serde_json::from_slice::<Value>(&body).unwrap()
+        })
+    }
+
+    #[test]
+    fn ok_webhook_veto() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let hooks = get_hooks(format!("http://{}", listener.local_addr().unwrap()));
+        let server = serve_once(listener, r#"{"allow": false, "reason": "store closed"}"#);
+
+        let result = hooks.before_step("flow", "checkout", &json!({"cart": 3}));
+        assert_eq!(result, Err("store closed".to_owned()));
+
+        let request = server.join().unwrap();
+        assert_eq!(request["hook"], "before_step");
+        assert_eq!(request["client"]["bot_id"], "hooks_bot");
+        assert_eq!(request["conversation_id"], "conversation");
+        assert_eq!(request["step_id"], "checkout");
+        assert_eq!(request["variables"], json!({"cart": 3}));
+    }
+
+    #[test]
+    fn ok_webhook_after_step() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let hooks = get_hooks(format!("http://{}", listener.local_addr().unwrap()));
+        let server = serve_once(listener, "{}");
+
+        hooks.after_step("flow", "start", Duration::from_millis(12), &json!({}));
+
+        let request = server.join().unwrap();
+        assert_eq!(request["hook"], "after_step");
+        assert_eq!(request["flow_id"], "flow");
+        assert_eq!(request["duration_ms"], 12);
+    }
+
+    #[test]
+    fn ok_unavailable_webhook_allows_step() {
+        // nothing listens on the port once the listener is dropped
+        let url = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+
+        assert_eq!(
+            get_hooks(url).before_step("flow", "start", &json!({})),
+            Ok(())
+        );
+    }
+}
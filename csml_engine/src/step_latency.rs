@@ -0,0 +1,267 @@
+/**
+ * Step latency: the interpreter reports when each executed step starts and ends, so that the
+ * slow steps (usually waiting for an HTTP or App call) can be found.
+ *
+ * The timings of the last interaction of a conversation are saved in the client state
+ * (type "steps") with the id of the conversation, and returned by the state of the conversation.
+ * The durations are also added to a histogram of each step of the bot version, saved in the
+ * state of the bot (type "step_latency", one key per version, "draft" for the bots run without
+ * version). The percentiles are read from the histogram: p50 and p95 are the upper bounds of
+ * the buckets of the median and of the 95th percentile.
+ */
+use crate::{
+    data::{Database, EngineError},
+    db_connectors::{state, utils::get_tenant_bot_id},
+    utils::get_bot_client,
+    Client,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+pub const DRAFT_VERSION: &str = "draft";
+// upper bounds of the buckets in milliseconds, the last bucket has the slower steps
+const BUCKETS: [u64; 15] = [
+    1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000,
+];
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_bucket(duration_ms: u64) -> usize {
+    BUCKETS
+        .iter()
+        .position(|bound| duration_ms <= *bound)
+        .unwrap_or(BUCKETS.len())
+}
+
+/**
+ * Upper bound of the bucket of the `percentile` of the step, its max duration for the slowest
+ * bucket
+ */
+fn get_percentile(histogram: &Value, percentile: f64) -> u64 {
+    let count = histogram["count"].as_u64().unwrap_or(0);
+    let max_ms = histogram["max_ms"].as_u64().unwrap_or(0);
+    let rank = ((count as f64) * percentile).ceil().max(1.) as u64;
+
+    let mut seen = 0;
+    for (bucket, bucket_count) in histogram["buckets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        seen += bucket_count.as_u64().unwrap_or(0);
+        if seen >= rank {
+            return match BUCKETS.get(bucket) {
+                Some(bound) => std::cmp::min(*bound, max_ms),
+                None => max_ms,
+            };
+        }
+    }
+
+    max_ms
+}
+
+fn add_to_histograms(histograms: &mut Map<String, Value>, timing: &Value) {
+    let (flow, step) = match (timing["flow"].as_str(), timing["step"].as_str()) {
+        (Some(flow), Some(step)) => (flow, step),
+        _ => return,
+    };
+    let duration_ms = timing["duration_ms"].as_u64().unwrap_or(0);
+
+    let histogram = histograms
+        .entry(format!("{}/{}", flow, step))
+        .or_insert_with(|| {
+            serde_json::json!({
+                "flow": flow,
+                "step": step,
+                "count": 0,
+                "max_ms": 0,
+                "buckets": vec![0; BUCKETS.len() + 1],
+            })
+        });
+
+    histogram["count"] = (histogram["count"].as_u64().unwrap_or(0) + 1).into();
+    let max_ms = histogram["max_ms"].as_u64().unwrap_or(0);
+    histogram["max_ms"] = std::cmp::max(max_ms, duration_ms).into();
+    if let Some(bucket) = histogram["buckets"].get_mut(get_bucket(duration_ms)) {
+        *bucket = (bucket.as_u64().unwrap_or(0) + 1).into();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * A step executed during the interaction, with its start and end times
+ */
+pub fn format_step_timing(
+    flow: &str,
+    step: &str,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+) -> Value {
+    serde_json::json!({
+        "flow": flow,
+        "step": step,
+        "started_at": started_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "ended_at": ended_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "duration_ms": (ended_at - started_at).num_milliseconds().max(0),
+    })
+}
+
+/**
+ * Save the timings of the interaction with the conversation and add them to the latency of
+ * the steps of the bot version
+ */
+pub fn save_step_timings(
+    client: &Client,
+    conversation_id: &str,
+    version_id: Option<&str>,
+    timings: Vec<Value>,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    if timings.is_empty() {
+        return Ok(());
+    }
+
+    let bot_client = get_bot_client(&get_tenant_bot_id(
+        client.tenant_id.as_deref(),
+        &client.bot_id,
+    ));
+    let version_id = version_id.unwrap_or(DRAFT_VERSION);
+
+    let mut histograms = match state::get_state_key(&bot_client, "step_latency", version_id, db)? {
+        Some(Value::Object(histograms)) => histograms,
+        _ => Map::new(),
+    };
+    for timing in timings.iter() {
+        add_to_histograms(&mut histograms, timing);
+    }
+
+    state::replace_state_item(
+        &bot_client,
+        "step_latency",
+        version_id,
+        &Value::Object(histograms),
+        None,
+        db,
+    )?;
+
+    let timings = Value::Array(timings);
+    state::replace_state_item(client, "steps", conversation_id, &timings, ttl, db)
+}
+
+/**
+ * Steps executed during the last interaction of the conversation
+ */
+pub fn get_step_timings(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<Value, EngineError> {
+    match state::get_state_key(client, "steps", conversation_id, db)? {
+        Some(timings) => Ok(timings),
+        None => Ok(Value::Array(vec![])),
+    }
+}
+
+/**
+ * [{"flow", "step", "count", "p50_ms", "p95_ms", "max_ms"}] of the steps executed with the
+ * bot version, slowest first. `bot_id` is the bot id of the tenant for the bots of a tenant.
+ */
+pub fn get_step_latency(
+    bot_id: &str,
+    version_id: &str,
+    db: &mut Database,
+) -> Result<Value, EngineError> {
+    let histograms =
+        match state::get_state_key(&get_bot_client(bot_id), "step_latency", version_id, db)? {
+            Some(Value::Object(histograms)) => histograms,
+            _ => Map::new(),
+        };
+
+    let mut steps: Vec<Value> = histograms
+        .values()
+        .map(|histogram| {
+            serde_json::json!({
+                "flow": histogram["flow"],
+                "step": histogram["step"],
+                "count": histogram["count"],
+                "p50_ms": get_percentile(histogram, 0.5),
+                "p95_ms": get_percentile(histogram, 0.95),
+                "max_ms": histogram["max_ms"],
+            })
+        })
+        .collect();
+    steps.sort_by(|a, b| b["p95_ms"].as_u64().cmp(&a["p95_ms"].as_u64()));
+
+    Ok(Value::Array(steps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+
+    fn get_timing(step: &str, duration_ms: i64) -> Value {
+        let started_at = Utc::now();
+        let ended_at = started_at + chrono::Duration::milliseconds(duration_ms);
+
+        format_step_timing("Default", step, started_at, ended_at)
+    }
+
+    #[test]
+    fn ok_percentiles() {
+        let mut histograms = Map::new();
+        for duration_ms in [3, 4, 4, 8, 40, 40, 45, 90, 200, 1_800] {
+            add_to_histograms(&mut histograms, &get_timing("start", duration_ms));
+        }
+
+        let histogram = &histograms["Default/start"];
+        assert_eq!(histogram["count"], 10);
+        assert_eq!(get_percentile(histogram, 0.5), 50);
+        assert_eq!(get_percentile(histogram, 0.95), 1_800);
+    }
+
+    #[test]
+    fn ok_step_latency() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+
+        let client = Client {
+            bot_id: "bot_step_latency".to_owned(),
+            channel_id: "channel_id".to_owned(),
+            user_id: "user_id".to_owned(),
+            tenant_id: None,
+        };
+        let bot_client = get_bot_client(&client.bot_id);
+        state::delete_state_key(&bot_client, "step_latency", "version", &mut db).unwrap();
+
+        let timings = vec![get_timing("start", 12), get_timing("api_call", 800)];
+        save_step_timings(
+            &client,
+            "conversation",
+            Some("version"),
+            timings,
+            None,
+            &mut db,
+        )
+        .unwrap();
+
+        let steps = get_step_latency(&client.bot_id, "version", &mut db).unwrap();
+        assert_eq!(steps[0]["step"], "api_call");
+        assert_eq!(steps[0]["p95_ms"], 800);
+        assert_eq!(steps[1]["p50_ms"], 12);
+
+        let saved = get_step_timings(&client, "conversation", &mut db).unwrap();
+        assert_eq!(saved[1]["duration_ms"], 800);
+
+        state::delete_state_key(&bot_client, "step_latency", "version", &mut db).unwrap();
+        state::delete_state_key(&client, "steps", "conversation", &mut db).unwrap();
+    }
+}
@@ -0,0 +1,374 @@
+/**
+ * Summaries of the conversations: when a conversation is closed, a summary of its messages is
+ * generated and saved on the conversation, returned with it by GET /conversations
+ * (handover to an agent, analytics...).
+ *
+ * The summaries of a bot are enabled with CONVERSATION_SUMMARY (CONVERSATION_SUMMARY_<BOT_ID>
+ * for a single bot):
+ * - llm: generated with the provider of the Generate() builtin, set in the secrets of the bot
+ *   (llm_provider, llm_url, llm_api_key, llm_model) or in the environment (LLM_PROVIDER...).
+ *   The prompt CONVERSATION_SUMMARY_PROMPT is followed by the transcript of the conversation.
+ * - webhook: {"client": Client, "conversation_id": String, "transcript": String} is POSTed to
+ *   CONVERSATION_SUMMARY_URL (CONVERSATION_SUMMARY_URL_<BOT_ID>), with the optional
+ *   Authorization header CONVERSATION_SUMMARY_AUTH, and answers {"summary": String}.
+ *
+ * The summaries are generated by a background thread: a slow provider never delays the
+ * conversations. When more than QUEUE_SIZE conversations are waiting, the new ones are not
+ * summarized.
+ */
+use crate::{
+    config::get_config,
+    data::{Database, EngineError},
+    db_connectors::{conversations, init_db, messages},
+    events::CONVERSATION_CLOSED,
+    secrets::get_bot_secrets,
+    shutdown::wait_until,
+    Client,
+};
+use csml_interpreter::{data::csml_logs::*, interpreter::builtins::generate};
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, SyncSender},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const QUEUE_SIZE: usize = 1_000;
+const DEFAULT_PROMPT: &str = "Summarize this conversation between a user and a bot \
+    in a few sentences, for the agent taking it over:";
+const DEFAULT_TIMEOUT: u64 = 30_000;
+const MAX_TOKENS: u64 = 512;
+// only the last messages of the longest conversations are summarized
+const MAX_MESSAGES: usize = 200;
+
+// conversations waiting for their summary
+static QUEUED_CONVERSATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn log_error(client: &Client, message: String) {
+    csml_logger(
+        CsmlLog::new(Some(client), None, None, message),
+        LogLvl::Error,
+    );
+}
+
+/**
+ * Text of a message in the transcript: its text, or its content type for the other messages
+ */
+fn get_message_text(payload: &Value) -> String {
+    let content = &payload["content"];
+
+    match (content["text"].as_str(), content["payload"].as_str()) {
+        (Some(text), _) => text.to_owned(),
+        (None, Some(payload)) => payload.to_owned(),
+        (None, None) => format!(
+            "[{}]",
+            payload["content_type"].as_str().unwrap_or("message")
+        ),
+    }
+}
+
+/**
+ * One line per message: "user: ..." for the received messages, "bot: ..." for the sent ones
+ */
+fn get_transcript(messages: &[Value]) -> String {
+    let start = messages.len().saturating_sub(MAX_MESSAGES);
+
+    messages[start..]
+        .iter()
+        .map(|message| {
+            let speaker = match message["direction"].as_str() {
+                Some("RECEIVE") => "user",
+                _ => "bot",
+            };
+
+            format!("{}: {}", speaker, get_message_text(&message["payload"]))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn request_webhook_summary(
+    url: &str,
+    client: &Client,
+    conversation_id: &str,
+    transcript: &str,
+) -> Result<String, String> {
+    let config = get_config();
+    let timeout = config
+        .integer("summaries.timeout")
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    let mut request = ureq::post(url).timeout(Duration::from_millis(timeout));
+    if let Some(auth) = config.text("summaries.auth") {
+        request = request.set("Authorization", &auth);
+    }
+
+    let body = serde_json::json!({
+        "client": client,
+        "conversation_id": conversation_id,
+        "transcript": transcript,
+    });
+
+    let response: Value = match request.send_json(body) {
+        Ok(response) => response.into_json().map_err(|err| err.to_string())?,
+        Err(ureq::Error::Status(status, _)) => return Err(format!("response status {}", status)),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    match response["summary"].as_str() {
+        Some(summary) => Ok(summary.to_owned()),
+        None => Err(format!("no summary in the response {}", response)),
+    }
+}
+
+fn request_summary(
+    provider: &str,
+    client: &Client,
+    conversation_id: &str,
+    transcript: &str,
+) -> Result<String, String> {
+    match provider {
+        "llm" => {
+            let prompt = get_config()
+                .text("summaries.prompt")
+                .unwrap_or_else(|| DEFAULT_PROMPT.to_owned());
+            let secrets = get_bot_secrets(&client.bot_id, "summary");
+
+            generate::complete(
+                &format!("{}\n\n{}", prompt, transcript),
+                MAX_TOKENS,
+                &secrets,
+                &get_config().section_vars(&["llm"]),
+            )
+        }
+        "webhook" => match get_config().text_for("summaries.url", &client.bot_id) {
+            Some(url) => request_webhook_summary(&url, client, conversation_id, transcript),
+            None => Err("the webhook summaries require CONVERSATION_SUMMARY_URL".to_owned()),
+        },
+        provider => Err(format!("unknown summary provider {}", provider)),
+    }
+}
+
+/**
+ * Generate the summary of a closed conversation and save it,
+ * the conversations without messages are not summarized
+ */
+fn summarize_conversation(
+    provider: &str,
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let messages = messages::get_conversation_messages(client, conversation_id, db)?;
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    let summary = request_summary(
+        provider,
+        client,
+        conversation_id,
+        &get_transcript(&messages),
+    )
+    .map_err(|err| EngineError::Manager(format!("summary failed: {}", err)))?;
+
+    conversations::set_conversation_summary(conversation_id, client, summary.trim(), db)
+}
+
+fn start_worker() -> Option<Mutex<SyncSender<(String, Client, String)>>> {
+    let mut db = match init_db() {
+        Ok(db) => db,
+        Err(err) => {
+            csml_logger(
+                CsmlLog::new(None, None, None, format!("summaries disabled: {:?}", err)),
+                LogLvl::Error,
+            );
+            return None;
+        }
+    };
+
+    let (sender, receiver) = mpsc::sync_channel::<(String, Client, String)>(QUEUE_SIZE);
+
+    thread::spawn(move || {
+        for (provider, client, conversation_id) in receiver {
+            if let Err(err) = summarize_conversation(&provider, &client, &conversation_id, &mut db)
+            {
+                log_error(
+                    &client,
+                    format!("conversation {} not summarized: {:?}", conversation_id, err),
+                );
+            }
+
+            QUEUED_CONVERSATIONS.fetch_sub(1, Ordering::SeqCst);
+        }
+    });
+
+    Some(Mutex::new(sender))
+}
+
+static WORKER: Lazy<Option<Mutex<SyncSender<(String, Client, String)>>>> = Lazy::new(start_worker);
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Queue the summary of the conversation of a conversation_closed event,
+ * if the summaries are enabled for its bot
+ */
+pub fn queue_event(event: &Value) {
+    if event["event"] != CONVERSATION_CLOSED {
+        return;
+    }
+
+    let (client, conversation_id) = match (
+        serde_json::from_value::<Client>(event["client"].clone()),
+        event["conversation_id"].as_str(),
+    ) {
+        (Ok(client), Some(conversation_id)) => (client, conversation_id.to_owned()),
+        _ => return,
+    };
+    let provider = match get_config().text_for("summaries.provider", &client.bot_id) {
+        Some(provider) => provider,
+        None => return,
+    };
+
+    if let Some(sender) = &*WORKER {
+        QUEUED_CONVERSATIONS.fetch_add(1, Ordering::SeqCst);
+
+        let queued =
+            sender
+                .lock()
+                .unwrap()
+                .try_send((provider, client.to_owned(), conversation_id));
+        if queued.is_err() {
+            QUEUED_CONVERSATIONS.fetch_sub(1, Ordering::SeqCst);
+            log_error(
+                &client,
+                "summaries queue full: conversation not summarized".to_owned(),
+            );
+        }
+    }
+}
+
+/**
+ * Wait until the queued conversations are summarized or the deadline,
+ * return false if some of them are still waiting
+ */
+pub fn flush(deadline: Instant) -> bool {
+    let flushed = wait_until(deadline, || {
+        QUEUED_CONVERSATIONS.load(Ordering::SeqCst) == 0
+    });
+
+    if !flushed {
+        csml_logger(
+            CsmlLog::new(
+                None,
+                None,
+                None,
+                format!(
+                    "{} conversations not summarized before the shutdown",
+                    QUEUED_CONVERSATIONS.load(Ordering::SeqCst)
+                ),
+            ),
+            LogLvl::Error,
+        );
+    }
+
+    flushed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    fn get_client() -> Client {
+        Client::new(
+            "summary_bot".to_owned(),
+            "channel".to_owned(),
+            "user".to_owned(),
+        )
+    }
+
+    #[test]
+    fn ok_transcript() {
+        let messages = vec![
+            serde_json::json!({
+                "direction": "RECEIVE",
+                "payload": {"content_type": "text", "content": {"text": "I lost my card"}},
+            }),
+            serde_json::json!({
+                "direction": "SEND",
+                "payload": {"content_type": "question", "content": {"title": "Block it?"}},
+            }),
+            serde_json::json!({
+                "direction": "RECEIVE",
+                "payload": {"content_type": "payload", "content": {"payload": "YES"}},
+            }),
+        ];
+
+        assert_eq!(
+            get_transcript(&messages),
+            "user: I lost my card\nbot: [question]\nuser: YES"
+        );
+    }
+
+    #[test]
+    fn ok_webhook_summary() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        length = value.parse().unwrap();
+                    }
+                    None if line.trim_end().is_empty() => break,
+                    _ => (),
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = r#"{"summary": " The user lost their card. "}"#;
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+
+            serde_json::from_slice::<Value>(&body).unwrap()
+        });
+
+        let summary =
+            request_webhook_summary(&url, &get_client(), "conversation", "user: I lost my card")
+                .unwrap();
+        assert_eq!(summary, " The user lost their card. ");
+
+        let request = server.join().unwrap();
+        assert_eq!(request["conversation_id"], "conversation");
+        assert_eq!(request["client"]["bot_id"], "summary_bot");
+        assert_eq!(request["transcript"], "user: I lost my card");
+    }
+}
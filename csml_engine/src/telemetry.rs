@@ -0,0 +1,535 @@
+/**
+ * Traces and error reports for the observability stacks, compiled with the telemetry feature:
+ * - the runs (start_conversation), their database calls and their Fn and App calls are exported
+ *   as OpenTelemetry spans by opentelemetry-otlp (OTLP/HTTP with protobuf encoding) to
+ *   OTEL_EXPORTER_OTLP_TRACES_ENDPOINT, or OTEL_EXPORTER_OTLP_ENDPOINT/v1/traces, with the headers
+ *   OTEL_EXPORTER_OTLP_HEADERS (key1=value1,key2=value2) and the service name OTEL_SERVICE_NAME
+ *   (default csml_engine)
+ * - the runs failing on an unexpected error (database, interpreter or internal error) are
+ *   reported by the sentry client with SENTRY_DSN, with the environment SENTRY_ENVIRONMENT and
+ *   the release SENTRY_RELEASE. The report holds the trace of the run.
+ *
+ * The database and Fn calls of a run are the children of the span of the run, which is the
+ * span in progress on its thread. The spans are exported by batches from the background thread
+ * of the batch span processor (see the OTEL_BSP_* variables), and the reports from the thread of
+ * the sentry transport: a slow collector never delays the conversations.
+ *
+ * Without the telemetry feature, or when neither exporter is configured, nothing is recorded.
+ */
+use crate::{config::get_config, data::EngineError, Client};
+
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "telemetry")]
+use csml_interpreter::data::csml_logs::*;
+#[cfg(feature = "telemetry")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "telemetry")]
+use opentelemetry::{
+    trace::{Span as _, Status, TraceContextExt, Tracer, TracerProvider as _},
+    Context, ContextGuard, KeyValue,
+};
+#[cfg(feature = "telemetry")]
+use opentelemetry_sdk::{
+    trace::{SdkTracer, SdkTracerProvider},
+    Resource,
+};
+#[cfg(feature = "telemetry")]
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+#[cfg(feature = "telemetry")]
+use std::{collections::HashMap, time::SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+    Server,
+    Client,
+}
+
+#[cfg(feature = "telemetry")]
+impl From<SpanKind> for opentelemetry::trace::SpanKind {
+    fn from(kind: SpanKind) -> Self {
+        match kind {
+            SpanKind::Server => opentelemetry::trace::SpanKind::Server,
+            SpanKind::Client => opentelemetry::trace::SpanKind::Client,
+        }
+    }
+}
+
+#[cfg(feature = "telemetry")]
+struct CurrentSpan {
+    name: String,
+    context: Context,
+    // the span is the span in progress on this thread until this guard is dropped
+    _guard: ContextGuard,
+}
+
+/**
+ * Span in progress, exported when it is dropped
+ */
+pub struct Span {
+    #[cfg(feature = "telemetry")]
+    current: Option<CurrentSpan>,
+}
+
+#[cfg_attr(not(feature = "telemetry"), allow(unused_variables))]
+impl Span {
+    fn disabled() -> Self {
+        Span {
+            #[cfg(feature = "telemetry")]
+            current: None,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        #[cfg(feature = "telemetry")]
+        return self.current.is_some();
+        #[cfg(not(feature = "telemetry"))]
+        return false;
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: &str) {
+        #[cfg(feature = "telemetry")]
+        if let Some(current) = &self.current {
+            current
+                .context
+                .span()
+                .set_attribute(KeyValue::new(key.to_owned(), value.to_owned()));
+        }
+    }
+
+    pub fn set_error(&mut self, message: &str) {
+        #[cfg(feature = "telemetry")]
+        if let Some(current) = &self.current {
+            current
+                .context
+                .span()
+                .set_status(Status::error(message.to_owned()));
+        }
+    }
+}
+
+#[cfg(feature = "telemetry")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(current) = self.current.take() {
+            current.context.span().end();
+        }
+    }
+}
+
+/**
+ * Start a span, the span in progress on this thread until it is dropped
+ */
+#[cfg_attr(not(feature = "telemetry"), allow(unused_variables))]
+pub fn start_span(name: &str, kind: SpanKind) -> Span {
+    #[cfg(feature = "telemetry")]
+    if let Some(tracing) = &*TRACING {
+        return start_tracer_span(&tracing.tracer, name, kind);
+    }
+
+    Span::disabled()
+}
+
+/**
+ * Span of a database call of the run in progress
+ */
+pub fn db_span(operation: &str) -> Span {
+    let mut span = start_span(&format!("db {}", operation), SpanKind::Client);
+    if span.is_recording() {
+        let system = get_config().text("database.type").unwrap_or_default();
+        span.set_attribute("db.system", &system);
+        span.set_attribute("db.operation", operation);
+    }
+
+    span
+}
+
+/**
+ * Export a Fn or App call of the run in progress, reported once it was answered
+ */
+#[cfg_attr(not(feature = "telemetry"), allow(unused_variables))]
+pub fn record_app_call(
+    fn_id: &str,
+    flow: &str,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    error: Option<&str>,
+) {
+    #[cfg(feature = "telemetry")]
+    if let Some(tracing) = &*TRACING {
+        record_tracer_app_call(&tracing.tracer, fn_id, flow, started_at, ended_at, error);
+    }
+}
+
+/**
+ * Span of a run, with the client and the request id of the run
+ */
+pub fn start_run_span(client: &Client, request_id: Option<&str>) -> Span {
+    let mut span = start_span("csml run", SpanKind::Server);
+    if span.is_recording() {
+        span.set_attribute("csml.bot_id", &client.bot_id);
+        span.set_attribute("csml.channel_id", &client.channel_id);
+        if let Some(request_id) = request_id {
+            span.set_attribute("csml.request_id", request_id);
+        }
+    }
+
+    span
+}
+
+/**
+ * Mark the span of a failed run, and report the unexpected errors to Sentry
+ */
+#[cfg_attr(not(feature = "telemetry"), allow(unused_variables))]
+pub fn capture_error(
+    span: &mut Span,
+    error: &EngineError,
+    client: &Client,
+    request_id: Option<&str>,
+) {
+    span.set_error(&error.to_string());
+
+    if !matches!(
+        error.code(),
+        "db_error" | "interpreter_error" | "internal_error"
+    ) {
+        return;
+    }
+
+    #[cfg(feature = "telemetry")]
+    if let Some(sentry) = &*SENTRY {
+        sentry.capture_event(get_error_event(span, error, client, request_id), None);
+    }
+}
+
+/**
+ * Wait until the spans and reports are exported or the deadline, return false if some
+ * of them are still waiting
+ */
+#[cfg(not(feature = "telemetry"))]
+pub fn flush(_deadline: std::time::Instant) -> bool {
+    true
+}
+
+/**
+ * Wait until the spans and reports are exported or the deadline, return false if some
+ * of them are still waiting
+ */
+#[cfg(feature = "telemetry")]
+pub fn flush(deadline: std::time::Instant) -> bool {
+    let mut flushed = true;
+
+    if let Some(tracing) = &*TRACING {
+        if let Err(err) = tracing.provider.force_flush() {
+            log_error(format!("spans not exported before the shutdown: {}", err));
+            flushed = false;
+        }
+    }
+    if let Some(sentry) = &*SENTRY {
+        let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+        if !sentry.flush(Some(timeout)) {
+            log_error("error reports not sent before the shutdown".to_owned());
+            flushed = false;
+        }
+    }
+
+    flushed
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// EXPORTERS
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "telemetry")]
+struct Tracing {
+    provider: SdkTracerProvider,
+    tracer: SdkTracer,
+}
+
+#[cfg(feature = "telemetry")]
+fn log_error(message: String) {
+    csml_logger(CsmlLog::new(None, None, None, message), LogLvl::Error);
+}
+
+/**
+ * Tracer of the OTLP exporter, None when no endpoint is configured
+ */
+#[cfg(feature = "telemetry")]
+fn init_tracing() -> Option<Tracing> {
+    let config = get_config();
+    let endpoint = match config.text("telemetry.otlp_traces_endpoint") {
+        Some(endpoint) => endpoint,
+        None => format!(
+            "{}/v1/traces",
+            config.text("telemetry.otlp_endpoint")?.trim_end_matches('/')
+        ),
+    };
+    // key1=value1,key2=value2
+    let headers: HashMap<String, String> = config
+        .text("telemetry.otlp_headers")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|header| header.split_once('='))
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+
+    let exporter = match opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .with_headers(headers)
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(err) => {
+            log_error(format!("invalid OTLP exporter, spans not exported: {}", err));
+            return None;
+        }
+    };
+
+    let service_name = config
+        .text("telemetry.service_name")
+        .unwrap_or_else(|| "csml_engine".to_owned());
+    let resource = Resource::builder().with_service_name(service_name).build();
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build();
+    let tracer = provider.tracer("csml_engine");
+
+    Some(Tracing { provider, tracer })
+}
+
+/**
+ * Sentry client of SENTRY_DSN, None when it is not set
+ */
+#[cfg(feature = "telemetry")]
+fn init_sentry() -> Option<sentry::Client> {
+    let config = get_config();
+    let dsn = config.text("telemetry.sentry_dsn")?;
+
+    let dsn = match dsn.parse() {
+        Ok(dsn) => dsn,
+        Err(_) => {
+            log_error(format!("invalid SENTRY_DSN {}: errors not reported", dsn));
+            return None;
+        }
+    };
+    let options = sentry::apply_defaults(sentry::ClientOptions {
+        dsn: Some(dsn),
+        environment: config.text("telemetry.sentry_environment").map(Into::into),
+        release: config.text("telemetry.sentry_release").map(Into::into),
+        ..Default::default()
+    });
+
+    Some(sentry::Client::with_options(options))
+}
+
+#[cfg(feature = "telemetry")]
+static TRACING: Lazy<Option<Tracing>> = Lazy::new(init_tracing);
+
+#[cfg(feature = "telemetry")]
+static SENTRY: Lazy<Option<sentry::Client>> = Lazy::new(init_sentry);
+
+#[cfg(feature = "telemetry")]
+fn start_tracer_span(tracer: &SdkTracer, name: &str, kind: SpanKind) -> Span {
+    let span = tracer
+        .span_builder(name.to_owned())
+        .with_kind(kind.into())
+        .start(tracer);
+    let context = Context::current_with_span(span);
+
+    Span {
+        current: Some(CurrentSpan {
+            name: name.to_owned(),
+            _guard: context.clone().attach(),
+            context,
+        }),
+    }
+}
+
+#[cfg(feature = "telemetry")]
+fn record_tracer_app_call(
+    tracer: &SdkTracer,
+    fn_id: &str,
+    flow: &str,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    error: Option<&str>,
+) {
+    let mut span = tracer
+        .span_builder(format!("fn {}", fn_id))
+        .with_kind(opentelemetry::trace::SpanKind::Client)
+        .with_start_time(SystemTime::from(started_at))
+        .with_attributes(vec![
+            KeyValue::new("csml.fn_id", fn_id.to_owned()),
+            KeyValue::new("csml.flow", flow.to_owned()),
+        ])
+        .start(tracer);
+    if let Some(error) = error {
+        span.set_status(Status::error(error.to_owned()));
+    }
+
+    span.end_with_timestamp(SystemTime::from(ended_at));
+}
+
+/**
+ * Sentry event of an error of a run, in the trace of the span of the run
+ */
+#[cfg(feature = "telemetry")]
+fn get_error_event(
+    span: &Span,
+    error: &EngineError,
+    client: &Client,
+    request_id: Option<&str>,
+) -> sentry::protocol::Event<'static> {
+    use sentry::protocol::{Event, Exception, Level, TraceContext};
+
+    let mut event = Event {
+        level: Level::Error,
+        logger: Some("csml_engine".to_owned()),
+        exception: vec![Exception {
+            ty: error.code().to_owned(),
+            value: Some(error.to_string()),
+            ..Default::default()
+        }]
+        .into(),
+        ..Default::default()
+    };
+
+    event.tags.insert("bot_id".to_owned(), client.bot_id.to_owned());
+    event
+        .tags
+        .insert("channel_id".to_owned(), client.channel_id.to_owned());
+    event
+        .tags
+        .insert("error_code".to_owned(), error.code().to_owned());
+    if let Some(request_id) = request_id {
+        event
+            .tags
+            .insert("request_id".to_owned(), request_id.to_owned());
+    }
+
+    if let Some(current) = &span.current {
+        let span_context = current.context.span().span_context().clone();
+        let trace = TraceContext {
+            trace_id: span_context.trace_id().to_bytes().into(),
+            span_id: span_context.span_id().to_bytes().into(),
+            op: Some(current.name.to_owned()),
+            ..Default::default()
+        };
+        event.contexts.insert("trace".to_owned(), trace.into());
+    }
+
+    event
+}
+
+#[cfg(all(test, feature = "telemetry"))]
+mod tests {
+    use super::*;
+    use opentelemetry_sdk::{
+        error::OTelSdkResult,
+        trace::{SpanData, SpanExporter},
+    };
+    use sentry::{protocol::Context as SentryContext, Envelope, Transport};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, Default)]
+    struct CollectedSpans(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for CollectedSpans {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            self.0.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct CollectedEnvelopes(Mutex<Vec<Envelope>>);
+
+    impl Transport for CollectedEnvelopes {
+        fn send_envelope(&self, envelope: Envelope) {
+            self.0.lock().unwrap().push(envelope);
+        }
+    }
+
+    fn get_client() -> Client {
+        Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        )
+    }
+
+    #[test]
+    fn ok_run_spans() {
+        let spans = CollectedSpans::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(spans.clone())
+            .build();
+        let tracer = provider.tracer("csml_engine");
+
+        let started_at = Utc::now();
+        {
+            let mut run = start_tracer_span(&tracer, "csml run", SpanKind::Server);
+            run.set_attribute("csml.bot_id", "bot_id");
+            {
+                let mut db = start_tracer_span(&tracer, "db get_latest_open", SpanKind::Client);
+                db.set_error("database error");
+            }
+            record_tracer_app_call(&tracer, "fn_id", "Default", started_at, Utc::now(), None);
+        }
+
+        let spans = spans.0.lock().unwrap();
+        assert_eq!(spans.len(), 3);
+        let run = spans.iter().find(|span| span.name == "csml run").unwrap();
+        assert_eq!(run.span_kind, opentelemetry::trace::SpanKind::Server);
+        assert_eq!(run.attributes[0], KeyValue::new("csml.bot_id", "bot_id"));
+
+        // the database and Fn calls are the children of the span of the run
+        for span in spans.iter().filter(|span| span.name != "csml run") {
+            assert_eq!(span.span_context.trace_id(), run.span_context.trace_id());
+            assert_eq!(span.parent_span_id, run.span_context.span_id());
+        }
+        let db = spans
+            .iter()
+            .find(|span| span.name == "db get_latest_open")
+            .unwrap();
+        assert_eq!(db.status, Status::error("database error"));
+        let app_call = spans.iter().find(|span| span.name == "fn fn_id").unwrap();
+        assert_eq!(app_call.start_time, SystemTime::from(started_at));
+    }
+
+    #[test]
+    fn ok_error_event() {
+        let provider = SdkTracerProvider::builder().build();
+        let tracer = provider.tracer("csml_engine");
+        let transport = Arc::new(CollectedEnvelopes::default());
+        let sentry = sentry::Client::with_options(sentry::ClientOptions {
+            dsn: Some("https://public@sentry.example.com/1".parse().unwrap()),
+            transport: Some(Arc::new(transport.clone())),
+            ..Default::default()
+        });
+
+        let span = start_tracer_span(&tracer, "csml run", SpanKind::Server);
+        let error = EngineError::Manager("unexpected".to_owned());
+        sentry.capture_event(
+            get_error_event(&span, &error, &get_client(), Some("request_id")),
+            None,
+        );
+        sentry.flush(None);
+
+        let envelopes = transport.0.lock().unwrap();
+        let event = envelopes[0].event().unwrap();
+        assert_eq!(event.exception[0].ty, "internal_error");
+        assert_eq!(event.tags["bot_id"], "bot_id");
+        assert_eq!(event.tags["request_id"], "request_id");
+        match &event.contexts["trace"] {
+            SentryContext::Trace(trace) => {
+                let span_context = span.current.as_ref().unwrap().context.span().span_context().clone();
+                assert_eq!(trace.trace_id, span_context.trace_id().to_bytes().into());
+                assert_eq!(trace.op.as_deref(), Some("csml run"));
+            }
+            _ => panic!("expect the trace of the run"),
+        }
+    }
+}
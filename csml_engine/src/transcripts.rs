@@ -0,0 +1,399 @@
+/**
+ * Transcripts of the conversations: the messages of a conversation rendered as a human-readable
+ * document, to send by email or to keep for audits.
+ *
+ * Each message is a line with its time, its speaker (user or bot) and its text. The components
+ * are flattened to a text description, ex: a question is its title followed by its buttons,
+ * an image is [image: url]. The typing and wait messages are left out, and the secure events
+ * (which payload is not saved) are shown as [secure message].
+ *
+ * The messages of the archived conversations are read from the object storage.
+ */
+use crate::{
+    archive,
+    data::{Database, EngineError},
+    db_connectors::messages,
+    Client,
+};
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Html,
+    Markdown,
+    #[default]
+    Txt,
+}
+
+impl TranscriptFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Html => "text/html; charset=utf-8",
+            TranscriptFormat::Markdown => "text/markdown; charset=utf-8",
+            TranscriptFormat::Txt => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+struct TranscriptLine {
+    time: String,
+    speaker: &'static str,
+    text: String,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_str<'a>(content: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .filter_map(|key| content[*key].as_str())
+        .find(|value| !value.is_empty())
+}
+
+/**
+ * " (buttons: Yes | No)", or nothing without buttons
+ */
+fn describe_buttons(content: &Value) -> String {
+    let titles: Vec<&str> = match content["buttons"].as_array() {
+        Some(buttons) => buttons
+            .iter()
+            .filter_map(|button| {
+                get_str(&button["content"], &["title", "payload"])
+                    .or_else(|| get_str(button, &["title", "payload"]))
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    match titles.is_empty() {
+        true => String::new(),
+        false => format!(" (buttons: {})", titles.join(" | ")),
+    }
+}
+
+/**
+ * Text description of a message, None for the messages without content for the reader
+ */
+fn describe_message(payload: &Value) -> Option<String> {
+    let content_type = payload["content_type"].as_str().unwrap_or("message");
+    let content = &payload["content"];
+
+    let text = match content_type {
+        "typing" | "wait" => return None,
+        "secure" => "[secure message]".to_owned(),
+        "question" | "card" => format!(
+            "{}{}",
+            get_str(content, &["title", "text"]).unwrap_or_default(),
+            describe_buttons(content)
+        ),
+        "button" => format!(
+            "[button: {}]",
+            get_str(content, &["title", "payload"]).unwrap_or_default()
+        ),
+        "carousel" => {
+            let cards: Vec<String> = content["cards"]
+                .as_array()
+                .map(|cards| cards.iter().filter_map(describe_message).collect())
+                .unwrap_or_default();
+
+            format!("[carousel: {}]", cards.join("; "))
+        }
+        "image" | "audio" | "video" | "file" => format!(
+            "[{}: {}]",
+            content_type,
+            get_str(content, &["url"]).unwrap_or_default()
+        ),
+        "url" => match (
+            get_str(content, &["title", "text"]),
+            get_str(content, &["url"]),
+        ) {
+            (Some(title), Some(url)) if title != url => format!("[link: {} ({})]", title, url),
+            (_, url) => format!("[link: {}]", url.unwrap_or_default()),
+        },
+        "message_edited" => format!(
+            "[edited message {}] {}",
+            get_str(content, &["reference_id"]).unwrap_or_default(),
+            get_str(content, &["text"]).unwrap_or_default()
+        ),
+        "reaction" => format!(
+            "[reaction to message {}: {}]",
+            get_str(content, &["reference_id"]).unwrap_or_default(),
+            get_str(content, &["reaction"]).unwrap_or_default()
+        ),
+        "payment_request" => format!(
+            "[payment request: {} {}]",
+            content["amount"],
+            get_str(content, &["currency"]).unwrap_or_default()
+        ),
+        _ => match get_str(content, &["text", "title", "payload"]) {
+            Some(text) => text.to_owned(),
+            None => format!("[{}]", content_type),
+        },
+    };
+
+    Some(text)
+}
+
+/**
+ * "2021-06-01T10:00:00.000Z" is displayed "2021-06-01 10:00:00"
+ */
+fn format_time(created_at: &Value) -> String {
+    match created_at.as_str() {
+        Some(date) => match DateTime::parse_from_rfc3339(date) {
+            Ok(date) => date.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Err(_) => date.to_owned(),
+        },
+        None => String::new(),
+    }
+}
+
+fn get_lines(messages: &[Value]) -> Vec<TranscriptLine> {
+    messages
+        .iter()
+        .filter_map(|message| {
+            let speaker = match message["direction"].as_str() {
+                Some("RECEIVE") => "user",
+                _ => "bot",
+            };
+
+            describe_message(&message["payload"]).map(|text| TranscriptLine {
+                time: format_time(&message["created_at"]),
+                speaker,
+                text,
+            })
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/**
+ * The markdown characters of the texts are escaped, the lines of a message stay in its quote
+ */
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("  \n> "),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn render(conversation_id: &str, lines: &[TranscriptLine], format: TranscriptFormat) -> String {
+    let title = format!("Transcript of the conversation {}", conversation_id);
+
+    match format {
+        TranscriptFormat::Txt => {
+            let mut transcript = format!("{}\n\n", title);
+            for line in lines {
+                transcript.push_str(&format!(
+                    "[{}] {}: {}\n",
+                    line.time, line.speaker, line.text
+                ));
+            }
+            transcript
+        }
+        TranscriptFormat::Markdown => {
+            let mut transcript = format!("# {}\n", title);
+            for line in lines {
+                transcript.push_str(&format!(
+                    "\n**{}** _{}_\n> {}\n",
+                    line.speaker,
+                    line.time,
+                    escape_markdown(&line.text)
+                ));
+            }
+            transcript
+        }
+        TranscriptFormat::Html => {
+            let mut transcript = format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+                 </head>\n<body>\n<h1>{0}</h1>\n",
+                escape_html(&title)
+            );
+            for line in lines {
+                transcript.push_str(&format!(
+                    "<p class=\"{0}\"><time>{1}</time> <strong>{0}</strong>: {2}</p>\n",
+                    line.speaker,
+                    escape_html(&line.time),
+                    escape_html(&line.text).replace('\n', "<br>")
+                ));
+            }
+            transcript.push_str("</body>\n</html>\n");
+            transcript
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Transcript of a conversation of the client, None if it has no message
+ */
+pub fn get_conversation_transcript(
+    client: &Client,
+    conversation_id: &str,
+    format: TranscriptFormat,
+    db: &mut Database,
+) -> Result<Option<String>, EngineError> {
+    let mut messages = messages::get_conversation_messages(client, conversation_id, db)?;
+
+    if messages.is_empty() && archive::is_enabled() {
+        if let Some(archive) = archive::get_archived_conversation(client, conversation_id)? {
+            messages = archive["messages"].as_array().cloned().unwrap_or_default();
+        }
+    }
+
+    if messages.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(render(conversation_id, &get_lines(&messages), format)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_messages() -> Vec<Value> {
+        vec![
+            json!({
+                "direction": "RECEIVE",
+                "created_at": "2021-06-01T10:00:00.000Z",
+                "payload": {"content_type": "text", "content": {"text": "I lost my <card>"}},
+            }),
+            json!({
+                "direction": "SEND",
+                "created_at": "2021-06-01T10:00:01.000Z",
+                "payload": {"content_type": "typing", "content": {"duration": 1000}},
+            }),
+            json!({
+                "direction": "SEND",
+                "created_at": "2021-06-01T10:00:01.000Z",
+                "payload": {
+                    "content_type": "question",
+                    "content": {
+                        "title": "Block it?",
+                        "buttons": [
+                            {"content_type": "button", "content": {"title": "Yes", "payload": "YES"}},
+                            {"content_type": "button", "content": {"title": "No", "payload": "NO"}},
+                        ],
+                    },
+                },
+            }),
+            json!({
+                "direction": "SEND",
+                "created_at": "2021-06-01T10:00:02.000Z",
+                "payload": {"content_type": "image", "content": {"url": "https://a.b/c.png"}},
+            }),
+        ]
+    }
+
+    #[test]
+    fn ok_txt_transcript() {
+        let transcript = render("conv", &get_lines(&get_messages()), TranscriptFormat::Txt);
+
+        assert_eq!(
+            transcript,
+            "Transcript of the conversation conv\n\n\
+             [2021-06-01 10:00:00] user: I lost my <card>\n\
+             [2021-06-01 10:00:01] bot: Block it? (buttons: Yes | No)\n\
+             [2021-06-01 10:00:02] bot: [image: https://a.b/c.png]\n"
+        );
+    }
+
+    #[test]
+    fn ok_html_transcript_escaped() {
+        let transcript = render("conv", &get_lines(&get_messages()), TranscriptFormat::Html);
+
+        assert!(transcript.starts_with("<!DOCTYPE html>"));
+        assert!(transcript.contains(
+            "<p class=\"user\"><time>2021-06-01 10:00:00</time> <strong>user</strong>: \
+             I lost my &lt;card&gt;</p>"
+        ));
+        assert!(!transcript.contains("<card>"));
+    }
+
+    #[test]
+    fn ok_markdown_transcript() {
+        let transcript = render(
+            "conv",
+            &get_lines(&get_messages()),
+            TranscriptFormat::Markdown,
+        );
+
+        assert!(transcript.starts_with("# Transcript of the conversation conv\n"));
+        assert!(transcript.contains("\n**user** _2021-06-01 10:00:00_\n> I lost my \\<card\\>\n"));
+        assert!(transcript.contains("> \\[image: https://a.b/c.png\\]\n"));
+    }
+
+    #[test]
+    fn ok_reference_descriptions() {
+        let edited = json!({
+            "content_type": "message_edited",
+            "content": {"reference_id": "m1", "text": "my new address"},
+        });
+        let reaction = json!({
+            "content_type": "reaction",
+            "content": {"reference_id": "m2", "reaction": "+1"},
+        });
+
+        assert_eq!(
+            describe_message(&edited),
+            Some("[edited message m1] my new address".to_owned())
+        );
+        assert_eq!(
+            describe_message(&reaction),
+            Some("[reaction to message m2: +1]".to_owned())
+        );
+    }
+
+    #[test]
+    fn ok_carousel_description() {
+        let payload = json!({
+            "content_type": "carousel",
+            "content": {"cards": [
+                {"content_type": "card", "content": {"title": "Gold"}},
+                {"content_type": "card", "content": {"title": "Silver", "buttons": [
+                    {"content_type": "button", "content": {"title": "Pick"}},
+                ]}},
+            ]},
+        });
+
+        assert_eq!(
+            describe_message(&payload),
+            Some("[carousel: Gold; Silver (buttons: Pick)]".to_owned())
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    config::get_config,
     data::{ConversationInfo, CsmlRequest, Database, EngineError, FlowTrigger},
     db_connectors::state::delete_state_key,
     send::send_to_callback_url,
@@ -11,7 +12,8 @@ use csml_interpreter::{
         ast::{Flow, InsertStep, InstructionScope},
         context::ContextStepInfo,
         csml_logs::*,
-        Client, Context, Event, Interval, Memory, Message,
+        event::get_reference_id,
+        Client, Context, Event, EventType, Interval, Memory, Message,
     },
     error_format::{ERROR_KEY_ALPHANUMERIC, ERROR_NUMBER_AS_KEY, ERROR_SIZE_IDENT},
     get_step,
@@ -20,7 +22,6 @@ use csml_interpreter::{
 use rand::seq::SliceRandom;
 use serde_json::{json, map::Map, Value};
 use std::collections::HashMap;
-use std::env;
 
 use md5::{Digest, Md5};
 use regex::Regex;
@@ -39,6 +40,14 @@ pub fn update_current_context(data: &mut ConversationInfo, memories: &HashMap<St
     }
 }
 
+/**
+ * Settings shared by all the users of a bot are saved in the state table
+ * with a client that is not bound to any channel or user.
+ */
+pub fn get_bot_client(bot_id: &str) -> Client {
+    Client::new(bot_id.to_owned(), String::new(), String::new())
+}
+
 /**
  * Check if memory key is valid
  */
@@ -63,56 +72,11 @@ pub fn validate_memory_key_format(key: &str) -> Result<(), EngineError> {
  * This will trim extra data and only keep the main value.
  */
 pub fn get_event_content(content_type: &str, metadata: &Value) -> Result<String, EngineError> {
-    match content_type {
-        file if ["file", "audio", "video", "image", "url"].contains(&file) => {
-            if let Some(val) = metadata["url"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "no url content in event".to_owned(),
-                ))
-            }
-        }
-        payload if payload == "payload" => {
-            if let Some(val) = metadata["payload"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "no payload content in event".to_owned(),
-                ))
-            }
-        }
-        text if text == "text" => {
-            if let Some(val) = metadata["text"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "no text content in event".to_owned(),
-                ))
-            }
-        }
-        regex if regex == "regex" => {
-            if let Some(val) = metadata["payload"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "invalid payload for event type regex".to_owned(),
-                ))
-            }
-        }
-        flow_trigger if flow_trigger == "flow_trigger" => {
-            match serde_json::from_value::<FlowTrigger>(metadata.clone()) {
-                Ok(_flow_trigger) => {
-                    Ok(metadata.to_string())
-                }
-                Err(_) => {
-                    Err(EngineError::Interpreter(
-                        "invalid content for event type flow_trigger: expect flow_id and optional step_id".to_owned(),
-                    ))
-                }
-            }
-        }
-        content_type => Err(EngineError::Interpreter(format!(
+    match EventType::from_content_type(content_type) {
+        Some(event_type) => event_type
+            .get_content_value(metadata)
+            .map_err(EngineError::Interpreter),
+        None => Err(EngineError::Interpreter(format!(
             "{} is not a valid content_type",
             content_type
         ))),
@@ -137,6 +101,7 @@ pub fn format_event(request: &CsmlRequest) -> Result<Event, EngineError> {
     let content = json_event["payload"]["content"].to_owned();
 
     let content_value = get_event_content(&content_type, &content)?;
+    let reference_id = get_reference_id(&content);
 
     Ok(Event {
         content_type,
@@ -146,6 +111,13 @@ pub fn format_event(request: &CsmlRequest) -> Result<Event, EngineError> {
         low_data_mode: json_event["low_data_mode"].as_bool(),
         step_limit,
         secure: json_event["payload"]["secure"].as_bool().unwrap_or(false),
+        locale: json_event["payload"]["locale"]
+            .as_str()
+            .map(|locale| locale.to_owned()),
+        // only set by the enrichers of the engine
+        enrichment: json!({}),
+        reference_id,
+        settings: get_config().section_vars(&["limits", "llm"]),
     })
 }
 
@@ -159,6 +131,10 @@ pub fn send_msg_to_callback_url(
     interaction_order: i32,
     end: bool,
 ) {
+    let delay = match data.callback_url {
+        Some(_) => get_messages_delay(&msg),
+        None => 0,
+    };
     let messages = messages_formatter(data, msg, interaction_order, end);
 
     csml_logger(
@@ -171,7 +147,29 @@ pub fn send_msg_to_callback_url(
         LogLvl::Debug,
     );
 
-    send_to_callback_url(data, serde_json::json!(messages))
+    send_to_callback_url(data, serde_json::json!(messages));
+
+    // the next messages are delivered after the Wait/Typing duration
+    if delay > 0 {
+        std::thread::sleep(std::time::Duration::from_millis(delay));
+    }
+}
+
+/**
+ * Total duration in milliseconds of the wait and typing messages, capped by
+ * CALLBACK_MAX_DELAY (default 10000) for each message
+ */
+fn get_messages_delay(messages: &[Message]) -> u64 {
+    let max_delay = get_config()
+        .integer("conversations.callback_max_delay")
+        .unwrap_or(10000);
+
+    messages
+        .iter()
+        .filter(|msg| msg.content_type == "wait" || msg.content_type == "typing")
+        .filter_map(|msg| msg.content["duration"].as_f64())
+        .map(|duration| std::cmp::min(duration.max(0.) as u64, max_delay))
+        .sum()
 }
 
 /**
@@ -273,11 +271,24 @@ pub fn search_flow<'a>(
     bot: &'a CsmlBot,
     client: &Client,
     db: &mut Database,
+) -> Result<(&'a CsmlFlow, String), EngineError> {
+    let flow_found = match_flow(event, bot)?;
+
+    // the user is requesting a flow: the hold position is reset
+    delete_state_key(&client, "hold", "position", db)?;
+
+    Ok(flow_found)
+}
+
+/**
+ * Match a flow of the bot with the user's input, without side effects
+ */
+pub fn match_flow<'a>(
+    event: &Event,
+    bot: &'a CsmlBot,
 ) -> Result<(&'a CsmlFlow, String), EngineError> {
     match event {
         event if event.content_type == "flow_trigger" => {
-            delete_state_key(&client, "hold", "position", db)?;
-
             let flow_trigger: FlowTrigger = serde_json::from_str(&event.content_value)?;
 
             match get_flow_by_id(&flow_trigger.flow_id, &bot.flows) {
@@ -309,10 +320,7 @@ pub fn search_flow<'a>(
             }
 
             match random_flows.choose(&mut rand::thread_rng()) {
-                Some(flow) => {
-                    delete_state_key(&client, "hold", "position", db)?;
-                    Ok((flow, "start".to_owned()))
-                }
+                Some(flow) => Ok((flow, "start".to_owned())),
                 None => Err(EngineError::Interpreter(format!(
                     "no match found for regex: {}",
                     event.content_value
@@ -334,10 +342,7 @@ pub fn search_flow<'a>(
             }
 
             match random_flows.choose(&mut rand::thread_rng()) {
-                Some(flow) => {
-                    delete_state_key(&client, "hold", "position", db)?;
-                    Ok((flow, "start".to_owned()))
-                }
+                Some(flow) => Ok((flow, "start".to_owned())),
                 None => Err(EngineError::Interpreter(format!(
                     "Flow '{}' does not exist",
                     event.content_value
@@ -462,13 +467,9 @@ pub fn get_ttl_duration_value(event: Option<&Event>) -> Option<chrono::Duration>
         }
     }
 
-    if let Ok(ttl) = env::var("TTL_DURATION") {
-        if let Some(ttl) = ttl.parse::<i64>().ok() {
-            return Some(chrono::Duration::days(ttl));
-        }
-    }
-
-    return None;
+    get_config()
+        .integer("conversations.ttl_duration")
+        .map(|ttl| chrono::Duration::days(ttl as i64))
 }
 
 pub fn get_low_data_mode_value(event: &Event) -> bool {
@@ -476,11 +477,33 @@ pub fn get_low_data_mode_value(event: &Event) -> bool {
         return low_data;
     }
 
-    if let Ok(low_data) = env::var("LOW_DATA_MODE") {
-        if let Ok(low_data) = low_data.parse::<bool>() {
-            return low_data;
-        }
-    }
+    get_config()
+        .boolean("conversations.low_data_mode")
+        .unwrap_or(false)
+}
 
-    return false;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_messages_delay() {
+        let messages = vec![
+            Message {
+                content_type: "typing".to_owned(),
+                content: json!({"duration": 1000}),
+            },
+            Message {
+                content_type: "text".to_owned(),
+                content: json!({"text": "hello", "duration": 5000}),
+            },
+            Message {
+                content_type: "wait".to_owned(),
+                content: json!({"duration": 500.5}),
+            },
+        ];
+
+        assert_eq!(get_messages_delay(&messages), 1500);
+        assert_eq!(get_messages_delay(&[]), 0);
+    }
 }
@@ -0,0 +1,613 @@
+/**
+ * Webhooks: the events of the conversations of a bot (see events.rs) are POSTed as JSON
+ * to the urls subscribed to them, for the teams that do not run an event bus.
+ *
+ * Each payload is signed like the signed /run requests: the X-Csml-Timestamp header holds the
+ * unix timestamp of the request and X-Csml-Webhook-Signature the hex encoded HMAC-SHA256
+ * of "{timestamp}.{body}" with the secret of the webhook.
+ *
+ * The failed deliveries are retried WEBHOOKS_MAX_ATTEMPTS times (default 5) with an exponential
+ * backoff starting at WEBHOOKS_RETRY_DELAY seconds (default 5). The last
+ * WEBHOOKS_DELIVERY_LOG_SIZE deliveries of each webhook (default 100) are kept in its delivery log.
+ *
+ * The webhooks of a bot are saved in its bot state and cached WEBHOOKS_CACHE_TTL seconds
+ * (default 60) by each engine process.
+ *
+ * The conversation_error events of every bot are also sent to the alerting webhook
+ * ERRORS_WEBHOOK_URL, signed with ERRORS_WEBHOOK_SECRET. Its deliveries are not logged.
+ *
+ * During a shutdown, the failed deliveries are retried without waiting for their backoff.
+ */
+use crate::{
+    config::get_config,
+    data::{Database, EngineError, Webhook, WebhookDelivery, WebhookRequest},
+    db_connectors::{init_db, state, utils::get_tenant_bot_id},
+    events::*,
+    shutdown::{is_shutting_down, wait_until},
+    signature::{sign, TIMESTAMP_HEADER},
+    utils::get_bot_client,
+    Client,
+};
+use csml_interpreter::data::csml_logs::*;
+
+use chrono::{prelude::Utc, SecondsFormat};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError, SyncSender},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const DELIVERY_PENDING: &str = "pending";
+pub const DELIVERY_DELIVERED: &str = "delivered";
+pub const DELIVERY_FAILED: &str = "failed";
+
+const SIGNATURE_HEADER: &str = "X-Csml-Webhook-Signature";
+const EVENTS: [&str; 9] = [
+    CONVERSATION_STARTED,
+    CONVERSATION_CLOSED,
+    STEP_ENTERED,
+    MESSAGE_SENT,
+    MEMORY_UPDATED,
+    EXPERIMENT_ASSIGNED,
+    STEP_STARTED,
+    STEP_COMPLETED,
+    CONVERSATION_ERROR,
+];
+// id of the alerting webhook ERRORS_WEBHOOK_URL
+const ERRORS_WEBHOOK_ID: &str = "errors";
+const QUEUE_SIZE: usize = 10_000;
+// the worker wakes up at least this often to retry the failed deliveries
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn get_setting_number(key: &str, default: u64) -> u64 {
+    get_config().integer(key).unwrap_or(default)
+}
+
+fn deliveries_key(webhook_id: &str) -> String {
+    format!("deliveries:{}", webhook_id)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// SUBSCRIPTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+// bot_id => (fetched at, webhooks)
+static WEBHOOKS_CACHE: Lazy<Mutex<HashMap<String, (Instant, Vec<Webhook>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn save_webhooks(bot_id: &str, webhooks: &[Webhook], db: &mut Database) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+    let value = serde_json::json!(webhooks);
+
+    state::replace_state_item(&client, "webhooks", "subscriptions", &value, None, db)?;
+
+    WEBHOOKS_CACHE.lock().unwrap().remove(bot_id);
+
+    Ok(())
+}
+
+pub fn get_webhooks(bot_id: &str, db: &mut Database) -> Result<Vec<Webhook>, EngineError> {
+    let client = get_bot_client(bot_id);
+
+    match state::get_state_key(&client, "webhooks", "subscriptions", db)? {
+        Some(value) => Ok(serde_json::from_value(value)?),
+        None => Ok(vec![]),
+    }
+}
+
+fn get_cached_webhooks(bot_id: &str, db: &mut Database) -> Result<Vec<Webhook>, EngineError> {
+    let ttl = Duration::from_secs(get_setting_number("webhooks.cache_ttl", 60));
+
+    if let Some((fetched_at, webhooks)) = WEBHOOKS_CACHE.lock().unwrap().get(bot_id) {
+        if fetched_at.elapsed() < ttl {
+            return Ok(webhooks.to_owned());
+        }
+    }
+
+    let webhooks = get_webhooks(bot_id, db)?;
+    WEBHOOKS_CACHE
+        .lock()
+        .unwrap()
+        .insert(bot_id.to_owned(), (Instant::now(), webhooks.to_owned()));
+
+    Ok(webhooks)
+}
+
+pub fn create_webhook(request: WebhookRequest, db: &mut Database) -> Result<Webhook, EngineError> {
+    if !request.url.starts_with("http://") && !request.url.starts_with("https://") {
+        return Err(EngineError::Format(format!(
+            "invalid webhook url [{}], expected an http(s) url",
+            request.url
+        )));
+    }
+    if let Some(event) = request
+        .events
+        .iter()
+        .find(|event| !EVENTS.contains(&event.as_str()))
+    {
+        return Err(EngineError::Format(format!(
+            "unknown webhook event [{}], expected one of {:?}",
+            event, EVENTS
+        )));
+    }
+
+    let webhook = Webhook {
+        webhook_id: uuid::Uuid::new_v4().to_string(),
+        bot_id: request.bot_id.to_owned(),
+        url: request.url,
+        events: request.events,
+        secret: match request.secret {
+            Some(secret) if !secret.is_empty() => Some(secret),
+            _ => Some(hex::encode(rand::random::<[u8; 32]>())),
+        },
+        created_at: now(),
+    };
+
+    let mut webhooks = get_webhooks(&request.bot_id, db)?;
+    webhooks.push(webhook.clone());
+    save_webhooks(&request.bot_id, &webhooks, db)?;
+
+    Ok(webhook)
+}
+
+/**
+ * Remove a webhook and its delivery log, return false if the bot has no such webhook
+ */
+pub fn delete_webhook(
+    bot_id: &str,
+    webhook_id: &str,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    let mut webhooks = get_webhooks(bot_id, db)?;
+    let count = webhooks.len();
+    webhooks.retain(|webhook| webhook.webhook_id != webhook_id);
+
+    if webhooks.len() == count {
+        return Ok(false);
+    }
+
+    save_webhooks(bot_id, &webhooks, db)?;
+    state::delete_state_key(
+        &get_bot_client(bot_id),
+        "webhooks",
+        &deliveries_key(webhook_id),
+        db,
+    )?;
+
+    Ok(true)
+}
+
+/**
+ * Last deliveries of a webhook, most recent first, or None if the bot has no such webhook
+ */
+pub fn get_webhook_deliveries(
+    bot_id: &str,
+    webhook_id: &str,
+    db: &mut Database,
+) -> Result<Option<Vec<WebhookDelivery>>, EngineError> {
+    if !get_webhooks(bot_id, db)?
+        .iter()
+        .any(|webhook| webhook.webhook_id == webhook_id)
+    {
+        return Ok(None);
+    }
+
+    let client = get_bot_client(bot_id);
+    match state::get_state_key(&client, "webhooks", &deliveries_key(webhook_id), db)? {
+        Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        None => Ok(Some(vec![])),
+    }
+}
+
+fn log_delivery(
+    bot_id: &str,
+    webhook_id: &str,
+    delivery: &WebhookDelivery,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let client = get_bot_client(bot_id);
+    let key = deliveries_key(webhook_id);
+    let size = get_setting_number("webhooks.delivery_log_size", 100) as usize;
+
+    let mut deliveries: Vec<WebhookDelivery> =
+        match state::get_state_key(&client, "webhooks", &key, db)? {
+            Some(value) => serde_json::from_value(value)?,
+            None => vec![],
+        };
+    deliveries.retain(|logged| logged.delivery_id != delivery.delivery_id);
+    deliveries.insert(0, delivery.to_owned());
+    deliveries.truncate(size);
+
+    let value = serde_json::json!(deliveries);
+    state::replace_state_item(&client, "webhooks", &key, &value, None, db)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// DELIVERIES
+////////////////////////////////////////////////////////////////////////////////
+
+struct PendingDelivery {
+    webhook: Webhook,
+    delivery: WebhookDelivery,
+    body: Vec<u8>,
+    next_attempt: Instant,
+}
+
+/**
+ * POST a signed payload to a webhook, return the status of the response
+ */
+fn send_payload(webhook: &Webhook, delivery: &WebhookDelivery, body: &[u8]) -> Result<u16, String> {
+    let secret = webhook.secret.as_deref().unwrap_or_default();
+    let timestamp = Utc::now().timestamp().to_string();
+    let signature = sign(secret, &timestamp, body).map_err(|err| format!("{:?}", err))?;
+
+    match ureq::post(&webhook.url)
+        .set("Content-Type", "application/json")
+        .set(TIMESTAMP_HEADER, &timestamp)
+        .set(SIGNATURE_HEADER, &hex::encode(signature))
+        .set("X-Csml-Event", &delivery.event)
+        .set("X-Csml-Delivery", &delivery.delivery_id)
+        .timeout(Duration::from_secs(10))
+        .send_bytes(body)
+    {
+        Ok(response) => Ok(response.status()),
+        Err(ureq::Error::Status(status, _)) => Err(format!("response status {}", status)),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/**
+ * Try to deliver a payload, return the delivery back if it must be retried
+ */
+fn attempt_delivery(mut pending: PendingDelivery, db: &mut Database) -> Option<PendingDelivery> {
+    let max_attempts = get_setting_number("webhooks.max_attempts", 5) as u32;
+    let retry_delay = get_setting_number("webhooks.retry_delay", 5);
+
+    pending.delivery.attempts += 1;
+    pending.delivery.updated_at = now();
+
+    let retry = match send_payload(&pending.webhook, &pending.delivery, &pending.body) {
+        Ok(status) => {
+            pending.delivery.status = DELIVERY_DELIVERED.to_owned();
+            pending.delivery.response_status = Some(status);
+            pending.delivery.error = None;
+            false
+        }
+        Err(error) => {
+            pending.delivery.error = Some(error);
+            if pending.delivery.attempts < max_attempts {
+                let delay = retry_delay << (pending.delivery.attempts - 1).min(16);
+                pending.next_attempt = Instant::now() + Duration::from_secs(delay);
+                true
+            } else {
+                pending.delivery.status = DELIVERY_FAILED.to_owned();
+                false
+            }
+        }
+    };
+
+    if pending.webhook.webhook_id == ERRORS_WEBHOOK_ID {
+        if let Some(error) = pending.delivery.error.as_ref().filter(|_| !retry) {
+            log_error(format!("failed to deliver error alert: {}", error));
+        }
+    } else if let Err(err) = log_delivery(
+        &pending.webhook.bot_id,
+        &pending.webhook.webhook_id,
+        &pending.delivery,
+        db,
+    ) {
+        log_error(format!("failed to log webhook delivery: {:?}", err));
+    }
+
+    match retry {
+        true => Some(pending),
+        false => None,
+    }
+}
+
+fn get_errors_webhook(bot_id: &str) -> Option<Webhook> {
+    let config = get_config();
+
+    config.text("errors.webhook_url").map(|url| Webhook {
+        webhook_id: ERRORS_WEBHOOK_ID.to_owned(),
+        bot_id: bot_id.to_owned(),
+        url,
+        events: vec![CONVERSATION_ERROR.to_owned()],
+        secret: config.text("errors.webhook_secret"),
+        created_at: now(),
+    })
+}
+
+fn get_pending_deliveries(
+    event: &serde_json::Value,
+    db: &mut Database,
+) -> Result<Vec<PendingDelivery>, EngineError> {
+    let client: Client = serde_json::from_value(event["client"].clone())?;
+    let bot_id = get_tenant_bot_id(client.tenant_id.as_deref(), &client.bot_id);
+    let name = event["event"].as_str().unwrap_or_default();
+
+    let mut webhooks = get_cached_webhooks(&bot_id, db)?;
+    if name == CONVERSATION_ERROR {
+        webhooks.extend(get_errors_webhook(&bot_id));
+    }
+    if webhooks.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let body = serde_json::to_vec(event)?;
+    let now = now();
+
+    Ok(webhooks
+        .into_iter()
+        .filter(|webhook| webhook.events.is_empty() || webhook.events.iter().any(|e| e == name))
+        .map(|webhook| PendingDelivery {
+            webhook,
+            delivery: WebhookDelivery {
+                delivery_id: uuid::Uuid::new_v4().to_string(),
+                event_id: event["id"].as_str().unwrap_or_default().to_owned(),
+                event: name.to_owned(),
+                status: DELIVERY_PENDING.to_owned(),
+                attempts: 0,
+                response_status: None,
+                error: None,
+                created_at: now.clone(),
+                updated_at: now.clone(),
+            },
+            body: body.clone(),
+            next_attempt: Instant::now(),
+        })
+        .collect())
+}
+
+fn log_error(message: String) {
+    csml_logger(CsmlLog::new(None, None, None, message), LogLvl::Error);
+}
+
+// events waiting in the queue of the worker and deliveries waiting for a retry
+static QUEUED_EVENTS: AtomicUsize = AtomicUsize::new(0);
+static RETRIES: AtomicUsize = AtomicUsize::new(0);
+
+fn start_worker() -> Option<Mutex<SyncSender<serde_json::Value>>> {
+    let mut db = match init_db() {
+        Ok(db) => db,
+        Err(err) => {
+            log_error(format!("webhooks disabled: {:?}", err));
+            return None;
+        }
+    };
+
+    let (sender, receiver) = mpsc::sync_channel::<serde_json::Value>(QUEUE_SIZE);
+
+    thread::spawn(move || {
+        let mut retries: Vec<PendingDelivery> = vec![];
+
+        loop {
+            let shutting_down = is_shutting_down();
+            let timeout = retries
+                .iter()
+                .map(|pending| match shutting_down {
+                    true => Duration::from_secs(0),
+                    false => pending
+                        .next_attempt
+                        .saturating_duration_since(Instant::now()),
+                })
+                .min()
+                .unwrap_or(IDLE_TIMEOUT);
+
+            let mut due = vec![];
+            let received = match receiver.recv_timeout(timeout) {
+                Ok(event) => {
+                    match get_pending_deliveries(&event, &mut db) {
+                        Ok(deliveries) => due.extend(deliveries),
+                        Err(err) => log_error(format!("failed to get webhooks: {:?}", err)),
+                    };
+                    true
+                }
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+
+            let now = Instant::now();
+            let (ready, waiting): (Vec<_>, Vec<_>) = retries
+                .into_iter()
+                .partition(|pending| shutting_down || pending.next_attempt <= now);
+            retries = waiting;
+            due.extend(ready);
+
+            for pending in due {
+                if let Some(retry) = attempt_delivery(pending, &mut db) {
+                    retries.push(retry);
+                }
+            }
+
+            // the retries are counted before the event is removed from the queue
+            RETRIES.store(retries.len(), Ordering::SeqCst);
+            if received {
+                QUEUED_EVENTS.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    });
+
+    Some(Mutex::new(sender))
+}
+
+static WORKER: Lazy<Option<Mutex<SyncSender<serde_json::Value>>>> = Lazy::new(start_worker);
+
+/**
+ * Queue an event for the webhooks of its bot. When the queue is full, the event is dropped.
+ */
+pub fn queue_event(event: &serde_json::Value) {
+    if let Some(sender) = &*WORKER {
+        QUEUED_EVENTS.fetch_add(1, Ordering::SeqCst);
+
+        if sender.lock().unwrap().try_send(event.to_owned()).is_err() {
+            QUEUED_EVENTS.fetch_sub(1, Ordering::SeqCst);
+            log_error("webhooks queue full: event dropped".to_owned());
+        }
+    }
+}
+
+/**
+ * Wait until the queued events are delivered (or failed all their attempts) or the deadline,
+ * return false if some deliveries are still pending
+ */
+pub fn flush(deadline: Instant) -> bool {
+    let pending = || QUEUED_EVENTS.load(Ordering::SeqCst) + RETRIES.load(Ordering::SeqCst);
+    let flushed = wait_until(deadline, || pending() == 0);
+
+    if !flushed {
+        log_error(format!(
+            "{} webhook deliveries pending at the shutdown",
+            pending()
+        ));
+    }
+
+    flushed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn ok_webhooks() {
+        crate::make_migrations().unwrap_or({});
+        let mut db = init_db().unwrap();
+        let bot_id = "webhook_bot";
+
+        for webhook in get_webhooks(bot_id, &mut db).unwrap() {
+            delete_webhook(bot_id, &webhook.webhook_id, &mut db).unwrap();
+        }
+
+        let invalid = WebhookRequest {
+            bot_id: bot_id.to_owned(),
+            url: "http://localhost".to_owned(),
+            events: vec!["unknown_event".to_owned()],
+            secret: None,
+        };
+        assert!(create_webhook(invalid, &mut db).is_err());
+
+        let request = WebhookRequest {
+            bot_id: bot_id.to_owned(),
+            url: "http://localhost/webhook".to_owned(),
+            events: vec![CONVERSATION_CLOSED.to_owned()],
+            secret: Some("secret".to_owned()),
+        };
+        let webhook = create_webhook(request, &mut db).unwrap();
+        assert_eq!(get_webhooks(bot_id, &mut db).unwrap().len(), 1);
+
+        let client = Client::new(
+            bot_id.to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        );
+        let event = serde_json::json!({
+            "id": "event_id",
+            "event": CONVERSATION_CLOSED,
+            "client": client,
+        });
+        let deliveries = get_pending_deliveries(&event, &mut db).unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].delivery.event_id, "event_id");
+
+        let other_event = serde_json::json!({"id": "id", "event": STEP_ENTERED, "client": client});
+        assert!(get_pending_deliveries(&other_event, &mut db)
+            .unwrap()
+            .is_empty());
+
+        log_delivery(
+            bot_id,
+            &webhook.webhook_id,
+            &deliveries[0].delivery,
+            &mut db,
+        )
+        .unwrap();
+        let logged = get_webhook_deliveries(bot_id, &webhook.webhook_id, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(logged[0].status, DELIVERY_PENDING);
+
+        assert!(delete_webhook(bot_id, &webhook.webhook_id, &mut db).unwrap());
+        assert!(!delete_webhook(bot_id, &webhook.webhook_id, &mut db).unwrap());
+        assert!(get_webhook_deliveries(bot_id, &webhook.webhook_id, &mut db)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn ok_signed_payload() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some((name, value)) => {
+                        headers.insert(name.to_lowercase(), value.to_owned());
+                    }
+                    None if line.trim_end().is_empty() => break,
+                    None => (),
+                }
+            }
+
+            let length = headers["content-length"].parse().unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+
+            (headers, body)
+        });
+
+        let webhook = Webhook {
+            webhook_id: "webhook_id".to_owned(),
+            bot_id: "bot_id".to_owned(),
+            url,
+            events: vec![],
+            secret: Some("secret".to_owned()),
+            created_at: now(),
+        };
+        let delivery = WebhookDelivery {
+            delivery_id: "delivery_id".to_owned(),
+            event_id: "event_id".to_owned(),
+            event: CONVERSATION_STARTED.to_owned(),
+            status: DELIVERY_PENDING.to_owned(),
+            attempts: 0,
+            response_status: None,
+            error: None,
+            created_at: now(),
+            updated_at: now(),
+        };
+        let body = br#"{"event":"conversation_started"}"#;
+
+        assert_eq!(send_payload(&webhook, &delivery, body), Ok(200));
+
+        let (headers, received) = server.join().unwrap();
+        let timestamp = &headers["x-csml-timestamp"];
+        let signature = hex::encode(sign("secret", timestamp, body).unwrap());
+        assert_eq!(received, body);
+        assert_eq!(headers["x-csml-webhook-signature"], signature);
+        assert_eq!(headers["x-csml-event"], CONVERSATION_STARTED);
+    }
+}
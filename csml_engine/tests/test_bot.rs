@@ -89,6 +89,12 @@ fn init_bot(bot_name: &str) -> Result<CsmlBot, std::io::Error> {
         })),
         modules: None,
         multibot: None,
+        apps: None,
+        apps_endpoint_options: None,
+        no_data_retention: None,
+        conversation_expiration: None,
+        metadata_schema: None,
+        locales: None,
     };
 
     Ok(bot)
@@ -101,6 +107,7 @@ fn init_request(string: &str, bot_id: String, channel_id: String) -> CsmlRequest
             user_id: "test".to_owned(),
             bot_id,
             channel_id,
+            tenant_id: None,
         },
         callback_url: Some("http://httpbin.org/post".to_owned()),
         payload: json!({
@@ -178,6 +185,7 @@ fn ok_test_hold() {
         user_id: "test".to_owned(),
         bot_id: bot_id.clone(),
         channel_id: channel_id.clone(),
+        tenant_id: None,
     })
     .unwrap();
 }
@@ -237,6 +245,7 @@ fn ok_test_import() {
         user_id: "test".to_owned(),
         bot_id: bot_id.clone(),
         channel_id: channel_id.clone(),
+        tenant_id: None,
     })
     .unwrap();
 }
@@ -296,6 +305,7 @@ fn ok_test_commands() {
         user_id: "test".to_owned(),
         bot_id: bot_id.clone(),
         channel_id: channel_id.clone(),
+        tenant_id: None,
     })
     .unwrap();
 }
@@ -355,6 +365,7 @@ fn ok_test_goto_var() {
         user_id: "test".to_owned(),
         bot_id: bot_id.clone(),
         channel_id: channel_id.clone(),
+        tenant_id: None,
     })
     .unwrap();
 }
@@ -425,6 +436,7 @@ fn ok_test_memory() {
         user_id: "test".to_owned(),
         bot_id: bot_id.clone(),
         channel_id: channel_id.clone(),
+        tenant_id: None,
     })
     .unwrap();
 }
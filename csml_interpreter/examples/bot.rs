@@ -97,6 +97,12 @@ fn main() {
         None,
         None,
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     // Create an Event
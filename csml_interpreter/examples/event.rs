@@ -34,6 +34,12 @@ fn main() {
         None,
         None,
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     // Create an Event
@@ -36,6 +36,12 @@ fn main() {
         None,
         None,
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     // Create an Event
@@ -47,6 +53,10 @@ fn main() {
         low_data_mode: None,
         step_limit: None,
         secure: false,
+        locale: None,
+        enrichment: serde_json::json!({}),
+        reference_id: None,
+        settings: HashMap::new(),
     };
 
     // Create context
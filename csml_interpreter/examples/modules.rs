@@ -42,6 +42,12 @@ fn main() {
             auth: None,
         }]),
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     // Create an Event
@@ -53,6 +59,10 @@ fn main() {
         low_data_mode: None,
         step_limit: None,
         secure: false,
+        locale: None,
+        enrichment: serde_json::json!({}),
+        reference_id: None,
+        settings: HashMap::new(),
     };
 
     // Create context
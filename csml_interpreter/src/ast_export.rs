@@ -0,0 +1,649 @@
+use crate::data::ast::*;
+use crate::data::tokens::{BUILT_IN, COMPONENT};
+use crate::error_format::ErrorInfo;
+use crate::format_flow::format_flow;
+use crate::interpreter::components::load_components;
+use crate::parser::{parse_flow, parse_flow_partial};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURES
+////////////////////////////////////////////////////////////////////////////////
+
+// version of the JSON representation, changed on every breaking change
+pub const AST_JSON_VERSION: u32 = 1;
+
+const ERROR_ONE_STEP: &str = "the source of a step must contain exactly one step";
+
+/**
+ * Syntax error of a flow, with the range of the source where it was found
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyntaxError {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: Option<u32>,
+    pub end_column: Option<u32>,
+    pub message: String,
+}
+
+/**
+ * Result of the parsing of a source that can be partially invalid: the AST of its valid parts
+ * (None if nothing could be parsed) and the syntax errors of the other ones
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseReport {
+    pub valid: bool,
+    pub ast: Option<Value>,
+    pub errors: Vec<SyntaxError>,
+}
+
+/**
+ * Result of formatting a valid flow: the formatted source, if formatting it again changes
+ * nothing and if its AST is the same as the AST of the original source, ranges excepted
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundTrip {
+    pub formatted: String,
+    pub idempotent: bool,
+    pub same_ast: bool,
+}
+
+/**
+ * Names needed to tell a call to a function of the flow, a builtin and a component apart
+ */
+struct Exporter {
+    functions: HashSet<String>,
+    components: Map<String, Value>,
+    // gotos and components of the step or function being exported
+    gotos: Vec<Value>,
+    used_components: Vec<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn infix_to_str(infix: &Infix) -> &'static str {
+    match infix {
+        Infix::Addition => "+",
+        Infix::Subtraction => "-",
+        Infix::Divide => "/",
+        Infix::Multiply => "*",
+        Infix::Remainder => "%",
+        Infix::Match => "match",
+        Infix::NotMatch => "!match",
+        Infix::Equal => "==",
+        Infix::NotEqual => "!=",
+        Infix::GreaterThanEqual => ">=",
+        Infix::LessThanEqual => "<=",
+        Infix::GreaterThan => ">",
+        Infix::LessThan => "<",
+        Infix::And => "&&",
+        Infix::Or => "||",
+        Infix::Coalesce => "??",
+    }
+}
+
+fn assign_to_str(assign: &AssignType) -> &'static str {
+    match assign {
+        AssignType::Assignment => "=",
+        AssignType::AdditionAssignment => "+=",
+        AssignType::SubtractionAssignment => "-=",
+        AssignType::MultiplicationAssignment => "*=",
+        AssignType::DivisionAssignment => "/=",
+        AssignType::RemainderAssignment => "%=",
+    }
+}
+
+fn range_to_json(interval: &Interval) -> Value {
+    json!({
+        "start_line": interval.start_line,
+        "start_column": interval.start_column,
+        "end_line": interval.end_line,
+        "end_column": interval.end_column,
+    })
+}
+
+impl Exporter {
+    fn call_kind(&self, name: &str) -> &'static str {
+        if self.functions.contains(name) {
+            "function"
+        } else if BUILT_IN.contains(&name) {
+            "builtin"
+        } else if self.components.contains_key(name) {
+            "component"
+        } else {
+            // function imported from another flow
+            "function"
+        }
+    }
+
+    fn use_component(&mut self, name: String) {
+        if !self.used_components.contains(&name) {
+            self.used_components.push(name);
+        }
+    }
+
+    fn block_to_json(&mut self, block: &Block) -> Value {
+        Value::Array(
+            block
+                .commands
+                .iter()
+                .map(|(expr, _)| self.expr_to_json(expr))
+                .collect(),
+        )
+    }
+
+    fn exprs_to_json(&mut self, exprs: &[Expr]) -> Value {
+        Value::Array(exprs.iter().map(|expr| self.expr_to_json(expr)).collect())
+    }
+
+    fn function_to_json(&mut self, function: &Function) -> Value {
+        let kind = self.call_kind(&function.name);
+        if kind == "component" {
+            self.use_component(function.name.to_owned());
+        }
+
+        json!({
+            "type": "call",
+            "kind": kind,
+            "name": function.name,
+            "args": self.expr_to_json(&function.args),
+            "range": range_to_json(&function.interval),
+        })
+    }
+
+    fn goto_value_to_json(&mut self, value: &Option<GotoValueType>) -> Value {
+        match value {
+            Some(GotoValueType::Name(ident)) => json!(ident.ident),
+            Some(GotoValueType::Variable(expr)) => json!({"variable": self.expr_to_json(expr)}),
+            None => Value::Null,
+        }
+    }
+
+    fn goto_to_json(&mut self, goto: &GotoType, interval: &Interval) -> Value {
+        let (step, flow, bot) = match goto {
+            GotoType::Step(value) => (Some(value.to_owned()), None, None),
+            GotoType::Flow(value) => (None, Some(value.to_owned()), None),
+            GotoType::StepFlow { step, flow, bot } => {
+                (step.to_owned(), flow.to_owned(), bot.to_owned())
+            }
+        };
+
+        let goto = json!({
+            "type": "goto",
+            "step": self.goto_value_to_json(&step),
+            "flow": self.goto_value_to_json(&flow),
+            "bot": self.goto_value_to_json(&bot),
+            "range": range_to_json(interval),
+        });
+
+        // summary of the transitions of the step, a variable target is only known at runtime
+        let target = |value: &Value| match value {
+            Value::String(_) | Value::Null => value.to_owned(),
+            _ => Value::Null,
+        };
+        let dynamic = [&goto["step"], &goto["flow"], &goto["bot"]]
+            .iter()
+            .any(|value| value.is_object());
+        self.gotos.push(json!({
+            "step": target(&goto["step"]),
+            "flow": target(&goto["flow"]),
+            "bot": target(&goto["bot"]),
+            "dynamic": dynamic,
+            "range": range_to_json(interval),
+        }));
+
+        goto
+    }
+
+    fn if_to_json(&mut self, if_statement: &IfStatement) -> Value {
+        match if_statement {
+            IfStatement::IfStmt {
+                cond,
+                consequence,
+                then_branch,
+                ..
+            } => json!({
+                "type": "if",
+                "condition": self.expr_to_json(cond),
+                "then": self.block_to_json(consequence),
+                "else": match then_branch {
+                    Some(else_branch) => self.if_to_json(else_branch),
+                    None => Value::Null,
+                },
+            }),
+            IfStatement::ElseStmt(block, ..) => self.block_to_json(block),
+        }
+    }
+
+    fn path_to_json(&mut self, literal: &Expr, path: &[(Interval, PathState)]) -> Value {
+        if let (Expr::IdentExpr(ident), Some((_, PathState::Func(function)))) =
+            (literal, path.first())
+        {
+            if ident.ident == COMPONENT {
+                self.use_component(format!("{}.{}", COMPONENT, function.name));
+            }
+        }
+
+        let path: Vec<Value> = path
+            .iter()
+            .map(|(interval, node)| match node {
+                PathState::StringIndex(key) => json!({"key": key}),
+                PathState::ExprIndex(expr) => json!({"index": self.expr_to_json(expr)}),
+                PathState::Func(function) => json!({
+                    "method": function.name,
+                    "args": self.expr_to_json(&function.args),
+                    "range": range_to_json(interval),
+                }),
+                PathState::Optional => json!({"optional": true}),
+            })
+            .collect();
+
+        json!({
+            "type": "path",
+            "target": self.expr_to_json(literal),
+            "path": path,
+        })
+    }
+
+    fn object_to_json(&mut self, object: &ObjectType) -> Value {
+        match object {
+            ObjectType::Goto(goto, interval) => self.goto_to_json(goto, interval),
+            ObjectType::Previous(PreviousType::Step(interval), ..) => {
+                json!({"type": "previous", "target": "step", "range": range_to_json(interval)})
+            }
+            ObjectType::Previous(PreviousType::Flow(interval), ..) => {
+                json!({"type": "previous", "target": "flow", "range": range_to_json(interval)})
+            }
+            ObjectType::Hold(interval) => json!({"type": "hold", "range": range_to_json(interval)}),
+            ObjectType::HoldSecure(interval) => {
+                json!({"type": "hold_secure", "range": range_to_json(interval)})
+            }
+            ObjectType::Break(interval) => {
+                json!({"type": "break", "range": range_to_json(interval)})
+            }
+            ObjectType::Continue(interval) => {
+                json!({"type": "continue", "range": range_to_json(interval)})
+            }
+            ObjectType::Say(expr) => json!({"type": "say", "value": self.expr_to_json(expr)}),
+            ObjectType::Return(expr) => {
+                json!({"type": "return", "value": self.expr_to_json(expr)})
+            }
+            ObjectType::Use(expr) => json!({"type": "use", "value": self.expr_to_json(expr)}),
+            ObjectType::Debug(expr, interval) => json!({
+                "type": "debug",
+                "value": self.expr_to_json(expr),
+                "range": range_to_json(interval),
+            }),
+            ObjectType::Log {
+                expr,
+                interval,
+                log_lvl,
+            } => json!({
+                "type": "log",
+                "level": log_lvl,
+                "value": self.expr_to_json(expr),
+                "range": range_to_json(interval),
+            }),
+            ObjectType::Do(DoType::Exec(expr)) => {
+                json!({"type": "do", "value": self.expr_to_json(expr)})
+            }
+            ObjectType::Do(DoType::Update(assign, target, value)) => json!({
+                "type": "do",
+                "operator": assign_to_str(assign),
+                "target": self.expr_to_json(target),
+                "value": self.expr_to_json(value),
+            }),
+            ObjectType::Assign(assign, target, value) => json!({
+                "type": "assign",
+                "operator": assign_to_str(assign),
+                "target": self.expr_to_json(target),
+                "value": self.expr_to_json(value),
+            }),
+            ObjectType::Remember(ident, expr) => self.remember_to_json("remember", ident, expr),
+            ObjectType::RememberTemp(ident, expr) => {
+                self.remember_to_json("remember_temp", ident, expr)
+            }
+            ObjectType::RememberUser(ident, expr) => {
+                self.remember_to_json("remember_user", ident, expr)
+            }
+            ObjectType::As(ident, expr) => json!({
+                "type": "as",
+                "name": ident.ident,
+                "value": self.expr_to_json(expr),
+                "range": range_to_json(&ident.interval),
+            }),
+            ObjectType::Forget(memories, interval) => {
+                let names = match memories {
+                    ForgetMemory::ALL => json!("*"),
+                    ForgetMemory::SINGLE(ident) => json!([ident.ident]),
+                    ForgetMemory::LIST(idents) => json!(idents
+                        .iter()
+                        .map(|ident| ident.ident.to_owned())
+                        .collect::<Vec<String>>()),
+                };
+
+                json!({"type": "forget", "memories": names, "range": range_to_json(interval)})
+            }
+            ObjectType::BuiltIn(function) => self.function_to_json(function),
+        }
+    }
+
+    fn remember_to_json(&mut self, action: &str, ident: &Identifier, expr: &Expr) -> Value {
+        json!({
+            "type": action,
+            "name": ident.ident,
+            "value": self.expr_to_json(expr),
+            "range": range_to_json(&ident.interval),
+        })
+    }
+
+    fn expr_to_json(&mut self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Scope { scope, .. } => self.block_to_json(scope),
+            Expr::ForEachExpr(item, index, iterable, block, interval) => json!({
+                "type": "foreach",
+                "item": item.ident,
+                "index": index.as_ref().map(|index| index.ident.to_owned()),
+                "iterable": self.expr_to_json(iterable),
+                "actions": self.block_to_json(block),
+                "range": range_to_json(interval),
+            }),
+            Expr::WhileExpr(cond, block, interval) => json!({
+                "type": "while",
+                "condition": self.expr_to_json(cond),
+                "actions": self.block_to_json(block),
+                "range": range_to_json(interval),
+            }),
+            // string with {{ }} interpolations
+            Expr::ComplexLiteral(parts, interval) => json!({
+                "type": "string",
+                "parts": self.exprs_to_json(parts),
+                "range": range_to_json(interval),
+            }),
+            Expr::MapExpr {
+                object, interval, ..
+            } => {
+                let mut keys: Vec<&String> = object.keys().collect();
+                keys.sort();
+
+                let mut fields = Map::new();
+                for key in keys {
+                    fields.insert(key.to_owned(), self.expr_to_json(&object[key]));
+                }
+
+                json!({"type": "object", "fields": fields, "range": range_to_json(interval)})
+            }
+            Expr::VecExpr(items, interval) => json!({
+                "type": "array",
+                "items": self.exprs_to_json(items),
+                "range": range_to_json(interval),
+            }),
+            Expr::AwaitAllExpr(items, interval) => json!({
+                "type": "await_all",
+                "items": self.exprs_to_json(items),
+                "range": range_to_json(interval),
+            }),
+            Expr::InfixExpr(infix, left, right) => json!({
+                "type": "infix",
+                "operator": infix_to_str(infix),
+                "left": self.expr_to_json(left),
+                "right": self.expr_to_json(right),
+            }),
+            Expr::PostfixExpr(_, expr) => json!({"type": "not", "value": self.expr_to_json(expr)}),
+            Expr::ObjectExpr(object) => self.object_to_json(object),
+            Expr::IfExpr(if_statement) => self.if_to_json(if_statement),
+            Expr::PathExpr { literal, path } => self.path_to_json(literal, path),
+            Expr::IdentExpr(ident) => json!({
+                "type": "identifier",
+                "name": ident.ident,
+                "range": range_to_json(&ident.interval),
+            }),
+            Expr::LitExpr { literal, .. } => json!({
+                "type": "literal",
+                "value_type": literal.primitive.get_type().to_string(),
+                "value": literal.primitive.to_json(),
+                "range": range_to_json(&literal.interval),
+            }),
+            Expr::FilterExpr(expr, filters) => {
+                let filters: Vec<Value> = filters
+                    .iter()
+                    .map(|filter| {
+                        json!({
+                            "name": filter.name,
+                            "args": self.expr_to_json(&filter.args),
+                            "range": range_to_json(&filter.interval),
+                        })
+                    })
+                    .collect();
+
+                json!({"type": "filter", "value": self.expr_to_json(expr), "filters": filters})
+            }
+        }
+    }
+
+    /**
+     * Actions of a step or function with the summary of its gotos and components
+     */
+    fn scope_to_json(&mut self, scope: &Expr) -> Value {
+        self.gotos = vec![];
+        self.used_components = vec![];
+
+        let actions = self.expr_to_json(scope);
+        let range = match scope {
+            Expr::Scope { range, .. } => range_to_json(range),
+            _ => Value::Null,
+        };
+
+        json!({
+            "range": range,
+            "actions": actions,
+            "gotos": std::mem::take(&mut self.gotos),
+            "components": std::mem::take(&mut self.used_components),
+        })
+    }
+}
+
+fn scope_offset(scope: &Expr) -> usize {
+    match scope {
+        Expr::Scope { range, .. } => range.offset,
+        _ => 0,
+    }
+}
+
+fn get_syntax_error(error: &ErrorInfo) -> SyntaxError {
+    let interval = &error.position.interval;
+
+    SyntaxError {
+        start_line: interval.start_line,
+        start_column: interval.start_column,
+        end_line: interval.end_line,
+        end_column: interval.end_column,
+        message: error.message.to_owned(),
+    }
+}
+
+// the ranges change when the source is formatted, not the AST.
+// The closures are exported with their raw AST, where the ranges are intervals.
+fn remove_ranges(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(key, _)| key != "range" && key != "interval")
+                .map(|(key, value)| (key, remove_ranges(value)))
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.into_iter().map(remove_ranges).collect()),
+        value => value,
+    }
+}
+
+fn import_to_json(name: &str, original_name: &Option<String>, from_flow: Value) -> Value {
+    json!({
+        "name": name,
+        "original_name": original_name,
+        "from_flow": from_flow,
+    })
+}
+
+fn flow_to_ast_json(flow: &Flow) -> Value {
+    let mut scopes: Vec<(&InstructionScope, &Expr)> = flow.flow_instructions.iter().collect();
+    scopes.sort_by_key(|(_, scope)| scope_offset(scope));
+
+    let mut exporter = Exporter {
+        functions: scopes
+            .iter()
+            .filter_map(|(instruction, _)| match instruction {
+                InstructionScope::FunctionScope { name, .. } => Some(name.to_owned()),
+                _ => None,
+            })
+            .collect(),
+        components: load_components().unwrap_or_default(),
+        gotos: vec![],
+        used_components: vec![],
+    };
+
+    let mut steps = vec![];
+    let mut functions = vec![];
+    let mut imports = vec![];
+    let mut inserts = vec![];
+
+    for (instruction, scope) in scopes {
+        match instruction {
+            InstructionScope::StepScope(name) => {
+                let mut step = exporter.scope_to_json(scope);
+                step["name"] = json!(name);
+                steps.push(step);
+            }
+            InstructionScope::FunctionScope { name, args } => {
+                let mut function = exporter.scope_to_json(scope);
+                function["name"] = json!(name);
+                function["args"] = json!(args);
+                functions.push(function);
+            }
+            InstructionScope::ImportScope(import) => {
+                let from_flow = match &import.from_flow {
+                    FromFlow::Normal(flow) | FromFlow::Extern(flow) => json!(flow),
+                    FromFlow::None => Value::Null,
+                };
+                imports.push(import_to_json(
+                    &import.name,
+                    &import.original_name,
+                    from_flow,
+                ));
+            }
+            InstructionScope::InsertStep(insert) => inserts.push(import_to_json(
+                &insert.name,
+                &insert.original_name,
+                json!(insert.from_flow),
+            )),
+            InstructionScope::Constant(_)
+            | InstructionScope::StepAccess(_)
+            | InstructionScope::StepTimeout(_)
+            | InstructionScope::DuplicateInstruction(..) => {}
+        }
+    }
+
+    let mut constant_names: Vec<&String> = flow.constants.keys().collect();
+    constant_names.sort();
+    let mut constants = Map::new();
+    for name in constant_names {
+        constants.insert(name.to_owned(), flow.constants[name].primitive.to_json());
+    }
+
+    json!({
+        "version": AST_JSON_VERSION,
+        "steps": steps,
+        "functions": functions,
+        "imports": imports,
+        "inserts": inserts,
+        "constants": constants,
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Parse a flow and return a JSON representation of its AST for external tools
+ * (visual editors, dependency graphs, ...). Steps and functions are in the order of the source,
+ * each one with its actions, the gotos it contains and the components it uses.
+ */
+pub fn parse_flow_to_ast_json(source: &str) -> Result<Value, ErrorInfo> {
+    let flow = parse_flow(source, "flow")?;
+
+    Ok(flow_to_ast_json(&flow))
+}
+
+/**
+ * Same JSON as parse_flow_to_ast_json for a flow with syntax errors: the invalid steps,
+ * functions and root items are left out of the AST and reported in the errors
+ */
+pub fn parse_flow_to_partial_ast_json(source: &str) -> ParseReport {
+    let (flow, errors) = parse_flow_partial(source, "flow");
+
+    ParseReport {
+        valid: errors.is_empty(),
+        ast: flow.as_ref().map(flow_to_ast_json),
+        errors: errors.iter().map(get_syntax_error).collect(),
+    }
+}
+
+/**
+ * Parse the source of a single step, its label and its actions. The AST is the JSON of the step,
+ * like in the steps of parse_flow_to_ast_json.
+ */
+pub fn parse_step(source: &str) -> ParseReport {
+    let mut report = parse_flow_to_partial_ast_json(source);
+
+    let step = report
+        .ast
+        .take()
+        .and_then(|mut ast| match ast["steps"].take() {
+            Value::Array(mut steps) if steps.len() == 1 => steps.pop(),
+            _ => None,
+        });
+
+    if step.is_none() && report.errors.is_empty() {
+        report.valid = false;
+        report.errors.push(SyntaxError {
+            start_line: 0,
+            start_column: 0,
+            end_line: None,
+            end_column: None,
+            message: ERROR_ONE_STEP.to_owned(),
+        });
+    }
+    report.ast = step;
+
+    report
+}
+
+/**
+ * Format a valid flow and check that formatting keeps its AST and is idempotent,
+ * the invariants of format_flow. Returns the syntax errors of an invalid flow.
+ */
+pub fn round_trip(source: &str) -> Result<RoundTrip, Vec<SyntaxError>> {
+    let ast = match parse_flow_to_ast_json(source) {
+        Ok(ast) => ast,
+        Err(error) => return Err(vec![get_syntax_error(&error)]),
+    };
+
+    let formatted = format_flow(source);
+    let same_ast = match parse_flow_to_ast_json(&formatted) {
+        Ok(formatted_ast) => remove_ranges(ast) == remove_ranges(formatted_ast),
+        Err(_) => false,
+    };
+
+    Ok(RoundTrip {
+        idempotent: format_flow(&formatted) == formatted,
+        same_ast,
+        formatted,
+    })
+}
@@ -0,0 +1,83 @@
+use crate::data::{
+    ast::{Block, Expr, IfStatement, InstructionScope},
+    csml_bot::CsmlBot,
+};
+use crate::interpreter::variable_handler::interval::interval_from_if_stmt;
+use crate::parser::parse_flow;
+
+use std::collections::BTreeMap;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn visit_block(block: &Block, branches: &mut Vec<(u32, u32)>) {
+    for (expr, _) in block.commands.iter() {
+        visit_expr(expr, branches);
+    }
+}
+
+fn visit_if(if_statement: &IfStatement, branches: &mut Vec<(u32, u32)>) {
+    let interval = interval_from_if_stmt(if_statement);
+    branches.push((interval.start_line, interval.start_column));
+
+    match if_statement {
+        IfStatement::IfStmt {
+            consequence,
+            then_branch,
+            ..
+        } => {
+            visit_block(consequence, branches);
+
+            if let Some(else_scope) = then_branch {
+                visit_if(else_scope, branches);
+            }
+        }
+        IfStatement::ElseStmt(block, ..) => visit_block(block, branches),
+    }
+}
+
+fn visit_expr(expr: &Expr, branches: &mut Vec<(u32, u32)>) {
+    match expr {
+        Expr::Scope { scope, .. } => visit_block(scope, branches),
+        Expr::ForEachExpr(_, _, _, block, ..) | Expr::WhileExpr(_, block, ..) => {
+            visit_block(block, branches)
+        }
+        Expr::IfExpr(if_statement) => visit_if(if_statement, branches),
+        _ => {}
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Steps of each flow of the bot, with the branches of their if statements: the line and
+ * column of the condition of each if and else if, and of each else, as sent by MSG::Branch.
+ * The flows that can't be parsed are left out.
+ */
+pub fn get_bot_branches(bot: &CsmlBot) -> BTreeMap<String, BTreeMap<String, Vec<(u32, u32)>>> {
+    let mut result = BTreeMap::new();
+
+    for flow in bot.flows.iter() {
+        let parsed_flow = match parse_flow(&flow.content, &flow.name) {
+            Ok(parsed_flow) => parsed_flow,
+            Err(_) => continue,
+        };
+
+        let mut steps = BTreeMap::new();
+        for (instruction_scope, scope) in parsed_flow.flow_instructions.iter() {
+            if let InstructionScope::StepScope(step_name) = instruction_scope {
+                let mut branches = vec![];
+                visit_expr(scope, &mut branches);
+                branches.sort();
+
+                steps.insert(step_name.to_owned(), branches);
+            }
+        }
+        result.insert(flow.name.to_owned(), steps);
+    }
+
+    result
+}
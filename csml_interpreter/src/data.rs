@@ -6,10 +6,13 @@ pub mod csml_flow;
 pub mod csml_logs;
 pub mod csml_result;
 pub mod data;
+pub mod debugger;
 pub mod error_info;
 pub mod event;
+pub mod execution_limits;
 pub mod fn_args_type;
 pub mod hold;
+pub mod knowledge_base;
 pub mod literal;
 pub mod memories;
 pub mod message;
@@ -17,26 +20,33 @@ pub mod message_data;
 pub mod msg;
 pub mod position;
 pub mod primitive;
+pub mod sandbox;
+pub mod step_hooks;
 pub mod tokens;
 pub mod warnings;
 
 pub use ast::Interval;
 pub use client::Client;
 pub use context::{ApiInfo, Context, PreviousBot};
-pub use csml_bot::{CsmlBot, Module, MultiBot};
+pub use csml_bot::{
+    AppOptions, BotApp, BotLocales, CircuitBreakerOptions, ConversationExpiration, CsmlBot,
+    LambdaFunction, Module, MultiBot,
+};
 pub use csml_flow::CsmlFlow;
 pub use csml_result::CsmlResult;
 pub use data::Data;
-pub use event::Event;
+pub use debugger::{Breakpoint, Debugger};
+pub use event::{Event, EventType};
+pub use execution_limits::{ExecutionLimits, STEP_LIMIT};
 pub use fn_args_type::ArgsType;
 pub use hold::{Hold, IndexInfo};
+pub use knowledge_base::{KnowledgeBase, Passage};
 pub use literal::Literal;
 pub use memories::{Memory, MemoryType};
 pub use message::Message;
 pub use message_data::MessageData;
 pub use position::Position;
+pub use sandbox::Sandbox;
+pub use step_hooks::StepHooks;
 
 pub use msg::MSG;
-
-// limit of steps in a single execution
-pub static STEP_LIMIT: usize = 100;
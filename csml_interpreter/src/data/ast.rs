@@ -14,6 +14,11 @@ pub struct Flow {
     pub flow_instructions: HashMap<InstructionScope, Expr>,
     pub flow_type: FlowType,
     pub constants: HashMap<String, Literal>,
+    // @requires_flow(...) of the flow and @requires(...) of its steps by step name
+    pub flow_access: Option<StepAccess>,
+    pub steps_access: HashMap<String, StepAccess>,
+    // @timeout(...) of its steps by step name
+    pub steps_timeout: HashMap<String, StepTimeout>,
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +77,63 @@ impl PartialEq for ImportScope {
 
 impl Eq for ImportScope {}
 
+/**
+ * Metadata claims required to enter a step, or every step of a flow:
+ * @requires(role = "agent", denied_step = "access_denied")
+ * Without them, the denied step is entered instead.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepAccess {
+    // None for the access of the flow
+    pub step: Option<String>,
+    pub claims: Vec<(String, String)>,
+    pub denied_step: Option<String>,
+    pub interval: Interval,
+}
+
+impl Hash for StepAccess {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.step.hash(state)
+    }
+}
+
+impl PartialEq for StepAccess {
+    fn eq(&self, other: &Self) -> bool {
+        self.step == other.step
+    }
+}
+
+impl Eq for StepAccess {}
+
+/**
+ * Budget of the external calls of a step (HTTP, App, Generate), in milliseconds:
+ * @timeout(5000, goto: slow_response, message: "One moment please...")
+ * Once it is exceeded, the calls are aborted, the interim message is sent
+ * and the fallback step is entered instead.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepTimeout {
+    pub step: String,
+    pub duration: u64,
+    pub fallback_step: String,
+    pub message: Option<String>,
+    pub interval: Interval,
+}
+
+impl Hash for StepTimeout {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.step.hash(state)
+    }
+}
+
+impl PartialEq for StepTimeout {
+    fn eq(&self, other: &Self) -> bool {
+        self.step == other.step
+    }
+}
+
+impl Eq for StepTimeout {}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InstructionScope {
     StepScope(String),
@@ -79,6 +141,10 @@ pub enum InstructionScope {
     ImportScope(ImportScope),
     InsertStep(InsertStep),
     Constant(String),
+    // only used during parsing, saved in the flow_access and steps_access of the flow
+    StepAccess(StepAccess),
+    // only used during parsing, saved in the steps_timeout of the flow
+    StepTimeout(StepTimeout),
 
     // this Variant is use to store all duplicated instruction during parsing
     // and use by the linter to display them all as errors
@@ -93,6 +159,8 @@ impl Hash for InstructionScope {
             InstructionScope::ImportScope(import_scope) => import_scope.hash(state),
             InstructionScope::InsertStep(insert_step) => insert_step.hash(state),
             InstructionScope::Constant(name) => name.hash(state),
+            InstructionScope::StepAccess(access) => access.hash(state),
+            InstructionScope::StepTimeout(timeout) => timeout.hash(state),
             InstructionScope::DuplicateInstruction(interval, ..) => interval.hash(state),
         }
     }
@@ -129,6 +197,12 @@ impl PartialEq for InstructionScope {
             (InstructionScope::Constant(name1), InstructionScope::Constant(name2)) => {
                 name1 == name2
             }
+            (InstructionScope::StepAccess(access1), InstructionScope::StepAccess(access2)) => {
+                access1 == access2
+            }
+            (InstructionScope::StepTimeout(timeout1), InstructionScope::StepTimeout(timeout2)) => {
+                timeout1 == timeout2
+            }
             (
                 InstructionScope::DuplicateInstruction(interval1, ..),
                 InstructionScope::DuplicateInstruction(interval2, ..),
@@ -159,6 +233,13 @@ impl Display for InstructionScope {
                 ..
             }) => write!(f, "insert {} from {:?} ", name, from_flow),
             InstructionScope::Constant(name) => write!(f, "constant {}", name),
+            InstructionScope::StepAccess(access) => match &access.step {
+                Some(step) => write!(f, "access of step {}", step),
+                None => write!(f, "access of the flow"),
+            },
+            InstructionScope::StepTimeout(timeout) => {
+                write!(f, "timeout of step {}", timeout.step)
+            }
             InstructionScope::DuplicateInstruction(index, ..) => {
                 write!(f, "duplicate instruction at line {}", index.start_line)
             }
@@ -172,6 +253,11 @@ impl InstructionScope {
             InstructionScope::StepScope(name, ..) => format!("step {}", name),
             InstructionScope::FunctionScope { name, .. } => format!("function {}", name),
             InstructionScope::Constant(name) => format!("constant {}", name),
+            InstructionScope::StepAccess(access) => match &access.step {
+                Some(step) => format!("access of step {}", step),
+                None => "access of the flow".to_owned(),
+            },
+            InstructionScope::StepTimeout(timeout) => format!("timeout of step {}", timeout.step),
             InstructionScope::ImportScope(ImportScope { name, .. }) => format!("import {}", name),
             InstructionScope::InsertStep(InsertStep { name, .. }) => format!("insert {}", name),
             InstructionScope::DuplicateInstruction(_, info) => format!("duplicate {}", info),
@@ -264,6 +350,10 @@ pub enum ObjectType {
     BuiltIn(Function),
     Break(Interval),
     Continue(Interval),
+    // variable kept only for the current conversation
+    RememberTemp(Identifier, Box<Expr>),
+    // memory shared by all the channels of the user
+    RememberUser(Identifier, Box<Expr>),
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -330,6 +420,8 @@ pub enum Expr {
         interval: Interval,
     },
     VecExpr(Vec<Expr>, Interval),
+    // await_all [Fn("a"), HTTP(url).get()]: the external calls are made concurrently
+    AwaitAllExpr(Vec<Expr>, Interval),
     InfixExpr(Infix, Box<Expr>, Box<Expr>),
     PostfixExpr(Vec<Pretfix>, Box<Expr>),
     ObjectExpr(ObjectType),
@@ -345,6 +437,8 @@ pub enum Expr {
         literal: Literal,
         in_in_substring: bool, // this value is use to determine if this literal was declare inside a string or not
     },
+    // {{ expr | filter | filter(args) }} inside a string
+    FilterExpr(Box<Expr>, Vec<Function>),
 }
 
 impl Expr {
@@ -373,6 +467,9 @@ pub enum Infix {
 
     And,
     Or,
+
+    // lhs ?? rhs: rhs when lhs is Null
+    Coalesce,
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -439,6 +536,8 @@ pub enum PathState {
     ExprIndex(Expr),
     StringIndex(String),
     Func(Function),
+    // ?. the rest of the path is skipped when the value is Null
+    Optional,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -450,6 +549,7 @@ pub enum PathLiteral {
         interval: Interval,
         args: ArgsType,
     },
+    Optional,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
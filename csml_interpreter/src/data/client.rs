@@ -3,6 +3,9 @@ pub struct Client {
     pub bot_id: String,
     pub channel_id: String,
     pub user_id: String,
+    // organization of the client when the engine serves several tenants
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
 }
 
 impl Client {
@@ -11,6 +14,7 @@ impl Client {
             bot_id,
             channel_id,
             user_id,
+            tenant_id: None,
         }
     }
 }
@@ -1,12 +1,14 @@
 use crate::data::{
     primitive::{PrimitiveObject, PrimitiveType},
-    Client, Hold, Interval, Literal,
+    AppOptions, BotApp, Client, Debugger, Hold, Interval, KnowledgeBase, Literal, Sandbox,
+    StepHooks,
 };
 
 use crate::interpreter::{json_to_literal, memory_to_literal};
 
 use nom::lib::std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 ////////////////////////////////////////////////////////////////////////////////
 // DATA STRUCTURE
@@ -15,7 +17,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone)]
 pub struct ApiInfo {
     pub client: Client,
-    pub apps_endpoint: String,
+    pub apps_endpoint: Option<String>,
+    pub apps_endpoint_options: AppOptions,
+    pub apps: Vec<BotApp>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +71,22 @@ pub struct Context {
     pub step: ContextStepInfo,
     pub flow: String,
     pub previous_bot: Option<PreviousBot>,
+    // secrets of the bot, only readable as secure variables: they can't be displayed or saved
+    pub secrets: HashMap<String, Literal>,
+    // memory shared by all the users of the bot, read only in the flows
+    pub globals: HashMap<String, Literal>,
+    // feature flags of the bot evaluated for the client, read only in the flows
+    pub flags: HashMap<String, Literal>,
+    // breakpoints of the conversation when it is debugged
+    pub debugger: Option<Debugger>,
+    // client of the conversation, the key of its experiment variants
+    pub client: Option<Client>,
+    // documents of the bot searched with Search_KB
+    pub knowledge_base: Option<Arc<dyn KnowledgeBase>>,
+    // deterministic execution: seeded random, injected time and responses, no network
+    pub sandbox: Option<Arc<Sandbox>>,
+    // observer of the steps, that can veto them
+    pub step_hooks: Option<Arc<dyn StepHooks>>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -135,6 +155,14 @@ impl Context {
             step: ContextStepInfo::Normal(step.to_owned()),
             flow: flow.to_owned(),
             previous_bot,
+            secrets: HashMap::new(),
+            globals: HashMap::new(),
+            flags: HashMap::new(),
+            debugger: None,
+            client: None,
+            knowledge_base: None,
+            sandbox: None,
+            step_hooks: None,
         }
     }
 }
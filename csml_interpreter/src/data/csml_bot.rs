@@ -2,6 +2,7 @@ use crate::data::{CsmlFlow, Position};
 use crate::error_format::*;
 use crate::Interval;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 ////////////////////////////////////////////////////////////////////////////////
 // DATA STRUCTURE
@@ -13,6 +14,8 @@ pub struct CsmlBot {
     pub name: String,
     #[serde(alias = "fn_endpoint")]
     pub apps_endpoint: Option<String>,
+    #[serde(alias = "fn_endpoint_options")]
+    pub apps_endpoint_options: Option<AppOptions>,
     pub flows: Vec<CsmlFlow>,
     pub modules: Option<Vec<Module>>,
     pub multibot: Option<Vec<MultiBot>>,
@@ -22,6 +25,14 @@ pub struct CsmlBot {
     pub bot_ast: Option<String>,
     pub no_interruption_delay: Option<i32>,
     pub env: Option<serde_json::Value>,
+    pub apps: Option<Vec<BotApp>>,
+    // when set, the engine does not store the messages of the conversations
+    pub no_data_retention: Option<bool>,
+    pub conversation_expiration: Option<ConversationExpiration>,
+    // JSON schema of the request metadata, validated by the engine on each request
+    pub metadata_schema: Option<serde_json::Value>,
+    // translations of the bot, used by the _t() builtin
+    pub locales: Option<BotLocales>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +52,76 @@ pub struct MultiBot {
     pub version_id: Option<String>,
 }
 
+/**
+ * Named app called by Fn()/App() instead of the bot apps_endpoint.
+ * The app is either called over http with its url or directly invoked as an AWS Lambda function.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotApp {
+    pub name: String,
+    #[serde(default)]
+    pub url: String,
+    pub auth_header: Option<String>,
+    pub lambda: Option<LambdaFunction>,
+    #[serde(flatten)]
+    pub options: AppOptions,
+}
+
+/**
+ * AWS Lambda function invoked with the default AWS credentials of the engine (env, profile or IAM role).
+ * When region is not set, the AWS_REGION of the engine is used.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LambdaFunction {
+    pub function_arn: String,
+    pub region: Option<String>,
+}
+
+/**
+ * Resilience options of the calls made to an app or to the apps_endpoint.
+ * timeout and backoff are in milliseconds, the n-th retry waits backoff * 2^(n-1).
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppOptions {
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub retries: u32,
+    pub backoff: Option<u64>,
+    pub circuit_breaker: Option<CircuitBreakerOptions>,
+}
+
+/**
+ * After failure_threshold consecutive failed calls, the circuit opens and the calls
+ * fail immediately during reset_timeout milliseconds.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerOptions {
+    pub failure_threshold: u32,
+    pub reset_timeout: u64,
+}
+
+/**
+ * Conversations without interaction for inactivity_timeout seconds are closed on the next event.
+ * When a flow is set, the new conversation starts in this flow with a conversation_expired event.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationExpiration {
+    pub inactivity_timeout: u64,
+    pub flow: Option<String>,
+}
+
+/**
+ * Translations of the bot by locale, e.g. {"en": {"greeting": "Hello {{name}}"}, "fr": {...}}.
+ * Keys can be nested objects, accessed with a dotted key ("menu.title").
+ * When a key is missing in the requested locale ("fr-CA"), it is searched in its
+ * language ("fr") and then in the default_locale.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotLocales {
+    pub default_locale: Option<String>,
+    pub translations: HashMap<String, serde_json::Value>,
+}
+
 fn default_version() -> String {
     "latest".to_string()
 }
@@ -63,6 +144,12 @@ impl CsmlBot {
         env: Option<serde_json::Value>,
         modules: Option<Vec<Module>>,
         multibot: Option<Vec<MultiBot>>,
+        apps: Option<Vec<BotApp>>,
+        apps_endpoint_options: Option<AppOptions>,
+        no_data_retention: Option<bool>,
+        conversation_expiration: Option<ConversationExpiration>,
+        metadata_schema: Option<serde_json::Value>,
+        locales: Option<BotLocales>,
     ) -> Self {
         Self {
             id: id.to_owned(),
@@ -77,6 +164,12 @@ impl CsmlBot {
             bot_ast,
             no_interruption_delay,
             env,
+            apps,
+            apps_endpoint_options,
+            no_data_retention,
+            conversation_expiration,
+            metadata_schema,
+            locales,
         }
     }
 
@@ -128,6 +221,24 @@ impl CsmlBot {
             serde_json::json!(self.no_interruption_delay),
         );
         map.insert("env".to_owned(), serde_json::json!(self.env));
+        map.insert("apps".to_owned(), serde_json::json!(self.apps));
+        map.insert(
+            "apps_endpoint_options".to_owned(),
+            serde_json::json!(self.apps_endpoint_options),
+        );
+        map.insert(
+            "no_data_retention".to_owned(),
+            serde_json::json!(self.no_data_retention),
+        );
+        map.insert(
+            "conversation_expiration".to_owned(),
+            serde_json::json!(self.conversation_expiration),
+        );
+        map.insert(
+            "metadata_schema".to_owned(),
+            serde_json::json!(self.metadata_schema),
+        );
+        map.insert("locales".to_owned(), serde_json::json!(self.locales));
 
         serde_json::json!(map)
     }
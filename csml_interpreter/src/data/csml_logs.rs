@@ -20,6 +20,8 @@ pub struct CsmlLog {
     channel_id: Option<String>,
     flow: Option<String>,
     line: Option<u32>,
+    // id of the request running the event, to correlate the logs of a conversation turn
+    request_id: Option<String>,
     message: String,
 }
 
@@ -45,6 +47,9 @@ impl std::fmt::Debug for CsmlLog {
         if let Some(channel_id) = &self.channel_id {
             debug_struct = debug_struct.field("channel_id", channel_id);
         }
+        if let Some(request_id) = &self.request_id {
+            debug_struct = debug_struct.field("request_id", request_id);
+        }
 
         debug_struct.finish()
     }
@@ -82,15 +87,32 @@ impl CsmlLog {
             channel_id,
             flow,
             line,
+            request_id: None,
             message,
         }
     }
+
+    pub fn with_request_id(mut self, request_id: Option<&str>) -> Self {
+        self.request_id = request_id.map(|request_id| request_id.to_owned());
+        self
+    }
 }
 
 pub fn init_logger() {
+    init_logger_with_level(None)
+}
+
+/**
+ * Init the logger with the given level, or CSML_LOG_LEVEL (default error)
+ */
+pub fn init_logger_with_level(level: Option<&str>) {
     let env = env_logger::Env::default().filter_or("CSML_LOG_LEVEL", "error");
+    let mut builder = env_logger::Builder::from_env(env);
+    if let Some(level) = level {
+        builder.parse_filters(level);
+    }
 
-    let _ = env_logger::Builder::from_env(env)
+    let _ = builder
         .filter_module("async_std", log::LevelFilter::Error)
         .filter_module("async_io", log::LevelFilter::Error)
         .filter_module("polling", log::LevelFilter::Error)
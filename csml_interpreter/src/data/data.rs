@@ -1,6 +1,7 @@
 use crate::data::context::Context;
-use crate::data::Event;
+use crate::data::csml_bot::BotLocales;
 use crate::data::{ast::*, Literal};
+use crate::data::{Event, ExecutionLimits};
 
 use crate::data::context::ContextStepInfo;
 
@@ -31,12 +32,13 @@ pub struct Data<'a> {
     pub loop_index: usize,
 
     pub step_count: &'a mut usize,
-    pub step_limit: usize,
+    pub limits: ExecutionLimits,
 
     pub step_vars: HashMap<String, Literal>,
     pub previous_info: Option<PreviousInfo>,
     pub custom_component: &'a serde_json::Map<String, serde_json::Value>,
     pub native_component: &'a serde_json::Map<String, serde_json::Value>,
+    pub locales: &'a Option<BotLocales>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -72,11 +74,12 @@ impl<'a> Data<'a> {
         loop_indexes: Vec<usize>,
         loop_index: usize,
         step_count: &'a mut usize,
-        step_limit: usize,
+        limits: ExecutionLimits,
         step_vars: HashMap<String, Literal>,
         previous_info: Option<PreviousInfo>,
         custom_component: &'a serde_json::Map<String, serde_json::Value>,
         native_component: &'a serde_json::Map<String, serde_json::Value>,
+        locales: &'a Option<BotLocales>,
     ) -> Self {
         let constants = flow.constants.clone();
 
@@ -92,11 +95,12 @@ impl<'a> Data<'a> {
             loop_indexes,
             loop_index,
             step_count,
-            step_limit,
+            limits,
             step_vars,
             previous_info,
             custom_component,
             native_component,
+            locales,
         }
     }
 
@@ -110,7 +114,7 @@ impl<'a> Data<'a> {
         Vec<usize>,
         usize,
         usize,
-        usize,
+        ExecutionLimits,
         HashMap<String, Literal>,
     ) {
         (
@@ -121,7 +125,7 @@ impl<'a> Data<'a> {
             self.loop_indexes.clone(),
             self.loop_index.clone(),
             *self.step_count,
-            self.step_limit,
+            self.limits,
             self.step_vars.clone(),
         )
     }
@@ -144,6 +148,14 @@ pub fn init_child_context(data: &Data) -> Context {
         step: data.context.step.clone(),
         flow: data.context.flow.clone(),
         previous_bot: data.context.previous_bot.clone(),
+        secrets: data.context.secrets.clone(),
+        globals: data.context.globals.clone(),
+        flags: data.context.flags.clone(),
+        debugger: None,
+        client: data.context.client.clone(),
+        knowledge_base: data.context.knowledge_base.clone(),
+        sandbox: data.context.sandbox.clone(),
+        step_hooks: data.context.step_hooks.clone(),
     }
 }
 
@@ -163,10 +175,11 @@ pub fn init_child_scope<'a>(
         data.loop_indexes.clone(),
         data.loop_index,
         step_count,
-        data.step_limit,
+        data.limits,
         HashMap::new(),
         data.previous_info.clone(),
         &data.custom_component,
         &data.native_component,
+        &data.locales,
     )
 }
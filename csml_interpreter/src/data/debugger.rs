@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Where the interpretation of a step is paused: when entering a step,
+ * or before the first instruction of a line of a flow
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Breakpoint {
+    Step { flow: String, step: String },
+    Line { flow: String, line: u32 },
+}
+
+/**
+ * Debug session of a conversation: the interpreter pauses at the breakpoints
+ * the same way it does on a hold, so the conversation can be resumed later.
+ * With step_over, it pauses again before the next instruction of a step
+ * (functions are executed as a single instruction).
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Debugger {
+    #[serde(default)]
+    pub breakpoints: Vec<Breakpoint>,
+    #[serde(default)]
+    pub step_over: bool,
+    // a breakpoint matched an if or a loop, the pause is before the next instruction
+    #[serde(skip)]
+    pending: bool,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+impl Debugger {
+    pub fn new(breakpoints: Vec<Breakpoint>) -> Self {
+        Self {
+            breakpoints,
+            step_over: false,
+            pending: false,
+        }
+    }
+
+    /**
+     * The interpretation only pauses before an instruction: blocks (if, foreach and while)
+     * share their index with their first instruction, so a hold can't be resumed on them.
+     *
+     * The instruction at command_index is the first of its step when its index is 0,
+     * previous_line is the line of the previous instruction of the same block:
+     * a line breakpoint only pauses before the first instruction of the line
+     */
+    pub(crate) fn should_pause(
+        &mut self,
+        flow: &str,
+        step: &str,
+        command_index: usize,
+        line: u32,
+        previous_line: Option<u32>,
+        is_block: bool,
+    ) -> bool {
+        let is_breakpoint = self.step_over
+            || self.breakpoints.iter().any(|breakpoint| match breakpoint {
+                Breakpoint::Step {
+                    flow: bp_flow,
+                    step: bp_step,
+                } => command_index == 0 && bp_flow == flow && bp_step == step,
+                Breakpoint::Line {
+                    flow: bp_flow,
+                    line: bp_line,
+                } => *bp_line == line && bp_flow == flow && previous_line != Some(line),
+            });
+
+        if is_block {
+            self.pending = self.pending || is_breakpoint;
+            return false;
+        }
+
+        let pause = self.pending || is_breakpoint;
+        self.pending = false;
+
+        pause
+    }
+}
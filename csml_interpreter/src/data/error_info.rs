@@ -10,11 +10,26 @@ use std::collections::HashMap;
 // DATA STRUCTURE
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub flow: String,
+    pub step: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorInfo {
     pub position: Position,
     pub message: String,
     pub additional_info: Option<HashMap<String, Literal>>,
+    // step executed when the error happened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    // line of the flow where the error happened, with a caret under the error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    // steps executed during the event, from the first one to the step of the error
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stack: Vec<StackFrame>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -29,6 +44,9 @@ impl ErrorInfo {
             position,
             message,
             additional_info: Some(error_info),
+            step: None,
+            snippet: None,
+            stack: vec![],
         }
     }
 
@@ -65,6 +83,55 @@ impl ErrorInfo {
 ////////////////////////////////////////////////////////////////////////////////
 
 impl ErrorInfo {
+    /**
+     * Add the step, the source line and the steps that led to the error.
+     * The source is the content of the flow where the error happened.
+     */
+    pub fn with_trace(mut self, step: &str, source: Option<&str>, stack: &[StackFrame]) -> Self {
+        if self.step.is_none() {
+            self.step = Some(step.to_owned());
+        }
+
+        if self.snippet.is_none() {
+            self.snippet = source.and_then(|source| self.get_snippet(source));
+        }
+
+        if self.stack.is_empty() {
+            self.stack = stack.to_vec();
+        }
+
+        self
+    }
+
+    fn get_snippet(&self, source: &str) -> Option<String> {
+        let interval = &self.position.interval;
+        if interval.start_line == 0 {
+            return None;
+        }
+
+        let line = source.lines().nth(interval.start_line as usize - 1)?;
+        let start = (interval.start_column as usize).max(1);
+
+        let width = match (interval.end_line, interval.end_column) {
+            (Some(end_line), Some(end_column))
+                if end_line == interval.start_line && end_column as usize > start =>
+            {
+                end_column as usize - start
+            }
+            _ => 1,
+        };
+
+        let number = interval.start_line.to_string();
+        Some(format!(
+            "{} | {}\n{} | {}{}",
+            number,
+            line,
+            " ".repeat(number.len()),
+            " ".repeat(start - 1),
+            "^".repeat(width)
+        ))
+    }
+
     pub fn format_error(&self) -> String {
         format!(
             "{} at line {}, column {} at flow [{}]",
@@ -82,6 +149,9 @@ impl From<std::io::Error> for ErrorInfo {
             position: Position::default(),
             message: e.to_string(),
             additional_info: None,
+            step: None,
+            snippet: None,
+            stack: vec![],
         }
     }
 }
@@ -92,6 +162,9 @@ impl From<serde_json::Error> for ErrorInfo {
             position: Position::default(),
             message: e.to_string(),
             additional_info: None,
+            step: None,
+            snippet: None,
+            stack: vec![],
         }
     }
 }
@@ -102,6 +175,9 @@ impl From<uuid::Error> for ErrorInfo {
             position: Position::default(),
             message: e.to_string(),
             additional_info: None,
+            step: None,
+            snippet: None,
+            stack: vec![],
         }
     }
 }
@@ -112,6 +188,9 @@ impl From<std::time::SystemTimeError> for ErrorInfo {
             position: Position::default(),
             message: e.to_string(),
             additional_info: None,
+            step: None,
+            snippet: None,
+            stack: vec![],
         }
     }
 }
@@ -1,7 +1,45 @@
+use std::collections::HashMap;
+
 ////////////////////////////////////////////////////////////////////////////////
 // DATA STRUCTURES
 ////////////////////////////////////////////////////////////////////////////////
 
+/**
+ * The content types an event can have, with the structure of their content:
+ * - text: {"text": String}
+ * - payload: {"payload": String}
+ * - image, file, audio, video, url: {"url": String}
+ * - location: {"latitude": Number, "longitude": Number}
+ * - regex: {"payload": String} (the regex to match against the flows commands)
+ * - flow_trigger: {"flow_id": String, "step_id": Option<String>}
+ * - message_edited: {"reference_id": String, "text": String} (new text of a previous message)
+ * - reaction: {"reference_id": String, "reaction": String} (emoji or name of the reaction)
+ *
+ * In the flows, `event` is the text representation of the event and its content
+ * is available with `event.content.*` (and `event.content_type`), the annotations
+ * of the enrichers of the engine with `event.enrichment.*`. The id of the previous message
+ * an event refers to (edited message, reaction...) is `event.reference_id`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventType {
+    Text,
+    Payload,
+    Image,
+    File,
+    Audio,
+    Video,
+    Url,
+    Location,
+    Regex,
+    FlowTrigger,
+    // values submitted for a Form component
+    Form,
+    // result of a PaymentRequest component
+    Payment,
+    MessageEdited,
+    Reaction,
+}
+
 #[derive(Debug, Clone)]
 pub struct Event {
     pub content_type: String,
@@ -11,6 +49,14 @@ pub struct Event {
     pub low_data_mode: Option<bool>,
     pub step_limit: Option<usize>,
     pub secure: bool,
+    // locale of the user (e.g. "fr-FR"), used by the _t() builtin when not set in the metadata
+    pub locale: Option<String>,
+    // annotations of the event (sentiment, entities...) by enricher name
+    pub enrichment: serde_json::Value,
+    // id of the previous message the event refers to, set by the channel in the content
+    pub reference_id: Option<String>,
+    // settings given by the engine by env var name (STEP_LIMIT, LLM_URL...), read before the env
+    pub settings: HashMap<String, String>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -27,6 +73,10 @@ impl Default for Event {
             low_data_mode: None,
             step_limit: None,
             secure: false,
+            locale: None,
+            enrichment: serde_json::json!({}),
+            reference_id: None,
+            settings: HashMap::new(),
         }
     }
 }
@@ -35,16 +85,135 @@ impl Default for Event {
 // METHOD FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
+impl EventType {
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "text" => Some(EventType::Text),
+            "payload" => Some(EventType::Payload),
+            "image" => Some(EventType::Image),
+            "file" => Some(EventType::File),
+            "audio" => Some(EventType::Audio),
+            "video" => Some(EventType::Video),
+            "url" => Some(EventType::Url),
+            "location" => Some(EventType::Location),
+            "regex" => Some(EventType::Regex),
+            "flow_trigger" => Some(EventType::FlowTrigger),
+            "form" => Some(EventType::Form),
+            "payment" => Some(EventType::Payment),
+            "message_edited" => Some(EventType::MessageEdited),
+            "reaction" => Some(EventType::Reaction),
+            _ => None,
+        }
+    }
+
+    /**
+     * Check the structure of the content and return the text representation of the event
+     */
+    pub fn get_content_value(&self, content: &serde_json::Value) -> Result<String, String> {
+        match self {
+            EventType::Text => match content["text"].as_str() {
+                Some(text) => Ok(text.to_owned()),
+                None => Err("no text content in event".to_owned()),
+            },
+            EventType::Payload => match content["payload"].as_str() {
+                Some(payload) => Ok(payload.to_owned()),
+                None => Err("no payload content in event".to_owned()),
+            },
+            EventType::Image
+            | EventType::File
+            | EventType::Audio
+            | EventType::Video
+            | EventType::Url => match content["url"].as_str() {
+                Some(url) => Ok(url.to_owned()),
+                None => Err("no url content in event".to_owned()),
+            },
+            EventType::Location => {
+                match (content["latitude"].as_f64(), content["longitude"].as_f64()) {
+                    (Some(latitude), Some(longitude)) => Ok(format!("{},{}", latitude, longitude)),
+                    _ => Err(
+                        "invalid content for event type location: expect latitude and longitude"
+                            .to_owned(),
+                    ),
+                }
+            }
+            EventType::Regex => match content["payload"].as_str() {
+                Some(regex) => Ok(regex.to_owned()),
+                None => Err("invalid payload for event type regex".to_owned()),
+            },
+            EventType::FlowTrigger => match (&content["flow_id"], &content["step_id"]) {
+                (serde_json::Value::String(_), serde_json::Value::String(_))
+                | (serde_json::Value::String(_), serde_json::Value::Null) => Ok(content.to_string()),
+                _ => Err(
+                    "invalid content for event type flow_trigger: expect flow_id and optional step_id"
+                        .to_owned(),
+                ),
+            },
+            EventType::Form => match &content["values"] {
+                serde_json::Value::Object(values) => Ok(serde_json::json!(values).to_string()),
+                _ => Err("invalid content for event type form: expect values".to_owned()),
+            },
+            EventType::Payment => match content["status"].as_str() {
+                Some(status) => Ok(status.to_owned()),
+                None => Err("invalid content for event type payment: expect status".to_owned()),
+            },
+            EventType::MessageEdited => match (&content["reference_id"], content["text"].as_str()) {
+                (serde_json::Value::String(_), Some(text)) => Ok(text.to_owned()),
+                _ => Err(
+                    "invalid content for event type message_edited: expect reference_id and text"
+                        .to_owned(),
+                ),
+            },
+            EventType::Reaction => match (&content["reference_id"], content["reaction"].as_str()) {
+                (serde_json::Value::String(_), Some(reaction)) => Ok(reaction.to_owned()),
+                _ => Err(
+                    "invalid content for event type reaction: expect reference_id and reaction"
+                        .to_owned(),
+                ),
+            },
+        }
+    }
+}
+
+/**
+ * Id of the previous message referenced by the content of an event
+ */
+pub fn get_reference_id(content: &serde_json::Value) -> Option<String> {
+    content["reference_id"]
+        .as_str()
+        .map(|reference_id| reference_id.to_owned())
+}
+
 impl Event {
     pub fn new(content_type: &str, content_value: &str, content: serde_json::Value) -> Self {
         Self {
             content_type: content_type.to_owned(),
             content_value: content_value.to_owned(),
+            reference_id: get_reference_id(&content),
             content,
             ttl_duration: None,
             low_data_mode: None,
             step_limit: None,
             secure: false,
+            locale: None,
+            enrichment: serde_json::json!({}),
+            settings: HashMap::new(),
         }
     }
+
+    /**
+     * Create an event from a request payload: the content must match the content type
+     */
+    pub fn from_payload(content_type: &str, content: serde_json::Value) -> Result<Self, String> {
+        let event_type = match EventType::from_content_type(content_type) {
+            Some(event_type) => event_type,
+            None => return Err(format!("{} is not a valid event type", content_type)),
+        };
+        let content_value = event_type.get_content_value(&content)?;
+
+        Ok(Event::new(content_type, &content_value, content))
+    }
+
+    pub fn get_type(&self) -> Option<EventType> {
+        EventType::from_content_type(&self.content_type)
+    }
 }
@@ -0,0 +1,238 @@
+use crate::data::primitive::{PrimitiveArray, PrimitiveObject, PrimitiveString};
+use crate::data::{ast::Interval, position::Position, Event, Literal};
+use crate::error_format::*;
+
+use std::env;
+use std::time::{Duration, Instant};
+
+// limit of steps in a single execution
+pub static STEP_LIMIT: usize = 100;
+// limit of iterations of a single foreach or while loop
+pub static LOOP_LIMIT: usize = 10_000;
+// limit of gotos to another flow in a single execution
+pub static GOTO_FLOW_LIMIT: usize = 50;
+// limit of the duration of a single execution, in milliseconds
+pub static EXECUTION_TIME_LIMIT: u64 = 30_000;
+// limits of the size of the strings (in bytes), arrays and objects saved in variables
+pub static STRING_SIZE_LIMIT: usize = 1_000_000;
+pub static ARRAY_SIZE_LIMIT: usize = 100_000;
+pub static OBJECT_SIZE_LIMIT: usize = 100_000;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Guards of a single execution of the interpreter, they stop pathological flows
+ * (infinite loops, endless gotos, ...) with an error message instead of blocking the thread.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutionLimits {
+    pub step_limit: usize,
+    pub loop_limit: usize,
+    pub goto_flow_limit: usize,
+    pub deadline: Option<Instant>,
+    // end of the budget of the external calls of the current step, set by its @timeout
+    pub step_deadline: Option<Instant>,
+    pub string_size_limit: usize,
+    pub array_size_limit: usize,
+    pub object_size_limit: usize,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_limit<T: std::str::FromStr>(event: &Event, key: &str, default: T) -> T {
+    let limit = match event.settings.get(key) {
+        Some(limit) => limit.to_owned(),
+        None => match env::var(key) {
+            Ok(limit) => limit,
+            Err(_) => return default,
+        },
+    };
+
+    limit.parse::<T>().unwrap_or(default)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Timeout of a call, in milliseconds, bounded by what remains before the deadline
+ */
+pub fn bound_timeout(timeout: Option<u64>, deadline: Option<Instant>) -> Option<u64> {
+    let remaining = match deadline {
+        // the calls are never given a timeout of 0
+        Some(deadline) => deadline
+            .saturating_duration_since(Instant::now())
+            .as_millis() as u64,
+        None => return timeout,
+    }
+    .max(1);
+
+    match timeout {
+        Some(timeout) => Some(timeout.min(remaining)),
+        None => Some(remaining),
+    }
+}
+
+/**
+ * The error of a call aborted by the timeout of its step
+ */
+pub fn is_step_timeout_error(error: &ErrorInfo) -> bool {
+    match &error.additional_info {
+        Some(info) => matches!(
+            info.get("error_type"),
+            Some(error_type) if error_type.primitive.to_string() == "step_timeout"
+        ),
+        None => false,
+    }
+}
+
+impl ExecutionLimits {
+    /**
+     * The step limit can be given by the event, the other limits are read from the settings
+     * of the event or from the env: STEP_LIMIT, LOOP_LIMIT, GOTO_FLOW_LIMIT,
+     * EXECUTION_TIME_LIMIT (in ms, 0 disables it), STRING_SIZE_LIMIT, ARRAY_SIZE_LIMIT
+     * and OBJECT_SIZE_LIMIT
+     */
+    pub fn new(event: &Event) -> Self {
+        let step_limit = match event.step_limit {
+            Some(step_limit) => step_limit,
+            None => get_limit(event, "STEP_LIMIT", STEP_LIMIT),
+        };
+
+        let deadline = match get_limit(event, "EXECUTION_TIME_LIMIT", EXECUTION_TIME_LIMIT) {
+            0 => None,
+            millis => Some(Instant::now() + Duration::from_millis(millis)),
+        };
+
+        Self {
+            step_limit,
+            loop_limit: get_limit(event, "LOOP_LIMIT", LOOP_LIMIT),
+            goto_flow_limit: get_limit(event, "GOTO_FLOW_LIMIT", GOTO_FLOW_LIMIT),
+            deadline,
+            step_deadline: None,
+            string_size_limit: get_limit(event, "STRING_SIZE_LIMIT", STRING_SIZE_LIMIT),
+            array_size_limit: get_limit(event, "ARRAY_SIZE_LIMIT", ARRAY_SIZE_LIMIT),
+            object_size_limit: get_limit(event, "OBJECT_SIZE_LIMIT", OBJECT_SIZE_LIMIT),
+        }
+    }
+
+    pub fn check_time(&self, interval: Interval, flow: &str) -> Result<(), ErrorInfo> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(gen_error_info(
+                Position::new(interval, flow),
+                ERROR_EXECUTION_TIME_LIMIT.to_owned(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Timeout of an external call of the step, in milliseconds: its own timeout
+     * bounded by what remains of the budget of the step
+     */
+    pub fn get_call_timeout(&self, timeout: Option<u64>) -> Option<u64> {
+        bound_timeout(timeout, self.step_deadline)
+    }
+
+    /**
+     * The budget of the external calls of the step is exceeded, the error is marked
+     * with the error_type "step_timeout" to enter the fallback step of the step
+     */
+    pub fn check_step_timeout(&self, interval: Interval, flow: &str) -> Result<(), ErrorInfo> {
+        match self.step_deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                let mut error = gen_error_info(
+                    Position::new(interval, flow),
+                    ERROR_STEP_TIMEOUT_EXCEEDED.to_owned(),
+                );
+                error.add_info(
+                    "error_type",
+                    PrimitiveString::get_literal("step_timeout", interval),
+                );
+
+                Err(error)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Check the time limit and the number of iterations of the current loop
+     */
+    pub fn check_loop(
+        &self,
+        iterations: usize,
+        interval: Interval,
+        flow: &str,
+    ) -> Result<(), ErrorInfo> {
+        if iterations >= self.loop_limit {
+            return Err(gen_error_info(
+                Position::new(interval, flow),
+                format!(
+                    "{}: {} iterations where executed in a single loop",
+                    ERROR_LOOP_LIMIT, self.loop_limit
+                ),
+            ));
+        }
+
+        self.check_time(interval, flow)
+    }
+
+    /**
+     * Check the size of a literal and of all its elements before saving it in a variable
+     */
+    pub fn check_literal(
+        &self,
+        literal: &Literal,
+        interval: Interval,
+        flow: &str,
+    ) -> Result<(), ErrorInfo> {
+        let primitive = literal.primitive.as_any();
+
+        let (size, limit, kind) = if let Some(string) = primitive.downcast_ref::<PrimitiveString>()
+        {
+            (
+                string.value.len(),
+                self.string_size_limit,
+                "bytes in a string",
+            )
+        } else if let Some(array) = primitive.downcast_ref::<PrimitiveArray>() {
+            for elem in array.value.iter() {
+                self.check_literal(elem, interval, flow)?;
+            }
+            (
+                array.value.len(),
+                self.array_size_limit,
+                "elements in an array",
+            )
+        } else if let Some(object) = primitive.downcast_ref::<PrimitiveObject>() {
+            for value in object.value.values() {
+                self.check_literal(value, interval, flow)?;
+            }
+            (
+                object.value.len(),
+                self.object_size_limit,
+                "keys in an object",
+            )
+        } else {
+            return Ok(());
+        };
+
+        if size > limit {
+            return Err(gen_error_info(
+                Position::new(interval, flow),
+                format!(
+                    "{}: {} {} (limit {})",
+                    ERROR_LITERAL_SIZE_LIMIT, size, kind, limit
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
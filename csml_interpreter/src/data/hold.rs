@@ -21,6 +21,8 @@ pub struct Hold {
     pub flow_name: String,
     pub previous: Option<PreviousInfo>,
     pub secure: bool,
+    // line of the instruction where the debugger paused, this instruction is not executed yet
+    pub breakpoint: Option<u32>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -43,6 +45,7 @@ impl Hold {
             flow_name,
             previous,
             secure,
+            breakpoint: None,
         }
     }
 
@@ -57,6 +60,7 @@ impl Hold {
             flow_name: "".to_owned(),
             previous: None,
             secure: false,
+            breakpoint: None,
         }
     }
 }
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Passage of a document of the knowledge base, with its similarity to the searched query
+ * (from 0 to 1 for a cosine similarity)
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Passage {
+    pub document_id: String,
+    pub text: String,
+    pub score: f64,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/**
+ * Documents of a bot searched by the Search_KB builtin. The engine provides it in the
+ * context of the conversations when a knowledge base is configured.
+ */
+pub trait KnowledgeBase: Debug + Send + Sync {
+    fn search(&self, query: &str, top_k: usize) -> Result<Vec<Passage>, String>;
+}
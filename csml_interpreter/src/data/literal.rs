@@ -33,6 +33,7 @@ pub enum ContentType {
     Jwt,
     Crypto,
     Time,
+    Url,
     Primitive,
 }
 
@@ -194,6 +195,7 @@ impl ContentType {
             "jwt" => ContentType::Jwt,
             "crypto" => ContentType::Crypto,
             "time" => ContentType::Time,
+            "url" => ContentType::Url,
             "event" => ContentType::Event(String::from("")),
             _ => ContentType::Primitive,
         }
@@ -71,10 +71,13 @@ impl MessageData {
 
                 MSG::send(
                     sender,
-                    MSG::Error(Message {
-                        content_type: "error".to_owned(),
-                        content: json_msg.clone(),
-                    }),
+                    MSG::Error(
+                        Message {
+                            content_type: "error".to_owned(),
+                            content: json_msg.clone(),
+                        },
+                        err,
+                    ),
                 );
 
                 Self {
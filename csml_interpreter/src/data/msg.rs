@@ -1,8 +1,10 @@
 use crate::data::{
     ast::ForgetMemory, context::ContextStepInfo, csml_logs::LogLvl, error_info::ErrorInfo,
-    hold::Hold, message::Message, primitive::PrimitiveNull, Literal, Memory, MessageData,
+    execution_limits::is_step_timeout_error, hold::Hold, message::Message, position::Position,
+    primitive::PrimitiveNull, warnings::Warnings, Data, Literal, Memory, MessageData,
 };
 
+use chrono::{DateTime, Utc};
 use std::sync::mpsc;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -12,6 +14,8 @@ use std::sync::mpsc;
 #[derive(Debug)]
 pub enum MSG {
     Remember(Memory),
+    RememberTemp(Memory),
+    RememberUser(Memory),
     Forget(ForgetMemory),
     Message(Message),
     Log {
@@ -26,7 +30,53 @@ pub enum MSG {
         step: Option<ContextStepInfo>,
         bot: Option<String>,
     },
-    Error(Message),
+    Schedule {
+        id: String,
+        event: serde_json::Value,
+        delay: i64,
+    },
+    Handover(serde_json::Value),
+    // labels attached to the current conversation
+    Tag(Vec<String>),
+    // variant of an experiment assigned to the client
+    Experiment {
+        name: String,
+        variant: String,
+    },
+    // chunk of a text streamed by GenerateStream, the last one is empty and done
+    Stream {
+        id: String,
+        text: String,
+        done: bool,
+    },
+    // the error message of the conversation, with the details of the error
+    Error(Message, ErrorInfo),
+    // value silently resolved at runtime (Null access, type coercion), with its step
+    Warning {
+        step: String,
+        warning: Warnings,
+    },
+    // a step was executed between these times
+    StepTiming {
+        flow: String,
+        step: String,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    },
+    // a Fn or App was called between these times, with the error of the call if it failed
+    AppCall {
+        fn_id: String,
+        flow: String,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        error: Option<String>,
+    },
+    // a branch of an if statement was taken, at the position of its condition or of its else
+    Branch {
+        flow: String,
+        line: u32,
+        column: u32,
+    },
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -40,6 +90,21 @@ impl MSG {
         }
     }
 
+    pub fn send_warning(
+        sender: &Option<mpsc::Sender<MSG>>,
+        data: &Data,
+        position: Position,
+        message: String,
+    ) {
+        MSG::send(
+            sender,
+            MSG::Warning {
+                step: data.context.step.get_step(),
+                warning: Warnings { message, position },
+            },
+        );
+    }
+
     pub fn send_error_msg(
         sender: &Option<mpsc::Sender<MSG>>,
         msg_data: &mut MessageData,
@@ -47,6 +112,13 @@ impl MSG {
     ) -> Literal {
         match value {
             Ok(value) => value,
+            // the step ends with the interim message of its timeout instead
+            Err(err) if is_step_timeout_error(&err) => {
+                let mut error_lit = PrimitiveNull::get_literal(err.position.interval);
+                error_lit.additional_info = err.additional_info;
+
+                error_lit
+            }
             Err(err) => {
                 let message = Message {
                     content_type: "error".to_owned(),
@@ -8,10 +8,15 @@ pub mod object;
 pub mod string;
 
 pub mod tools;
+pub mod tools_contact;
 pub mod tools_crypto;
+pub mod tools_form;
+pub mod tools_fuzzy;
 pub mod tools_jwt;
+pub mod tools_number;
 pub mod tools_smtp;
 pub mod tools_time;
+pub mod tools_url;
 
 use crate::data::literal::ContentType;
 pub use array::PrimitiveArray;
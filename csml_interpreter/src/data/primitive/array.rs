@@ -7,6 +7,7 @@ use crate::data::{
         Primitive, PrimitiveBoolean, PrimitiveClosure, PrimitiveInt, PrimitiveNull,
         PrimitiveString, PrimitiveType, Right,
     },
+    sandbox::with_rng,
     tokens::TYPES,
     ArgsType, Interval, Literal, MemoryType, Message, MessageData, MSG,
 };
@@ -529,11 +530,11 @@ impl PrimitiveArray {
             ));
         }
 
-        if let Some(res) = array
-            .value
-            .get(rand::thread_rng().gen_range(0..array.value.len()))
-        {
-            return Ok(res.to_owned());
+        if !array.value.is_empty() {
+            let len = array.value.len();
+            let index = with_rng(&data.context.sandbox, |rng| rng.gen_range(0..len));
+
+            return Ok(array.value[index].to_owned());
         }
 
         Ok(PrimitiveNull::get_literal(interval))
@@ -671,7 +672,7 @@ impl PrimitiveArray {
 
         let mut vector = array.value.to_owned();
 
-        vector.shuffle(&mut rand::thread_rng());
+        with_rng(&data.context.sandbox, |rng| vector.shuffle(rng));
 
         Ok(PrimitiveArray::get_literal(&vector, interval))
     }
@@ -7,16 +7,18 @@ use crate::data::{
     literal::ContentType,
     message::Message,
     primitive::{
-        tools_crypto, tools_jwt, tools_smtp, tools_time, Data, MessageData, Primitive,
-        PrimitiveArray, PrimitiveBoolean, PrimitiveInt, PrimitiveNull, PrimitiveString,
+        tools_crypto, tools_form, tools_jwt, tools_smtp, tools_time, tools_url, Data, MessageData,
+        Primitive, PrimitiveArray, PrimitiveBoolean, PrimitiveInt, PrimitiveNull, PrimitiveString,
         PrimitiveType, Right, MSG,
     },
-    tokens::TYPES,
+    sandbox::check_network,
+    tokens::{SMTP, TYPES},
     Literal, MemoryType,
 };
 use crate::error_format::*;
 use crate::interpreter::{
-    builtins::http_builtin::http_request, json_to_rust::json_to_literal,
+    builtins::http_builtin::{get_step_request, http_request},
+    json_to_rust::json_to_literal,
     variable_handler::match_literals::match_obj,
 };
 use std::cmp::Ordering;
@@ -87,6 +89,12 @@ const FUNCTIONS_HEX: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map!
     "decode" => (PrimitiveObject::hex_decode as PrimitiveMethod, Right::Read),
 };
 
+const FUNCTIONS_URL: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map! {
+    "path" => (PrimitiveObject::url_path as PrimitiveMethod, Right::Read),
+    "query" => (PrimitiveObject::url_query as PrimitiveMethod, Right::Read),
+    "parse" => (PrimitiveObject::url_parse as PrimitiveMethod, Right::Read),
+};
+
 const FUNCTIONS_EVENT: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map! {
     "get_type" => (PrimitiveObject::get_type as PrimitiveMethod, Right::Read),
     "get_content" => (PrimitiveObject::get_content as PrimitiveMethod, Right::Read),
@@ -94,6 +102,7 @@ const FUNCTIONS_EVENT: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_ma
     "is_secure" => (PrimitiveObject::is_secure as PrimitiveMethod, Right::Read),
     "match" => (PrimitiveObject::match_args as PrimitiveMethod, Right::Read),
     "match_array" => (PrimitiveObject::match_array as PrimitiveMethod, Right::Read),
+    "validate" => (PrimitiveObject::validate as PrimitiveMethod, Right::Read),
 };
 
 const FUNCTIONS_READ: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map! {
@@ -498,8 +507,21 @@ impl PrimitiveObject {
                 }
             };
 
-            let (value, response_info) =
-                http_request(&object.value, method, &data.context.flow, interval, false)?;
+            data.limits
+                .check_step_timeout(interval, &data.context.flow)?;
+
+            let request = get_step_request(&object.value, &data.limits, interval);
+            let response = match &data.context.sandbox {
+                Some(sandbox) => sandbox.http_request(&request, &data.context.flow, interval),
+                None => http_request(&request, method, &data.context.flow, interval, false),
+            };
+            // the calls aborted by the timeout of the step end the step
+            if response.is_err() {
+                data.limits
+                    .check_step_timeout(interval, &data.context.flow)?;
+            }
+
+            let (value, response_info) = response?;
             let mut literal = json_to_literal(&value, interval, &data.context.flow)?;
             // add additional information about the http request response: status and headers
             literal.add_info_block(response_info);
@@ -804,6 +826,8 @@ impl PrimitiveObject {
         _content_type: &str,
     ) -> Result<Literal, ErrorInfo> {
         let usage = "send(email) => smtp object";
+        check_network(&data.context.sandbox, SMTP, &data.context.flow, interval)?;
+
         if args.len() < 1 {
             return Err(gen_error_info(
                 Position::new(interval, &data.context.flow),
@@ -1759,6 +1783,117 @@ impl PrimitiveObject {
     }
 }
 
+impl PrimitiveObject {
+    fn url_path(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let usage = "Url(\"https://...\").path(segment: String, ...) => Url";
+
+        let url = get_url(object, usage, data, interval)?;
+
+        let mut segments = vec![];
+        for index in 0..args.len() {
+            match args.get(&format!("arg{}", index)) {
+                Some(literal) => segments.extend(get_url_values(literal)),
+                None => {
+                    return Err(gen_error_info(
+                        Position::new(interval, &data.context.flow),
+                        format!("usage: {}", usage),
+                    ))
+                }
+            }
+        }
+
+        let new_url = tools_url::add_path(&url, &segments)
+            .map_err(|err| gen_error_info(Position::new(interval, &data.context.flow), err))?;
+
+        Ok(set_url(object, &url, &new_url, interval))
+    }
+
+    fn url_query(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let usage =
+            "Url(\"https://...\").query(key: String, value) or query(parameters: Object) => Url";
+
+        let url = get_url(object, usage, data, interval)?;
+
+        let mut parameters = vec![];
+        match (args.get("arg0"), args.get("arg1"), args.len()) {
+            (Some(key), Some(value), 2)
+                if key.primitive.get_type() == PrimitiveType::PrimitiveString =>
+            {
+                for value in get_url_values(value) {
+                    parameters.push((key.primitive.to_string(), value));
+                }
+            }
+            (Some(literal), None, 1)
+                if literal.primitive.get_type() == PrimitiveType::PrimitiveObject =>
+            {
+                let map = Literal::get_value::<HashMap<String, Literal>>(
+                    &literal.primitive,
+                    &data.context.flow,
+                    interval,
+                    format!("usage: {}", usage),
+                )?;
+
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                for key in keys {
+                    for value in get_url_values(&map[key]) {
+                        parameters.push((key.to_owned(), value));
+                    }
+                }
+            }
+            _ => {
+                return Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    format!("usage: {}", usage),
+                ))
+            }
+        }
+
+        let new_url = tools_url::add_query(&url, &parameters)
+            .map_err(|err| gen_error_info(Position::new(interval, &data.context.flow), err))?;
+
+        Ok(set_url(object, &url, &new_url, interval))
+    }
+
+    fn url_parse(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let usage = "Url(\"https://...\").parse() => Object";
+
+        if !args.is_empty() {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                format!("usage: {}", usage),
+            ));
+        }
+
+        let url = get_url(object, usage, data, interval)?;
+        let parts = tools_url::parse_url(&url)
+            .map_err(|err| gen_error_info(Position::new(interval, &data.context.flow), err))?;
+
+        json_to_literal(&parts, interval, &data.context.flow)
+    }
+}
+
 impl PrimitiveObject {
     fn hex_encode(
         object: &mut PrimitiveObject,
@@ -1975,6 +2110,51 @@ impl PrimitiveObject {
             None => Ok(PrimitiveNull::get_literal(interval)),
         }
     }
+
+    /**
+     * Errors of the values of a submitted Form (event.values) or of the result
+     * of a PaymentRequest, an empty array if the event is valid
+     */
+    fn validate(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let usage = "validate(Form or PaymentRequest) => array";
+
+        let component = match args.get("arg0") {
+            Some(lit) if args.len() == 1 => lit,
+            _ => {
+                return Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    format!("usage: {}", usage),
+                ))
+            }
+        };
+
+        let event = object.to_json();
+        let errors = match component.content_type.as_str() {
+            "form" => tools_form::validate_form(&component.primitive.to_json(), &event["values"]),
+            "paymentrequest" => {
+                tools_form::validate_payment(&component.primitive.to_json(), &event)
+            }
+            _ => {
+                return Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    format!("usage: {}", usage),
+                ))
+            }
+        };
+
+        json_to_literal(
+            &serde_json::Value::Array(errors),
+            interval,
+            &data.context.flow,
+        )
+    }
 }
 
 impl PrimitiveObject {
@@ -2525,6 +2705,64 @@ fn insert_to_object(
         .or_insert_with(|| literal.to_owned());
 }
 
+fn get_url(
+    object: &PrimitiveObject,
+    usage: &str,
+    data: &Data,
+    interval: Interval,
+) -> Result<String, ErrorInfo> {
+    match object.value.get("url") {
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Ok(lit.primitive.to_string())
+        }
+        _ => Err(gen_error_info(
+            Position::new(interval, &data.context.flow),
+            format!("usage: {}", usage),
+        )),
+    }
+}
+
+/**
+ * Values of a path segment or a query parameter, an array gives one value per element
+ */
+fn get_url_values(literal: &Literal) -> Vec<String> {
+    match literal.primitive.to_json() {
+        serde_json::Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                serde_json::Value::String(value) => value.to_owned(),
+                value => value.to_string(),
+            })
+            .collect(),
+        _ => vec![literal.primitive.to_string()],
+    }
+}
+
+/**
+ * The Url component with its new url, the text and title that showed the url follow it
+ */
+fn set_url(object: &PrimitiveObject, url: &str, new_url: &str, interval: Interval) -> Literal {
+    let mut object = object.to_owned();
+
+    for key in ["url", "text", "title"].iter() {
+        let shows_url = match object.value.get(*key) {
+            Some(lit) => *key == "url" || lit.primitive.to_string() == url,
+            None => false,
+        };
+        if shows_url {
+            object.value.insert(
+                key.to_string(),
+                PrimitiveString::get_literal(new_url, interval),
+            );
+        }
+    }
+
+    let mut result = PrimitiveObject::get_literal(&object.value, interval);
+    result.set_content_type("url");
+
+    result
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
@@ -2734,6 +2972,7 @@ impl Primitive for PrimitiveObject {
         let jwt = vec![FUNCTIONS_JWT];
         let crypto = vec![FUNCTIONS_CRYPTO];
         let time = vec![FUNCTIONS_TIME];
+        let url = vec![FUNCTIONS_URL, FUNCTIONS_READ, FUNCTIONS_WRITE];
         let generics = vec![FUNCTIONS_READ, FUNCTIONS_WRITE];
 
         let mut is_event = false;
@@ -2751,6 +2990,7 @@ impl Primitive for PrimitiveObject {
             ContentType::Jwt => ("", jwt),
             ContentType::Crypto => ("", crypto),
             ContentType::Time => ("", time),
+            ContentType::Url => ("", url),
             ContentType::Primitive => ("", generics),
         };
 
@@ -0,0 +1,45 @@
+use once_cell::sync::Lazy;
+use phonenumber::{country, Mode};
+use regex::Regex;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]+(\.[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]+)*@([A-Za-z0-9]([A-Za-z0-9-]{0,61}[A-Za-z0-9])?\.)+[A-Za-z]{2,63}$",
+    )
+    .unwrap()
+});
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn is_email(email: &str) -> bool {
+    email.len() <= 254 && EMAIL_REGEX.is_match(email)
+}
+
+/**
+ * Country of the numbering plans of an ISO 3166 country code ("FR", "us", ...)
+ */
+pub fn get_phone_country(country: &str) -> Option<country::Id> {
+    country::Id::from_str(&country.trim().to_ascii_uppercase()).ok()
+}
+
+/**
+ * The phone number in the E.164 format (+33612345678), None if it is not a valid number
+ * of the numbering plan of its country.
+ * The numbers written in the national format (06 12 34 56 78) need their country,
+ * the numbers in the international format (+33 6 12 34 56 78) can be of any country.
+ */
+pub fn normalize_phone(phone: &str, country: Option<country::Id>) -> Option<String> {
+    let number = phonenumber::parse(country, phone.trim()).ok()?;
+
+    match number.is_valid() {
+        true => Some(number.format().mode(Mode::E164).to_string()),
+        false => None,
+    }
+}
@@ -0,0 +1,117 @@
+use crate::data::primitive::tools_contact::is_email;
+use serde_json::Value;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn field_error(field: &str, error: &str) -> Value {
+    serde_json::json!({"field": field, "error": error})
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(string) => string.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/**
+ * Error of a submitted value according to the type of its field:
+ * text (default), email, number, boolean or select (one of its options)
+ */
+fn check_value(field: &Value, value: &Value) -> Option<&'static str> {
+    match (field["type"].as_str().unwrap_or("text"), value) {
+        ("text", Value::String(_)) => None,
+        ("email", Value::String(email)) => match is_email(email) {
+            true => None,
+            false => Some("invalid_email"),
+        },
+        ("number", Value::Number(number)) => {
+            let number = number.as_f64().unwrap_or_default();
+            match (field["min"].as_f64(), field["max"].as_f64()) {
+                (Some(min), _) if number < min => Some("too_small"),
+                (_, Some(max)) if number > max => Some("too_large"),
+                _ => None,
+            }
+        }
+        ("boolean", Value::Bool(_)) => None,
+        ("select", value) => match field["options"].as_array() {
+            Some(options) if options.contains(value) => None,
+            _ => Some("invalid_option"),
+        },
+        _ => Some("invalid_type"),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Errors of the values submitted for a Form: [{"field": String, "error": String}].
+ * The fields are declared as {"name": String, "type": String, "required": Boolean,
+ * "options": Array, "min": Number, "max": Number}.
+ */
+pub fn validate_form(form: &Value, values: &Value) -> Vec<Value> {
+    let fields = match form["fields"].as_array() {
+        Some(fields) => fields,
+        None => return vec![],
+    };
+    let mut errors = vec![];
+
+    for field in fields {
+        let name = match field["name"].as_str() {
+            Some(name) => name,
+            None => continue,
+        };
+
+        match &values[name] {
+            value if is_empty(value) => {
+                if field["required"].as_bool().unwrap_or(false) {
+                    errors.push(field_error(name, "required"));
+                }
+            }
+            value => {
+                if let Some(error) = check_value(field, value) {
+                    errors.push(field_error(name, error));
+                }
+            }
+        }
+    }
+
+    if let Some(values) = values.as_object() {
+        for name in values.keys() {
+            if !fields.iter().any(|field| field["name"] == *name.as_str()) {
+                errors.push(field_error(name, "unknown_field"));
+            }
+        }
+    }
+
+    errors
+}
+
+/**
+ * Errors of the result of a PaymentRequest: the payment must have succeeded,
+ * for the requested amount and currency
+ */
+pub fn validate_payment(request: &Value, payment: &Value) -> Vec<Value> {
+    let mut errors = vec![];
+
+    if payment["status"] != "succeeded" {
+        errors.push(field_error("status", "not_succeeded"));
+    }
+
+    match (request["amount"].as_f64(), payment["amount"].as_f64()) {
+        (Some(requested), Some(paid)) if (requested - paid).abs() < f64::EPSILON => {}
+        _ => errors.push(field_error("amount", "mismatch")),
+    }
+
+    match (request["currency"].as_str(), payment["currency"].as_str()) {
+        (Some(requested), Some(paid)) if requested.eq_ignore_ascii_case(paid) => {}
+        _ => errors.push(field_error("currency", "mismatch")),
+    }
+
+    errors
+}
@@ -0,0 +1,143 @@
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Similarity {
+    Levenshtein,
+    JaroWinkler,
+}
+
+impl Similarity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "levenshtein" => Some(Similarity::Levenshtein),
+            "jaro_winkler" => Some(Similarity::JaroWinkler),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * The texts are compared without their case and surrounding spaces
+ */
+fn normalize(text: &str) -> Vec<char> {
+    text.trim().to_lowercase().chars().collect()
+}
+
+fn jaro(lhs: &[char], rhs: &[char]) -> f64 {
+    if lhs.is_empty() && rhs.is_empty() {
+        return 1.0;
+    }
+    if lhs.is_empty() || rhs.is_empty() {
+        return 0.0;
+    }
+
+    let window = (lhs.len().max(rhs.len()) / 2).saturating_sub(1);
+    let mut lhs_matches = vec![false; lhs.len()];
+    let mut rhs_matches = vec![false; rhs.len()];
+    let mut matches = 0;
+
+    for (i, c) in lhs.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(rhs.len());
+
+        for j in start..end {
+            if !rhs_matches[j] && rhs[j] == *c {
+                lhs_matches[i] = true;
+                rhs_matches[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let lhs_matched = lhs.iter().zip(&lhs_matches).filter(|(_, m)| **m);
+    let rhs_matched = rhs.iter().zip(&rhs_matches).filter(|(_, m)| **m);
+    let transpositions = lhs_matched
+        .zip(rhs_matched)
+        .filter(|((l, _), (r, _))| l != r)
+        .count()
+        / 2;
+
+    let matches = matches as f64;
+    (matches / lhs.len() as f64
+        + matches / rhs.len() as f64
+        + (matches - transpositions as f64) / matches)
+        / 3.0
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Number of insertions, deletions and substitutions of characters from lhs to rhs
+ */
+pub fn levenshtein(lhs: &[char], rhs: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=rhs.len()).collect();
+    let mut current = vec![0; rhs.len() + 1];
+
+    for (i, l) in lhs.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, r) in rhs.iter().enumerate() {
+            let substitution = previous[j] + (l != r) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[rhs.len()]
+}
+
+/**
+ * Jaro similarity boosted by the common prefix (up to 4 characters)
+ */
+pub fn jaro_winkler(lhs: &[char], rhs: &[char]) -> f64 {
+    let jaro = jaro(lhs, rhs);
+    let prefix = lhs
+        .iter()
+        .zip(rhs)
+        .take(4)
+        .take_while(|(l, r)| l == r)
+        .count();
+
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/**
+ * Score between 0 (nothing in common) and 1 (same text)
+ */
+pub fn similarity(lhs: &str, rhs: &str, algorithm: Similarity) -> f64 {
+    let (lhs, rhs) = (normalize(lhs), normalize(rhs));
+
+    match algorithm {
+        Similarity::Levenshtein => match lhs.len().max(rhs.len()) {
+            0 => 1.0,
+            len => 1.0 - levenshtein(&lhs, &rhs) as f64 / len as f64,
+        },
+        Similarity::JaroWinkler => jaro_winkler(&lhs, &rhs),
+    }
+}
+
+/**
+ * (index, score) of the option the most similar to the input, the first one on equal scores
+ */
+pub fn best_match(input: &str, options: &[String], algorithm: Similarity) -> Option<(usize, f64)> {
+    options
+        .iter()
+        .map(|option| similarity(input, option, algorithm))
+        .enumerate()
+        .fold(None, |best, (index, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((index, score)),
+        })
+}
@@ -0,0 +1,234 @@
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Separators of the numbers of a locale, and the position of the currency symbols
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberLocale {
+    pub group: &'static str,
+    pub decimal: char,
+    pub symbol_first: bool,
+}
+
+const EN: NumberLocale = NumberLocale {
+    group: ",",
+    decimal: '.',
+    symbol_first: true,
+};
+// narrow no-break space
+const FR: NumberLocale = NumberLocale {
+    group: "\u{202f}",
+    decimal: ',',
+    symbol_first: false,
+};
+const DE: NumberLocale = NumberLocale {
+    group: ".",
+    decimal: ',',
+    symbol_first: false,
+};
+const CH: NumberLocale = NumberLocale {
+    group: "\u{2019}",
+    decimal: '.',
+    symbol_first: true,
+};
+
+// (unit, dimension, value of the unit in the base unit of its dimension)
+const UNITS: &[(&str, &str, f64)] = &[
+    ("mm", "length", 0.001),
+    ("cm", "length", 0.01),
+    ("m", "length", 1.0),
+    ("km", "length", 1000.0),
+    ("in", "length", 0.0254),
+    ("ft", "length", 0.3048),
+    ("yd", "length", 0.9144),
+    ("mi", "length", 1609.344),
+    ("mg", "mass", 0.001),
+    ("g", "mass", 1.0),
+    ("kg", "mass", 1000.0),
+    ("oz", "mass", 28.349523125),
+    ("lb", "mass", 453.59237),
+    ("ml", "volume", 0.001),
+    ("cl", "volume", 0.01),
+    ("l", "volume", 1.0),
+    ("gal", "volume", 3.785411784),
+    ("floz", "volume", 0.0295735295625),
+    ("m/s", "speed", 1.0),
+    ("km/h", "speed", 1.0 / 3.6),
+    ("mph", "speed", 0.44704),
+];
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn group_digits(digits: &str, group: &str) -> String {
+    let mut grouped = String::new();
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && i % 3 == digits.len() % 3 {
+            grouped.push_str(group);
+        }
+        grouped.push(c);
+    }
+
+    grouped
+}
+
+fn to_celsius(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "c" => Some(value),
+        "f" => Some((value - 32.0) * 5.0 / 9.0),
+        "k" => Some(value - 273.15),
+        _ => None,
+    }
+}
+
+fn from_celsius(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "c" => Some(value),
+        "f" => Some(value * 9.0 / 5.0 + 32.0),
+        "k" => Some(value + 273.15),
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Separators of a locale ("fr-FR", "de", ...), the english ones for the unknown locales
+ */
+pub fn get_number_locale(locale: &str) -> NumberLocale {
+    let locale = locale.to_lowercase().replace('_', "-");
+    let language = locale.split('-').next().unwrap_or_default();
+
+    match (language, locale.as_str()) {
+        (_, "de-ch") | (_, "fr-ch") | (_, "it-ch") => CH,
+        ("fr", _) | ("nb", _) | ("sv", _) | ("fi", _) | ("cs", _) | ("pl", _) | ("ru", _) => FR,
+        ("de", _)
+        | ("es", _)
+        | ("it", _)
+        | ("nl", _)
+        | ("pt", _)
+        | ("da", _)
+        | ("id", _)
+        | ("tr", _) => DE,
+        _ => EN,
+    }
+}
+
+/**
+ * The number with the separators of the locale. Without decimals, the number keeps up to 3
+ * decimals, without its trailing zeros.
+ */
+pub fn format_number(value: f64, locale: NumberLocale, decimals: Option<usize>) -> String {
+    let fixed = match decimals {
+        Some(decimals) => format!("{:.*}", decimals, value.abs()),
+        None => {
+            let fixed = format!("{:.3}", value.abs());
+            fixed.trim_end_matches('0').trim_end_matches('.').to_owned()
+        }
+    };
+
+    let (integer, fraction) = match fixed.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (fixed.as_str(), None),
+    };
+
+    let mut formatted = String::new();
+    if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        formatted.push('-');
+    }
+    formatted.push_str(&group_digits(integer, locale.group));
+    if let Some(fraction) = fraction {
+        formatted.push(locale.decimal);
+        formatted.push_str(fraction);
+    }
+
+    formatted
+}
+
+/**
+ * (symbol, decimals) of an ISO 4217 currency code, the code is its own symbol by default
+ */
+pub fn get_currency(code: &str) -> (String, usize) {
+    let code = code.to_uppercase();
+
+    match code.as_str() {
+        "EUR" => ("€".to_owned(), 2),
+        "USD" => ("$".to_owned(), 2),
+        "GBP" => ("£".to_owned(), 2),
+        "JPY" => ("¥".to_owned(), 0),
+        "KRW" => ("₩".to_owned(), 0),
+        "INR" => ("₹".to_owned(), 2),
+        "CNY" => ("CN¥".to_owned(), 2),
+        _ => (code, 2),
+    }
+}
+
+/**
+ * The amount with the symbol of the currency, placed according to the locale:
+ * "€19.90" in english, "19,90 €" in french
+ */
+pub fn format_currency(value: f64, code: &str, locale: NumberLocale) -> String {
+    let (symbol, decimals) = get_currency(code);
+    let amount = format_number(value, locale, Some(decimals));
+
+    // the currency codes used as symbols are separated from the amount
+    let separator = match symbol.chars().all(|c| c.is_ascii_alphabetic()) {
+        true => "\u{a0}",
+        false => "",
+    };
+
+    match (locale.symbol_first, amount.strip_prefix('-')) {
+        (true, Some(amount)) => format!("-{}{}{}", symbol, separator, amount),
+        (true, None) => format!("{}{}{}", symbol, separator, amount),
+        (false, _) => format!("{}\u{a0}{}", amount, symbol),
+    }
+}
+
+/**
+ * Number written with the separators of the locale, ex: "1 234,5" in french.
+ * The spaces and the group separators are ignored, None if the text is not a number.
+ */
+pub fn parse_number(text: &str, locale: NumberLocale) -> Option<f64> {
+    let mut normalized = String::new();
+
+    for c in text.trim().chars() {
+        match c {
+            _ if c == locale.decimal => normalized.push('.'),
+            _ if locale.group.contains(c) => {}
+            ' ' | '\u{a0}' | '\u{202f}' | '\'' | '\u{2019}' => {}
+            '0'..='9' | '-' | '+' => normalized.push(c),
+            _ => return None,
+        }
+    }
+
+    normalized
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+}
+
+/**
+ * Value converted between units of the same dimension (length, mass, volume, speed,
+ * temperature), None for unknown units or units of different dimensions
+ */
+pub fn convert_unit(value: f64, from: &str, to: &str) -> Option<f64> {
+    let (from, to) = (from.to_lowercase(), to.to_lowercase());
+
+    if let Some(celsius) = to_celsius(value, &from) {
+        return from_celsius(celsius, &to);
+    }
+
+    let (_, from_dimension, from_base) = UNITS.iter().find(|(unit, ..)| *unit == from)?;
+    let (_, to_dimension, to_base) = UNITS.iter().find(|(unit, ..)| *unit == to)?;
+
+    match from_dimension == to_dimension {
+        true => Some(value * from_base / to_base),
+        false => None,
+    }
+}
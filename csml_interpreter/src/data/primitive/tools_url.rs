@@ -0,0 +1,69 @@
+use serde_json::{json, Map, Value};
+use url::Url;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn parse(url: &str) -> Result<Url, String> {
+    Url::parse(url).map_err(|err| format!("invalid url '{}': {}", url, err))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * The url with the path segments added to its path, each segment is percent-encoded:
+ * ("https://a.b/shop", ["my cart", "1"]) -> "https://a.b/shop/my%20cart/1"
+ */
+pub fn add_path(url: &str, segments: &[String]) -> Result<String, String> {
+    let mut parsed = parse(url)?;
+
+    parsed
+        .path_segments_mut()
+        .map_err(|_| format!("the url '{}' can not have a path", url))?
+        .pop_if_empty()
+        .extend(segments);
+
+    Ok(parsed.to_string())
+}
+
+/**
+ * The url with the parameters added to its query, form-urlencoded:
+ * ("https://a.b/search", [("q", "fish & chips")]) -> "https://a.b/search?q=fish+%26+chips"
+ */
+pub fn add_query(url: &str, parameters: &[(String, String)]) -> Result<String, String> {
+    let mut url = parse(url)?;
+
+    url.query_pairs_mut().extend_pairs(parameters);
+
+    Ok(url.to_string())
+}
+
+/**
+ * {"scheme", "host", "port", "path", "query": {key: value}, "fragment"} of the url,
+ * the values of the query and of the path are decoded
+ */
+pub fn parse_url(url: &str) -> Result<Value, String> {
+    let url = parse(url)?;
+
+    let mut query = Map::new();
+    for (key, value) in url.query_pairs() {
+        query.insert(key.into_owned(), Value::String(value.into_owned()));
+    }
+
+    let path = match urlencoding::decode(url.path()) {
+        Ok(path) => path.into_owned(),
+        Err(_) => url.path().to_owned(),
+    };
+
+    Ok(json!({
+        "scheme": url.scheme(),
+        "host": url.host_str(),
+        "port": url.port_or_known_default(),
+        "path": path,
+        "query": query,
+        "fragment": url.fragment(),
+    }))
+}
@@ -0,0 +1,158 @@
+use crate::data::primitive::{PrimitiveInt, PrimitiveObject};
+use crate::data::{ast::Interval, position::Position, Literal};
+use crate::error_format::*;
+use crate::interpreter::builtins::http_builtin::get_url;
+
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Execution profile of the deterministic runs (replays, tests): the same flow with the same
+ * event and sandbox always gives the same messages.
+ * - Random, Shuffle, OneOf, UUID and the experiments without client draw from a seeded generator
+ * - Time returns the injected time, 0 (1970-01-01) by default
+ * - the HTTP and the Fn/App calls return the injected responses, and fail without one
+ * - the other builtins calling the network (Generate, SMTP) fail
+ */
+#[derive(Debug)]
+pub struct Sandbox {
+    rng: Mutex<StdRng>,
+    // timestamp of Time(), in milliseconds
+    now: i64,
+    // responses of the HTTP calls by url (with its query), data of the Fn/App calls by fn_id
+    http_responses: HashMap<String, serde_json::Value>,
+    app_responses: HashMap<String, serde_json::Value>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_response_info(interval: Interval) -> HashMap<String, Literal> {
+    let mut response_info = HashMap::new();
+
+    response_info.insert(
+        "status".to_owned(),
+        PrimitiveInt::get_literal(200, interval),
+    );
+    response_info.insert(
+        "headers".to_owned(),
+        PrimitiveObject::get_literal(&HashMap::new(), interval),
+    );
+
+    response_info
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+impl Sandbox {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            now: 0,
+            http_responses: HashMap::new(),
+            app_responses: HashMap::new(),
+        }
+    }
+
+    pub fn with_time(mut self, milliseconds: i64) -> Self {
+        self.now = milliseconds;
+        self
+    }
+
+    pub fn with_http_response(mut self, url: &str, response: serde_json::Value) -> Self {
+        self.http_responses.insert(url.to_owned(), response);
+        self
+    }
+
+    pub fn with_app_response(mut self, fn_id: &str, response: serde_json::Value) -> Self {
+        self.app_responses.insert(fn_id.to_owned(), response);
+        self
+    }
+
+    pub fn now(&self) -> i64 {
+        self.now
+    }
+
+    /**
+     * Injected response of an HTTP object, with the status and headers of a successful call
+     */
+    pub fn http_request(
+        &self,
+        object: &HashMap<String, Literal>,
+        flow_name: &str,
+        interval: Interval,
+    ) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo> {
+        let url = get_url(object, flow_name, interval)?;
+
+        match self.http_responses.get(&url) {
+            Some(response) => Ok((response.to_owned(), get_response_info(interval))),
+            None => Err(gen_error_info(
+                Position::new(interval, flow_name),
+                format!("{}: {}", ERROR_SANDBOX_HTTP, url),
+            )),
+        }
+    }
+
+    /**
+     * Injected response of a Fn/App call: the data returned by the app
+     */
+    pub fn app_request(
+        &self,
+        fn_id: &str,
+        flow_name: &str,
+        interval: Interval,
+    ) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo> {
+        match self.app_responses.get(fn_id) {
+            Some(response) => Ok((
+                serde_json::json!({ "data": response }),
+                get_response_info(interval),
+            )),
+            None => Err(gen_error_info(
+                Position::new(interval, flow_name),
+                format!("{}: {}", ERROR_SANDBOX_APP, fn_id),
+            )),
+        }
+    }
+}
+
+/**
+ * Run f with the random generator of the execution: the seeded one of the sandbox if any
+ */
+pub fn with_rng<T>(sandbox: &Option<Arc<Sandbox>>, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    match sandbox {
+        Some(sandbox) => {
+            let mut rng = sandbox
+                .rng
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut *rng)
+        }
+        None => f(&mut rand::thread_rng()),
+    }
+}
+
+/**
+ * The builtins calling the network without injected responses are disabled in the sandbox
+ */
+pub fn check_network(
+    sandbox: &Option<Arc<Sandbox>>,
+    builtin: &str,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<(), ErrorInfo> {
+    match sandbox {
+        Some(_) => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            format!("{} {}", builtin, ERROR_SANDBOX_NETWORK),
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::data::Literal;
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::Duration;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Observer of the steps of a conversation, called before and after each executed step with
+ * a snapshot of its variables. The engine provides it in the context of the conversations
+ * when step hooks are configured for their bot.
+ */
+pub trait StepHooks: Debug + Send + Sync {
+    /**
+     * Err(reason) vetoes the step: it is not executed and the conversation ends with an error
+     */
+    fn before_step(&self, flow: &str, step: &str, variables: &Value) -> Result<(), String>;
+
+    fn after_step(&self, flow: &str, step: &str, duration: Duration, variables: &Value);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * {name: value} of the memories and step variables, the secure variables are left out
+ */
+pub fn variables_snapshot(variables: &HashMap<String, Literal>) -> Value {
+    let mut keys: Vec<&String> = variables
+        .iter()
+        .filter(|(_, literal)| !literal.secure_variable)
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+
+    let mut snapshot = Map::new();
+    for key in keys {
+        snapshot.insert(key.to_owned(), variables[key].primitive.to_json());
+    }
+
+    Value::Object(snapshot)
+}
@@ -25,6 +25,9 @@ pub const ASSIGN: &str = "=";
 
 pub const OR: &str = "||";
 pub const AND: &str = "&&";
+pub const COALESCE: &str = "??";
+pub const OPTIONAL: &str = "?";
+pub const PIPE: &str = "|";
 
 pub const SUBTRACTION_ASSIGNMENT: &str = "-=";
 pub const ADDITION_ASSIGNMENT: &str = "+=";
@@ -57,6 +60,7 @@ pub const R2_BRACE: &str = "}}";
 
 pub const FOREACH: &str = "foreach";
 pub const WHILE: &str = "while";
+pub const AWAIT_ALL: &str = "await_all";
 pub const IF: &str = "if";
 pub const ELSE: &str = "else";
 
@@ -64,6 +68,12 @@ pub const IMPORT: &str = "import";
 pub const CONST: &str = "const";
 pub const INSERT: &str = "insert";
 pub const FROM: &str = "from";
+pub const AT: &str = "@";
+pub const REQUIRES: &str = "requires";
+pub const REQUIRES_FLOW: &str = "requires_flow";
+pub const DENIED_STEP: &str = "denied_step";
+pub const TIMEOUT: &str = "timeout";
+pub const TIMEOUT_MESSAGE: &str = "message";
 pub const AS: &str = "as";
 pub const IN: &str = "in";
 pub const DO: &str = "do";
@@ -84,16 +94,33 @@ pub const MATCH: &str = "match";
 pub const NOT_MATCH: &str = "!match";
 pub const DEFAULT: &str = "default";
 pub const REMEMBER: &str = "remember";
+pub const REMEMBER_TEMP: &str = "remember_temp";
+pub const SCOPE: &str = "scope";
+pub const USER_SCOPE: &str = "user";
+pub const CHANNEL_SCOPE: &str = "channel";
+pub const CONVERSATION_SCOPE: &str = "conversation";
 pub const FORGET: &str = "forget";
 pub const _METADATA: &str = "_metadata";
 pub const _MEMORY: &str = "_memory";
 pub const _ENV: &str = "_env";
+pub const _SECRETS: &str = "_secrets";
+pub const _GLOBAL: &str = "_global";
+pub const _FLAGS: &str = "_flags";
 pub const BREAK: &str = "break";
 pub const CONTINUE: &str = "continue";
 pub const RETURN: &str = "return";
 
-pub const FN_SCOPE_REJECTED: &[&str] =
-    &[SAY, GOTO, REMEMBER, FORGET, USE, HOLD, HOLD_SECURE, BREAK];
+pub const FN_SCOPE_REJECTED: &[&str] = &[
+    SAY,
+    GOTO,
+    REMEMBER,
+    REMEMBER_TEMP,
+    FORGET,
+    USE,
+    HOLD,
+    HOLD_SECURE,
+    BREAK,
+];
 
 pub const TRUE: &str = "true";
 pub const FALSE: &str = "false";
@@ -122,18 +149,19 @@ pub const TYPES: &[&str] = &[
 
 pub const RESERVED: &[&str] = &[
     FOREACH, WHILE, IF, ELSE, IMPORT, CONST, INSERT, AS, IN, DO, FROM, EVENT, FLOW, FILE, STEP,
-    SAY, USE, HOLD, GOTO, MATCH, _METADATA, _MEMORY, _ENV, DEFAULT, REMEMBER, FORGET, TRUE, FALSE,
-    NULL, BREAK, COMPONENT,
+    SAY, USE, HOLD, GOTO, MATCH, _METADATA, _MEMORY, _ENV, _SECRETS, DEFAULT, REMEMBER, FORGET, TRUE,
+    FALSE, NULL, BREAK, COMPONENT, REMEMBER_TEMP, _GLOBAL, _FLAGS, AWAIT_ALL,
 ];
 
 pub const UTILISATION_RESERVED: &[&str] = &[
     FOREACH, WHILE, IF, ELSE, IMPORT, CONST, INSERT, AS, DO, FLOW, STEP, SAY, USE, HOLD, GOTO,
-    MATCH, REMEMBER, FORGET, BREAK, COMPONENT,
+    MATCH, REMEMBER, REMEMBER_TEMP, FORGET, BREAK, COMPONENT,
 ];
 
 pub const ASSIGNATION_RESERVED: &[&str] = &[
     FOREACH, WHILE, IF, ELSE, IMPORT, AS, DO, EVENT, FLOW, STEP, SAY, USE, HOLD, GOTO, MATCH,
-    REMEMBER, FORGET, _METADATA, _MEMORY, _ENV, TRUE, FALSE, NULL, BREAK, COMPONENT,
+    REMEMBER, REMEMBER_TEMP, FORGET, _METADATA, _MEMORY, _ENV, _SECRETS, _GLOBAL, _FLAGS, TRUE,
+    FALSE, NULL, BREAK, COMPONENT, AWAIT_ALL,
 ];
 
 pub const TYPING: &str = "Typing";
@@ -145,6 +173,7 @@ pub const ONE_OF: &str = "OneOf";
 pub const SHUFFLE: &str = "Shuffle";
 pub const LENGTH: &str = "Length";
 pub const FIND: &str = "Find";
+pub const FUZZY_MATCH: &str = "Fuzzy_Match";
 pub const RANDOM: &str = "Random";
 pub const FLOOR: &str = "Floor";
 pub const VIDEO: &str = "Video";
@@ -167,12 +196,44 @@ pub const DEBUG: &str = "Debug";
 pub const UUID: &str = "UUID";
 pub const TIME: &str = "Time";
 pub const EXISTS: &str = "Exists";
+pub const SCHEDULE: &str = "Schedule";
+pub const HANDOVER: &str = "Handover";
+pub const TAG: &str = "Tag";
+pub const EXPERIMENT: &str = "Experiment";
+pub const GENERATE: &str = "Generate";
+pub const GENERATE_STREAM: &str = "GenerateStream";
+pub const SEARCH_KB: &str = "Search_KB";
+pub const TRANSLATE: &str = "_t";
+pub const FORMAT_NUMBER: &str = "Format_Number";
+pub const CURRENCY: &str = "Currency";
+pub const TO_NUMBER: &str = "To_Number";
+pub const CONVERT_UNIT: &str = "Convert_Unit";
+pub const IS_EMAIL: &str = "Is_Email";
+pub const IS_PHONE: &str = "Is_Phone";
+pub const NORMALIZE_PHONE: &str = "Normalize_Phone";
 
 pub const OBJECT: &str = "Object";
 
+// filters of the expandable strings: "{{ name | capitalize }}"
+pub const CAPITALIZE_FILTER: &str = "capitalize";
+pub const UPPER_FILTER: &str = "upper";
+pub const LOWER_FILTER: &str = "lower";
+pub const DATE_FILTER: &str = "date";
+pub const DEFAULT_FILTER: &str = "default";
+
+pub const FILTERS: &[&str] = &[
+    CAPITALIZE_FILTER,
+    UPPER_FILTER,
+    LOWER_FILTER,
+    DATE_FILTER,
+    DEFAULT_FILTER,
+];
+
 pub const BUILT_IN: &[&str] = &[
     ONE_OF, SHUFFLE, LENGTH, FIND, RANDOM, FLOOR, FN, APP, HTTP, OBJECT, DEBUG, UUID, BASE64, HEX,
-    JWT, CRYPTO, TIME, SMTP, EXISTS,
+    JWT, CRYPTO, TIME, SMTP, EXISTS, SCHEDULE, HANDOVER, TAG, EXPERIMENT, GENERATE,
+    GENERATE_STREAM, SEARCH_KB, TRANSLATE, FUZZY_MATCH, FORMAT_NUMBER, CURRENCY, TO_NUMBER,
+    CONVERT_UNIT, IS_EMAIL, IS_PHONE, NORMALIZE_PHONE,
 ];
 
 pub const OR_BUILT_IN: &str = "Or";
@@ -9,7 +9,11 @@ use serde::{Deserialize, Serialize};
 pub const WARNING_FN: &str =
     "'Fn()' will soon be deprecated. Please use the 'App()' keyword instead";
 pub const WARNING_OBJECT: & str = "'Object(key = value)' will be soon a deprecated Macro please use '{key: value}' instead; https://docs.csml.dev/automatic-type-inference/literals-objects-arrays";
+pub const WARNING_UNREACHABLE_CODE: &str =
+    "unreachable code: the actions after a 'goto' are never executed";
 pub const WARNING_USE: & str = "use will be soon a deprecated keyword please use 'do' instead. https://docs.csml.dev/memory/temporary-and-long-term-variables";
+pub const WARNING_NULL_VALUE: &str = "resolved to Null:";
+pub const WARNING_COERCION: &str = "string coerced to a number:";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Warnings {
@@ -23,6 +23,10 @@ pub const ERROR_PARSING: &str =
     "Invalid argument. One of the action keywords [say, do, if, ...] is missing";
 pub const ERROR_REMEMBER: &str =
     "'remember' must be assigning to a variable via '='. Example: 'remember key = value'";
+pub const ERROR_REMEMBER_TEMP: &str =
+    "'remember_temp' must be assigning to a variable via '='. Example: 'remember_temp key = value'";
+pub const ERROR_REMEMBER_SCOPE: &str =
+    "'scope' must be one of \"user\", \"channel\" or \"conversation\". Example: 'remember key = value scope \"user\"'";
 pub const ERROR_USE: &str =
     "'use' must be assigning a variable with keyword 'as'. Example: 'use value as key'";
 pub const ERROR_ACTION_ARGUMENT: &str =
@@ -31,6 +35,12 @@ pub const ERROR_IMPORT_ARGUMENT: &str =
     "'import' expecting valid function name. Example: 'import function from flow'";
 pub const ERROR_INSERT_ARGUMENT: &str =
     "'insert' expecting valid step name. Example: 'insert step from flow'";
+pub const ERROR_STEP_ACCESS: &str =
+    "'@requires' expecting the claims of the step. Example: '@requires(role = \"agent\", denied_step = \"denied\")'";
+pub const ERROR_STEP_ACCESS_STEP: &str = "'@requires' must be followed by a step";
+pub const ERROR_STEP_TIMEOUT: &str =
+    "'@timeout' expecting the budget of the step in milliseconds and its fallback step. Example: '@timeout(5000, goto: slow_response)'";
+pub const ERROR_STEP_TIMEOUT_STEP: &str = "'@timeout' must be followed by a step";
 pub const ERROR_BREAK: &str = "break can only be used inside loops";
 pub const ERROR_RETURN: &str = "return expects a value to return";
 pub const ERROR_LEFT_BRACE: &str = "expecting '{'";
@@ -44,6 +54,8 @@ pub const ERROR_DOUBLE_CLOSE_BRACE: &str = "expecting '}}' to end expandable str
 pub const ERROR_UNREACHABLE: &str = "unreachable";
 pub const ERROR_WRONG_ARGUMENT_EXPANDABLE_STRING: &str =
     "wrong argument(s) given to expandable string";
+pub const ERROR_UNKNOWN_FILTER: &str =
+    "unknown filter in expandable string. Available filters are capitalize, upper, lower, date and default";
 pub const ERROR_FN_SCOPE: &str =
     "invalid action. Use a valid action for this type of scope [do, if, return, ...]"; //\ndoc: https://docs.csml.dev/language/native-csml-functions
 
@@ -53,6 +65,11 @@ pub const ERROR_NO_FLOW: &str = "bot must have at least one flow";
 // ##Interpreter Errors
 // ### Validation
 pub const ERROR_STEP_EXIST: &str = "step does not exist";
+pub const ERROR_STEP_VETOED: &str = "step vetoed by the step hooks of the bot";
+pub const ERROR_STEP_TIMEOUT_EXCEEDED: &str =
+    "step timeout: the external calls of the step exceeded its budget";
+pub const ERROR_STEP_ACCESS_DENIED: &str =
+    "access denied: the metadata of the request is missing the claims required by the step";
 pub const ERROR_INVALID_FLOW: &str = "invalid flow: ";
 pub const ERROR_START_INSTRUCTIONS: &str =
     "to start an action one of the following instructions is expected: [say, do, if, foreach, goto]";
@@ -70,6 +87,11 @@ pub const ERROR_PAYLOAD_EXCEED_MAX_SIZE: &str = "payload exceeds max payload siz
 
 pub const ERROR_STEP_LIMIT: &str =
     "[Infinite loop] Step limit reached: 100 steps where executed in a single run";
+pub const ERROR_LOOP_LIMIT: &str = "[Infinite loop] Loop limit reached";
+pub const ERROR_GOTO_FLOW_LIMIT: &str = "[Infinite loop] Goto limit reached";
+pub const ERROR_LITERAL_SIZE_LIMIT: &str = "[Memory limit] Value too large";
+pub const ERROR_EXECUTION_TIME_LIMIT: &str =
+    "[Execution limit] Time limit reached: the execution of the event took too long";
 
 // Event
 pub const ERROR_EVENT_CONTENT_TYPE: &str = "event can only be of ContentType::Event";
@@ -84,6 +106,11 @@ pub const ERROR_COMPONENT_UNKNOWN: &str = "function does not exist for component
 // Fn API
 pub const ERROR_FN_ID: &str = "App name must be of type string";
 pub const ERROR_FN_ENDPOINT: &str = "App can not be called because apps_endpoint is not set in bot";
+pub const ERROR_APP_CIRCUIT_OPEN: &str =
+    "Apps service: too many failed calls, the endpoint is considered unhealthy";
+pub const ERROR_APP_LAMBDA_DISABLED: &str =
+    "Apps service: lambda apps require the engine to be built with the aws_lambda feature";
+pub const ERROR_APP_LAMBDA: &str = "Apps service: lambda invocation failed";
 pub const ERROR_FAIL_RESPONSE_JSON: &str = "failed to read response as JSON";
 
 // ### Import
@@ -126,6 +153,7 @@ pub const ERROR_SHUFFLE: &str =
 pub const ERROR_LENGTH: &str =
     "Length builtin expects one value of type Array or String. Example: Length( value )";
 pub const ERROR_FIND: &str = "Find builtin expects 'in' param to be of type String. Example: Find(value, in = \"hola\", case_sensitive = true)";
+pub const ERROR_FUZZY_MATCH: &str = "Fuzzy_Match builtin expects an input of type String, options of type Array of String, an optional threshold between 0 and 1 and an optional algorithm \"levenshtein\" or \"jaro_winkler\". Example: Fuzzy_Match(event, [\"pizza\", \"pasta\"], 0.7)";
 pub const ERROR_FLOOR: &str =
     "Floor builtin expects one argument of type float. Example: Floor(4.2)";
 pub const ERROR_UUID: &str =
@@ -157,7 +185,31 @@ pub const ERROR_SMTP: &str =
     "SMTP builtin expects SMTP Server Address. Example: SMTP(\"smtp.gmail.com\")";
 pub const ERROR_CRYPTO: &str =
     "CRYPTO builtin expects one argument of type string. Example: CRYPTO(\"text\")";
+pub const ERROR_SCHEDULE: &str = "Schedule builtin expects an event of type String or Object with a content_type and a content, and a delay in seconds of type Int. Example: Schedule(\"reminder\", delay = 7200)";
+pub const ERROR_HANDOVER: &str = "Handover builtin expects no argument, a reason of type String or metadata of type Object. Example: Handover(\"billing question\")";
+pub const ERROR_TAG: &str = "Tag builtin expects a tag of type String or a list of tags of type Array of String. Example: Tag(\"vip\")";
+pub const ERROR_EXPERIMENT: &str = "Experiment builtin expects a name of type String, a list of variants of type Array of String and optional weights of type Array of Int, one per variant. Example: Experiment(\"welcome_test\", [\"A\", \"B\"], weights=[50, 50])";
+pub const ERROR_GENERATE: &str = "Generate builtin expects a prompt of type String, an optional model of type String and optional max_tokens of type Int. Example: Generate(\"Summarize: {{text}}\", model=\"gpt-4o-mini\", max_tokens=200)";
+pub const ERROR_GENERATE_PROVIDER: &str = "Generate failed";
+pub const ERROR_SEARCH_KB: &str = "Search_KB builtin expects a query of type String and an optional top_k of type Int. Example: Search_KB(\"how to reset my password\", top_k=3)";
+pub const ERROR_SEARCH_KB_DISABLED: &str = "Search_KB requires a knowledge base, none is configured for this bot";
+pub const ERROR_SEARCH_KB_FAILED: &str = "Search_KB failed";
+pub const ERROR_AWAIT_ALL: &str = "await_all call interrupted";
+pub const ERROR_SANDBOX_HTTP: &str = "no response was injected in the sandbox for the url";
+pub const ERROR_SANDBOX_APP: &str = "no response was injected in the sandbox for the app";
+pub const ERROR_SANDBOX_NETWORK: &str = "calls the network, it is disabled in the sandbox";
+pub const ERROR_FILTER_DATE: &str = "date filter expects a Time object or a timestamp in milliseconds and an optional format of type String. Example: {{ created_at | date(\\\"%Y-%m-%d\\\") }}";
+pub const ERROR_FILTER_DEFAULT: &str = "default filter expects one argument. Example: {{ name | default(\\\"there\\\") }}";
+pub const ERROR_TRANSLATE: &str = "_t builtin expects a key of type String and optional params of type Object. Example: _t(\"greeting\", {\"name\": name})";
+pub const ERROR_FORMAT_NUMBER: &str = "Format_Number builtin expects a value of type Int or Float, an optional locale of type String and optional decimals of type Int. Example: Format_Number(1234.5, locale=\"fr-FR\", decimals=2)";
+pub const ERROR_CURRENCY: &str = "Currency builtin expects an amount of type Int or Float, a currency code of type String and an optional locale of type String. Example: Currency(19.9, \"EUR\", locale=\"fr-FR\")";
+pub const ERROR_TO_NUMBER: &str = "To_Number builtin expects a text of type String and an optional locale of type String. Example: To_Number(\"1 234,5\", locale=\"fr-FR\")";
+pub const ERROR_CONVERT_UNIT: &str = "Convert_Unit builtin expects a value of type Int or Float and two units of the same dimension (length, mass, volume, speed or temperature) of type String. Example: Convert_Unit(10, \"km\", \"mi\")";
+pub const ERROR_IS_EMAIL: &str = "Is_Email builtin expects one value. Example: Is_Email(\"jane@example.com\")";
+pub const ERROR_IS_PHONE: &str = "Is_Phone builtin expects a phone number and an optional country code of type String. Example: Is_Phone(\"06 12 34 56 78\", \"FR\")";
+pub const ERROR_NORMALIZE_PHONE: &str = "Normalize_Phone builtin expects a phone number and an optional country code of type String. Example: Normalize_Phone(\"06 12 34 56 78\", \"FR\")";
 pub const ERROR_BUILTIN_UNKNOWN: &str = "Unknown builtin";
+pub const ERROR_PLUGIN: &str = "Plugin call failed";
 
 // ### native Components
 pub const ERROR_HTTP_NOT_DATA: &str = "bad format: no 'data' in HTTP response";
@@ -0,0 +1,525 @@
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURES
+////////////////////////////////////////////////////////////////////////////////
+
+const INDENT: &str = "    ";
+
+// longest operators first so that '==' is not read as two '='
+const OPERATORS: &[&str] = &[
+    "==", "!=", ">=", "<=", "+=", "-=", "*=", "/=", "%=", "&&", "||", "=", ">", "<",
+];
+
+const ROOT_KEYWORDS: &[&str] = &["fn", "import", "const", "insert"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    String,
+    // {{ expr }} inside a string
+    Interpolation,
+    BlockComment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineKind {
+    Blank,
+    // step name or function declared with a colon
+    Label,
+    // fn, import, const, insert or @requires_flow
+    Root,
+    // @requires or @timeout before a step
+    Annotation,
+    Comment,
+    Code,
+    // line starting inside a string or a comment, kept as it is
+    Verbatim,
+}
+
+struct FormattedLine {
+    kind: LineKind,
+    level: usize,
+    content: String,
+}
+
+/**
+ * Strings, interpolations and comments can span several lines,
+ * their content is never changed by the formatter
+ */
+#[derive(Default)]
+struct Lexer {
+    stack: Vec<Mode>,
+    // number of opened '{', '(' and '[' in the code
+    depth: usize,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn starts_with_at(chars: &[char], index: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| chars.get(index + offset) == Some(&c))
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn ends_with_keyword(out: &str, keywords: &[&str]) -> bool {
+    let trimmed = out.trim_end();
+
+    keywords.iter().any(|keyword| {
+        trimmed.ends_with(keyword)
+            && !matches!(
+                trimmed[..trimmed.len() - keyword.len()].chars().last(),
+                Some(c) if is_ident_char(c)
+            )
+    })
+}
+
+fn push_spaced(out: &mut String, token: &str) {
+    let len = out.trim_end().len();
+    out.truncate(len);
+
+    if !out.is_empty() {
+        out.push(' ');
+    }
+    out.push_str(token);
+    out.push(' ');
+}
+
+/**
+ * Canonical spacing of the code between strings and comments:
+ * single spaces, spaces around operators, after commas and colons and before blocks
+ */
+fn format_code(code: &[char], out: &mut String) {
+    let mut index = 0;
+
+    while index < code.len() {
+        let c = code[index];
+
+        if c.is_whitespace() {
+            if !out.is_empty() && !out.ends_with(' ') {
+                out.push(' ');
+            }
+            index += 1;
+            continue;
+        }
+
+        if let Some(operator) = OPERATORS
+            .iter()
+            .find(|operator| starts_with_at(code, index, operator))
+        {
+            push_spaced(out, operator);
+            index += operator.len();
+        } else if c == ',' || c == ':' {
+            let len = out.trim_end().len();
+            out.truncate(len);
+            out.push(c);
+            out.push(' ');
+            index += 1;
+        } else {
+            let previous = out.trim_end().chars().last();
+
+            if c == '{' && matches!(previous, Some(p) if p == ')' || is_ident_char(p)) {
+                push_spaced(out, "{");
+                out.pop();
+            } else if c == '(' && ends_with_keyword(out, &["if", "while", "foreach"]) {
+                push_spaced(out, "(");
+                out.pop();
+            } else {
+                // `} else` and `(item) in`
+                if is_ident_char(c) && out.ends_with(&[')', '}'][..]) {
+                    out.push(' ');
+                }
+                // `[1, 2,]` and `(a)`
+                if (c == ')' || c == ']' || (c == '}' && previous == Some(',')))
+                    && previous.is_some()
+                {
+                    let len = out.trim_end().len();
+                    out.truncate(len);
+                }
+                out.push(c);
+            }
+            index += 1;
+            continue;
+        }
+
+        // the spaces after an operator, a comma or a colon are already written
+        while index < code.len() && code[index].is_whitespace() {
+            index += 1;
+        }
+    }
+}
+
+impl Lexer {
+    fn is_code(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /**
+     * Format one line: the code is respaced, strings and comments are copied as they are
+     */
+    fn format_line(&mut self, line: &str) -> String {
+        let chars: Vec<char> = line.chars().collect();
+        let mut out = String::new();
+        let mut code: Vec<char> = vec![];
+        let mut index = 0;
+
+        while index < chars.len() {
+            let c = chars[index];
+
+            match self.stack.last() {
+                None => {
+                    if c == '"' || starts_with_at(&chars, index, "/*") {
+                        format_code(&code, &mut out);
+                        code.clear();
+
+                        let mode = match c {
+                            '"' => Mode::String,
+                            _ => Mode::BlockComment,
+                        };
+                        self.stack.push(mode);
+                        out.push(c);
+                    } else if starts_with_at(&chars, index, "//") {
+                        format_code(&code, &mut out);
+                        code.clear();
+
+                        if !out.is_empty() && !out.ends_with(' ') {
+                            out.push(' ');
+                        }
+                        out.extend(chars[index..].iter());
+                        return out.trim_end().to_owned();
+                    } else {
+                        match c {
+                            '{' | '(' | '[' => self.depth += 1,
+                            '}' | ')' | ']' => self.depth = self.depth.saturating_sub(1),
+                            _ => {}
+                        }
+                        code.push(c);
+                    }
+                }
+                Some(Mode::String) => {
+                    if c == '\\' && index + 1 < chars.len() {
+                        out.push(c);
+                        index += 1;
+                        out.push(chars[index]);
+                    } else if c == '"' {
+                        out.push(c);
+                        self.stack.pop();
+                    } else if starts_with_at(&chars, index, "{{") {
+                        out.push_str("{{");
+                        index += 1;
+                        self.stack.push(Mode::Interpolation);
+                    } else {
+                        out.push(c);
+                    }
+                }
+                Some(Mode::Interpolation) => {
+                    if starts_with_at(&chars, index, "}}") {
+                        out.push_str("}}");
+                        index += 1;
+                        self.stack.pop();
+                    } else {
+                        if c == '"' {
+                            self.stack.push(Mode::String);
+                        }
+                        out.push(c);
+                    }
+                }
+                Some(Mode::BlockComment) => {
+                    if starts_with_at(&chars, index, "*/") {
+                        out.push_str("*/");
+                        index += 1;
+                        self.stack.pop();
+                    } else {
+                        out.push(c);
+                    }
+                }
+            }
+
+            index += 1;
+        }
+
+        format_code(&code, &mut out);
+
+        match self.is_code() {
+            true => out.trim_end().to_owned(),
+            // the end of the line is inside a string: its spaces are part of the string
+            false => out,
+        }
+    }
+}
+
+/**
+ * Split "step_name: actions" in the step name and the actions on the same line
+ */
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    let name_len = trimmed
+        .char_indices()
+        .find(|(_, c)| !is_ident_char(*c))
+        .map_or(trimmed.len(), |(index, _)| index);
+
+    if name_len == 0 || ROOT_KEYWORDS.contains(&&trimmed[..name_len]) {
+        return None;
+    }
+
+    trimmed[name_len..]
+        .trim_start()
+        .strip_prefix(':')
+        .map(|actions| (&trimmed[..name_len], actions))
+}
+
+fn is_root_keyword(line: &str) -> bool {
+    let trimmed = line.trim_start();
+
+    ROOT_KEYWORDS.iter().any(|keyword| {
+        trimmed.starts_with(keyword)
+            && !matches!(
+                trimmed[keyword.len()..].chars().next(),
+                Some(c) if is_ident_char(c)
+            )
+    })
+}
+
+fn is_annotation(line: &str) -> bool {
+    line.trim_start().starts_with('@')
+}
+
+/**
+ * @requires(...) and @timeout(...) belong to the step below them
+ */
+fn is_step_annotation(line: &str) -> bool {
+    let trimmed = line.trim_start();
+
+    ["@requires", "@timeout"].iter().any(|annotation| {
+        trimmed.starts_with(annotation)
+            && !matches!(
+                trimmed[annotation.len()..].chars().next(),
+                Some(c) if is_ident_char(c)
+            )
+    })
+}
+
+fn format_lines(source: &str) -> Vec<FormattedLine> {
+    let mut lexer = Lexer::default();
+    let mut lines = vec![];
+    let mut in_step = false;
+
+    for raw_line in source.lines() {
+        if !lexer.is_code() {
+            lexer.format_line(raw_line);
+            lines.push(FormattedLine {
+                kind: LineKind::Verbatim,
+                level: 0,
+                content: raw_line.to_owned(),
+            });
+            continue;
+        }
+
+        let mut line = raw_line;
+
+        if lexer.depth == 0 {
+            if let Some((name, actions)) = split_label(line) {
+                in_step = true;
+                lines.push(FormattedLine {
+                    kind: LineKind::Label,
+                    level: 0,
+                    content: format!("{}:", name),
+                });
+
+                if actions.trim().is_empty() {
+                    continue;
+                }
+                line = actions;
+            } else if is_root_keyword(line) {
+                let content = lexer.format_line(line);
+                // functions declared with a colon have a body like a step
+                in_step = content.ends_with(':');
+
+                lines.push(FormattedLine {
+                    kind: LineKind::Root,
+                    level: 0,
+                    content,
+                });
+                continue;
+            } else if is_annotation(line) {
+                // @requires_flow is a root item
+                let kind = match is_step_annotation(line) {
+                    true => LineKind::Annotation,
+                    false => LineKind::Root,
+                };
+
+                lines.push(FormattedLine {
+                    kind,
+                    level: 0,
+                    content: lexer.format_line(line),
+                });
+                continue;
+            }
+        }
+
+        let depth = lexer.depth;
+        let content = lexer.format_line(line);
+
+        let kind = if content.is_empty() {
+            LineKind::Blank
+        } else if content.starts_with("//") || content.starts_with("/*") {
+            LineKind::Comment
+        } else {
+            LineKind::Code
+        };
+
+        let mut level = depth + in_step as usize;
+        if content.starts_with(&['}', ')', ']'][..]) {
+            level = level.saturating_sub(1);
+        } else if content.starts_with('.') {
+            // method called on the expression of the previous line
+            level += 1;
+        }
+
+        lines.push(FormattedLine {
+            kind,
+            level,
+            content,
+        });
+    }
+
+    lines
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Byte offsets of the lines starting a step, a function or a root item (import, const, insert,
+ * annotation). The parser uses them to report the syntax errors of every step of a flow in one
+ * pass. A step starts at its first annotation (@requires, @timeout).
+ */
+pub(crate) fn get_sections_offsets(source: &str) -> Vec<usize> {
+    let mut lexer = Lexer::default();
+    let mut offsets = vec![];
+    let mut offset = 0;
+    let mut after_annotation = false;
+
+    for line in source.split_inclusive('\n') {
+        let unindented = !line.starts_with(char::is_whitespace);
+        let at_root = lexer.is_code() && (lexer.depth == 0 || unindented);
+
+        if at_root
+            && ((split_label(line).is_some() && !after_annotation)
+                || is_root_keyword(line)
+                || (is_annotation(line) && !after_annotation))
+        {
+            // a block left open in a broken step must not hide the next steps
+            lexer.depth = 0;
+            offsets.push(offset);
+        }
+
+        let trimmed = line.trim();
+        if lexer.is_code() && !trimmed.is_empty() && !trimmed.starts_with("//") {
+            after_annotation = at_root && is_step_annotation(line);
+        }
+
+        lexer.format_line(line.trim_end_matches(&['\n', '\r'][..]));
+        offset += line.len();
+    }
+
+    offsets
+}
+
+/**
+ * Pretty print a CSML flow: steps at the root, 4 spaces per block level,
+ * single spaces in the code and at most one blank line between actions.
+ * Comments and strings are kept as they are.
+ */
+pub fn format_flow(source: &str) -> String {
+    let mut lines = format_lines(source);
+
+    // the comments and annotations right above a step or a function belong to it
+    for index in 0..lines.len() {
+        if let LineKind::Label | LineKind::Root = lines[index].kind {
+            let mut comment = index;
+            while comment > 0
+                && matches!(
+                    lines[comment - 1].kind,
+                    LineKind::Comment | LineKind::Annotation
+                )
+            {
+                comment -= 1;
+                lines[comment].level = 0;
+            }
+        }
+    }
+
+    let mut output: Vec<String> = vec![];
+    let mut previous: Option<&FormattedLine> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let next = lines[index + 1..]
+            .iter()
+            .find(|line| line.kind != LineKind::Blank);
+
+        match line.kind {
+            LineKind::Blank => {
+                let skip = match (previous, next) {
+                    (None, _) | (_, None) => true,
+                    (Some(previous), _) if previous.kind == LineKind::Blank => true,
+                    (Some(previous), _)
+                        if previous.kind == LineKind::Label || previous.content.ends_with('{') =>
+                    {
+                        true
+                    }
+                    (_, Some(next)) => next.content.starts_with('}'),
+                };
+
+                if !skip {
+                    output.push(String::new());
+                    previous = Some(line);
+                }
+                continue;
+            }
+            LineKind::Comment | LineKind::Annotation | LineKind::Label
+                if line.level == 0 && index > 0 =>
+            {
+                // one blank line before each step and the comments and annotations above it
+                let starts_step = lines[index..]
+                    .iter()
+                    .find(|line| !matches!(line.kind, LineKind::Comment | LineKind::Annotation))
+                    .filter(|line| line.kind == LineKind::Label)
+                    .is_some();
+                let after_comment = matches!(
+                    previous,
+                    Some(p) if matches!(p.kind, LineKind::Comment | LineKind::Annotation)
+                        && p.level == 0
+                );
+                let after_blank = match output.last() {
+                    Some(last) => last.is_empty(),
+                    None => true,
+                };
+
+                if starts_step && !after_comment && !after_blank {
+                    output.push(String::new());
+                }
+            }
+            _ => {}
+        }
+
+        match line.kind {
+            LineKind::Verbatim => output.push(line.content.to_owned()),
+            _ => output.push(format!("{}{}", INDENT.repeat(line.level), line.content)),
+        }
+        previous = Some(line);
+    }
+
+    while let Some(true) = output.last().map(|last| last.is_empty()) {
+        output.pop();
+    }
+
+    let mut formatted = output.join("\n");
+    formatted.push('\n');
+    formatted
+}
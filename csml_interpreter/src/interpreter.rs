@@ -37,6 +37,45 @@ fn step_vars_to_json(map: HashMap<String, Literal>) -> serde_json::Value {
     serde_json::json!(json_map)
 }
 
+fn should_pause(
+    data: &mut Data,
+    action: &Expr,
+    command_index: usize,
+    line: u32,
+    previous_line: Option<u32>,
+) -> bool {
+    let is_block = !matches!(action, Expr::ObjectExpr(..));
+    let flow = &data.context.flow;
+    let step = data.context.step.get_step_ref();
+
+    match &mut data.context.debugger {
+        Some(debugger) => {
+            debugger.should_pause(flow, step, command_index, line, previous_line, is_block)
+        }
+        None => false,
+    }
+}
+
+/**
+ * The debugger pauses the conversation like a hold, before the command at command_index
+ */
+fn debugger_hold(data: &Data, command_index: usize, line: u32) -> Hold {
+    let mut hold = Hold::new(
+        IndexInfo {
+            command_index,
+            loop_index: data.loop_indexes.clone(),
+        },
+        step_vars_to_json(data.step_vars.to_owned()),
+        data.context.step.get_step(),
+        data.context.flow.clone(),
+        data.previous_info.clone(),
+        false,
+    );
+    hold.breakpoint = Some(line);
+
+    hold
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTION
 ////////////////////////////////////////////////////////////////////////////////
@@ -47,22 +86,49 @@ pub fn interpret_scope(
     sender: &Option<mpsc::Sender<MSG>>,
 ) -> Result<MessageData, ErrorInfo> {
     let mut message_data = MessageData::default();
+    let mut previous_line = None;
 
     for (action, instruction_info) in actions.commands.iter() {
         let instruction_total = instruction_info.index + instruction_info.total;
+        let line = interval_from_expr(action).start_line;
+        let mut resumed = false;
 
         if let Some(hold) = &mut data.context.hold {
             if hold.index.command_index > instruction_total {
                 continue;
-            } else if hold.index.command_index == instruction_info.index {
+            // blocks share their index with their first command, the debugger only pauses on commands
+            } else if hold.index.command_index == instruction_info.index
+                && (hold.breakpoint.is_none() || matches!(action, Expr::ObjectExpr(..)))
+            {
+                let breakpoint = hold.breakpoint;
                 data.context.hold = None;
-                continue; // this command is the hold, we need to skip it in order to continue the conversation
+
+                // the debugger paused before this command: unlike a hold, it still needs to be executed
+                if breakpoint.is_none() {
+                    continue; // this command is the hold, we need to skip it in order to continue the conversation
+                }
             }
+            // this command is resumed or contains the resumed command
+            resumed = true;
         }
 
         if message_data.exit_condition.is_some() {
             return Ok(message_data);
         }
+        // a call aborted by the timeout of the step ends the step before its next action
+        data.limits
+            .check_step_timeout(interval_from_expr(action), &data.context.flow)?;
+
+        if !resumed && should_pause(data, action, instruction_info.index, line, previous_line) {
+            let hold = debugger_hold(data, instruction_info.index, line);
+
+            message_data.hold = Some(hold.to_owned());
+
+            MSG::send(&sender, MSG::Hold(hold));
+            message_data.exit_condition = Some(ExitCondition::Hold);
+            return Ok(message_data);
+        }
+        previous_line = Some(line);
 
         match action {
             Expr::ObjectExpr(ObjectType::Return(var)) => {
@@ -1,9 +1,11 @@
 mod actions;
+mod await_all;
 mod for_loop;
 mod if_statement;
 mod while_loop;
 
 pub use actions::match_actions;
+pub use await_all::await_all;
 pub use for_loop::for_loop;
 pub use if_statement::{evaluate_condition, solve_if_statement};
 pub use while_loop::while_loop;
@@ -172,7 +172,7 @@ pub fn match_actions(
                 tmp_loop_indexes,
                 tmp_loop_index,
                 mut tmp_step_count,
-                tmp_step_limit,
+                tmp_limits,
                 tmp_step_vars,
             ) = data.copy_scope();
 
@@ -187,11 +187,12 @@ pub fn match_actions(
                 tmp_loop_indexes,
                 tmp_loop_index,
                 &mut tmp_step_count,
-                tmp_step_limit,
+                tmp_limits,
                 tmp_step_vars,
                 data.previous_info.clone(),
                 data.custom_component,
                 data.native_component,
+                data.locales,
             );
             // #####################
 
@@ -287,6 +288,14 @@ pub fn match_actions(
                 sender,
             )?;
 
+            if update {
+                new_scope_data.limits.check_literal(
+                    lit,
+                    interval_from_expr(new),
+                    &new_scope_data.context.flow,
+                )?;
+            }
+
             save_literal_in_mem(
                 lit.to_owned(),
                 name,
@@ -540,7 +549,9 @@ pub fn match_actions(
 
             Ok(msg_data)
         }
-        ObjectType::Remember(name, variable) => {
+        ObjectType::Remember(name, variable)
+        | ObjectType::RememberTemp(name, variable)
+        | ObjectType::RememberUser(name, variable) => {
             let mut new_value = expr_to_literal(
                 variable,
                 &DisplayWarnings::On,
@@ -565,12 +576,25 @@ pub fn match_actions(
             let memory: HashMap<String, Literal> = data.get_all_memories();
             capture_variables(&mut &mut new_value, memory, &data.context.flow);
 
-            msg_data.add_to_memory(&name.ident, new_value.clone());
+            data.limits.check_literal(
+                &new_value,
+                interval_from_expr(variable),
+                &data.context.flow,
+            )?;
 
-            MSG::send(
-                &sender,
-                MSG::Remember(Memory::new(name.ident.to_owned(), new_value.clone())),
-            );
+            let memory = Memory::new(name.ident.to_owned(), new_value.clone());
+            match function {
+                // context variables are only kept for the current conversation
+                ObjectType::RememberTemp(..) => MSG::send(&sender, MSG::RememberTemp(memory)),
+                ObjectType::RememberUser(..) => {
+                    msg_data.add_to_memory(&name.ident, new_value.clone());
+                    MSG::send(&sender, MSG::RememberUser(memory));
+                }
+                _ => {
+                    msg_data.add_to_memory(&name.ident, new_value.clone());
+                    MSG::send(&sender, MSG::Remember(memory));
+                }
+            }
 
             data.context
                 .current
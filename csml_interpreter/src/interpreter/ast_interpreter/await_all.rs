@@ -0,0 +1,249 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveArray, PrimitiveType};
+use crate::data::{
+    ast::*, tokens::*, warnings::DisplayWarnings, Data, Literal, MessageData, Sandbox, MSG,
+};
+use crate::error_format::*;
+use crate::interpreter::{
+    builtins::{
+        api::{get_api_response, prepare_api_call, ApiCall},
+        http_builtin::{get_step_request, http_request},
+    },
+    json_to_rust::json_to_literal,
+    variable_handler::{expr_to_literal, resolve_fn_args},
+};
+
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc},
+    thread,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+type Response = Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo>;
+
+/**
+ * External call of await_all, prepared on the interpreter thread
+ */
+enum Call {
+    App(ApiCall),
+    Http {
+        object: HashMap<String, Literal>,
+        method: &'static str,
+        sandbox: Option<Arc<Sandbox>>,
+        interval: Interval,
+    },
+}
+
+enum Item {
+    Value(Literal),
+    Call(Call),
+}
+
+enum Running {
+    Value(Literal),
+    // the call, its response and the times it was sent and answered
+    Call(thread::JoinHandle<(Call, Response, DateTime<Utc>, DateTime<Utc>)>),
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_http_method(
+    object: &HashMap<String, Literal>,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<&'static str, ErrorInfo> {
+    let method = match object.get("method") {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            literal.primitive.to_string()
+        }
+        _ => "get".to_owned(),
+    };
+
+    match method.as_str() {
+        "delete" => Ok("delete"),
+        "put" => Ok("put"),
+        "patch" => Ok("patch"),
+        "post" => Ok("post"),
+        "get" => Ok("get"),
+        _ => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_HTTP_UNKNOWN_METHOD.to_string(),
+        )),
+    }
+}
+
+/**
+ * Fn and App calls and HTTP objects become calls, the other expressions are evaluated
+ */
+fn prepare_item(
+    expr: &Expr,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Item, ErrorInfo> {
+    if let Expr::ObjectExpr(ObjectType::BuiltIn(Function {
+        name,
+        args,
+        interval,
+    })) = expr
+    {
+        if name == FN || name == APP {
+            let args = resolve_fn_args(args, data, msg_data, &DisplayWarnings::On, sender)?;
+
+            return match prepare_api_call(&args, *interval, data) {
+                Ok(call) => Ok(Item::Call(Call::App(call))),
+                Err(err) => Ok(Item::Value(MSG::send_error_msg(sender, msg_data, Err(err)))),
+            };
+        }
+    }
+
+    let literal = expr_to_literal(expr, &DisplayWarnings::On, None, data, msg_data, sender)?;
+    if literal.content_type != "http" {
+        return Ok(Item::Value(literal));
+    }
+
+    let object = Literal::get_value::<HashMap<String, Literal>>(
+        &literal.primitive,
+        &data.context.flow,
+        literal.interval,
+        ERROR_HTTP_SEND.to_owned(),
+    )?;
+    let method = get_http_method(object, &data.context.flow, literal.interval)?;
+
+    data.limits
+        .check_step_timeout(literal.interval, &data.context.flow)?;
+
+    Ok(Item::Call(Call::Http {
+        object: get_step_request(object, &data.limits, literal.interval),
+        method,
+        sandbox: data.context.sandbox.clone(),
+        interval: literal.interval,
+    }))
+}
+
+fn send_call(call: &Call, flow_name: &str) -> Response {
+    match call {
+        Call::App(call) => call.send(),
+        Call::Http {
+            object,
+            sandbox: Some(sandbox),
+            interval,
+            ..
+        } => sandbox.http_request(object, flow_name, *interval),
+        Call::Http {
+            object,
+            method,
+            sandbox: None,
+            interval,
+        } => http_request(object, method, flow_name, *interval, false),
+    }
+}
+
+fn get_response_literal(
+    call: &Call,
+    response: Response,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    match call {
+        Call::App(call) => get_api_response(call, response, data, msg_data, sender),
+        Call::Http { interval, .. } => {
+            // the calls aborted by the timeout of the step end the step
+            if response.is_err() {
+                data.limits
+                    .check_step_timeout(*interval, &data.context.flow)?;
+            }
+
+            let literal = response.and_then(|(value, response_info)| {
+                let mut literal = json_to_literal(&value, *interval, &data.context.flow)?;
+                // add additional information about the http request response: status and headers
+                literal.add_info_block(response_info);
+
+                Ok(literal)
+            });
+
+            Ok(MSG::send_error_msg(sender, msg_data, literal))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * await_all [Fn("a"), Fn("b"), HTTP(url).get()]: the arguments of the calls are evaluated in
+ * order, then the calls are made concurrently. Returns the array of their results, in the
+ * order of the calls. A failed call is null and sends an error message, like a single call.
+ */
+pub fn await_all(
+    exprs: &[Expr],
+    interval: Interval,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let mut items = vec![];
+    for expr in exprs.iter() {
+        items.push(prepare_item(expr, data, msg_data, sender)?);
+    }
+
+    let running: Vec<_> = items
+        .into_iter()
+        .map(|item| match item {
+            Item::Value(literal) => Running::Value(literal),
+            Item::Call(call) => {
+                let flow_name = data.context.flow.to_owned();
+
+                Running::Call(thread::spawn(move || {
+                    let started_at = Utc::now();
+                    let response = send_call(&call, &flow_name);
+                    (call, response, started_at, Utc::now())
+                }))
+            }
+        })
+        .collect();
+
+    let mut array = vec![];
+    let mut is_secure = false;
+
+    for item in running {
+        let literal = match item {
+            Running::Value(literal) => literal,
+            Running::Call(handle) => match handle.join() {
+                Ok((call, response, started_at, ended_at)) => {
+                    if let Call::App(app_call) = &call {
+                        app_call.send_timing(started_at, ended_at, &response, sender);
+                    }
+                    get_response_literal(&call, response, data, msg_data, sender)?
+                }
+                Err(_) => {
+                    let err = gen_error_info(
+                        Position::new(interval, &data.context.flow),
+                        ERROR_AWAIT_ALL.to_owned(),
+                    );
+                    MSG::send_error_msg(sender, msg_data, Err(err))
+                }
+            },
+        };
+        if literal.secure_variable {
+            is_secure = true;
+        }
+
+        array.push(literal);
+    }
+
+    let mut literal = PrimitiveArray::get_literal(&array, interval);
+    literal.secure_variable = is_secure;
+
+    Ok(literal)
+}
@@ -23,7 +23,7 @@ pub fn for_loop(
     index: &Option<Identifier>,
     expr: &Expr,
     block: &Block,
-    _range_interval: &Interval,
+    range_interval: &Interval,
     mut msg_data: MessageData,
     data: &mut Data,
     sender: &Option<mpsc::Sender<MSG>>,
@@ -42,6 +42,9 @@ pub fn for_loop(
     let array = hold_index_start_loop(data, &mut array, &mut value_skipped);
 
     for (for_loop_index, elem) in array.iter().enumerate() {
+        data.limits
+            .check_loop(for_loop_index, *range_interval, &data.context.flow)?;
+
         data.step_vars
             .insert(ident.ident.to_owned(), elem.to_owned());
         if let Some(index) = index {
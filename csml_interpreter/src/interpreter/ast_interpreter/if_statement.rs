@@ -1,6 +1,8 @@
 use crate::data::{
-    ast::{Block, Expr, IfStatement, Infix, InstructionInfo},
+    ast::{Block, Expr, IfStatement, Infix, InstructionInfo, Interval},
     context::ContextStepInfo,
+    position::Position,
+    primitive::PrimitiveType,
     warnings::DisplayWarnings,
     Data, Literal, MessageData, MSG,
 };
@@ -9,7 +11,8 @@ use crate::interpreter::{
     interpret_scope,
     variable_handler::{
         expr_to_literal, get_var,
-        operations::{evaluate_infix, evaluate_postfix, valid_literal},
+        interval::interval_from_expr,
+        operations::{evaluate_infix, evaluate_postfix, get_coercion_warning, valid_literal},
     },
 };
 use std::sync::mpsc;
@@ -52,6 +55,22 @@ pub fn valid_condition(
     }
 }
 
+fn send_branch(interval: Interval, data: &Data, sender: &Option<mpsc::Sender<MSG>>) {
+    let flow = match &data.context.step {
+        ContextStepInfo::InsertedStep { flow, .. } => flow.to_owned(),
+        _ => data.context.flow.to_owned(),
+    };
+
+    MSG::send(
+        sender,
+        MSG::Branch {
+            flow,
+            line: interval.start_line,
+            column: interval.start_column,
+        },
+    );
+}
+
 fn evaluate_if_condition(
     cond: &Expr,
     mut msg_data: MessageData,
@@ -62,6 +81,7 @@ fn evaluate_if_condition(
     then_branch: &Option<Box<IfStatement>>,
 ) -> Result<MessageData, ErrorInfo> {
     if valid_condition(cond, data, &mut msg_data, sender) {
+        send_branch(interval_from_expr(cond), data, sender);
         msg_data = msg_data + interpret_scope(consequence, data, sender)?;
         return Ok(msg_data);
     }
@@ -90,32 +110,27 @@ pub fn evaluate_condition(
         data.context.flow.clone()
     };
 
-    match (expr1, expr2) {
-        (Expr::InfixExpr(i1, ex1, ex2), Expr::InfixExpr(i2, exp_1, exp_2)) => evaluate_infix(
-            &flow_name,
-            infix,
-            evaluate_condition(i1, ex1, ex2, data, msg_data, sender),
-            evaluate_condition(i2, exp_1, exp_2, data, msg_data, sender),
-        ),
-        (Expr::InfixExpr(i1, ex1, ex2), exp) => evaluate_infix(
-            &flow_name,
-            infix,
-            evaluate_condition(i1, ex1, ex2, data, msg_data, sender),
-            expr_to_literal(exp, &DisplayWarnings::Off, None, data, msg_data, sender),
-        ),
-        (exp, Expr::InfixExpr(i1, ex1, ex2)) => evaluate_infix(
-            &flow_name,
-            infix,
-            expr_to_literal(exp, &DisplayWarnings::Off, None, data, msg_data, sender),
-            evaluate_condition(i1, ex1, ex2, data, msg_data, sender),
-        ),
-        (exp_1, exp_2) => evaluate_infix(
-            &flow_name,
-            infix,
-            expr_to_literal(exp_1, &DisplayWarnings::Off, None, data, msg_data, sender),
-            expr_to_literal(exp_2, &DisplayWarnings::Off, None, data, msg_data, sender),
-        ),
+    let lhs = match expr1 {
+        Expr::InfixExpr(i1, ex1, ex2) => evaluate_condition(i1, ex1, ex2, data, msg_data, sender),
+        exp => expr_to_literal(exp, &DisplayWarnings::Off, None, data, msg_data, sender),
+    };
+    // lhs ?? rhs, the rhs is only evaluated when the lhs is Null
+    if let (Infix::Coalesce, Ok(literal)) = (infix, &lhs) {
+        if literal.primitive.get_type() != PrimitiveType::PrimitiveNull {
+            return lhs;
+        }
+    }
+    let rhs = match expr2 {
+        Expr::InfixExpr(i1, ex1, ex2) => evaluate_condition(i1, ex1, ex2, data, msg_data, sender),
+        exp => expr_to_literal(exp, &DisplayWarnings::Off, None, data, msg_data, sender),
+    };
+
+    if let (Some(warning), Ok(literal)) = (get_coercion_warning(infix, &lhs, &rhs), &lhs) {
+        let position = Position::new(literal.interval, &flow_name);
+        MSG::send_warning(sender, data, position, warning);
     }
+
+    evaluate_infix(&flow_name, infix, lhs, rhs)
 }
 
 pub fn solve_if_statement(
@@ -160,7 +175,8 @@ pub fn solve_if_statement(
             }
             Ok(msg_data)
         }
-        IfStatement::ElseStmt(consequence, ..) => {
+        IfStatement::ElseStmt(consequence, interval) => {
+            send_branch(*interval, data, sender);
             msg_data = msg_data + interpret_scope(consequence, data, sender)?;
             Ok(msg_data)
         }
@@ -20,12 +20,18 @@ use std::sync::mpsc;
 pub fn while_loop(
     cond: &Expr,
     block: &Block,
-    _range_interval: &Interval,
+    range_interval: &Interval,
     mut msg_data: MessageData,
     data: &mut Data,
     sender: &Option<mpsc::Sender<MSG>>,
 ) -> Result<MessageData, ErrorInfo> {
+    let mut iterations = 0;
+
     while valid_condition(cond, data, &mut msg_data, sender) {
+        data.limits
+            .check_loop(iterations, *range_interval, &data.context.flow)?;
+        iterations += 1;
+
         msg_data = msg_data + interpret_scope(block, data, sender)?;
 
         match msg_data.exit_condition {
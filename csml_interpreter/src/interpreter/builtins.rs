@@ -1,12 +1,25 @@
 pub mod api;
+pub mod circuit_breaker;
+pub mod contact;
 pub mod crypto;
 pub mod exists;
+pub mod experiment;
 pub mod format;
 pub mod functions;
+pub mod fuzzy_match;
+pub mod generate;
+pub mod handover;
 pub mod http_builtin;
 pub mod jwt;
+pub mod lambda;
+pub mod number;
+pub mod plugins;
+pub mod schedule;
+pub mod search_kb;
 pub mod smtp;
+pub mod tag;
 pub mod time;
+pub mod translate;
 
 pub mod tools;
 
@@ -18,14 +31,24 @@ use crate::interpreter::variable_handler::gen_generic_component::gen_generic_com
 use std::sync::mpsc;
 
 use api::api;
+use contact::*;
 use crypto::crypto;
 use exists::exists;
+use experiment::experiment;
 use format::*;
 use functions::*;
+use fuzzy_match::fuzzy_match;
+use generate::generate;
+use handover::handover;
 use http_builtin::http;
 use jwt::jwt;
+use number::*;
+use schedule::schedule;
+use search_kb::search_kb;
 use smtp::smtp;
+use tag::tag;
 use time::time;
+use translate::translate;
 // use uri::*;
 
 pub fn match_native_builtin(
@@ -58,19 +81,35 @@ pub fn match_builtin(
         BASE64 => base64(args, &data.context.flow, interval),
         HEX => hex(args, &data.context.flow, interval),
         FN | APP => api(args, interval, data, msg_data, sender),
-        ONE_OF => one_of(args, &data.context.flow, interval),
+        ONE_OF => one_of(args, &data.context.flow, interval, &data.context.sandbox),
         OR_BUILT_IN => or(args, &data.context.flow, interval),
-        SHUFFLE => shuffle(args, &data.context.flow, interval),
+        SHUFFLE => shuffle(args, &data.context.flow, interval, &data.context.sandbox),
         LENGTH => length(args, &data.context.flow, interval),
         FIND => find(args, &data.context.flow, interval),
-        RANDOM => random(interval),
+        FUZZY_MATCH => fuzzy_match(args, &data.context.flow, interval),
+        RANDOM => random(interval, &data.context.sandbox),
         DEBUG => debug(args, interval),
         FLOOR => floor(args, &data.context.flow, interval),
-        UUID => uuid_command(args, &data.context.flow, interval),
+        UUID => uuid_command(args, &data.context.flow, interval, &data.context.sandbox),
         JWT => jwt(args, &data.context.flow, interval),
         CRYPTO => crypto(args, &data.context.flow, interval),
-        TIME => time(args, &data.context.flow, interval),
+        TIME => time(args, &data.context.flow, interval, &data.context.sandbox),
         EXISTS => exists(args, data, interval),
+        SCHEDULE => schedule(args, &data.context.flow, interval, sender),
+        HANDOVER => handover(args, &data.context.flow, interval, sender),
+        TAG => tag(args, &data.context.flow, interval, sender),
+        EXPERIMENT => experiment(args, data, interval, msg_data, sender),
+        GENERATE => generate(args, data, interval, sender, false),
+        GENERATE_STREAM => generate(args, data, interval, sender, true),
+        SEARCH_KB => search_kb(args, data, interval),
+        TRANSLATE => translate(args, data, interval),
+        FORMAT_NUMBER => format_number_builtin(args, data, interval),
+        CURRENCY => currency(args, data, interval),
+        TO_NUMBER => to_number(args, data, interval),
+        CONVERT_UNIT => convert_unit_builtin(args, &data.context.flow, interval),
+        IS_EMAIL => is_email_builtin(args, &data.context.flow, interval),
+        IS_PHONE => is_phone(args, &data.context.flow, interval),
+        NORMALIZE_PHONE => normalize_phone_builtin(args, &data.context.flow, interval),
 
         //old builtin
         _object => object(args, &data.context.flow, interval),
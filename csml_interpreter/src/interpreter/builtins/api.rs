@@ -1,46 +1,60 @@
 use crate::data::error_info::ErrorInfo;
 use crate::data::position::Position;
-use crate::data::primitive::{PrimitiveObject, PrimitiveString, PrimitiveType};
-use crate::data::{ast::Interval, ApiInfo, ArgsType, Client, Data, Literal, MessageData, MSG};
+use crate::data::primitive::{
+    PrimitiveInt, PrimitiveNull, PrimitiveObject, PrimitiveString, PrimitiveType,
+};
+use crate::data::{
+    ast::Interval, csml_logs::*, execution_limits::bound_timeout, AppOptions, ArgsType, BotApp,
+    Client, Data, Literal, MessageData, Sandbox, MSG,
+};
 use crate::error_format::*;
 use crate::interpreter::{
-    builtins::{http_builtin::http_request, tools::*},
+    builtins::{circuit_breaker, http_builtin::http_request, lambda::invoke_lambda, tools::*},
     json_to_rust::interpolate,
 };
 
-use std::{collections::HashMap, sync::mpsc};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+fn get_fn_id(args: &ArgsType, flow_name: &str, interval: Interval) -> Result<String, ErrorInfo> {
+    match args.get("fn_id", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Literal::get_value::<String>(
+                &literal.primitive,
+                flow_name,
+                literal.interval,
+                ERROR_FN_ID.to_owned(),
+            )
+            .map(|fn_id| fn_id.to_owned())
+        }
+        _ => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_FN_ID.to_owned(),
+        )),
+    }
+}
 
 fn format_body(
     args: &ArgsType,
+    fn_id: &str,
     flow_name: &str,
     interval: Interval,
     client: Client,
 ) -> Result<Literal, ErrorInfo> {
     let mut map: HashMap<String, Literal> = HashMap::new();
 
-    match args.get("fn_id", 0) {
-        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
-            let fn_id = Literal::get_value::<String>(
-                &literal.primitive,
-                flow_name,
-                literal.interval,
-                ERROR_FN_ID.to_owned(),
-            )?;
+    map.insert(
+        "function_id".to_owned(),
+        PrimitiveString::get_literal(fn_id, interval),
+    );
 
-            map.insert(
-                "function_id".to_owned(),
-                PrimitiveString::get_literal(&fn_id, interval),
-            );
-        }
-        _ => {
-            return Err(gen_error_info(
-                Position::new(interval, flow_name),
-                ERROR_FN_ID.to_owned(),
-            ))
-        }
-    };
     let mut sub_map = HashMap::new();
-    args.populate(&mut sub_map, &["fn_id"], flow_name, interval)?;
+    args.populate(&mut sub_map, &["fn_id", "_timeout", "_retries"], flow_name, interval)?;
 
     let client = client_to_json(&client, interval);
 
@@ -70,18 +84,279 @@ fn format_headers(interval: Interval) -> HashMap<String, Literal> {
     header
 }
 
-pub fn api(
-    args: ArgsType,
+/**
+ * Options of a call can be overridden with the _timeout and _retries named arguments,
+ * ex: App("fn_id", _timeout = 2000, _retries = 3)
+ */
+fn get_call_options(args: &ArgsType, options: &AppOptions, flow_name: &str) -> AppOptions {
+    let mut options = options.to_owned();
+
+    if let ArgsType::Named(map) = args {
+        for (key, literal) in map.iter() {
+            let value = match Literal::get_value::<i64>(
+                &literal.primitive,
+                flow_name,
+                literal.interval,
+                ERROR_FN_ID.to_owned(),
+            ) {
+                Ok(value) if *value >= 0 => *value,
+                _ => continue,
+            };
+
+            match key.as_str() {
+                "_timeout" => options.timeout = Some(value as u64),
+                "_retries" => options.retries = value as u32,
+                _ => {}
+            }
+        }
+    }
+
+    options
+}
+
+fn error_literal(err: ErrorInfo, interval: Interval) -> Literal {
+    let mut error_lit = PrimitiveNull::get_literal(interval);
+    error_lit.additional_info = err.additional_info;
+
+    error_lit
+}
+
+fn is_circuit_open_error(err: &ErrorInfo) -> bool {
+    match &err.additional_info {
+        Some(info) => info.contains_key("error_type"),
+        None => false,
+    }
+}
+
+fn send_http_request(
+    url: &str,
+    auth_header: &Option<String>,
+    timeout: Option<u64>,
+    body: &Literal,
+    flow_name: &str,
     interval: Interval,
-    data: &mut Data,
-    msg_data: &mut MessageData,
-    sender: &Option<mpsc::Sender<MSG>>,
-) -> Result<Literal, ErrorInfo> {
-    let (client, url) = match &data.context.api_info {
-        Some(ApiInfo {
-            client,
-            apps_endpoint,
-        }) => (client.to_owned(), apps_endpoint.to_owned()),
+) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo> {
+    let mut http: HashMap<String, Literal> = HashMap::new();
+    let mut header = format_headers(interval);
+
+    if let Some(auth_header) = auth_header {
+        header.insert(
+            "authorization".to_owned(),
+            PrimitiveString::get_literal(auth_header, interval),
+        );
+    }
+
+    http.insert("url".to_owned(), PrimitiveString::get_literal(url, interval));
+    http.insert(
+        "header".to_owned(),
+        PrimitiveObject::get_literal(&header, interval),
+    );
+    http.insert("body".to_owned(), body.to_owned());
+
+    if let Some(timeout) = timeout {
+        http.insert(
+            "timeout".to_owned(),
+            PrimitiveInt::get_literal(timeout as i64, interval),
+        );
+    }
+
+    http_request(&http, "post", flow_name, interval, true)
+}
+
+/**
+ * Make the call with the retries and circuit breaker of the options.
+ * The circuit is identified by the url of the app or the ARN of the lambda.
+ * The call is given its timeout, bounded by the deadline of the step: there is no retry
+ * once the deadline is passed.
+ */
+fn send_request<F>(
+    circuit_key: &str,
+    options: &AppOptions,
+    deadline: Option<Instant>,
+    flow_name: &str,
+    interval: Interval,
+    call: F,
+) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo>
+where
+    F: Fn(Option<u64>) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo>,
+{
+    if let Some(circuit_breaker) = &options.circuit_breaker {
+        if !circuit_breaker::is_call_allowed(circuit_key, circuit_breaker) {
+            let mut err = gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_APP_CIRCUIT_OPEN.to_owned(),
+            );
+            err.add_info(
+                "error_type",
+                PrimitiveString::get_literal("circuit_open", interval),
+            );
+
+            return Err(err);
+        }
+    }
+
+    let mut retries = 0;
+    let response = loop {
+        let is_expired = matches!(deadline, Some(deadline) if Instant::now() >= deadline);
+
+        match call(bound_timeout(options.timeout, deadline)) {
+            Err(_) if retries < options.retries && !is_expired => {
+                retries += 1;
+
+                csml_logger(
+                    CsmlLog::new(
+                        None,
+                        Some(flow_name.to_owned()),
+                        Some(interval.start_line),
+                        format!("App call failed, retry {}/{}", retries, options.retries),
+                    ),
+                    LogLvl::Warn,
+                );
+
+                if let Some(backoff) = options.backoff {
+                    let delay = backoff.saturating_mul(2u64.saturating_pow(retries - 1));
+                    thread::sleep(Duration::from_millis(delay));
+                }
+            }
+            response => break response,
+        }
+    };
+
+    if let Some(circuit_breaker) = &options.circuit_breaker {
+        match response {
+            Ok(_) => circuit_breaker::record_success(circuit_key),
+            Err(_) => circuit_breaker::record_failure(circuit_key, circuit_breaker),
+        }
+    }
+
+    response
+}
+
+/**
+ * Call of an app prepared on the interpreter thread, that can be sent from another thread
+ */
+pub struct ApiCall {
+    fn_id: String,
+    body: Literal,
+    app: Option<BotApp>,
+    url: Option<String>,
+    options: AppOptions,
+    // deadline of the timeout of the step
+    deadline: Option<Instant>,
+    // the response is injected in the sandbox
+    sandbox: Option<Arc<Sandbox>>,
+    flow_name: String,
+    interval: Interval,
+}
+
+impl ApiCall {
+    /**
+     * Make the call with the retries and circuit breaker of its options
+     */
+    pub fn send(&self) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo> {
+        let (flow_name, interval, options) = (&self.flow_name, self.interval, &self.options);
+        let deadline = self.deadline;
+
+        if let Some(sandbox) = &self.sandbox {
+            return sandbox.app_request(&self.fn_id, flow_name, interval);
+        }
+
+        match (&self.app, &self.url) {
+            (Some(app), _) => match &app.lambda {
+                Some(function) => send_request(
+                    &function.function_arn,
+                    options,
+                    deadline,
+                    flow_name,
+                    interval,
+                    |timeout| invoke_lambda(function, &self.body, timeout, flow_name, interval),
+                ),
+                None => send_request(
+                    &app.url,
+                    options,
+                    deadline,
+                    flow_name,
+                    interval,
+                    |timeout| {
+                        send_http_request(
+                            &app.url,
+                            &app.auth_header,
+                            timeout,
+                            &self.body,
+                            flow_name,
+                            interval,
+                        )
+                    },
+                ),
+            },
+            (None, Some(url)) => {
+                send_request(url, options, deadline, flow_name, interval, |timeout| {
+                    send_http_request(url, &None, timeout, &self.body, flow_name, interval)
+                })
+            }
+            (None, None) => Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_FN_ENDPOINT.to_owned(),
+            )),
+        }
+    }
+
+    /**
+     * Report a call and its duration to the engine, for the traces of the calls.
+     * The injected responses are not reported.
+     */
+    pub fn send_timing(
+        &self,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        response: &Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo>,
+        sender: &Option<mpsc::Sender<MSG>>,
+    ) {
+        if self.sandbox.is_some() {
+            return;
+        }
+
+        MSG::send(
+            sender,
+            MSG::AppCall {
+                fn_id: self.fn_id.to_owned(),
+                flow: self.flow_name.to_owned(),
+                started_at,
+                ended_at,
+                error: response.as_ref().err().map(|err| err.message.to_owned()),
+            },
+        );
+    }
+}
+
+/**
+ * Prepare the call of Fn or App with its arguments
+ */
+pub fn prepare_api_call(
+    args: &ArgsType,
+    interval: Interval,
+    data: &Data,
+) -> Result<ApiCall, ErrorInfo> {
+    // the apps of the bot are not needed to answer with the injected responses
+    if let Some(sandbox) = &data.context.sandbox {
+        return Ok(ApiCall {
+            fn_id: get_fn_id(args, &data.context.flow, interval)?,
+            body: PrimitiveNull::get_literal(interval),
+            app: None,
+            url: None,
+            options: AppOptions::default(),
+            deadline: None,
+            sandbox: Some(sandbox.to_owned()),
+            flow_name: data.context.flow.to_owned(),
+            interval,
+        });
+    }
+
+    data.limits
+        .check_step_timeout(interval, &data.context.flow)?;
+
+    let api_info = match &data.context.api_info {
+        Some(api_info) => api_info.to_owned(),
         None => {
             return Err(gen_error_info(
                 Position::new(interval, &data.context.flow),
@@ -90,36 +365,95 @@ pub fn api(
         }
     };
 
-    let mut http: HashMap<String, Literal> = HashMap::new();
-    let header = format_headers(interval);
-    let body = format_body(&args, &data.context.flow, interval, client)?;
+    let fn_id = get_fn_id(args, &data.context.flow, interval)?;
+    let body = format_body(args, &fn_id, &data.context.flow, interval, api_info.client)?;
 
-    http.insert(
-        "url".to_owned(),
-        PrimitiveString::get_literal(&url, interval),
-    );
+    let flow_name = data.context.flow.to_owned();
+    let app = api_info.apps.into_iter().find(|app| app.name == fn_id);
+
+    // named apps of the bot take precedence over the apps_endpoint
+    let options = match (&app, &api_info.apps_endpoint) {
+        (Some(app), _) => get_call_options(args, &app.options, &flow_name),
+        (None, Some(_)) => get_call_options(args, &api_info.apps_endpoint_options, &flow_name),
+        (None, None) => {
+            return Err(gen_error_info(
+                Position::new(interval, &flow_name),
+                ERROR_FN_ENDPOINT.to_owned(),
+            ))
+        }
+    };
 
-    let lit_header = PrimitiveObject::get_literal(&header, interval);
-    http.insert("header".to_owned(), lit_header);
-    http.insert("body".to_owned(), body);
+    Ok(ApiCall {
+        fn_id,
+        body,
+        app,
+        url: api_info.apps_endpoint,
+        options,
+        deadline: data.limits.step_deadline,
+        sandbox: None,
+        flow_name,
+        interval,
+    })
+}
 
-    match http_request(&http, "post", &data.context.flow, interval, true) {
+/**
+ * Literal of the response of a call, the errors of named apps are returned as error literals
+ */
+pub fn get_api_response(
+    call: &ApiCall,
+    response: Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo>,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let interval = call.interval;
+
+    let error = match response {
         Ok((value, response_info)) => match value.get("data") {
             Some(value) => {
                 let mut literal = interpolate(value, interval, data, msg_data, sender)?;
                 // add additional information about the http request response: status and headers
                 literal.add_info_block(response_info);
 
-                Ok(literal)
-            }
-            None => {
-                let err = gen_error_info(
-                    Position::new(interval, &data.context.flow),
-                    ERROR_HTTP_NOT_DATA.to_owned(),
-                );
-                Ok(MSG::send_error_msg(sender, msg_data, Err(err)))
+                return Ok(literal);
             }
+            None => gen_error_info(
+                Position::new(interval, &data.context.flow),
+                ERROR_HTTP_NOT_DATA.to_owned(),
+            ),
         },
-        Err(err) => Ok(MSG::send_error_msg(sender, msg_data, Err(err))),
+        // the calls aborted by the timeout of the step end the step
+        Err(err) => {
+            data.limits
+                .check_step_timeout(interval, &data.context.flow)?;
+            err
+        }
+    };
+
+    match &call.app {
+        // errors of named apps are returned as error literals, without error message
+        Some(app) => {
+            let mut literal = error_literal(error, interval);
+            literal.add_info("app", PrimitiveString::get_literal(&app.name, interval));
+
+            Ok(literal)
+        }
+        None if is_circuit_open_error(&error) => Ok(error_literal(error, interval)),
+        None => Ok(MSG::send_error_msg(sender, msg_data, Err(error))),
     }
 }
+
+pub fn api(
+    args: ArgsType,
+    interval: Interval,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let call = prepare_api_call(&args, interval, data)?;
+    let started_at = Utc::now();
+    let response = call.send();
+    call.send_timing(started_at, Utc::now(), &response, sender);
+
+    get_api_response(&call, response, data, msg_data, sender)
+}
@@ -0,0 +1,54 @@
+use crate::data::CircuitBreakerOptions;
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+////////////////////////////////////////////////////////////////////////////////
+/// DATA TYPES
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+// circuits are shared by all the conversations handled by this process, by endpoint url
+static CIRCUITS: Lazy<Mutex<HashMap<String, CircuitState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+////////////////////////////////////////////////////////////////////////////////
+/// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Calls are refused while the circuit is open. Once reset_timeout is elapsed,
+ * calls are allowed again: the next failure opens the circuit again, a success closes it.
+ */
+pub fn is_call_allowed(url: &str, options: &CircuitBreakerOptions) -> bool {
+    let circuits = CIRCUITS.lock().unwrap();
+
+    match circuits.get(url).and_then(|circuit| circuit.opened_at) {
+        Some(opened_at) => opened_at.elapsed() >= Duration::from_millis(options.reset_timeout),
+        None => true,
+    }
+}
+
+pub fn record_success(url: &str) {
+    let mut circuits = CIRCUITS.lock().unwrap();
+
+    circuits.remove(url);
+}
+
+pub fn record_failure(url: &str, options: &CircuitBreakerOptions) {
+    let mut circuits = CIRCUITS.lock().unwrap();
+    let circuit = circuits.entry(url.to_owned()).or_default();
+
+    circuit.failures += 1;
+
+    if circuit.failures >= options.failure_threshold {
+        circuit.opened_at = Some(Instant::now());
+    }
+}
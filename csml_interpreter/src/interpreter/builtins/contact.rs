@@ -0,0 +1,97 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{
+    tools_contact::*, PrimitiveBoolean, PrimitiveNull, PrimitiveString, PrimitiveType,
+};
+use crate::data::{ast::Interval, ArgsType, Literal};
+use crate::error_format::*;
+use crate::interpreter::builtins::tools::get_arg;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * The value to check: the values that are not strings are never valid
+ */
+fn get_value(args: &ArgsType, key: &str) -> Option<Option<String>> {
+    match get_arg(args, key, 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(Some(literal.primitive.to_string()))
+        }
+        Some(_) => Some(None),
+        None => None,
+    }
+}
+
+/**
+ * Normalized phone of Is_Phone(phone, country) and Normalize_Phone(phone, country)
+ */
+fn get_phone(
+    args: &ArgsType,
+    error_message: &str,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Option<String>, ErrorInfo> {
+    let error = || gen_error_info(Position::new(interval, flow_name), error_message.to_owned());
+
+    let phone = get_value(args, "phone").ok_or_else(error)?;
+    let country = match get_arg(args, "country", 1) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(get_phone_country(&literal.primitive.to_string()).ok_or_else(error)?)
+        }
+        Some(_) => return Err(error()),
+        None => None,
+    };
+
+    Ok(phone.and_then(|phone| normalize_phone(&phone, country)))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Is_Email("jane@example.com") returns true
+ */
+pub fn is_email_builtin(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    match get_value(&args, "email") {
+        Some(email) => Ok(PrimitiveBoolean::get_literal(
+            matches!(email, Some(email) if is_email(email.trim())),
+            interval,
+        )),
+        None => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_IS_EMAIL.to_owned(),
+        )),
+    }
+}
+
+/**
+ * Is_Phone("06 12 34 56 78", "FR") returns true. Without country, only the numbers written
+ * in the international format are valid.
+ */
+pub fn is_phone(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+    let phone = get_phone(&args, ERROR_IS_PHONE, flow_name, interval)?;
+
+    Ok(PrimitiveBoolean::get_literal(phone.is_some(), interval))
+}
+
+/**
+ * Normalize_Phone("06 12 34 56 78", "FR") returns "+33612345678" (E.164), Null if the number
+ * is not valid
+ */
+pub fn normalize_phone_builtin(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    match get_phone(&args, ERROR_NORMALIZE_PHONE, flow_name, interval)? {
+        Some(phone) => Ok(PrimitiveString::get_literal(&phone, interval)),
+        None => Ok(PrimitiveNull::get_literal(interval)),
+    }
+}
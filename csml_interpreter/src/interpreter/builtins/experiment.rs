@@ -0,0 +1,188 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveObject, PrimitiveString, PrimitiveType};
+use crate::data::{
+    ast::Interval, sandbox::with_rng, ArgsType, Client, Data, Literal, Memory, MessageData,
+    Sandbox, MSG,
+};
+use crate::error_format::*;
+use crate::interpreter::builtins::tools::get_arg;
+
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc};
+
+// memory of the variants assigned to the client: {experiment: variant}
+const EXPERIMENTS_MEMORY: &str = "_experiments";
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * FNV-1a: unlike the std hasher, its values never change between builds
+ */
+fn hash(value: &str) -> u64 {
+    value.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/**
+ * The same client always falls in the same bucket of an experiment. Without client
+ * (interpreter used without the engine), the bucket is random.
+ */
+fn get_bucket(
+    name: &str,
+    client: &Option<Client>,
+    total: u64,
+    sandbox: &Option<Arc<Sandbox>>,
+) -> u64 {
+    match client {
+        Some(client) => {
+            let key = format!(
+                "{}:{}:{}:{}",
+                name, client.bot_id, client.channel_id, client.user_id
+            );
+            hash(&key) % total
+        }
+        None => with_rng(sandbox, |rng| rng.gen_range(0..total)),
+    }
+}
+
+fn pick_variant(variants: &[String], weights: &[u64], bucket: u64) -> String {
+    let mut bucket = bucket;
+
+    for (variant, weight) in variants.iter().zip(weights) {
+        if bucket < *weight {
+            return variant.to_owned();
+        }
+        bucket -= weight;
+    }
+
+    variants[variants.len() - 1].to_owned()
+}
+
+fn get_strings(literal: &Literal) -> Option<Vec<String>> {
+    match literal.primitive.to_json() {
+        serde_json::Value::Array(values) => values
+            .iter()
+            .map(|value| match value.as_str() {
+                Some(value) if !value.is_empty() => Some(value.to_owned()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn get_weights(literal: &Literal) -> Option<Vec<u64>> {
+    match literal.primitive.to_json() {
+        serde_json::Value::Array(values) => values.iter().map(|value| value.as_u64()).collect(),
+        _ => None,
+    }
+}
+
+fn get_assignments(data: &Data) -> HashMap<String, Literal> {
+    match data.context.current.get(EXPERIMENTS_MEMORY) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveObject => {
+            match literal.primitive.as_any().downcast_ref::<PrimitiveObject>() {
+                Some(object) => object.value.clone(),
+                None => HashMap::new(),
+            }
+        }
+        _ => HashMap::new(),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Assign the client to a variant of an experiment and return it.
+ * The variant is drawn from a hash of the experiment and the client, following the weights
+ * (equal by default), then remembered in _experiments: the client keeps its variant when the
+ * weights change, as long as it is still one of the variants. The engine is told about new assignments for the analytics.
+ */
+pub fn experiment(
+    args: ArgsType,
+    data: &mut Data,
+    interval: Interval,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let error = || {
+        gen_error_info(
+            Position::new(interval, &data.context.flow),
+            ERROR_EXPERIMENT.to_owned(),
+        )
+    };
+
+    let name = match get_arg(&args, "name", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            literal.primitive.to_string()
+        }
+        _ => return Err(error()),
+    };
+    let variants = match get_arg(&args, "variants", 1) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveArray => {
+            get_strings(literal).ok_or_else(error)?
+        }
+        _ => return Err(error()),
+    };
+    let weights = match get_arg(&args, "weights", 2) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveArray => {
+            get_weights(literal).ok_or_else(error)?
+        }
+        Some(_) => return Err(error()),
+        None => vec![1; variants.len()],
+    };
+
+    let total: u64 = weights.iter().sum();
+    if name.trim().is_empty()
+        || variants.is_empty()
+        || weights.len() != variants.len()
+        || total == 0
+    {
+        return Err(error());
+    }
+
+    let mut assignments = get_assignments(data);
+    if let Some(variant) = assignments.get(&name) {
+        let variant = variant.primitive.to_string();
+        if variants.contains(&variant) {
+            return Ok(PrimitiveString::get_literal(&variant, interval));
+        }
+    }
+
+    let bucket = get_bucket(&name, &data.context.client, total, &data.context.sandbox);
+    let variant = pick_variant(&variants, &weights, bucket);
+
+    assignments.insert(
+        name.to_owned(),
+        PrimitiveString::get_literal(&variant, interval),
+    );
+    let memory = PrimitiveObject::get_literal(&assignments, interval);
+
+    msg_data.add_to_memory(EXPERIMENTS_MEMORY, memory.clone());
+    MSG::send(
+        sender,
+        MSG::Remember(Memory::new(EXPERIMENTS_MEMORY.to_owned(), memory.clone())),
+    );
+    MSG::send(
+        sender,
+        MSG::Experiment {
+            name,
+            variant: variant.to_owned(),
+        },
+    );
+    data.context
+        .current
+        .insert(EXPERIMENTS_MEMORY.to_owned(), memory);
+
+    Ok(PrimitiveString::get_literal(&variant, interval))
+}
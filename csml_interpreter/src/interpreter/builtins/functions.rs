@@ -2,21 +2,27 @@ use crate::data::position::Position;
 use crate::data::primitive::{
     PrimitiveArray, PrimitiveBoolean, PrimitiveFloat, PrimitiveInt, PrimitiveString,
 };
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::data::{ast::Interval, ArgsType, Literal};
+use crate::data::{ast::Interval, sandbox::with_rng, ArgsType, Literal, Sandbox};
 use crate::error_format::*;
 use uuid::v1::{Context, Timestamp};
-use uuid::Uuid;
+use uuid::{Builder, Uuid};
 
 use rand::seq::SliceRandom;
 use rand::Rng;
+use std::sync::Arc;
 
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
-pub fn one_of(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+pub fn one_of(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+    sandbox: &Option<Arc<Sandbox>>,
+) -> Result<Literal, ErrorInfo> {
     match args.get("array", 0) {
         Some(literal) => {
             let res = Literal::get_value::<Vec<Literal>>(
@@ -25,7 +31,11 @@ pub fn one_of(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Lit
                 literal.interval,
                 ERROR_ONE_OF.to_owned(),
             )?;
-            match res.get(rand::thread_rng().gen_range(0..res.len())) {
+            let index = match res.len() {
+                0 => 0,
+                len => with_rng(sandbox, |rng| rng.gen_range(0..len)),
+            };
+            match res.get(index) {
                 Some(lit) => Ok(lit.to_owned()),
                 None => Err(gen_error_info(
                     Position::new(literal.interval, flow_name),
@@ -53,7 +63,12 @@ pub fn or(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal
     }
 }
 
-pub fn shuffle(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+pub fn shuffle(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+    sandbox: &Option<Arc<Sandbox>>,
+) -> Result<Literal, ErrorInfo> {
     match args.get("array", 0) {
         Some(literal) => {
             let res = Literal::get_value::<Vec<Literal>>(
@@ -63,7 +78,7 @@ pub fn shuffle(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Li
                 ERROR_SHUFFLE.to_owned(),
             )?;
             let mut vec = res.to_owned();
-            vec.shuffle(&mut rand::thread_rng());
+            with_rng(sandbox, |rng| vec.shuffle(rng));
             Ok(PrimitiveArray::get_literal(&vec, literal.interval))
         }
         None => Err(gen_error_info(
@@ -169,10 +184,8 @@ pub fn find(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Liter
     }
 }
 
-pub fn random(interval: Interval) -> Result<Literal, ErrorInfo> {
-    let mut rng = rand::thread_rng();
-
-    let random: f64 = rng.gen();
+pub fn random(interval: Interval, sandbox: &Option<Arc<Sandbox>>) -> Result<Literal, ErrorInfo> {
+    let random: f64 = with_rng(sandbox, |rng| rng.gen());
 
     Ok(PrimitiveFloat::get_literal(random, interval))
 }
@@ -195,14 +208,27 @@ pub fn floor(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Lite
     }
 }
 
+fn new_uuid_v4(sandbox: &Option<Arc<Sandbox>>) -> Uuid {
+    match sandbox {
+        Some(_) => {
+            let mut bytes = [0; 16];
+            with_rng(sandbox, |rng| rng.fill_bytes(&mut bytes));
+
+            Builder::from_random_bytes(bytes).into_uuid()
+        }
+        None => Uuid::new_v4(),
+    }
+}
+
 pub fn uuid_command(
     args: ArgsType,
     flow_name: &str,
     interval: Interval,
+    sandbox: &Option<Arc<Sandbox>>,
 ) -> Result<Literal, ErrorInfo> {
     if args.len() == 0 {
         return Ok(PrimitiveString::get_literal(
-            &Uuid::new_v4().to_string(),
+            &new_uuid_v4(sandbox).to_string(),
             interval,
         ));
     }
@@ -218,25 +244,21 @@ pub fn uuid_command(
 
             match arg {
                 arg if arg == "v1" => {
-                    let time = SystemTime::now().duration_since(UNIX_EPOCH)?;
-                    let context = Context::new(rand::thread_rng().gen());
+                    let time = match sandbox {
+                        Some(sandbox) => Duration::from_millis(sandbox.now().max(0) as u64),
+                        None => SystemTime::now().duration_since(UNIX_EPOCH)?,
+                    };
+                    let (counter, node_id) = with_rng(sandbox, |rng| (rng.gen(), rng.gen()));
+                    let context = Context::new(counter);
                     let ts = Timestamp::from_unix(&context, time.as_secs(), time.subsec_nanos());
 
-                    let node_id = &[
-                        rand::thread_rng().gen(),
-                        rand::thread_rng().gen(),
-                        rand::thread_rng().gen(),
-                        rand::thread_rng().gen(),
-                        rand::thread_rng().gen(),
-                        rand::thread_rng().gen(),
-                    ];
                     Ok(PrimitiveString::get_literal(
-                        &Uuid::new_v1(ts, node_id).hyphenated().to_string(),
+                        &Uuid::new_v1(ts, &node_id).hyphenated().to_string(),
                         interval,
                     ))
                 }
                 arg if arg == "v4" => Ok(PrimitiveString::get_literal(
-                    &Uuid::new_v4().to_string(),
+                    &new_uuid_v4(sandbox).to_string(),
                     interval,
                 )),
                 _ => Err(gen_error_info(
@@ -0,0 +1,88 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{
+    tools_fuzzy::{best_match, Similarity},
+    PrimitiveFloat, PrimitiveInt, PrimitiveNull, PrimitiveObject, PrimitiveString, PrimitiveType,
+};
+use crate::data::{ast::Interval, ArgsType, Literal};
+use crate::error_format::*;
+use crate::interpreter::builtins::tools::get_arg;
+
+use std::collections::HashMap;
+
+const DEFAULT_THRESHOLD: f64 = 0.7;
+
+fn get_options(literal: &Literal) -> Option<Vec<String>> {
+    match literal.primitive.to_json() {
+        serde_json::Value::Array(values) if !values.is_empty() => values
+            .iter()
+            .map(|value| value.as_str().map(str::to_owned))
+            .collect(),
+        _ => None,
+    }
+}
+
+/**
+ * Option the most similar to the input, to tolerate typos in menus without an NLU service:
+ * Fuzzy_Match(input, options, threshold=0.7, algorithm="levenshtein" | "jaro_winkler")
+ * returns {"match": String, "score": Float, "index": Int}, or Null when the score of the best
+ * option is under the threshold. The texts are compared without their case.
+ */
+pub fn fuzzy_match(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let error = || {
+        gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_FUZZY_MATCH.to_owned(),
+        )
+    };
+
+    let input = match get_arg(&args, "input", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            literal.primitive.to_string()
+        }
+        _ => return Err(error()),
+    };
+    let options = match get_arg(&args, "options", 1) {
+        Some(literal) => get_options(literal).ok_or_else(error)?,
+        None => return Err(error()),
+    };
+    let threshold = match get_arg(&args, "threshold", 2) {
+        Some(literal) => match literal.primitive.to_json().as_f64() {
+            Some(threshold) if (0.0..=1.0).contains(&threshold) => threshold,
+            _ => return Err(error()),
+        },
+        None => DEFAULT_THRESHOLD,
+    };
+    let algorithm = match get_arg(&args, "algorithm", 3) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Similarity::from_name(&literal.primitive.to_string()).ok_or_else(error)?
+        }
+        Some(_) => return Err(error()),
+        None => Similarity::Levenshtein,
+    };
+
+    match best_match(&input, &options, algorithm) {
+        Some((index, score)) if score >= threshold => {
+            let mut result = HashMap::new();
+            result.insert(
+                "match".to_owned(),
+                PrimitiveString::get_literal(&options[index], interval),
+            );
+            result.insert(
+                "score".to_owned(),
+                PrimitiveFloat::get_literal(score, interval),
+            );
+            result.insert(
+                "index".to_owned(),
+                PrimitiveInt::get_literal(index as i64, interval),
+            );
+
+            Ok(PrimitiveObject::get_literal(&result, interval))
+        }
+        _ => Ok(PrimitiveNull::get_literal(interval)),
+    }
+}
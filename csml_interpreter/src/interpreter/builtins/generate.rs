@@ -0,0 +1,393 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveString, PrimitiveType};
+use crate::data::{
+    ast::Interval, csml_logs::*, sandbox::check_network, tokens::GENERATE, ArgsType, Data,
+    Literal, MSG,
+};
+use crate::error_format::*;
+use crate::interpreter::builtins::tools::get_arg;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use std::time::Duration;
+
+const DEFAULT_MAX_TOKENS: u64 = 256;
+const DEFAULT_TIMEOUT: u64 = 30_000;
+const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+struct Generation {
+    prompt: String,
+    model: Option<String>,
+    max_tokens: u64,
+    stream: bool,
+    // in milliseconds
+    timeout: u64,
+}
+
+/**
+ * An API generating the completion of a prompt
+ */
+trait GenerationProvider {
+    fn request(&self, generation: &Generation) -> Result<(ureq::Request, Value), String>;
+
+    // text of a complete response
+    fn get_text(&self, response: &Value) -> Option<String>;
+
+    // text of a line of a streamed response, None for the other lines
+    fn get_chunk(&self, line: &str) -> Option<String>;
+}
+
+/**
+ * OpenAI compatible chat completions API (OpenAI, Azure OpenAI, Mistral, vLLM, Ollama...),
+ * streamed as server-sent events
+ */
+struct OpenAiProvider {
+    url: String,
+    api_key: Option<String>,
+}
+
+impl GenerationProvider for OpenAiProvider {
+    fn request(&self, generation: &Generation) -> Result<(ureq::Request, Value), String> {
+        let model = match &generation.model {
+            Some(model) => model,
+            None => {
+                return Err(
+                    "no model set (model argument, llm_model secret or LLM_MODEL)".to_owned(),
+                )
+            }
+        };
+
+        let mut request = ureq::post(&format!(
+            "{}/chat/completions",
+            self.url.trim_end_matches('/')
+        ));
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+
+        let body = serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": generation.prompt}],
+            "max_tokens": generation.max_tokens,
+            "stream": generation.stream,
+        });
+
+        Ok((request, body))
+    }
+
+    fn get_text(&self, response: &Value) -> Option<String> {
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|text| text.to_owned())
+    }
+
+    fn get_chunk(&self, line: &str) -> Option<String> {
+        let data = line.strip_prefix("data:")?.trim();
+        let chunk: Value = serde_json::from_str(data).ok()?;
+
+        chunk["choices"][0]["delta"]["content"]
+            .as_str()
+            .map(|text| text.to_owned())
+    }
+}
+
+/**
+ * Model served by a local endpoint: receives {"prompt", "model", "max_tokens", "stream"} and
+ * answers {"text": String}, or one {"text": String} JSON object per line when streamed
+ */
+struct LocalProvider {
+    url: String,
+    api_key: Option<String>,
+}
+
+impl GenerationProvider for LocalProvider {
+    fn request(&self, generation: &Generation) -> Result<(ureq::Request, Value), String> {
+        let mut request = ureq::post(&self.url);
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+
+        let body = serde_json::json!({
+            "prompt": generation.prompt,
+            "model": generation.model,
+            "max_tokens": generation.max_tokens,
+            "stream": generation.stream,
+        });
+
+        Ok((request, body))
+    }
+
+    fn get_text(&self, response: &Value) -> Option<String> {
+        response["text"].as_str().map(|text| text.to_owned())
+    }
+
+    fn get_chunk(&self, line: &str) -> Option<String> {
+        let chunk: Value = serde_json::from_str(line).ok()?;
+
+        self.get_text(&chunk)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Setting of the bot in its secrets (llm_url...), or in the settings of the engine
+ * or the environment (LLM_URL...)
+ */
+fn get_setting(
+    secrets: &HashMap<String, Literal>,
+    settings: &HashMap<String, String>,
+    name: &str,
+) -> Option<String> {
+    match secrets.get(name) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(literal.primitive.to_string())
+        }
+        _ => match settings.get(&name.to_uppercase()) {
+            Some(value) => Some(value.to_owned()),
+            None => match env::var(name.to_uppercase()) {
+                Ok(value) if !value.is_empty() => Some(value),
+                _ => None,
+            },
+        },
+    }
+}
+
+fn get_provider(
+    secrets: &HashMap<String, Literal>,
+    settings: &HashMap<String, String>,
+) -> Result<Box<dyn GenerationProvider>, String> {
+    let url = get_setting(secrets, settings, "llm_url");
+    let api_key = get_setting(secrets, settings, "llm_api_key");
+
+    match get_setting(secrets, settings, "llm_provider").as_deref() {
+        None | Some("openai") => Ok(Box::new(OpenAiProvider {
+            url: url.unwrap_or_else(|| DEFAULT_OPENAI_URL.to_owned()),
+            api_key,
+        })),
+        Some("local") => match url {
+            Some(url) => Ok(Box::new(LocalProvider { url, api_key })),
+            None => Err("the local provider requires llm_url or LLM_URL".to_owned()),
+        },
+        Some(provider) => Err(format!("unknown provider {}", provider)),
+    }
+}
+
+fn get_timeout(settings: &HashMap<String, String>) -> u64 {
+    match settings.get("LLM_TIMEOUT") {
+        Some(timeout) => Some(timeout.to_owned()),
+        None => env::var("LLM_TIMEOUT").ok(),
+    }
+    .and_then(|timeout| timeout.parse::<u64>().ok())
+    .unwrap_or(DEFAULT_TIMEOUT)
+}
+
+fn send_request(
+    provider: &dyn GenerationProvider,
+    generation: &Generation,
+) -> Result<ureq::Response, String> {
+    let (request, body) = provider.request(generation)?;
+
+    match request
+        .timeout(Duration::from_millis(generation.timeout))
+        .send_json(body)
+    {
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(status, response)) => Err(format!(
+            "response status {}: {}",
+            status,
+            response.into_string().unwrap_or_default()
+        )),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/**
+ * Read the streamed response line by line, each chunk of text is sent to the engine
+ * as soon as it is received
+ */
+fn read_stream(
+    provider: &dyn GenerationProvider,
+    response: ureq::Response,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<String, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut text = String::new();
+
+    for line in BufReader::new(response.into_reader()).lines() {
+        let line = line.map_err(|err| err.to_string())?;
+
+        if let Some(chunk) = provider.get_chunk(line.trim()) {
+            text.push_str(&chunk);
+            MSG::send(
+                sender,
+                MSG::Stream {
+                    id: id.to_owned(),
+                    text: chunk,
+                    done: false,
+                },
+            );
+        }
+    }
+
+    MSG::send(
+        sender,
+        MSG::Stream {
+            id,
+            text: String::new(),
+            done: true,
+        },
+    );
+
+    Ok(text)
+}
+
+fn generate_text(
+    secrets: &HashMap<String, Literal>,
+    settings: &HashMap<String, String>,
+    flow: Option<String>,
+    generation: &Generation,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<String, String> {
+    let provider = get_provider(secrets, settings)?;
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            flow,
+            None,
+            format!("generate with model {:?}", generation.model),
+        ),
+        LogLvl::Info,
+    );
+
+    let response = send_request(provider.as_ref(), generation)?;
+
+    match generation.stream {
+        true => read_stream(provider.as_ref(), response, sender),
+        false => {
+            let response: Value = response.into_json().map_err(|err| err.to_string())?;
+
+            provider
+                .get_text(&response)
+                .ok_or_else(|| format!("no text in the response {}", response))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Generate the completion of a prompt with the model of the bot's provider:
+ * Generate(prompt, model="gpt-4o-mini", max_tokens=256).
+ * The provider (openai or local), its url and api key are read from the secrets of the bot
+ * (llm_provider, llm_url, llm_api_key, llm_model), or from the settings of the event
+ * or the environment (LLM_PROVIDER...).
+ * GenerateStream sends the chunks of the text to the channel while it is generated,
+ * both return the whole text.
+ */
+pub fn generate(
+    args: ArgsType,
+    data: &Data,
+    interval: Interval,
+    sender: &Option<mpsc::Sender<MSG>>,
+    stream: bool,
+) -> Result<Literal, ErrorInfo> {
+    let error = |message: &str| {
+        gen_error_info(
+            Position::new(interval, &data.context.flow),
+            message.to_owned(),
+        )
+    };
+
+    let prompt = match get_arg(&args, "prompt", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            literal.primitive.to_string()
+        }
+        _ => return Err(error(ERROR_GENERATE)),
+    };
+    let model = match get_arg(&args, "model", 1) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(literal.primitive.to_string())
+        }
+        Some(_) => return Err(error(ERROR_GENERATE)),
+        None => get_setting(&data.context.secrets, &data.event.settings, "llm_model"),
+    };
+    let max_tokens = match get_arg(&args, "max_tokens", 2) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveInt => {
+            match literal.primitive.to_json().as_u64() {
+                Some(max_tokens) if max_tokens > 0 => max_tokens,
+                _ => return Err(error(ERROR_GENERATE)),
+            }
+        }
+        Some(_) => return Err(error(ERROR_GENERATE)),
+        None => DEFAULT_MAX_TOKENS,
+    };
+
+    // bounded by the timeout of the step
+    let timeout = get_timeout(&data.event.settings);
+    let generation = Generation {
+        prompt,
+        model,
+        max_tokens,
+        stream,
+        timeout: data
+            .limits
+            .get_call_timeout(Some(timeout))
+            .unwrap_or(timeout),
+    };
+
+    check_network(&data.context.sandbox, GENERATE, &data.context.flow, interval)?;
+    data.limits
+        .check_step_timeout(interval, &data.context.flow)?;
+
+    let flow = Some(data.context.flow.to_string());
+    match generate_text(
+        &data.context.secrets,
+        &data.event.settings,
+        flow,
+        &generation,
+        sender,
+    ) {
+        Ok(text) => Ok(PrimitiveString::get_literal(&text, interval)),
+        Err(err) => {
+            data.limits
+                .check_step_timeout(interval, &data.context.flow)?;
+            Err(error(&format!("{}: {}", ERROR_GENERATE_PROVIDER, err)))
+        }
+    }
+}
+
+/**
+ * Completion of a prompt outside of the flows (summary of a conversation...), with the provider
+ * and the model set in the given secrets of the bot, in the settings of the engine
+ * (LLM_URL...) or in the environment
+ */
+pub fn complete(
+    prompt: &str,
+    max_tokens: u64,
+    secrets: &HashMap<String, Literal>,
+    settings: &HashMap<String, String>,
+) -> Result<String, String> {
+    let generation = Generation {
+        prompt: prompt.to_owned(),
+        model: get_setting(secrets, settings, "llm_model"),
+        max_tokens,
+        stream: false,
+        timeout: get_timeout(settings),
+    };
+
+    generate_text(secrets, settings, None, &generation, &None)
+}
@@ -0,0 +1,41 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveNull, PrimitiveType};
+use crate::data::{ast::Interval, ArgsType, Literal, MSG};
+use crate::error_format::*;
+use std::sync::mpsc;
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Ask the engine to pause the current conversation until a human agent resumes it.
+ * Takes an optional reason (String) or metadata (Object) that is saved with the handover.
+ */
+pub fn handover(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let metadata = match args.get("reason", 0) {
+        None => serde_json::json!({}),
+        Some(literal) => match literal.primitive.get_type() {
+            PrimitiveType::PrimitiveString => serde_json::json!({
+                "reason": literal.primitive.to_string()
+            }),
+            PrimitiveType::PrimitiveObject => literal.primitive.to_json(),
+            _ => {
+                return Err(gen_error_info(
+                    Position::new(interval, flow_name),
+                    ERROR_HANDOVER.to_owned(),
+                ))
+            }
+        },
+    };
+
+    MSG::send(sender, MSG::Handover(metadata));
+
+    Ok(PrimitiveNull::get_literal(interval))
+}
@@ -1,12 +1,13 @@
 use crate::data::error_info::ErrorInfo;
 use crate::data::position::Position;
 use crate::data::primitive::{PrimitiveInt, PrimitiveObject, PrimitiveString, PrimitiveType};
-use crate::data::{ast::Interval, csml_logs::*, ArgsType, Literal};
+use crate::data::{ast::Interval, csml_logs::*, ArgsType, ExecutionLimits, Literal};
 use crate::error_format::*;
 use std::collections::HashMap;
 use std::env;
 
 use std::sync::Arc;
+use std::time::Duration;
 use ureq::{Request, Response};
 
 use rustls::{
@@ -222,6 +223,26 @@ fn get_http_request(
     Ok(request)
 }
 
+/**
+ * The http object of a call with the timeout of its step, when the step has a budget for its calls
+ */
+pub fn get_step_request(
+    object: &HashMap<String, Literal>,
+    limits: &ExecutionLimits,
+    interval: Interval,
+) -> HashMap<String, Literal> {
+    let mut request = object.to_owned();
+
+    if let Some(timeout) = limits.get_call_timeout(None) {
+        request.insert(
+            "timeout".to_owned(),
+            PrimitiveInt::get_literal(timeout as i64, interval),
+        );
+    }
+
+    request
+}
+
 pub fn http_request(
     object: &HashMap<String, Literal>,
     method: &str,
@@ -242,6 +263,20 @@ pub fn http_request(
 
     let mut request = get_http_request(method, &url, flow_name, interval, is_ssl_disable)?;
 
+    // timeout in milliseconds, only set by the engine for app calls and the steps with a timeout
+    if let Some(timeout) = object.get("timeout") {
+        if let PrimitiveType::PrimitiveInt = timeout.primitive.get_type() {
+            let timeout = Literal::get_value::<i64>(
+                &timeout.primitive,
+                flow_name,
+                interval,
+                format!("'timeout' {}", ERROR_HTTP_GET_VALUE),
+            )?;
+
+            request = request.timeout(Duration::from_millis(*timeout as u64));
+        }
+    }
+
     for key in header.keys() {
         let value = match header.get(key) {
             Some(val) => val.primitive.to_string(),
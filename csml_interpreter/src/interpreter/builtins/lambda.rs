@@ -0,0 +1,117 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::{ast::Interval, LambdaFunction, Literal};
+use crate::error_format::*;
+
+use std::collections::HashMap;
+
+/**
+ * Invoke the lambda synchronously with the app payload.
+ * The lambda must answer with the same format as an http app: {"data": ...}
+ */
+#[cfg(feature = "aws_lambda")]
+pub fn invoke_lambda(
+    function: &LambdaFunction,
+    body: &Literal,
+    timeout: Option<u64>,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo> {
+    use crate::data::csml_logs::*;
+    use crate::data::primitive::{PrimitiveInt, PrimitiveString};
+
+    use rusoto_core::Region;
+    use rusoto_lambda::{InvocationRequest, Lambda, LambdaClient};
+    use std::{str::FromStr, time::Duration};
+
+    let lambda_error = |message: String| {
+        gen_error_info(
+            Position::new(interval, flow_name),
+            format!("{}: {}", ERROR_APP_LAMBDA, message),
+        )
+    };
+
+    let region = match &function.region {
+        Some(region) => Region::from_str(region).map_err(|err| lambda_error(err.to_string()))?,
+        None => Region::default(),
+    };
+
+    let client = LambdaClient::new(region);
+    let request = InvocationRequest {
+        function_name: function.function_arn.to_owned(),
+        payload: Some(bytes::Bytes::from(body.primitive.to_json().to_string())),
+        ..Default::default()
+    };
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            Some(flow_name.to_string()),
+            Some(interval.start_line),
+            format!("Invoke lambda app: {}", function.function_arn),
+        ),
+        LogLvl::Info,
+    );
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| lambda_error(err.to_string()))?;
+
+    let response = runtime.block_on(async {
+        match timeout {
+            Some(timeout) => {
+                match tokio::time::timeout(Duration::from_millis(timeout), client.invoke(request))
+                    .await
+                {
+                    Ok(response) => response.map_err(|err| err.to_string()),
+                    Err(_) => Err("timeout".to_owned()),
+                }
+            }
+            None => client.invoke(request).await.map_err(|err| err.to_string()),
+        }
+    });
+
+    let response = response.map_err(lambda_error)?;
+
+    let mut response_info = HashMap::new();
+    if let Some(status) = response.status_code {
+        response_info.insert(
+            "status".to_owned(),
+            PrimitiveInt::get_literal(status, interval),
+        );
+    }
+
+    let payload = match response.payload {
+        Some(payload) => String::from_utf8_lossy(&payload).to_string(),
+        None => String::new(),
+    };
+
+    // the function itself failed: the payload contains the error raised by the lambda
+    if let Some(function_error) = response.function_error {
+        let mut error = lambda_error(function_error);
+        error.add_info_block(response_info);
+        error.add_info("body", PrimitiveString::get_literal(&payload, interval));
+
+        return Err(error);
+    }
+
+    match serde_json::from_str::<serde_json::Value>(&payload) {
+        Ok(value) => Ok((value, response_info)),
+        Err(_) => Ok((serde_json::json!(payload), response_info)),
+    }
+}
+
+#[cfg(not(feature = "aws_lambda"))]
+pub fn invoke_lambda(
+    _function: &LambdaFunction,
+    _body: &Literal,
+    _timeout: Option<u64>,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<(serde_json::Value, HashMap<String, Literal>), ErrorInfo> {
+    Err(gen_error_info(
+        Position::new(interval, flow_name),
+        ERROR_APP_LAMBDA_DISABLED.to_owned(),
+    ))
+}
@@ -0,0 +1,157 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{
+    tools_number::*, PrimitiveFloat, PrimitiveInt, PrimitiveNull, PrimitiveString, PrimitiveType,
+};
+use crate::data::{ast::Interval, ArgsType, Data, Literal};
+use crate::error_format::*;
+use crate::interpreter::builtins::tools::get_arg;
+use crate::interpreter::builtins::translate::get_user_locale;
+
+const DEFAULT_LOCALE: &str = "en";
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_number(literal: Option<&Literal>) -> Option<f64> {
+    match literal {
+        Some(literal)
+            if literal.primitive.get_type() == PrimitiveType::PrimitiveInt
+                || literal.primitive.get_type() == PrimitiveType::PrimitiveFloat =>
+        {
+            literal.primitive.to_json().as_f64()
+        }
+        _ => None,
+    }
+}
+
+fn get_string(literal: Option<&Literal>) -> Option<String> {
+    match literal {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(literal.primitive.to_string())
+        }
+        _ => None,
+    }
+}
+
+/**
+ * The locale argument, then the locale of the user
+ */
+fn get_locale(literal: Option<&Literal>, data: &Data) -> Option<NumberLocale> {
+    match literal {
+        Some(_) => get_string(literal).map(|locale| get_number_locale(&locale)),
+        None => Some(get_number_locale(
+            &get_user_locale(data).unwrap_or_else(|| DEFAULT_LOCALE.to_owned()),
+        )),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Format_Number(1234.5, locale="fr-FR", decimals=2) returns "1 234,50". The locale of the
+ * user (metadata.locale or the locale of the event) is used by default.
+ */
+pub fn format_number_builtin(
+    args: ArgsType,
+    data: &Data,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let error = || {
+        gen_error_info(
+            Position::new(interval, &data.context.flow),
+            ERROR_FORMAT_NUMBER.to_owned(),
+        )
+    };
+
+    let value = get_number(get_arg(&args, "value", 0)).ok_or_else(error)?;
+    let locale = get_locale(get_arg(&args, "locale", 1), data).ok_or_else(error)?;
+    let decimals = match get_arg(&args, "decimals", 2) {
+        Some(literal) => match literal.primitive.to_json().as_u64() {
+            Some(decimals) if decimals <= 10 => Some(decimals as usize),
+            _ => return Err(error()),
+        },
+        None => None,
+    };
+
+    Ok(PrimitiveString::get_literal(
+        &format_number(value, locale, decimals),
+        interval,
+    ))
+}
+
+/**
+ * Currency(19.9, "EUR", locale="fr-FR") returns "19,90 €", with the decimals of the currency
+ */
+pub fn currency(args: ArgsType, data: &Data, interval: Interval) -> Result<Literal, ErrorInfo> {
+    let error = || {
+        gen_error_info(
+            Position::new(interval, &data.context.flow),
+            ERROR_CURRENCY.to_owned(),
+        )
+    };
+
+    let value = get_number(get_arg(&args, "amount", 0)).ok_or_else(error)?;
+    let code = match get_string(get_arg(&args, "currency", 1)) {
+        Some(code) if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) => code,
+        _ => return Err(error()),
+    };
+    let locale = get_locale(get_arg(&args, "locale", 2), data).ok_or_else(error)?;
+
+    Ok(PrimitiveString::get_literal(
+        &format_currency(value, &code, locale),
+        interval,
+    ))
+}
+
+/**
+ * To_Number("1 234,5", locale="fr-FR") returns 1234.5: an Int without decimals, a Float with
+ * decimals, Null when the text is not a number
+ */
+pub fn to_number(args: ArgsType, data: &Data, interval: Interval) -> Result<Literal, ErrorInfo> {
+    let error = || {
+        gen_error_info(
+            Position::new(interval, &data.context.flow),
+            ERROR_TO_NUMBER.to_owned(),
+        )
+    };
+
+    let text = get_string(get_arg(&args, "text", 0)).ok_or_else(error)?;
+    let locale = get_locale(get_arg(&args, "locale", 1), data).ok_or_else(error)?;
+
+    match parse_number(&text, locale) {
+        Some(number) if !text.contains(locale.decimal) && number.abs() < i64::MAX as f64 => {
+            Ok(PrimitiveInt::get_literal(number as i64, interval))
+        }
+        Some(number) => Ok(PrimitiveFloat::get_literal(number, interval)),
+        None => Ok(PrimitiveNull::get_literal(interval)),
+    }
+}
+
+/**
+ * Convert_Unit(10, "km", "mi") returns the value in the new unit, as a Float
+ */
+pub fn convert_unit_builtin(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let error = || {
+        gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_CONVERT_UNIT.to_owned(),
+        )
+    };
+
+    let value = get_number(get_arg(&args, "value", 0)).ok_or_else(error)?;
+    let from = get_string(get_arg(&args, "from", 1)).ok_or_else(error)?;
+    let to = get_string(get_arg(&args, "to", 2)).ok_or_else(error)?;
+
+    match convert_unit(value, &from, &to) {
+        Some(converted) => Ok(PrimitiveFloat::get_literal(converted, interval)),
+        None => Err(error()),
+    }
+}
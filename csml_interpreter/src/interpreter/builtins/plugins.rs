@@ -0,0 +1,247 @@
+/**
+ * WASM plugins: operators drop .wasm modules in the CSML_PLUGINS_DIR directory and every
+ * function exported by those modules becomes a builtin callable from the flows.
+ *
+ * Plugins must follow this ABI:
+ * - export their `memory` and an `alloc(len: i32) -> i32` function
+ * - builtins are exported functions with the signature `(ptr: i32, len: i32) -> i64`
+ * - the arguments of the call are written in the plugin memory as JSON (an array for
+ *   positional arguments, an object for named arguments)
+ * - the function returns the position of its JSON result in memory: (ptr << 32) | len
+ *
+ * Plugins have no access to the host (no WASI). Each call runs in a new instance limited by:
+ * - CSML_PLUGINS_FUEL: number of instructions, default 10_000_000
+ * - CSML_PLUGINS_TIMEOUT: execution time in milliseconds, default 1000
+ * - CSML_PLUGINS_MEMORY_LIMIT: memory in MB, default 32
+ *
+ * Plugins are only loaded when the interpreter is built with the wasm_plugins feature.
+ */
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::{ast::Interval, ArgsType, Literal};
+use crate::error_format::*;
+use crate::interpreter::json_to_rust::json_to_literal;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn args_to_json(args: &ArgsType) -> serde_json::Value {
+    match args {
+        ArgsType::Normal(map) => {
+            let args = (0..map.len())
+                .filter_map(|index| map.get(&format!("arg{}", index)))
+                .map(|literal| literal.primitive.to_json())
+                .collect();
+
+            serde_json::Value::Array(args)
+        }
+        ArgsType::Named(map) => {
+            let args = map
+                .iter()
+                .map(|(key, literal)| (key.to_owned(), literal.primitive.to_json()))
+                .collect();
+
+            serde_json::Value::Object(args)
+        }
+    }
+}
+
+#[cfg(feature = "wasm_plugins")]
+mod runtime {
+    use once_cell::sync::Lazy;
+    use std::collections::HashMap;
+    use std::{env, fs, thread, time::Duration};
+    use wasmtime::{
+        Config, Engine, ExternType, Instance, Module, Store, StoreLimits, StoreLimitsBuilder,
+        ValType,
+    };
+
+    // the epoch of the engine is incremented at this interval to enforce the timeout of the calls
+    const EPOCH_TICK: u64 = 10;
+
+    struct Plugins {
+        engine: Engine,
+        // exported function name => module
+        functions: HashMap<String, Module>,
+    }
+
+    static PLUGINS: Lazy<Option<Plugins>> = Lazy::new(load_plugins);
+
+    fn get_env_value(key: &str, default: u64) -> u64 {
+        match env::var(key) {
+            Ok(value) => value.parse::<u64>().unwrap_or(default),
+            Err(_) => default,
+        }
+    }
+
+    fn is_plugin_signature(name: &str, ty: &ExternType) -> bool {
+        match ty {
+            ExternType::Func(func) if name != "alloc" => {
+                func.params().collect::<Vec<ValType>>() == vec![ValType::I32, ValType::I32]
+                    && func.results().collect::<Vec<ValType>>() == vec![ValType::I64]
+            }
+            _ => false,
+        }
+    }
+
+    fn load_plugins() -> Option<Plugins> {
+        let dir = env::var("CSML_PLUGINS_DIR").ok()?;
+
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+
+        let engine = match Engine::new(&config) {
+            Ok(engine) => engine,
+            Err(err) => {
+                eprintln!("WASM plugins: failed to start engine: {}", err);
+                return None;
+            }
+        };
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!("WASM plugins: failed to read {}: {}", dir, err);
+                return None;
+            }
+        };
+
+        let mut functions = HashMap::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("wasm") {
+                continue;
+            }
+
+            let module = match Module::from_file(&engine, &path) {
+                Ok(module) => module,
+                Err(err) => {
+                    eprintln!("WASM plugins: failed to load {:?}: {}", path, err);
+                    continue;
+                }
+            };
+
+            for export in module.exports() {
+                if !is_plugin_signature(export.name(), &export.ty()) {
+                    continue;
+                }
+
+                if functions.contains_key(export.name()) {
+                    eprintln!(
+                        "WASM plugins: function {} is already exported by another plugin, ignored in {:?}",
+                        export.name(),
+                        path
+                    );
+                    continue;
+                }
+
+                functions.insert(export.name().to_owned(), module.clone());
+            }
+        }
+
+        let ticker = engine.clone();
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_millis(EPOCH_TICK));
+            ticker.increment_epoch();
+        });
+
+        Some(Plugins { engine, functions })
+    }
+
+    pub fn is_plugin_function(name: &str) -> bool {
+        match &*PLUGINS {
+            Some(plugins) => plugins.functions.contains_key(name),
+            None => false,
+        }
+    }
+
+    pub fn call(name: &str, input: &str) -> Result<serde_json::Value, String> {
+        let plugins = PLUGINS.as_ref().ok_or("no plugins loaded")?;
+        let module = plugins
+            .functions
+            .get(name)
+            .ok_or(format!("unknown plugin function {}", name))?;
+
+        let limits: StoreLimits = StoreLimitsBuilder::new()
+            .memory_size(get_env_value("CSML_PLUGINS_MEMORY_LIMIT", 32) as usize * 1024 * 1024)
+            .build();
+
+        let mut store = Store::new(&plugins.engine, limits);
+        store.limiter(|limits| limits);
+        store
+            .add_fuel(get_env_value("CSML_PLUGINS_FUEL", 10_000_000))
+            .map_err(|err| err.to_string())?;
+        let timeout = get_env_value("CSML_PLUGINS_TIMEOUT", 1000);
+        store.set_epoch_deadline(1 + timeout / EPOCH_TICK);
+
+        let instance = Instance::new(&mut store, module, &[]).map_err(|err| err.to_string())?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or("plugin does not export its memory")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(|err| err.to_string())?;
+        let function = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, name)
+            .map_err(|err| err.to_string())?;
+
+        let len = input.len() as i32;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|err| err.to_string())?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input.as_bytes())
+            .map_err(|err| err.to_string())?;
+
+        let result = function
+            .call(&mut store, (ptr, len))
+            .map_err(|err| err.to_string())?;
+
+        let ptr = (result >> 32) as u32 as usize;
+        let len = (result & 0xffff_ffff) as u32 as usize;
+        let mut output = vec![0u8; len];
+        memory
+            .read(&store, ptr, &mut output)
+            .map_err(|err| err.to_string())?;
+
+        serde_json::from_slice(&output).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(not(feature = "wasm_plugins"))]
+mod runtime {
+    pub fn is_plugin_function(_name: &str) -> bool {
+        false
+    }
+
+    pub fn call(name: &str, _input: &str) -> Result<serde_json::Value, String> {
+        Err(format!("unknown plugin function {}", name))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn is_plugin_function(name: &str) -> bool {
+    runtime::is_plugin_function(name)
+}
+
+pub fn plugin(
+    name: &str,
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let input = args_to_json(&args).to_string();
+
+    match runtime::call(name, &input) {
+        Ok(value) => json_to_literal(&value, interval, flow_name),
+        Err(err) => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            format!("{} [{}]: {}", ERROR_PLUGIN, name, err),
+        )),
+    }
+}
@@ -0,0 +1,100 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveString, PrimitiveType};
+use crate::data::{ast::Interval, ArgsType, Literal, MSG};
+use crate::error_format::*;
+use std::sync::mpsc;
+use uuid::Uuid;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_event(
+    literal: &Literal,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<serde_json::Value, ErrorInfo> {
+    match literal.primitive.get_type() {
+        PrimitiveType::PrimitiveString => Ok(serde_json::json!({
+            "content_type": "text",
+            "content": { "text": literal.primitive.to_string() }
+        })),
+        PrimitiveType::PrimitiveObject => {
+            let event = literal.primitive.to_json();
+
+            match (event["content_type"].is_string(), event["content"].is_object()) {
+                (true, true) => Ok(event),
+                _ => Err(gen_error_info(
+                    Position::new(interval, flow_name),
+                    ERROR_SCHEDULE.to_owned(),
+                )),
+            }
+        }
+        _ => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_SCHEDULE.to_owned(),
+        )),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Ask the engine to send an event to the current client after a delay in seconds.
+ * The event is either a text or an event payload ({content_type, content}).
+ * Returns the id of the scheduled event.
+ */
+pub fn schedule(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let event = match args.get("event", 0) {
+        Some(literal) => get_event(literal, flow_name, interval)?,
+        None => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_SCHEDULE.to_owned(),
+            ))
+        }
+    };
+
+    let delay = match args.get("delay", 1) {
+        Some(literal) => *Literal::get_value::<i64>(
+            &literal.primitive,
+            flow_name,
+            interval,
+            ERROR_SCHEDULE.to_owned(),
+        )?,
+        None => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_SCHEDULE.to_owned(),
+            ))
+        }
+    };
+
+    if delay < 0 {
+        return Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_SCHEDULE.to_owned(),
+        ));
+    }
+
+    let id = Uuid::new_v4().to_string();
+
+    MSG::send(
+        sender,
+        MSG::Schedule {
+            id: id.clone(),
+            event,
+            delay,
+        },
+    );
+
+    Ok(PrimitiveString::get_literal(&id, interval))
+}
@@ -0,0 +1,51 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::PrimitiveType;
+use crate::data::{ast::Interval, ArgsType, Data, Literal};
+use crate::error_format::*;
+use crate::interpreter::builtins::tools::get_arg;
+use crate::interpreter::json_to_rust::json_to_literal;
+
+const DEFAULT_TOP_K: usize = 5;
+const MAX_TOP_K: usize = 50;
+
+/**
+ * Passages of the knowledge base of the bot the most similar to the query, best first:
+ * Search_KB(query, top_k=5) returns [{"document_id", "text", "score", "metadata"}]
+ */
+pub fn search_kb(args: ArgsType, data: &Data, interval: Interval) -> Result<Literal, ErrorInfo> {
+    let error =
+        |message: String| gen_error_info(Position::new(interval, &data.context.flow), message);
+
+    let query = match get_arg(&args, "query", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            literal.primitive.to_string()
+        }
+        _ => return Err(error(ERROR_SEARCH_KB.to_owned())),
+    };
+    let top_k = match get_arg(&args, "top_k", 1) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveInt => {
+            match literal.primitive.to_json().as_u64() {
+                Some(top_k) if top_k > 0 => (top_k as usize).min(MAX_TOP_K),
+                _ => return Err(error(ERROR_SEARCH_KB.to_owned())),
+            }
+        }
+        Some(_) => return Err(error(ERROR_SEARCH_KB.to_owned())),
+        None => DEFAULT_TOP_K,
+    };
+
+    if query.trim().is_empty() {
+        return Err(error(ERROR_SEARCH_KB.to_owned()));
+    }
+
+    let knowledge_base = match &data.context.knowledge_base {
+        Some(knowledge_base) => knowledge_base,
+        None => return Err(error(ERROR_SEARCH_KB_DISABLED.to_owned())),
+    };
+
+    let passages = knowledge_base
+        .search(&query, top_k)
+        .map_err(|err| error(format!("{}: {}", ERROR_SEARCH_KB_FAILED, err)))?;
+
+    json_to_literal(&serde_json::json!(passages), interval, &data.context.flow)
+}
@@ -0,0 +1,47 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveNull, PrimitiveType};
+use crate::data::{ast::Interval, ArgsType, Literal, MSG};
+use crate::error_format::*;
+use std::sync::mpsc;
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Ask the engine to attach labels to the current conversation.
+ * Takes a tag (String) or a list of tags (Array of String).
+ */
+pub fn tag(
+    args: ArgsType,
+    flow_name: &str,
+    interval: Interval,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let error = || gen_error_info(Position::new(interval, flow_name), ERROR_TAG.to_owned());
+
+    let tags = match args.get("tag", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            vec![literal.primitive.to_string()]
+        }
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveArray => {
+            match literal.primitive.to_json() {
+                serde_json::Value::Array(values) => values
+                    .iter()
+                    .map(|value| value.as_str().map(str::to_owned).ok_or_else(error))
+                    .collect::<Result<Vec<String>, ErrorInfo>>()?,
+                _ => return Err(error()),
+            }
+        }
+        _ => return Err(error()),
+    };
+
+    if tags.iter().any(|tag| tag.trim().is_empty()) {
+        return Err(error());
+    }
+
+    MSG::send(sender, MSG::Tag(tags));
+
+    Ok(PrimitiveNull::get_literal(interval))
+}
@@ -1,20 +1,29 @@
 use crate::data::error_info::ErrorInfo;
 use crate::data::primitive::{PrimitiveInt, PrimitiveObject};
-use crate::data::{ast::Interval, ArgsType, Literal};
+use crate::data::{ast::Interval, ArgsType, Literal, Sandbox};
 use chrono::Utc;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 ////////////////////////////////////////////////////////////////////////////////
 /// PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
-pub fn time(_args: ArgsType, _flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+pub fn time(
+    _args: ArgsType,
+    _flow_name: &str,
+    interval: Interval,
+    sandbox: &Option<Arc<Sandbox>>,
+) -> Result<Literal, ErrorInfo> {
     let mut time: HashMap<String, Literal> = HashMap::new();
-    let date = Utc::now();
+    let milliseconds = match sandbox {
+        Some(sandbox) => sandbox.now(),
+        None => Utc::now().timestamp_millis(),
+    };
 
     time.insert(
         "milliseconds".to_owned(),
-        PrimitiveInt::get_literal(date.timestamp_millis(), interval),
+        PrimitiveInt::get_literal(milliseconds, interval),
     );
 
     let mut result = PrimitiveObject::get_literal(&time, interval);
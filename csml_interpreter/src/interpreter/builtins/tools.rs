@@ -1,8 +1,16 @@
 use crate::data::primitive::{PrimitiveArray, PrimitiveString};
-use crate::data::{Client, Interval, Literal};
+use crate::data::{ArgsType, Client, Interval, Literal};
 use crate::error_format::*;
 use std::collections::HashMap;
 
+/**
+ * Positional arguments stay readable when they are followed by named ones
+ */
+pub fn get_arg<'a>(args: &'a ArgsType, key: &str, index: usize) -> Option<&'a Literal> {
+    args.get(key, index)
+        .or_else(|| args.get(&format!("arg{}", index), index))
+}
+
 pub fn client_to_json(client: &Client, interval: Interval) -> HashMap<String, Literal> {
     let mut map = HashMap::new();
 
@@ -0,0 +1,107 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveString, PrimitiveType};
+use crate::data::{ast::Interval, csml_bot::BotLocales, ArgsType, Data, Literal};
+use crate::error_format::*;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * The locale of the user is metadata.locale, or the locale of the event
+ */
+pub(crate) fn get_user_locale(data: &Data) -> Option<String> {
+    match data.context.metadata.get("locale") {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(literal.primitive.to_string())
+        }
+        _ => data.event.locale.to_owned(),
+    }
+}
+
+/**
+ * Locales to search, in order: "fr-CA" -> "fr" -> default_locale
+ */
+fn get_fallback_chain(locale: Option<String>, locales: &BotLocales) -> Vec<String> {
+    let mut chain = vec![];
+
+    for locale in locale.iter().chain(locales.default_locale.iter()) {
+        let language = locale.split(|c| c == '-' || c == '_').next();
+
+        for locale in std::iter::once(locale.as_str()).chain(language) {
+            if !chain.iter().any(|l: &String| l == locale) {
+                chain.push(locale.to_owned());
+            }
+        }
+    }
+
+    chain
+}
+
+fn get_translation<'a>(translations: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    if let Some(value) = translations.get(key) {
+        return value.as_str();
+    }
+
+    key.split('.')
+        .try_fold(translations, |value, key| value.get(key))
+        .and_then(|value| value.as_str())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Translate a key in the locale of the user. {{name}} placeholders are replaced
+ * by the values of the optional params object. Unknown keys are returned as is.
+ */
+pub fn translate(args: ArgsType, data: &Data, interval: Interval) -> Result<Literal, ErrorInfo> {
+    let key = match args.get("key", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            literal.primitive.to_string()
+        }
+        _ => {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                ERROR_TRANSLATE.to_owned(),
+            ))
+        }
+    };
+
+    let params = match args.get("params", 1) {
+        None => serde_json::Map::new(),
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveObject => {
+            match literal.primitive.to_json() {
+                serde_json::Value::Object(params) => params,
+                _ => serde_json::Map::new(),
+            }
+        }
+        Some(_) => {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                ERROR_TRANSLATE.to_owned(),
+            ))
+        }
+    };
+
+    let translation = match data.locales {
+        Some(locales) => get_fallback_chain(get_user_locale(data), locales)
+            .iter()
+            .filter_map(|locale| locales.translations.get(locale))
+            .find_map(|translations| get_translation(translations, &key)),
+        None => None,
+    };
+
+    let mut text = translation.unwrap_or(&key).to_owned();
+    for (name, value) in params.iter() {
+        let value = match value {
+            serde_json::Value::String(value) => value.to_owned(),
+            value => value.to_string(),
+        };
+        text = text.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+
+    Ok(PrimitiveString::get_literal(&text, interval))
+}
@@ -3,7 +3,9 @@ pub mod button;
 pub mod card;
 pub mod carousel;
 pub mod file;
+pub mod form;
 pub mod image;
+pub mod payment_request;
 pub mod question;
 pub mod text;
 pub mod typing;
@@ -64,7 +66,9 @@ pub fn load_components() -> Result<serde_json::Map<String, serde_json::Value>, E
     card::add_card(&mut map);
     carousel::add_carousel(&mut map);
     file::add_file(&mut map);
+    form::add_form(&mut map);
     image::add_image(&mut map);
+    payment_request::add_payment_request(&mut map);
     question::add_question(&mut map);
     text::add_text(&mut map);
     typing::add_typing(&mut map);
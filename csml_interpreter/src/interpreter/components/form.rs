@@ -0,0 +1,32 @@
+pub fn add_form(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.insert(
+        "Form".to_owned(),
+        serde_json::json!(
+            {
+                "params": [
+                    {
+                        "fields": {
+                            "required": true,
+                            "type": "Array"
+                        }
+                    },
+                    {
+                        "title": {
+                            "required": false,
+                            "type": "String"
+                        }
+                    },
+                    {
+                        "submit": {
+                            "required": false,
+                            "type": "String",
+                            "default_value": [
+                                {"$_set": "Submit"}
+                            ]
+                        }
+                    }
+                ]
+            }
+        ),
+    );
+}
@@ -0,0 +1,29 @@
+pub fn add_payment_request(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.insert(
+        "PaymentRequest".to_owned(),
+        serde_json::json!(
+            {
+                "params": [
+                    {
+                        "amount": {
+                            "required": true,
+                            "type": "Number"
+                        }
+                    },
+                    {
+                        "currency": {
+                            "required": true,
+                            "type": "String"
+                        }
+                    },
+                    {
+                        "description": {
+                            "required": false,
+                            "type": "String"
+                        }
+                    }
+                ]
+            }
+        ),
+    );
+}
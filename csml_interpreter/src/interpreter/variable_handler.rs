@@ -7,6 +7,7 @@ pub mod match_literals;
 pub mod memory;
 pub mod operations;
 pub mod resolve_csml_object;
+pub mod string_filters;
 
 use crate::data::literal::ContentType;
 pub use expr_to_literal::{expr_to_literal, resolve_fn_args};
@@ -19,8 +20,8 @@ use crate::data::primitive::{
 use crate::data::{
     ast::{Expr, Function, GotoValueType, Identifier, Interval, PathLiteral, PathState},
     data::Data,
-    tokens::{COMPONENT, EVENT, _ENV, _MEMORY, _METADATA},
-    warnings::DisplayWarnings,
+    tokens::{COMPONENT, EVENT, _ENV, _FLAGS, _GLOBAL, _MEMORY, _METADATA, _SECRETS},
+    warnings::{DisplayWarnings, WARNING_NULL_VALUE},
     ArgsType, Literal, MemoryType, MessageData, MSG,
 };
 use crate::error_format::*;
@@ -36,6 +37,28 @@ use std::{collections::HashMap, sync::mpsc};
 // PRIVATE FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
+/**
+ * In the conditions, the missing values are Null without error message: the access is
+ * reported as a warning of the execution
+ */
+fn null_warning(err: ErrorInfo, data: &Data, sender: &Option<mpsc::Sender<MSG>>) -> Literal {
+    MSG::send_warning(
+        sender,
+        data,
+        err.position.clone(),
+        format!("{} {}", WARNING_NULL_VALUE, err.message),
+    );
+
+    PrimitiveNull::get_literal(err.position.interval)
+}
+
+/**
+ * The missing values followed by ?. are Null, without error or warning
+ */
+fn is_optional(path: &Iter<(Interval, PathLiteral)>) -> bool {
+    matches!(path.as_slice().first(), Some((_, PathLiteral::Optional)))
+}
+
 fn get_var_from_step_var<'a>(
     name: &Identifier,
     data: &'a mut Data,
@@ -88,15 +111,16 @@ fn loop_path(
                         old_string = Some((lit.clone(), *index));
                         *lit = new_lit
                     }
+                    None if is_optional(path) => {
+                        return Ok((PrimitiveNull::get_literal(*interval), tmp_update_var))
+                    }
                     None => {
                         let err = gen_error_info(
                             Position::new(*interval, &data.context.flow),
                             format!("[{}] {}", index, ERROR_ARRAY_INDEX),
                         );
                         let null = match dis_warnings {
-                            &DisplayWarnings::Off => {
-                                PrimitiveNull::get_literal(err.position.interval)
-                            }
+                            &DisplayWarnings::Off => null_warning(err, data, sender),
                             &DisplayWarnings::On => {
                                 MSG::send_error_msg(&sender, msg_data, Err(err))
                             }
@@ -107,13 +131,16 @@ fn loop_path(
             }
             PathLiteral::VecIndex(index) => match get_at_index(lit, &data.context.flow, *index) {
                 Some(new_lit) => lit = new_lit,
+                None if is_optional(path) => {
+                    return Ok((PrimitiveNull::get_literal(*interval), tmp_update_var))
+                }
                 None => {
                     let err = gen_error_info(
                         Position::new(*interval, &data.context.flow),
                         format!("[{}] {}", index, ERROR_ARRAY_INDEX),
                     );
                     let null = match dis_warnings {
-                        &DisplayWarnings::Off => PrimitiveNull::get_literal(err.position.interval),
+                        &DisplayWarnings::Off => null_warning(err, data, sender),
                         &DisplayWarnings::On => MSG::send_error_msg(&sender, msg_data, Err(err)),
                     };
                     return Ok((null, tmp_update_var));
@@ -145,6 +172,9 @@ fn loop_path(
                 } else {
                     match get_value_from_key(lit, &data.context.flow, key) {
                         Some(new_lit) => lit = new_lit,
+                        None if is_optional(path) => {
+                            return Ok((PrimitiveNull::get_literal(*interval), tmp_update_var))
+                        }
                         None => {
                             let err = gen_error_info(
                                 Position::new(*interval, &data.context.flow),
@@ -157,9 +187,7 @@ fn loop_path(
                             // if value does not exist in memory we create a null value and we apply all the path actions
                             // if we are not in a condition an error message is created and send
                             let mut null = match dis_warnings {
-                                &DisplayWarnings::Off => {
-                                    PrimitiveNull::get_literal(err.position.interval)
-                                }
+                                &DisplayWarnings::Off => null_warning(err, data, sender),
                                 &DisplayWarnings::On => {
                                     MSG::send_error_msg(&sender, msg_data, Err(err))
                                 }
@@ -172,6 +200,10 @@ fn loop_path(
                     }
                 };
             }
+            PathLiteral::Optional if lit.primitive.get_type() == PrimitiveType::PrimitiveNull => {
+                return Ok((lit.to_owned(), tmp_update_var));
+            }
+            PathLiteral::Optional => {}
             PathLiteral::Func {
                 name,
                 interval,
@@ -412,6 +444,7 @@ pub fn resolve_path(
             PathState::StringIndex(key) => {
                 new_path.push((interval.to_owned(), PathLiteral::MapIndex(key.to_owned())))
             }
+            PathState::Optional => new_path.push((interval.to_owned(), PathLiteral::Optional)),
         }
     }
     Ok(new_path)
@@ -629,6 +662,81 @@ pub fn get_var(
             }
             None => Ok(data.env.clone()),
         },
+        name if name == _SECRETS => {
+            let mut secrets = data.context.secrets.clone();
+            for secret in secrets.values_mut() {
+                secret.secure_variable = true;
+            }
+
+            let mut lit = PrimitiveObject::get_literal(&secrets, var.interval);
+            lit.secure_variable = true;
+
+            match path {
+                Some(path) => {
+                    let path = resolve_path(path, dis_warnings, data, msg_data, sender)?;
+                    let (lit, _tmp_mem_update) = exec_path_actions(
+                        &mut lit,
+                        dis_warnings,
+                        &MemoryType::Constant,
+                        None,
+                        &Some(path),
+                        &ContentType::Primitive,
+                        data,
+                        msg_data,
+                        sender,
+                    )?;
+
+                    Ok(lit)
+                }
+                None => Ok(lit),
+            }
+        }
+        name if name == _GLOBAL => {
+            let mut lit = PrimitiveObject::get_literal(&data.context.globals, var.interval);
+
+            match path {
+                Some(path) => {
+                    let path = resolve_path(path, dis_warnings, data, msg_data, sender)?;
+                    let (lit, _tmp_mem_update) = exec_path_actions(
+                        &mut lit,
+                        dis_warnings,
+                        &MemoryType::Constant,
+                        None,
+                        &Some(path),
+                        &ContentType::Primitive,
+                        data,
+                        msg_data,
+                        sender,
+                    )?;
+
+                    Ok(lit)
+                }
+                None => Ok(lit),
+            }
+        }
+        name if name == _FLAGS => {
+            let mut lit = PrimitiveObject::get_literal(&data.context.flags, var.interval);
+
+            match path {
+                Some(path) => {
+                    let path = resolve_path(path, dis_warnings, data, msg_data, sender)?;
+                    let (lit, _tmp_mem_update) = exec_path_actions(
+                        &mut lit,
+                        dis_warnings,
+                        &MemoryType::Constant,
+                        None,
+                        &Some(path),
+                        &ContentType::Primitive,
+                        data,
+                        msg_data,
+                        sender,
+                    )?;
+
+                    Ok(lit)
+                }
+                None => Ok(lit),
+            }
+        }
         name if name == _METADATA => match path {
             Some(path) => {
                 let path = resolve_path(path, dis_warnings, data, msg_data, sender)?;
@@ -683,7 +791,7 @@ pub fn get_var(
                 tmp_loop_indexes,
                 tmp_loop_index,
                 mut tmp_step_count,
-                tmp_step_limit,
+                tmp_limits,
                 tmp_step_vars,
             ) = data.copy_scope();
 
@@ -698,11 +806,12 @@ pub fn get_var(
                 tmp_loop_indexes,
                 tmp_loop_index,
                 &mut tmp_step_count,
-                tmp_step_limit,
+                tmp_limits,
                 tmp_step_vars,
                 data.previous_info.clone(),
                 data.custom_component,
                 data.native_component,
+                data.locales,
             );
             // #####################
 
@@ -725,6 +834,14 @@ pub fn get_var(
                         Err(err) => (MSG::send_error_msg(&sender, msg_data, Err(err)), false),
                     };
 
+                    if update_mem {
+                        new_scope_data.limits.check_literal(
+                            lit,
+                            var.interval,
+                            &new_scope_data.context.flow,
+                        )?;
+                    }
+
                     save_literal_in_mem(
                         lit.to_owned(),
                         name,
@@ -736,13 +853,16 @@ pub fn get_var(
                     );
                     Ok(new_literal)
                 }
+                Err(_) if matches!(path, Some([(_, PathState::Optional), ..])) => {
+                    Ok(PrimitiveNull::get_literal(var.interval))
+                }
                 Err(err) => {
                     let error = PrimitiveString::get_literal(&err.message, err.position.interval);
 
                     // if value does not exist in memory we create a null value and we apply all the path actions
                     // if we are not in a condition an error message is created and send
                     let mut null = match dis_warnings {
-                        &DisplayWarnings::Off => PrimitiveNull::get_literal(err.position.interval),
+                        &DisplayWarnings::Off => null_warning(err, data, sender),
                         &DisplayWarnings::On => MSG::send_error_msg(&sender, msg_data, Err(err)),
                     };
 
@@ -2,15 +2,16 @@ use crate::data::error_info::ErrorInfo;
 use crate::data::literal::ContentType;
 use crate::data::primitive::{closure::capture_variables, PrimitiveArray, PrimitiveObject};
 use crate::data::{
-    ast::*, warnings::DisplayWarnings, ArgsType, Data, Literal, MemoryType, MessageData, Position,
-    MSG,
+    ast::*, tokens::DEFAULT_FILTER, warnings::DisplayWarnings, ArgsType, Data, Literal, MemoryType,
+    MessageData, Position, MSG,
 };
 use crate::error_format::*;
 use crate::interpreter::{
-    ast_interpreter::evaluate_condition,
+    ast_interpreter::{await_all, evaluate_condition},
     variable_handler::{
         exec_path_actions, get_string_from_complex_string, get_var, interval::interval_from_expr,
         operations::evaluate_postfix, resolve_csml_object::resolve_object, resolve_path,
+        string_filters::apply_filters,
     },
 };
 use std::{collections::HashMap, sync::mpsc};
@@ -126,6 +127,21 @@ pub fn expr_to_literal(
 
             exec_path_literal(&mut literal, dis_warnings, path, data, msg_data, sender)
         }
+        Expr::AwaitAllExpr(vec, range_interval) => {
+            let mut literal = await_all(vec, *range_interval, data, msg_data, sender)?;
+
+            exec_path_literal(&mut literal, dis_warnings, path, data, msg_data, sender)
+        }
+        Expr::FilterExpr(expr, filters) => {
+            // with a default filter, a missing value is expected
+            let expr_warnings = match filters.iter().any(|filter| filter.name == DEFAULT_FILTER) {
+                true => &DisplayWarnings::Off,
+                false => dis_warnings,
+            };
+            let literal = expr_to_literal(expr, expr_warnings, None, data, msg_data, sender)?;
+            let mut literal = apply_filters(literal, filters, data, msg_data, sender)?;
+            exec_path_literal(&mut literal, dis_warnings, path, data, msg_data, sender)
+        }
         Expr::PostfixExpr(pretfix, expr) => {
             let mut literal = evaluate_postfix(pretfix, expr, data, msg_data, sender)?;
             exec_path_literal(&mut literal, dis_warnings, path, data, msg_data, sender)
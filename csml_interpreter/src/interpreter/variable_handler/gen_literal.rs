@@ -32,6 +32,58 @@ pub fn gen_literal_from_event(
     match path {
         Some(path) => {
             let path = resolve_path(path, dis_warnings, data, msg_data, sender)?;
+
+            // structured access to the event: event.content.*, event.content_type and
+            // event.enrichment.* (unless the content itself has a field with the same name)
+            let structured_lit = match path.first() {
+                Some((_, PathLiteral::MapIndex(key)))
+                    if key == "content" && data.event.content.get("content").is_none() =>
+                {
+                    Some(json_to_literal(
+                        &data.event.content,
+                        interval.to_owned(),
+                        &data.context.flow,
+                    )?)
+                }
+                Some((_, PathLiteral::MapIndex(key)))
+                    if key == "content_type"
+                        && data.event.content.get("content_type").is_none() =>
+                {
+                    Some(PrimitiveString::get_literal(
+                        &data.event.content_type,
+                        interval.to_owned(),
+                    ))
+                }
+                Some((_, PathLiteral::MapIndex(key)))
+                    if key == "enrichment" && data.event.content.get("enrichment").is_none() =>
+                {
+                    Some(json_to_literal(
+                        &data.event.enrichment,
+                        interval.to_owned(),
+                        &data.context.flow,
+                    )?)
+                }
+                _ => None,
+            };
+
+            if let Some(mut lit) = structured_lit {
+                lit.secure_variable = data.event.secure;
+
+                let (lit, _tmp_mem_update) = exec_path_actions(
+                    &mut lit,
+                    dis_warnings,
+                    &MemoryType::Event("event".to_owned()),
+                    None,
+                    &Some(path[1..].to_vec()),
+                    &ContentType::Primitive,
+                    data,
+                    msg_data,
+                    sender,
+                )?;
+
+                return Ok(lit);
+            }
+
             let mut lit =
                 json_to_literal(&data.event.content, interval.to_owned(), &data.context.flow)?;
 
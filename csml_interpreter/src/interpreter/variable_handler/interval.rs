@@ -12,6 +12,7 @@ pub fn interval_from_expr(expr: &Expr) -> Interval {
             ..
         } => *range_interval,
         Expr::VecExpr(_e, range_interval) => *range_interval,
+        Expr::AwaitAllExpr(_e, range_interval) => *range_interval,
         Expr::ObjectExpr(fnexpr) => interval_from_reserved_fn(fnexpr),
         Expr::InfixExpr(_i, expr, _e) => interval_from_expr(expr), // RangeInterval ?
         Expr::PostfixExpr(_p, expr) => interval_from_expr(expr),   // RangeInterval ?
@@ -21,6 +22,7 @@ pub fn interval_from_expr(expr: &Expr) -> Interval {
         Expr::IdentExpr(ident) => ident.interval.to_owned(),
         Expr::LitExpr { literal, .. } => literal.interval.to_owned(),
         Expr::IfExpr(ifstmt) => interval_from_if_stmt(ifstmt),
+        Expr::FilterExpr(expr, _filters) => interval_from_expr(expr),
     }
 }
 
@@ -42,7 +44,9 @@ pub fn interval_from_reserved_fn(reserved_fn: &ObjectType) -> Interval {
         ObjectType::Debug(_expr, interval) => interval.to_owned(),
         ObjectType::Log { interval, .. } => interval.to_owned(),
         ObjectType::Return(expr) => interval_from_expr(expr),
-        ObjectType::Remember(ident, ..) => ident.interval.to_owned(),
+        ObjectType::Remember(ident, ..)
+        | ObjectType::RememberTemp(ident, ..)
+        | ObjectType::RememberUser(ident, ..) => ident.interval.to_owned(),
         ObjectType::Forget(_, interval) => interval.to_owned(),
         ObjectType::Assign(_assign, ident, ..) => interval_from_expr(ident),
         ObjectType::As(ident, ..) => ident.interval.to_owned(),
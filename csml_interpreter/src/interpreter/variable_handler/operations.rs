@@ -1,8 +1,8 @@
 use crate::data::{
     ast::{Expr, Infix, Pretfix},
     position::Position,
-    primitive::boolean::PrimitiveBoolean,
-    warnings::DisplayWarnings,
+    primitive::{boolean::PrimitiveBoolean, tools::get_integer, PrimitiveType},
+    warnings::{DisplayWarnings, WARNING_COERCION},
     Data, Literal, MessageData, MSG,
 };
 use crate::error_format::{gen_error_info, ErrorInfo};
@@ -134,10 +134,51 @@ pub fn evaluate_infix(
             !match_obj(lhs, rhs),
             lhs.interval,
         )),
+        (Infix::Coalesce, Ok(lhs), Ok(rhs)) => match lhs.primitive.get_type() {
+            PrimitiveType::PrimitiveNull => Ok(rhs),
+            _ => Ok(lhs),
+        },
         (_, Err(e), ..) | (.., Err(e)) => Err(e),
     }
 }
 
+/**
+ * Warning of the operations between a number and a string holding a number,
+ * the string is silently coerced: "42" + 1 is 43
+ */
+pub fn get_coercion_warning(
+    infix: &Infix,
+    lhs: &Result<Literal, ErrorInfo>,
+    rhs: &Result<Literal, ErrorInfo>,
+) -> Option<String> {
+    let (lhs, rhs) = match (infix, lhs, rhs) {
+        (Infix::And, ..)
+        | (Infix::Or, ..)
+        | (Infix::Match, ..)
+        | (Infix::NotMatch, ..)
+        | (Infix::Coalesce, ..) => return None,
+        (_, Ok(lhs), Ok(rhs)) => (lhs, rhs),
+        _ => return None,
+    };
+
+    let is_number = |literal: &Literal| {
+        matches!(
+            literal.primitive.get_type(),
+            PrimitiveType::PrimitiveInt | PrimitiveType::PrimitiveFloat
+        )
+    };
+    let text = match (lhs.primitive.get_type(), rhs.primitive.get_type()) {
+        (PrimitiveType::PrimitiveString, _) if is_number(rhs) => lhs.primitive.to_string(),
+        (_, PrimitiveType::PrimitiveString) if is_number(lhs) => rhs.primitive.to_string(),
+        _ => return None,
+    };
+
+    match get_integer(&text) {
+        Ok(_) => Some(format!("{} {:?} ({:?})", WARNING_COERCION, text, infix)),
+        Err(_) => None,
+    }
+}
+
 pub fn evaluate_postfix(
     postfixes: &[Pretfix],
     expr: &Box<Expr>,
@@ -10,7 +10,10 @@ use crate::data::{
 };
 use crate::error_format::*;
 use crate::interpreter::{
-    builtins::{match_builtin, match_native_builtin},
+    builtins::{
+        match_builtin, match_native_builtin,
+        plugins::{is_plugin_function, plugin},
+    },
     function_scope::exec_fn_in_new_scope,
     variable_handler::resolve_fn_args,
     variable_handler::save_literal_in_mem,
@@ -29,6 +32,7 @@ enum ObjType {
     Function { fn_args: Vec<String>, scope: Expr },
     Import,
     Closure { fn_args: Vec<String>, scope: Expr },
+    Plugin,
     Error,
 }
 ////////////////////////////////////////////////////////////////////////////////
@@ -80,6 +84,10 @@ fn get_type<'a>(name: &'a str, interval: Interval, data: &'a Data) -> ObjType {
         return ObjType::Closure { fn_args, scope };
     }
 
+    if is_plugin_function(name) {
+        return ObjType::Plugin;
+    }
+
     ObjType::Error
 }
 
@@ -124,6 +132,9 @@ fn search_function<'a>(
                     position: Position::new(import.interval, origin_flow_name),
                     message: error_message,
                     additional_info: Some(error_info),
+                    step: None,
+                    snippet: None,
+                    stack: vec![],
                 })
             }
             None => {
@@ -137,6 +148,9 @@ fn search_function<'a>(
                     position: Position::new(import.interval, origin_flow_name),
                     message: error_message,
                     additional_info: Some(error_info),
+                    step: None,
+                    snippet: None,
+                    stack: vec![],
                 })
             }
         },
@@ -152,6 +166,9 @@ fn search_function<'a>(
                     position: Position::new(import.interval, origin_flow_name),
                     message: error_message,
                     additional_info: Some(error_info),
+                    step: None,
+                    snippet: None,
+                    stack: vec![],
                 })
             }
             None => {
@@ -165,6 +182,9 @@ fn search_function<'a>(
                     position: Position::new(import.interval, origin_flow_name),
                     message: error_message,
                     additional_info: Some(error_info),
+                    step: None,
+                    snippet: None,
+                    stack: vec![],
                 })
             }
         },
@@ -181,6 +201,9 @@ fn search_function<'a>(
                 position: Position::new(import.interval, origin_flow_name),
                 message: error_message,
                 additional_info: Some(error_info),
+                step: None,
+                snippet: None,
+                stack: vec![],
             })
         }
     }
@@ -388,6 +411,14 @@ pub fn resolve_object(
             )
         }
 
+        ObjType::Plugin => {
+            let resolved_args =
+                resolve_fn_args(args, data, msg_data, &DisplayWarnings::On, sender)?;
+
+            let value = plugin(name, resolved_args, &data.context.flow, interval);
+            Ok(MSG::send_error_msg(&sender, msg_data, value))
+        }
+
         ObjType::Error => {
             let err = gen_error_info(
                 Position::new(interval, &data.context.flow),
@@ -0,0 +1,164 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::literal::ContentType;
+use crate::data::primitive::{PrimitiveInt, PrimitiveObject, PrimitiveString, PrimitiveType};
+use crate::data::{
+    ast::{Function, Interval, PathLiteral},
+    tokens::*,
+    warnings::DisplayWarnings,
+    Data, Literal, MemoryType, MessageData, Position, MSG,
+};
+use crate::error_format::*;
+use crate::interpreter::variable_handler::{exec_path_actions, resolve_fn_args};
+use chrono::DateTime;
+use std::{collections::HashMap, sync::mpsc};
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn capitalize(string: &str) -> String {
+    let mut chars = string.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/**
+ * Get a Time object from a Time object, a timestamp in milliseconds or a RFC 3339 date
+ */
+fn get_time_literal(
+    literal: &Literal,
+    data: &Data,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let millis = match literal.primitive.get_type() {
+        PrimitiveType::PrimitiveObject if literal.content_type == "time" => {
+            return Ok(literal.to_owned())
+        }
+        PrimitiveType::PrimitiveInt => literal.primitive.to_string().parse::<i64>().ok(),
+        PrimitiveType::PrimitiveString => {
+            DateTime::parse_from_rfc3339(&literal.primitive.to_string())
+                .ok()
+                .map(|date| date.timestamp_millis())
+        }
+        _ => None,
+    };
+
+    match millis {
+        Some(millis) => {
+            let mut time = HashMap::new();
+            time.insert(
+                "milliseconds".to_owned(),
+                PrimitiveInt::get_literal(millis, interval),
+            );
+
+            let mut literal = PrimitiveObject::get_literal(&time, interval);
+            literal.set_content_type("time");
+
+            Ok(literal)
+        }
+        None => Err(gen_error_info(
+            Position::new(interval, &data.context.flow),
+            ERROR_FILTER_DATE.to_owned(),
+        )),
+    }
+}
+
+fn apply_filter(
+    literal: Literal,
+    filter: &Function,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let interval = filter.interval;
+    let args = resolve_fn_args(&filter.args, data, msg_data, &DisplayWarnings::On, sender)?;
+
+    let mut result = match filter.name.as_str() {
+        CAPITALIZE_FILTER => {
+            PrimitiveString::get_literal(&capitalize(&literal.primitive.to_string()), interval)
+        }
+        UPPER_FILTER => {
+            PrimitiveString::get_literal(&literal.primitive.to_string().to_uppercase(), interval)
+        }
+        LOWER_FILTER => {
+            PrimitiveString::get_literal(&literal.primitive.to_string().to_lowercase(), interval)
+        }
+        DEFAULT_FILTER => {
+            let default = match args.get("value", 0) {
+                Some(default) => default.to_owned(),
+                None => {
+                    return Err(gen_error_info(
+                        Position::new(interval, &data.context.flow),
+                        ERROR_FILTER_DEFAULT.to_owned(),
+                    ))
+                }
+            };
+
+            match literal.primitive.get_type() {
+                PrimitiveType::PrimitiveNull => default,
+                PrimitiveType::PrimitiveString if literal.primitive.to_string().is_empty() => {
+                    default
+                }
+                _ => literal.to_owned(),
+            }
+        }
+        DATE_FILTER => {
+            let mut time = get_time_literal(&literal, data, interval)?;
+            let content_type = ContentType::get(&time);
+            let path = vec![(
+                interval,
+                PathLiteral::Func {
+                    name: "format".to_owned(),
+                    interval,
+                    args,
+                },
+            )];
+
+            let (formatted, _) = exec_path_actions(
+                &mut time,
+                &DisplayWarnings::On,
+                &MemoryType::Use,
+                None,
+                &Some(path),
+                &content_type,
+                data,
+                msg_data,
+                sender,
+            )?;
+
+            formatted
+        }
+        _ => {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                ERROR_UNKNOWN_FILTER.to_owned(),
+            ))
+        }
+    };
+
+    result.secure_variable = literal.secure_variable;
+
+    Ok(result)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Apply the filters of an expandable string in order: "{{ name | default(\"there\") | capitalize }}"
+ */
+pub fn apply_filters(
+    literal: Literal,
+    filters: &[Function],
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    filters.iter().try_fold(literal, |literal, filter| {
+        apply_filter(literal, filter, data, msg_data, sender)
+    })
+}
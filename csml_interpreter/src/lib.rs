@@ -1,48 +1,225 @@
+pub mod ast_export;
+pub mod coverage;
 pub mod data;
 pub mod error_format;
 pub mod fold_bot;
+pub mod format_flow;
 pub mod interpreter;
 pub mod linter;
 pub mod parser;
+pub mod snapshot;
 
+pub use ast_export::{
+    parse_flow_to_ast_json, parse_flow_to_partial_ast_json, parse_step, round_trip,
+};
 pub use data::csml_logs;
+pub use format_flow::format_flow;
 pub use interpreter::components::load_components;
 pub use parser::step_checksum::get_step;
 
 use interpreter::{interpret_scope, json_to_literal};
-use parser::parse_flow;
+use parser::{parse_flow, parse_flow_with_recovery};
 
-use data::ast::{Expr, Flow, InsertStep, InstructionScope, Interval};
+use data::ast::{Expr, Flow, InsertStep, InstructionScope, Interval, StepAccess, StepTimeout};
 use data::context::{get_hashmap_from_mem, ContextStepInfo};
-use data::error_info::ErrorInfo;
+use data::error_info::{ErrorInfo, StackFrame};
 use data::event::Event;
+use data::execution_limits::is_step_timeout_error;
 use data::literal::create_error_info;
+use data::message::Message;
 use data::message_data::MessageData;
 use data::msg::MSG;
+use data::step_hooks::variables_snapshot;
 use data::CsmlResult;
 use data::{csml_bot::CsmlBot, CsmlFlow};
-use data::{Context, Data, Position, STEP_LIMIT};
+use data::{Context, Data, ExecutionLimits, Literal, Position};
 use error_format::*;
 use fold_bot::fold_bot as fold;
 use linter::{linter::lint_bot, FlowToValidate};
 use parser::ExitCondition;
 
+use chrono::Utc;
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::env;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 ////////////////////////////////////////////////////////////////////////////////
 // PRIVATE FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
+fn get_flow_content<'a>(bot: &'a CsmlBot, flow_name: &str) -> Option<&'a str> {
+    let modules = bot
+        .modules
+        .iter()
+        .flatten()
+        .filter_map(|module| module.flow.as_ref());
+
+    bot.flows
+        .iter()
+        .chain(modules)
+        .find(|flow| flow.name == flow_name)
+        .map(|flow| flow.content.as_str())
+}
+
+fn add_error_trace(
+    result: Result<MessageData, ErrorInfo>,
+    step: &str,
+    bot: &CsmlBot,
+    stack: &[StackFrame],
+) -> Result<MessageData, ErrorInfo> {
+    result.map_err(|error| {
+        let source = get_flow_content(bot, &error.position.flow);
+        error.with_trace(step, source, stack)
+    })
+}
+
+/**
+ * A claim is granted when the metadata of the request has its value, or an array containing it
+ */
+fn has_claim(metadata: &HashMap<String, Literal>, name: &str, value: &str) -> bool {
+    match metadata.get(name).map(|literal| literal.primitive.to_json()) {
+        Some(serde_json::Value::String(claim)) => claim == value,
+        Some(serde_json::Value::Array(claims)) => {
+            claims.iter().any(|claim| claim.as_str() == Some(value))
+        }
+        _ => false,
+    }
+}
+
+/**
+ * Access of the flow (unless the step is its denied step) or of the step
+ * missing claims in the metadata of the request
+ */
+fn get_denied_access<'a>(
+    flow: &'a Flow,
+    step: &str,
+    metadata: &HashMap<String, Literal>,
+) -> Option<&'a StepAccess> {
+    let flow_access = flow
+        .flow_access
+        .as_ref()
+        .filter(|access| access.denied_step.as_deref() != Some(step));
+
+    flow_access
+        .into_iter()
+        .chain(flow.steps_access.get(step))
+        .find(|access| {
+            !access
+                .claims
+                .iter()
+                .all(|(name, value)| has_claim(metadata, name, value))
+        })
+}
+
+/**
+ * Enter the denied step of the access instead of the step, the conversation ends with an error
+ * if the access has no denied step or if it is denied too
+ */
+fn deny_step(
+    access: &StepAccess,
+    step: &str,
+    flow: &Flow,
+    data: &mut Data,
+    bot: &CsmlBot,
+    stack: &[StackFrame],
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> MessageData {
+    match &access.denied_step {
+        Some(denied_step)
+            if get_denied_access(flow, denied_step, &data.context.metadata).is_none() =>
+        {
+            data.context.step = ContextStepInfo::Normal(denied_step.to_owned());
+            MSG::send(
+                &sender,
+                MSG::Next {
+                    flow: Some(data.context.flow.to_owned()),
+                    step: Some(data.context.step.clone()),
+                    bot: None,
+                },
+            );
+
+            MessageData::default()
+        }
+        _ => {
+            let msg_data = Err(gen_error_info(
+                Position::new(access.interval, &data.context.flow),
+                format!("[{}] {}", step, ERROR_STEP_ACCESS_DENIED),
+            ));
+
+            let msg_data = add_error_trace(msg_data, step, bot, stack);
+            MessageData::error_to_message(msg_data, sender)
+        }
+    }
+}
+
+/**
+ * The external calls of the step exceeded its budget: send the interim message of the timeout
+ * and enter its fallback step. Without message, the interim message is a "timeout" message
+ * the channel can render.
+ */
+fn enter_fallback_step(
+    timeout: &StepTimeout,
+    data: &mut Data,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> MessageData {
+    csml_logs::csml_logger(
+        csml_logs::CsmlLog::new(
+            None,
+            Some(data.context.flow.to_owned()),
+            Some(timeout.interval.start_line),
+            format!(
+                "step {} timed out after {}ms, goto {}",
+                timeout.step, timeout.duration, timeout.fallback_step
+            ),
+        ),
+        csml_logs::LogLvl::Warn,
+    );
+
+    let message = match &timeout.message {
+        Some(text) => Message {
+            content_type: "text".to_owned(),
+            content: serde_json::json!({ "text": text }),
+        },
+        None => Message {
+            content_type: "timeout".to_owned(),
+            content: serde_json::json!({
+                "step": timeout.step,
+                "timeout": timeout.duration,
+                "goto": timeout.fallback_step,
+            }),
+        },
+    };
+    MSG::send(sender, MSG::Message(message.clone()));
+
+    data.context.step = ContextStepInfo::Normal(timeout.fallback_step.to_owned());
+    MSG::send(
+        sender,
+        MSG::Next {
+            flow: Some(data.context.flow.to_owned()),
+            step: Some(data.context.step.clone()),
+            bot: None,
+        },
+    );
+
+    MessageData {
+        exit_condition: Some(ExitCondition::Goto),
+        ..MessageData::default()
+    }
+    .add_message(message)
+}
+
 fn execute_step(
     step: &str,
     flow: &Flow,
     mut data: &mut Data,
+    bot: &CsmlBot,
+    stack: &[StackFrame],
     sender: &Option<mpsc::Sender<MSG>>,
 ) -> MessageData {
     // stop execution if step_count >= STEP_LIMIT in order to avoid infinite loops
-    if *data.step_count >= data.step_limit {
+    if *data.step_count >= data.limits.step_limit {
         let msg_data = Err(gen_error_info(
             Position::new(
                 Interval::new_as_u32(0, 0, 0, None, None),
@@ -51,9 +228,47 @@ fn execute_step(
             format!("{}, stop at step {}", ERROR_STEP_LIMIT, step),
         ));
 
+        let msg_data = add_error_trace(msg_data, step, bot, stack);
+        return MessageData::error_to_message(msg_data, sender);
+    }
+
+    if let Err(error) = data.limits.check_time(
+        Interval::new_as_u32(0, 0, 0, None, None),
+        &data.context.flow,
+    ) {
+        let msg_data = add_error_trace(Err(error), step, bot, stack);
         return MessageData::error_to_message(msg_data, sender);
     }
 
+    if let Some(access) = get_denied_access(flow, step, &data.context.metadata) {
+        return deny_step(access, step, flow, data, bot, stack, sender);
+    }
+
+    let step_hooks = data.context.step_hooks.clone();
+    if let Some(hooks) = &step_hooks {
+        let variables = variables_snapshot(&data.get_all_memories());
+
+        if let Err(reason) = hooks.before_step(&data.context.flow, step, &variables) {
+            let msg_data = Err(gen_error_info(
+                Position::new(
+                    Interval::new_as_u32(0, 0, 0, None, None),
+                    &data.context.flow,
+                ),
+                format!("[{}] {}: {}", step, ERROR_STEP_VETOED, reason),
+            ));
+
+            let msg_data = add_error_trace(msg_data, step, bot, stack);
+            return MessageData::error_to_message(msg_data, sender);
+        }
+    }
+    let flow_name = data.context.flow.to_owned();
+    let start = Instant::now();
+    let started_at = Utc::now();
+
+    let timeout = flow.steps_timeout.get(step);
+    data.limits.step_deadline =
+        timeout.map(|timeout| start + Duration::from_millis(timeout.duration));
+
     let mut msg_data = match flow
         .flow_instructions
         .get(&InstructionScope::StepScope(step.to_owned()))
@@ -71,6 +286,24 @@ fn execute_step(
         )),
     };
 
+    if let Some(timeout) = timeout {
+        let is_expired = data
+            .limits
+            .check_step_timeout(timeout.interval, &flow_name)
+            .is_err();
+
+        msg_data = match msg_data {
+            Err(error) if is_step_timeout_error(&error) => {
+                Ok(enter_fallback_step(timeout, data, sender))
+            }
+            // the last action of the step was aborted by the timeout
+            Ok(msg_data) if msg_data.exit_condition.is_none() && is_expired => {
+                Ok(msg_data + enter_fallback_step(timeout, data, sender))
+            }
+            msg_data => msg_data,
+        };
+    }
+
     if let Ok(msg_data) = &mut msg_data {
         match &mut msg_data.exit_condition {
             Some(condition) if *condition == ExitCondition::Goto => {
@@ -93,15 +326,22 @@ fn execute_step(
         }
     }
 
-    MessageData::error_to_message(msg_data, sender)
-}
-
-fn get_step_limit(event: &Event) -> usize {
-    match (event.step_limit, env::var("STEP_LIMIT").ok()) {
-        (Some(step_limit), _) => step_limit,
-        (None, Some(step_limit)) => step_limit.parse::<usize>().unwrap_or(STEP_LIMIT),
-        _ => STEP_LIMIT,
+    if let Some(hooks) = &step_hooks {
+        let variables = variables_snapshot(&data.get_all_memories());
+        hooks.after_step(&flow_name, step, start.elapsed(), &variables);
     }
+    MSG::send(
+        sender,
+        MSG::StepTiming {
+            flow: flow_name,
+            step: step.to_owned(),
+            started_at,
+            ended_at: Utc::now(),
+        },
+    );
+
+    let msg_data = add_error_trace(msg_data, step, bot, stack);
+    MessageData::error_to_message(msg_data, sender)
 }
 
 fn get_flow_ast<'a, 'b>(
@@ -124,6 +364,9 @@ fn get_flow_ast<'a, 'b>(
                     },
                     message: error_message,
                     additional_info: Some(error_info),
+                    step: None,
+                    snippet: None,
+                    stack: vec![],
                 }),
                 &sender,
             ))
@@ -216,54 +459,57 @@ pub fn get_steps_from_flow(bot: CsmlBot) -> HashMap<String, Vec<String>> {
     result
 }
 
-pub fn validate_bot(bot: &CsmlBot) -> CsmlResult {
-    csml_logs::init_logger();
+/**
+ * Parse the flows and the modules of the bot in parallel.
+ * The results are collected in the order of the bot flows,
+ * so the errors are always reported in the same order.
+ */
+fn parse_bot_flows(
+    bot: &CsmlBot,
+) -> (
+    Vec<FlowToValidate<'_>>,
+    Vec<FlowToValidate<'_>>,
+    Vec<ErrorInfo>,
+) {
+    fn parse(flow: &CsmlFlow) -> Result<FlowToValidate<'_>, Vec<ErrorInfo>> {
+        parse_flow_with_recovery(&flow.content, &flow.name).map(|ast_flow| FlowToValidate {
+            flow_name: flow.name.to_owned(),
+            ast: ast_flow,
+            raw_flow: &flow.content,
+        })
+    }
+
+    let parsed_flows: Vec<_> = bot.flows.par_iter().map(parse).collect();
 
+    let module_flows: Vec<&CsmlFlow> = match &bot.modules {
+        Some(modules) => modules
+            .iter()
+            .filter_map(|module| module.flow.as_ref())
+            .collect(),
+        None => vec![],
+    };
+    let parsed_modules: Vec<_> = module_flows.par_iter().map(|flow| parse(flow)).collect();
+
+    let mut errors = Vec::new();
     let mut flows = vec![];
     let mut modules = vec![];
-    let mut errors = Vec::new();
-    let mut imports = Vec::new();
 
-    for flow in bot.flows.iter() {
-        match parse_flow(&flow.content, &flow.name) {
-            Ok(ast_flow) => {
-                for (scope, ..) in ast_flow.flow_instructions.iter() {
-                    if let InstructionScope::ImportScope(import_scope) = scope {
-                        imports.push(import_scope.clone());
-                    }
-                }
-
-                // flows.insert(flow.name.to_owned(), ast_flow);
-                flows.push(FlowToValidate {
-                    flow_name: flow.name.to_owned(),
-                    ast: ast_flow,
-                    raw_flow: &flow.content,
-                });
-            }
-            Err(error) => {
-                errors.push(error);
+    for (results, parsed) in [(parsed_flows, &mut flows), (parsed_modules, &mut modules)] {
+        for result in results {
+            match result {
+                Ok(flow) => parsed.push(flow),
+                Err(flow_errors) => errors.extend(flow_errors),
             }
         }
     }
 
-    if let Some(ref mods) = bot.modules {
-        for module in mods.iter() {
-            if let Some(flow) = &module.flow {
-                match parse_flow(&flow.content, &flow.name) {
-                    Ok(ast_flow) => {
-                        modules.push(FlowToValidate {
-                            flow_name: flow.name.to_owned(),
-                            ast: ast_flow,
-                            raw_flow: &flow.content,
-                        });
-                    }
-                    Err(error) => {
-                        errors.push(error);
-                    }
-                }
-            }
-        }
-    }
+    (flows, modules, errors)
+}
+
+pub fn validate_bot(bot: &CsmlBot) -> CsmlResult {
+    csml_logs::init_logger();
+
+    let (flows, modules, mut errors) = parse_bot_flows(bot);
 
     let mut warnings = vec![];
     // only use the linter if there is no error in the paring otherwise the linter will catch false errors
@@ -289,51 +535,7 @@ pub fn validate_bot(bot: &CsmlBot) -> CsmlResult {
 pub fn fold_bot(bot: &CsmlBot) -> String {
     csml_logs::init_logger();
 
-    let mut flows = vec![];
-    let mut modules = vec![];
-    let mut errors = Vec::new();
-    let mut imports = Vec::new();
-
-    for flow in bot.flows.iter() {
-        match parse_flow(&flow.content, &flow.name) {
-            Ok(ast_flow) => {
-                for (scope, ..) in ast_flow.flow_instructions.iter() {
-                    if let InstructionScope::ImportScope(import_scope) = scope {
-                        imports.push(import_scope.clone());
-                    }
-                }
-
-                // flows.insert(flow.name.to_owned(), ast_flow);
-                flows.push(FlowToValidate {
-                    flow_name: flow.name.to_owned(),
-                    ast: ast_flow,
-                    raw_flow: &flow.content,
-                });
-            }
-            Err(error) => {
-                errors.push(error);
-            }
-        }
-    }
-
-    if let Some(ref mods) = bot.modules {
-        for module in mods.iter() {
-            if let Some(flow) = &module.flow {
-                match parse_flow(&flow.content, &flow.name) {
-                    Ok(ast_flow) => {
-                        modules.push(FlowToValidate {
-                            flow_name: flow.name.to_owned(),
-                            ast: ast_flow,
-                            raw_flow: &flow.content,
-                        });
-                    }
-                    Err(error) => {
-                        errors.push(error);
-                    }
-                }
-            }
-        }
-    }
+    let (flows, modules, mut errors) = parse_bot_flows(bot);
 
     let mut warnings = vec![];
     // only use the fold if there is no error in the paring otherwise the linter will catch false errors
@@ -453,7 +655,9 @@ pub fn interpret(
     let mut step = context.step.to_owned();
 
     let mut step_count = 0;
-    let step_limit = get_step_limit(&event);
+    let mut goto_flow_count = 0;
+    let limits = ExecutionLimits::new(&event);
+    let mut stack = vec![];
 
     let mut step_vars = match &context.hold {
         Some(hold) => get_hashmap_from_mem(&hold.step_vars, &flow),
@@ -472,8 +676,8 @@ pub fn interpret(
 
     let (flows, extern_flows) = get_flows(&bot);
 
-    let env = match bot.env {
-        Some(env) => json_to_literal(&env, Interval::default(), &flow).unwrap(),
+    let env = match &bot.env {
+        Some(env) => json_to_literal(env, Interval::default(), &flow).unwrap(),
         None => data::primitive::PrimitiveNull::get_literal(Interval::default()),
     };
 
@@ -510,24 +714,44 @@ pub fn interpret(
             vec![],
             0,
             &mut step_count,
-            step_limit,
+            limits,
             step_vars,
             previous_info.clone(),
             &custom,
             &native,
+            &bot.locales,
         );
 
-        msg_data = match inserted_ast {
-            Some(inserted_ast) => {
-                msg_data + execute_step(&step.get_step(), &inserted_ast, &mut data, &sender)
-            }
-            None => msg_data + execute_step(&step.get_step(), &ast, &mut data, &sender),
-        };
+        let step_name = step.get_step();
+        stack.push(StackFrame {
+            flow: flow.to_owned(),
+            step: step_name.to_owned(),
+        });
+
+        let step_ast = inserted_ast.unwrap_or(ast);
+        msg_data = msg_data + execute_step(&step_name, step_ast, &mut data, &bot, &stack, &sender);
 
         previous_info = data.previous_info.clone();
-        flow = data.context.flow.to_string();
         step = data.context.step.clone();
 
+        // stop execution if the flows keep going to each other
+        if data.context.flow != flow {
+            goto_flow_count += 1;
+        }
+        flow = data.context.flow.to_string();
+
+        if msg_data.exit_condition.is_none() && goto_flow_count > limits.goto_flow_limit {
+            let error = Err(gen_error_info(
+                Position::new(Interval::new_as_u32(0, 0, 0, None, None), &flow),
+                format!(
+                    "{}: {} gotos to another flow where executed in a single run",
+                    ERROR_GOTO_FLOW_LIMIT, limits.goto_flow_limit
+                ),
+            ));
+
+            return msg_data + MessageData::error_to_message(error, &sender);
+        }
+
         // add reset loops index
         step_vars = HashMap::new();
     }
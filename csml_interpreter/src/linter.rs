@@ -1,5 +1,6 @@
 pub mod data;
 pub mod linter;
+pub mod variables;
 
 use crate::data::ast::Flow;
 pub use data::{
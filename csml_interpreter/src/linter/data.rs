@@ -106,6 +106,10 @@ pub struct LinterInfo<'a> {
     pub errors: &'a mut Vec<ErrorInfo>,
     pub warnings: &'a mut Vec<Warnings>,
     pub native_components: &'a Option<serde_json::Map<String, serde_json::Value>>,
+    // flows in which a goto computed at runtime (goto $var) can reach any step
+    pub dynamic_goto_flows: HashSet<String>,
+    // a goto computed at runtime can reach any step of any flow (goto step@$var)
+    pub dynamic_goto_any_flow: bool,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -257,6 +261,8 @@ impl<'a> LinterInfo<'a> {
             errors,
             warnings,
             native_components,
+            dynamic_goto_flows: HashSet::new(),
+            dynamic_goto_any_flow: false,
         }
     }
 }
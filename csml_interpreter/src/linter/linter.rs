@@ -10,7 +10,9 @@ use crate::error_format::{
     convert_error_from_interval, gen_error_info, gen_infinite_loop_error_msg, gen_warning_info,
     ErrorInfo,
 };
+use crate::interpreter::builtins::plugins::is_plugin_function;
 use crate::interpreter::variable_handler::interval::interval_from_expr;
+use crate::linter::variables::validate_variables;
 use crate::linter::{
     ConstantInfo, FlowConstantUse, FlowToValidate, FunctionCallInfo, FunctionInfo, ImportInfo,
     InsertInfo, LinterInfo, ScopeType, State, StepBreakers, StepInfo,
@@ -90,6 +92,7 @@ pub fn lint_bot(
     }
 
     validate_gotos(&mut linter_info);
+    validate_dead_steps(flows, &mut linter_info);
     validate_imports(&mut linter_info);
     validate_functions(&mut linter_info);
     validate_constants(&mut linter_info);
@@ -113,6 +116,8 @@ pub fn lint_bot(
         }
         None => {}
     }
+
+    validate_variables(flows, modules, linter_info.warnings);
 }
 
 pub fn validate_gotos(linter_info: &mut LinterInfo) {
@@ -121,21 +126,83 @@ pub fn validate_gotos(linter_info: &mut LinterInfo) {
             continue;
         }
 
-        // toto
-
-        // if let None = linter_info.step_list.get(&goto_info) {
-        //     linter_info.errors.push(gen_error_info(
-        //         Position::new(goto_info.interval.to_owned(), &goto_info.in_flow),
-        //         convert_error_from_interval(
-        //             Span::new(goto_info.raw_flow),
-        //             format!(
-        //                 "step {} at flow {} does not exist",
-        //                 goto_info.step, goto_info.flow
-        //             ),
-        //             goto_info.interval.to_owned(),
-        //         ),
-        //     ));
-        // }
+        let message = if !linter_info
+            .step_list
+            .iter()
+            .any(|step_info| step_info.flow == goto_info.flow)
+        {
+            format!("goto target flow [{}] does not exist", goto_info.flow)
+        } else if linter_info.step_list.get(goto_info).is_none()
+            && !is_inserted_step(linter_info, &goto_info.flow, &goto_info.step)
+        {
+            format!(
+                "goto target step [{}] does not exist in flow [{}]",
+                goto_info.step, goto_info.flow
+            )
+        } else {
+            continue;
+        };
+
+        linter_info.warnings.push(gen_warning_info(
+            Position::new(goto_info.interval.to_owned(), &goto_info.in_flow),
+            convert_error_from_interval(
+                Span::new(goto_info.raw_flow),
+                message,
+                goto_info.interval.to_owned(),
+            ),
+        ));
+    }
+}
+
+/**
+ * Warn about the steps that can never be executed: the 'start' step of a flow is
+ * reached by its commands or by the default flow, the other steps need a goto or an insert
+ */
+pub fn validate_dead_steps(flows: &[FlowToValidate], linter_info: &mut LinterInfo) {
+    if linter_info.dynamic_goto_any_flow {
+        return;
+    }
+
+    for flow in flows.iter() {
+        if linter_info.dynamic_goto_flows.contains(&flow.flow_name) {
+            continue;
+        }
+
+        for (instruction_scope, scope) in flow.ast.flow_instructions.iter() {
+            let step_name = match instruction_scope {
+                InstructionScope::StepScope(step_name) if step_name != "start" => step_name,
+                _ => continue,
+            };
+
+            let is_goto_target = linter_info
+                .goto_list
+                .iter()
+                .any(|goto_info| goto_info.flow == flow.flow_name && &goto_info.step == step_name);
+            let is_insert_source = linter_info.insert_list.iter().any(|insert_info| {
+                let original_name = match insert_info.original_name {
+                    Some(ref name) => name,
+                    None => &insert_info.as_name,
+                };
+
+                insert_info.from_flow == flow.flow_name && original_name == step_name
+            });
+
+            if !is_goto_target && !is_insert_source {
+                let interval = interval_from_expr(scope);
+
+                linter_info.warnings.push(gen_warning_info(
+                    Position::new(interval, &flow.flow_name),
+                    convert_error_from_interval(
+                        Span::new(flow.raw_flow),
+                        format!(
+                            "step [{}] in flow [{}] is never reached by a goto",
+                            step_name, flow.flow_name
+                        ),
+                        interval,
+                    ),
+                ));
+            }
+        }
     }
 }
 
@@ -312,15 +379,11 @@ pub fn validate_inserts(linter_info: &mut LinterInfo) {
 
 pub fn validate_functions(linter_info: &mut LinterInfo) {
     for info in linter_info.functions_call_list.iter() {
-        let is_native_component = match linter_info.native_components {
-            Some(native_component) => native_component.contains_key(&info.name),
-            None => false,
-        };
-
-        if !is_native_component
+        if !is_native_component(&info.name, linter_info)
             && !BUILT_IN.contains(&info.name.as_str())
             && !BUILT_IN_WITHOUT_WARNINGS.contains(&info.name.as_str())
             && COMPONENT != info.name
+            && !is_plugin_function(&info.name)
             && !validate_closure(&info, linter_info)
             && !function_exist(&info, linter_info)
         {
@@ -371,6 +434,32 @@ pub fn validate_flow_ast(flow: &FlowToValidate, linter_info: &mut LinterInfo, ex
         };
     }
 
+    // the denied steps of the restricted steps are entered like gotos
+    for access in flow.ast.flow_access.iter().chain(flow.ast.steps_access.values()) {
+        if let Some(denied_step) = &access.denied_step {
+            linter_info.goto_list.push(StepInfo::new(
+                linter_info.flow_name,
+                denied_step,
+                linter_info.raw_flow,
+                linter_info.flow_name.to_owned(),
+                vec![],
+                access.interval.to_owned(),
+            ));
+        }
+    }
+
+    // the fallback steps of the timeouts too
+    for timeout in flow.ast.steps_timeout.values() {
+        linter_info.goto_list.push(StepInfo::new(
+            linter_info.flow_name,
+            &timeout.fallback_step,
+            linter_info.raw_flow,
+            linter_info.flow_name.to_owned(),
+            vec![],
+            timeout.interval.to_owned(),
+        ));
+    }
+
     for (instruction_scope, scope) in flow.ast.flow_instructions.iter() {
         match instruction_scope {
             InstructionScope::StepScope(step_name) => {
@@ -440,7 +529,9 @@ pub fn validate_flow_ast(flow: &FlowToValidate, linter_info: &mut LinterInfo, ex
                 ));
             }
 
-            InstructionScope::Constant(_) => {}
+            InstructionScope::Constant(_)
+            | InstructionScope::StepAccess(_)
+            | InstructionScope::StepTimeout(_) => {}
 
             InstructionScope::DuplicateInstruction(interval, info) => {
                 linter_info.errors.push(gen_error_info(
@@ -497,11 +588,158 @@ fn register_flow_breaker(
     }
 }
 
+fn is_inserted_step(linter_info: &LinterInfo, flow: &str, step: &str) -> bool {
+    linter_info
+        .insert_list
+        .iter()
+        .any(|insert_info| insert_info.in_flow == flow && insert_info.as_name == step)
+}
+
 fn is_in_list(list: &Vec<(String, String)>, flow: &str, step: &str) -> bool {
     list.iter()
         .any(|(next_flow, next_step)| flow == next_flow && step == next_step)
 }
 
+/**
+ * Type of the value of an argument known before the execution, with the names of the
+ * types of the components parameters ("String", "Object", ...)
+ */
+fn get_static_type(expr: &Expr, linter_info: &LinterInfo) -> Option<&'static str> {
+    match expr {
+        Expr::ComplexLiteral(..) => Some("String"),
+        Expr::MapExpr { .. } => Some("Object"),
+        Expr::VecExpr(..) => Some("Array"),
+        Expr::LitExpr { literal, .. } => match literal.primitive.get_type() {
+            PrimitiveType::PrimitiveString => Some("String"),
+            PrimitiveType::PrimitiveInt | PrimitiveType::PrimitiveFloat => Some("Number"),
+            PrimitiveType::PrimitiveBoolean => Some("Bool"),
+            PrimitiveType::PrimitiveNull => Some("Null"),
+            PrimitiveType::PrimitiveArray => Some("Array"),
+            PrimitiveType::PrimitiveObject => Some("Object"),
+            _ => None,
+        },
+        // the Text component is a string, the other components are objects
+        Expr::ObjectExpr(ObjectType::BuiltIn(Function { name, .. }))
+            if is_native_component(name, linter_info) =>
+        {
+            match name.as_str() {
+                "Text" => Some("String"),
+                _ => Some("Object"),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_native_component(name: &str, linter_info: &LinterInfo) -> bool {
+    match linter_info.native_components {
+        Some(native_component) => native_component.contains_key(name),
+        None => false,
+    }
+}
+
+/**
+ * The value is added to the default value of its parameter when the component is generated:
+ * only the values of the same type and the strings added to arrays are valid
+ */
+fn is_valid_param_type(found: &str, expected: &str) -> bool {
+    found == expected || (found == "String" && expected == "Array")
+}
+
+/**
+ * Parameter of the component (name, type) of a named or positional argument
+ */
+fn get_component_param<'a>(
+    params: &'a [serde_json::Value],
+    name: Option<&str>,
+    index: usize,
+) -> Option<(&'a str, &'a str)> {
+    let (key, param) = match name {
+        Some(name) => params
+            .iter()
+            .filter_map(|param| param.as_object())
+            .find_map(|param| param.get_key_value(name))?,
+        None => params.get(index)?.as_object()?.iter().next()?,
+    };
+
+    Some((key.as_str(), param.get("type")?.as_str()?))
+}
+
+/**
+ * The arguments of a native component must match the types of its parameters,
+ * Button(title = {"text": "yes"}) fails when the component is generated
+ */
+fn validate_component_args(name: &str, args: &Expr, linter_info: &mut LinterInfo) {
+    let native_components = linter_info.native_components;
+    let params = match native_components
+        .as_ref()
+        .and_then(|components| components.get(name))
+        .and_then(|component| component.get("params"))
+        .and_then(|params| params.as_array())
+    {
+        Some(params) => params,
+        None => return,
+    };
+    let args = match args {
+        Expr::VecExpr(args, ..) => args,
+        _ => return,
+    };
+    let is_named = args
+        .iter()
+        .any(|arg| matches!(arg, Expr::ObjectExpr(ObjectType::Assign(..))));
+
+    for (index, arg) in args.iter().enumerate() {
+        let (param, value) = match arg {
+            Expr::ObjectExpr(ObjectType::Assign(_, ident, value)) => match &**ident {
+                Expr::IdentExpr(ident) => {
+                    (get_component_param(params, Some(&ident.ident), 0), &**value)
+                }
+                _ => continue,
+            },
+            // with named arguments, only the first argument can be positional
+            _ if is_named && index > 0 => continue,
+            value => (get_component_param(params, None, index), value),
+        };
+        let (key, expected) = match param {
+            Some(param) => param,
+            None => continue,
+        };
+        let interval = interval_from_expr(value);
+
+        match get_static_type(value, linter_info) {
+            Some(found) if !is_valid_param_type(found, expected) => {
+                linter_info.errors.push(gen_error_info(
+                    Position::new(interval, linter_info.flow_name),
+                    convert_error_from_interval(
+                        Span::new(linter_info.raw_flow),
+                        format!(
+                            "parameter '{}' of {} must be of type {}, {} found",
+                            key, name, expected, found
+                        ),
+                        interval,
+                    ),
+                ));
+            }
+            _ => {}
+        }
+
+        // the objects interpolated in a text are rendered as JSON
+        if let (Expr::ComplexLiteral(exprs, ..), "String") = (value, expected) {
+            for expr in exprs.iter() {
+                if let Some(found @ ("Object" | "Array")) = get_static_type(expr, linter_info) {
+                    linter_info.warnings.push(gen_warning_info(
+                        Position::new(interval_from_expr(expr), linter_info.flow_name),
+                        format!(
+                            "{} interpolated in the String parameter '{}' of {}: rendered as JSON",
+                            found, key, name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
 fn validate_expr_literals(to_be_literal: &Expr, state: &mut State, linter_info: &mut LinterInfo) {
     match to_be_literal {
         Expr::ObjectExpr(ObjectType::As(name, value)) => {
@@ -549,6 +787,7 @@ fn validate_expr_literals(to_be_literal: &Expr, state: &mut State, linter_info:
                 interval.to_owned(),
             ));
 
+            validate_component_args(name, args, linter_info);
             validate_expr_literals(args, state, linter_info);
         }
         Expr::MapExpr { object, .. } => {
@@ -556,7 +795,7 @@ fn validate_expr_literals(to_be_literal: &Expr, state: &mut State, linter_info:
                 validate_expr_literals(expr, state, linter_info);
             }
         }
-        Expr::VecExpr(vec, ..) | Expr::ComplexLiteral(vec, ..) => {
+        Expr::VecExpr(vec, ..) | Expr::ComplexLiteral(vec, ..) | Expr::AwaitAllExpr(vec, ..) => {
             for expr in vec.iter() {
                 validate_expr_literals(expr, state, linter_info);
             }
@@ -565,6 +804,13 @@ fn validate_expr_literals(to_be_literal: &Expr, state: &mut State, linter_info:
             validate_expr_literals(exp_1, state, linter_info);
             validate_expr_literals(exp_2, state, linter_info);
         }
+        Expr::FilterExpr(expr, filters) => {
+            validate_expr_literals(expr, state, linter_info);
+
+            for filter in filters.iter() {
+                validate_expr_literals(&filter.args, state, linter_info);
+            }
+        }
         Expr::LitExpr { literal, .. } => {
             if literal.primitive.get_type() == PrimitiveType::PrimitiveClosure {
                 if let Ok(closure) = Literal::get_value::<PrimitiveClosure>(
@@ -623,6 +869,26 @@ fn validate_scope(
     linter_info: &mut LinterInfo,
     step_breakers: &mut Option<&mut Vec<StepBreakers>>,
 ) {
+    // a goto always leaves the current step, the next actions of its scope are never executed
+    if let Some(index) = scope
+        .commands
+        .iter()
+        .position(|(action, _)| matches!(action, Expr::ObjectExpr(ObjectType::Goto(..))))
+    {
+        if let Some((unreachable, _)) = scope.commands.get(index + 1) {
+            let interval = interval_from_expr(unreachable);
+
+            linter_info.warnings.push(gen_warning_info(
+                Position::new(interval, linter_info.flow_name),
+                convert_error_from_interval(
+                    Span::new(linter_info.raw_flow),
+                    WARNING_UNREACHABLE_CODE.to_owned(),
+                    interval,
+                ),
+            ));
+        }
+    }
+
     for (action, _) in scope.commands.iter() {
         match action {
             Expr::ObjectExpr(ObjectType::Return(value)) => {
@@ -757,6 +1023,31 @@ fn validate_scope(
                             interval.to_owned(),
                         ))
                     }
+                    // the step is only known at runtime, every step of its flow can be reached
+                    GotoType::Step(GotoValueType::Variable(_))
+                    | GotoType::StepFlow {
+                        step: Some(GotoValueType::Variable(_)),
+                        flow: None,
+                        bot: None,
+                    } => {
+                        linter_info
+                            .dynamic_goto_flows
+                            .insert(linter_info.flow_name.to_owned());
+                    }
+                    GotoType::StepFlow {
+                        step: Some(GotoValueType::Variable(_)),
+                        flow: Some(GotoValueType::Name(flow)),
+                        bot: None,
+                    } => {
+                        linter_info.dynamic_goto_flows.insert(flow.ident.to_owned());
+                    }
+                    GotoType::StepFlow {
+                        step: Some(_),
+                        flow: Some(GotoValueType::Variable(_)),
+                        bot: None,
+                    } => {
+                        linter_info.dynamic_goto_any_flow = true;
+                    }
                     _ => {}
                 }
             }
@@ -844,7 +1135,9 @@ fn validate_scope(
                 validate_expr_literals(expr, state, linter_info);
             }
 
-            Expr::ObjectExpr(ObjectType::Remember(ref name, value)) => {
+            Expr::ObjectExpr(ObjectType::Remember(ref name, value))
+            | Expr::ObjectExpr(ObjectType::RememberTemp(ref name, value))
+            | Expr::ObjectExpr(ObjectType::RememberUser(ref name, value)) => {
                 register_closure(name, true, value, linter_info);
 
                 if state.in_function > 0 {
@@ -0,0 +1,354 @@
+use crate::data::{
+    ast::*,
+    position::Position,
+    primitive::{PrimitiveClosure, PrimitiveType},
+    tokens::{Span, _ENV, _FLAGS, _GLOBAL, _MEMORY, _METADATA, _SECRETS, COMPONENT, EVENT},
+    warnings::Warnings,
+    Literal,
+};
+use crate::error_format::{convert_error_from_interval, gen_warning_info};
+use crate::linter::FlowToValidate;
+
+use std::collections::{HashMap, HashSet};
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURES
+////////////////////////////////////////////////////////////////////////////////
+
+struct VariableUse<'a> {
+    name: String,
+    flow: &'a str,
+    raw_flow: &'a str,
+    interval: Interval,
+}
+
+/**
+ * Every variable read and written in the bot, the analysis is done at the bot level:
+ * a memory remembered in a flow can be read in any other flow
+ */
+struct VariablesInfo<'a> {
+    flow: &'a str,
+    raw_flow: &'a str,
+    reads: Vec<VariableUse<'a>>,
+    writes: HashSet<String>,
+    memories: Vec<VariableUse<'a>>,
+    // memories forgotten or read with the _memory path
+    used_memories: HashSet<String>,
+    // the whole _memory object is read, every memory can be used
+    read_all_memories: bool,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn is_reserved(name: &str) -> bool {
+    [
+        COMPONENT, EVENT, _ENV, _SECRETS, _GLOBAL, _FLAGS, _METADATA, _MEMORY,
+    ]
+    .contains(&name)
+}
+
+impl<'a> VariablesInfo<'a> {
+    fn read(&mut self, ident: &Identifier) {
+        if ident.ident == _MEMORY {
+            self.read_all_memories = true;
+        }
+        if is_reserved(&ident.ident) {
+            return;
+        }
+
+        self.reads.push(VariableUse {
+            name: ident.ident.to_owned(),
+            flow: self.flow,
+            raw_flow: self.raw_flow,
+            interval: ident.interval,
+        });
+    }
+
+    fn remember(&mut self, ident: &Identifier) {
+        self.writes.insert(ident.ident.to_owned());
+        self.memories.push(VariableUse {
+            name: ident.ident.to_owned(),
+            flow: self.flow,
+            raw_flow: self.raw_flow,
+            interval: ident.interval,
+        });
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        for (expr, _) in block.commands.iter() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_if(&mut self, if_statement: &IfStatement) {
+        match if_statement {
+            IfStatement::IfStmt {
+                cond,
+                consequence,
+                then_branch,
+                ..
+            } => {
+                self.visit_expr(cond);
+                self.visit_block(consequence);
+
+                if let Some(else_scope) = then_branch {
+                    self.visit_if(else_scope);
+                }
+            }
+            IfStatement::ElseStmt(block, ..) => self.visit_block(block),
+        }
+    }
+
+    fn visit_goto_value(&mut self, value: &GotoValueType) {
+        if let GotoValueType::Variable(expr) = value {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        if literal.primitive.get_type() != PrimitiveType::PrimitiveClosure {
+            return;
+        }
+
+        if let Some(closure) = literal
+            .primitive
+            .as_any()
+            .downcast_ref::<PrimitiveClosure>()
+        {
+            self.writes.extend(closure.args.iter().cloned());
+            self.visit_expr(&closure.func);
+        }
+    }
+
+    fn visit_path(&mut self, literal: &Expr, path: &[(Interval, PathState)]) {
+        match (literal, path.first()) {
+            (Expr::IdentExpr(ident), Some((_, PathState::StringIndex(key))))
+                if ident.ident == _MEMORY =>
+            {
+                self.used_memories.insert(key.to_owned());
+            }
+            _ => self.visit_expr(literal),
+        }
+
+        for (_, node) in path.iter() {
+            match node {
+                PathState::ExprIndex(expr) => self.visit_expr(expr),
+                PathState::Func(Function { args, .. }) => self.visit_expr(args),
+                PathState::StringIndex(_) | PathState::Optional => {}
+            }
+        }
+    }
+
+    fn visit_object(&mut self, object: &ObjectType) {
+        match object {
+            ObjectType::Goto(GotoType::Step(value), ..)
+            | ObjectType::Goto(GotoType::Flow(value), ..) => self.visit_goto_value(value),
+            ObjectType::Goto(GotoType::StepFlow { step, flow, bot }, ..) => {
+                for value in [step, flow, bot].iter().filter_map(|value| value.as_ref()) {
+                    self.visit_goto_value(value);
+                }
+            }
+            ObjectType::Say(expr)
+            | ObjectType::Debug(expr, ..)
+            | ObjectType::Log { expr, .. }
+            | ObjectType::Return(expr)
+            | ObjectType::Use(expr)
+            | ObjectType::Do(DoType::Exec(expr)) => self.visit_expr(expr),
+            ObjectType::Do(DoType::Update(_, target, new)) | ObjectType::Assign(_, target, new) => {
+                match &**target {
+                    Expr::IdentExpr(ident) => {
+                        self.writes.insert(ident.ident.to_owned());
+                    }
+                    // updating a key of a variable needs the variable to exist
+                    target => self.visit_expr(target),
+                }
+                self.visit_expr(new);
+            }
+            ObjectType::Remember(ident, expr)
+            | ObjectType::RememberTemp(ident, expr)
+            | ObjectType::RememberUser(ident, expr) => {
+                self.remember(ident);
+                self.visit_expr(expr);
+            }
+            ObjectType::As(ident, expr) => {
+                self.writes.insert(ident.ident.to_owned());
+                self.visit_expr(expr);
+            }
+            ObjectType::Forget(ForgetMemory::SINGLE(ident), ..) => {
+                self.used_memories.insert(ident.ident.to_owned());
+            }
+            ObjectType::Forget(ForgetMemory::LIST(idents), ..) => {
+                self.used_memories
+                    .extend(idents.iter().map(|ident| ident.ident.to_owned()));
+            }
+            ObjectType::BuiltIn(Function { args, .. }) => self.visit_expr(args),
+            _ => {}
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Scope { scope, .. } => self.visit_block(scope),
+            Expr::ForEachExpr(ident, index, expr, block, ..) => {
+                self.writes.insert(ident.ident.to_owned());
+                if let Some(index) = index {
+                    self.writes.insert(index.ident.to_owned());
+                }
+
+                self.visit_expr(expr);
+                self.visit_block(block);
+            }
+            Expr::WhileExpr(cond, block, ..) => {
+                self.visit_expr(cond);
+                self.visit_block(block);
+            }
+            Expr::ComplexLiteral(vec, ..)
+            | Expr::VecExpr(vec, ..)
+            | Expr::AwaitAllExpr(vec, ..) => {
+                for expr in vec.iter() {
+                    // named arguments of a function are not variables
+                    match expr {
+                        Expr::ObjectExpr(ObjectType::Assign(_, _, value)) => self.visit_expr(value),
+                        expr => self.visit_expr(expr),
+                    }
+                }
+            }
+            Expr::MapExpr { object, .. } => {
+                for expr in object.values() {
+                    self.visit_expr(expr);
+                }
+            }
+            Expr::InfixExpr(_, exp_1, exp_2) => {
+                self.visit_expr(exp_1);
+                self.visit_expr(exp_2);
+            }
+            Expr::PostfixExpr(_, expr) => self.visit_expr(expr),
+            Expr::ObjectExpr(object) => self.visit_object(object),
+            Expr::IfExpr(if_statement) => self.visit_if(if_statement),
+            Expr::PathExpr { literal, path } => self.visit_path(literal, path),
+            Expr::IdentExpr(ident) => self.read(ident),
+            Expr::LitExpr { literal, .. } => self.visit_literal(literal),
+            Expr::FilterExpr(expr, filters) => {
+                self.visit_expr(expr);
+
+                for filter in filters.iter() {
+                    self.visit_expr(&filter.args);
+                }
+            }
+        }
+    }
+
+    fn new() -> Self {
+        Self {
+            flow: "",
+            raw_flow: "",
+            reads: vec![],
+            writes: HashSet::new(),
+            memories: vec![],
+            used_memories: HashSet::new(),
+            read_all_memories: false,
+        }
+    }
+
+    fn visit_flow(&mut self, flow_name: &'a str, ast: &Flow, raw_flow: &'a str) {
+        self.flow = flow_name;
+        self.raw_flow = raw_flow;
+
+        self.writes.extend(ast.constants.keys().cloned());
+
+        for (instruction_scope, scope) in ast.flow_instructions.iter() {
+            match instruction_scope {
+                InstructionScope::StepScope(_) => self.visit_expr(scope),
+                InstructionScope::FunctionScope { args, .. } => {
+                    self.writes.extend(args.iter().cloned());
+                    self.visit_expr(scope);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn gen_variable_warning(variable: &VariableUse, message: String) -> Warnings {
+    gen_warning_info(
+        Position::new(variable.interval, variable.flow),
+        convert_error_from_interval(Span::new(variable.raw_flow), message, variable.interval),
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Warn about the variables read but never written anywhere in the bot
+ * and about the memories remembered but never read (typos like 'usr_name' and 'user_name')
+ */
+pub fn validate_variables(
+    flows: &[FlowToValidate],
+    modules: &[FlowToValidate],
+    warnings: &mut Vec<Warnings>,
+) {
+    let mut info = VariablesInfo::new();
+
+    for flow in flows.iter().chain(modules.iter()) {
+        info.visit_flow(&flow.flow_name, &flow.ast, flow.raw_flow);
+    }
+
+    for variable in info.reads.iter() {
+        if !info.writes.contains(&variable.name) {
+            warnings.push(gen_variable_warning(
+                variable,
+                format!(
+                    "variable [{}] is used but never written in the bot",
+                    variable.name
+                ),
+            ));
+        }
+    }
+
+    if info.read_all_memories {
+        return;
+    }
+
+    for memory in info.memories.iter() {
+        let is_read = info.used_memories.contains(&memory.name)
+            || info
+                .reads
+                .iter()
+                .any(|variable| variable.name == memory.name);
+
+        if !is_read {
+            warnings.push(gen_variable_warning(
+                memory,
+                format!(
+                    "memory [{}] is remembered but never read in the bot",
+                    memory.name
+                ),
+            ));
+        }
+    }
+}
+
+/**
+ * Names of all the memories remembered in the flows of a bot, sorted alphabetically
+ */
+pub fn get_memories_names(flows: &HashMap<String, Flow>) -> Vec<String> {
+    let mut info = VariablesInfo::new();
+
+    for (flow_name, ast) in flows.iter() {
+        info.visit_flow(flow_name, ast, "");
+    }
+
+    let mut names: Vec<String> = info
+        .memories
+        .into_iter()
+        .map(|memory| memory.name)
+        .collect();
+    names.sort();
+    names.dedup();
+
+    names
+}
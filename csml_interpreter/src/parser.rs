@@ -18,6 +18,7 @@ pub mod parse_parenthesis;
 pub mod parse_path;
 pub mod parse_previous;
 pub mod parse_scope;
+pub mod parse_step_access;
 pub mod parse_string;
 pub mod parse_var_types;
 pub mod parse_while_loop;
@@ -29,8 +30,10 @@ use crate::parser::parse_idents::parse_idents_assignation;
 pub use state_context::ExitCondition;
 
 use crate::data::position::Position;
+use crate::data::primitive::PrimitiveNull;
 use crate::data::{ast::*, tokens::*};
 use crate::error_format::*;
+use crate::format_flow::get_sections_offsets;
 use crate::interpreter::variable_handler::interval::interval_from_expr;
 use parse_comments::comment;
 use parse_constant::{constant_expr_to_lit, parse_constant};
@@ -38,10 +41,14 @@ use parse_functions::parse_function;
 use parse_import::parse_import;
 use parse_insert::parse_insert;
 use parse_scope::parse_root;
+use parse_step_access::{parse_flow_access, parse_step_access, parse_step_timeout};
 use tools::*;
 
 use nom::error::{ContextError, ParseError};
-use nom::{branch::alt, bytes::complete::tag, multi::fold_many0, sequence::preceded, Err, *};
+use nom::{
+    branch::alt, bytes::complete::tag, combinator::opt, multi::fold_many0, sequence::preceded,
+    Err, *,
+};
 use std::collections::HashMap;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -84,6 +91,9 @@ pub fn parse_flow<'a>(slice: &'a str, flow_name: &'a str) -> Result<Flow, ErrorI
         Ok((_, (instructions, flow_type))) => {
             let mut flow_instructions = HashMap::new();
             let mut constants = HashMap::new();
+            let mut flow_access = None;
+            let mut steps_access = HashMap::new();
+            let mut steps_timeout = HashMap::new();
             // let mut inserts = vec![];
 
             for instruction in instructions.into_iter() {
@@ -96,6 +106,21 @@ pub fn parse_flow<'a>(slice: &'a str, flow_name: &'a str) -> Result<Flow, ErrorI
 
                         constants.insert(name, lit);
                     }
+                    Instruction {
+                        instruction_type: InstructionScope::StepAccess(access),
+                        ..
+                    } => match &access.step {
+                        Some(step) => {
+                            steps_access.insert(step.to_owned(), access);
+                        }
+                        None => flow_access = Some(access),
+                    },
+                    Instruction {
+                        instruction_type: InstructionScope::StepTimeout(timeout),
+                        ..
+                    } => {
+                        steps_timeout.insert(timeout.step.to_owned(), timeout);
+                    }
                     // Instruction {
                     //     instruction_type: InstructionScope::InsertStep(insert_step),
                     //     actions: _,
@@ -127,6 +152,9 @@ pub fn parse_flow<'a>(slice: &'a str, flow_name: &'a str) -> Result<Flow, ErrorI
                 flow_instructions,
                 flow_type,
                 constants,
+                flow_access,
+                steps_access,
+                steps_timeout,
             })
         }
         Err(e) => match e {
@@ -155,32 +183,162 @@ pub fn parse_flow<'a>(slice: &'a str, flow_name: &'a str) -> Result<Flow, ErrorI
     }
 }
 
+/**
+ * Parse a flow and, if it is invalid, report the syntax errors of all its steps instead of the first one.
+ * Each step, function and root item is parsed again alone: the sections before it are replaced by spaces
+ * so that the lines, columns and offsets of the errors stay the same.
+ */
+pub fn parse_flow_with_recovery<'a>(
+    slice: &'a str,
+    flow_name: &'a str,
+) -> Result<Flow, Vec<ErrorInfo>> {
+    match parse_flow_partial(slice, flow_name) {
+        (Some(flow), errors) if errors.is_empty() => Ok(flow),
+        (_, errors) => Err(errors),
+    }
+}
+
+/**
+ * Parse a flow even if some of its sections are invalid: returns the flow of its valid steps,
+ * functions and root items with the syntax errors of the invalid ones, which are replaced by
+ * spaces. The flow is None when the errors can't be isolated in their sections,
+ * ex: a block opened in a step and closed in another.
+ */
+pub fn parse_flow_partial<'a>(
+    slice: &'a str,
+    flow_name: &'a str,
+) -> (Option<Flow>, Vec<ErrorInfo>) {
+    let first_error = match parse_flow(slice, flow_name) {
+        Ok(flow) => return (Some(flow), vec![]),
+        Err(error) => error,
+    };
+
+    let mut offsets = get_sections_offsets(slice);
+    if offsets.len() < 2 {
+        return (None, vec![first_error]);
+    }
+    // comments before the first step belong to it
+    offsets[0] = 0;
+    offsets.push(slice.len());
+
+    let mut errors: Vec<ErrorInfo> = vec![];
+    let mut invalid_sections = vec![];
+    for section in offsets.windows(2) {
+        let masked = mask_flow(slice, section[0], section[1]);
+
+        if let Err(error) = parse_flow(&masked, flow_name) {
+            invalid_sections.push((section[0], section[1]));
+
+            let is_reported = errors
+                .iter()
+                .any(|reported| reported.position.interval == error.position.interval);
+
+            if !is_reported {
+                errors.push(error);
+            }
+        }
+    }
+
+    // the error can come from several sections together, ex: a block opened in a step and closed in another
+    if errors.is_empty() {
+        return (None, vec![first_error]);
+    }
+
+    let valid_flow = blank_sections(slice, &invalid_sections);
+
+    (parse_flow(&valid_flow, flow_name).ok(), errors)
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PRIVATE FUNCTION
 ////////////////////////////////////////////////////////////////////////////////
 
+fn mask_flow(slice: &str, start: usize, end: usize) -> String {
+    slice[..end]
+        .char_indices()
+        .map(|(index, c)| match c {
+            '\n' | '\r' => c.to_string(),
+            // keep the byte offsets of the section
+            _ if index < start => " ".repeat(c.len_utf8()),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+fn blank_sections(slice: &str, sections: &[(usize, usize)]) -> String {
+    slice
+        .char_indices()
+        .map(|(index, c)| match c {
+            '\n' | '\r' => c.to_string(),
+            _ if sections
+                .iter()
+                .any(|(start, end)| *start <= index && index < *end) =>
+            {
+                " ".repeat(c.len_utf8())
+            }
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
 fn parse_step<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Vec<Instruction>, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
 {
+    // the decorators of the step can be written in any order
+    let (s, access) = opt(parse_step_access)(s)?;
+    let (s, timeout) = opt(parse_step_timeout)(s)?;
+    let (s, access) = match access {
+        Some(access) => (s, Some(access)),
+        None => opt(parse_step_access)(s)?,
+    };
+
     let (s, mut interval) = preceded(comment, get_interval)(s)?;
-    let (s, ident) = parse_step_name(s)?;
+    let (s, ident) = match (parse_step_name(s), &access, &timeout) {
+        (Err(Err::Error(_)), Some(_), _) | (Err(Err::Failure(_)), Some(_), _) => {
+            return Err(gen_nom_failure(s, ERROR_STEP_ACCESS_STEP))
+        }
+        (Err(Err::Error(_)), _, Some(_)) | (Err(Err::Failure(_)), _, Some(_)) => {
+            return Err(gen_nom_failure(s, ERROR_STEP_TIMEOUT_STEP))
+        }
+        (result, ..) => result?,
+    };
 
     let (s, actions) = preceded(comment, parse_root)(s)?;
     let (s, end) = get_interval(s)?;
     interval.add_end(end);
 
-    Ok((
-        s,
-        vec![Instruction {
-            instruction_type: InstructionScope::StepScope(ident.ident),
-            actions: Expr::Scope {
-                block_type: BlockType::Step,
-                scope: actions,
-                range: interval,
+    let mut instructions = vec![];
+    if let Some(mut access) = access {
+        access.step = Some(ident.ident.to_owned());
+        instructions.push(Instruction {
+            instruction_type: InstructionScope::StepAccess(access),
+            actions: Expr::LitExpr {
+                literal: PrimitiveNull::get_literal(interval),
+                in_in_substring: false,
             },
-        }],
-    ))
+        });
+    }
+    if let Some(mut timeout) = timeout {
+        timeout.step = ident.ident.to_owned();
+        instructions.push(Instruction {
+            instruction_type: InstructionScope::StepTimeout(timeout),
+            actions: Expr::LitExpr {
+                literal: PrimitiveNull::get_literal(interval),
+                in_in_substring: false,
+            },
+        });
+    }
+    instructions.push(Instruction {
+        instruction_type: InstructionScope::StepScope(ident.ident),
+        actions: Expr::Scope {
+            block_type: BlockType::Step,
+            scope: actions,
+            range: interval,
+        },
+    });
+
+    Ok((s, instructions))
 }
 
 fn start_parsing<'a, E>(s: Span<'a>) -> IResult<Span<'a>, (Vec<Instruction>, FlowType), E>
@@ -195,6 +353,7 @@ where
             parse_import,
             parse_insert,
             parse_function,
+            parse_flow_access,
             parse_step,
         )),
         Vec::new,
@@ -1,5 +1,6 @@
 use crate::data::{ast::*, tokens::*};
 use crate::parser::operator::tools::and_operator;
+use crate::parser::operator::tools::coalesce_operator;
 use crate::parser::operator::tools::or_operator;
 use crate::parser::operator::tools::parse_infix_operators;
 use crate::parser::operator::tools::parse_item_operator;
@@ -103,6 +104,29 @@ where
     Ok((s, value))
 }
 
+fn parse_or_condition<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, value) = parse_and_condition(s)?;
+
+    let (s, mut v) = many0(parse_or)(s)?;
+
+    let value = v.drain(0..).fold(value, |acc, expr| {
+        Expr::InfixExpr(Infix::Or, Box::new(acc), Box::new(expr))
+    });
+
+    Ok((s, value))
+}
+
+fn parse_coalesce<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, _) = preceded(comment, coalesce_operator)(s)?;
+    parse_or_condition(s)
+}
+
 fn parse_or<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
@@ -119,12 +143,12 @@ pub fn parse_operator<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
 {
-    let (s, value) = parse_and_condition(s)?;
+    let (s, value) = parse_or_condition(s)?;
 
-    let (s, mut v) = many0(parse_or)(s)?;
+    let (s, mut v) = many0(parse_coalesce)(s)?;
 
     let value = v.drain(0..).fold(value, |acc, expr| {
-        Expr::InfixExpr(Infix::Or, Box::new(acc), Box::new(expr))
+        Expr::InfixExpr(Infix::Coalesce, Box::new(acc), Box::new(expr))
     });
 
     Ok((s, value))
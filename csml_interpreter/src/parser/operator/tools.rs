@@ -50,6 +50,14 @@ where
     Ok((rest, Infix::Or))
 }
 
+pub fn coalesce_operator<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Infix, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (rest, ..) = tag(COALESCE)(s)?;
+    Ok((rest, Infix::Coalesce))
+}
+
 pub fn divide_operator<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Infix, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
@@ -1,6 +1,7 @@
 use crate::data::{ast::*, csml_logs::LogLvl, tokens::*};
 use crate::error_format::{
-    gen_nom_failure, ERROR_ACTION_ARGUMENT, ERROR_REMEMBER, ERROR_RETURN, ERROR_USE,
+    gen_nom_failure, ERROR_ACTION_ARGUMENT, ERROR_REMEMBER, ERROR_REMEMBER_SCOPE,
+    ERROR_REMEMBER_TEMP, ERROR_RETURN, ERROR_USE,
 };
 use crate::parser::{
     operator::parse_operator,
@@ -18,11 +19,11 @@ use crate::parser::{
 
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while1},
+    bytes::complete::{tag, take_while, take_while1},
     combinator::opt,
     error::{ContextError, ErrorKind, ParseError},
     multi::separated_list0,
-    sequence::{preceded, terminated, tuple},
+    sequence::{delimited, preceded, terminated, tuple},
     Err, IResult,
 };
 
@@ -223,7 +224,56 @@ where
     let (s, (idents, expr)) =
         parse_action_argument(s, alt((parse_assignation, parse_remember_as)))?;
 
-    Ok((s, Expr::ObjectExpr(ObjectType::Remember(idents, expr))))
+    let (s, scope) = opt(preceded(comment, parse_remember_scope))(s)?;
+
+    let remember = match scope {
+        Some(scope) if scope.fragment() == &USER_SCOPE => ObjectType::RememberUser(idents, expr),
+        Some(scope) if scope.fragment() == &CONVERSATION_SCOPE => {
+            ObjectType::RememberTemp(idents, expr)
+        }
+        Some(scope) if scope.fragment() != &CHANNEL_SCOPE => {
+            return Err(gen_nom_failure(scope, ERROR_REMEMBER_SCOPE))
+        }
+        _ => ObjectType::Remember(idents, expr),
+    };
+
+    Ok((s, Expr::ObjectExpr(remember)))
+}
+
+/**
+ * remember key = value scope "user"
+ */
+fn parse_remember_scope<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Span<'a>, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, name) = get_string(s)?;
+    let (s, ..) = get_tag(name, SCOPE)(s)?;
+
+    preceded(
+        comment,
+        delimited(
+            tag(DOUBLE_QUOTE),
+            take_while(|c: char| c != '"' && c != '\n'),
+            tag(DOUBLE_QUOTE),
+        ),
+    )(s)
+}
+
+fn parse_remember_temp<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, name) = preceded(comment, get_string)(s)?;
+    let (s, ..) = get_tag(name, REMEMBER_TEMP)(s)?;
+
+    let (s, (idents, expr)) = match parse_action_argument(s, parse_assignation) {
+        Ok(value) => value,
+        Err(Err::Error(e)) => return Err(Err::Failure(E::add_context(s, ERROR_REMEMBER_TEMP, e))),
+        Err(err) => return Err(err),
+    };
+
+    Ok((s, Expr::ObjectExpr(ObjectType::RememberTemp(idents, expr))))
 }
 
 fn parse_forget<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
@@ -437,6 +487,7 @@ where
         parse_previous,
         parse_say,
         parse_remember,
+        parse_remember_temp,
         parse_forget,
         parse_hold,
         parse_hold_secure,
@@ -7,6 +7,7 @@ use crate::parser::{
 use nom::{
     branch::alt,
     bytes::complete::tag,
+    combinator::peek,
     error::{ContextError, ParseError},
     multi::many1,
     sequence::{preceded, terminated},
@@ -63,6 +64,18 @@ where
     }
 }
 
+// ?.name
+// ?.method()
+fn parse_optional<'a, E>(s: Span<'a>) -> IResult<Span<'a>, (Interval, PathState), E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, interval) = get_interval(s)?;
+    let (s, _) = terminated(tag(OPTIONAL), peek(tag(DOT)))(s)?;
+
+    Ok((s, (interval, PathState::Optional)))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTION
 ////////////////////////////////////////////////////////////////////////////////
@@ -71,8 +84,11 @@ pub fn parse_path<'a, E>(s: Span<'a>, expr: Expr) -> IResult<Span<'a>, Expr, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
 {
-    let path: IResult<Span<'a>, Vec<(Interval, PathState)>, E> =
-        many1(alt((parse_index, preceded(comment, parse_dot_path))))(s);
+    let path: IResult<Span<'a>, Vec<(Interval, PathState)>, E> = many1(alt((
+        parse_index,
+        preceded(comment, parse_dot_path),
+        preceded(comment, parse_optional),
+    )))(s);
 
     match path {
         Ok((s, path)) => Ok((
@@ -0,0 +1,201 @@
+use crate::data::{ast::*, primitive::PrimitiveNull, tokens::*};
+use crate::error_format::*;
+use crate::parser::{get_interval, get_string, get_tag, parse_comments::comment};
+
+use nom::{
+    bytes::complete::{tag, take_till},
+    character::complete::digit1,
+    combinator::opt,
+    error::{ContextError, ErrorKind, ParseError},
+    multi::separated_list1,
+    sequence::{delimited, preceded, terminated},
+    Err, IResult,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+//// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn parse_claim<'a, E>(s: Span<'a>) -> IResult<Span<'a>, (String, String), E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, name) = preceded(comment, get_string)(s)?;
+    let (s, _) = preceded(comment, tag(ASSIGN))(s)?;
+    let (s, value) = parse_quoted_string(s)?;
+
+    Ok((s, (name, value)))
+}
+
+fn parse_quoted_string<'a, E>(s: Span<'a>) -> IResult<Span<'a>, String, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, value) = preceded(
+        comment,
+        delimited(
+            tag(DOUBLE_QUOTE),
+            take_till(|c: char| c == '"'),
+            tag(DOUBLE_QUOTE),
+        ),
+    )(s)?;
+
+    Ok((s, (*value.fragment()).to_owned()))
+}
+
+fn parse_claims<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Vec<(String, String)>, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let result: IResult<Span<'a>, Vec<(String, String)>, E> = preceded(
+        preceded(comment, tag(L_PAREN)),
+        terminated(
+            terminated(
+                separated_list1(preceded(comment, tag(COMMA)), parse_claim),
+                opt(preceded(comment, tag(COMMA))),
+            ),
+            preceded(comment, tag(R_PAREN)),
+        ),
+    )(s);
+
+    match result {
+        Ok(value) => Ok(value),
+        Err(Err::Error(_)) | Err(Err::Failure(_)) => Err(gen_nom_failure(s, ERROR_STEP_ACCESS)),
+        Err(Err::Incomplete(needed)) => Err(Err::Incomplete(needed)),
+    }
+}
+
+/**
+ * (duration, goto: step, message: "text"), the message is optional
+ */
+fn parse_timeout_args<'a, E>(s: Span<'a>) -> IResult<Span<'a>, (u64, String, Option<String>), E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, _) = preceded(comment, tag(L_PAREN))(s)?;
+    let (s, duration) = preceded(comment, digit1)(s)?;
+    let (s, _) = preceded(comment, tag(COMMA))(s)?;
+
+    let (s, name) = preceded(comment, get_string)(s)?;
+    let (s, _) = get_tag(name, GOTO)(s)?;
+    let (s, _) = preceded(comment, tag(COLON))(s)?;
+    let (s, fallback_step) = preceded(comment, get_string)(s)?;
+
+    let (s, message) = opt(preceded(preceded(comment, tag(COMMA)), |s| {
+        let (s, name) = preceded(comment, get_string)(s)?;
+        let (s, _) = get_tag(name, TIMEOUT_MESSAGE)(s)?;
+        let (s, _) = preceded(comment, tag(COLON))(s)?;
+
+        parse_quoted_string(s)
+    }))(s)?;
+
+    let (s, _) = opt(preceded(comment, tag(COMMA)))(s)?;
+    let (s, _) = preceded(comment, tag(R_PAREN))(s)?;
+
+    match duration.fragment().parse::<u64>() {
+        Ok(duration) => Ok((s, (duration, fallback_step, message))),
+        Err(_) => Err(Err::Error(E::from_error_kind(s, ErrorKind::Digit))),
+    }
+}
+
+/**
+ * @keyword(name = "value", ...): the denied_step argument is not a claim
+ */
+fn parse_access<'a, E>(
+    s: Span<'a>,
+    keyword: &'static str,
+    step: Option<String>,
+) -> IResult<Span<'a>, StepAccess, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, interval) = preceded(comment, get_interval)(s)?;
+    let (s, _) = tag(AT)(s)?;
+    let (s, name) = get_string(s)?;
+    let (s, _) = get_tag(name, keyword)(s)?;
+
+    let (s, arguments) = parse_claims(s)?;
+
+    let mut access = StepAccess {
+        step,
+        claims: vec![],
+        denied_step: None,
+        interval,
+    };
+    for (name, value) in arguments.into_iter() {
+        match name.as_str() {
+            DENIED_STEP => access.denied_step = Some(value),
+            _ => access.claims.push((name, value)),
+        }
+    }
+
+    Ok((s, access))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * @requires(...) before a step, the step is set by the parser of the step
+ */
+pub fn parse_step_access<'a, E>(s: Span<'a>) -> IResult<Span<'a>, StepAccess, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    parse_access(s, REQUIRES, None)
+}
+
+/**
+ * @timeout(duration, goto: step) before a step, the step is set by the parser of the step
+ */
+pub fn parse_step_timeout<'a, E>(s: Span<'a>) -> IResult<Span<'a>, StepTimeout, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, interval) = preceded(comment, get_interval)(s)?;
+    let (s, _) = tag(AT)(s)?;
+    let (s, name) = get_string(s)?;
+    let (s, _) = get_tag(name, TIMEOUT)(s)?;
+
+    let (s, (duration, fallback_step, message)) = match parse_timeout_args::<E>(s) {
+        Ok(value) => value,
+        Err(Err::Error(_)) | Err(Err::Failure(_)) => {
+            return Err(gen_nom_failure(s, ERROR_STEP_TIMEOUT))
+        }
+        Err(Err::Incomplete(needed)) => return Err(Err::Incomplete(needed)),
+    };
+
+    Ok((
+        s,
+        StepTimeout {
+            step: String::new(),
+            duration,
+            fallback_step,
+            message,
+            interval,
+        },
+    ))
+}
+
+/**
+ * @requires_flow(...) at the root of the flow, for all its steps but its denied step
+ */
+pub fn parse_flow_access<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Vec<Instruction>, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, access) = parse_access(s, REQUIRES_FLOW, None)?;
+    let interval = access.interval;
+
+    Ok((
+        s,
+        vec![Instruction {
+            instruction_type: InstructionScope::StepAccess(access),
+            actions: Expr::LitExpr {
+                literal: PrimitiveNull::get_literal(interval),
+                in_in_substring: false,
+            },
+        }],
+    ))
+}
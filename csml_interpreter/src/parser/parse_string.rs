@@ -7,11 +7,13 @@ use crate::error_format::{gen_nom_failure, CustomError, *};
 use crate::interpreter::variable_handler::expr_to_literal;
 use crate::parser::operator::parse_operator;
 use crate::parser::parse_comments::comment;
-use crate::parser::tools::{get_interval, get_range_interval, parse_error};
+use crate::parser::parse_var_types::parse_expr_list;
+use crate::parser::tools::{get_interval, get_range_interval, get_string, parse_error};
 use nom::{
     bytes::complete::tag,
     combinator::cut,
     error::{ContextError, ParseError},
+    multi::many0,
     sequence::{delimited, preceded},
     *,
 };
@@ -162,6 +164,47 @@ where
     Ok((rest, expr))
 }
 
+fn parse_filter<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Function, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, _) = preceded(comment, tag(PIPE))(s)?;
+    let (s, interval) = preceded(comment, get_interval)(s)?;
+    let (rest, name) = get_string(s)?;
+
+    if !FILTERS.contains(&name.as_str()) {
+        return Err(gen_nom_failure(s, ERROR_UNKNOWN_FILTER));
+    }
+
+    let (rest, args) = match preceded(comment, tag(L_PAREN))(rest) as IResult<Span<'a>, Span<'a>, E>
+    {
+        Ok(_) => parse_expr_list(rest)?,
+        Err(_) => (rest, Expr::VecExpr(vec![], interval)),
+    };
+
+    Ok((
+        rest,
+        Function {
+            name,
+            interval,
+            args: Box::new(args),
+        },
+    ))
+}
+
+fn parse_interpolation<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, expr) = parse_complex_string(s)?;
+    let (s, filters) = many0(parse_filter)(s)?;
+
+    match filters.is_empty() {
+        true => Ok((s, expr)),
+        false => Ok((s, Expr::FilterExpr(Box::new(expr), filters))),
+    }
+}
+
 fn check_escaped_right_brace<'a, E>(
     s: Span<'a>,
     len: usize,
@@ -235,7 +278,7 @@ where
                                 &mut interval,
                             )?;
                             let (split_rest, expression) =
-                                delimited(tag("{{"), parse_complex_string, parse_close_bracket)(
+                                delimited(tag("{{"), parse_interpolation, parse_close_bracket)(
                                     split_rest,
                                 )?;
                             vector.push(expression);
@@ -657,6 +700,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ok_expand_filters() {
+        let string = r#""{{ user.name | default(\"there\") | capitalize }}""#;
+        let span = Span::new(string);
+
+        match test_string(span) {
+            Ok((_, Expr::ComplexLiteral(vec, _))) => {
+                match vec.iter().find(|expr| matches!(expr, Expr::FilterExpr(..))) {
+                    Some(Expr::FilterExpr(_, filters)) => assert_eq!(filters.len(), 2),
+                    _ => panic!("expected filters, got {:?}", vec),
+                }
+            }
+            Ok((_, expr)) => panic!("expected complex literal, got {:?}", expr),
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn ok_expand_or_is_not_filter() {
+        let string = r#""{{ a || b }}""#;
+        let span = Span::new(string);
+
+        match test_string(span) {
+            Ok((_, Expr::ComplexLiteral(vec, _))) => {
+                assert!(!vec.iter().any(|expr| matches!(expr, Expr::FilterExpr(..))))
+            }
+            Ok((_, expr)) => panic!("expected complex literal, got {:?}", expr),
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn err_expand_unknown_filter() {
+        let string = r#""{{ name | reverse }}""#;
+        let span = Span::new(string);
+
+        match test_string(span) {
+            Ok(..) => panic!("need to fail"),
+            Err(_) => {}
+        }
+    }
+
     #[test]
     fn err_expand_open() {
         let string = "\"{{ Hello\"";
@@ -71,6 +71,28 @@ where
     ))
 }
 
+fn parse_array_items<'a, E>(start: Span<'a>, s: Span<'a>) -> IResult<Span<'a>, Vec<Expr>, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, (vec, _)) = parse_error(
+        start,
+        s,
+        preceded(
+            tag(L_BRACKET),
+            terminated(
+                tuple((
+                    separated_list0(preceded(comment, tag(COMMA)), parse_operator), //parse_basic_expr
+                    opt(preceded(comment, tag(COMMA))),
+                )),
+                preceded(comment, parse_r_bracket),
+            ),
+        ),
+    )?;
+
+    Ok((s, vec))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
@@ -154,26 +176,32 @@ where
 {
     let (start, mut interval) = preceded(comment, get_interval)(s)?;
 
-    let (s, (vec, _)) = parse_error(
-        start,
-        s,
-        preceded(
-            tag(L_BRACKET),
-            terminated(
-                tuple((
-                    separated_list0(preceded(comment, tag(COMMA)), parse_operator), //parse_basic_expr
-                    opt(preceded(comment, tag(COMMA))),
-                )),
-                preceded(comment, parse_r_bracket),
-            ),
-        ),
-    )?;
+    let (s, vec) = parse_array_items(start, s)?;
     let (s, end) = get_interval(s)?;
     interval.add_end(end);
 
     Ok((s, Expr::VecExpr(vec, interval)))
 }
 
+/**
+ * await_all [Fn("a"), HTTP(url).get()]
+ */
+pub fn parse_await_all<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, mut interval) = preceded(comment, get_interval)(s)?;
+    let (s, name) = get_string(s)?;
+    let (s, _) = get_tag(name, AWAIT_ALL)(s)?;
+
+    let (start, _) = preceded(comment, get_interval)(s)?;
+    let (s, vec) = cut(|s| parse_array_items(start, s))(start)?;
+    let (s, end) = get_interval(s)?;
+    interval.add_end(end);
+
+    Ok((s, Expr::AwaitAllExpr(vec, interval)))
+}
+
 pub fn parse_basic_expr<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
@@ -185,6 +213,7 @@ where
         parse_condition_group,
         parse_object,
         parse_expr_array,
+        parse_await_all,
         parse_literal_expr,
         parse_built_in,
         parse_string,
@@ -110,7 +110,9 @@ fn get_offsets(ast: &Flow) -> (Vec<(String, usize)>, Vec<usize>) {
                 let interval = interval_from_expr(block);
                 skip_offsets.push(interval.offset)
             }
-            InstructionScope::DuplicateInstruction(..) => {}
+            InstructionScope::StepAccess(_)
+            | InstructionScope::StepTimeout(_)
+            | InstructionScope::DuplicateInstruction(..) => {}
         }
     }
     offsets.sort_by(|(_, a), (_, b)| a.cmp(b));
@@ -0,0 +1,187 @@
+use crate::data::{
+    context::ContextStepInfo, primitive::PrimitiveType, Context, Hold, Literal, MessageData,
+};
+use crate::parser::ExitCondition;
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn literals_to_json(map: &HashMap<String, Literal>) -> Value {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+
+    let mut object = Map::new();
+    for key in keys {
+        object.insert(key.to_owned(), literal_to_json(&map[key]));
+    }
+
+    Value::Object(object)
+}
+
+fn step_to_json(step: &ContextStepInfo) -> Value {
+    match step {
+        ContextStepInfo::Normal(step) => json!({"type": "normal", "step": step}),
+        ContextStepInfo::UnknownFlow(step) => json!({"type": "unknown_flow", "step": step}),
+        ContextStepInfo::InsertedStep { step, flow } => {
+            json!({"type": "inserted_step", "step": step, "flow": flow})
+        }
+    }
+}
+
+fn hold_to_json(hold: &Hold) -> Value {
+    json!({
+        "command_index": hold.index.command_index,
+        "loop_index": hold.index.loop_index,
+        "step_vars": hold.step_vars,
+        "step_name": hold.step_name,
+        "flow_name": hold.flow_name,
+        "secure": hold.secure,
+    })
+}
+
+fn exit_condition_to_json(exit_condition: &ExitCondition) -> Value {
+    match exit_condition {
+        ExitCondition::Goto => json!("goto"),
+        ExitCondition::End => json!("end"),
+        ExitCondition::Error => json!("error"),
+        ExitCondition::Break => json!("break"),
+        ExitCondition::Continue => json!("continue"),
+        ExitCondition::Hold => json!("hold"),
+        ExitCondition::Return(literal) => json!({"return": literal_to_json(literal)}),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Same JSON with the keys of every object sorted: two equal values always serialize to the
+ * same string, whatever the order in which their keys were inserted
+ */
+pub fn canonical_json(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+
+            let mut object = Map::new();
+            for key in keys {
+                object.insert(key.to_owned(), canonical_json(&map[key]));
+            }
+
+            Value::Object(object)
+        }
+        Value::Array(array) => Value::Array(array.iter().map(canonical_json).collect()),
+        value => value.to_owned(),
+    }
+}
+
+/**
+ * {"content_type": String, "type": String, "value": Value}: the content type is explicit at
+ * every level, the values of arrays and objects are the JSON of their literals.
+ * The interval and the secure flag are not part of the snapshot.
+ */
+pub fn literal_to_json(literal: &Literal) -> Value {
+    let primitive_type = literal.primitive.get_type();
+
+    let value = match primitive_type {
+        PrimitiveType::PrimitiveObject => {
+            match literal
+                .primitive
+                .get_value()
+                .downcast_ref::<HashMap<String, Literal>>()
+            {
+                Some(map) => literals_to_json(map),
+                None => canonical_json(&literal.primitive.to_json()),
+            }
+        }
+        PrimitiveType::PrimitiveArray => {
+            match literal.primitive.get_value().downcast_ref::<Vec<Literal>>() {
+                Some(array) => Value::Array(array.iter().map(literal_to_json).collect()),
+                None => canonical_json(&literal.primitive.to_json()),
+            }
+        }
+        _ => canonical_json(&literal.primitive.to_json()),
+    };
+
+    json!({
+        "content_type": literal.content_type,
+        "type": primitive_type.to_string(),
+        "value": value,
+    })
+}
+
+/**
+ * Current flow and step, memories, metadata and hold of the context.
+ * The secrets, the globals and the feature flags of the bot are left out.
+ */
+pub fn context_to_json(context: &Context) -> Value {
+    json!({
+        "current": literals_to_json(&context.current),
+        "flow": context.flow,
+        "hold": context.hold.as_ref().map(hold_to_json),
+        "metadata": literals_to_json(&context.metadata),
+        "previous_bot": canonical_json(&json!(context.previous_bot)),
+        "step": step_to_json(&context.step),
+    })
+}
+
+/**
+ * Messages and memories sent by the interpreter, in their order, and its exit condition
+ */
+pub fn message_data_to_json(message_data: &MessageData) -> Value {
+    let messages: Vec<Value> = message_data
+        .messages
+        .iter()
+        .map(|message| canonical_json(&message.to_owned().message_to_json()))
+        .collect();
+
+    let memories: Vec<Value> = message_data
+        .memories
+        .iter()
+        .flatten()
+        .map(|memory| json!({"key": memory.key, "value": canonical_json(&memory.value)}))
+        .collect();
+
+    json!({
+        "exit_condition": message_data.exit_condition.as_ref().map(exit_condition_to_json),
+        "hold": message_data.hold.as_ref().map(hold_to_json),
+        "memories": memories,
+        "messages": messages,
+    })
+}
+
+/**
+ * Pretty printed canonical JSON, to compare with a snapshot file
+ */
+pub fn to_snapshot_string(value: &Value) -> String {
+    // a Value always serializes
+    serde_json::to_string_pretty(&canonical_json(value)).unwrap_or_default()
+}
+
+/**
+ * Deep equality of two literals: same content types, types and values at every level,
+ * whatever their intervals and the order of the keys of their objects
+ */
+pub fn literals_eq(left: &Literal, right: &Literal) -> bool {
+    literal_to_json(left) == literal_to_json(right)
+}
+
+/**
+ * Deep equality of the snapshots of two contexts
+ */
+pub fn contexts_eq(left: &Context, right: &Context) -> bool {
+    context_to_json(left) == context_to_json(right)
+}
+
+/**
+ * Deep equality of the messages, memories, hold and exit condition of two interpreter outputs
+ */
+pub fn message_data_eq(left: &MessageData, right: &MessageData) -> bool {
+    message_data_to_json(left) == message_data_to_json(right)
+}
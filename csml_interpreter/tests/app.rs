@@ -0,0 +1,106 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use csml_interpreter::data::{ApiInfo, AppOptions, BotApp, CircuitBreakerOptions, Client};
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[test]
+fn app_0() {
+    let data = r#"{"messages":[ {"content":{"text": "true"},"content_type":"text"}, {"content":{"text": "unreachable"},"content_type":"text"} ],"memories":[]}"#;
+
+    let api_info = ApiInfo {
+        client: Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        ),
+        apps_endpoint: None,
+        apps_endpoint_options: AppOptions::default(),
+        apps: vec![BotApp {
+            name: "unreachable".to_owned(),
+            url: "http://127.0.0.1:1".to_owned(),
+            auth_header: Some("Bearer token".to_owned()),
+            lambda: None,
+            options: AppOptions {
+                timeout: Some(1000),
+                retries: 1,
+                backoff: None,
+                circuit_breaker: None,
+            },
+        }],
+    };
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            Some(api_info),
+            None,
+            "app_0",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/app.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn app_1() {
+    let data = r#"{"messages":[ {"content":{"text": "true"},"content_type":"text"}, {"content":{"text": "circuit_open"},"content_type":"text"} ],"memories":[]}"#;
+
+    let api_info = ApiInfo {
+        client: Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        ),
+        apps_endpoint: None,
+        apps_endpoint_options: AppOptions::default(),
+        apps: vec![BotApp {
+            name: "unhealthy".to_owned(),
+            url: "http://127.0.0.1:2".to_owned(),
+            auth_header: None,
+            lambda: None,
+            options: AppOptions {
+                timeout: Some(1000),
+                retries: 2,
+                backoff: Some(10),
+                circuit_breaker: Some(CircuitBreakerOptions {
+                    failure_threshold: 1,
+                    reset_timeout: 60_000,
+                }),
+            },
+        }],
+    };
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            Some(api_info),
+            None,
+            "app_1",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/app.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
@@ -0,0 +1,175 @@
+use csml_interpreter::{
+    parse_flow_to_ast_json, parse_flow_to_partial_ast_json, parse_step, round_trip,
+};
+use serde_json::json;
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+fn get_csml_files(dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            get_csml_files(&path, files);
+        } else if path.extension() == Some(OsStr::new("csml")) {
+            files.push(path.to_str().unwrap().to_owned());
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// AST EXPORT
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ast_export_steps() {
+    let source = "start:\n    say \"hello\"\n    goto next\n\nnext:\n    say Button(\"yes\")\n    goto target@other\n";
+    let ast = parse_flow_to_ast_json(source).unwrap();
+
+    let names: Vec<&str> = ast["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["start", "next"]);
+
+    let start = &ast["steps"][0];
+    assert_eq!(start["actions"][0]["type"], "say");
+    assert_eq!(start["actions"][0]["value"]["type"], "string");
+    assert_eq!(start["actions"][0]["value"]["parts"][0]["type"], "literal");
+    assert_eq!(start["actions"][0]["value"]["parts"][0]["value"], "hello");
+    assert_eq!(start["gotos"][0]["step"], "next");
+    assert_eq!(start["gotos"][0]["flow"], json!(null));
+    assert_eq!(start["range"]["start_line"], 1);
+
+    let next = &ast["steps"][1];
+    assert_eq!(next["components"], json!(["Button"]));
+    assert_eq!(next["actions"][0]["value"]["kind"], "component");
+    assert_eq!(next["gotos"][0]["step"], "target");
+    assert_eq!(next["gotos"][0]["flow"], "other");
+    assert_eq!(next["gotos"][0]["dynamic"], false);
+}
+
+#[test]
+fn ast_export_dynamic_goto() {
+    let source = "start:\n    do next = \"other\"\n    goto $next\n";
+    let ast = parse_flow_to_ast_json(source).unwrap();
+
+    let goto = &ast["steps"][0]["actions"][1];
+    assert_eq!(goto["type"], "goto");
+    assert_eq!(goto["step"]["variable"]["name"], "next");
+
+    let summary = &ast["steps"][0]["gotos"][0];
+    assert_eq!(summary["step"], json!(null));
+    assert_eq!(summary["dynamic"], true);
+}
+
+#[test]
+fn ast_export_functions_and_imports() {
+    let source = "import format from utils\nconst MAX = 3\n\nstart:\n    if (add(1, 2) > MAX) {\n        say \"{{format(MAX)}}\"\n    }\n    goto end\n\nfn add(a, b) {\n    return a + b\n}\n";
+    let ast = parse_flow_to_ast_json(source).unwrap();
+
+    assert_eq!(ast["version"], 1);
+    assert_eq!(ast["constants"], json!({"MAX": 3}));
+    assert_eq!(ast["imports"][0]["name"], "format");
+    assert_eq!(ast["imports"][0]["from_flow"], "utils");
+
+    assert_eq!(ast["functions"][0]["name"], "add");
+    assert_eq!(ast["functions"][0]["args"], json!(["a", "b"]));
+    assert_eq!(ast["functions"][0]["actions"][0]["type"], "return");
+    assert_eq!(ast["functions"][0]["actions"][0]["value"]["operator"], "+");
+
+    let condition = &ast["steps"][0]["actions"][0];
+    assert_eq!(condition["type"], "if");
+    assert_eq!(condition["condition"]["left"]["kind"], "function");
+    assert_eq!(condition["then"][0]["value"]["type"], "string");
+    assert_eq!(ast["steps"][0]["gotos"][0]["step"], "end");
+}
+
+#[test]
+fn ast_export_parse_error() {
+    assert!(parse_flow_to_ast_json("start:\n    say (\n").is_err());
+}
+
+#[test]
+fn ast_export_partial_ast() {
+    let source =
+        "start:\n    say \"hello\"\n    goto next\n\nnext:\n    say (\n\nlast:\n    goto end\n";
+    let report = parse_flow_to_partial_ast_json(source);
+
+    assert!(!report.valid);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].start_line, 6);
+
+    let ast = report.ast.unwrap();
+    let names: Vec<&str> = ast["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["start", "last"]);
+    assert_eq!(ast["steps"][1]["range"]["start_line"], 8);
+}
+
+#[test]
+fn ast_export_partial_ast_valid_flow() {
+    let report = parse_flow_to_partial_ast_json("start:\n    say \"hello\"\n");
+
+    assert!(report.valid);
+    assert!(report.errors.is_empty());
+    assert_eq!(report.ast.unwrap()["steps"][0]["name"], "start");
+}
+
+#[test]
+fn ast_export_parse_step() {
+    let report = parse_step("start:\n    say \"hello\"\n    goto end\n");
+    assert!(report.valid);
+
+    let step = report.ast.unwrap();
+    assert_eq!(step["name"], "start");
+    assert_eq!(step["actions"][0]["type"], "say");
+    assert_eq!(step["gotos"][0]["step"], "end");
+
+    let report = parse_step("start:\n    goto next\n\nnext:\n    goto end\n");
+    assert!(!report.valid);
+    assert!(report.ast.is_none());
+    assert_eq!(report.errors.len(), 1);
+
+    let report = parse_step("start:\n    say (\n");
+    assert!(!report.valid);
+    assert!(report.ast.is_none());
+}
+
+#[test]
+fn ast_export_round_trip() {
+    let trip = round_trip("start:\n  do x=[1,2 ,3]\n  say   x\n  goto end").unwrap();
+
+    assert_eq!(
+        trip.formatted,
+        "start:\n    do x = [1, 2, 3]\n    say x\n    goto end\n"
+    );
+    assert!(trip.idempotent);
+    assert!(trip.same_ast);
+
+    let errors = round_trip("start:\n    say (\n").unwrap_err();
+    assert_eq!(errors[0].start_line, 2);
+}
+
+#[test]
+fn ast_export_round_trip_csml_files() {
+    let mut files = vec![];
+    get_csml_files(Path::new("CSML"), &mut files);
+
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+
+        if let Ok(trip) = round_trip(&source) {
+            assert!(trip.idempotent, "{} is not stable", file);
+            assert!(trip.same_ast, "{} has another AST once formatted", file);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+mod support;
+
+use csml_interpreter::data::context::{get_hashmap_from_json, Context};
+use csml_interpreter::data::event::Event;
+use csml_interpreter::data::{ApiInfo, AppOptions, Client};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+const DELAY: Duration = Duration::from_millis(500);
+
+/**
+ * Answer each request after DELAY: the apps get back the data they were sent,
+ * the other requests {"ok": true}
+ */
+fn mock_server(requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                            length = value.parse().unwrap();
+                        }
+                        None if line.trim_end().is_empty() => break,
+                        _ => (),
+                    }
+                }
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let response = match serde_json::from_slice::<Value>(&body) {
+                    Ok(request) => serde_json::json!({"data": request["data"]}),
+                    Err(_) => serde_json::json!({"ok": true}),
+                }
+                .to_string();
+
+                thread::sleep(DELAY);
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            response.len(),
+                            response
+                        )
+                        .as_bytes(),
+                    )
+                    .unwrap();
+            });
+        }
+    });
+
+    url
+}
+
+fn get_context(step: &str, apps_endpoint: Option<String>) -> Context {
+    let api_info = ApiInfo {
+        client: Client::new(
+            "bot_id".to_owned(),
+            "channel_id".to_owned(),
+            "user_id".to_owned(),
+        ),
+        apps_endpoint: apps_endpoint.to_owned(),
+        apps_endpoint_options: AppOptions::default(),
+        apps: vec![],
+    };
+
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        Some(api_info),
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.secrets = get_hashmap_from_json(&serde_json::json!({ "url": apps_endpoint }), "flow");
+
+    context
+}
+
+#[test]
+fn ok_await_all() {
+    let data = r#"{"messages":[ {"content":{ "text": "1 2 true 42" },"content_type":"text"} ],"memories":[]}"#;
+    let url = mock_server(3);
+
+    let start = Instant::now();
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start", Some(url)),
+        "CSML/basic_test/await_all.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2);
+
+    // the three calls were made concurrently
+    assert!(start.elapsed() < DELAY * 2);
+}
+
+#[test]
+fn ok_await_all_error() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("await_all_error", None),
+        "CSML/basic_test/await_all.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    assert_eq!(v1["messages"][0]["content_type"], "error");
+    assert_eq!(v1["messages"][1]["content"]["text"], "1");
+    assert_eq!(v1["messages"][2]["content"]["text"], "true");
+}
@@ -0,0 +1,85 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_texts(step: &str) -> Value {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            step,
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/contact.csml",
+    );
+
+    let messages = message_to_json_value(msg)["messages"].to_owned();
+    messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["content"]["text"].to_owned())
+        .collect()
+}
+
+#[test]
+fn ok_is_email() {
+    assert_eq!(
+        get_texts("start"),
+        serde_json::json!(["true", "true", "false", "false", "false"])
+    );
+}
+
+#[test]
+fn ok_is_phone() {
+    assert_eq!(
+        get_texts("is_phone"),
+        serde_json::json!(["true", "true", "false", "false", "false"])
+    );
+}
+
+#[test]
+fn ok_normalize_phone() {
+    assert_eq!(
+        get_texts("normalize_phone"),
+        serde_json::json!([
+            "+33612345678",
+            "+33612345678",
+            "+33612345678",
+            "+14155552671",
+            "+390212345678",
+            "+821012345678",
+            "invalid"
+        ])
+    );
+}
+
+#[test]
+fn ok_phone_unknown_country() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "unknown_country",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/contact.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
@@ -0,0 +1,192 @@
+mod support;
+
+use csml_interpreter::data::csml_bot::CsmlBot;
+use csml_interpreter::data::csml_flow::CsmlFlow;
+use csml_interpreter::data::event::Event;
+use csml_interpreter::data::{Breakpoint, Context, Debugger, Hold, MSG};
+use csml_interpreter::{interpret, load_components};
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+use crate::support::tools::read_file;
+
+use serde_json::Value;
+
+fn get_context(step: &str, debugger: Debugger, hold: Option<Hold>) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        hold,
+        step,
+        "flow",
+        None,
+    );
+    context.debugger = Some(debugger);
+
+    context
+}
+
+/**
+ * Run the debugger flow and return the texts said by the bot and the pause of the debugger
+ */
+fn run(context: Context) -> (Vec<Value>, Option<Hold>) {
+    let content = read_file("CSML/basic_test/debugger.csml".to_owned()).unwrap();
+    let flow = CsmlFlow::new("id", "flow", &content, Vec::default());
+
+    let bot = CsmlBot::new(
+        "id",
+        "bot",
+        None,
+        vec![flow],
+        Some(load_components().unwrap()),
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let (sender, receiver) = mpsc::channel::<MSG>();
+    let msg_data = interpret(
+        bot,
+        context,
+        Event::new("payload", "", serde_json::json!({})),
+        Some(sender),
+    );
+
+    let hold = receiver.try_iter().find_map(|msg| match msg {
+        MSG::Hold(hold) => Some(hold),
+        _ => None,
+    });
+    let texts = msg_data
+        .messages
+        .into_iter()
+        .map(|msg| msg.content["text"].clone())
+        .collect();
+
+    (texts, hold)
+}
+
+fn line_breakpoint(line: u32) -> Breakpoint {
+    Breakpoint::Line {
+        flow: "flow".to_owned(),
+        line,
+    }
+}
+
+#[test]
+fn debugger_line_breakpoint() {
+    let debugger = Debugger::new(vec![line_breakpoint(4)]);
+    let (texts, hold) = run(get_context("start", debugger, None));
+
+    assert_eq!(texts, vec![serde_json::json!("1")]);
+
+    let hold = hold.unwrap();
+    assert_eq!(hold.breakpoint, Some(4));
+    assert_eq!(hold.step_name, "start");
+    assert_eq!(hold.index.command_index, 2);
+    assert_eq!(hold.step_vars["value"], serde_json::json!(42));
+}
+
+#[test]
+fn debugger_continue() {
+    let debugger = Debugger::new(vec![line_breakpoint(4)]);
+    let (_, hold) = run(get_context("start", debugger.clone(), None));
+
+    // the paused instruction is executed when the conversation is resumed
+    let (texts, hold) = run(get_context("start", debugger, hold));
+
+    assert_eq!(texts, vec![serde_json::json!("2"), serde_json::json!("3")]);
+    assert!(hold.is_none());
+}
+
+#[test]
+fn debugger_step_breakpoint() {
+    let debugger = Debugger::new(vec![Breakpoint::Step {
+        flow: "flow".to_owned(),
+        step: "next".to_owned(),
+    }]);
+    let (texts, hold) = run(get_context("start", debugger, None));
+
+    assert_eq!(texts, vec![serde_json::json!("1"), serde_json::json!("2")]);
+
+    let hold = hold.unwrap();
+    assert_eq!(hold.step_name, "next");
+    assert_eq!(hold.breakpoint, Some(8));
+}
+
+#[test]
+fn debugger_step_over() {
+    let mut debugger = Debugger::new(vec![line_breakpoint(4)]);
+    let (_, hold) = run(get_context("start", debugger.clone(), None));
+
+    debugger.step_over = true;
+    let (texts, hold) = run(get_context("start", debugger, hold));
+
+    assert_eq!(texts, vec![serde_json::json!("2")]);
+    assert_eq!(hold.unwrap().breakpoint, Some(5));
+}
+
+#[test]
+fn debugger_breakpoint_in_loop() {
+    let debugger = Debugger::new(vec![line_breakpoint(13)]);
+    let (texts, hold) = run(get_context("loop", debugger.clone(), None));
+
+    assert!(texts.is_empty());
+
+    // the breakpoint pauses every iteration of the loop
+    let (texts, hold) = run(get_context("loop", debugger.clone(), hold));
+    assert_eq!(texts, vec![serde_json::json!("1")]);
+
+    let (texts, hold) = run(get_context("loop", debugger, hold));
+    assert_eq!(
+        texts,
+        vec![serde_json::json!("2"), serde_json::json!("done")]
+    );
+    assert!(hold.is_none());
+}
+
+#[test]
+fn debugger_breakpoint_on_block() {
+    // the pause of a breakpoint on a loop is before its first instruction
+    let debugger = Debugger::new(vec![line_breakpoint(12)]);
+    let (texts, hold) = run(get_context("loop", debugger.clone(), None));
+
+    assert!(texts.is_empty());
+    assert_eq!(hold.as_ref().unwrap().breakpoint, Some(13));
+
+    let (texts, hold) = run(get_context("loop", debugger, hold));
+    assert_eq!(
+        texts,
+        vec![
+            serde_json::json!("1"),
+            serde_json::json!("2"),
+            serde_json::json!("done")
+        ]
+    );
+    assert!(hold.is_none());
+}
+
+#[test]
+fn debugger_no_breakpoint() {
+    let (texts, hold) = run(get_context("start", Debugger::default(), None));
+
+    assert_eq!(
+        texts,
+        vec![
+            serde_json::json!("1"),
+            serde_json::json!("2"),
+            serde_json::json!("3")
+        ]
+    );
+    assert!(hold.is_none());
+}
@@ -0,0 +1,83 @@
+mod support;
+
+use csml_interpreter::data::csml_bot::CsmlBot;
+use csml_interpreter::data::csml_flow::CsmlFlow;
+use csml_interpreter::data::error_info::{ErrorInfo, StackFrame};
+use csml_interpreter::data::{Context, Event, MSG};
+use csml_interpreter::interpret;
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+use support::tools::read_file;
+
+fn get_error(filepath: &str) -> Option<ErrorInfo> {
+    let content = read_file(filepath.to_string()).unwrap();
+    let flow = CsmlFlow::new("id", "flow", &content, Vec::default());
+
+    let bot = CsmlBot::new(
+        "id",
+        "bot",
+        None,
+        vec![flow],
+        None,
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let (sender, receiver) = mpsc::channel();
+    interpret(
+        bot,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        Event::new("payload", "", serde_json::json!({})),
+        Some(sender),
+    );
+
+    receiver.try_iter().find_map(|msg| match msg {
+        MSG::Error(_, error) => Some(error),
+        _ => None,
+    })
+}
+
+#[test]
+fn error_trace() {
+    let error = get_error("CSML/basic_test/error_trace.csml").unwrap();
+
+    assert_eq!(error.position.flow, "flow");
+    assert_eq!(error.step, Some("step_1".to_owned()));
+    assert_eq!(
+        error.snippet,
+        Some("5 |     foreach (elem) in 42 {\n  |                       ^".to_owned())
+    );
+    assert_eq!(
+        error.stack,
+        vec![
+            StackFrame {
+                flow: "flow".to_owned(),
+                step: "start".to_owned()
+            },
+            StackFrame {
+                flow: "flow".to_owned(),
+                step: "step_1".to_owned()
+            },
+        ]
+    );
+}
@@ -402,3 +402,171 @@ fn event_types() {
         }
     }
 }
+
+#[test]
+fn event_content() {
+    let data = r#"{
+        "memories":[
+        ],
+        "messages":[
+            {"content":{"text": "location"}, "content_type":"text"},
+            {"content":{"text": "48.85"}, "content_type":"text"},
+            {"content":{"text": "48.85,2.35"}, "content_type":"text"}
+        ]}"#;
+
+    let event = Event::from_payload(
+        "location",
+        serde_json::json!({"latitude": 48.85, "longitude": 2.35}),
+    )
+    .unwrap();
+
+    let msg = format_message(
+        event,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "event_content",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/event.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn event_from_payload() {
+    let event = Event::from_payload("image", serde_json::json!({"url": "https://csml.dev/logo.png"}))
+        .unwrap();
+    assert_eq!(event.content_value, "https://csml.dev/logo.png");
+
+    assert!(Event::from_payload("location", serde_json::json!({"latitude": 48.85})).is_err());
+    assert!(Event::from_payload("text", serde_json::json!({"payload": "hello"})).is_err());
+    assert!(Event::from_payload("unknown", serde_json::json!({})).is_err());
+}
+
+#[test]
+fn event_reference() {
+    let data = r#"{"memories":[], "messages":[
+            {"content":{"text": "reaction"}, "content_type":"text"},
+            {"content":{"text": "channel-message-1"}, "content_type":"text"},
+            {"content":{"text": "+1"}, "content_type":"text"}
+        ]}"#;
+
+    let event = Event::from_payload(
+        "reaction",
+        serde_json::json!({"reference_id": "channel-message-1", "reaction": "+1"}),
+    )
+    .unwrap();
+    assert_eq!(event.reference_id, Some("channel-message-1".to_owned()));
+
+    let msg = format_message(
+        event,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "event_reference",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/event.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn event_message_edited_from_payload() {
+    let event = Event::from_payload(
+        "message_edited",
+        serde_json::json!({"reference_id": "channel-message-1", "text": "new text"}),
+    )
+    .unwrap();
+    assert_eq!(event.content_value, "new text");
+    assert_eq!(event.reference_id, Some("channel-message-1".to_owned()));
+
+    assert!(
+        Event::from_payload("message_edited", serde_json::json!({"text": "new text"})).is_err()
+    );
+    assert!(Event::from_payload("reaction", serde_json::json!({"reference_id": "1"})).is_err());
+}
+
+#[test]
+fn event_enrichment() {
+    let data = r#"{"memories":[], "messages":[
+            {"content":{"text": "negative"}, "content_type":"text"},
+            {"content":{"text": "1"}, "content_type":"text"}
+        ]}"#;
+
+    let mut event = Event::new("text", "my order is late", serde_json::json!({"text": "my order is late"}));
+    event.enrichment = serde_json::json!({
+        "sentiment": {"label": "negative", "score": -0.7},
+        "entities": [{"type": "order"}]
+    });
+
+    let msg = format_message(
+        event,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "event_enrichment",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/event.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn event_file() {
+    let data = r#"{"memories":[], "messages":[
+            {"content":{"text": "https://files.example.com/csml-files/photo.jpg"}, "content_type":"text"},
+            {"content":{"text": "stored"}, "content_type":"text"}
+        ]}"#;
+
+    let event = Event::new(
+        "image",
+        "https://channel.example.com/tmp/photo.jpg",
+        serde_json::json!({
+            "url": "https://channel.example.com/tmp/photo.jpg",
+            "file": {"status": "stored", "url": "https://files.example.com/csml-files/photo.jpg"}
+        }),
+    );
+
+    let msg = format_message(
+        event,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "event_file",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/event.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
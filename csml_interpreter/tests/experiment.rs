@@ -0,0 +1,98 @@
+mod support;
+
+use csml_interpreter::data::context::{get_hashmap_from_mem, Context};
+use csml_interpreter::data::event::Event;
+use csml_interpreter::data::Client;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str, user_id: &str) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.client = Some(Client::new(
+        "bot_id".to_owned(),
+        "channel_id".to_owned(),
+        user_id.to_owned(),
+    ));
+
+    context
+}
+
+#[test]
+fn ok_experiment() {
+    let data = r#"{"messages":[ {"content":{ "text": "A" },"content_type":"text"} ],"memories":[ {"key":"_experiments", "value":{"_content": {"welcome_test": "A"}, "_content_type": "object"}} ]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start", "user_id"),
+        "CSML/basic_test/built-in/experiment.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_experiment_remembered() {
+    let data = r#"{"messages":[ {"content":{ "text": "B" },"content_type":"text"} ],"memories":[]}"#;
+    let mut context = get_context("start", "user_id");
+    context.current = get_hashmap_from_mem(
+        &serde_json::json!({"_experiments": {"welcome_test": "B"}}),
+        "flow",
+    );
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        context,
+        "CSML/basic_test/built-in/experiment.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_experiment_deterministic() {
+    let mut variants = vec![];
+
+    for user_id in ["alice", "bob", "carol", "dave", "alice"] {
+        let msg = format_message(
+            Event::new("payload", "", serde_json::json!({})),
+            get_context("equal_weights", user_id),
+            "CSML/basic_test/built-in/experiment.csml",
+        );
+
+        // the variant is remembered: the second call of the step returns the same one
+        assert_eq!(msg.messages[0].content, msg.messages[1].content);
+        assert_eq!(msg.memories.unwrap().len(), 1);
+
+        variants.push(msg.messages[0].content["text"].to_string());
+    }
+
+    assert_eq!(variants[0], variants[4]);
+}
+
+#[test]
+fn ok_experiment_invalid() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("experiment_invalid", "user_id"),
+        "CSML/basic_test/built-in/experiment.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
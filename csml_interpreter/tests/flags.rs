@@ -0,0 +1,85 @@
+mod support;
+
+use csml_interpreter::data::context::{get_hashmap_from_mem, Context};
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str, new_menu: bool) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.flags = get_hashmap_from_mem(&serde_json::json!({ "new_menu": new_menu }), "flow");
+
+    context
+}
+
+#[test]
+fn flags_enabled() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content": {"text": "new menu"}, "content_type":"text"}
+        ]
+    }"#;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start", true),
+        "CSML/basic_test/flags.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn flags_disabled() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content": {"text": "old menu"}, "content_type":"text"}
+        ]
+    }"#;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start", false),
+        "CSML/basic_test/flags.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn flags_missing_key() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content": {"text": "flag disabled"}, "content_type":"text"}
+        ]
+    }"#;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("missing", true),
+        "CSML/basic_test/flags.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2)
+}
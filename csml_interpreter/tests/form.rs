@@ -0,0 +1,134 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str) -> Context {
+    Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    )
+}
+
+#[test]
+fn ok_form_components() {
+    let data = r#"{"memories":[],"messages":[
+        {"content":{"fields":[{"name":"email","required":true,"type":"email"},{"name":"plan","options":["free","pro"],"type":"select"}],"submit":"Submit","title":"Sign up"},"content_type":"form"},
+        {"content":{"amount":19.99,"currency":"EUR"},"content_type":"paymentrequest"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start"),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_validate_form() {
+    let data = r#"{"memories":[],"messages":[
+        {"content":[{"error":"invalid_email","field":"email"},{"error":"invalid_option","field":"plan"},{"error":"unknown_field","field":"other"}],"content_type":"array"}
+    ]}"#;
+    let msg = format_message(
+        Event::new(
+            "form",
+            "",
+            serde_json::json!({"values": {"email": "jane", "plan": "gold", "other": 1}}),
+        ),
+        get_context("validate_form"),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_validate_form_required() {
+    let data = r#"{"memories":[],"messages":[
+        {"content":[{"error":"required","field":"email"}],"content_type":"array"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("form", "", serde_json::json!({"values": {"plan": "pro"}})),
+        get_context("validate_form"),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_validate_payment() {
+    let data = r#"{"memories":[],"messages":[ {"content":[],"content_type":"array"} ]}"#;
+    let msg = format_message(
+        Event::new(
+            "payment",
+            "",
+            serde_json::json!({"status": "succeeded", "amount": 19.99, "currency": "eur"}),
+        ),
+        get_context("validate_payment"),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_validate_payment_failed() {
+    let data = r#"{"memories":[],"messages":[
+        {"content":[{"error":"not_succeeded","field":"status"},{"error":"mismatch","field":"amount"}],"content_type":"array"}
+    ]}"#;
+    let msg = format_message(
+        Event::new(
+            "payment",
+            "",
+            serde_json::json!({"status": "failed", "amount": 9.99, "currency": "EUR"}),
+        ),
+        get_context("validate_payment"),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_form_event_types() {
+    let event = Event::from_payload(
+        "form",
+        serde_json::json!({"values": {"email": "jane@example.com"}}),
+    )
+    .unwrap();
+    assert_eq!(event.content_value, r#"{"email":"jane@example.com"}"#);
+
+    let event = Event::from_payload("payment", serde_json::json!({"status": "succeeded"})).unwrap();
+    assert_eq!(event.content_value, "succeeded");
+
+    assert!(Event::from_payload("form", serde_json::json!({"email": "jane"})).is_err());
+    assert!(Event::from_payload("payment", serde_json::json!({"amount": 10})).is_err());
+}
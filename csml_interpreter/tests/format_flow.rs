@@ -0,0 +1,123 @@
+use csml_interpreter::data::{csml_bot::CsmlBot, csml_flow::CsmlFlow};
+use csml_interpreter::{format_flow, validate_bot};
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+fn is_valid(content: &str) -> bool {
+    let bot = CsmlBot::new(
+        "bot_id",
+        "bot",
+        None,
+        vec![CsmlFlow::new("id", "flow", content, vec![])],
+        None,
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    validate_bot(&bot).errors.is_none()
+}
+
+fn get_csml_files(dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            get_csml_files(&path, files);
+        } else if path.extension() == Some(OsStr::new("csml")) {
+            files.push(path.to_str().unwrap().to_owned());
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// FORMAT FLOW
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn format_flow_indentation() {
+    let source = "start:\nsay   \"hello\"\n  if (x>1){\nsay x\n      }else {\n goto end\n}\n\n\n\nnext: goto end";
+
+    assert_eq!(
+        format_flow(source),
+        "start:\n    say \"hello\"\n    if (x > 1) {\n        say x\n    } else {\n        goto end\n    }\n\nnext:\n    goto end\n"
+    );
+}
+
+#[test]
+fn format_flow_spacing() {
+    let source = "start:\n  do x=[1,2 ,3, ]\n  do obj={a:1,  b:Button(\"b\")}\n  foreach(item,index)in x{\n say item\n}\n  goto end";
+
+    assert_eq!(
+        format_flow(source),
+        "start:\n    do x = [1, 2, 3,]\n    do obj = {a: 1, b: Button(\"b\")}\n    foreach (item, index) in x {\n        say item\n    }\n    goto end\n"
+    );
+}
+
+#[test]
+fn format_flow_comments_and_strings() {
+    let source = "// first step\nstart:\n  say \"a  =  {{ x==1 }}\"   //  keep  me\n  /* multi\n     line */\n  say \"multi\n  line\"\ngoto end";
+
+    assert_eq!(
+        format_flow(source),
+        "// first step\nstart:\n    say \"a  =  {{ x==1 }}\" //  keep  me\n    /* multi\n     line */\n    say \"multi\n  line\"\n    goto end\n"
+    );
+}
+
+#[test]
+fn format_flow_functions() {
+    let source = "import fn a from b\nstart:\n    goto end\n\nfn add(a,b){\nreturn a+b\n}\nfn sub(a, b):\nreturn a - b";
+
+    assert_eq!(
+        format_flow(source),
+        "import fn a from b\n\nstart:\n    goto end\n\nfn add(a, b) {\n    return a+b\n}\nfn sub(a, b):\n    return a - b\n"
+    );
+}
+
+#[test]
+fn format_flow_step_access() {
+    let source = "@requires_flow(plan=\"premium\")\nstart:\n    goto admin\n  @requires(role=\"agent\",denied_step=\"start\")\nadmin:\ngoto end";
+
+    assert_eq!(
+        format_flow(source),
+        "@requires_flow(plan = \"premium\")\n\nstart:\n    goto admin\n\n@requires(role = \"agent\", denied_step = \"start\")\nadmin:\n    goto end\n"
+    );
+}
+
+#[test]
+fn format_flow_step_timeout() {
+    let source = "start:\n    goto search\n@requires(role=\"agent\")\n  @timeout(5000,goto:slow)\nsearch:\ngoto end";
+
+    assert_eq!(
+        format_flow(source),
+        "start:\n    goto search\n\n@requires(role = \"agent\")\n@timeout(5000, goto: slow)\nsearch:\n    goto end\n"
+    );
+}
+
+#[test]
+fn format_flow_examples() {
+    let mut files = vec![];
+    get_csml_files(Path::new("CSML"), &mut files);
+
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        let formatted = format_flow(&source);
+
+        assert_eq!(format_flow(&formatted), formatted, "{} is not stable", file);
+        if is_valid(&source) {
+            assert!(is_valid(&formatted), "{} is not valid once formatted", file);
+        }
+    }
+}
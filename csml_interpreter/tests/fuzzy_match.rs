@@ -0,0 +1,79 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str) -> Context {
+    Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    )
+}
+
+#[test]
+fn ok_fuzzy_match() {
+    let data = r#"{"messages":[ {"content":{ "text": "pizza" },"content_type":"text"}, {"content":{ "text": "0" },"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start"),
+        "CSML/basic_test/built-in/fuzzy_match.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_fuzzy_match_under_threshold() {
+    let data =
+        r#"{"messages":[ {"content":{ "text": "no match" },"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("no_match"),
+        "CSML/basic_test/built-in/fuzzy_match.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_fuzzy_match_jaro_winkler() {
+    let data = r#"{"messages":[ {"content":{ "text": "marhta" },"content_type":"text"}, {"content":{ "text": "96" },"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("jaro_winkler"),
+        "CSML/basic_test/built-in/fuzzy_match.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_fuzzy_match_invalid() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("fuzzy_match_invalid"),
+        "CSML/basic_test/built-in/fuzzy_match.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
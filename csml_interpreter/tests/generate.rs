@@ -0,0 +1,159 @@
+mod support;
+
+use csml_interpreter::data::context::{get_hashmap_from_json, Context};
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+/**
+ * Answer a single request, return the body of the request
+ */
+fn mock_provider(content_type: &str, response: String) -> (String, thread::JoinHandle<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let content_type = content_type.to_owned();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            match line.trim_end().split_once(": ") {
+                Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                    length = value.parse().unwrap();
+                }
+                None if line.trim_end().is_empty() => break,
+                _ => (),
+            }
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+                    content_type,
+                    response.len(),
+                    response
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+
+        serde_json::from_slice(&body).unwrap()
+    });
+
+    (url, server)
+}
+
+fn get_context(step: &str, secrets: Value) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.secrets = get_hashmap_from_json(&secrets, "flow");
+
+    context
+}
+
+#[test]
+fn ok_generate() {
+    let data =
+        r#"{"messages":[ {"content":{ "text": "Hello!" },"content_type":"text"} ],"memories":[]}"#;
+    let (url, server) = mock_provider(
+        "application/json",
+        r#"{"choices": [{"message": {"role": "assistant", "content": "Hello!"}}]}"#.to_owned(),
+    );
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context(
+            "start",
+            serde_json::json!({"llm_url": url, "llm_api_key": "key"}),
+        ),
+        "CSML/basic_test/built-in/generate.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2);
+
+    let request = server.join().unwrap();
+    assert_eq!(request["model"], "test-model");
+    assert_eq!(request["max_tokens"], 10);
+    assert_eq!(request["messages"][0]["content"], "Say hello");
+}
+
+#[test]
+fn ok_generate_stream() {
+    let data = r#"{"messages":[ {"content":{ "text": "Hello there!" },"content_type":"text"} ],"memories":[]}"#;
+    let events = [
+        r#"data: {"choices": [{"delta": {"role": "assistant"}}]}"#,
+        r#"data: {"choices": [{"delta": {"content": "Hello"}}]}"#,
+        r#"data: {"choices": [{"delta": {"content": " there!"}}]}"#,
+        "data: [DONE]",
+    ];
+    let (url, server) = mock_provider("text/event-stream", events.join("\n\n"));
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("generate_stream", serde_json::json!({"llm_url": url})),
+        "CSML/basic_test/built-in/generate.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2);
+
+    assert_eq!(server.join().unwrap()["stream"], true);
+}
+
+#[test]
+fn ok_generate_local() {
+    let data =
+        r#"{"messages":[ {"content":{ "text": "Hi" },"content_type":"text"} ],"memories":[]}"#;
+    let (url, server) = mock_provider("application/json", r#"{"text": "Hi"}"#.to_owned());
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context(
+            "start",
+            serde_json::json!({"llm_provider": "local", "llm_url": url}),
+        ),
+        "CSML/basic_test/built-in/generate.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2);
+
+    assert_eq!(server.join().unwrap()["prompt"], "Say hello");
+}
+
+#[test]
+fn ok_generate_invalid() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("generate_invalid", serde_json::json!({})),
+        "CSML/basic_test/built-in/generate.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
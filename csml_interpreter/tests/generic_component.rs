@@ -46,6 +46,12 @@ fn format_message(
         None,
         None,
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     interpret(bot, context, event, None)
@@ -0,0 +1,72 @@
+mod support;
+
+use csml_interpreter::data::context::{get_hashmap_from_mem, Context};
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.globals = get_hashmap_from_mem(
+        &serde_json::json!({
+            "announcement": "We are closed today",
+            "flags": {"new_menu": true}
+        }),
+        "flow",
+    );
+
+    context
+}
+
+#[test]
+fn global_read() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content": {"text": "We are closed today"}, "content_type":"text"},
+            {"content": {"text": "true"}, "content_type":"text"}
+        ]
+    }"#;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start"),
+        "CSML/basic_test/global.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn global_missing_key() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content": {"text": "no global"}, "content_type":"text"}
+        ]
+    }"#;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("missing"),
+        "CSML/basic_test/global.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2)
+}
@@ -2,6 +2,8 @@ mod support;
 
 use csml_interpreter::data::context::Context;
 use csml_interpreter::data::event::Event;
+use csml_interpreter::data::{csml_bot::CsmlBot, csml_flow::CsmlFlow};
+use csml_interpreter::interpret;
 use std::collections::HashMap;
 
 use crate::support::tools::format_message;
@@ -29,3 +31,74 @@ fn infinite_loop() {
 
     assert_eq!("error", error["messages"][0]["content_type"])
 }
+
+#[test]
+fn infinite_while_loop() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/infinite_while_loop.csml",
+    );
+
+    let error: Value = message_to_json_value(msg);
+
+    assert_eq!("error", error["messages"][0]["content_type"]);
+    assert!(error["messages"][0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Loop limit reached"));
+}
+
+#[test]
+fn infinite_goto_flow() {
+    let flows = vec![
+        CsmlFlow::new(
+            "id_a",
+            "flow_a",
+            "start: if (true) { goto @flow_b } goto end",
+            vec![],
+        ),
+        CsmlFlow::new(
+            "id_b",
+            "flow_b",
+            "start: if (true) { goto @flow_a } goto end",
+            vec![],
+        ),
+    ];
+    let bot = CsmlBot::new(
+        "id", "bot", None, flows, None, None, "flow_a", None, None, None, None, None, None, None,
+        None, None, None, None,
+    );
+
+    let msg = interpret(
+        bot,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow_a",
+            None,
+        ),
+        Event::new("payload", "", serde_json::json!({})),
+        None,
+    );
+
+    let error: Value = message_to_json_value(msg);
+    let messages = error["messages"].as_array().unwrap();
+
+    assert_eq!("error", messages[messages.len() - 1]["content_type"]);
+    assert!(messages[messages.len() - 1]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Goto limit reached"));
+}
@@ -0,0 +1,37 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[test]
+fn literal_size_limit() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/literal_size_limit.csml",
+    );
+
+    let error: Value = message_to_json_value(msg);
+    let messages = error["messages"].as_array().unwrap();
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!("error", messages[0]["content_type"]);
+    assert!(messages[0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Value too large"));
+}
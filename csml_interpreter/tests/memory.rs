@@ -38,6 +38,12 @@ fn format_message(event: Event, context: Context, vector: &[&str]) -> MessageDat
         None,
         None,
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     interpret(bot, context, event, None)
@@ -0,0 +1,63 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_texts(step: &str) -> Value {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            step,
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/null_safety.csml",
+    );
+
+    let messages = message_to_json_value(msg)["messages"].to_owned();
+    messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["content"]["text"].to_owned())
+        .collect()
+}
+
+#[test]
+fn ok_optional_chaining() {
+    assert_eq!(
+        get_texts("start"),
+        serde_json::json!([
+            "friend",
+            "Jane",
+            "unknown",
+            "no order",
+            "no profile",
+            "no variable",
+            "Hello friend"
+        ])
+    );
+}
+
+#[test]
+fn ok_coalesce() {
+    assert_eq!(
+        get_texts("coalesce"),
+        serde_json::json!(["0", "", "last", "3", "nobody"])
+    );
+}
+
+#[test]
+fn ok_chaining_existing_values() {
+    assert_eq!(get_texts("chaining"), serde_json::json!(["Jane", "2", "b"]));
+}
@@ -0,0 +1,89 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, primitive::PrimitiveString, Interval};
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str, locale: Option<&str>) -> Context {
+    let mut metadata = HashMap::new();
+    if let Some(locale) = locale {
+        metadata.insert(
+            "locale".to_owned(),
+            PrimitiveString::get_literal(locale, Interval::default()),
+        );
+    }
+
+    Context::new(HashMap::new(), metadata, None, None, step, "flow", None)
+}
+
+fn get_texts(step: &str, locale: Option<&str>) -> Value {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context(step, locale),
+        "CSML/basic_test/built-in/number.csml",
+    );
+
+    let messages = message_to_json_value(msg)["messages"].to_owned();
+    messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["content"]["text"].to_owned())
+        .collect()
+}
+
+#[test]
+fn ok_format_number() {
+    assert_eq!(
+        get_texts("start", None),
+        serde_json::json!(["1,234,567.891", "1\u{202f}234,50", "1.234,5", "0"])
+    );
+}
+
+#[test]
+fn ok_format_number_user_locale() {
+    assert_eq!(
+        get_texts("user_locale", Some("fr-FR")),
+        serde_json::json!(["1\u{202f}234,5", "19,90\u{a0}€"])
+    );
+}
+
+#[test]
+fn ok_currency() {
+    assert_eq!(
+        get_texts("currency", None),
+        serde_json::json!(["€19.90", "19,90\u{a0}€", "¥1,500", "-CHF\u{a0}5.00"])
+    );
+}
+
+#[test]
+fn ok_to_number() {
+    assert_eq!(
+        get_texts("to_number", None),
+        serde_json::json!(["1234.5", "1201", "not a number"])
+    );
+}
+
+#[test]
+fn ok_convert_unit() {
+    assert_eq!(
+        get_texts("convert_unit", None),
+        serde_json::json!(["621", "212"])
+    );
+}
+
+#[test]
+fn ok_convert_unit_invalid() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("convert_unit_invalid", None),
+        "CSML/basic_test/built-in/number.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "wasm_plugins")]
+
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+const PLUGIN: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (data (i32.const 0) "{\"answer\":42}")
+    (func (export "alloc") (param i32) (result i32)
+        i32.const 1024)
+    (func (export "answer") (param i32 i32) (result i64)
+        i64.const 13)
+    (func (export "echo") (param i32 i32) (result i64)
+        local.get 0
+        i64.extend_i32_u
+        i64.const 32
+        i64.shl
+        local.get 1
+        i64.extend_i32_u
+        i64.or)
+    (func (export "spin") (param i32 i32) (result i64)
+        (loop (br 0))
+        i64.const 0)
+)
+"#;
+
+static INIT: Once = Once::new();
+
+fn init_plugins() {
+    INIT.call_once(|| {
+        let dir = std::env::temp_dir().join("csml_test_plugins");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("test.wasm"), PLUGIN).unwrap();
+
+        std::env::set_var("CSML_PLUGINS_DIR", dir);
+    });
+}
+
+#[test]
+fn plugins_call() {
+    init_plugins();
+
+    let data = r#"{"messages":[ {"content":{"text": "42"},"content_type":"text"}, {"content":[1, "two"],"content_type":"array"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/plugins.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn plugins_fuel_limit() {
+    init_plugins();
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "spin_step",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/plugins.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+
+    assert_eq!(v1["messages"][1]["content"]["text"], "true");
+}
@@ -96,3 +96,47 @@ fn remember_6() {
 
     assert!(result);
 }
+
+#[test]
+fn remember_7() {
+    let result = match format_message("CSML/basic_test/syntax/remember/remember_7.csml".to_owned())
+    {
+        Ok(_) => true,
+        Err(_) => false,
+    };
+
+    assert!(result);
+}
+
+#[test]
+fn remember_8() {
+    let result = match format_message("CSML/basic_test/syntax/remember/remember_8.csml".to_owned())
+    {
+        Ok(_) => false,
+        Err(_) => true,
+    };
+
+    assert!(result);
+}
+
+#[test]
+fn remember_9() {
+    let result = match format_message("CSML/basic_test/syntax/remember/remember_9.csml".to_owned())
+    {
+        Ok(_) => true,
+        Err(_) => false,
+    };
+
+    assert!(result);
+}
+
+#[test]
+fn remember_10() {
+    let result = match format_message("CSML/basic_test/syntax/remember/remember_10.csml".to_owned())
+    {
+        Ok(_) => false,
+        Err(_) => true,
+    };
+
+    assert!(result);
+}
@@ -0,0 +1,79 @@
+mod support;
+
+use csml_interpreter::data::csml_bot::CsmlBot;
+use csml_interpreter::data::csml_flow::CsmlFlow;
+use csml_interpreter::data::warnings::{Warnings, WARNING_COERCION, WARNING_NULL_VALUE};
+use csml_interpreter::data::{Context, Event, MSG};
+use csml_interpreter::interpret;
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+use support::tools::read_file;
+
+fn get_warnings(filepath: &str) -> Vec<(String, Warnings)> {
+    let content = read_file(filepath.to_string()).unwrap();
+    let flow = CsmlFlow::new("id", "flow", &content, Vec::default());
+
+    let bot = CsmlBot::new(
+        "id",
+        "bot",
+        None,
+        vec![flow],
+        None,
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let (sender, receiver) = mpsc::channel();
+    interpret(
+        bot,
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        Event::new("payload", "", serde_json::json!({})),
+        Some(sender),
+    );
+
+    receiver
+        .try_iter()
+        .filter_map(|msg| match msg {
+            MSG::Warning { step, warning } => Some((step, warning)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn runtime_warnings() {
+    let warnings = get_warnings("CSML/basic_test/runtime_warnings.csml");
+
+    assert_eq!(warnings.len(), 2);
+
+    let (step, warning) = &warnings[0];
+    assert_eq!(step, "start");
+    assert!(warning.message.starts_with(WARNING_NULL_VALUE));
+    assert!(warning.message.contains("age"));
+    assert_eq!(warning.position.flow, "flow");
+    assert_eq!(warning.position.interval.start_line, 3);
+
+    let (_, warning) = &warnings[1];
+    assert!(warning.message.starts_with(WARNING_COERCION));
+    assert_eq!(warning.position.interval.start_line, 6);
+}
@@ -0,0 +1,95 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, Sandbox};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::{json, Value};
+
+fn run_step(step: &str, sandbox: Sandbox) -> Value {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.sandbox = Some(Arc::new(sandbox));
+
+    let msg = format_message(
+        Event::new("payload", "", json!({})),
+        context,
+        "CSML/basic_test/sandbox.csml",
+    );
+
+    message_to_json_value(msg)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// SANDBOX
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ok_sandbox_random() {
+    let first = run_step("start", Sandbox::new(42));
+    let second = run_step("start", Sandbox::new(42));
+    let other = run_step("start", Sandbox::new(7));
+
+    assert_eq!(first["messages"][0]["content_type"], "text");
+    assert_eq!(first["messages"], second["messages"]);
+    assert_ne!(first["messages"], other["messages"]);
+}
+
+#[test]
+fn ok_sandbox_time() {
+    let msg = run_step("time", Sandbox::new(0).with_time(1_600_000_000_000));
+    assert_eq!(msg["messages"][0]["content"]["text"], "1600000000000");
+
+    let msg = run_step("time", Sandbox::new(0));
+    assert_eq!(msg["messages"][0]["content"]["text"], "0");
+}
+
+#[test]
+fn ok_sandbox_http() {
+    let sandbox = Sandbox::new(0)
+        .with_http_response("https://example.com/users?id=42", json!({"name": "Jane"}));
+    let msg = run_step("http", sandbox);
+
+    assert_eq!(msg["messages"][0]["content"]["text"], "Jane");
+}
+
+#[test]
+fn ok_sandbox_http_without_response() {
+    let msg = run_step("http_error", Sandbox::new(0));
+
+    assert_eq!(msg["messages"][0]["content_type"], "error");
+    assert!(msg["messages"][0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("https://example.com/unknown"));
+}
+
+#[test]
+fn ok_sandbox_app() {
+    let sandbox = Sandbox::new(0).with_app_response("get_user", json!({"name": "Jane"}));
+    let msg = run_step("app", sandbox);
+
+    assert_eq!(msg["messages"][0]["content"]["text"], "Jane");
+}
+
+#[test]
+fn ok_sandbox_network() {
+    let msg = run_step("generate", Sandbox::new(0));
+
+    assert_eq!(msg["messages"][0]["content_type"], "error");
+    assert!(msg["messages"][0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("disabled in the sandbox"));
+}
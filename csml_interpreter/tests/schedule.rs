@@ -0,0 +1,96 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[test]
+fn ok_schedule() {
+    let data =
+        r#"{"messages":[ {"content":{ "text": "36" },"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/schedule.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_schedule_payload() {
+    let data =
+        r#"{"messages":[ {"content":{ "text": "36" },"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "schedule_payload",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/schedule.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_schedule_invalid_event() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "schedule_invalid_event",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/schedule.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
+
+#[test]
+fn ok_schedule_invalid_delay() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "schedule_invalid_delay",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/schedule.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
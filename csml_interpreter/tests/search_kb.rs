@@ -0,0 +1,101 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use csml_interpreter::data::{KnowledgeBase, Passage};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[derive(Debug)]
+struct TestKnowledgeBase;
+
+impl KnowledgeBase for TestKnowledgeBase {
+    fn search(&self, query: &str, top_k: usize) -> Result<Vec<Passage>, String> {
+        let passages = vec![
+            Passage {
+                document_id: "faq".to_owned(),
+                text: format!("To {}, open the settings.", query),
+                score: 0.92,
+                metadata: serde_json::json!({}),
+            },
+            Passage {
+                document_id: "guide".to_owned(),
+                text: "Passwords expire every 90 days.".to_owned(),
+                score: 0.71,
+                metadata: serde_json::json!({}),
+            },
+            Passage {
+                document_id: "terms".to_owned(),
+                text: "Terms of use.".to_owned(),
+                score: 0.12,
+                metadata: serde_json::json!({}),
+            },
+        ];
+
+        Ok(passages.into_iter().take(top_k).collect())
+    }
+}
+
+fn get_context(step: &str) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.knowledge_base = Some(Arc::new(TestKnowledgeBase));
+
+    context
+}
+
+#[test]
+fn ok_search_kb() {
+    let data = r#"{"messages":[
+        {"content":{ "text": "2" },"content_type":"text"},
+        {"content":{ "text": "To reset password, open the settings." },"content_type":"text"},
+        {"content":{ "text": "faq" },"content_type":"text"}
+    ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start"),
+        "CSML/basic_test/built-in/search_kb.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_search_kb_invalid() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("search_kb_invalid"),
+        "CSML/basic_test/built-in/search_kb.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
+
+#[test]
+fn ok_search_kb_disabled() {
+    let mut context = get_context("start");
+    context.knowledge_base = None;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        context,
+        "CSML/basic_test/built-in/search_kb.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
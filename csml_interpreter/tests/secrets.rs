@@ -0,0 +1,74 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, primitive::PrimitiveString, Interval};
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str) -> Context {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.secrets.insert(
+        "api_key".to_owned(),
+        PrimitiveString::get_literal("secret_key", Interval::default()),
+    );
+
+    context
+}
+
+#[test]
+fn secrets_read() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content": {"text": "ok"}, "content_type":"text"}
+        ]
+    }"#;
+
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start"),
+        "CSML/basic_test/secrets.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn secrets_display() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("display"),
+        "CSML/basic_test/secrets.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    assert_eq!(v1["messages"].as_array().unwrap().len(), 1);
+    assert_eq!(v1["messages"][0]["content_type"], "error");
+}
+
+#[test]
+fn secrets_save() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("save"),
+        "CSML/basic_test/secrets.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    assert_eq!(v1["memories"], serde_json::json!([]));
+    assert_eq!(v1["messages"][0]["content_type"], "error");
+}
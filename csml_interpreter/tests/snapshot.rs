@@ -0,0 +1,131 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{
+    event::Event,
+    primitive::{PrimitiveInt, PrimitiveObject, PrimitiveString},
+    Interval, Literal,
+};
+use csml_interpreter::snapshot::*;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+
+use serde_json::json;
+
+fn get_object(keys: &[&str]) -> Literal {
+    let mut object = HashMap::new();
+    for (index, key) in keys.iter().enumerate() {
+        object.insert(
+            key.to_string(),
+            PrimitiveInt::get_literal(index as i64, Interval::default()),
+        );
+    }
+
+    PrimitiveObject::get_literal(&object, Interval::default())
+}
+
+fn get_context() -> Context {
+    Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        "start",
+        "flow",
+        None,
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// SNAPSHOT
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ok_canonical_json() {
+    let value = json!({"b": {"d": 1, "c": [{"f": true, "e": null}]}, "a": "x"});
+
+    assert_eq!(
+        serde_json::to_string(&canonical_json(&value)).unwrap(),
+        r#"{"a":"x","b":{"c":[{"e":null,"f":true}],"d":1}}"#
+    );
+}
+
+#[test]
+fn ok_literal_to_json() {
+    let literal = get_object(&["name"]);
+
+    assert_eq!(
+        literal_to_json(&literal),
+        json!({
+            "content_type": "object",
+            "type": "object",
+            "value": {
+                "name": {"content_type": "int", "type": "int", "value": 0}
+            }
+        })
+    );
+}
+
+#[test]
+fn ok_literals_eq() {
+    let mut left = get_object(&["a", "b", "c"]);
+    let right = get_object(&["a", "b", "c"]);
+    let other = get_object(&["a", "b"]);
+
+    left.interval = Interval::new_as_u32(4, 2, 0, None, None);
+    assert!(literals_eq(&left, &right));
+    assert!(!literals_eq(&left, &other));
+
+    let mut text = PrimitiveString::get_literal("hello", Interval::default());
+    let string = text.clone();
+    text.content_type = "text".to_owned();
+    assert!(!literals_eq(&text, &string));
+}
+
+#[test]
+fn ok_contexts_eq() {
+    let mut left = get_context();
+    let right = get_context();
+    assert!(contexts_eq(&left, &right));
+
+    left.current
+        .insert("user".to_owned(), get_object(&["name"]));
+    assert!(!contexts_eq(&left, &right));
+    assert_eq!(
+        context_to_json(&left)["current"]["user"]["value"]["name"]["value"],
+        0
+    );
+}
+
+#[test]
+fn ok_message_data_snapshot() {
+    let first = format_message(
+        Event::new("payload", "", json!({})),
+        get_context(),
+        "CSML/basic_test/snapshot.csml",
+    );
+    let second = format_message(
+        Event::new("payload", "", json!({})),
+        get_context(),
+        "CSML/basic_test/snapshot.csml",
+    );
+
+    assert!(message_data_eq(&first, &second));
+
+    let snapshot = message_data_to_json(&first);
+    assert_eq!(
+        to_snapshot_string(&snapshot),
+        to_snapshot_string(&message_data_to_json(&second))
+    );
+    assert_eq!(snapshot["exit_condition"], "end");
+    assert_eq!(snapshot["memories"][0]["key"], "user");
+    assert_eq!(
+        snapshot["memories"][0]["value"]["_content"],
+        json!({"age": 42, "name": "Jane", "tags": ["a", "b"]})
+    );
+    assert_eq!(
+        snapshot["messages"][0]["content"],
+        json!({"age": 42, "name": "Jane", "tags": ["a", "b"]})
+    );
+}
@@ -0,0 +1,100 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, Interval, Literal};
+use csml_interpreter::interpreter::json_to_literal;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::{json, Value};
+
+fn run_step(step: &str, metadata: Value, filepath: &str) -> Value {
+    let metadata: HashMap<String, Literal> = metadata
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| {
+            let literal = json_to_literal(value, Interval::default(), "flow").unwrap();
+            (key.to_owned(), literal)
+        })
+        .collect();
+
+    let msg = format_message(
+        Event::new("payload", "", json!({})),
+        Context::new(HashMap::new(), metadata, None, None, step, "flow", None),
+        filepath,
+    );
+
+    message_to_json_value(msg)
+}
+
+fn texts(msg: &Value) -> Vec<Value> {
+    msg["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["content"]["text"].to_owned())
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// STEP ACCESS
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ok_step_access_granted() {
+    let msg = run_step(
+        "start",
+        json!({"role": "agent"}),
+        "CSML/basic_test/step_access.csml",
+    );
+
+    assert_eq!(texts(&msg), vec![json!("Hello"), json!("Welcome agent")]);
+}
+
+#[test]
+fn ok_step_access_denied_step() {
+    let msg = run_step(
+        "start",
+        json!({"role": "user"}),
+        "CSML/basic_test/step_access.csml",
+    );
+
+    assert_eq!(texts(&msg), vec![json!("Hello"), json!("Agents only")]);
+}
+
+#[test]
+fn ok_step_access_array_claim() {
+    let msg = run_step(
+        "admin",
+        json!({"role": ["user", "agent"]}),
+        "CSML/basic_test/step_access.csml",
+    );
+
+    assert_eq!(texts(&msg), vec![json!("Welcome agent")]);
+}
+
+#[test]
+fn ok_step_access_denied_without_denied_step() {
+    let msg = run_step("billing", json!({}), "CSML/basic_test/step_access.csml");
+
+    assert_eq!(msg["messages"].as_array().unwrap().len(), 1);
+    assert_eq!(msg["messages"][0]["content_type"], "error");
+    assert!(msg["messages"][0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("access denied"));
+}
+
+#[test]
+fn ok_flow_access() {
+    let filepath = "CSML/basic_test/step_access_flow.csml";
+
+    let msg = run_step("start", json!({}), filepath);
+    assert_eq!(texts(&msg), vec![json!("Upgrade your plan")]);
+
+    let msg = run_step("start", json!({"plan": "premium"}), filepath);
+    assert_eq!(texts(&msg), vec![json!("Premium content")]);
+}
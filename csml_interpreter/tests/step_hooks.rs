@@ -0,0 +1,106 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, StepHooks};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::{json, Value};
+
+#[derive(Debug, Default)]
+struct RecordedHooks {
+    calls: Mutex<Vec<Value>>,
+    vetoed_step: Option<String>,
+}
+
+impl StepHooks for RecordedHooks {
+    fn before_step(&self, flow: &str, step: &str, variables: &Value) -> Result<(), String> {
+        self.calls.lock().unwrap().push(json!({
+            "hook": "before_step", "flow": flow, "step": step, "variables": variables
+        }));
+
+        match &self.vetoed_step {
+            Some(vetoed) if vetoed == step => Err("the store is closed".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    fn after_step(&self, flow: &str, step: &str, _duration: Duration, variables: &Value) {
+        self.calls.lock().unwrap().push(json!({
+            "hook": "after_step", "flow": flow, "step": step, "variables": variables
+        }));
+    }
+}
+
+fn run_step(step: &str, hooks: Arc<RecordedHooks>) -> Value {
+    let mut context = Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    );
+    context.step_hooks = Some(hooks);
+
+    let msg = format_message(
+        Event::new("payload", "", json!({})),
+        context,
+        "CSML/basic_test/step_hooks.csml",
+    );
+
+    message_to_json_value(msg)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// STEP HOOKS
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ok_step_hooks() {
+    let hooks = Arc::new(RecordedHooks::default());
+    let msg = run_step("start", hooks.clone());
+
+    assert_eq!(msg["messages"][0]["content"]["text"], "Hello Jane");
+    assert_eq!(
+        *hooks.calls.lock().unwrap(),
+        vec![
+            json!({"hook": "before_step", "flow": "flow", "step": "start", "variables": {}}),
+            json!({
+                "hook": "after_step", "flow": "flow", "step": "start",
+                "variables": {"count": 1, "name": "Jane"}
+            }),
+            json!({
+                "hook": "before_step", "flow": "flow", "step": "greet",
+                "variables": {"name": "Jane"}
+            }),
+            json!({
+                "hook": "after_step", "flow": "flow", "step": "greet",
+                "variables": {"name": "Jane"}
+            }),
+        ]
+    );
+}
+
+#[test]
+fn ok_step_hooks_veto() {
+    let hooks = Arc::new(RecordedHooks {
+        calls: Mutex::new(vec![]),
+        vetoed_step: Some("closed".to_owned()),
+    });
+    let msg = run_step("closed", hooks.clone());
+
+    assert_eq!(msg["messages"].as_array().unwrap().len(), 1);
+    assert_eq!(msg["messages"][0]["content_type"], "error");
+    assert!(msg["messages"][0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("the store is closed"));
+    // a vetoed step is not executed
+    assert_eq!(hooks.calls.lock().unwrap().len(), 1);
+}
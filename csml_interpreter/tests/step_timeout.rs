@@ -0,0 +1,104 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, Interval, Literal};
+use csml_interpreter::interpreter::json_to_literal;
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::{json, Value};
+
+/**
+ * Url of a server accepting the requests without ever answering them
+ */
+fn slow_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut buffer = [0; 1024];
+                while let Ok(size) = stream.read(&mut buffer) {
+                    if size == 0 {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    url
+}
+
+fn run_step(step: &str, metadata: Value) -> Value {
+    let metadata: HashMap<String, Literal> = metadata
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| {
+            let literal = json_to_literal(value, Interval::default(), "flow").unwrap();
+            (key.to_owned(), literal)
+        })
+        .collect();
+
+    let msg = format_message(
+        Event::new("payload", "", json!({ "url": slow_server() })),
+        Context::new(HashMap::new(), metadata, None, None, step, "flow", None),
+        "CSML/basic_test/step_timeout.csml",
+    );
+
+    message_to_json_value(msg)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// STEP TIMEOUT
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ok_step_timeout_fallback_step() {
+    let start = Instant::now();
+    let msg = run_step("start", json!({}));
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        msg["messages"],
+        json!([
+            {"content": {"text": "Hello"}, "content_type": "text"},
+            {"content": {"text": "One moment please..."}, "content_type": "text"},
+            {"content": {"text": "The search is not available"}, "content_type": "text"},
+        ])
+    );
+}
+
+#[test]
+fn ok_step_timeout_without_message() {
+    let msg = run_step("agent_search", json!({"role": "agent"}));
+
+    assert_eq!(
+        msg["messages"],
+        json!([
+            {
+                "content": {"step": "agent_search", "timeout": 200, "goto": "slow_response"},
+                "content_type": "timeout"
+            },
+            {"content": {"text": "The search is not available"}, "content_type": "text"},
+        ])
+    );
+}
+
+#[test]
+fn ok_step_timeout_not_exceeded() {
+    let msg = run_step("fast_search", json!({}));
+
+    assert_eq!(
+        msg["messages"],
+        json!([{"content": {"text": "Found"}, "content_type": "text"}])
+    );
+}
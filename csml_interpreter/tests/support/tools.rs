@@ -42,6 +42,12 @@ pub fn format_message(event: Event, context: Context, filepath: &str) -> Message
         None,
         None,
         None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     );
 
     interpret(bot, context, event, None)
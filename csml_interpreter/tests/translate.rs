@@ -0,0 +1,122 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{
+    event::Event, primitive::PrimitiveString, BotLocales, CsmlBot, CsmlFlow, Interval, MessageData,
+};
+use csml_interpreter::interpret;
+use std::collections::HashMap;
+
+use crate::support::tools::{message_to_json_value, read_file};
+
+use serde_json::Value;
+
+fn translate_message(step: &str, locale: Option<&str>, event: Event) -> MessageData {
+    let content = read_file("CSML/basic_test/built-in/translate.csml".to_owned()).unwrap();
+    let flow = CsmlFlow::new("id", "flow", &content, Vec::default());
+
+    let mut translations = HashMap::new();
+    translations.insert(
+        "en".to_owned(),
+        serde_json::json!({"greeting": "Hello {{name}}", "menu": {"title": "Menu"}}),
+    );
+    translations.insert(
+        "fr".to_owned(),
+        serde_json::json!({"greeting": "Bonjour {{name}}"}),
+    );
+
+    let mut bot = CsmlBot::new(
+        "id",
+        "bot",
+        None,
+        vec![flow],
+        None,
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    bot.locales = Some(BotLocales {
+        default_locale: Some("en".to_owned()),
+        translations,
+    });
+
+    let mut metadata = HashMap::new();
+    if let Some(locale) = locale {
+        metadata.insert(
+            "locale".to_owned(),
+            PrimitiveString::get_literal(locale, Interval::default()),
+        );
+    }
+
+    let context = Context::new(HashMap::new(), metadata, None, None, step, "flow", None);
+
+    interpret(bot, context, event, None)
+}
+
+#[test]
+fn ok_translate_fallback_language() {
+    let data = r#"{"messages":[ {"content":{ "text": "Bonjour Jane" },"content_type":"text"} ],"memories":[]}"#;
+    let event = Event::new("payload", "", serde_json::json!({}));
+    let msg = translate_message("start", Some("fr-CA"), event);
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_translate_event_locale() {
+    let data = r#"{"messages":[ {"content":{ "text": "Bonjour Jane" },"content_type":"text"} ],"memories":[]}"#;
+    let mut event = Event::new("payload", "", serde_json::json!({}));
+    event.locale = Some("fr".to_owned());
+    let msg = translate_message("start", None, event);
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_translate_default_locale() {
+    let data =
+        r#"{"messages":[ {"content":{ "text": "Menu" },"content_type":"text"} ],"memories":[]}"#;
+    let event = Event::new("payload", "", serde_json::json!({}));
+    let msg = translate_message("translate_nested", Some("fr"), event);
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_translate_missing_key() {
+    let data = r#"{"messages":[ {"content":{ "text": "unknown_key" },"content_type":"text"} ],"memories":[]}"#;
+    let event = Event::new("payload", "", serde_json::json!({}));
+    let msg = translate_message("translate_missing", Some("en"), event);
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ko_translate_invalid_key() {
+    let event = Event::new("payload", "", serde_json::json!({}));
+    let msg = translate_message("translate_invalid", Some("en"), event);
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
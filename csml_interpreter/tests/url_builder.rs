@@ -0,0 +1,78 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn get_context(step: &str) -> Context {
+    Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        None,
+        step,
+        "flow",
+        None,
+    )
+}
+
+#[test]
+fn ok_url_path_query() {
+    let data = r#"{"messages":[ {"content":{ "url": "https://shop.example.com/products/my%20cart/12?q=fish+%26+chips", "text": "https://shop.example.com/products/my%20cart/12?q=fish+%26+chips", "title": "https://shop.example.com/products/my%20cart/12?q=fish+%26+chips" },"content_type":"url"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("start"),
+        "CSML/basic_test/built-in/url_builder.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_url_query_object() {
+    let data = r#"{"messages":[ {"content":{ "url": "https://example.com/search?page=1&lang=fr&tag=a+b&tag=c", "text": "Search", "title": "https://example.com/search?page=1&lang=fr&tag=a+b&tag=c" },"content_type":"url"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("query_object"),
+        "CSML/basic_test/built-in/url_builder.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_url_parse() {
+    let data = r#"{"messages":[ {"content":{ "text": "example.com" },"content_type":"text"}, {"content":{ "text": "8080" },"content_type":"text"}, {"content":{ "text": "/a b" },"content_type":"text"}, {"content":{ "text": "fish & chips" },"content_type":"text"}, {"content":{ "text": "top" },"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("parse"),
+        "CSML/basic_test/built-in/url_builder.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_url_invalid() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        get_context("invalid_url"),
+        "CSML/basic_test/built-in/url_builder.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
@@ -0,0 +1,370 @@
+use csml_interpreter::data::warnings::WARNING_UNREACHABLE_CODE;
+use csml_interpreter::data::{csml_bot::CsmlBot, csml_flow::CsmlFlow, CsmlResult};
+use csml_interpreter::{load_components, validate_bot};
+
+fn get_bot(flows: Vec<CsmlFlow>) -> CsmlBot {
+    CsmlBot::new(
+        "bot_id", "bot", None, flows, None, None, "flow_0", None, None, None, None, None, None,
+        None, None, None, None, None,
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// VALIDATE BOT
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn validate_bot_ok() {
+    let flows = (0..10)
+        .map(|index| {
+            CsmlFlow::new(
+                &format!("id_{}", index),
+                &format!("flow_{}", index),
+                "start: say \"hello\" goto end",
+                vec![],
+            )
+        })
+        .collect();
+
+    let result = validate_bot(&get_bot(flows));
+
+    assert!(result.errors.is_none());
+    assert_eq!(result.flows.unwrap().len(), 10);
+}
+
+#[test]
+fn validate_bot_errors_order() {
+    let flows = (0..10)
+        .map(|index| {
+            CsmlFlow::new(
+                &format!("id_{}", index),
+                &format!("flow_{}", index),
+                "start: say \"hello\" goto",
+                vec![],
+            )
+        })
+        .collect();
+
+    let errors = validate_bot(&get_bot(flows)).errors.unwrap();
+    let errors_flows: Vec<String> = errors
+        .into_iter()
+        .map(|error| error.position.flow)
+        .collect();
+
+    let expected: Vec<String> = (0..10).map(|index| format!("flow_{}", index)).collect();
+    assert_eq!(errors_flows, expected);
+}
+
+#[test]
+fn validate_bot_errors_all_steps() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start:\n    say \"hello\"\n    goto\n\nok:\n    goto end\n\nbroken:\n    if (x {\n        goto end\n    }\n\nlast:\n    say (\n",
+        vec![],
+    )];
+
+    let errors = validate_bot(&get_bot(flows)).errors.unwrap();
+    let lines: Vec<u32> = errors
+        .iter()
+        .map(|error| error.position.interval.start_line)
+        .collect();
+
+    // the missing step name of the goto is found at the start of the next step
+    assert_eq!(lines, vec![5, 9, 14]);
+}
+
+#[test]
+fn validate_bot_single_error() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start:\n    say \"hello\"\n    goto next\n\nnext:\n    say (\n",
+        vec![],
+    )];
+
+    let errors = validate_bot(&get_bot(flows)).errors.unwrap();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].position.interval.start_line, 6);
+}
+
+#[test]
+fn validate_bot_step_access_errors() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start:\n    goto admin\n\n@requires(role)\nadmin:\n    goto end\n\n@requires(role = \"agent\")\n",
+        vec![],
+    )];
+
+    let errors = validate_bot(&get_bot(flows)).errors.unwrap();
+    let lines: Vec<u32> = errors
+        .iter()
+        .map(|error| error.position.interval.start_line)
+        .collect();
+
+    assert_eq!(lines, vec![4, 9]);
+    assert!(errors[0].message.contains("'@requires' expecting the claims of the step"));
+    assert!(errors[1].message.contains("'@requires' must be followed by a step"));
+}
+
+#[test]
+fn validate_bot_step_timeout_errors() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start:\n    goto search\n\n@timeout(fast, goto: slow)\nsearch:\n    goto end\n\n@timeout(5000, goto: slow)\n",
+        vec![],
+    )];
+
+    let errors = validate_bot(&get_bot(flows)).errors.unwrap();
+    let lines: Vec<u32> = errors
+        .iter()
+        .map(|error| error.position.interval.start_line)
+        .collect();
+
+    assert_eq!(lines, vec![4, 9]);
+    assert!(errors[0].message.contains("'@timeout' expecting the budget of the step"));
+    assert!(errors[1].message.contains("'@timeout' must be followed by a step"));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// DEAD STEPS AND UNREACHABLE CODE
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_warnings(flows: Vec<CsmlFlow>) -> Vec<String> {
+    let result = validate_bot(&get_bot(flows));
+
+    assert!(result.errors.is_none());
+    result
+        .warnings
+        .unwrap_or_default()
+        .into_iter()
+        .map(|warning| warning.message)
+        .collect()
+}
+
+#[test]
+fn validate_bot_no_warnings() {
+    let flows = vec![
+        CsmlFlow::new(
+            "id_0",
+            "flow_0",
+            "start: goto next\n next: goto start@flow_1",
+            vec![],
+        ),
+        CsmlFlow::new("id_1", "flow_1", "start: say \"hello\" goto end", vec![]),
+    ];
+
+    assert!(get_warnings(flows).is_empty());
+}
+
+#[test]
+fn validate_bot_dead_step() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: goto end\n dead: say \"hello\"",
+        vec![],
+    )];
+
+    let warnings = get_warnings(flows);
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("step [dead] in flow [flow_0] is never reached by a goto"));
+}
+
+#[test]
+fn validate_bot_dynamic_goto_no_dead_step() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: do next = \"other\" goto $next\n other: goto end",
+        vec![],
+    )];
+
+    assert!(get_warnings(flows).is_empty());
+}
+
+#[test]
+fn validate_bot_step_access_denied_steps() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: goto admin\n @requires(role = \"agent\", denied_step = \"denied\")\n admin: goto end\n denied: goto end\n @requires(team = \"billing\", denied_step = \"unknown\")\n billing: goto end",
+        vec![],
+    )];
+
+    let warnings = get_warnings(flows);
+
+    // the denied step is reached, the billing step is not
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("goto target step [unknown] does not exist in flow [flow_0]"));
+    assert!(warnings[1].contains("step [billing] in flow [flow_0] is never reached by a goto"));
+}
+
+#[test]
+fn validate_bot_step_timeout_fallback_steps() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: goto search\n @timeout(5000, goto: slow)\n search: goto end\n slow: goto end\n start_2: goto other\n @timeout(5000, goto: unknown)\n other: goto start",
+        vec![],
+    )];
+
+    let warnings = get_warnings(flows);
+
+    // the fallback step is reached
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("goto target step [unknown] does not exist in flow [flow_0]"));
+    assert!(warnings[1].contains("step [start_2] in flow [flow_0] is never reached by a goto"));
+}
+
+#[test]
+fn validate_bot_goto_unknown_targets() {
+    let flows = vec![
+        CsmlFlow::new(
+            "id_0",
+            "flow_0",
+            "start: if (true) { goto unknown_step } goto flow unknown_flow",
+            vec![],
+        ),
+        CsmlFlow::new("id_1", "flow_1", "start: goto other@flow_0", vec![]),
+    ];
+
+    let warnings = get_warnings(flows);
+
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings[0].contains("goto target step [unknown_step] does not exist in flow [flow_0]"));
+    assert!(warnings[1].contains("goto target flow [unknown_flow] does not exist"));
+    assert!(warnings[2].contains("goto target step [other] does not exist in flow [flow_0]"));
+}
+
+#[test]
+fn validate_bot_unreachable_code() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: goto end\n say \"hello\"",
+        vec![],
+    )];
+
+    let warnings = get_warnings(flows);
+
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains(WARNING_UNREACHABLE_CODE));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// VARIABLES USAGE
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn validate_bot_variables_ok() {
+    let flows = vec![
+        CsmlFlow::new(
+            "id_0",
+            "flow_0",
+            "start: remember user_name = event\n foreach (elem, index) in [1, 2] { say \"{{elem}} {{index}}\" } goto flow flow_1",
+            vec![],
+        ),
+        CsmlFlow::new(
+            "id_1",
+            "flow_1",
+            "start: say \"hello {{user_name}} {{_memory.counter}}\"\n remember counter = 1 goto end",
+            vec![],
+        ),
+    ];
+
+    assert!(get_warnings(flows).is_empty());
+}
+
+#[test]
+fn validate_bot_variables_typo() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: remember usr_name = event\n say \"hello {{user_name}}\" goto end",
+        vec![],
+    )];
+
+    let mut warnings = get_warnings(flows);
+    warnings.sort();
+
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("memory [usr_name] is remembered but never read in the bot"));
+    assert!(warnings[1].contains("variable [user_name] is used but never written in the bot"));
+}
+
+#[test]
+fn validate_bot_variables_all_memories_read() {
+    let flows = vec![CsmlFlow::new(
+        "id_0",
+        "flow_0",
+        "start: remember usr_name = event\n say \"{{_memory}}\" goto end",
+        vec![],
+    )];
+
+    assert!(get_warnings(flows).is_empty());
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// COMPONENTS ARGUMENTS
+////////////////////////////////////////////////////////////////////////////////
+
+fn validate_components(flow: &str) -> CsmlResult {
+    let mut bot = get_bot(vec![CsmlFlow::new("id_0", "flow_0", flow, vec![])]);
+    bot.native_components = Some(load_components().unwrap());
+
+    validate_bot(&bot)
+}
+
+#[test]
+fn validate_bot_component_args_ok() {
+    let result = validate_components(
+        "start:
+            do name = \"Jane\"
+            say Button(\"yes\")
+            say Button(title = \"Hi {{name}}\", payload = \"hi\")
+            say Question(\"Pick\", buttons = [Button(\"a\"), Button(\"b\")])
+            say Text(\"hello\")
+            goto end",
+    );
+
+    assert!(result.errors.is_none());
+    assert!(result.warnings.is_none());
+}
+
+#[test]
+fn validate_bot_component_args_type() {
+    let result = validate_components(
+        "start:
+            say Button({\"text\": \"yes\"})
+            say Question(\"Pick\", buttons = 42)
+            goto end",
+    );
+
+    let errors = result.errors.unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].position.interval.start_line, 2);
+    assert!(errors[0]
+        .message
+        .contains("parameter 'title' of Button must be of type String, Object found"));
+    assert_eq!(errors[1].position.interval.start_line, 3);
+    assert!(errors[1]
+        .message
+        .contains("parameter 'buttons' of Question must be of type Array, Number found"));
+}
+
+#[test]
+fn validate_bot_component_args_interpolation() {
+    let result = validate_components("start:\n say Button(\"Pick {{[1, 2]}}\")\n goto end");
+
+    assert!(result.errors.is_none());
+    let warnings = result.warnings.unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0]
+        .message
+        .contains("Array interpolated in the String parameter 'title' of Button"));
+}
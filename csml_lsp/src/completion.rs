@@ -0,0 +1,46 @@
+use csml_interpreter::data::ast::Flow;
+use csml_interpreter::linter::variables::get_memories_names;
+use lsp_types::{CompletionItem, CompletionItemKind};
+
+use std::collections::HashMap;
+
+/**
+ * Completion of the names of the memories remembered anywhere in the bot
+ */
+pub fn get_completion(flows: &HashMap<String, Flow>) -> Vec<CompletionItem> {
+    get_memories_names(flows)
+        .into_iter()
+        .map(|name| CompletionItem {
+            label: name,
+            kind: Some(CompletionItemKind::VARIABLE),
+            detail: Some("memory".to_owned()),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::documents::Documents;
+    use csml_interpreter::validate_bot;
+    use lsp_types::Url;
+
+    #[test]
+    fn ok_completion() {
+        let mut documents = Documents::default();
+        documents.files.insert(
+            Url::parse("file:///bot/Default.csml").unwrap(),
+            "start:\n    remember user_name = \"name\"\n    remember age = 42\n    goto end"
+                .to_owned(),
+        );
+
+        let flows = validate_bot(&documents.get_bot()).flows.unwrap();
+        let labels: Vec<String> = get_completion(&flows)
+            .into_iter()
+            .map(|item| item.label)
+            .collect();
+
+        assert_eq!(labels, vec!["age".to_owned(), "user_name".to_owned()]);
+    }
+}
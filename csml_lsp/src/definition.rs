@@ -0,0 +1,172 @@
+use crate::documents::{get_word_bounds, interval_to_range, Documents};
+
+use csml_interpreter::data::ast::{Expr, Flow, InstructionScope};
+use lsp_types::{Location, Position, Range, Url};
+
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, PartialEq)]
+pub enum GotoTarget {
+    Step { step: String, flow: Option<String> },
+    Flow(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_step_range(flow: &Flow, step: &str) -> Option<Range> {
+    match flow
+        .flow_instructions
+        .get(&InstructionScope::StepScope(step.to_owned()))
+    {
+        Some(Expr::Scope { range, .. }) => {
+            let range = interval_to_range(range);
+            // only select the line of the step name, not the whole step
+            Some(Range::new(range.start, range.start))
+        }
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Step or flow targeted by the goto under the cursor:
+ * `goto step`, `goto step step`, `goto flow flow`, `goto step@flow` and `goto @flow`
+ */
+pub fn get_goto_target(line: &str, character: usize) -> Option<GotoTarget> {
+    let chars: Vec<char> = line.chars().collect();
+    let (start, end) = get_word_bounds(line, character)?;
+
+    let word: String = chars[start..end].iter().collect();
+    let before: String = chars[..start].iter().collect();
+    let after: String = chars[end..].iter().collect();
+
+    let goto_index = before.rfind("goto")?;
+    let target_prefix = before[goto_index + "goto".len()..].trim();
+
+    match target_prefix {
+        "" | "step" if word == "end" => None,
+        "" | "step" if word != "step" && word != "flow" => {
+            let flow = match after.strip_prefix('@') {
+                Some(flow) => flow
+                    .split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .next()
+                    .filter(|flow| !flow.is_empty())
+                    .map(|flow| flow.to_owned()),
+                None => None,
+            };
+
+            Some(GotoTarget::Step { step: word, flow })
+        }
+        "flow" => Some(GotoTarget::Flow(word)),
+        prefix if prefix.ends_with('@') && !prefix.starts_with('$') => Some(GotoTarget::Flow(word)),
+        _ => None,
+    }
+}
+
+/**
+ * Location of the step or flow targeted by the goto under the cursor
+ */
+pub fn get_definition(
+    documents: &Documents,
+    flows: &HashMap<String, Flow>,
+    uri: &Url,
+    position: Position,
+) -> Option<Location> {
+    let line = documents.get_line(uri, position)?;
+
+    let (flow_name, step) = match get_goto_target(line, position.character as usize)? {
+        GotoTarget::Step { step, flow } => {
+            let flow_name = flow.unwrap_or_else(|| Documents::flow_name(uri));
+            (flow_name, step)
+        }
+        GotoTarget::Flow(flow_name) => (flow_name, "start".to_owned()),
+    };
+
+    let target_uri = documents.get_uri(&flow_name)?;
+    let range = match flows.get(&flow_name) {
+        Some(flow) => get_step_range(flow, &step)?,
+        None => Range::default(),
+    };
+
+    Some(Location::new(target_uri.to_owned(), range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::validate_bot;
+
+    fn step(step: &str, flow: Option<&str>) -> Option<GotoTarget> {
+        Some(GotoTarget::Step {
+            step: step.to_owned(),
+            flow: flow.map(|flow| flow.to_owned()),
+        })
+    }
+
+    #[test]
+    fn ok_goto_target() {
+        assert_eq!(get_goto_target("    goto next", 10), step("next", None));
+        assert_eq!(
+            get_goto_target("    goto step next", 15),
+            step("next", None)
+        );
+        assert_eq!(
+            get_goto_target("    goto next@other", 10),
+            step("next", Some("other"))
+        );
+        assert_eq!(
+            get_goto_target("    goto next@other", 16),
+            Some(GotoTarget::Flow("other".to_owned()))
+        );
+        assert_eq!(
+            get_goto_target("    goto flow other", 16),
+            Some(GotoTarget::Flow("other".to_owned()))
+        );
+        assert_eq!(
+            get_goto_target("    goto @other", 12),
+            Some(GotoTarget::Flow("other".to_owned()))
+        );
+    }
+
+    #[test]
+    fn ko_goto_target() {
+        assert_eq!(get_goto_target("    goto end", 10), None);
+        assert_eq!(get_goto_target("    goto $next", 10), None);
+        assert_eq!(get_goto_target("    say next", 9), None);
+        assert_eq!(get_goto_target("    goto flow other", 7), None);
+    }
+
+    #[test]
+    fn ok_definition() {
+        let mut documents = Documents::default();
+        let default = Url::parse("file:///bot/Default.csml").unwrap();
+        let other = Url::parse("file:///bot/other.csml").unwrap();
+
+        documents
+            .files
+            .insert(default.to_owned(), "start:\n    goto next@other".to_owned());
+        documents.files.insert(
+            other.to_owned(),
+            "start:\n    goto next\n\nnext:\n    goto end".to_owned(),
+        );
+
+        let flows = validate_bot(&documents.get_bot()).flows.unwrap();
+
+        let location = get_definition(&documents, &flows, &default, Position::new(1, 10)).unwrap();
+        assert_eq!(location.uri, other);
+        assert_eq!(location.range.start, Position::new(3, 0));
+
+        let location = get_definition(&documents, &flows, &default, Position::new(1, 16)).unwrap();
+        assert_eq!(location.uri, other);
+        assert_eq!(location.range.start, Position::new(0, 0));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::documents::{interval_to_range, Documents};
+
+use csml_interpreter::data::{csml_result::CsmlResult, position::Position};
+use lsp_types::{Diagnostic, DiagnosticSeverity, PublishDiagnosticsParams};
+
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+fn gen_diagnostic(position: &Position, message: &str, severity: DiagnosticSeverity) -> Diagnostic {
+    Diagnostic {
+        range: interval_to_range(&position.interval),
+        severity: Some(severity),
+        source: Some("csml".to_owned()),
+        message: message.to_owned(),
+        ..Diagnostic::default()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Errors and warnings of the validation of the bot, for every opened file.
+ * A file without any diagnostic gets an empty list to clear the previous ones.
+ */
+pub fn get_diagnostics(
+    documents: &Documents,
+    result: &CsmlResult,
+) -> Vec<PublishDiagnosticsParams> {
+    let mut diagnostics: HashMap<String, Vec<Diagnostic>> = documents
+        .files
+        .keys()
+        .map(|uri| (Documents::flow_name(uri), vec![]))
+        .collect();
+
+    let errors = result.errors.iter().flatten().map(|error| {
+        (
+            &error.position,
+            gen_diagnostic(&error.position, &error.message, DiagnosticSeverity::ERROR),
+        )
+    });
+    let warnings = result.warnings.iter().flatten().map(|warning| {
+        (
+            &warning.position,
+            gen_diagnostic(
+                &warning.position,
+                &warning.message,
+                DiagnosticSeverity::WARNING,
+            ),
+        )
+    });
+
+    for (position, diagnostic) in errors.chain(warnings) {
+        if let Some(flow_diagnostics) = diagnostics.get_mut(&position.flow) {
+            flow_diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+        .into_iter()
+        .filter_map(|(flow_name, diagnostics)| {
+            let uri = documents.get_uri(&flow_name)?;
+            Some(PublishDiagnosticsParams::new(
+                uri.to_owned(),
+                diagnostics,
+                None,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::validate_bot;
+    use lsp_types::Url;
+
+    #[test]
+    fn ok_diagnostics() {
+        let mut documents = Documents::default();
+        let default = Url::parse("file:///bot/Default.csml").unwrap();
+        let other = Url::parse("file:///bot/other.csml").unwrap();
+
+        documents.files.insert(
+            default.to_owned(),
+            "start:\n    say \"hello\"\n    goto unknown".to_owned(),
+        );
+        documents
+            .files
+            .insert(other.to_owned(), "start:\n    goto end".to_owned());
+
+        let result = validate_bot(&documents.get_bot());
+        let diagnostics = get_diagnostics(&documents, &result);
+        assert_eq!(diagnostics.len(), 2);
+
+        let default_diagnostics = diagnostics
+            .iter()
+            .find(|params| params.uri == default)
+            .unwrap();
+        assert_eq!(default_diagnostics.diagnostics.len(), 1);
+
+        let diagnostic = &default_diagnostics.diagnostics[0];
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostic.range.start.line, 2);
+
+        let other_diagnostics = diagnostics
+            .iter()
+            .find(|params| params.uri == other)
+            .unwrap();
+        assert!(other_diagnostics.diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use csml_interpreter::data::{ast::Interval, csml_bot::CsmlBot, csml_flow::CsmlFlow};
+use csml_interpreter::load_components;
+use lsp_types::{Position, Range, Url};
+
+use std::collections::HashMap;
+
+pub const DEFAULT_FLOW: &str = "Default";
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * The CSML files opened in the editor, each file is a flow named after the file:
+ * flows/Default.csml is the flow 'Default'
+ */
+#[derive(Debug, Default)]
+pub struct Documents {
+    pub files: HashMap<Url, String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+impl Documents {
+    pub fn flow_name(uri: &Url) -> String {
+        let path = uri.path();
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+
+        match file_name.strip_suffix(".csml") {
+            Some(flow_name) => flow_name.to_owned(),
+            None => file_name.to_owned(),
+        }
+    }
+
+    pub fn get_uri(&self, flow_name: &str) -> Option<&Url> {
+        self.files
+            .keys()
+            .find(|uri| Self::flow_name(uri) == flow_name)
+    }
+
+    /**
+     * Bot made of all the opened flows, the default flow is 'Default' if it is opened
+     */
+    pub fn get_bot(&self) -> CsmlBot {
+        let flows: Vec<CsmlFlow> = self
+            .files
+            .iter()
+            .map(|(uri, content)| {
+                let flow_name = Self::flow_name(uri);
+                CsmlFlow::new(&flow_name, &flow_name, content, vec![])
+            })
+            .collect();
+
+        let default_flow = match flows.iter().find(|flow| flow.name == DEFAULT_FLOW) {
+            Some(flow) => flow.name.to_owned(),
+            None => flows
+                .first()
+                .map(|flow| flow.name.to_owned())
+                .unwrap_or_else(|| DEFAULT_FLOW.to_owned()),
+        };
+
+        CsmlBot::new(
+            "csml_lsp",
+            "csml_lsp",
+            None,
+            flows,
+            load_components().ok(),
+            None,
+            &default_flow,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /**
+     * Identifier (step, variable, function or component name) under the cursor
+     */
+    pub fn get_word(&self, uri: &Url, position: Position) -> Option<String> {
+        let line = self.get_line(uri, position)?;
+        let (start, end) = get_word_bounds(line, position.character as usize)?;
+
+        Some(line.chars().skip(start).take(end - start).collect())
+    }
+
+    pub fn get_line(&self, uri: &Url, position: Position) -> Option<&str> {
+        self.files.get(uri)?.lines().nth(position.line as usize)
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/**
+ * Start and end (in chars) of the identifier at the given column
+ */
+pub fn get_word_bounds(line: &str, character: usize) -> Option<(usize, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+
+    let mut start = character.min(chars.len());
+    while start > 0 && is_ident_char(chars[start - 1]) {
+        start -= 1;
+    }
+
+    let mut end = character.min(chars.len());
+    while end < chars.len() && is_ident_char(chars[end]) {
+        end += 1;
+    }
+
+    match start < end {
+        true => Some((start, end)),
+        false => None,
+    }
+}
+
+/**
+ * The intervals of the interpreter start at line 1 column 1, the LSP positions at 0
+ */
+pub fn interval_to_range(interval: &Interval) -> Range {
+    let start = Position::new(
+        interval.start_line.saturating_sub(1),
+        interval.start_column.saturating_sub(1),
+    );
+
+    let end = match (interval.end_line, interval.end_column) {
+        (Some(line), Some(column)) => {
+            Position::new(line.saturating_sub(1), column.saturating_sub(1))
+        }
+        _ => Position::new(start.line, start.character + 1),
+    };
+
+    Range::new(start, end)
+}
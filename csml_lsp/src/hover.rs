@@ -0,0 +1,127 @@
+use csml_interpreter::data::tokens::{BUILT_IN, COMPONENT};
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind};
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_builtin_doc(name: &str) -> Option<&'static str> {
+    let doc = match name {
+        "OneOf" => "`OneOf(value, ...)`: returns one of its arguments at random",
+        "Shuffle" => "`Shuffle(array)`: returns a copy of the array in a random order",
+        "Length" => "`Length(value)`: number of elements of an array or characters of a string",
+        "Find" => "`Find(value, in = string)`: checks if a string contains a value",
+        "Random" => "`Random()`: random float between 0 and 1",
+        "Floor" => "`Floor(number)`: largest integer less than or equal to the number",
+        "Fn" => "`Fn(\"name\", args...)`: calls an App (deprecated, use `App`)",
+        "App" => "`App(\"name\", args...)`: calls an App of the bot and returns its result",
+        "HTTP" => "`HTTP(url)`: builds an HTTP request, send it with `.get()`, `.post(body)`, ...",
+        "Object" => "`Object(key = value)`: builds an object (deprecated, use `{key: value}`)",
+        "Debug" => "`Debug(value)`: prints a value in the debug messages of the conversation",
+        "UUID" => "`UUID()`: generates a random v4 UUID",
+        "Base64" => "`Base64(string)`: `.encode()` and `.decode()` base64 strings",
+        "Hex" => "`Hex(string)`: `.encode()` and `.decode()` hexadecimal strings",
+        "JWT" => "`JWT(claims)`: `.sign(algo, secret)`, `.decode(secret)` and `.verify(secret)` tokens",
+        "Crypto" => "`Crypto(string)`: hashes (`.create_hash(algo)`) and HMACs (`.create_hmac(algo, key)`)",
+        "Time" => "`Time()`: current date and time, `.at(...)`, `.add(seconds)`, `.format(fmt)`, `.parse(string)`, ...",
+        "SMTP" => "`SMTP(host)`: sends emails, `.auth(user, password)`, `.send(email)`",
+        "Exists" => "`Exists(\"variable\")`: checks if a variable or memory exists",
+        "Schedule" => "`Schedule(event, delay)`: sends an event to the current client after a delay in seconds",
+        "Handover" => "`Handover(reason)`: pauses the conversation until a human agent resumes it",
+        "_t" => "`_t(\"key\")`: translation of a key in the locale of the user",
+        _ => return None,
+    };
+
+    Some(doc)
+}
+
+fn get_component_doc(name: &str, component: &serde_json::Value) -> String {
+    let params: Vec<String> = component["params"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|param| param.as_object())
+        .flat_map(|param| param.iter())
+        .map(|(param_name, info)| {
+            let required = match info["required"].as_bool() {
+                Some(true) => "",
+                _ => "?",
+            };
+
+            match info["type"].as_str() {
+                Some(param_type) => format!("{}{}: {}", param_name, required, param_type),
+                None => format!("{}{}", param_name, required),
+            }
+        })
+        .collect();
+
+    format!("`{}({})`: {} component", name, params.join(", "), name)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTION
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Documentation of the builtin or component under the cursor
+ */
+pub fn get_hover(
+    word: &str,
+    components: &Option<serde_json::Map<String, serde_json::Value>>,
+) -> Option<Hover> {
+    let doc = match components
+        .as_ref()
+        .and_then(|components| components.get(word))
+    {
+        Some(component) => get_component_doc(word, component),
+        None if word == COMPONENT => {
+            "`Component.name(args)`: calls a custom component of the bot".to_owned()
+        }
+        None if BUILT_IN.contains(&word) => get_builtin_doc(word)?.to_owned(),
+        None => return None,
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: doc,
+        }),
+        range: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::load_components;
+
+    fn get_value(hover: Hover) -> String {
+        match hover.contents {
+            HoverContents::Markup(content) => content.value,
+            _ => panic!("hover should be markdown"),
+        }
+    }
+
+    #[test]
+    fn ok_hover() {
+        let components = load_components().ok();
+
+        let button = get_value(get_hover("Button", &components).unwrap());
+        assert_eq!(
+            button,
+            "`Button(title: String, payload?: String, accepts?: Array)`: Button component"
+        );
+
+        let uuid = get_value(get_hover("UUID", &components).unwrap());
+        assert!(uuid.starts_with("`UUID()`"));
+
+        assert!(get_hover("user_name", &components).is_none());
+    }
+
+    #[test]
+    fn ok_hover_all_builtins() {
+        for builtin in BUILT_IN.iter() {
+            assert!(get_hover(builtin, &None).is_some(), "{}", builtin);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+mod completion;
+mod definition;
+mod diagnostics;
+mod documents;
+mod hover;
+
+use documents::Documents;
+
+use csml_interpreter::{data::ast::Flow, load_components, validate_bot};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as LspRequest},
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, HoverParams, HoverProviderCapability, OneOf, PublishDiagnosticsParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+};
+
+use std::collections::HashMap;
+use std::error::Error;
+
+type LspResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+////////////////////////////////////////////////////////////////////////////////
+// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+struct Server {
+    connection: Connection,
+    documents: Documents,
+    // flows parsed during the last validation of the opened files
+    flows: HashMap<String, Flow>,
+    components: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+impl Server {
+    fn run(&mut self) -> LspResult<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+
+                    let response = self.handle_request(request)?;
+                    self.connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> LspResult<Response> {
+        match request.method.as_str() {
+            GotoDefinition::METHOD => {
+                let (id, params) =
+                    request.extract::<GotoDefinitionParams>(GotoDefinition::METHOD)?;
+                let position = params.text_document_position_params;
+
+                let location = definition::get_definition(
+                    &self.documents,
+                    &self.flows,
+                    &position.text_document.uri,
+                    position.position,
+                );
+
+                Ok(Response::new_ok(
+                    id,
+                    location.map(GotoDefinitionResponse::Scalar),
+                ))
+            }
+            HoverRequest::METHOD => {
+                let (id, params) = request.extract::<HoverParams>(HoverRequest::METHOD)?;
+                let position = params.text_document_position_params;
+
+                let hover = self
+                    .documents
+                    .get_word(&position.text_document.uri, position.position)
+                    .and_then(|word| hover::get_hover(&word, &self.components));
+
+                Ok(Response::new_ok(id, hover))
+            }
+            Completion::METHOD => {
+                let (id, _params) = request.extract::<CompletionParams>(Completion::METHOD)?;
+                let items = completion::get_completion(&self.flows);
+
+                Ok(Response::new_ok(id, CompletionResponse::Array(items)))
+            }
+            method => Ok(Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request: {}", method),
+            )),
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> LspResult<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification
+                    .extract::<DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)?;
+
+                self.documents
+                    .files
+                    .insert(params.text_document.uri, params.text_document.text);
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification
+                    .extract::<DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)?;
+
+                // the documents are synchronized in full, the last change is the new content
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents
+                        .files
+                        .insert(params.text_document.uri, change.text);
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = notification
+                    .extract::<DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)?;
+
+                self.documents.files.remove(&params.text_document.uri);
+                self.publish_diagnostics(PublishDiagnosticsParams::new(
+                    params.text_document.uri,
+                    vec![],
+                    None,
+                ))?;
+            }
+            _ => return Ok(()),
+        }
+
+        self.validate()
+    }
+
+    /**
+     * Validate all the opened files as a single bot and publish its errors and warnings
+     */
+    fn validate(&mut self) -> LspResult<()> {
+        let result = validate_bot(&self.documents.get_bot());
+
+        for params in diagnostics::get_diagnostics(&self.documents, &result) {
+            self.publish_diagnostics(params)?;
+        }
+
+        self.flows = result.flows.unwrap_or_default();
+
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, params: PublishDiagnosticsParams) -> LspResult<()> {
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        self.connection
+            .sender
+            .send(Message::Notification(notification))?;
+
+        Ok(())
+    }
+}
+
+fn main() -> LspResult<()> {
+    // stdout is used by the protocol, the logs of the interpreter are written to stderr
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..ServerCapabilities::default()
+    })?;
+    connection.initialize(capabilities)?;
+
+    let mut server = Server {
+        connection,
+        documents: Documents::default(),
+        flows: HashMap::new(),
+        components: load_components().ok(),
+    };
+    server.run()?;
+
+    // the connection must be dropped before joining the io threads
+    drop(server);
+    io_threads.join()?;
+
+    Ok(())
+}
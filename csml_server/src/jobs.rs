@@ -0,0 +1,187 @@
+/**
+ * Background jobs of the server: each job runs every few seconds or hours in a tokio task.
+ *
+ * When several servers share the same database, each run of a job is only made by one of
+ * them: the servers try to lock the job in the "jobs" table of the database and the server
+ * getting the lock is the leader of the job until its next run. A lock is kept at most
+ * ENGINE_JOBS_LEASE seconds (default 600) when its server stops during a run.
+ *
+ * - scheduled_events: sends the events scheduled in the flows with the Schedule() builtin,
+ *   every ENGINE_SCHEDULER_INTERVAL seconds (default 10), unless ENGINE_SCHEDULER_ENABLED=false
+ * - archive: exports the old closed conversations to the object storage every
+ *   ENGINE_ARCHIVE_INTERVAL seconds (default 3600), when ARCHIVE_S3_BUCKET is set,
+ *   unless ENGINE_ARCHIVE_ENABLED=false
+ * - expired_data: deletes the data whose TTL expired every ENGINE_EXPIRED_DATA_INTERVAL
+ *   seconds (default 3600), when ENGINE_EXPIRED_DATA_ENABLED=true
+ * - outbox: sends again the messages that their callback_url did not accept, every
+ *   ENGINE_OUTBOX_INTERVAL seconds (default 10), unless ENGINE_OUTBOX_ENABLED=false
+ *
+ * The servers that should never run the jobs can disable them with ENGINE_JOBS_ENABLED=false.
+ * The jobs stop claiming runs once the server is shutting down.
+ * The servers are told apart with ENGINE_INSTANCE_ID (default: hostname and process id).
+ */
+use actix_web::web;
+use csml_engine::{data::EngineError, get_config};
+use std::{env, process, sync::Arc, time::Duration};
+
+// maximum number of scheduled events or outbox messages sent at each run
+const BATCH_SIZE: i64 = 100;
+// the servers check if the runs of the jobs are due at least this often (in seconds)
+const POLL_INTERVAL: u64 = 10;
+
+struct Job {
+    name: &'static str,
+    // seconds between the end of a run and the start of the next one
+    interval: u64,
+    run: fn() -> Result<(), EngineError>,
+}
+
+fn is_enabled(key: &str, default: bool) -> bool {
+    get_config().boolean(key).unwrap_or(default)
+}
+
+fn get_interval(key: &str, default: u64) -> u64 {
+    get_config().integer(key).unwrap_or(default)
+}
+
+fn get_instance_id() -> String {
+    if let Some(instance_id) = get_config().text("server.instance_id") {
+        return instance_id;
+    }
+
+    let hostname = env::var("HOSTNAME").unwrap_or_else(|_| "csml_server".to_owned());
+    format!("{}-{}", hostname, process::id())
+}
+
+fn run_scheduled_events() -> Result<(), EngineError> {
+    csml_engine::run_scheduled_events(BATCH_SIZE)?;
+
+    Ok(())
+}
+
+fn run_outbox() -> Result<(), EngineError> {
+    csml_engine::deliver_outbox_messages(BATCH_SIZE)?;
+
+    Ok(())
+}
+
+fn run_archive() -> Result<(), EngineError> {
+    let archived = csml_engine::archive_conversations()?;
+    if archived > 0 {
+        println!("{} conversations archived", archived);
+    }
+
+    Ok(())
+}
+
+fn get_jobs() -> Vec<Job> {
+    let mut jobs = vec![];
+
+    if is_enabled("jobs.scheduler.enabled", true) {
+        jobs.push(Job {
+            name: "scheduled_events",
+            interval: get_interval("jobs.scheduler.interval", 10),
+            run: run_scheduled_events,
+        });
+    }
+
+    if is_enabled("jobs.outbox.enabled", true) {
+        jobs.push(Job {
+            name: "outbox",
+            interval: get_interval("jobs.outbox.interval", 10),
+            run: run_outbox,
+        });
+    }
+
+    if is_enabled("jobs.archive.enabled", true) && get_config().is_set("archive.s3_bucket") {
+        jobs.push(Job {
+            name: "archive",
+            interval: get_interval("jobs.archive.interval", 3600),
+            run: run_archive,
+        });
+    }
+
+    if is_enabled("jobs.expired_data.enabled", false) {
+        jobs.push(Job {
+            name: "expired_data",
+            interval: get_interval("jobs.expired_data.interval", 3600),
+            run: csml_engine::delete_expired_data,
+        });
+    }
+
+    jobs
+}
+
+/**
+ * Claim the job and run it if no other server is running it.
+ * Returns false when the job was not due or is run by another server.
+ */
+fn run_job(job: &Job, owner: &str, lease: i64) -> Result<bool, EngineError> {
+    if !csml_engine::claim_job(job.name, owner, lease)? {
+        return Ok(false);
+    }
+
+    let error = (job.run)().err().map(|err| format!("{:?}", err));
+    if let Some(error) = &error {
+        eprintln!("JobError: {} {}", job.name, error);
+    }
+
+    csml_engine::complete_job(job.name, owner, job.interval as i64, error.as_deref())?;
+
+    Ok(true)
+}
+
+pub fn start() {
+    if !is_enabled("jobs.enabled", true) {
+        return;
+    }
+
+    let owner = get_instance_id();
+    let lease = get_interval("jobs.lease", 600) as i64;
+
+    for job in get_jobs() {
+        let owner = owner.clone();
+        let poll_interval = Duration::from_secs(job.interval.clamp(1, POLL_INTERVAL));
+
+        actix_rt::spawn(async move {
+            let job = Arc::new(job);
+
+            // no new run is claimed once the server is shutting down
+            while !csml_engine::is_shutting_down() {
+                let (job_ref, owner_ref) = (job.clone(), owner.clone());
+                match web::block(move || run_job(&job_ref, &owner_ref, lease)).await {
+                    Ok(Ok(_)) => (),
+                    Ok(Err(err)) => eprintln!("JobError: {} {:?}", job.name, err),
+                    Err(err) => eprintln!("JobError: {} {:?}", job.name, err),
+                }
+
+                actix_rt::time::sleep(poll_interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs_config() {
+        env::set_var("ENGINE_SCHEDULER_INTERVAL", "30");
+        env::remove_var("ENGINE_EXPIRED_DATA_ENABLED");
+
+        let jobs = get_jobs();
+        let scheduler = jobs
+            .iter()
+            .find(|job| job.name == "scheduled_events")
+            .unwrap();
+        assert_eq!(scheduler.interval, 30);
+        assert!(jobs.iter().all(|job| job.name != "expired_data"));
+
+        env::set_var("ENGINE_EXPIRED_DATA_ENABLED", "true");
+        assert!(get_jobs().iter().any(|job| job.name == "expired_data"));
+
+        env::remove_var("ENGINE_SCHEDULER_INTERVAL");
+        env::remove_var("ENGINE_EXPIRED_DATA_ENABLED");
+    }
+}
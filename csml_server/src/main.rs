@@ -1,63 +1,199 @@
 use actix_cors::Cors;
 use actix_files as fs;
 use actix_web::{http::header, middleware, web, App, HttpServer};
-use csml_engine::make_migrations;
-use csml_interpreter::csml_logs::init_logger;
+use csml_engine::{
+    create_dynamodb_table, data::EngineError, get_config, load_config, make_migrations,
+    validate_config, validate_environment,
+};
+use csml_interpreter::csml_logs::init_logger_with_level;
 
+mod jobs;
+mod request_id;
 mod routes;
+mod shutdown;
 
 const MAX_BODY_SIZE: usize = 8_388_608; // 8MB
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
-    init_logger();
+    // csml_server [--config path/to/csml.toml] [--check] [--create-dynamodb-table],
+    // the env vars override the file
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|index| args.get(index + 1));
 
-    let server_port: String = match std::env::var("ENGINE_SERVER_PORT") {
-        Ok(val) => val,
-        Err(_) => "5000".to_owned(),
+    match load_config(config_path.map(|path| path.as_str())) {
+        Ok(Some(path)) => println!("Configuration loaded from {}", path),
+        Ok(None) => (),
+        Err(err) => {
+            match err {
+                EngineError::Format(errors) => {
+                    errors.lines().for_each(|err| eprintln!("Configuration ERROR: {}", err))
+                }
+                err => eprintln!("Configuration ERROR: {:?}", err),
+            }
+            std::process::exit(1);
+        }
+    }
+
+    // csml_server --check: print the diagnostics of the environment and exit
+    if args.iter().any(|arg| arg == "--check") {
+        let report = validate_environment();
+        for check in report.checks.iter() {
+            println!(
+                "[{}] {}: {}",
+                check.status.to_uppercase(),
+                check.name,
+                check.message
+            );
+        }
+
+        std::process::exit(match report.is_ok() {
+            true => 0,
+            false => 1,
+        });
+    }
+
+    // csml_server --create-dynamodb-table: create the table of the engine (if needed) and exit
+    if args.iter().any(|arg| arg == "--create-dynamodb-table") {
+        match create_dynamodb_table() {
+            Ok(true) => println!("DynamoDB table created"),
+            Ok(false) => println!("DynamoDB table already exists"),
+            Err(err) => {
+                eprintln!("DynamoDB ERROR: {:?}", err);
+                std::process::exit(1);
+            }
+        }
+        std::process::exit(0);
+    }
+
+    let errors = validate_config();
+    if !errors.is_empty() {
+        for err in errors.iter() {
+            eprintln!("Configuration ERROR: {}", err);
+        }
+        std::process::exit(1);
+    }
+
+    let config = get_config();
+    init_logger_with_level(config.text("log.level").as_deref());
+
+    let server_port: String = match config.text("server.port") {
+        Some(val) => val,
+        None => "5000".to_owned(),
     };
     println!("CSML Server listening on port {}", server_port);
 
-    // make migrations for PgSQL and do nothing for MongoDB and DynamoDB
+    // make migrations for PgSQL, SQLite and MongoDB and do nothing for DynamoDB
     match make_migrations() {
         Ok(_) => (),
-        Err(err) => panic!("PgSQL Migration ERROR: {:?}", err),
+        Err(err) => panic!("Migration ERROR: {:?}", err),
     };
 
-    HttpServer::new(|| {
+    // scheduled events, archival and expired data jobs, each run by only one server
+    jobs::start();
+
+    let server = HttpServer::new(|| {
         App::new()
             .wrap(
                 Cors::default()
                     .send_wildcard()
-                    .allowed_methods(vec!["GET", "POST", "DELETE"])
+                    .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
                     .allowed_headers(vec![
                         header::AUTHORIZATION,
                         header::ACCEPT,
                         header::CONTENT_TYPE,
+                        header::HeaderName::from_static("x-request-id"),
                     ])
+                    .expose_headers(vec![header::HeaderName::from_static("x-request-id")])
                     .max_age(86_400), //24h
             )
-            .wrap(middleware::Logger::default())
-            .app_data(web::JsonConfig::default().limit(MAX_BODY_SIZE))
+            .wrap(request_id::RequestIdHeader)
+            // the default format of the access logs, with the X-Request-Id of the response
+            .wrap(middleware::Logger::new(
+                "%a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T %{X-Request-Id}o",
+            ))
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(MAX_BODY_SIZE)
+                    .error_handler(routes::tools::json_error_handler),
+            )
+            .app_data(web::PayloadConfig::new(MAX_BODY_SIZE))
             .service(fs::Files::new("/static", "./static").use_last_modified(true))
             .service(routes::index::home)
             .service(routes::validate::handler)
+            .service(routes::format::handler)
+            .service(routes::lint::handler)
             .service(routes::status::get_status)
             .service(routes::run::handler)
+            .service(routes::run::batch_handler)
+            .service(routes::run::get_run_messages)
             .service(routes::sns::handler)
             .service(routes::bot_versions::make_bot_fold)
+            .service(routes::bot_versions::import_bot)
             .service(routes::bot_versions::add_bot_version)
             .service(routes::bot_versions::get_bot_version)
+            .service(routes::bot_versions::export_bot)
+            .service(routes::bot_versions::get_bot_graph)
+            .service(routes::bot_versions::get_bot_step_latency)
             .service(routes::bot_versions::get_bot_latest_version)
             .service(routes::bot_versions::get_bot_latest_versions)
             .service(routes::bot_versions::delete_bot_version)
             .service(routes::bot_versions::delete_bot_versions)
+            .service(routes::rollout::get_rollout)
+            .service(routes::rollout::set_rollout)
+            .service(routes::rollout::delete_rollout)
+            .service(routes::globals::get_globals)
+            .service(routes::globals::set_global)
+            .service(routes::globals::delete_global)
+            .service(routes::bot_config::get_config)
+            .service(routes::bot_config::set_config)
+            .service(routes::feature_flags::get_flags)
+            .service(routes::feature_flags::set_flag)
+            .service(routes::feature_flags::delete_flag)
+            .service(routes::bot_components::get_components)
+            .service(routes::bot_components::set_components)
+            .service(routes::bot_components::delete_component)
+            .service(routes::knowledge_base::ingest_document)
+            .service(routes::knowledge_base::search_knowledge_base)
+            .service(routes::knowledge_base::delete_document)
+            .service(routes::environments::get_environment)
+            .service(routes::environments::set_environment)
+            .service(routes::environments::delete_environment)
+            .service(routes::broadcasts::create_broadcast)
+            .service(routes::broadcasts::get_broadcast_status)
+            .service(routes::usage::get_usage)
+            .service(routes::search::search)
+            .service(routes::webhooks::create_webhook)
+            .service(routes::webhooks::get_webhooks)
+            .service(routes::webhooks::delete_webhook)
+            .service(routes::webhooks::get_webhook_deliveries)
+            .service(routes::errors::get_bot_errors)
             .service(routes::conversations::get_open)
             .service(routes::conversations::close_user_conversations)
+            .service(routes::conversations::handover_conversation)
+            .service(routes::conversations::resume_agent_conversation)
             .service(routes::conversations::get_client_conversations)
+            .service(routes::conversations::get_conversation_error)
+            .service(routes::conversations::get_conversation_warnings)
+            .service(routes::conversations::get_conversation_state)
+            .service(routes::conversations::replay_conversation)
+            .service(routes::conversations::fork_conversation)
+            .service(routes::conversations::get_archived_conversation)
+            .service(routes::conversations::get_conversation_transcript)
+            .service(routes::conversations::get_conversation_metadata)
+            .service(routes::conversations::update_conversation_metadata)
+            .service(routes::debug::start_debug_session)
+            .service(routes::debug::inspect_debug_session)
+            .service(routes::debug::stop_debug_session)
+            .service(routes::debug::continue_debug_session)
+            .service(routes::debug::step_over_debug_session)
             .service(routes::memories::create_client_memory)
             .service(routes::memories::get_memories)
             .service(routes::memories::get_memory)
+            .service(routes::memories::get_memory_history)
             .service(routes::memories::delete_memories)
             .service(routes::memories::delete_memory)
             .service(routes::messages::get_client_messages)
@@ -67,6 +203,15 @@ async fn main() -> std::io::Result<()> {
             .service(routes::data::delete_client)
     })
     .bind(format!("0.0.0.0:{}", server_port))?
-    .run()
-    .await
+    .shutdown_timeout(shutdown::get_timeout().as_secs())
+    .disable_signals()
+    .run();
+
+    // stop accepting runs on SIGTERM/SIGINT, then drain the requests and the engine
+    shutdown::handle_signals(server.handle());
+    server.await?;
+
+    shutdown::drain();
+
+    Ok(())
 }
@@ -0,0 +1,67 @@
+/**
+ * X-Request-Id of the requests: the id sent by the caller, or a new uuid. It is returned in the
+ * X-Request-Id header of the response, given to the engine with the runs (saved with their
+ * messages and added to their logs) and added to the error responses, so that a failing
+ * conversation turn can be traced across systems.
+ */
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+
+use crate::routes::tools::{read_request_id, RequestId};
+
+pub struct RequestIdHeader;
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdHeader
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware { service }))
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = read_request_id(req.headers());
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        let res = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = res.await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static("x-request-id"), value);
+            }
+
+            Ok(res)
+        })
+    }
+}
@@ -1,8 +1,11 @@
 pub mod index;
 pub mod validate;
+pub mod format;
+pub mod lint;
 pub mod run;
 pub mod sns;
 pub mod conversations;
+pub mod debug;
 pub mod data;
 pub mod memories;
 pub mod messages;
@@ -10,5 +13,17 @@ pub mod state;
 pub mod status;
 
 pub mod bot_versions;
+pub mod rollout;
+pub mod globals;
+pub mod bot_components;
+pub mod bot_config;
+pub mod feature_flags;
+pub mod knowledge_base;
+pub mod environments;
+pub mod broadcasts;
+pub mod usage;
+pub mod search;
+pub mod webhooks;
+pub mod errors;
 
 pub mod tools;
@@ -0,0 +1,121 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{delete, get, put, web, HttpResponse};
+use csml_engine::{delete_bot_component, get_bot_components, set_bot_components};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdPath {
+    bot_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComponentNamePath {
+    bot_id: String,
+    name: String,
+}
+
+/**
+ * Get the custom components registered on a given bot
+ *
+ * {"statusCode": 200,"body": {"name": COMPONENT}}
+ */
+#[get("/bots/{bot_id}/components")]
+pub async fn get_components(
+    path: web::Path<BotIdPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_bot_components(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(components) => HttpResponse::Ok().json(components),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Register custom components on a given bot, merged over the custom components of its version
+ * when it runs. The conflicts are the components of the last version of the bot they replace.
+ *
+ * {"statusCode": 200,"body": {"conflicts": [String]}}
+ */
+#[put("/bots/{bot_id}/components")]
+pub async fn set_components(
+    path: web::Path<BotIdPath>,
+    body: web::Json<serde_json::Value>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let components = body.into_inner();
+    let res = thread::spawn(move || set_bot_components(&bot_id, components))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(conflicts) => HttpResponse::Ok().json(serde_json::json!({ "conflicts": conflicts })),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Deregister a custom component of a given bot
+ *
+ * {"statusCode": 204}
+ */
+#[delete("/bots/{bot_id}/components/{name}")]
+pub async fn delete_component(
+    path: web::Path<ComponentNamePath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let name = path.name.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || delete_bot_component(&bot_id, &name))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_set_native_component() {
+        let mut app = test::init_service(App::new().service(set_components)).await;
+
+        let resp = test::TestRequest::put()
+            .uri("/bots/bot_id/components")
+            .set_json(&serde_json::json!({"Button": {"params": []}}))
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
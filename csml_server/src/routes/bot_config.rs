@@ -0,0 +1,83 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{get, put, web, HttpResponse};
+use csml_engine::{get_bot_config, set_bot_config};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdPath {
+    bot_id: String,
+}
+
+/**
+ * Get the configuration of a given bot
+ *
+ * {"statusCode": 200,"body": {"key": Value}}
+ */
+#[get("/bots/{bot_id}/config")]
+pub async fn get_config(path: web::Path<BotIdPath>, req: actix_web::HttpRequest) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_bot_config(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(config) => HttpResponse::Ok().json(config),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Replace the configuration of a given bot, merged at run time over its env (_env.<key>)
+ * and used as the defaults of the metadata of its events (_metadata.<key>)
+ *
+ * {"statusCode": 200,"body": {"key": Value}}
+ */
+#[put("/bots/{bot_id}/config")]
+pub async fn set_config(
+    path: web::Path<BotIdPath>,
+    body: web::Json<serde_json::Value>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let config = body.into_inner();
+    let res = thread::spawn(move || set_bot_config(&bot_id, config))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(config) => HttpResponse::Ok().json(config),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_set_config_invalid_body() {
+        let mut app = test::init_service(App::new().service(set_config)).await;
+
+        let resp = test::TestRequest::put()
+            .uri("/bots/bot_id/config")
+            .set_json(&serde_json::json!(["api_url"]))
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
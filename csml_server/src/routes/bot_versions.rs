@@ -1,8 +1,12 @@
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{
+    engine_error_response, get_tenant_bot_id, get_tenant_id, validate_api_key, validate_bot_id,
+};
 use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::data::{BotBundle, BotGraphFormat};
 use csml_engine::{
-    create_bot_version, delete_all_bot_versions, delete_bot_version_id, fold_bot,
-    get_bot_by_version_id, get_bot_versions, get_last_bot_version,
+    create_bot_version, delete_all_bot_versions, delete_bot_version_id, export_bot_version,
+    fold_bot, get_bot_by_version_id, get_bot_version_graph, get_bot_versions, get_last_bot_version,
+    get_step_latency, import_bot_bundle,
 };
 use csml_interpreter::data::csml_bot::CsmlBot;
 use serde::{Deserialize, Serialize};
@@ -27,10 +31,7 @@ pub async fn make_bot_fold(body: web::Json<CsmlBot>, req: actix_web::HttpRequest
 
     match res {
         Ok(flow) => HttpResponse::Created().json(serde_json::json!({ "flow": flow })),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -45,7 +46,12 @@ pub async fn add_bot_version(
     body: web::Json<CsmlBot>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let bot = body.to_owned();
+    let mut bot = body.to_owned();
+    if let Some(value) = validate_bot_id(&bot.id) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+    bot.id = get_tenant_bot_id(&req, &bot.id);
 
     if let Some(value) = validate_api_key(&req) {
         eprintln!("AuthError: {:?}", value);
@@ -58,10 +64,7 @@ pub async fn add_bot_version(
 
     match res {
         Ok(data) => HttpResponse::Created().json(serde_json::json!(data)),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -96,7 +99,7 @@ pub async fn get_bot_latest_version(
     path: web::Path<BotIdPath>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let bot_id = path.bot_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
 
     if let Some(value) = validate_api_key(&req) {
         eprintln!("AuthError: {:?}", value);
@@ -110,10 +113,7 @@ pub async fn get_bot_latest_version(
     match res {
         Ok(Some(bot_version)) => HttpResponse::Ok().json(bot_version.flatten()),
         Ok(None) => HttpResponse::NotFound().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -127,7 +127,7 @@ pub async fn delete_bot_versions(
     path: web::Path<BotIdPath>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let bot_id = path.bot_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
 
     if let Some(value) = validate_api_key(&req) {
         eprintln!("AuthError: {:?}", value);
@@ -140,10 +140,7 @@ pub async fn delete_bot_versions(
 
     match res {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -169,7 +166,7 @@ pub async fn get_bot_latest_versions(
     query: web::Query<GetBotVersionsQuery>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let bot_id = path.bot_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
     let limit = query.limit.to_owned();
     let pagination_key = match query.pagination_key.to_owned() {
         Some(pagination_key) if pagination_key == "" => None,
@@ -188,10 +185,7 @@ pub async fn get_bot_latest_versions(
 
     match res {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -221,7 +215,7 @@ pub async fn get_bot_version(
     path: web::Path<BotVersionPath>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let bot_id = path.bot_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
     let version_id = path.version_id.to_owned();
 
     if let Some(value) = validate_api_key(&req) {
@@ -236,10 +230,140 @@ pub async fn get_bot_version(
     match res {
         Ok(Some(bot_version)) => HttpResponse::Ok().json(bot_version.flatten()),
         Ok(None) => HttpResponse::NotFound().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/*
+ * Export a specific version of a bot as a signed bundle
+ *
+ * {"statusCode": 200,"body": Bundle}
+ *
+ * BUNDLE = {
+ *  "version_id": String,
+ *  "engine_version": String,
+ *  "exported_at": String,
+ *  "bot": Bot,
+ *  "signature": String
+ * }
+ */
+#[get("/bots/{bot_id}/versions/{version_id}/export")]
+pub async fn export_bot(
+    path: web::Path<BotVersionPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let version_id = path.version_id.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || export_bot_version(&bot_id, &version_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(bundle)) => HttpResponse::Ok().json(bundle),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotGraphQuery {
+    format: Option<BotGraphFormat>,
+}
+
+/*
+ * Graph of the steps of a bot version and of the goto transitions between them,
+ * in Mermaid (default) or DOT format: ?format=mermaid|dot
+ *
+ * {"statusCode": 200,"body": {"format": String, "graph": String}}
+ */
+#[get("/bots/{bot_id}/versions/{version_id}/graph")]
+pub async fn get_bot_graph(
+    path: web::Path<BotVersionPath>,
+    query: web::Query<BotGraphQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let version_id = path.version_id.to_owned();
+    let format = query.format.unwrap_or(BotGraphFormat::Mermaid);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_bot_version_graph(&bot_id, &version_id, format))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(graph)) => HttpResponse::Ok().json(serde_json::json!({
+            "format": format,
+            "graph": graph,
+        })),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/*
+ * Latency of the steps executed with a bot version, slowest first, to find the steps waiting
+ * for slow external calls. The bots run without version have the "draft" version_id.
+ *
+ * {"statusCode": 200,"body": {"steps": [{"flow": String, "step": String, "count": Integer,
+ * "p50_ms": Integer, "p95_ms": Integer, "max_ms": Integer}]}}
+ */
+#[get("/bots/{bot_id}/versions/{version_id}/step_latency")]
+pub async fn get_bot_step_latency(
+    path: web::Path<BotVersionPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let version_id = path.version_id.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_step_latency(&bot_id, &version_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(steps) => HttpResponse::Ok().json(serde_json::json!({ "steps": steps })),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * import a bot bundle exported by another engine instance as a new bot version
+ *
+ * {"statusCode": 201,"body": {"version_id": String, "engine_version": String, "source_version_id": String, "source_engine_version": String, "exported_at": String} }
+ *
+ */
+#[post("/bots/import")]
+pub async fn import_bot(body: web::Json<BotBundle>, req: actix_web::HttpRequest) -> HttpResponse {
+    let bundle = body.to_owned();
+    let tenant_id = get_tenant_id(&req);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || import_bot_bundle(bundle, tenant_id.as_deref()))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(data) => HttpResponse::Created().json(serde_json::json!(data)),
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -253,7 +377,7 @@ pub async fn delete_bot_version(
     path: web::Path<BotVersionPath>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let bot_id = path.bot_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
     let version_id = path.version_id.to_owned();
 
     if let Some(value) = validate_api_key(&req) {
@@ -267,10 +391,7 @@ pub async fn delete_bot_version(
 
     match res {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -334,6 +455,42 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::CREATED);
     }
 
+    #[actix_rt::test]
+    async fn test_add_invalid_bot_version() {
+        let mut app = test::init_service(App::new().service(add_bot_version)).await;
+
+        let resp = test::TestRequest::post()
+            .uri(&format!("/bots"))
+            .set_json(&serde_json::json!({
+                "id": "bot_id",
+                "name": "test",
+                "flows": [
+                  {
+                    "id": "Default",
+                    "name": "Default",
+                    "content": "start: say (",
+                    "commands": [],
+                  }
+                ],
+                "default_flow": "Default",
+            }))
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/problem+json"
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(body["code"], "invalid_bot");
+        assert_eq!(body["status"], 400);
+        assert_eq!(body["instance"], "/bots");
+        assert!(body["request_id"].is_string());
+        assert_eq!(body["errors"][0]["position"]["flow"], "Default");
+    }
+
     #[actix_rt::test]
     async fn test_get_bot_latest_versions() {
         let mut app = test::init_service(App::new().service(get_bot_latest_versions)).await;
@@ -348,6 +505,21 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn test_get_bot_step_latency() {
+        let mut app = test::init_service(App::new().service(get_bot_step_latency)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/bots/botid/versions/unknown/step_latency")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value =
+            serde_json::from_slice(&resp.into_body().try_into_bytes().unwrap()).unwrap();
+        assert_eq!(body["steps"], serde_json::json!([]));
+    }
+
     #[actix_rt::test]
     async fn test_get_bot_version() {
         let mut app =
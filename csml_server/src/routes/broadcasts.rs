@@ -0,0 +1,96 @@
+use crate::routes::tools::{
+    engine_error_response, get_tenant_bot_id, set_client_tenant, validate_api_key,
+    validate_bot_id,
+};
+use actix_web::{get, post, web, HttpResponse};
+use csml_engine::data::BroadcastRequest;
+use csml_engine::{get_broadcast, start_broadcast};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastIdPath {
+    broadcast_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdQuery {
+    bot_id: String,
+}
+
+/**
+ * Trigger a flow for a list of clients, or for all the clients matching a filter.
+ * The conversations are started in the background.
+ *
+ * {"statusCode": 201,"body": Broadcast}
+ *
+ * BROADCAST = {
+ *  "broadcast_id": String, "bot_id": String, "status": "running" | "done",
+ *  "total": Integer, "sent": Integer, "failed": Integer,
+ *  "created_at": String, "updated_at": String
+ * }
+ */
+#[post("/broadcasts")]
+pub async fn create_broadcast(
+    body: web::Json<BroadcastRequest>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let mut request = body.to_owned();
+    if let Some(value) = validate_bot_id(&request.bot_id) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+    request.bot_id = get_tenant_bot_id(&req, &request.bot_id);
+    if let Some(clients) = &mut request.clients {
+        for client in clients.iter_mut() {
+            if let Some(value) = set_client_tenant(&req, client) {
+                eprintln!("AuthError: {:?}", value);
+                return HttpResponse::Forbidden().finish();
+            }
+        }
+    }
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || start_broadcast(request))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(broadcast) => HttpResponse::Created().json(broadcast),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Get the status of a broadcast
+ *
+ * {"statusCode": 200,"body": Broadcast}
+ */
+#[get("/broadcasts/{broadcast_id}")]
+pub async fn get_broadcast_status(
+    path: web::Path<BroadcastIdPath>,
+    query: web::Query<BotIdQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let broadcast_id = path.broadcast_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &query.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_broadcast(&bot_id, &broadcast_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(broadcast)) => HttpResponse::Ok().json(broadcast),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
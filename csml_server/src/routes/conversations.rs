@@ -1,8 +1,11 @@
-use actix_web::{get, post, web, HttpResponse};
-use csml_engine::{user_close_all_conversations, get_open_conversation, Client};
+use actix_web::{get, patch, post, web, HttpResponse};
+use csml_engine::data::{BotOpt, ResumeRequest};
+use csml_engine::{user_close_all_conversations, get_open_conversation, pause_conversation_for_agent, resume_conversation, Client, TranscriptFormat};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{
+    engine_error_response, get_tenant_id, set_client_tenant, validate_api_key,
+};
 
 
 /**
@@ -17,17 +20,20 @@ pub async fn get_open(body: web::Json<Client>, req: actix_web::HttpRequest) -> H
     return HttpResponse::Forbidden().finish()
   }
 
+  let mut client = body.into_inner();
+  if let Some(value) = set_client_tenant(&req, &mut client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
   let res = thread::spawn(move || {
-    get_open_conversation(&body)
+    get_open_conversation(&client)
   }).join().unwrap();
 
   match res {
     Ok(Some(conversation)) => HttpResponse::Ok().json(conversation),
     Ok(None) => HttpResponse::Ok().finish(),
-    Err(err) => {
-      eprintln!("EngineError: {:?}", err);
-      HttpResponse::InternalServerError().finish()
-    }
+    Err(err) => engine_error_response(&req, err),
   }
 
 }
@@ -43,16 +49,82 @@ pub async fn close_user_conversations(body: web::Json<Client>, req: actix_web::H
     return HttpResponse::Forbidden().finish()
   }
 
+  let mut client = body.into_inner();
+  if let Some(value) = set_client_tenant(&req, &mut client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
   let res = thread::spawn(move || {
-    user_close_all_conversations(body.clone())
+    user_close_all_conversations(client)
   }).join().unwrap();
 
   match res {
     Ok(()) => HttpResponse::Ok().finish(),
-    Err(err) => {
-      eprintln!("EngineError: {:?}", err);
-      HttpResponse::InternalServerError().finish()
-    }
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HandoverBody {
+  client: Client,
+  #[serde(default)]
+  metadata: serde_json::Value,
+}
+
+/**
+ * Hand the open conversation over to a human agent: its status becomes PAUSED_FOR_AGENT
+ * and the user's events are saved but not interpreted until it is resumed
+ */
+#[post("/conversations/handover")]
+pub async fn handover_conversation(body: web::Json<HandoverBody>, req: actix_web::HttpRequest) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let mut body = body.into_inner();
+  if let Some(value) = set_client_tenant(&req, &mut body.client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let res = thread::spawn(move || {
+    pause_conversation_for_agent(&body.client, body.metadata.clone())
+  }).join().unwrap();
+
+  match res {
+    Ok(conversation) => HttpResponse::Ok().json(conversation),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Give a conversation paused for an agent back to the bot, starting at the given step.
+ * Returns the bot's messages, like /run
+ */
+#[post("/conversations/resume")]
+pub async fn resume_agent_conversation(body: web::Json<ResumeRequest>, req: actix_web::HttpRequest) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let mut request = body.to_owned();
+  if let Some(value) = set_client_tenant(&req, &mut request.client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let res = thread::spawn(move || {
+    resume_conversation(request)
+  }).join().unwrap();
+
+  match res {
+    Ok(data) => HttpResponse::Ok().json(data),
+    Err(err) => engine_error_response(&req, err),
   }
 }
 
@@ -63,6 +135,8 @@ pub struct GetClientInfoQuery {
   channel_id: String,
   limit: Option<i64>,
   pagination_key: Option<String>,
+  // only the conversations with this tag
+  tag: Option<String>,
 }
 
 /**
@@ -79,7 +153,8 @@ pub async fn get_client_conversations(query: web::Query<GetClientInfoQuery>, req
   let client = Client {
     bot_id: query.bot_id.to_owned(),
     channel_id: query.channel_id.to_owned(),
-    user_id: query.user_id.to_owned()
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
   };
 
   let limit = query.limit.to_owned();
@@ -88,17 +163,380 @@ pub async fn get_client_conversations(query: web::Query<GetClientInfoQuery>, req
     Some(pagination_key) => Some(pagination_key),
     None => None,
   };
+  let tag = query.tag.to_owned().filter(|tag| !tag.is_empty());
 
   let res = thread::spawn(move || {
-    csml_engine::get_client_conversations(&client, limit, pagination_key)
+    csml_engine::get_client_conversations(&client, limit, pagination_key, tag.as_deref())
   }).join().unwrap();
 
   match res {
     Ok(data) => HttpResponse::Ok().json(data),
-    Err(err) => {
-    eprintln!("EngineError: {:?}", err);
-    HttpResponse::InternalServerError().finish()
-    }
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientQuery {
+  user_id: String,
+  bot_id: String,
+  channel_id: String,
+}
+
+/**
+ * Return the error that ended a conversation, with the flow, step and line of the error
+ * and the steps that led to it
+ */
+#[get("/conversations/{conversation_id}/error")]
+pub async fn get_conversation_error(
+  path: web::Path<String>,
+  query: web::Query<ClientQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let conversation_id = path.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::get_conversation_error(&client, &conversation_id)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(error)) => HttpResponse::Ok().json(error),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Return the runtime warnings of the interpreter during a conversation, grouped by interaction:
+ * [{"request_id", "warnings": [{"message", "flow", "step", "line", "column"}], "created_at"}]
+ */
+#[get("/conversations/{conversation_id}/warnings")]
+pub async fn get_conversation_warnings(
+  path: web::Path<String>,
+  query: web::Query<ClientQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let conversation_id = path.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::get_conversation_warnings(&client, &conversation_id)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(warnings)) => HttpResponse::Ok().json(warnings),
+    Ok(None) => HttpResponse::Ok().json(serde_json::json!([])),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Return where the user is in the open conversation, for the support tools:
+ * {"conversation_id", "status", "flow", "step", "last_interaction_at", "hold", "step_vars",
+ * "memories"}
+ */
+#[get("/conversations/{conversation_id}/state")]
+pub async fn get_conversation_state(
+  path: web::Path<String>,
+  query: web::Query<ClientQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let conversation_id = path.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::get_conversation_state(&client, &conversation_id)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(state)) => HttpResponse::Ok().json(state),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Return a conversation archived to the object storage with its messages,
+ * {"conversation": {...}, "messages": [...]}
+ */
+#[get("/conversations/{conversation_id}/archive")]
+pub async fn get_archived_conversation(
+  path: web::Path<String>,
+  query: web::Query<ClientQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let conversation_id = path.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::get_archived_conversation(&client, &conversation_id)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(archive)) => HttpResponse::Ok().json(archive),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranscriptQuery {
+  user_id: String,
+  bot_id: String,
+  channel_id: String,
+  #[serde(default)]
+  format: TranscriptFormat,
+}
+
+/**
+ * Return the messages of a conversation as a human-readable transcript (html, markdown or txt),
+ * the components are flattened to text descriptions
+ */
+#[get("/conversations/{conversation_id}/transcript")]
+pub async fn get_conversation_transcript(
+  path: web::Path<String>,
+  query: web::Query<TranscriptQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let conversation_id = path.into_inner();
+  let format = query.format;
+
+  let res = thread::spawn(move || {
+    csml_engine::get_conversation_transcript(&client, &conversation_id, format)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(transcript)) => HttpResponse::Ok().content_type(format.content_type()).body(transcript),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Return the metadata patched during a conversation, an empty object if there is none
+ */
+#[get("/conversations/{conversation_id}/metadata")]
+pub async fn get_conversation_metadata(
+  path: web::Path<String>,
+  query: web::Query<ClientQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let conversation_id = path.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::get_conversation_metadata(&client, &conversation_id)
+  }).join().unwrap();
+
+  match res {
+    Ok(metadata) => HttpResponse::Ok().json(metadata),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataBody {
+  client: Client,
+  metadata: serde_json::Value,
+}
+
+/**
+ * Patch the metadata of an open conversation (JSON merge patch: the keys set to null are removed),
+ * they are readable as _metadata by its next events
+ */
+#[patch("/conversations/{conversation_id}/metadata")]
+pub async fn update_conversation_metadata(
+  path: web::Path<String>,
+  body: web::Json<MetadataBody>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let conversation_id = path.into_inner();
+  let MetadataBody { mut client, metadata } = body.into_inner();
+  if let Some(value) = set_client_tenant(&req, &mut client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let res = thread::spawn(move || {
+    csml_engine::update_conversation_metadata(&client, &conversation_id, &metadata)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(metadata)) => HttpResponse::Ok().json(metadata),
+    // the conversation is not the open conversation of the client
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayBody {
+  client: Client,
+  version_id: Option<String>,
+  // seeded random builtins, time of the events and no network calls
+  #[serde(default)]
+  deterministic: bool,
+}
+
+/**
+ * Replay the events of a conversation against a version of the bot (the last one by default),
+ * without saving anything. Returns the interactions where the messages of the bot diverge,
+ * and the coverage of the bot steps and branches by the replay
+ */
+#[post("/conversations/{conversation_id}/replay")]
+pub async fn replay_conversation(
+  path: web::Path<String>,
+  body: web::Json<ReplayBody>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let conversation_id = path.into_inner();
+  let ReplayBody { mut client, version_id, deterministic } = body.into_inner();
+  if let Some(value) = set_client_tenant(&req, &mut client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let bot_opt = match version_id {
+    Some(version_id) => BotOpt::Id {
+      version_id,
+      bot_id: client.bot_id.to_owned(),
+      apps_endpoint: None,
+      multibot: None,
+    },
+    None => BotOpt::BotId {
+      bot_id: client.bot_id.to_owned(),
+      apps_endpoint: None,
+      multibot: None,
+      environment: None,
+    },
+  };
+
+  let res = thread::spawn(move || {
+    csml_engine::replay_conversation(&client, &conversation_id, bot_opt, deterministic)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(replay)) => HttpResponse::Ok().json(replay),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForkBody {
+  client: Client,
+}
+
+/**
+ * Clone the open conversation into a sandbox client, to try other events from the state of the
+ * user without changing the real conversation: {"client", "conversation_id", "forked_from"}
+ */
+#[post("/conversations/{conversation_id}/fork")]
+pub async fn fork_conversation(
+  path: web::Path<String>,
+  body: web::Json<ForkBody>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let conversation_id = path.into_inner();
+  let ForkBody { mut client } = body.into_inner();
+  if let Some(value) = set_client_tenant(&req, &mut client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let res = thread::spawn(move || {
+    csml_engine::fork_conversation(&client, &conversation_id)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(fork)) => HttpResponse::Ok().json(fork),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
   }
 }
 
@@ -165,4 +603,142 @@ mod tests {
 
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[actix_rt::test]
+    async fn test_get_conversation_error() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(get_conversation_error)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "conversation-error-channel", "botid");
+
+        let resp = test::TestRequest::get()
+                    .uri(&format!("/conversations/unknown/error?user_id={}&channel_id={}&bot_id={}", user_id, channel_id, bot_id))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_conversation_warnings() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(get_conversation_warnings)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "conversation-warnings-channel", "botid");
+
+        let resp = test::TestRequest::get()
+                    .uri(&format!("/conversations/unknown/warnings?user_id={}&channel_id={}&bot_id={}", user_id, channel_id, bot_id))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_conversation_state() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(get_conversation_state)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "conversation-state-channel", "botid");
+
+        let resp = test::TestRequest::get()
+                    .uri(&format!("/conversations/unknown/state?user_id={}&channel_id={}&bot_id={}", user_id, channel_id, bot_id))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_conversation_transcript() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(get_conversation_transcript)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "conversation-transcript-channel", "botid");
+
+        let resp = test::TestRequest::get()
+                    .uri(&format!("/conversations/unknown/transcript?user_id={}&channel_id={}&bot_id={}&format=html", user_id, channel_id, bot_id))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = test::TestRequest::get()
+                    .uri(&format!("/conversations/unknown/transcript?user_id={}&channel_id={}&bot_id={}&format=pdf", user_id, channel_id, bot_id))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_replay_conversation() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(replay_conversation)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "replay-conversation-channel", "botid");
+
+        let resp = test::TestRequest::post()
+                    .uri("/conversations/unknown/replay")
+                    .set_json(&serde_json::json!({
+                      "client": {
+                        "user_id": user_id,
+                        "channel_id": channel_id,
+                        "bot_id": bot_id
+                      }
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_fork_conversation() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(fork_conversation)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "fork-conversation-channel", "botid");
+
+        let resp = test::TestRequest::post()
+                    .uri("/conversations/unknown/fork")
+                    .set_json(&serde_json::json!({
+                      "client": {
+                        "user_id": user_id,
+                        "channel_id": channel_id,
+                        "bot_id": bot_id
+                      }
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_update_metadata_without_open_conversation() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(update_conversation_metadata)
+        ).await;
+
+        let resp = test::TestRequest::patch()
+                    .uri("/conversations/unknown-conversation/metadata")
+                    .set_json(&serde_json::json!({
+                      "client": {
+                        "user_id": "test",
+                        "channel_id": "metadata-channel",
+                        "bot_id": "botid"
+                      },
+                      "metadata": {"priority": 1}
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
 }
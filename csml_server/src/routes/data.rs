@@ -2,7 +2,9 @@ use actix_web::{delete, post, web, HttpResponse};
 use csml_interpreter::data::{Client};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{
+    engine_error_response, get_tenant_bot_id, get_tenant_id, validate_api_key,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClientQuery {
@@ -28,6 +30,7 @@ pub async fn delete_client(query: web::Query<ClientQuery>, req: actix_web::HttpR
         user_id: query.user_id.clone(),
         channel_id: query.channel_id.clone(),
         bot_id: query.bot_id.clone(),
+        tenant_id: get_tenant_id(&req),
     };
 
     if let Some(_value) = validate_api_key(&req) {
@@ -40,10 +43,7 @@ pub async fn delete_client(query: web::Query<ClientQuery>, req: actix_web::HttpR
 
     match res {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -60,16 +60,14 @@ pub async fn delete_bot(path: web::Path<BotIdPath>, req: actix_web::HttpRequest)
         return HttpResponse::Forbidden().finish()
     }
 
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
     let res = thread::spawn(move || {
-        csml_engine::delete_all_bot_data(&path.bot_id)
+        csml_engine::delete_all_bot_data(&bot_id)
     }).join().unwrap();
 
     match res {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -81,7 +79,7 @@ pub async fn delete_bot(path: web::Path<BotIdPath>, req: actix_web::HttpRequest)
  *
  */
 #[post("/data/cleanup")]
-pub async fn delete_expired_data() -> HttpResponse {
+pub async fn delete_expired_data(req: actix_web::HttpRequest) -> HttpResponse {
 
     let res = thread::spawn(move || {
         csml_engine::delete_expired_data()
@@ -89,9 +87,6 @@ pub async fn delete_expired_data() -> HttpResponse {
 
     match res {
         Ok(_) => HttpResponse::Ok().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
    }
 }
@@ -0,0 +1,225 @@
+use crate::routes::tools::{
+    engine_error_response, get_tenant_id, set_client_tenant, validate_api_key,
+};
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::data::EngineError;
+use csml_engine::{Breakpoint, Client};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientQuery {
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+}
+
+impl ClientQuery {
+    fn get_client(&self, req: &actix_web::HttpRequest) -> Client {
+        Client {
+            bot_id: self.bot_id.to_owned(),
+            channel_id: self.channel_id.to_owned(),
+            user_id: self.user_id.to_owned(),
+            tenant_id: get_tenant_id(req),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DebugSessionBody {
+    client: Client,
+    #[serde(default)]
+    breakpoints: Vec<Breakpoint>,
+}
+
+/**
+ * Debug the conversation of a client: it pauses at the given breakpoints,
+ * {"flow": "Default", "step": "start"} or {"flow": "Default", "line": 12}
+ */
+#[post("/debug")]
+pub async fn start_debug_session(
+    body: web::Json<DebugSessionBody>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let mut body = body.into_inner();
+    if let Some(value) = set_client_tenant(&req, &mut body.client) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res =
+        thread::spawn(move || csml_engine::start_debug_session(&body.client, body.breakpoints))
+            .join()
+            .unwrap();
+
+    match res {
+        Ok(session) => HttpResponse::Created().json(session),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Inspect the debug session of a client: its breakpoints and, if the conversation is paused,
+ * the paused position with the variables of the step and the memories of the client
+ */
+#[get("/debug")]
+pub async fn inspect_debug_session(
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let client = query.get_client(&req);
+    let res = thread::spawn(move || csml_engine::inspect_debug_session(&client))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(session)) => HttpResponse::Ok().json(session),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Stop debugging the conversation of a client
+ */
+#[delete("/debug")]
+pub async fn stop_debug_session(
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let client = query.get_client(&req);
+    let res = thread::spawn(move || csml_engine::stop_debug_session(&client))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+fn resume_response(
+    req: &actix_web::HttpRequest,
+    res: Result<serde_json::Map<String, serde_json::Value>, EngineError>,
+) -> HttpResponse {
+    match res {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => engine_error_response(req, err),
+    }
+}
+
+/**
+ * Resume a paused conversation until the next breakpoint.
+ * Returns the bot's messages, like /run, with the state of the debug session
+ */
+#[post("/debug/continue")]
+pub async fn continue_debug_session(
+    body: web::Json<Client>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let mut client = body.into_inner();
+    if let Some(value) = set_client_tenant(&req, &mut client) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::continue_debug_session(&client))
+        .join()
+        .unwrap();
+
+    resume_response(&req, res)
+}
+
+/**
+ * Resume a paused conversation and pause it again before its next instruction
+ */
+#[post("/debug/step-over")]
+pub async fn step_over_debug_session(
+    body: web::Json<Client>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let mut client = body.into_inner();
+    if let Some(value) = set_client_tenant(&req, &mut client) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::step_over_debug_session(&client))
+        .join()
+        .unwrap();
+
+    resume_response(&req, res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_debug_session() {
+        let mut app = test::init_service(
+            App::new()
+                .service(start_debug_session)
+                .service(inspect_debug_session)
+                .service(continue_debug_session),
+        )
+        .await;
+
+        let client = serde_json::json!({
+            "bot_id": "botid",
+            "channel_id": "debug-channel",
+            "user_id": "test",
+        });
+
+        let resp = test::TestRequest::post()
+            .uri("/debug")
+            .set_json(&serde_json::json!({
+                "client": client,
+                "breakpoints": [{"flow": "Default", "line": 2}],
+            }))
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = test::TestRequest::get()
+            .uri("/debug?user_id=test&channel_id=debug-channel&bot_id=botid")
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // the conversation is not paused
+        let resp = test::TestRequest::post()
+            .uri("/debug/continue")
+            .set_json(&client)
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
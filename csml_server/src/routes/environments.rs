@@ -0,0 +1,117 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::{
+    delete_environment_bot_version, get_environment_bot_version, set_environment_bot_version,
+};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentPath {
+    bot_id: String,
+    env: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentBody {
+    version_id: String,
+}
+
+/**
+ * Get the active version of a bot in a given environment
+ *
+ * {"statusCode": 200,"body": Bot}
+ *
+ * BOT = {
+ *  "version_id": String,
+ *  "id": String,
+ *  "name": String,
+ *  "custom_components": Option<String>,
+ *  "default_flow": String
+ *  "engine_version": String
+ *  "created_at": String
+ * }
+ */
+#[get("/bots/{bot_id}/environments/{env}")]
+pub async fn get_environment(
+    path: web::Path<EnvironmentPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let environment = path.env.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_environment_bot_version(&bot_id, &environment))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(bot_version)) => HttpResponse::Ok().json(bot_version.flatten()),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Set the active version of a bot in a given environment
+ *
+ * {"statusCode": 201}
+ */
+#[post("/bots/{bot_id}/environments/{env}")]
+pub async fn set_environment(
+    path: web::Path<EnvironmentPath>,
+    body: web::Json<EnvironmentBody>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let environment = path.env.to_owned();
+    let version_id = body.version_id.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || {
+        set_environment_bot_version(&bot_id, &environment, &version_id)
+    })
+    .join()
+    .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::Created().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Remove the active version of a bot in a given environment
+ *
+ * {"statusCode": 204}
+ */
+#[delete("/bots/{bot_id}/environments/{env}")]
+pub async fn delete_environment(
+    path: web::Path<EnvironmentPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let environment = path.env.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || delete_environment_bot_version(&bot_id, &environment))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
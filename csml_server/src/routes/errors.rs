@@ -0,0 +1,67 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorsQuery {
+    bot_id: String,
+    limit: Option<i64>,
+    pagination_key: Option<String>,
+}
+
+/**
+ * List the errors that ended the conversations of a bot, newest first
+ *
+ * {"statusCode": 200,"body": {"errors": [ERROR], "pagination_key": String}}
+ *
+ * ERROR = {
+ *  "id": String, "client": Client, "conversation_id": String, "flow_id": String,
+ *  "step_id": String, "created_at": String,
+ *  "error": {
+ *    "message": String, "interval": Object, "snippet": String, "stack": Array,
+ *    "last_event": {"content_type": String, "content": Object}, "request_id": String
+ *  }
+ * }
+ */
+#[get("/errors")]
+pub async fn get_bot_errors(
+    query: web::Query<ErrorsQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let query = query.into_inner();
+    let bot_id = get_tenant_bot_id(&req, &query.bot_id);
+    let limit = query.limit;
+    let pagination_key = query.pagination_key.filter(|key| !key.is_empty());
+    let res = thread::spawn(move || csml_engine::get_bot_errors(&bot_id, limit, pagination_key))
+    .join()
+    .unwrap();
+
+    match res {
+        Ok(errors) => HttpResponse::Ok().json(errors),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_errors_invalid_pagination_key() {
+        let mut app = test::init_service(App::new().service(get_bot_errors)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/errors?bot_id=bot&pagination_key=invalid")
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
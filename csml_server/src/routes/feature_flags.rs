@@ -0,0 +1,126 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{delete, get, put, web, HttpResponse};
+use csml_engine::{
+    data::FeatureFlag, delete_bot_feature_flag, get_bot_feature_flags, set_bot_feature_flag,
+};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdPath {
+    bot_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlagKeyPath {
+    bot_id: String,
+    key: String,
+}
+
+/**
+ * Get the feature flags of a given bot
+ *
+ * {"statusCode": 200,"body": {"key": FLAG}}
+ *
+ * FLAG = {
+ *  "enabled": bool,
+ *  "percentage": Option<u8>,
+ *  "conditions": [{"attribute": String, "operator": "eq|neq|in|not_in", "value": Value}]
+ * }
+ */
+#[get("/bots/{bot_id}/flags")]
+pub async fn get_flags(path: web::Path<BotIdPath>, req: actix_web::HttpRequest) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_bot_feature_flags(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(flags) => HttpResponse::Ok().json(flags),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Create or update a feature flag of a given bot, readable in the flows with _flags.<key>
+ *
+ * {"statusCode": 201}
+ */
+#[put("/bots/{bot_id}/flags/{key}")]
+pub async fn set_flag(
+    path: web::Path<FlagKeyPath>,
+    body: web::Json<FeatureFlag>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let key = path.key.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let flag = body.into_inner();
+    let res = thread::spawn(move || set_bot_feature_flag(&bot_id, &key, flag))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::Created().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Delete a feature flag of a given bot
+ *
+ * {"statusCode": 204}
+ */
+#[delete("/bots/{bot_id}/flags/{key}")]
+pub async fn delete_flag(
+    path: web::Path<FlagKeyPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let key = path.key.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || delete_bot_feature_flag(&bot_id, &key))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_set_flag_invalid_percentage() {
+        let mut app = test::init_service(App::new().service(set_flag)).await;
+
+        let resp = test::TestRequest::put()
+            .uri("/bots/bot_id/flags/new_menu")
+            .set_json(&serde_json::json!({"enabled": true, "percentage": 150}))
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,45 @@
+use actix_web::{post, web, HttpResponse};
+use csml_engine::format_flow;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormatFlowBody {
+  content: String,
+}
+
+/**
+ * Pretty print the content of a flow, comments and strings are kept as they are
+ */
+#[post("/format")]
+pub async fn handler(body: web::Json<FormatFlowBody>) -> HttpResponse {
+  HttpResponse::Ok().json(FormatFlowBody {
+    content: format_flow(&body.content),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_format() {
+        let mut app = test::init_service(App::new().service(handler)).await;
+
+        let resp = test::TestRequest::post()
+            .uri("/format")
+            .set_json(&serde_json::json!({
+                "content": "start:\nsay   \"Hello\"\ngoto end",
+            }))
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body,
+            serde_json::json!({"content": "start:\n    say \"Hello\"\n    goto end\n"})
+        );
+    }
+}
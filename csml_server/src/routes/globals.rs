@@ -0,0 +1,103 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::{delete_bot_global, get_bot_globals, set_bot_global};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdPath {
+    bot_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalKeyPath {
+    bot_id: String,
+    key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Global {
+    key: String,
+    value: serde_json::Value,
+}
+
+/**
+ * Get the globals of a given bot, shared by all its users
+ *
+ * {"statusCode": 200,"body": {"key": Value}}
+ */
+#[get("/bots/{bot_id}/globals")]
+pub async fn get_globals(path: web::Path<BotIdPath>, req: actix_web::HttpRequest) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_bot_globals(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(globals) => HttpResponse::Ok().json(globals),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Create or update a global of a given bot, readable in the flows with _global.<key>
+ *
+ * {"statusCode": 201}
+ */
+#[post("/bots/{bot_id}/globals")]
+pub async fn set_global(
+    path: web::Path<BotIdPath>,
+    body: web::Json<Global>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res =
+        thread::spawn(move || set_bot_global(&bot_id, body.key.to_owned(), body.value.to_owned()))
+            .join()
+            .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::Created().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Delete a global of a given bot
+ *
+ * {"statusCode": 204}
+ */
+#[delete("/bots/{bot_id}/globals/{key}")]
+pub async fn delete_global(
+    path: web::Path<GlobalKeyPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let key = path.key.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || delete_bot_global(&bot_id, &key))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::data::KbDocument;
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdPath {
+    bot_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentIdPath {
+    bot_id: String,
+    document_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KbSearchQuery {
+    q: String,
+    top_k: Option<usize>,
+}
+
+/**
+ * Add a document to the knowledge base of a bot, replacing the document with the same id
+ *
+ * {"statusCode": 201,"body": {"document_id": String, "passages": Integer}}
+ */
+#[post("/bots/{bot_id}/knowledge_base")]
+pub async fn ingest_document(
+    path: web::Path<BotIdPath>,
+    body: web::Json<KbDocument>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let document = body.into_inner();
+    let res = thread::spawn(move || csml_engine::ingest_kb_document(&bot_id, document))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(document) => HttpResponse::Created().json(document),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Search the passages of the knowledge base of a bot the most similar to a query, best first
+ *
+ * {"statusCode": 200,"body": [{"document_id": String, "text": String, "score": Number, "metadata": Object}]}
+ */
+#[get("/bots/{bot_id}/knowledge_base/search")]
+pub async fn search_knowledge_base(
+    path: web::Path<BotIdPath>,
+    query: web::Query<KbSearchQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let query = query.into_inner();
+    if query.q.trim().is_empty() {
+        return HttpResponse::BadRequest().body("the query must not be empty");
+    }
+
+    let res = thread::spawn(move || {
+        csml_engine::search_kb(&bot_id, &query.q, query.top_k.unwrap_or(5).clamp(1, 50))
+    })
+    .join()
+    .unwrap();
+
+    match res {
+        Ok(passages) => HttpResponse::Ok().json(passages),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Remove a document from the knowledge base of a bot
+ *
+ * {"statusCode": 204}
+ */
+#[delete("/bots/{bot_id}/knowledge_base/{document_id}")]
+pub async fn delete_document(
+    path: web::Path<DocumentIdPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let document_id = path.document_id.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::delete_kb_document(&bot_id, &document_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
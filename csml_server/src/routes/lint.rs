@@ -0,0 +1,52 @@
+use actix_web::{post, web, HttpResponse};
+use csml_engine::{parse_flow_to_partial_ast_json, parse_step};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintFlowBody {
+  content: String,
+  // the content is the source of a single step
+  #[serde(default)]
+  step: bool,
+}
+
+/**
+ * Parse the content of a flow and return its AST with its syntax errors:
+ * the AST of an invalid flow only contains its valid steps, functions and root items
+ */
+#[post("/lint")]
+pub async fn handler(body: web::Json<LintFlowBody>) -> HttpResponse {
+  let report = match body.step {
+    true => parse_step(&body.content),
+    false => parse_flow_to_partial_ast_json(&body.content),
+  };
+
+  HttpResponse::Ok().json(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_lint() {
+        let mut app = test::init_service(App::new().service(handler)).await;
+
+        let resp = test::TestRequest::post()
+            .uri("/lint")
+            .set_json(&serde_json::json!({
+                "content": "start:\n    say \"Hello\"\n    goto next\n\nnext:\n    say (\n",
+            }))
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["valid"], false);
+        assert_eq!(body["ast"]["steps"][0]["name"], "start");
+        assert_eq!(body["ast"]["steps"].as_array().unwrap().len(), 1);
+        assert_eq!(body["errors"][0]["start_line"], 6);
+    }
+}
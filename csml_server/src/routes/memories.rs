@@ -1,4 +1,4 @@
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{engine_error_response, get_tenant_id, validate_api_key};
 use actix_web::{delete, get, post, web, HttpResponse};
 use csml_interpreter::data::Client;
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,8 @@ pub struct ClientQuery {
     pub bot_id: String,
     pub channel_id: String,
     pub user_id: String,
+    // "user" or "channel" (default)
+    pub scope: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,6 +24,27 @@ pub struct Memory {
     value: serde_json::Value,
 }
 
+/**
+ * Client owning the memories of the requested scope: "user" memories are shared
+ * by all the channels of the user
+ */
+fn get_scope_client(
+    query: &ClientQuery,
+    req: &actix_web::HttpRequest,
+) -> Result<Client, HttpResponse> {
+    let client = Client {
+        user_id: query.user_id.clone(),
+        channel_id: query.channel_id.clone(),
+        bot_id: query.bot_id.clone(),
+        tenant_id: get_tenant_id(req),
+    };
+
+    match csml_engine::get_memory_scope_client(&client, query.scope.as_deref()) {
+        Ok(client) => Ok(client),
+        Err(err) => Err(engine_error_response(req, err)),
+    }
+}
+
 /**
  * Create client memory
  *
@@ -34,10 +57,9 @@ pub async fn create_client_memory(
     body: web::Json<Memory>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let client = Client {
-        user_id: query.user_id.clone(),
-        channel_id: query.channel_id.clone(),
-        bot_id: query.bot_id.clone(),
+    let client = match get_scope_client(&query, &req) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
 
     if let Some(_value) = validate_api_key(&req) {
@@ -52,10 +74,7 @@ pub async fn create_client_memory(
 
     match res {
         Ok(_) => HttpResponse::Created().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -73,10 +92,9 @@ pub async fn delete_memory(
 ) -> HttpResponse {
     let memory_key = path.key.to_owned();
 
-    let client = Client {
-        user_id: query.user_id.clone(),
-        channel_id: query.channel_id.clone(),
-        bot_id: query.bot_id.clone(),
+    let client = match get_scope_client(&query, &req) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
 
     if let Some(_value) = validate_api_key(&req) {
@@ -89,10 +107,7 @@ pub async fn delete_memory(
 
     match res {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -107,10 +122,9 @@ pub async fn delete_memories(
     query: web::Query<ClientQuery>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let client = Client {
-        user_id: query.user_id.clone(),
-        channel_id: query.channel_id.clone(),
-        bot_id: query.bot_id.clone(),
+    let client = match get_scope_client(&query, &req) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
 
     if let Some(_value) = validate_api_key(&req) {
@@ -123,10 +137,7 @@ pub async fn delete_memories(
 
     match res {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -142,10 +153,9 @@ pub async fn get_memory(
 ) -> HttpResponse {
     let memory_key = path.key.to_owned();
 
-    let client = Client {
-        user_id: query.user_id.clone(),
-        channel_id: query.channel_id.clone(),
-        bot_id: query.bot_id.clone(),
+    let client = match get_scope_client(&query, &req) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
 
     if let Some(_value) = validate_api_key(&req) {
@@ -158,10 +168,39 @@ pub async fn get_memory(
 
     match res {
         Ok(memory) => HttpResponse::Ok().json(memory),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Get the previous values of a key in client memory, newest first
+ *
+ * {"statusCode": 200,"body": [{"value": Value, "interaction_id": String, "created_at": String}]}
+ */
+#[get("/memories/{key}/history")]
+pub async fn get_memory_history(
+    path: web::Path<MemoryKeyPath>,
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let memory_key = path.key.to_owned();
+
+    let client = match get_scope_client(&query, &req) {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    if let Some(_value) = validate_api_key(&req) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::get_client_memory_history(&client, &memory_key))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -174,10 +213,9 @@ pub async fn get_memories(
     query: web::Query<ClientQuery>,
     req: actix_web::HttpRequest,
 ) -> HttpResponse {
-    let client = Client {
-        user_id: query.user_id.clone(),
-        channel_id: query.channel_id.clone(),
-        bot_id: query.bot_id.clone(),
+    let client = match get_scope_client(&query, &req) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
 
     if let Some(_value) = validate_api_key(&req) {
@@ -190,10 +228,7 @@ pub async fn get_memories(
 
     match res {
         Ok(memory) => HttpResponse::Ok().json(memory),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
@@ -2,7 +2,7 @@ use actix_web::{get, web, HttpResponse};
 use csml_interpreter::data::{Client};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{engine_error_response, get_tenant_id, validate_api_key};
 
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,7 +30,8 @@ pub async fn get_client_messages(query: web::Query<GetClientInfoQuery>, req: act
     let client = Client {
         bot_id: query.bot_id.to_owned(),
         channel_id: query.channel_id.to_owned(),
-        user_id: query.user_id.to_owned()
+        user_id: query.user_id.to_owned(),
+        tenant_id: get_tenant_id(&req),
     };
 
     let limit = query.limit.to_owned();
@@ -53,10 +54,7 @@ pub async fn get_client_messages(query: web::Query<GetClientInfoQuery>, req: act
 
     match res {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-        eprintln!("EngineError: {:?}", err);
-        HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
 
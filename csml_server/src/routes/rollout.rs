@@ -0,0 +1,96 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::data::BotRollout;
+use csml_engine::{delete_bot_rollout, get_bot_rollout, set_bot_rollout};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdPath {
+    bot_id: String,
+}
+
+/**
+ * Get the current rollout of a given bot
+ *
+ * {"statusCode": 200,"body": Rollout}
+ *
+ * ROLLOUT = {
+ *  "versions": [{"version_id": String, "percentage": Integer}]
+ * }
+ */
+#[get("/bots/{bot_id}/rollout")]
+pub async fn get_rollout(path: web::Path<BotIdPath>, req: actix_web::HttpRequest) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || get_bot_rollout(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(rollout)) => HttpResponse::Ok().json(rollout),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Set the rollout of a given bot. Percentages must add up to 100.
+ *
+ * {"statusCode": 201}
+ */
+#[post("/bots/{bot_id}/rollout")]
+pub async fn set_rollout(
+    path: web::Path<BotIdPath>,
+    body: web::Json<BotRollout>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+    let rollout = body.to_owned();
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || set_bot_rollout(&bot_id, rollout))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::Created().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Remove the rollout of a given bot
+ *
+ * {"statusCode": 204}
+ */
+#[delete("/bots/{bot_id}/rollout")]
+pub async fn delete_rollout(
+    path: web::Path<BotIdPath>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &path.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || delete_bot_rollout(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
@@ -1,26 +1,63 @@
-use actix_web::{post, web, HttpResponse};
-use csml_engine::{start_conversation};
-use csml_engine::data::{RunRequest};
+use actix_web::{get, post, web, HttpResponse};
+use csml_engine::{run_batch, start_conversation_with_context};
+use csml_engine::data::{BatchRunRequest, EngineError, RunRequest};
+use csml_interpreter::data::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{
+    engine_error_response, get_error_status, get_run_context, get_tenant_id, set_client_tenant,
+    validate_api_key, validate_bot_opt, validate_client_bot_id, validate_signature,
+};
 
-#[post("/run")]
-pub async fn handler(body: web::Json<RunRequest>, req: actix_web::HttpRequest) -> HttpResponse {
-  let mut request = body.event.to_owned();
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunMessagesPath {
+  request_id: String,
+}
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunMessagesQuery {
+  user_id: String,
+  bot_id: String,
+  channel_id: String,
+  limit: Option<usize>,
+  pagination_key: Option<String>,
+}
+
+#[post("/run")]
+pub async fn handler(bytes: web::Bytes, req: actix_web::HttpRequest) -> HttpResponse {
   if let Some(value) = validate_api_key(&req) {
     eprintln!("AuthError: {:?}", value);
     return HttpResponse::Forbidden().finish()
   }
 
+  // the body is parsed by hand: its signature is computed on the raw bytes
+  let body: RunRequest = match serde_json::from_slice(&bytes) {
+    Ok(run_request) => run_request,
+    Err(err) => return engine_error_response(&req, EngineError::Format(err.to_string())),
+  };
+  let mut request = body.event.to_owned();
+  if let Some(value) = set_client_tenant(&req, &mut request.client) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
   let bot_opt = match body.get_bot_opt() {
     Ok(bot_opt) => bot_opt,
-    Err(err) => {
-      eprintln!("EngineError: {:?}", err);
-      return HttpResponse::BadRequest().finish()
-    }
+    Err(err) => return engine_error_response(&req, err),
   };
+  if let Some(value) = validate_bot_opt(&bot_opt) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+  if let Some(err) = validate_client_bot_id(&request.client, &bot_opt) {
+    return engine_error_response(&req, err)
+  }
+
+  if let Some(value) = validate_signature(&req, bot_opt.get_bot_id(), &bytes) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Unauthorized().finish()
+  }
 
   // request metadata should be an empty object by default
   request.metadata = match request.metadata {
@@ -28,16 +65,145 @@ pub async fn handler(body: web::Json<RunRequest>, req: actix_web::HttpRequest) -
     val => val,
   };
 
+  let run_context = get_run_context(&req);
   let res = thread::spawn(move || {
-    start_conversation(request, bot_opt)
+    start_conversation_with_context(request, bot_opt, run_context)
   }).join().unwrap();
 
   match res {
     Ok(data) => HttpResponse::Ok().json(data),
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Result of a run of a batch, with the http status of the same run sent to /run
+ */
+fn format_batch_result(
+  request_id: &str,
+  res: Result<serde_json::Map<String, Value>, EngineError>,
+) -> Value {
+  let (status, body) = match res {
+    Ok(data) => (200, json!({"data": data})),
+    Err(EngineError::Metadata(errors)) => (400, json!({"errors": errors})),
+    Err(EngineError::Format(err)) => (400, json!({"error": err})),
+    Err(EngineError::QuotaExceeded(quota)) => {
+      (429, json!({"error": "quota_exceeded", "quota": quota}))
+    }
+    Err(EngineError::ClientBusy) => (429, json!({"error": "client_busy"})),
+    Err(EngineError::Conflict) => (409, json!({"error": "conversation_conflict"})),
+    Err(EngineError::ShuttingDown) => (503, json!({"error": "shutting_down"})),
     Err(err) => {
       eprintln!("EngineError: {:?}", err);
-      HttpResponse::InternalServerError().finish()
+      (get_error_status(&err).as_u16(), json!({"error": err.code()}))
     }
+  };
+
+  let mut result = json!({"request_id": request_id, "status": status});
+  if let (Some(result), Value::Object(body)) = (result.as_object_mut(), body) {
+    result.extend(body);
+  }
+
+  result
+}
+
+/**
+ * Interpret independent runs in one call, to backfill events or to load test a bot:
+ * {"runs": [RunRequest], "concurrency": 4} returns {"results": [{"request_id", "status", ...}]}
+ * in the order of the runs
+ */
+#[post("/run/batch")]
+pub async fn batch_handler(bytes: web::Bytes, req: actix_web::HttpRequest) -> HttpResponse {
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let mut body: BatchRunRequest = match serde_json::from_slice(&bytes) {
+    Ok(batch_request) => batch_request,
+    Err(err) => return engine_error_response(&req, EngineError::Format(err.to_string())),
+  };
+  body.run_context = get_run_context(&req);
+
+  // the signature of the body is checked for each bot of the batch
+  for run in body.runs.iter_mut() {
+    if let Some(value) = set_client_tenant(&req, &mut run.event.client) {
+      eprintln!("AuthError: {:?}", value);
+      return HttpResponse::Forbidden().finish()
+    }
+    // the runs without bot fail alone
+    let bot_opt = match run.get_bot_opt() {
+      Ok(bot_opt) => bot_opt,
+      Err(_) => continue,
+    };
+    if let Some(value) = validate_bot_opt(&bot_opt) {
+      eprintln!("AuthError: {:?}", value);
+      return HttpResponse::Forbidden().finish()
+    }
+    if let Some(err) = validate_client_bot_id(&run.event.client, &bot_opt) {
+      return engine_error_response(&req, err)
+    }
+
+    if let Some(value) = validate_signature(&req, bot_opt.get_bot_id(), &bytes) {
+      eprintln!("AuthError: {:?}", value);
+      return HttpResponse::Unauthorized().finish()
+    }
+  }
+
+  let request_ids: Vec<String> = body.runs.iter().map(|run| run.event.request_id.to_owned()).collect();
+
+  let res = thread::spawn(move || {
+    run_batch(body)
+  }).join().unwrap();
+
+  match res {
+    Ok(results) => {
+      let results: Vec<Value> = request_ids.iter()
+        .zip(results)
+        .map(|(request_id, res)| format_batch_result(request_id, res))
+        .collect();
+
+      HttpResponse::Ok().json(json!({"results": results}))
+    }
+    Err(err) => engine_error_response(&req, err),
+  }
+}
+
+/**
+ * Next page of the messages of a run over RUN_MESSAGES_LIMIT, from its pagination_key
+ */
+#[get("/run/{request_id}/messages")]
+pub async fn get_run_messages(
+  path: web::Path<RunMessagesPath>,
+  query: web::Query<RunMessagesQuery>,
+  req: actix_web::HttpRequest,
+) -> HttpResponse {
+  if let Some(value) = validate_api_key(&req) {
+    eprintln!("AuthError: {:?}", value);
+    return HttpResponse::Forbidden().finish()
+  }
+
+  let client = Client {
+    bot_id: query.bot_id.to_owned(),
+    channel_id: query.channel_id.to_owned(),
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
+  };
+  let request_id = path.request_id.to_owned();
+  let limit = query.limit;
+  let pagination_key = match query.pagination_key.to_owned() {
+    Some(pagination_key) if pagination_key.is_empty() => None,
+    pagination_key => pagination_key,
+  };
+
+  let res = thread::spawn(move || {
+    csml_engine::get_run_messages(&client, &request_id, limit, pagination_key)
+  }).join().unwrap();
+
+  match res {
+    Ok(Some(data)) => HttpResponse::Ok().json(data),
+    Ok(None) => HttpResponse::NotFound().finish(),
+    Err(err) => engine_error_response(&req, err),
   }
 }
 
@@ -90,4 +256,238 @@ mod tests {
 
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[actix_rt::test]
+    async fn test_run_without_signature() {
+        std::env::set_var("RUN_SIGNATURE_SECRET_TEST_RUN_SIGNED", "secret");
+
+        let mut app = test::init_service(
+            App::new()
+                    .service(handler)
+        ).await;
+
+        let resp = test::TestRequest::post()
+                    .uri(&format!("/run"))
+                    .set_json(&serde_json::json!({
+                        "version_id": "version_id",
+                        "bot_id": "test_run_signed",
+                        "event": {
+                            "request_id": "request_id",
+                            "client": {
+                                "user_id": "user_id",
+                                "channel_id": "channel_id",
+                                "bot_id": "test_run_signed"
+                            },
+                            "payload": {
+                              "content_type": "text" ,
+                              "content": {
+                                "text": "toto"
+                              }
+                            },
+                            "metadata": Value::Null,
+                        },
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_rt::test]
+    async fn test_run_client_of_another_bot() {
+        std::env::set_var("RUN_SIGNATURE_SECRET_TEST_RUN_SIGNED_BOT", "secret");
+
+        let mut app = test::init_service(
+            App::new()
+                    .service(handler)
+                    .service(batch_handler)
+        ).await;
+
+        // the client can't name an unsigned bot to run the signed one
+        let run = serde_json::json!({
+            "version_id": "version_id",
+            "bot_id": "test_run_signed_bot",
+            "event": {
+                "request_id": "request_id",
+                "client": {
+                    "user_id": "user_id",
+                    "channel_id": "channel_id",
+                    "bot_id": "test_run_unsigned_bot"
+                },
+                "payload": {
+                  "content_type": "text" ,
+                  "content": {
+                    "text": "toto"
+                  }
+                },
+                "metadata": Value::Null,
+            },
+        });
+
+        let resp = test::TestRequest::post()
+                    .uri("/run")
+                    .set_json(&run)
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = test::TestRequest::post()
+                    .uri("/run/batch")
+                    .set_json(&serde_json::json!({"runs": [run]}))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_run_tenant_bot_id() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(handler)
+                    .service(get_run_messages)
+        ).await;
+
+        // the tenant is only given by the api key
+        let resp = test::TestRequest::post()
+                    .uri(&format!("/run"))
+                    .set_json(&serde_json::json!({
+                        "bot_id": "tenant::test_run",
+                        "event": {
+                            "request_id": "request_id",
+                            "client": {
+                                "user_id": "user_id",
+                                "channel_id": "channel_id",
+                                "bot_id": "tenant::test_run"
+                            },
+                            "payload": {
+                              "content_type": "text" ,
+                              "content": {
+                                "text": "toto"
+                              }
+                            },
+                            "metadata": Value::Null,
+                        },
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let resp = test::TestRequest::get()
+                    .uri("/run/request_id/messages?user_id=user_id&channel_id=channel_id&bot_id=tenant::test_run")
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_rt::test]
+    async fn test_run_batch() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(batch_handler)
+        ).await;
+
+        let bot = serde_json::json!({
+            "id": "test_run_batch",
+            "name": "test_run_batch",
+            "flows": [
+              {
+                "id": "Default",
+                "name": "Default",
+                "content": "start: say \"Hello\" goto end",
+                "commands": [],
+              }
+            ],
+            "default_flow": "Default",
+        });
+        let run = |user_id: &str, bot: Value| serde_json::json!({
+            "bot": bot,
+            "event": {
+                "request_id": user_id,
+                "client": {
+                    "user_id": user_id,
+                    "channel_id": "channel_id",
+                    "bot_id": "test_run_batch"
+                },
+                "payload": {
+                  "content_type": "text" ,
+                  "content": {
+                    "text": "toto"
+                  }
+                },
+                "metadata": Value::Null,
+            },
+        });
+
+        let resp = test::TestRequest::post()
+                    .uri("/run/batch")
+                    .set_json(&serde_json::json!({
+                        "runs": [run("batch_user_1", bot.clone()), run("batch_user_2", Value::Null)],
+                        "concurrency": 2,
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["results"][0]["request_id"], "batch_user_1");
+        assert_eq!(body["results"][0]["status"], 200);
+        // the run without bot fails alone
+        assert_eq!(body["results"][1]["status"], 400);
+    }
+
+    #[actix_rt::test]
+    async fn test_run_invalid_body() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(handler)
+        ).await;
+
+        let resp = test::TestRequest::post()
+                    .uri("/run")
+                    .insert_header(("content-type", "application/json"))
+                    .insert_header(("X-Request-Id", "request_id"))
+                    .set_payload("{\"bot\": ")
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "invalid_request");
+        assert_eq!(body["title"], "Bad Request");
+        assert_eq!(body["instance"], "/run");
+        assert_eq!(body["request_id"], "request_id");
+    }
+
+    #[actix_rt::test]
+    async fn test_run_request_id_header() {
+        let mut app = test::init_service(
+            App::new()
+                    .wrap(crate::request_id::RequestIdHeader)
+                    .service(handler)
+        ).await;
+
+        // an invalid X-Request-Id is replaced by a new id, returned in the response
+        let resp = test::TestRequest::post()
+                    .uri("/run")
+                    .insert_header(("content-type", "application/json"))
+                    .insert_header(("X-Request-Id", "invalid id"))
+                    .set_payload("{\"bot\": ")
+                    .send_request(&mut app).await;
+
+        let request_id = resp.headers().get("X-Request-Id").unwrap().to_str().unwrap().to_owned();
+        assert_ne!(request_id, "invalid id");
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["request_id"], request_id.as_str());
+    }
+
+    #[actix_rt::test]
+    async fn test_run_messages_not_found() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(get_run_messages)
+        ).await;
+
+        let resp = test::TestRequest::get()
+                    .uri("/run/unknown_request_id/messages?user_id=user_id&channel_id=channel_id&bot_id=test_run")
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
 }
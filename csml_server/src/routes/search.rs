@@ -0,0 +1,64 @@
+use crate::routes::tools::{engine_error_response, get_tenant_bot_id, validate_api_key};
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchQuery {
+    bot_id: String,
+    q: String,
+    // unix timestamps in seconds
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+}
+
+/**
+ * Search a text in the messages and memories of a bot, most recent first
+ *
+ * {"statusCode": 200,"body": {"messages": [MESSAGE], "memories": [MEMORY]}}
+ *
+ * MESSAGE = {
+ *  "client": Client, "conversation_id": String, "flow_id": String, "step_id": String,
+ *  "direction": String, "payload": Object, "created_at": String
+ * }
+ * MEMORY = {"client": Client, "key": String, "value": Any, "created_at": String}
+ */
+#[get("/search")]
+pub async fn search(query: web::Query<SearchQuery>, req: actix_web::HttpRequest) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let query = query.into_inner();
+    let bot_id = get_tenant_bot_id(&req, &query.bot_id);
+    let res = thread::spawn(move || {
+        csml_engine::search(&bot_id, &query.q, query.from, query.to, query.limit)
+    })
+    .join()
+    .unwrap();
+
+    match res {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_search_without_text() {
+        let mut app = test::init_service(App::new().service(search)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/search?bot_id=bot&q=")
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
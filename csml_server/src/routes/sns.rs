@@ -1,7 +1,8 @@
+use crate::routes::tools::{engine_error_response, get_run_context};
 use actix_web::{post, web, HttpRequest, HttpResponse};
 use awc::Client;
 use csml_engine::data::RunRequest;
-use csml_engine::start_conversation;
+use csml_engine::start_conversation_with_context;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::thread;
@@ -36,7 +37,7 @@ struct SnsMessage {
     message: String,
 }
 
-async fn handle_notification(body: &str) -> HttpResponse {
+async fn handle_notification(req: &HttpRequest, body: &str) -> HttpResponse {
     // All requests with an invalid should return a 200 code,
     // as we don't want the SNS event to be retried (same result).
     // Ideally, it should however raise an error on some logging/monitoring system
@@ -73,16 +74,15 @@ async fn handle_notification(body: &str) -> HttpResponse {
         val => val,
     };
 
-    let res = thread::spawn(move || start_conversation(event, bot_opt))
+    let run_context = get_run_context(req);
+    let res = thread::spawn(move || start_conversation_with_context(event, bot_opt, run_context))
         .join()
         .unwrap();
 
     match res {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        // SNS retries the message, on another server
+        Err(err) => engine_error_response(req, err),
     }
 }
 
@@ -111,7 +111,7 @@ pub async fn handler(req: HttpRequest, body: web::Bytes) -> HttpResponse {
             return confirm_subscription(&body_string).await;
         }
         if val == "Notification" {
-            return handle_notification(&body_string).await;
+            return handle_notification(&req, &body_string).await;
         }
     };
 
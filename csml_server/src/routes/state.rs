@@ -2,7 +2,7 @@ use actix_web::{get, web, HttpResponse};
 use csml_engine::{Client};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::{engine_error_response, get_tenant_id, validate_api_key};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClientQuery {
@@ -17,7 +17,8 @@ pub async fn get_client_current_state(query: web::Query<ClientQuery>, req: actix
   let client = Client {
     bot_id: query.bot_id.to_owned(),
     channel_id: query.channel_id.to_owned(),
-    user_id: query.user_id.to_owned()
+    user_id: query.user_id.to_owned(),
+    tenant_id: get_tenant_id(&req),
   };
 
   if let Some(value) = validate_api_key(&req) {
@@ -31,10 +32,7 @@ pub async fn get_client_current_state(query: web::Query<ClientQuery>, req: actix
 
   match res {
     Ok(data) => HttpResponse::Ok().json(data),
-    Err(err) => {
-        eprintln!("EngineError: {:?}", err);
-        HttpResponse::InternalServerError().finish()
-    }
+    Err(err) => engine_error_response(&req, err),
   }
 }
 
@@ -1,4 +1,5 @@
-use actix_web::{get, HttpResponse};
+use crate::routes::tools::engine_error_response;
+use actix_web::{get, HttpRequest, HttpResponse};
 use std::thread;
 
 /*
@@ -8,7 +9,7 @@ use std::thread;
 *
 */
 #[get("/status")]
-pub async fn get_status() -> HttpResponse {
+pub async fn get_status(req: HttpRequest) -> HttpResponse {
 
     let res = thread::spawn(move || {
         csml_engine::get_status()
@@ -16,9 +17,6 @@ pub async fn get_status() -> HttpResponse {
 
     match res {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("EngineError: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => engine_error_response(&req, err),
     }
 }
\ No newline at end of file
@@ -1,22 +1,319 @@
+use actix_web::{
+    error::{InternalError, JsonPayloadError},
+    http::{header, StatusCode},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use csml_engine::{
+    data::{DbErrorKind, EngineError, RunContext},
+    get_config,
+};
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+// separates the tenant and the bot in the bot_id of the bots of a tenant
+const TENANT_SEPARATOR: &str = "::";
+
+/**
+ * X-Request-Id of the request, set by the RequestIdHeader middleware
+ */
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/**
+ * ENGINE_SERVER_TENANT_API_KEYS=key:tenant_id,... gives each tenant its own api key:
+ * the requests made with this key only reach the bots and clients of the tenant
+ */
+fn get_tenant_api_keys() -> Vec<(String, String)> {
+    match get_config().map("auth.tenant_api_keys") {
+      Some(keys) => {
+        keys.into_iter()
+          // a tenant_id with the separator would reach the bots of another tenant
+          .filter(|(key, tenant_id)| {
+            !key.is_empty() && !tenant_id.is_empty() && !tenant_id.contains(TENANT_SEPARATOR)
+          })
+          .collect()
+      },
+      None => vec![],
+    }
+}
 
 pub fn validate_api_key(req: &actix_web::HttpRequest) -> Option<String> {
-    let api_keys = match std::env::var("ENGINE_SERVER_API_KEYS") {
-      Ok(val) if !val.is_empty() => val,
-      _ => return None
-    };
+    let api_keys = get_config().list("auth.api_keys").unwrap_or_default();
+    let tenant_api_keys = get_tenant_api_keys();
 
-    let vec = api_keys.split(',').collect::<Vec<&str>>();
+    if api_keys.is_empty() && tenant_api_keys.is_empty() {
+      return validate_request_bot_id(req)
+    }
+
+    let vec = api_keys.iter().map(String::as_str).collect::<Vec<&str>>();
 
     match req.headers().get("X-Api-Key") {
       Some(val) => {
         let val = val.to_str().unwrap_or("");
-        if val.is_empty() || !vec.contains(&val) {
+        if val.is_empty()
+          || (!vec.contains(&val) && !tenant_api_keys.iter().any(|(key, _)| key == val))
+        {
           return Some(format!("Invalid X-Api-Key value [{}]", val))
         }
-        None
+        validate_request_bot_id(req)
       },
       None => {
         Some("Missing X-Api-Key in header".to_owned())
       }
     }
 }
+
+/**
+ * The bot_ids of the requests never hold a tenant: the tenant is only given by the api key,
+ * so that a key without tenant can't reach the bots of a tenant
+ */
+pub fn validate_bot_id(bot_id: &str) -> Option<String> {
+    match bot_id.contains(TENANT_SEPARATOR) {
+      true => Some(format!("Invalid bot_id [{}]", bot_id)),
+      false => None,
+    }
+}
+
+/**
+ * The bot_id of the path or of the query string of the request
+ */
+fn validate_request_bot_id(req: &actix_web::HttpRequest) -> Option<String> {
+    if let Some(err) = req.match_info().get("bot_id").and_then(validate_bot_id) {
+      return Some(err)
+    }
+
+    let query = actix_web::web::Query::<std::collections::HashMap<String, String>>::from_query(req.query_string());
+    match query.ok().and_then(|query| query.get("bot_id").cloned()) {
+      Some(bot_id) => validate_bot_id(&bot_id),
+      None => None,
+    }
+}
+
+/**
+ * The bot of a run, given by its bot_id or in the request
+ */
+pub fn validate_bot_opt(bot_opt: &csml_engine::data::BotOpt) -> Option<String> {
+    match bot_opt {
+      csml_engine::data::BotOpt::CsmlBot(bot) => validate_bot_id(&bot.id),
+      csml_engine::data::BotOpt::Id { bot_id, .. }
+      | csml_engine::data::BotOpt::BotId { bot_id, .. } => validate_bot_id(bot_id),
+    }
+}
+
+/**
+ * The client of a run belongs to the bot that runs, whose secret signs the body of the request
+ */
+pub fn validate_client_bot_id(client: &csml_engine::Client, bot_opt: &csml_engine::data::BotOpt) -> Option<EngineError> {
+    match client.bot_id == bot_opt.get_bot_id() {
+      true => None,
+      false => Some(EngineError::Format(format!(
+        "client.bot_id [{}] is not the bot_id of the run [{}]",
+        client.bot_id,
+        bot_opt.get_bot_id()
+      ))),
+    }
+}
+
+/**
+ * Tenant of the api key of the request, None for the keys of ENGINE_SERVER_API_KEYS
+ */
+pub fn get_tenant_id(req: &actix_web::HttpRequest) -> Option<String> {
+    let val = req.headers().get("X-Api-Key")?.to_str().ok()?;
+
+    get_tenant_api_keys()
+      .into_iter()
+      .find(|(key, _)| key == val)
+      .map(|(_, tenant_id)| tenant_id)
+}
+
+/**
+ * The tenant of a client is always the tenant of the api key, never the one sent in the request
+ */
+pub fn set_client_tenant(req: &actix_web::HttpRequest, client: &mut csml_engine::Client) -> Option<String> {
+    client.tenant_id = get_tenant_id(req);
+    validate_bot_id(&client.bot_id)
+}
+
+/**
+ * Bot id under which the bot of the tenant of the api key is stored
+ */
+pub fn get_tenant_bot_id(req: &actix_web::HttpRequest, bot_id: &str) -> String {
+    csml_engine::get_tenant_bot_id(get_tenant_id(req).as_deref(), bot_id)
+}
+
+/**
+ * When the requests of the bot are signed, check the signature of the body of the request
+ */
+pub fn validate_signature(req: &actix_web::HttpRequest, bot_id: &str, body: &[u8]) -> Option<String> {
+    let header = csml_engine::get_request_signature_header(bot_id)?;
+
+    let signature = req.headers().get(header.as_str()).and_then(|val| val.to_str().ok());
+    let timestamp = req.headers().get(csml_engine::TIMESTAMP_HEADER).and_then(|val| val.to_str().ok());
+
+    match (signature, timestamp) {
+      (Some(signature), Some(timestamp)) => {
+        match csml_engine::verify_request_signature(bot_id, signature, timestamp, body) {
+          Ok(()) => None,
+          Err(err) => Some(format!("{:?}", err)),
+        }
+      },
+      _ => {
+        Some(format!("Missing {} or {} in header", header, csml_engine::TIMESTAMP_HEADER))
+      }
+    }
+}
+
+/**
+ * Http status of an error of the engine
+ */
+pub fn get_error_status(err: &EngineError) -> StatusCode {
+    match err {
+      EngineError::Format(_)
+      | EngineError::Metadata(_)
+      | EngineError::Parring(_)
+      | EngineError::InvalidBot(_)
+      | EngineError::Serde(_) => StatusCode::BAD_REQUEST,
+      EngineError::BotNotFound(_) => StatusCode::NOT_FOUND,
+      // another server interpreted an event of the client during the run
+      EngineError::Conflict => StatusCode::CONFLICT,
+      // a quota of the bot or of its tenant is reached for the month,
+      // or the previous events of the client are still being interpreted
+      EngineError::QuotaExceeded(_) | EngineError::ClientBusy => StatusCode::TOO_MANY_REQUESTS,
+      // the server is stopping: the request can be sent again to another server
+      EngineError::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+      // the database is not configured: sending the request again does not help
+      EngineError::Db(DbErrorKind::Setup, _) => StatusCode::INTERNAL_SERVER_ERROR,
+      err if err.code() == "db_error" => StatusCode::SERVICE_UNAVAILABLE,
+      _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+      && request_id.len() <= MAX_REQUEST_ID_LENGTH
+      && request_id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+}
+
+/**
+ * Id of the request from its X-Request-Id header, generated if the header is missing or is
+ * not a valid id (at most 128 letters, digits, '-', '_', '.' or ':')
+ */
+pub fn read_request_id(headers: &header::HeaderMap) -> String {
+    match headers.get(REQUEST_ID_HEADER).and_then(|val| val.to_str().ok()) {
+      Some(request_id) if is_valid_request_id(request_id) => request_id.to_owned(),
+      _ => uuid::Uuid::new_v4().to_string(),
+    }
+}
+
+/**
+ * Id of the request, set by the RequestIdHeader middleware or read from its headers
+ */
+pub fn get_request_id(req: &HttpRequest) -> String {
+    match req.extensions().get::<RequestId>() {
+      Some(RequestId(request_id)) => request_id.to_owned(),
+      None => read_request_id(req.headers()),
+    }
+}
+
+/**
+ * Context of the run of the request for the engine, with its X-Request-Id
+ */
+pub fn get_run_context(req: &HttpRequest) -> RunContext {
+    RunContext {
+      request_id: Some(get_request_id(req)),
+    }
+}
+
+/**
+ * RFC 7807 problem+json response: {"type", "title", "status", "detail", "instance", "code",
+ * "request_id"}, the detail is optional
+ */
+pub fn problem_response(
+    req: &HttpRequest,
+    request_id: &str,
+    status: StatusCode,
+    code: &str,
+    detail: Option<String>,
+    extensions: serde_json::Map<String, serde_json::Value>,
+) -> HttpResponse {
+    let mut problem = serde_json::json!({
+      "type": format!("urn:csml:error:{}", code),
+      "title": status.canonical_reason().unwrap_or("Error"),
+      "status": status.as_u16(),
+      "instance": req.path(),
+      "code": code,
+      "request_id": request_id,
+    });
+    if let Some(detail) = detail {
+      problem["detail"] = detail.into();
+    }
+    if let Some(problem) = problem.as_object_mut() {
+      problem.extend(extensions);
+    }
+
+    HttpResponse::build(status)
+      .content_type(PROBLEM_CONTENT_TYPE)
+      .json(problem)
+}
+
+/**
+ * problem+json response of an error of the engine, with its code and its status.
+ * The detail of the server errors is only logged, it may contain details of the database.
+ */
+pub fn engine_error_response(req: &HttpRequest, err: EngineError) -> HttpResponse {
+    let status = get_error_status(&err);
+    // the id (generated if the request has none) is logged to find the error from the response
+    let request_id = get_request_id(req);
+    eprintln!("EngineError [{}]: {:?}", request_id, err);
+
+    let mut extensions = serde_json::Map::new();
+    match &err {
+      EngineError::Metadata(errors) => {
+        extensions.insert("errors".to_owned(), serde_json::json!(errors));
+      },
+      EngineError::InvalidBot(errors) => {
+        extensions.insert("errors".to_owned(), serde_json::json!(errors));
+      },
+      EngineError::QuotaExceeded(quota) => {
+        extensions.insert("quota".to_owned(), serde_json::json!(quota));
+      },
+      _ => {},
+    }
+    let detail = match status.is_server_error() && !matches!(err, EngineError::ShuttingDown) {
+      true => None,
+      false => Some(err.to_string()),
+    };
+
+    let mut response = problem_response(req, &request_id, status, err.code(), detail, extensions);
+    if matches!(err, EngineError::ClientBusy | EngineError::ShuttingDown) {
+      response.headers_mut().insert(
+        header::RETRY_AFTER,
+        header::HeaderValue::from_static("1"),
+      );
+    }
+
+    response
+}
+
+/**
+ * problem+json response of the json bodies that can not be read
+ */
+pub fn json_error_handler(err: JsonPayloadError, req: &HttpRequest) -> actix_web::Error {
+    let status = match err {
+      JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+        StatusCode::PAYLOAD_TOO_LARGE
+      },
+      _ => StatusCode::BAD_REQUEST,
+    };
+    let response = problem_response(
+      req,
+      &get_request_id(req),
+      status,
+      "invalid_request",
+      Some(err.to_string()),
+      serde_json::Map::new(),
+    );
+
+    InternalError::from_response(err, response).into()
+}
@@ -0,0 +1,69 @@
+use crate::routes::tools::{engine_error_response, get_tenant_id, validate_api_key};
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageQuery {
+    bot_id: Option<String>,
+    // YYYY-MM, the current month by default
+    period: Option<String>,
+}
+
+/**
+ * Get the number of conversations and messages of a bot and/or of the tenant of the api key
+ * for a month, with their quotas
+ *
+ * {"statusCode": 200,"body": {"period": String, "bot": USAGE | null, "tenant": USAGE | null}}
+ *
+ * USAGE = {
+ *  "id": String, "conversations": Integer, "messages": Integer,
+ *  "limits": {"conversations": Integer | null, "messages": Integer | null}
+ * }
+ */
+#[get("/usage")]
+pub async fn get_usage(query: web::Query<UsageQuery>, req: actix_web::HttpRequest) -> HttpResponse {
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let tenant_id = get_tenant_id(&req);
+    if query.bot_id.is_none() && tenant_id.is_none() {
+        return HttpResponse::BadRequest().body("bot_id is required");
+    }
+
+    let query = query.into_inner();
+    let res = thread::spawn(move || {
+        csml_engine::get_usage(
+            query.bot_id.as_deref(),
+            tenant_id.as_deref(),
+            query.period.as_deref(),
+        )
+    })
+    .join()
+    .unwrap();
+
+    match res {
+        Ok(usage) => HttpResponse::Ok().json(usage),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_usage_without_bot_id() {
+        let mut app = test::init_service(App::new().service(get_usage)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/usage")
+            .send_request(&mut app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
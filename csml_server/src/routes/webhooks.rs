@@ -0,0 +1,149 @@
+use crate::routes::tools::{
+    engine_error_response, get_tenant_bot_id, validate_api_key, validate_bot_id,
+};
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::data::WebhookRequest;
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookIdPath {
+    webhook_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BotIdQuery {
+    bot_id: String,
+}
+
+/**
+ * Subscribe an url to the events of the conversations of a bot (all of them if events is empty):
+ * conversation_started, conversation_closed, step_entered, message_sent, memory_updated,
+ * experiment_assigned.
+ * The secret signing the payloads is generated if not set, and only returned here.
+ *
+ * {"statusCode": 201,"body": Webhook}
+ *
+ * WEBHOOK = {
+ *  "webhook_id": String, "bot_id": String, "url": String, "events": [String],
+ *  "secret": String, "created_at": String
+ * }
+ */
+#[post("/webhooks")]
+pub async fn create_webhook(
+    body: web::Json<WebhookRequest>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let mut request = body.into_inner();
+    if let Some(value) = validate_bot_id(&request.bot_id) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+    request.bot_id = get_tenant_bot_id(&req, &request.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::create_webhook(request))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(webhook) => HttpResponse::Created().json(webhook),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Get the webhooks of a bot, without their secrets
+ *
+ * {"statusCode": 200,"body": [Webhook]}
+ */
+#[get("/webhooks")]
+pub async fn get_webhooks(
+    query: web::Query<BotIdQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let bot_id = get_tenant_bot_id(&req, &query.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::get_webhooks(&bot_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(webhooks) => HttpResponse::Ok().json(webhooks),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Remove a webhook and its delivery log
+ */
+#[delete("/webhooks/{webhook_id}")]
+pub async fn delete_webhook(
+    path: web::Path<WebhookIdPath>,
+    query: web::Query<BotIdQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let webhook_id = path.webhook_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &query.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::delete_webhook(&bot_id, &webhook_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
+
+/**
+ * Get the last deliveries of a webhook, most recent first
+ *
+ * {"statusCode": 200,"body": [Delivery]}
+ *
+ * DELIVERY = {
+ *  "delivery_id": String, "event_id": String, "event": String,
+ *  "status": "pending" | "delivered" | "failed", "attempts": Integer,
+ *  "response_status": Integer | null, "error": String | null,
+ *  "created_at": String, "updated_at": String
+ * }
+ */
+#[get("/webhooks/{webhook_id}/deliveries")]
+pub async fn get_webhook_deliveries(
+    path: web::Path<WebhookIdPath>,
+    query: web::Query<BotIdQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let webhook_id = path.webhook_id.to_owned();
+    let bot_id = get_tenant_bot_id(&req, &query.bot_id);
+
+    if let Some(value) = validate_api_key(&req) {
+        eprintln!("AuthError: {:?}", value);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let res = thread::spawn(move || csml_engine::get_webhook_deliveries(&bot_id, &webhook_id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(Some(deliveries)) => HttpResponse::Ok().json(deliveries),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => engine_error_response(&req, err),
+    }
+}
@@ -0,0 +1,88 @@
+/**
+ * Graceful shutdown of the server on SIGTERM or SIGINT, for the rolling deploys:
+ *
+ * 1. the new runs are refused with a 503 (and the background jobs stop claiming new runs)
+ * 2. the server stops accepting connections and waits for the requests in progress
+ * 3. the engine waits for the runs in progress (broadcasts, scheduled events...) to save their
+ *    conversations and sends the queued events and webhook deliveries
+ *
+ * The process exits at the latest ENGINE_SHUTDOWN_TIMEOUT seconds (default 30) after the signal.
+ */
+use actix_web::dev::ServerHandle;
+use csml_engine::get_config;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// unix timestamp in milliseconds of the shutdown signal, 0 until it is received
+static SIGNAL_RECEIVED_AT: AtomicU64 = AtomicU64::new(0);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+pub fn get_timeout() -> Duration {
+    let timeout = get_config()
+        .integer("server.shutdown_timeout")
+        .unwrap_or(30);
+
+    Duration::from_secs(timeout)
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use actix_rt::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            eprintln!("ShutdownError: {:?}", err);
+            actix_rt::signal::ctrl_c().await.ok();
+            return;
+        }
+    };
+
+    futures::future::select(Box::pin(terminate.recv()), Box::pin(actix_rt::signal::ctrl_c())).await;
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    actix_rt::signal::ctrl_c().await.ok();
+}
+
+/**
+ * Stop the server gracefully on the first signal
+ */
+pub fn handle_signals(server: ServerHandle) {
+    actix_rt::spawn(async move {
+        wait_for_signal().await;
+
+        SIGNAL_RECEIVED_AT.store(now_millis(), Ordering::SeqCst);
+        println!("Shutting down, in-flight requests get {:?}", get_timeout());
+
+        csml_engine::start_shutdown();
+        server.stop(true).await;
+    });
+}
+
+/**
+ * Once the server is stopped, wait for the engine until the end of the shutdown timeout
+ */
+pub fn drain() {
+    let signal_received_at = SIGNAL_RECEIVED_AT.load(Ordering::SeqCst);
+    if signal_received_at == 0 {
+        return;
+    }
+
+    let elapsed = Duration::from_millis(now_millis().saturating_sub(signal_received_at));
+    let remaining = get_timeout().checked_sub(elapsed).unwrap_or_default();
+
+    match csml_engine::drain(remaining) {
+        true => println!("CSML Server stopped"),
+        false => eprintln!("ShutdownError: the engine was not drained before the timeout"),
+    }
+}